@@ -146,6 +146,7 @@ final class BLETransport: NSObject, BoardTransport,
                     || char.uuid == GATT.position
                     || char.uuid == GATT.lastMove
                     || char.uuid == GATT.movePlayed
+                    || char.uuid == GATT.pendingPromotion
                 {
                     peripheral.setNotifyValue(true, for: char)
                 }
@@ -170,6 +171,9 @@ final class BLETransport: NSObject, BoardTransport,
             if let lm = characteristics[GATT.lastMove] {
                 peripheral.readValue(for: lm)
             }
+            if let pp = characteristics[GATT.pendingPromotion] {
+                peripheral.readValue(for: pp)
+            }
         default:
             break
         }
@@ -225,6 +229,17 @@ final class BLETransport: NSObject, BoardTransport,
                     }
                 )
             }
+        case GATT.pendingPromotion:
+            if data.isEmpty {
+                owner?.pendingPromotion = nil
+            } else {
+                decodeAndHandleMove(
+                    data: data,
+                    handler: { color, uci in
+                        owner?.pendingPromotion = (color, uci)
+                    }
+                )
+            }
         case GATT.movePlayed:
             decodeAndHandleMove(
                 data: data,
@@ -53,6 +53,9 @@ class BoardConnection {
     /// Last move played: (color: Turn, uci: String).
     var lastMove: (color: Turn, uci: String)?
 
+    /// Promotion played on the board that is waiting for a piece choice.
+    var pendingPromotion: (color: Turn, uci: String)?
+
     /// Called when the board emits a MovePlayed notification.
     var onMovePlayed: ((Turn, String) -> Void)?
 
@@ -135,6 +138,47 @@ class BoardConnection {
         transport.write(Data([0x01]), to: GATT.matchControl)
     }
 
+    /// Reports a result decided off the board (e.g. by an online opponent).
+    ///
+    /// Wire format: `[action: u8 (0x02 = report result), result: u8, color?: u8]`
+    func reportResult(_ result: ExternalResult) {
+        lastCommandResult = nil
+        transport.write(result.encoded, to: GATT.matchControl)
+    }
+
+    /// Chooses the piece for the promotion the board is holding.
+    ///
+    /// Wire format: `[action: u8 (0x03 = choose promotion), piece: u8]`
+    func choosePromotion(_ piece: PromotionPiece) {
+        lastCommandResult = nil
+        transport.write(Data([0x03, piece.rawValue]), to: GATT.matchControl)
+    }
+
+    /// Starts a mini-game or analysis on the board.
+    ///
+    /// Wire format: `[action: u8 (0x04 = start mode), mode: u8, arg?: u8]`
+    func startMode(_ mode: BoardMode) {
+        lastCommandResult = nil
+        transport.write(mode.encoded, to: GATT.matchControl)
+    }
+
+    /// Presses a side of the board's clock, committing a move held for
+    /// confirmation.
+    ///
+    /// Wire format: `[action: u8 (0x05 = press clock), color: u8]`
+    func pressClock(color: Turn) {
+        lastCommandResult = nil
+        transport.write(Data([0x05, color.rawValue]), to: GATT.matchControl)
+    }
+
+    /// Sets the clock for the next game, or turns it off with `nil`.
+    ///
+    /// Wire format: `[action: u8 (0x06 = set clock), minutes: u8, increment_secs: u8, flags: u8]`
+    func setClock(_ clock: ClockSettings?) {
+        lastCommandResult = nil
+        transport.write(clock?.encoded ?? Data([0x06, 0x00, 0x00, 0x00]), to: GATT.matchControl)
+    }
+
     /// Sends a move to the board.
     ///
     /// Wire format: `[length: u8, ...uci_bytes]`
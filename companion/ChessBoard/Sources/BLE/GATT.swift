@@ -33,11 +33,15 @@ enum GATT {
     static let movePlayed = CBUUID(
         string: "3d6343a2-101a-44ea-8fc2-3568d7216866"
     )
+    static let pendingPromotion = CBUUID(
+        string: "3d6343a2-101b-44ea-8fc2-3568d7216866"
+    )
 
     static let allServices = [gameService]
 
     static let gameCharacteristics: [CBUUID] = [
         whitePlayer, blackPlayer, startGame, matchControl,
         gameStatus, commandResult, submitMove, position, lastMove, movePlayed,
+        pendingPromotion,
     ]
 }
@@ -21,6 +21,28 @@ private func terminalMessage(for status: String) -> String? {
     }
 }
 
+// MARK: - Board results for terminal statuses
+
+/// Maps a Lichess terminal status to a result the board can show.
+///
+/// Returns `nil` when the board cannot represent the outcome; the caller
+/// cancels the game instead.
+private func externalResult(for status: String, winner: String?) -> ExternalResult? {
+    let loser: Turn?
+    switch winner {
+    case "white": loser = .black
+    case "black": loser = .white
+    default: loser = nil
+    }
+    switch status {
+    case "resign": return loser.map { .resigned(color: $0) }
+    case "timeout", "outoftime": return loser.map { .timeout(loser: $0) }
+    case "draw", "stalemate": return .draw
+    case "aborted", "noStart": return .aborted
+    default: return nil
+    }
+}
+
 // MARK: - LichessService
 
 /// Orchestrates a Lichess game, bridging between the board and Lichess API.
@@ -30,7 +52,8 @@ private func terminalMessage(for status: String) -> String? {
 /// 2. Receives board's `MovePlayed` → filters by humanColor → sends to Lichess
 ///    via `makeMove`
 /// 3. Receives Lichess AI move → sends to board via `board.submitMove()`
-/// 4. Lichess game-over event → calls `board.cancelGame()` to sync board state
+/// 4. Lichess game-over event → calls `board.reportResult()` to sync board
+///    state, falling back to `board.cancelGame()` for unrepresentable results
 /// 5. Board game ends → `stop()`
 @MainActor
 @Observable
@@ -177,13 +200,17 @@ class LichessService {
             }
             lastMoveCount = plies
 
-        case .gameState(let moves, let status, _):
+        case .gameState(let moves, let status, let winner):
             if terminalStatuses.contains(status) {
                 // For mate, the board detects checkmate from position itself.
-                // For all other terminal states, force the board out of the
-                // current game and surface a human-readable message.
+                // For all other terminal states, report the result so the
+                // board ends the game, and surface a human-readable message.
                 if status != "mate" {
-                    board.cancelGame()
+                    if let result = externalResult(for: status, winner: winner) {
+                        board.reportResult(result)
+                    } else {
+                        board.cancelGame()
+                    }
                     error = terminalMessage(for: status)
                 }
                 streamTask?.cancel()
@@ -13,6 +13,9 @@ enum BoardError: UInt8, Equatable {
     case illegalMove = 0x03
     case cannotResignForRemotePlayer = 0x04
     case invalidCommand = 0x05
+    case noRemotePlayer = 0x06
+    case noPendingPromotion = 0x07
+    case noPendingMove = 0x08
 }
 
 struct CommandResult: Equatable {
@@ -12,10 +12,13 @@ enum GameStatus: Equatable {
     case checkmate(loser: Turn)
     case stalemate
     case resigned(color: Turn)
+    case timeout(loser: Turn)
+    case draw
+    case aborted
 
     var isTerminal: Bool {
         switch self {
-        case .checkmate, .stalemate, .resigned: return true
+        case .checkmate, .stalemate, .resigned, .timeout, .draw, .aborted: return true
         case .idle, .awaitingPieces, .inProgress: return false
         }
     }
@@ -37,7 +40,82 @@ enum GameStatus: Equatable {
                 return nil
             }
             return .resigned(color: turn)
+        case 0x06:
+            guard data.count >= 2, let turn = Turn(rawValue: data[1]) else {
+                return nil
+            }
+            return .timeout(loser: turn)
+        case 0x07: return .draw
+        case 0x08: return .aborted
         default: return nil
         }
     }
 }
+
+/// A game result decided off the board, reported via Match Control.
+enum ExternalResult: Equatable {
+    case resigned(color: Turn)
+    case timeout(loser: Turn)
+    case draw
+    case aborted
+
+    /// Wire format: `[0x02, result: u8, color?: u8]`
+    var encoded: Data {
+        switch self {
+        case .resigned(let color): return Data([0x02, 0x00, color.rawValue])
+        case .timeout(let loser): return Data([0x02, 0x01, loser.rawValue])
+        case .draw: return Data([0x02, 0x02])
+        case .aborted: return Data([0x02, 0x03])
+        }
+    }
+}
+
+/// Piece chosen for a promotion held by the board.
+enum PromotionPiece: UInt8, CaseIterable {
+    case queen = 0x00
+    case rook = 0x01
+    case bishop = 0x02
+    case knight = 0x03
+
+    var displayName: String {
+        switch self {
+        case .queen: return "Queen"
+        case .rook: return "Rook"
+        case .bishop: return "Bishop"
+        case .knight: return "Knight"
+        }
+    }
+}
+
+/// A board activity other than a client-managed game, started via Match Control.
+enum BoardMode: Equatable {
+    case coordinateTraining
+    case knightsTour
+    case pawnCapture(puzzle: UInt8)
+    case analysis
+    case checkers
+
+    /// Wire format: `[0x04, mode: u8, arg?: u8]`
+    var encoded: Data {
+        switch self {
+        case .coordinateTraining: return Data([0x04, 0x00])
+        case .knightsTour: return Data([0x04, 0x01])
+        case .pawnCapture(let puzzle): return Data([0x04, 0x02, puzzle])
+        case .analysis: return Data([0x04, 0x03])
+        case .checkers: return Data([0x04, 0x04])
+        }
+    }
+}
+
+/// On-board clock for a game.
+struct ClockSettings: Equatable {
+    var minutes: UInt8
+    var incrementSeconds: UInt8
+    /// Tournament rules: moves count only once the player presses their clock.
+    var confirmMoves: Bool
+
+    /// Wire format: `[0x06, minutes: u8, increment_secs: u8, flags: u8]`
+    var encoded: Data {
+        Data([0x06, minutes, incrementSeconds, confirmMoves ? 0x01 : 0x00])
+    }
+}
@@ -11,6 +11,10 @@ extension GameStatus {
         case .stalemate: return "Stalemate"
         case .resigned(let color):
             return "\(color == .white ? "White" : "Black") Resigned"
+        case .timeout(let loser):
+            return "Time Out – \(loser == .white ? "White" : "Black") loses"
+        case .draw: return "Draw"
+        case .aborted: return "Game Aborted"
         }
     }
 
@@ -22,6 +26,9 @@ extension GameStatus {
         case .checkmate: return "crown.fill"
         case .stalemate: return "equal.circle.fill"
         case .resigned: return "flag.fill"
+        case .timeout: return "clock.badge.xmark"
+        case .draw: return "equal.circle.fill"
+        case .aborted: return "xmark.circle"
         }
     }
 
@@ -32,6 +32,21 @@ struct ActiveGameView: View {
 
             Spacer()
 
+            if let promotion = board.pendingPromotion {
+                VStack(spacing: 12) {
+                    Text("Promote on \(String(promotion.uci.suffix(2)))")
+                        .font(.headline)
+                    HStack {
+                        ForEach(PromotionPiece.allCases, id: \.self) { piece in
+                            Button(piece.displayName) {
+                                board.choosePromotion(piece)
+                            }
+                            .buttonStyle(.bordered)
+                        }
+                    }
+                }
+            }
+
             if board.gameStatus == .awaitingPieces {
                 Button("Cancel") { board.cancelGame() }
             }
@@ -162,4 +162,45 @@ import Testing
         #expect(transport.writeArgs[0].data == Data([0x00, 0x01]))
         #expect(transport.writeArgs[0].characteristic == GATT.startGame)
     }
+
+    @Test func choosePromotionWritesMatchControl() {
+        let transport = MockTransport()
+        let board = BoardConnection(transport: transport)
+
+        board.choosePromotion(.knight)
+
+        #expect(transport.writeArgs.last?.data == Data([0x03, 0x03]))
+        #expect(transport.writeArgs.last?.characteristic == GATT.matchControl)
+    }
+
+    @Test func startModeWritesMatchControl() {
+        let transport = MockTransport()
+        let board = BoardConnection(transport: transport)
+
+        board.startMode(.pawnCapture(puzzle: 2))
+
+        #expect(transport.writeArgs.last?.data == Data([0x04, 0x02, 0x02]))
+        #expect(transport.writeArgs.last?.characteristic == GATT.matchControl)
+    }
+
+    @Test func pressClockWritesMatchControl() {
+        let transport = MockTransport()
+        let board = BoardConnection(transport: transport)
+
+        board.pressClock(color: .black)
+
+        #expect(transport.writeArgs.last?.data == Data([0x05, 0x01]))
+        #expect(transport.writeArgs.last?.characteristic == GATT.matchControl)
+    }
+
+    @Test func setClockEncodesSettings() {
+        let transport = MockTransport()
+        let board = BoardConnection(transport: transport)
+
+        board.setClock(ClockSettings(minutes: 5, incrementSeconds: 3, confirmMoves: true))
+        #expect(transport.writeArgs.last?.data == Data([0x06, 0x05, 0x03, 0x01]))
+
+        board.setClock(nil)
+        #expect(transport.writeArgs.last?.data == Data([0x06, 0x00, 0x00, 0x00]))
+    }
 }
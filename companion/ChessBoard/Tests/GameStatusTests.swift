@@ -60,6 +60,24 @@ import Testing
         #expect(GameStatus.decode(Data([0x05, 0xFF])) == nil)
     }
 
+    @Test func decodeTimeout() {
+        #expect(
+            GameStatus.decode(Data([0x06, 0x01])) == .timeout(loser: .black)
+        )
+    }
+
+    @Test func decodeTimeoutMissingColor() {
+        #expect(GameStatus.decode(Data([0x06])) == nil)
+    }
+
+    @Test func decodeDraw() {
+        #expect(GameStatus.decode(Data([0x07])) == .draw)
+    }
+
+    @Test func decodeAborted() {
+        #expect(GameStatus.decode(Data([0x08])) == .aborted)
+    }
+
     @Test func decodeInvalidTag() {
         #expect(GameStatus.decode(Data([0xFF])) == nil)
     }
@@ -80,5 +98,8 @@ import Testing
         #expect(GameStatus.stalemate.isTerminal)
         #expect(GameStatus.resigned(color: .white).isTerminal)
         #expect(GameStatus.resigned(color: .black).isTerminal)
+        #expect(GameStatus.timeout(loser: .white).isTerminal)
+        #expect(GameStatus.draw.isTerminal)
+        #expect(GameStatus.aborted.isTerminal)
     }
 }
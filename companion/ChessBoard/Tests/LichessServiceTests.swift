@@ -169,12 +169,35 @@ struct LichessServiceTests {
         // (cancelGame writes through MockTransport; isActive is the primary signal)
     }
 
-    @Test func resignGameStateTriggersCancelGame() async {
+    @Test func resignGameStateReportsResultToBoard() async {
         let api = MockLichessAPI()
         api.streamEvents = [
             .gameState(moves: "e2e4", status: "resign", winner: "white")
         ]
-        let board = BoardConnection(transport: MockTransport())
+        let transport = MockTransport()
+        let board = BoardConnection(transport: transport)
+        board.connectionState = .ready
+        let service = LichessService(
+            api: api,
+            board: board,
+            humanColor: .white
+        )
+
+        await service.start(level: 3)
+
+        try? await Task.sleep(for: .milliseconds(50))
+
+        #expect(!service.isActive)
+        #expect(transport.writeArgs.last?.data == Data([0x02, 0x00, 0x01]))
+    }
+
+    @Test func timeoutGameStateReportsLoserToBoard() async {
+        let api = MockLichessAPI()
+        api.streamEvents = [
+            .gameState(moves: "e2e4 e7e5", status: "outoftime", winner: "black")
+        ]
+        let transport = MockTransport()
+        let board = BoardConnection(transport: transport)
         board.connectionState = .ready
         let service = LichessService(
             api: api,
@@ -187,9 +210,10 @@ struct LichessServiceTests {
         try? await Task.sleep(for: .milliseconds(50))
 
         #expect(!service.isActive)
+        #expect(transport.writeArgs.last?.data == Data([0x02, 0x01, 0x00]))
     }
 
-    @Test func stalemateGameStateTriggersCancelGame() async {
+    @Test func stalemateGameStateReportsDraw() async {
         let api = MockLichessAPI()
         api.streamEvents = [
             .gameState(moves: "e2e4 e7e5", status: "stalemate", winner: nil)
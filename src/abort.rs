@@ -0,0 +1,246 @@
+//! On-board gesture for abandoning a game in progress.
+//!
+//! Clearing every piece off the board for [`ABORT_HOLD`] arms the gesture
+//! and starts blinking the four centre squares. Placing a single piece on
+//! one of them within [`ABORT_CONFIRM_TIMEOUT`] confirms the abort; putting
+//! pieces back anywhere else, or waiting out the timeout, keeps the game.
+//! Two deliberate steps make it hard to lose a long game by accident
+//! (e.g. while tidying the board mid-game).
+
+use std::time::Duration;
+
+use shakmaty::{Bitboard, ByColor, Chess};
+
+use crate::feedback::{BoardFeedback, SquareFeedback};
+use crate::gestures::{GestureDef, GestureRecognizer, GestureSignal, Pattern, Step};
+
+/// How long the board must stay empty before the abort prompt appears.
+pub const ABORT_HOLD: Duration = Duration::from_secs(3);
+
+/// How long the prompt waits for the confirming piece.
+pub const ABORT_CONFIRM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Length of one on/off phase of the prompt blink.
+pub const ABORT_BLINK_PHASE: Duration = Duration::from_millis(250);
+
+/// Squares that confirm the abort when a piece is placed on them.
+/// d4, e4, d5 and e5.
+pub const CONFIRM_SQUARES: Bitboard = Bitboard(0x0000_0018_1800_0000);
+
+/// What the caller should do after feeding the gesture a sensor reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbortSignal {
+    /// No prompt showing; carry on with the game.
+    Inactive,
+    /// The prompt is showing; display [`prompt_feedback`] instead of game feedback.
+    Prompting { elapsed: Duration },
+    /// The player confirmed; discard the game.
+    Confirmed,
+}
+
+/// Clear the board, then a single piece on a centre square. A timed-out
+/// prompt waits for pieces to return before re-arming.
+const ABORT: GestureDef<()> = GestureDef::new(
+    (),
+    &[
+        Step::hold(Pattern::Empty, ABORT_HOLD),
+        Step::hold(Pattern::LoneOn(CONFIRM_SQUARES), Duration::ZERO).within(ABORT_CONFIRM_TIMEOUT),
+    ],
+);
+
+/// Tracks the clear-board abort gesture across ticks.
+#[derive(Debug, Clone)]
+pub struct AbortGesture {
+    recognizer: GestureRecognizer<()>,
+}
+
+impl AbortGesture {
+    pub fn new() -> Self {
+        Self {
+            recognizer: GestureRecognizer::with_definitions(&[ABORT]),
+        }
+    }
+
+    /// Advance the gesture with the current sensor reading.
+    pub fn update(
+        &mut self,
+        position: &Chess,
+        sensors: ByColor<Bitboard>,
+        now: Duration,
+    ) -> AbortSignal {
+        match self.recognizer.update(position, sensors, now) {
+            GestureSignal::Holding {
+                step: 1, elapsed, ..
+            } => {
+                if elapsed.is_zero() {
+                    log::info!("Board cleared, asking to confirm abort");
+                }
+                AbortSignal::Prompting { elapsed }
+            }
+            GestureSignal::Recognized(()) => AbortSignal::Confirmed,
+            _ => AbortSignal::Inactive,
+        }
+    }
+}
+
+impl Default for AbortGesture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Feedback for the abort prompt: the confirm squares blinking.
+pub fn prompt_feedback(elapsed: Duration) -> BoardFeedback {
+    let mut fb = BoardFeedback::new();
+    let phase = elapsed.as_millis() / ABORT_BLINK_PHASE.as_millis();
+    if phase.is_multiple_of(2) {
+        for sq in CONFIRM_SQUARES {
+            fb.set(sq, SquareFeedback::Check);
+        }
+    }
+    fb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::Square;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    /// A reading of white pieces on `occupied`; the abort patterns only
+    /// look at occupancy.
+    fn reading(occupied: Bitboard) -> ByColor<Bitboard> {
+        ByColor {
+            white: occupied,
+            black: Bitboard::EMPTY,
+        }
+    }
+
+    fn armed() -> AbortGesture {
+        let mut gesture = AbortGesture::new();
+        gesture.update(&Chess::default(), reading(Bitboard::EMPTY), Duration::ZERO);
+        assert!(matches!(
+            gesture.update(&Chess::default(), reading(Bitboard::EMPTY), ABORT_HOLD),
+            AbortSignal::Prompting { .. }
+        ));
+        gesture
+    }
+
+    #[test]
+    fn confirm_squares_are_the_centre() {
+        let centre = [Square::D4, Square::E4, Square::D5, Square::E5]
+            .into_iter()
+            .collect::<Bitboard>();
+        assert_eq!(CONFIRM_SQUARES, centre);
+    }
+
+    #[test]
+    fn occupied_board_never_prompts() {
+        let mut gesture = AbortGesture::new();
+        for t in 0..200 {
+            let signal = gesture.update(&Chess::default(), reading(Bitboard::FULL), MS * 50 * t);
+            assert_eq!(signal, AbortSignal::Inactive);
+        }
+    }
+
+    #[test]
+    fn briefly_cleared_board_does_not_prompt() {
+        let mut gesture = AbortGesture::new();
+        gesture.update(&Chess::default(), reading(Bitboard::EMPTY), Duration::ZERO);
+        assert_eq!(
+            gesture.update(&Chess::default(), reading(Bitboard::EMPTY), ABORT_HOLD - MS),
+            AbortSignal::Inactive
+        );
+        assert_eq!(
+            gesture.update(&Chess::default(), reading(Bitboard::FULL), ABORT_HOLD),
+            AbortSignal::Inactive
+        );
+        assert_eq!(
+            gesture.update(&Chess::default(), reading(Bitboard::EMPTY), ABORT_HOLD + MS),
+            AbortSignal::Inactive
+        );
+    }
+
+    #[test]
+    fn piece_on_centre_square_confirms() {
+        let mut gesture = armed();
+        assert_eq!(
+            gesture.update(
+                &Chess::default(),
+                reading(Square::E4.into()),
+                ABORT_HOLD + MS * 500
+            ),
+            AbortSignal::Confirmed
+        );
+    }
+
+    #[test]
+    fn piece_elsewhere_declines() {
+        let mut gesture = armed();
+        assert_eq!(
+            gesture.update(
+                &Chess::default(),
+                reading(Square::A1.into()),
+                ABORT_HOLD + MS * 500
+            ),
+            AbortSignal::Inactive
+        );
+        assert_eq!(
+            gesture.update(
+                &Chess::default(),
+                reading(Bitboard::EMPTY),
+                ABORT_HOLD + MS * 550
+            ),
+            AbortSignal::Inactive
+        );
+    }
+
+    #[test]
+    fn two_pieces_in_centre_decline() {
+        let mut gesture = armed();
+        let two = Bitboard::from(Square::D4) | Bitboard::from(Square::E5);
+        assert_eq!(
+            gesture.update(&Chess::default(), reading(two), ABORT_HOLD + MS * 500),
+            AbortSignal::Inactive
+        );
+    }
+
+    #[test]
+    fn prompt_times_out_and_stays_dismissed_while_empty() {
+        let mut gesture = armed();
+        let expiry = ABORT_HOLD + ABORT_CONFIRM_TIMEOUT;
+        assert_eq!(
+            gesture.update(&Chess::default(), reading(Bitboard::EMPTY), expiry),
+            AbortSignal::Inactive
+        );
+        assert_eq!(
+            gesture.update(
+                &Chess::default(),
+                reading(Bitboard::EMPTY),
+                expiry + ABORT_HOLD * 2
+            ),
+            AbortSignal::Inactive
+        );
+        assert_eq!(
+            gesture.update(
+                &Chess::default(),
+                reading(Square::D4.into()),
+                expiry + ABORT_HOLD * 3
+            ),
+            AbortSignal::Inactive,
+            "a dismissed prompt must not confirm"
+        );
+    }
+
+    #[test]
+    fn prompt_feedback_blinks_centre_squares() {
+        let on = prompt_feedback(Duration::ZERO);
+        assert_eq!(on.get(Square::D4), Some(SquareFeedback::Check));
+        assert_eq!(on.get(Square::E5), Some(SquareFeedback::Check));
+        assert_eq!(on.squares().count(), 4);
+
+        assert!(prompt_feedback(ABORT_BLINK_PHASE).is_empty());
+        assert!(!prompt_feedback(ABORT_BLINK_PHASE * 2).is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+//! Who may control the board, and how many may watch.
+//!
+//! Each client connection is a controller, which may send operations, or a
+//! read-only spectator, which only receives state and events. A connection
+//! is a controller if a controller slot is free when it connects, and a
+//! spectator otherwise. Once the spectator slots are full too, further
+//! connections are refused, so a club stream with many viewers cannot
+//! starve the game loop. A spectator keeps its role for its connection; to
+//! take control it reconnects after the controller has left.
+//!
+//! Pairing (see [`crate::pairing`]) applies to controllers on top of this.
+
+/// Transport-specific handle of one connection (the BLE connection handle).
+pub type ConnectionId = u16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Controller,
+    /// Reads state and receives events, but cannot send operations.
+    Spectator,
+}
+
+/// Most connections of each kind open at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionLimits {
+    pub controllers: usize,
+    pub spectators: usize,
+}
+
+impl Default for ConnectionLimits {
+    /// One controller and three spectators.
+    fn default() -> Self {
+        Self {
+            controllers: 1,
+            spectators: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum AccessError {
+    #[error("board is full ({controllers} controllers, {spectators} spectators)")]
+    Full {
+        controllers: usize,
+        spectators: usize,
+    },
+    #[error("connection {0} is a read-only spectator")]
+    ReadOnly(ConnectionId),
+    #[error("unknown connection {0}")]
+    Unknown(ConnectionId),
+}
+
+/// The open connections and their access.
+#[derive(Debug, Clone, Default)]
+pub struct Connections {
+    limits: ConnectionLimits,
+    /// Oldest first.
+    open: Vec<(ConnectionId, Access)>,
+}
+
+impl Connections {
+    pub fn new(limits: ConnectionLimits) -> Self {
+        Self {
+            limits,
+            open: Vec::new(),
+        }
+    }
+
+    /// Admit connection `id`, returning its access, or refuse it if every
+    /// slot is taken.
+    pub fn connect(&mut self, id: ConnectionId) -> Result<Access, AccessError> {
+        self.disconnect(id);
+        let access = if self.count(Access::Controller) < self.limits.controllers {
+            Access::Controller
+        } else if self.count(Access::Spectator) < self.limits.spectators {
+            Access::Spectator
+        } else {
+            return Err(AccessError::Full {
+                controllers: self.limits.controllers,
+                spectators: self.limits.spectators,
+            });
+        };
+        self.open.push((id, access));
+        Ok(access)
+    }
+
+    pub fn disconnect(&mut self, id: ConnectionId) {
+        self.open.retain(|&(open, _)| open != id);
+    }
+
+    pub fn access(&self, id: ConnectionId) -> Option<Access> {
+        self.open
+            .iter()
+            .find(|&&(open, _)| open == id)
+            .map(|&(_, access)| access)
+    }
+
+    /// Whether connection `id` may send operations.
+    pub fn check_write(&self, id: ConnectionId) -> Result<(), AccessError> {
+        match self.access(id) {
+            Some(Access::Controller) => Ok(()),
+            Some(Access::Spectator) => Err(AccessError::ReadOnly(id)),
+            None => Err(AccessError::Unknown(id)),
+        }
+    }
+
+    /// Open connections with `access`, oldest first.
+    pub fn ids(&self, access: Access) -> impl Iterator<Item = ConnectionId> + '_ {
+        self.open
+            .iter()
+            .filter(move |&&(_, a)| a == access)
+            .map(|&(id, _)| id)
+    }
+
+    pub fn count(&self, access: Access) -> usize {
+        self.ids(access).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_connection_controls_and_later_ones_watch_until_full() {
+        let mut connections = Connections::new(ConnectionLimits {
+            controllers: 1,
+            spectators: 2,
+        });
+
+        assert_eq!(connections.connect(1), Ok(Access::Controller));
+        assert_eq!(connections.connect(2), Ok(Access::Spectator));
+        assert_eq!(connections.connect(3), Ok(Access::Spectator));
+        assert_eq!(
+            connections.connect(4),
+            Err(AccessError::Full {
+                controllers: 1,
+                spectators: 2
+            })
+        );
+        assert_eq!(connections.check_write(1), Ok(()));
+        assert_eq!(connections.check_write(2), Err(AccessError::ReadOnly(2)));
+        assert_eq!(connections.check_write(4), Err(AccessError::Unknown(4)));
+    }
+
+    #[test]
+    fn freed_slots_are_reused_without_promoting_spectators() {
+        let mut connections = Connections::new(ConnectionLimits {
+            controllers: 1,
+            spectators: 1,
+        });
+        connections.connect(1).unwrap();
+        connections.connect(2).unwrap();
+
+        connections.disconnect(1);
+        assert_eq!(connections.ids(Access::Spectator).collect::<Vec<_>>(), [2]);
+        assert_eq!(connections.count(Access::Controller), 0);
+        assert_eq!(connections.connect(3), Ok(Access::Controller));
+    }
+}
@@ -0,0 +1,303 @@
+//! Automatic result adjudication.
+//!
+//! FIDE ends a game as a draw on its own after a fivefold repetition or 75
+//! moves without a capture or pawn move, without either player claiming
+//! it. A dead position (no sequence of legal moves can mate) is also a
+//! draw, and so is a flag fall when the opponent could not have mated.
+//! Casual games may also end threefold repetitions and 50-move stretches
+//! at once, which FIDE only draws when a player claims them. The board
+//! cannot rely on players noticing any of this, so
+//! [`crate::session::GameSession`] applies the rules enabled in
+//! [`Adjudication`] after every move and logs each decision.
+//!
+//! When those two are left to a claim, [`claimable`] says when one can be
+//! made; the session reports it and takes the claim from the board.
+
+use shakmaty::{Bitboard, Board, Chess, Color, EnPassantMode, Position, Square};
+
+use crate::board_api::GameStatus;
+
+/// Which automatic adjudication rules apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Adjudication {
+    /// Draw when the same position occurs for the fifth time.
+    pub fivefold_repetition: bool,
+    /// Draw after 75 moves by each side without a capture or pawn move.
+    pub seventy_five_moves: bool,
+    /// Draw when the same position occurs for the third time, without
+    /// waiting for a claim.
+    pub threefold_repetition: bool,
+    /// Draw after 50 moves by each side without a capture or pawn move,
+    /// without waiting for a claim.
+    pub fifty_moves: bool,
+    /// Draw when neither side has mating material.
+    pub dead_position: bool,
+    /// A flag fall is a draw, not a loss, when the opponent has no mating
+    /// material.
+    pub timeout_vs_insufficient_material: bool,
+}
+
+impl Default for Adjudication {
+    /// The rules FIDE applies without a claim.
+    fn default() -> Self {
+        Self {
+            fivefold_repetition: true,
+            seventy_five_moves: true,
+            threefold_repetition: false,
+            fifty_moves: false,
+            dead_position: true,
+            timeout_vs_insufficient_material: false,
+        }
+    }
+}
+
+/// Why a game was adjudicated as drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawReason {
+    FivefoldRepetition,
+    SeventyFiveMoves,
+    ThreefoldRepetition,
+    FiftyMoves,
+    DeadPosition,
+}
+
+/// What makes two positions the same for repetition: pieces, side to
+/// move, castling rights and a capturable en passant square.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PositionKey {
+    board: Board,
+    turn: Color,
+    castling: Bitboard,
+    ep_square: Option<Square>,
+}
+
+impl PositionKey {
+    fn of(position: &Chess) -> Self {
+        Self {
+            board: position.board().clone(),
+            turn: position.turn(),
+            castling: position.castles().castling_rights(),
+            ep_square: position.ep_square(EnPassantMode::Legal),
+        }
+    }
+}
+
+/// Positions since the last irreversible move.
+///
+/// Captures and pawn moves make earlier positions unreachable, so only
+/// the positions within the current halfmove clock are kept.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    positions: Vec<PositionKey>,
+}
+
+impl History {
+    pub fn new(position: &Chess) -> Self {
+        let mut history = Self::default();
+        history.push(position);
+        history
+    }
+
+    /// Record the position reached by a move.
+    pub fn push(&mut self, position: &Chess) {
+        if position.halfmoves() == 0 {
+            self.positions.clear();
+        }
+        self.positions.push(PositionKey::of(position));
+    }
+
+    /// How often `position` has occurred, including now if it was pushed.
+    pub fn occurrences(&self, position: &Chess) -> usize {
+        let key = PositionKey::of(position);
+        self.positions.iter().filter(|k| **k == key).count()
+    }
+}
+
+/// The draw the player to move may claim in `position`: a threefold
+/// repetition, or 50 moves by each side without a capture or pawn move.
+pub fn claimable(position: &Chess, history: &History) -> Option<DrawReason> {
+    if position.legal_moves().is_empty() {
+        None
+    } else if history.occurrences(position) >= 3 {
+        Some(DrawReason::ThreefoldRepetition)
+    } else if position.halfmoves() >= 100 {
+        Some(DrawReason::FiftyMoves)
+    } else {
+        None
+    }
+}
+
+impl Adjudication {
+    /// Check the position after a move. Checkmate and stalemate are decided
+    /// by the position itself and take precedence.
+    pub fn after_move(&self, position: &Chess, history: &History) -> Option<DrawReason> {
+        if position.legal_moves().is_empty() {
+            return None;
+        }
+        let occurrences = history.occurrences(position);
+        if self.fivefold_repetition && occurrences >= 5 {
+            Some(DrawReason::FivefoldRepetition)
+        } else if self.seventy_five_moves && position.halfmoves() >= 150 {
+            Some(DrawReason::SeventyFiveMoves)
+        } else if self.threefold_repetition && occurrences >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.fifty_moves && position.halfmoves() >= 100 {
+            Some(DrawReason::FiftyMoves)
+        } else if self.dead_position && position.is_insufficient_material() {
+            Some(DrawReason::DeadPosition)
+        } else {
+            None
+        }
+    }
+
+    /// The result when `loser`'s flag falls in `position`.
+    pub fn timeout(&self, position: &Chess, loser: Color) -> GameStatus {
+        if self.timeout_vs_insufficient_material
+            && position.has_insufficient_material(loser.other())
+        {
+            GameStatus::Draw
+        } else {
+            GameStatus::Timeout { loser }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::CastlingMode;
+    use shakmaty::fen::Fen;
+    use shakmaty::uci::UciMove;
+
+    fn position(fen: &str) -> Chess {
+        fen.parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap()
+    }
+
+    fn play(position: &mut Chess, history: &mut History, uci: &str) {
+        let mv = uci.parse::<UciMove>().unwrap().to_move(position).unwrap();
+        position.play_unchecked(mv);
+        history.push(position);
+    }
+
+    #[test]
+    fn fivefold_repetition_is_a_draw() {
+        let mut pos = Chess::default();
+        let mut history = History::new(&pos);
+        let rules = Adjudication::default();
+
+        for _ in 0..3 {
+            for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                play(&mut pos, &mut history, uci);
+                assert_eq!(rules.after_move(&pos, &history), None);
+            }
+        }
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            play(&mut pos, &mut history, uci);
+        }
+
+        assert_eq!(history.occurrences(&pos), 5);
+        assert_eq!(
+            rules.after_move(&pos, &history),
+            Some(DrawReason::FivefoldRepetition)
+        );
+    }
+
+    #[test]
+    fn pawn_move_clears_repetitions() {
+        let mut pos = Chess::default();
+        let mut history = History::new(&pos);
+        play(&mut pos, &mut history, "g1f3");
+
+        play(&mut pos, &mut history, "e7e5");
+
+        assert_eq!(history.positions.len(), 1);
+    }
+
+    #[test]
+    fn seventy_five_move_rule_is_a_draw() {
+        let pos = position("4k3/8/8/8/8/8/4K3/R7 w - - 150 100");
+
+        assert_eq!(
+            Adjudication::default().after_move(&pos, &History::new(&pos)),
+            Some(DrawReason::SeventyFiveMoves)
+        );
+    }
+
+    #[test]
+    fn claimable_draws_only_when_enabled() {
+        let casual = Adjudication {
+            threefold_repetition: true,
+            fifty_moves: true,
+            ..Adjudication::default()
+        };
+        let mut pos = Chess::default();
+        let mut history = History::new(&pos);
+        for _ in 0..2 {
+            for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                play(&mut pos, &mut history, uci);
+            }
+        }
+        assert_eq!(Adjudication::default().after_move(&pos, &history), None);
+        assert_eq!(
+            casual.after_move(&pos, &history),
+            Some(DrawReason::ThreefoldRepetition)
+        );
+        assert_eq!(
+            claimable(&pos, &history),
+            Some(DrawReason::ThreefoldRepetition)
+        );
+
+        let pos = position("4k3/8/8/8/8/8/4K3/R7 w - - 100 80");
+        let history = History::new(&pos);
+        assert_eq!(Adjudication::default().after_move(&pos, &history), None);
+        assert_eq!(
+            casual.after_move(&pos, &history),
+            Some(DrawReason::FiftyMoves)
+        );
+        assert_eq!(claimable(&pos, &history), Some(DrawReason::FiftyMoves));
+        assert_eq!(claimable(&Chess::default(), &History::default()), None);
+    }
+
+    #[test]
+    fn dead_position_is_drawn_unless_disabled() {
+        let pos = position("4k3/8/8/8/8/8/4K3/2B5 b - - 0 1");
+        let history = History::new(&pos);
+
+        assert_eq!(
+            Adjudication::default().after_move(&pos, &history),
+            Some(DrawReason::DeadPosition)
+        );
+        let rules = Adjudication {
+            dead_position: false,
+            ..Adjudication::default()
+        };
+        assert_eq!(rules.after_move(&pos, &history), None);
+    }
+
+    #[test]
+    fn flag_fall_against_bare_king_is_a_draw() {
+        // White has a rook; black only a king.
+        let pos = position("4k3/8/8/8/8/8/4K3/R7 w - - 0 1");
+        let rules = Adjudication {
+            timeout_vs_insufficient_material: true,
+            ..Adjudication::default()
+        };
+
+        assert_eq!(rules.timeout(&pos, Color::White), GameStatus::Draw);
+        assert_eq!(
+            rules.timeout(&pos, Color::Black),
+            GameStatus::Timeout {
+                loser: Color::Black
+            }
+        );
+        assert_eq!(
+            Adjudication::default().timeout(&pos, Color::White),
+            GameStatus::Timeout {
+                loser: Color::White
+            }
+        );
+    }
+}
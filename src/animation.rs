@@ -0,0 +1,581 @@
+//! Time-based animations layered over rendered feedback.
+//!
+//! [`Animator`] turns a [`BoardFeedback`] plus the current time into a
+//! [`Frame`]. Some animations are derived from the feedback itself (the
+//! check pulse and the gentler pulse on origin squares); others are
+//! triggered explicitly via [`Animator::play`] (the move-confirm flash and
+//! the game-start sweep). Feedback changes crossfade, so a completed move's
+//! guidance fades out. Time is passed in, so sequences are fully
+//! deterministic on the host, and displays render a frame per main-loop
+//! tick instead of waiting for an animation to finish.
+
+use std::time::Duration;
+
+use shakmaty::{CastlingSide, File, Move, Rank, Square};
+
+use crate::feedback::{BoardFeedback, SquareFeedback};
+use crate::frame::{Frame, LedPalette};
+
+/// Length of one on/off phase of the move-confirm flash.
+pub const MOVE_CONFIRM_PHASE: Duration = Duration::from_millis(100);
+
+/// Total length of the move-confirm flash (on, off, on).
+pub const MOVE_CONFIRM_DURATION: Duration = Duration::from_millis(300);
+
+/// Period of the check pulse (bright → dim → bright).
+pub const CHECK_PULSE_PERIOD: Duration = Duration::from_millis(1000);
+
+/// Period of the origin pulse, slower than the check pulse.
+pub const ORIGIN_PULSE_PERIOD: Duration = Duration::from_millis(1600);
+
+/// Time the game-start sweep spends on each rank.
+pub const SWEEP_RANK_DURATION: Duration = Duration::from_millis(50);
+
+/// Default length of the crossfade between consecutive feedback frames.
+pub const DEFAULT_FADE_DURATION: Duration = Duration::from_millis(150);
+
+/// Dimmest brightness level of the check pulse, out of 255.
+const CHECK_PULSE_MIN_LEVEL: u8 = 64;
+
+/// Dimmest brightness level of the origin pulse, out of 255.
+const ORIGIN_PULSE_MIN_LEVEL: u8 = 128;
+
+/// A transient animation triggered by the application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Animation {
+    /// Flash a square to acknowledge that a move was committed there.
+    MoveConfirm { square: Square },
+    /// Run a lit rank across the board from `from` (the first or eighth
+    /// rank) to the other edge, e.g. when a game starts.
+    Sweep { from: Rank },
+}
+
+impl Animation {
+    /// Move-confirm flash on the square the moving piece landed on.
+    ///
+    /// For castling this is the king's destination, matching the square
+    /// highlighted while the move was being made.
+    pub fn move_confirm(mv: &Move) -> Self {
+        let square = match *mv {
+            Move::Castle { king, rook } => {
+                let side = CastlingSide::from_king_side(king < rook);
+                Square::from_coords(side.king_to_file(), king.rank())
+            }
+            _ => mv.to(),
+        };
+        Animation::MoveConfirm { square }
+    }
+
+    /// Sweep from White's side of the board as a game starts.
+    pub fn game_start() -> Self {
+        Animation::Sweep { from: Rank::First }
+    }
+
+    /// The same animation on a board turned 180° (see
+    /// [`BoardFeedback::rotated`](crate::feedback::BoardFeedback::rotated)).
+    pub fn rotated(self) -> Self {
+        match self {
+            Animation::MoveConfirm { square } => Animation::MoveConfirm {
+                square: square.rotate_180(),
+            },
+            Animation::Sweep { from } => Animation::Sweep {
+                from: from.flip_vertical(),
+            },
+        }
+    }
+}
+
+/// Renders feedback into frames, applying active animations.
+///
+/// When the feedback changes, the underlying colors crossfade from what
+/// was shown to the new frame over the fade duration. Pulses and flashes
+/// are drawn on top of the fading frame.
+#[derive(Debug, Clone)]
+pub struct Animator {
+    flashes: Vec<(Square, Duration)>,
+    /// Sweep in progress: the rank it started from and when.
+    sweep: Option<(Rank, Duration)>,
+    check_since: Option<Duration>,
+    origin_since: Option<Duration>,
+    fade_duration: Duration,
+    /// The feedback frame last rendered, before animations.
+    base: Option<Frame>,
+    /// Crossfade in progress: the frame faded from and when it started.
+    fade: Option<(Frame, Duration)>,
+}
+
+impl Default for Animator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Animator {
+    pub fn new() -> Self {
+        Self::with_fade(DEFAULT_FADE_DURATION)
+    }
+
+    /// An animator whose feedback crossfades take `fade_duration`
+    /// (`Duration::ZERO` switches instantly).
+    pub fn with_fade(fade_duration: Duration) -> Self {
+        Self {
+            flashes: Vec::new(),
+            sweep: None,
+            check_since: None,
+            origin_since: None,
+            fade_duration,
+            base: None,
+            fade: None,
+        }
+    }
+
+    /// Start an animation at time `now`.
+    pub fn play(&mut self, animation: Animation, now: Duration) {
+        match animation {
+            Animation::MoveConfirm { square } => {
+                self.flashes.retain(|&(sq, _)| sq != square);
+                self.flashes.push((square, now));
+            }
+            Animation::Sweep { from } => self.sweep = Some((from, now)),
+        }
+    }
+
+    /// Whether any animation is running, i.e. whether the frame would change
+    /// over time even if the feedback does not.
+    pub fn is_animating(&self) -> bool {
+        !self.flashes.is_empty()
+            || self.sweep.is_some()
+            || self.check_since.is_some()
+            || self.origin_since.is_some()
+            || self.fade.is_some()
+    }
+
+    /// Render `feedback` at time `now`.
+    ///
+    /// Status indications are shown as-is; animations only apply to
+    /// square feedback.
+    pub fn render(
+        &mut self,
+        feedback: &BoardFeedback,
+        palette: &LedPalette,
+        now: Duration,
+    ) -> Frame {
+        let mut frame = self.crossfade(Frame::render(feedback, palette), now);
+        self.flashes
+            .retain(|&(_, start)| now.saturating_sub(start) < MOVE_CONFIRM_DURATION);
+        if self
+            .sweep
+            .is_some_and(|(_, start)| now.saturating_sub(start) >= SWEEP_RANK_DURATION * 8)
+        {
+            self.sweep = None;
+        }
+
+        if feedback.status().is_some() {
+            self.check_since = None;
+            self.origin_since = None;
+            return frame;
+        }
+
+        let (mut in_check, mut has_origin) = (false, false);
+        for (sq, fb) in feedback.squares() {
+            let (since, color, period, min_level) = match fb {
+                SquareFeedback::Check => {
+                    in_check = true;
+                    (
+                        &mut self.check_since,
+                        palette.check,
+                        CHECK_PULSE_PERIOD,
+                        CHECK_PULSE_MIN_LEVEL,
+                    )
+                }
+                SquareFeedback::Origin => {
+                    has_origin = true;
+                    (
+                        &mut self.origin_since,
+                        palette.origin,
+                        ORIGIN_PULSE_PERIOD,
+                        ORIGIN_PULSE_MIN_LEVEL,
+                    )
+                }
+                _ => continue,
+            };
+            let elapsed = now.saturating_sub(*since.get_or_insert(now));
+            frame.set(sq, color.scale(pulse_level(elapsed, period, min_level)));
+        }
+        if !in_check {
+            self.check_since = None;
+        }
+        if !has_origin {
+            self.origin_since = None;
+        }
+
+        if let Some((from, start)) = self.sweep {
+            let step =
+                (now.saturating_sub(start).as_millis() / SWEEP_RANK_DURATION.as_millis()) as u32;
+            let rank = match from {
+                Rank::First => Rank::new(step),
+                _ => Rank::new(7 - step),
+            };
+            for file in File::ALL {
+                frame.set(Square::from_coords(file, rank), palette.destination);
+            }
+        }
+
+        for &(sq, start) in &self.flashes {
+            let phase = now.saturating_sub(start).as_millis() / MOVE_CONFIRM_PHASE.as_millis();
+            let color = if phase.is_multiple_of(2) {
+                palette.destination
+            } else {
+                palette.off
+            };
+            frame.set(sq, color);
+        }
+
+        frame
+    }
+
+    /// Blend from the previously shown frame towards `target`, restarting
+    /// the fade whenever the target changes.
+    fn crossfade(&mut self, target: Frame, now: Duration) -> Frame {
+        match self.base {
+            Some(prev) if prev != target => {
+                // Start from whatever is on screen, which may itself be mid-fade.
+                let shown = self.blend(prev, now);
+                self.fade = Some((shown, now));
+            }
+            _ => {}
+        }
+        self.base = Some(target);
+        self.blend(target, now)
+    }
+
+    fn blend(&mut self, target: Frame, now: Duration) -> Frame {
+        let Some((from, start)) = self.fade else {
+            return target;
+        };
+        let elapsed = now.saturating_sub(start);
+        if elapsed >= self.fade_duration {
+            self.fade = None;
+            return target;
+        }
+        let t = (elapsed.as_micros() * 255 / self.fade_duration.as_micros()) as u8;
+        from.blend(&target, t)
+    }
+}
+
+/// Triangle wave over `period` from full brightness down to `min_level`
+/// and back.
+fn pulse_level(elapsed: Duration, period: Duration, min_level: u8) -> u8 {
+    let period = period.as_millis();
+    let half = period / 2;
+    let phase = elapsed.as_millis() % period;
+    let distance = phase.abs_diff(half);
+    let span = u128::from(u8::MAX - min_level);
+    min_level + (span * distance / half) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::TICK_INTERVAL;
+    use crate::frame::Rgb8;
+    use shakmaty::{Chess, uci::UciMove};
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    /// Color of `square` at each tick from `start` for `ticks` ticks.
+    fn timeline(
+        animator: &mut Animator,
+        feedback: &BoardFeedback,
+        square: Square,
+        start: Duration,
+        ticks: u32,
+    ) -> Vec<Rgb8> {
+        let palette = LedPalette::default();
+        (0..ticks)
+            .map(|i| {
+                animator
+                    .render(feedback, &palette, start + TICK_INTERVAL * i)
+                    .get(square)
+            })
+            .collect()
+    }
+
+    // ── golden: move confirm flash ──────────────────────────────────
+
+    #[test]
+    fn golden_move_confirm_flash() {
+        const ON: Rgb8 = Rgb8::new(0, 20, 0);
+        const OFF: Rgb8 = Rgb8::new(0, 0, 0);
+
+        let mut animator = Animator::new();
+        animator.play(Animation::MoveConfirm { square: Square::E4 }, ms(1000));
+
+        let frames = timeline(
+            &mut animator,
+            &BoardFeedback::new(),
+            Square::E4,
+            ms(1000),
+            8,
+        );
+
+        assert_eq!(frames, [ON, ON, OFF, OFF, ON, ON, OFF, OFF]);
+        assert!(!animator.is_animating());
+    }
+
+    #[test]
+    fn golden_move_confirm_full_frame() {
+        let palette = LedPalette::default();
+        let mut animator = Animator::new();
+        animator.play(Animation::MoveConfirm { square: Square::E4 }, ms(0));
+
+        let frame = animator.render(&BoardFeedback::new(), &palette, ms(0));
+
+        let mut expected = Frame::filled(palette.off);
+        expected.set(Square::E4, palette.destination);
+        assert_eq!(frame, expected);
+    }
+
+    #[test]
+    fn move_confirm_overrides_existing_square_feedback() {
+        let mut fb = BoardFeedback::new();
+        fb.set(Square::E4, SquareFeedback::Origin);
+        let mut animator = Animator::new();
+        animator.play(Animation::MoveConfirm { square: Square::E4 }, ms(0));
+
+        let frames = timeline(&mut animator, &fb, Square::E4, ms(0), 7);
+
+        let palette = LedPalette::default();
+        assert_eq!(frames[2], palette.off);
+        let level = pulse_level(
+            TICK_INTERVAL * 6,
+            ORIGIN_PULSE_PERIOD,
+            ORIGIN_PULSE_MIN_LEVEL,
+        );
+        assert_eq!(frames[6], palette.origin.scale(level));
+    }
+
+    #[test]
+    fn replaying_move_confirm_restarts_flash() {
+        let mut animator = Animator::new();
+        let palette = LedPalette::default();
+        animator.play(Animation::MoveConfirm { square: Square::E4 }, ms(0));
+        animator.play(Animation::MoveConfirm { square: Square::E4 }, ms(250));
+
+        let frame = animator.render(&BoardFeedback::new(), &palette, ms(320));
+        assert_eq!(frame.get(Square::E4), palette.destination);
+    }
+
+    #[test]
+    fn move_confirm_for_castle_uses_king_destination() {
+        let pos: Chess = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"
+            .parse::<shakmaty::fen::Fen>()
+            .unwrap()
+            .into_position(shakmaty::CastlingMode::Standard)
+            .unwrap();
+        let mv = "e1g1".parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+
+        assert_eq!(
+            Animation::move_confirm(&mv),
+            Animation::MoveConfirm { square: Square::G1 }
+        );
+    }
+
+    // ── golden: check pulse ─────────────────────────────────────────
+
+    #[test]
+    fn golden_check_pulse() {
+        let mut fb = BoardFeedback::new();
+        fb.set(Square::E1, SquareFeedback::Check);
+        let mut animator = Animator::new();
+
+        // Sample every 250ms across one full period, starting when check appears.
+        let palette = LedPalette::default();
+        let reds: Vec<u8> = (0..5)
+            .map(|i| {
+                animator
+                    .render(&fb, &palette, ms(5000 + 250 * i))
+                    .get(Square::E1)
+                    .r
+            })
+            .collect();
+
+        assert_eq!(reds, [20, 12, 5, 12, 20]);
+    }
+
+    #[test]
+    fn check_pulse_restarts_when_check_reappears() {
+        let mut check = BoardFeedback::new();
+        check.set(Square::E1, SquareFeedback::Check);
+        let palette = LedPalette::default();
+        let mut animator = Animator::new();
+
+        animator.render(&check, &palette, ms(0));
+        animator.render(&BoardFeedback::new(), &palette, ms(500));
+        let frame = animator.render(&check, &palette, ms(700));
+
+        assert_eq!(frame.get(Square::E1), palette.check);
+    }
+
+    #[test]
+    fn checker_square_does_not_pulse() {
+        let mut fb = BoardFeedback::new();
+        fb.set(Square::E1, SquareFeedback::Check);
+        fb.set(Square::E8, SquareFeedback::Checker);
+        let palette = LedPalette::default();
+
+        let frames = timeline(&mut Animator::new(), &fb, Square::E8, ms(0), 10);
+
+        assert!(frames.iter().all(|&c| c == palette.checker));
+    }
+
+    #[test]
+    fn status_suppresses_animations() {
+        let mut fb = BoardFeedback::with_status(crate::feedback::StatusKind::Pending);
+        fb.set(Square::E1, SquareFeedback::Check);
+        let palette = LedPalette::default();
+        let mut animator = Animator::new();
+        animator.play(Animation::MoveConfirm { square: Square::C3 }, ms(0));
+
+        let frame = animator.render(&fb, &palette, ms(0));
+
+        assert_eq!(frame, Frame::render(&fb, &palette));
+    }
+
+    #[test]
+    fn origin_pulses_gently() {
+        let mut fb = BoardFeedback::new();
+        fb.set(Square::E2, SquareFeedback::Origin);
+        let mut animator = Animator::with_fade(Duration::ZERO);
+
+        let palette = LedPalette::default();
+        let blues: Vec<u8> = (0..5)
+            .map(|i| {
+                animator
+                    .render(&fb, &palette, ms(2000 + 400 * i))
+                    .get(Square::E2)
+                    .b
+            })
+            .collect();
+
+        assert_eq!(blues, [20, 14, 10, 14, 20]);
+        assert!(animator.is_animating());
+    }
+
+    // ── golden: game-start sweep ────────────────────────────────────
+
+    #[test]
+    fn golden_sweep_crosses_the_board_once() {
+        let palette = LedPalette::default();
+        let mut animator = Animator::new();
+        animator.play(Animation::game_start(), ms(0));
+
+        let lit_ranks: Vec<Vec<Rank>> = (0..9)
+            .map(|i| {
+                let frame = animator.render(&BoardFeedback::new(), &palette, ms(50 * i));
+                Rank::ALL
+                    .into_iter()
+                    .filter(|&rank| frame.get(Square::from_coords(File::C, rank)) != palette.off)
+                    .collect()
+            })
+            .collect();
+
+        for (i, ranks) in lit_ranks[..8].iter().enumerate() {
+            assert_eq!(ranks, &[Rank::new(i as u32)]);
+        }
+        assert!(lit_ranks[8].is_empty());
+        assert!(!animator.is_animating());
+    }
+
+    #[test]
+    fn rotated_sweep_starts_from_the_eighth_rank() {
+        let palette = LedPalette::default();
+        let mut animator = Animator::new();
+        animator.play(Animation::game_start().rotated(), ms(0));
+
+        let frame = animator.render(&BoardFeedback::new(), &palette, ms(0));
+
+        assert_eq!(frame.get(Square::A8), palette.destination);
+        assert_eq!(frame.get(Square::A1), palette.off);
+    }
+
+    // ── golden: crossfade ───────────────────────────────────────────
+
+    #[test]
+    fn golden_destination_fade_out() {
+        let mut dest = BoardFeedback::new();
+        dest.set(Square::E4, SquareFeedback::Destination);
+        let palette = LedPalette::default();
+        let mut animator = Animator::new();
+        animator.render(&dest, &palette, ms(0));
+
+        let greens: Vec<u8> = timeline(
+            &mut animator,
+            &BoardFeedback::new(),
+            Square::E4,
+            ms(1000),
+            5,
+        )
+        .iter()
+        .map(|c| c.g)
+        .collect();
+
+        assert_eq!(greens, [20, 14, 7, 0, 0]);
+        assert!(!animator.is_animating());
+    }
+
+    #[test]
+    fn first_render_does_not_fade() {
+        let mut fb = BoardFeedback::new();
+        fb.set(Square::E4, SquareFeedback::Destination);
+        let palette = LedPalette::default();
+
+        let frame = Animator::new().render(&fb, &palette, ms(0));
+
+        assert_eq!(frame, Frame::render(&fb, &palette));
+    }
+
+    #[test]
+    fn fade_counts_as_animating() {
+        let mut fb = BoardFeedback::new();
+        fb.set(Square::E4, SquareFeedback::Destination);
+        let palette = LedPalette::default();
+        let mut animator = Animator::new();
+
+        animator.render(&BoardFeedback::new(), &palette, ms(0));
+        animator.render(&fb, &palette, ms(50));
+
+        assert!(animator.is_animating());
+    }
+
+    #[test]
+    fn changing_mid_fade_starts_from_shown_colors() {
+        let mut dest = BoardFeedback::new();
+        dest.set(Square::E4, SquareFeedback::Destination);
+        let palette = LedPalette::default();
+        let mut animator = Animator::new();
+
+        animator.render(&dest, &palette, ms(0));
+        animator.render(&BoardFeedback::new(), &palette, ms(1000));
+        let halfway = animator.render(&BoardFeedback::new(), &palette, ms(1075));
+        let back = animator.render(&dest, &palette, ms(1075));
+
+        assert_eq!(back.get(Square::E4), halfway.get(Square::E4));
+    }
+
+    #[test]
+    fn zero_fade_switches_instantly() {
+        let mut dest = BoardFeedback::new();
+        dest.set(Square::E4, SquareFeedback::Destination);
+        let palette = LedPalette::default();
+        let mut animator = Animator::with_fade(Duration::ZERO);
+
+        animator.render(&dest, &palette, ms(0));
+        let frame = animator.render(&BoardFeedback::new(), &palette, ms(50));
+
+        assert_eq!(frame.get(Square::E4), palette.off);
+        assert!(!animator.is_animating());
+    }
+}
@@ -0,0 +1,4499 @@
+//! Platform-independent board application loop.
+//!
+//! [`BoardApp`] owns the game lifecycle state machine (Idle → AwaitingPieces →
+//! InProgress) that used to live in `main.rs`. It is generic over the sensor,
+//! display, notifier, and clock so the exact loop that runs on the ESP32 can
+//! also run on the host against scripted inputs and virtual time.
+
+use std::borrow::Cow;
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use shakmaty::fen::Fen;
+use shakmaty::uci::UciMove;
+use shakmaty::{
+    Bitboard, ByColor, CastlingMode, Chess, Color, EnPassantMode, Move, Position, Role,
+};
+
+use crate::abort::{AbortGesture, AbortSignal, prompt_feedback};
+use crate::access::ConnectionId;
+use crate::adjudication::Adjudication;
+use crate::animation::Animation;
+use crate::arbitration::GestureArbiter;
+use crate::ble_protocol::{BleCommand, CommandResult, CommandSource, ErrorCode};
+use crate::board_api::{ExternalResult, GameEvent, GameStatus, PlayerType, StateChecksum};
+use crate::board_config::BoardConfig;
+use crate::calibration::{Calibrator, empty_board_faults, self_test_colors, self_test_feedback};
+use crate::chess_clock::{ClockSettings, GameClock, StartHandshake, overlay_clock_bar};
+use crate::debounce::FeedbackDebounce;
+use crate::edge::EdgeFeedback;
+use crate::feedback::{BoardFeedback, SquareFeedback, result_feedback};
+use crate::gestures::{Gesture, GestureSignal, gesture_feedback};
+use crate::heatmap::HeatMapMode;
+use crate::log_filter;
+use crate::mode::{GameMode, ModeSelection, ModeStatus};
+use crate::net::lichess::{Lichess, LichessGame, LichessTransport, Matchmaking};
+use crate::net::relay::{Relay, RelayGame, RelayTransport};
+use crate::pairing::{Pairing, PairingError, Token};
+use crate::player::{HumanPlayer, MatcherKind, Player, PlayerStatus, RandomPlayer, RemotePlayer};
+use crate::profiles::{Profile, ProfileStore};
+use crate::saved_game::{GameStore, SavedClock, SavedGame};
+use crate::session::{GameSession, MoveConfirmation, PromotionPolicy, TickEvent};
+use crate::settings::{AssistLevel, DisplaySettings, Setting, Theme};
+use crate::setup::{Placement, setup_placement};
+use crate::stats::SessionStats;
+use crate::tournament::{GameResult, Tournament, TournamentStore};
+use crate::wifi::WifiStatus;
+use crate::{BoardDisplay, EdgeDisplay, PieceSensor};
+
+/// Delay between loop iterations during normal operation.
+pub const TICK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a promotion waits for a client to choose a piece before
+/// defaulting to a queen.
+pub const PROMOTION_CHOICE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Length of one on/off phase of the blink on a move waiting for
+/// confirmation because it may be a misdetection.
+pub const UNCERTAIN_MOVE_BLINK_PHASE: Duration = Duration::from_millis(250);
+
+/// Delay before retrying after a failed sensor read.
+pub const SENSOR_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Monotonic time source for the application loop.
+///
+/// Abstracts over the system clock (hardware) and a manually advanced
+/// clock (host simulation).
+pub trait Clock {
+    /// Time elapsed since an arbitrary, fixed origin.
+    fn now(&self) -> Duration;
+}
+
+/// [`Clock`] backed by [`std::time::Instant`].
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl SystemClock {
+    /// Create a clock whose origin is the moment of construction.
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// Something a client connection did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientEvent {
+    /// The connection sent a command.
+    Command(ConnectionId, BleCommand),
+    /// The connection closed; its authentication ends (see
+    /// [`crate::pairing`]).
+    Disconnected(ConnectionId),
+}
+
+/// Source of inbound client events, drained once per loop iteration.
+pub trait CommandQueue {
+    /// Return the next queued event without blocking.
+    fn try_recv(&mut self) -> Option<ClientEvent>;
+}
+
+/// Sink for board state pushed to connected clients.
+///
+/// Mirrors the state and events of `docs/board-api.md`. Implemented by the
+/// BLE characteristic handles on hardware and by a recorder in tests.
+pub trait BoardNotifier {
+    /// Publish a new game status.
+    fn notify_game_status(&mut self, status: &GameStatus);
+
+    /// Publish the result of a client command.
+    fn notify_command_result(&mut self, result: &CommandResult);
+
+    /// Publish the player type for one side after a game starts.
+    fn update_player_type(&mut self, color: Color, player_type: PlayerType);
+
+    /// Emit a `MovePlayed` event; `checksum` is of the position after it.
+    fn notify_move_played(&mut self, color: Color, uci: &str, checksum: StateChecksum);
+
+    /// Publish the current position as FEN.
+    fn update_position(&mut self, fen: &str);
+
+    /// Publish the most recent move, with the checksum of the position
+    /// after it.
+    fn update_last_move(&mut self, color: Color, uci: &str, checksum: StateChecksum);
+
+    /// Reset both player types to unset (after a game ends).
+    fn reset_player_types(&mut self);
+
+    /// Clear the published position.
+    fn reset_position(&mut self);
+
+    /// Clear the published last move.
+    fn reset_last_move(&mut self);
+
+    /// Publish a promotion waiting for a piece choice (`uci` without the
+    /// promotion suffix, e.g. `e7e8`).
+    fn update_pending_promotion(&mut self, color: Color, uci: &str);
+
+    /// Clear the published pending promotion.
+    fn reset_pending_promotion(&mut self);
+
+    /// Emit a [`GameEvent`].
+    fn notify_game_event(&mut self, event: &GameEvent);
+
+    /// Whether a client is connected, shown on the edge LEDs.
+    fn is_connected(&self) -> bool;
+
+    /// Remaining time on the on-board clock whenever it starts or switches
+    /// sides. Not part of the BLE protocol; event exporters use it (see
+    /// [`crate::export`]). Default is a no-op.
+    fn update_clock(&mut self, _remaining: ByColor<Duration>, _running: Option<Color>) {}
+
+    /// Hand a newly paired client its token (see [`crate::pairing`]).
+    /// Default is a no-op.
+    fn notify_pairing_token(&mut self, _token: Token) {}
+}
+
+/// No client link, e.g. when BLE failed to start: nothing arrives.
+impl<Q: CommandQueue> CommandQueue for Option<Q> {
+    fn try_recv(&mut self) -> Option<ClientEvent> {
+        self.as_mut()?.try_recv()
+    }
+}
+
+/// No client link: updates go nowhere and no client is connected.
+impl<N: BoardNotifier> BoardNotifier for Option<N> {
+    fn notify_game_status(&mut self, status: &GameStatus) {
+        if let Some(n) = self {
+            n.notify_game_status(status);
+        }
+    }
+
+    fn notify_command_result(&mut self, result: &CommandResult) {
+        if let Some(n) = self {
+            n.notify_command_result(result);
+        }
+    }
+
+    fn update_player_type(&mut self, color: Color, player_type: PlayerType) {
+        if let Some(n) = self {
+            n.update_player_type(color, player_type);
+        }
+    }
+
+    fn notify_move_played(&mut self, color: Color, uci: &str, checksum: StateChecksum) {
+        if let Some(n) = self {
+            n.notify_move_played(color, uci, checksum);
+        }
+    }
+
+    fn update_position(&mut self, fen: &str) {
+        if let Some(n) = self {
+            n.update_position(fen);
+        }
+    }
+
+    fn update_last_move(&mut self, color: Color, uci: &str, checksum: StateChecksum) {
+        if let Some(n) = self {
+            n.update_last_move(color, uci, checksum);
+        }
+    }
+
+    fn reset_player_types(&mut self) {
+        if let Some(n) = self {
+            n.reset_player_types();
+        }
+    }
+
+    fn reset_position(&mut self) {
+        if let Some(n) = self {
+            n.reset_position();
+        }
+    }
+
+    fn reset_last_move(&mut self) {
+        if let Some(n) = self {
+            n.reset_last_move();
+        }
+    }
+
+    fn update_pending_promotion(&mut self, color: Color, uci: &str) {
+        if let Some(n) = self {
+            n.update_pending_promotion(color, uci);
+        }
+    }
+
+    fn reset_pending_promotion(&mut self) {
+        if let Some(n) = self {
+            n.reset_pending_promotion();
+        }
+    }
+
+    fn notify_game_event(&mut self, event: &GameEvent) {
+        if let Some(n) = self {
+            n.notify_game_event(event);
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        self.as_ref().is_some_and(N::is_connected)
+    }
+
+    fn update_clock(&mut self, remaining: ByColor<Duration>, running: Option<Color>) {
+        if let Some(n) = self {
+            n.update_clock(remaining, running);
+        }
+    }
+
+    fn notify_pairing_token(&mut self, token: Token) {
+        if let Some(n) = self {
+            n.notify_pairing_token(token);
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MoveParseError {
+    #[error("invalid UCI notation")]
+    InvalidUci,
+    #[error("illegal move in current position")]
+    IllegalMove,
+}
+
+/// Parse a UCI string into a legal move in `position`.
+pub fn parse_uci_move(position: &Chess, uci: &str) -> Result<Move, MoveParseError> {
+    let uci_move = UciMove::from_str(uci).map_err(|_| MoveParseError::InvalidUci)?;
+    uci_move
+        .to_move(position)
+        .map_err(|_| MoveParseError::IllegalMove)
+}
+
+/// Build the [`Player`] for a side, plus the sender used to deliver its
+/// moves when it is remote. `seed` drives a random player's choices. An
+/// online side plays `online`, the opponent found for it; without one,
+/// e.g. in a game restored after a restart, its moves come from
+/// `SubmitMove` as a remote side's do.
+pub fn create_player(
+    player_type: PlayerType,
+    initial_positions: ByColor<Bitboard>,
+    matcher: MatcherKind,
+    seed: u32,
+    online: Option<Box<dyn Player>>,
+) -> (Box<dyn Player>, Option<mpsc::Sender<Move>>) {
+    if player_type.is_online()
+        && let Some(opponent) = online
+    {
+        return (opponent, None);
+    }
+    match player_type {
+        PlayerType::Human => (
+            Box::new(HumanPlayer::with_matcher(matcher.build(initial_positions))),
+            None,
+        ),
+        PlayerType::Remote | PlayerType::Lichess | PlayerType::Relay => {
+            let (tx, rx) = mpsc::channel();
+            (Box::new(RemotePlayer::new(rx)), Some(tx))
+        }
+        PlayerType::Random => (Box::new(RandomPlayer::new(seed)), None),
+    }
+}
+
+enum BoardState {
+    Idle,
+    AwaitingPieces {
+        white: PlayerType,
+        black: PlayerType,
+        /// How the board differed from the starting position last tick.
+        placement: Placement,
+        /// The online side's opponent, while it is being found.
+        opponent: Option<OnlineOpponent>,
+    },
+    InProgress {
+        session: Box<GameSession>,
+        white_tx: Option<mpsc::Sender<Move>>,
+        black_tx: Option<mpsc::Sender<Move>>,
+        started_at: Duration,
+        abort: AbortGesture,
+        /// When the pending promotion (if any) was first published.
+        promotion_since: Option<Duration>,
+        clock: Option<GameClock>,
+        /// Readiness check before the clock starts; `None` once it runs.
+        handshake: Option<StartHandshake>,
+        players: ByColor<PlayerType>,
+    },
+    /// A [`GameMode`] other than a client-managed game (mini-game, analysis, ...).
+    Mode {
+        mode: Box<dyn GameMode>,
+    },
+    /// Sensor calibration driven by client commands.
+    Calibrating {
+        calibrator: Calibrator,
+    },
+}
+
+/// An opponent found online for a [`PlayerType::Lichess`] or
+/// [`PlayerType::Relay`] side. The service decides the colors.
+enum OnlineOpponent {
+    Lichess(LichessGame),
+    Relay(RelayGame),
+}
+
+impl OnlineOpponent {
+    /// The side the board plays, once the opponent is found.
+    fn local_color(&mut self) -> Option<Color> {
+        match self {
+            OnlineOpponent::Lichess(game) => game.local_color(),
+            OnlineOpponent::Relay(game) => game.local_color(),
+        }
+    }
+
+    fn player(&self) -> &dyn Player {
+        match self {
+            OnlineOpponent::Lichess(game) => game,
+            OnlineOpponent::Relay(game) => game,
+        }
+    }
+
+    fn into_player(self) -> Box<dyn Player> {
+        match self {
+            OnlineOpponent::Lichess(game) => Box::new(game),
+            OnlineOpponent::Relay(game) => Box::new(game),
+        }
+    }
+}
+
+/// Preferences a [`Profile`] overrides, put back when its game ends.
+#[derive(Debug, Clone, Copy)]
+struct Preferences {
+    theme: Theme,
+    assist: AssistLevel,
+    clock: Option<ClockSettings>,
+    orientation: Color,
+}
+
+/// Whether command draining should continue or stop so the loop ticks first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommandFlow {
+    Continue,
+    Tick,
+}
+
+/// The board application: command handling, game lifecycle, and per-tick
+/// sensor → session → display plumbing.
+pub struct BoardApp<S, D, N, C> {
+    sensor: S,
+    display: D,
+    notifier: N,
+    clock: C,
+    state: BoardState,
+    prev_positions: Option<ByColor<Bitboard>>,
+    prev_game_state: Option<GameStatus>,
+    /// Squares with failed sensors, passed on to each game session.
+    dead_squares: Bitboard,
+    /// Clock for games started from now on.
+    clock_settings: Option<ClockSettings>,
+    /// Draw remaining clock time on the board edge.
+    clock_bar: bool,
+    /// Automatic draw rules for games started from now on.
+    adjudication: Adjudication,
+    /// Usage since power-on.
+    stats: SessionStats,
+    /// Moves of the last game that ended, for the heat map mode.
+    last_game: Option<Vec<Move>>,
+    /// How human moves are detected in future games.
+    matcher: MatcherKind,
+    /// Which human moves future games without tournament confirmation hold.
+    move_confirmation: MoveConfirmation,
+    /// Hides game feedback of readings that pass in a few ticks.
+    debounce: FeedbackDebounce,
+    /// Resign and new-game gestures during a game, giving way to moves.
+    gestures: GestureArbiter,
+    /// Commands are only accepted from paired clients when set.
+    pairing: Option<Pairing>,
+    /// A pairing code is on the display.
+    pairing_code_shown: bool,
+    /// Brightness and theme last handed to the display.
+    display_settings: DisplaySettings,
+    /// How much games guide the player, including the one in progress.
+    assist: AssistLevel,
+    /// Shown on the edge LEDs on boards that use WiFi.
+    wifi: Option<WifiStatus>,
+    /// Keeps the game in progress across power cuts.
+    game_store: Option<Box<dyn GameStore>>,
+    /// Moves of the game in the store, if one is saved.
+    saved_plies: Option<usize>,
+    /// Club tournament whose games are played on this board.
+    tournament: Option<Tournament>,
+    /// Keeps the tournament across power cycles.
+    tournament_store: Option<Box<dyn TournamentStore>>,
+    /// Side set up along the board's first rank unless a game decides (see
+    /// [`Self::perspective`]).
+    orientation: Color,
+    /// Players' preferences, selected by clients before a game.
+    profiles: Vec<Profile>,
+    /// Keeps the profiles across power cycles.
+    profile_store: Option<Box<dyn ProfileStore>>,
+    /// The selected profile's name and the preferences it replaced.
+    selected_profile: Option<(String, Preferences)>,
+    /// Finds [`PlayerType::Lichess`] opponents once an account is linked.
+    lichess: Option<Box<dyn Fn(Matchmaking) -> LichessGame>>,
+    /// Finds [`PlayerType::Relay`] opponents once a room is set.
+    relay: Option<Box<dyn Fn() -> RelayGame>>,
+}
+
+impl<S, D, N, C> std::fmt::Debug for BoardApp<S, D, N, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BoardApp")
+            .field("status", &self.status())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, D, N, C> BoardApp<S, D, N, C> {
+    /// Create an idle application.
+    pub fn new(sensor: S, display: D, notifier: N, clock: C) -> Self {
+        Self {
+            sensor,
+            display,
+            notifier,
+            clock,
+            state: BoardState::Idle,
+            prev_positions: None,
+            prev_game_state: None,
+            dead_squares: Bitboard::EMPTY,
+            clock_settings: None,
+            clock_bar: true,
+            adjudication: Adjudication::default(),
+            stats: SessionStats::new(),
+            last_game: None,
+            matcher: MatcherKind::default(),
+            move_confirmation: MoveConfirmation::default(),
+            debounce: FeedbackDebounce::new(Duration::ZERO),
+            gestures: GestureArbiter::new(),
+            pairing: None,
+            pairing_code_shown: false,
+            display_settings: DisplaySettings::default(),
+            assist: AssistLevel::default(),
+            wifi: None,
+            game_store: None,
+            saved_plies: None,
+            tournament: None,
+            tournament_store: None,
+            orientation: Color::White,
+            profiles: Vec::new(),
+            profile_store: None,
+            selected_profile: None,
+            lichess: None,
+            relay: None,
+        }
+    }
+
+    /// Show `status` on the edge ring's WiFi LED from now on (see
+    /// [`crate::wifi`]).
+    pub fn set_wifi_status(&mut self, status: WifiStatus) {
+        self.wifi = Some(status);
+    }
+
+    /// Only accept commands from clients paired through `pairing` (see
+    /// [`crate::pairing`]). Off by default.
+    pub fn set_pairing(&mut self, pairing: Pairing) {
+        self.pairing = Some(pairing);
+    }
+
+    /// Find [`PlayerType::Lichess`] opponents from `lichess`'s account:
+    /// with a clock set, a seek at its time control; without one, the next
+    /// challenge. Off by default.
+    pub fn set_lichess<T: LichessTransport>(&mut self, lichess: Lichess<T>) {
+        self.lichess = Some(Box::new(move |matchmaking| lichess.find_game(matchmaking)));
+    }
+
+    /// Find [`PlayerType::Relay`] opponents in `relay`'s room; `random`
+    /// draws each game's session and nonces. Off by default.
+    pub fn set_relay<T: RelayTransport>(
+        &mut self,
+        relay: Relay<T>,
+        random: impl FnMut() -> u32 + Clone + Send + 'static,
+    ) {
+        self.relay = Some(Box::new(move || relay.find_game(random.clone())));
+    }
+
+    /// Keep player profiles in `store`, starting with the ones it holds.
+    pub fn set_profile_store(&mut self, store: Box<dyn ProfileStore>) {
+        self.profiles = store.load();
+        self.profile_store = Some(store);
+    }
+
+    /// Replace the player profiles clients select from, saving them to
+    /// the store.
+    pub fn set_profiles(&mut self, profiles: Vec<Profile>) {
+        self.profiles = profiles;
+        if let Some(store) = &mut self.profile_store
+            && let Err(e) = store.save(&self.profiles)
+        {
+            log::warn!("Saving the profiles failed: {e}");
+        }
+    }
+
+    pub fn profiles(&self) -> &[Profile] {
+        &self.profiles
+    }
+
+    /// Choose which side's pieces are set up along the board's first rank
+    /// when no game decides it (see [`Self::perspective`]). White by
+    /// default.
+    pub fn set_orientation(&mut self, orientation: Color) {
+        self.orientation = orientation;
+    }
+
+    /// The side whose pieces sit along the board's first rank, nearest the
+    /// player. A game between a human and an engine or remote opponent is
+    /// set up from the human's side, whatever the orientation; other games,
+    /// modes and idle boards use the orientation. Readings, guidance and the
+    /// edge LEDs are all turned to match.
+    pub fn perspective(&self) -> Color {
+        match self.state {
+            BoardState::AwaitingPieces { white, black, .. } => {
+                seat(ByColor { white, black }, self.orientation)
+            }
+            BoardState::InProgress { players, .. } => seat(players, self.orientation),
+            _ => self.orientation,
+        }
+    }
+
+    /// Save the game in progress to `store` after every move, and clear it
+    /// once the game ends (see [`crate::saved_game`]). Off by default.
+    pub fn set_game_store(&mut self, store: Box<dyn GameStore>) {
+        self.game_store = Some(store);
+    }
+
+    /// Save the tournament to `store` whenever a result is recorded. Off
+    /// by default.
+    pub fn set_tournament_store(&mut self, store: Box<dyn TournamentStore>) {
+        self.tournament_store = Some(store);
+    }
+
+    /// The tournament being played on this board, if any.
+    pub fn tournament(&self) -> Option<&Tournament> {
+        self.tournament.as_ref()
+    }
+
+    /// Choose how future games detect moves on the board (see
+    /// [`crate::player::matcher`]).
+    pub fn set_move_matcher(&mut self, matcher: MatcherKind) {
+        self.matcher = matcher;
+    }
+
+    /// Choose which moves future games hold until the mover confirms them
+    /// (see [`MoveConfirmation`]). A clock set with `confirm_moves`
+    /// overrides this with [`MoveConfirmation::Always`].
+    pub fn set_move_confirmation(&mut self, confirmation: MoveConfirmation) {
+        self.move_confirmation = confirmation;
+    }
+
+    /// Only show game feedback once it has held for `threshold` (see
+    /// [`crate::debounce`]), hiding intermediate states of fast moves. Off
+    /// (zero) by default.
+    pub fn set_feedback_settle(&mut self, threshold: Duration) {
+        self.debounce = FeedbackDebounce::new(threshold);
+    }
+
+    /// Games played, results and sensor health since power-on.
+    pub fn stats(&self) -> &SessionStats {
+        &self.stats
+    }
+
+    /// Choose which draws future games declare automatically (see
+    /// [`crate::adjudication`]).
+    pub fn set_adjudication(&mut self, adjudication: Adjudication) {
+        self.adjudication = adjudication;
+    }
+
+    /// Show remaining clock time as LED bars on the board edge (see
+    /// [`overlay_clock_bar`]). On by default; turn it off when a screen
+    /// shows the clock instead.
+    pub fn set_clock_bar(&mut self, enabled: bool) {
+        self.clock_bar = enabled;
+    }
+
+    /// Treat the sensors on `squares` as dead in future games.
+    ///
+    /// Setup assumes dead squares hold their starting piece, and sessions
+    /// infer their occupancy (see [`GameSession::set_dead_squares`]).
+    pub fn set_dead_squares(&mut self, squares: Bitboard) {
+        if squares.any() {
+            let listed: Vec<_> = squares.into_iter().collect();
+            log::warn!("Masking dead sensors on {listed:?}");
+        }
+        self.dead_squares = squares;
+    }
+
+    /// Current lifecycle status as exposed to clients.
+    pub fn status(&self) -> GameStatus {
+        match &self.state {
+            BoardState::Idle => GameStatus::Idle,
+            BoardState::AwaitingPieces { .. } => GameStatus::AwaitingPieces,
+            BoardState::InProgress { session, .. } => session.game_state(),
+            // Modes are not games from the clients' point of view.
+            BoardState::Mode { .. } | BoardState::Calibrating { .. } => GameStatus::Idle,
+        }
+    }
+
+    /// While waiting for the starting position, the squares still missing
+    /// a piece and the pieces that have to come off.
+    pub fn setup_placement(&self) -> Option<Placement> {
+        match self.state {
+            BoardState::AwaitingPieces { placement, .. } => Some(placement),
+            _ => None,
+        }
+    }
+
+    /// Name of the running [`GameMode`], if any.
+    pub fn mode_name(&self) -> Option<&'static str> {
+        match &self.state {
+            BoardState::Mode { mode } => Some(mode.name()),
+            _ => None,
+        }
+    }
+
+    /// The active game session, if a game is in progress.
+    pub fn session(&self) -> Option<&GameSession> {
+        match &self.state {
+            BoardState::InProgress { session, .. } => Some(session.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// The running game clock, if the game has one.
+    pub fn game_clock(&self) -> Option<&GameClock> {
+        match &self.state {
+            BoardState::InProgress { clock, .. } => clock.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The sensor driving this application.
+    pub fn sensor_mut(&mut self) -> &mut S {
+        &mut self.sensor
+    }
+
+    /// The display driven by this application.
+    pub fn display(&self) -> &D {
+        &self.display
+    }
+
+    pub fn display_mut(&mut self) -> &mut D {
+        &mut self.display
+    }
+
+    /// The notifier receiving state updates.
+    pub fn notifier(&self) -> &N {
+        &self.notifier
+    }
+
+    /// Mutable access to the notifier (e.g. to clear recorded events in tests).
+    pub fn notifier_mut(&mut self) -> &mut N {
+        &mut self.notifier
+    }
+}
+
+impl<S, D, N, C> BoardApp<S, D, N, C>
+where
+    S: PieceSensor,
+    D: BoardDisplay + EdgeDisplay,
+    N: BoardNotifier,
+    C: Clock,
+{
+    /// Run one loop iteration: drain pending commands, then tick.
+    ///
+    /// Returns how long the caller should wait before the next step.
+    pub fn step(&mut self, commands: &mut impl CommandQueue) -> Duration {
+        while let Some(event) = commands.try_recv() {
+            match event {
+                ClientEvent::Command(connection, cmd) => {
+                    if self.handle_command(connection, cmd) == CommandFlow::Tick {
+                        break;
+                    }
+                }
+                ClientEvent::Disconnected(connection) => {
+                    if let Some(pairing) = &mut self.pairing {
+                        pairing.disconnect(connection);
+                    }
+                }
+            }
+        }
+        let delay = self.tick();
+        self.show_edge();
+        self.save_game();
+        delay
+    }
+
+    /// Apply a preference at once: display settings from the next frame
+    /// on, the assist level to the game in progress and future games.
+    pub fn change_setting(&mut self, setting: Setting) {
+        log::info!("Setting changed: {setting:?}");
+        match setting {
+            Setting::Brightness(level) => self.display_settings.brightness = level,
+            Setting::Theme(theme) => self.display_settings.theme = theme,
+            Setting::Assist(assist) => {
+                self.assist = assist;
+                if let BoardState::InProgress { session, .. } = &mut self.state {
+                    session.set_assist_level(assist);
+                }
+                return;
+            }
+        }
+        self.display.apply_settings(&self.display_settings);
+    }
+
+    /// Apply the owner's stored preferences: the theme at once, the
+    /// orientation and clock from the next game on. While a profile is
+    /// selected they wait until its game ends.
+    pub fn apply_config(&mut self, config: &BoardConfig) {
+        if let Some((_, previous)) = &mut self.selected_profile {
+            previous.theme = config.theme;
+            previous.orientation = config.orientation;
+            previous.clock = config.clock_settings();
+            return;
+        }
+        self.change_setting(Setting::Theme(config.theme));
+        self.set_orientation(config.orientation);
+        self.clock_settings = config.clock_settings();
+    }
+
+    fn apply_preferences(&mut self, preferences: Preferences) {
+        self.change_setting(Setting::Theme(preferences.theme));
+        self.change_setting(Setting::Assist(preferences.assist));
+        self.clock_settings = preferences.clock;
+        self.orientation = preferences.orientation;
+    }
+
+    /// Put back the preferences a selected profile replaced, once its
+    /// game is over.
+    fn release_profile(&mut self) {
+        if let Some((name, previous)) = self.selected_profile.take() {
+            log::info!("Game over, restoring the preferences from before {name}'s profile");
+            self.apply_preferences(previous);
+        }
+    }
+
+    /// Play the games of `tournament` on this board, in place of any
+    /// tournament before it: every finished game between two players on
+    /// the board is recorded as the next pairing's result, and the pairing
+    /// after it is lit once the game is over (see [`crate::tournament`]).
+    pub fn start_tournament(&mut self, tournament: Tournament) {
+        self.tournament = Some(tournament);
+        self.save_tournament();
+        let near = self.perspective();
+        self.announce_pairing(matches!(self.state, BoardState::Idle), None, near);
+    }
+
+    /// Log the next pairing, or the final standings once there is none.
+    /// With `show`, light the pairing on the board over `result`.
+    fn announce_pairing(&mut self, show: bool, result: Option<BoardFeedback>, near: Color) {
+        let announcement = match &self.tournament {
+            Some(tournament) => match tournament.next_game() {
+                Some(game) => {
+                    log::info!("Next: {}", tournament.describe(game));
+                    tournament.announcement()
+                }
+                None => {
+                    log::info!("Tournament over:\n{}", tournament.crosstable());
+                    None
+                }
+            },
+            None => None,
+        };
+        if !show {
+            return;
+        }
+        let shown = match (announcement, result) {
+            (Some(mut announcement), Some(result)) => {
+                // The result keeps its squares.
+                for (square, feedback) in result.squares() {
+                    announcement.set(square, feedback);
+                }
+                Some(announcement)
+            }
+            (announcement, result) => announcement.or(result),
+        };
+        if let Some(fb) = shown
+            && let Err(e) = self.display.show(&oriented_feedback(&fb, near))
+        {
+            log::warn!("LED update failed: {e}");
+        }
+    }
+
+    /// Record `status` as the result of the next tournament game. Aborted
+    /// games are replayed, so they are not recorded.
+    fn record_tournament_result(&mut self, status: &GameStatus) {
+        let (Some(tournament), Some(result)) =
+            (&mut self.tournament, GameResult::from_status(status))
+        else {
+            return;
+        };
+        if let Err(e) = tournament.record(result) {
+            log::warn!("Recording the tournament result failed: {e}");
+            return;
+        }
+        log::info!("Standings:\n{}", tournament.crosstable());
+        self.save_tournament();
+    }
+
+    /// Save the tournament to its store, if there is one of each.
+    fn save_tournament(&mut self) {
+        if let (Some(store), Some(tournament)) = (&mut self.tournament_store, &self.tournament)
+            && let Err(e) = store.save(tournament)
+        {
+            log::warn!("Saving the tournament failed: {e}");
+        }
+    }
+
+    /// Brightness and theme currently applied to the display.
+    pub fn display_settings(&self) -> DisplaySettings {
+        self.display_settings
+    }
+
+    /// Continue a game from a stored position, e.g. after power was lost.
+    ///
+    /// Moves are read against the stored board rather than the current
+    /// one, so a move that was under way when the board went down carries
+    /// on: a piece still in hand lights its destinations, a move already
+    /// finished on the board is played on the first tick, and anything
+    /// else is guided back to the stored position.
+    pub fn resume_game(
+        &mut self,
+        white: PlayerType,
+        black: PlayerType,
+        position: Chess,
+    ) -> Result<(), ErrorCode> {
+        if !matches!(self.state, BoardState::Idle) {
+            return Err(ErrorCode::GameAlreadyInProgress);
+        }
+        let stored = position_sensors(&position);
+        self.notifier.update_player_type(Color::White, white);
+        self.notifier.update_player_type(Color::Black, black);
+        self.begin_session(white, black, position, Vec::new(), stored, None);
+        log::info!("Game resumed from a stored position");
+        Ok(())
+    }
+
+    /// Continue a game saved to the [`GameStore`], with its move history
+    /// and clock, as [`Self::resume_game`] does for a bare position.
+    ///
+    /// The clock is stopped with the time each side had at the last move,
+    /// and restarts once both players have confirmed they are back. Its
+    /// settings stay in force for later games, as if set with `SetClock`.
+    pub fn restore_game(&mut self, saved: SavedGame) -> Result<(), ErrorCode> {
+        if !matches!(self.state, BoardState::Idle) {
+            return Err(ErrorCode::GameAlreadyInProgress);
+        }
+        let stored = position_sensors(&saved.position());
+        self.clock_settings = saved.clock.map(|clock| clock.settings);
+        self.notifier.update_player_type(Color::White, saved.white);
+        self.notifier.update_player_type(Color::Black, saved.black);
+        self.begin_session(
+            saved.white,
+            saved.black,
+            saved.start,
+            saved.moves,
+            stored,
+            None,
+        );
+        if let (BoardState::InProgress { clock, .. }, Some(saved)) = (&mut self.state, saved.clock)
+        {
+            *clock = Some(GameClock::resume(
+                saved.settings.time_control,
+                saved.remaining,
+            ));
+        }
+        log::info!("Saved game restored");
+        Ok(())
+    }
+
+    /// Bring the [`GameStore`] up to date: save the game in progress once
+    /// per move, and clear it when the game is over.
+    fn save_game(&mut self) {
+        let Some(store) = &mut self.game_store else {
+            return;
+        };
+        let result = match &self.state {
+            BoardState::InProgress {
+                session,
+                clock,
+                players,
+                ..
+            } if !session.is_game_over() => {
+                let plies = session.moves().len();
+                if self.saved_plies == Some(plies) {
+                    return;
+                }
+                self.saved_plies = Some(plies);
+                let now = self.clock.now();
+                store.save(&SavedGame {
+                    white: players.white,
+                    black: players.black,
+                    start: session.start().clone(),
+                    moves: session.moves().to_vec(),
+                    clock: clock
+                        .as_ref()
+                        .zip(self.clock_settings)
+                        .map(|(clock, settings)| SavedClock {
+                            settings,
+                            remaining: ByColor::new_with(|color| clock.remaining(color, now)),
+                        }),
+                })
+            }
+            _ => {
+                if self.saved_plies.take().is_none() {
+                    return;
+                }
+                store.clear()
+            }
+        };
+        if let Err(e) = result {
+            log::warn!("Saving the game failed: {e}");
+        }
+    }
+
+    /// Update the edge LEDs from the current state.
+    fn show_edge(&mut self) {
+        let connected = self.notifier.is_connected();
+        let edge = match &self.state {
+            BoardState::InProgress { session, clock, .. } => EdgeFeedback::game(
+                session.position().turn(),
+                clock.as_ref(),
+                self.clock.now(),
+                connected,
+            ),
+            _ => EdgeFeedback::idle(connected),
+        };
+        let edge = EdgeFeedback {
+            wifi: self.wifi,
+            near: self.perspective(),
+            ..edge
+        };
+        if let Err(e) = self.display.show_edge(&edge) {
+            log::warn!("Edge LED update failed: {e}");
+        }
+    }
+
+    fn handle_command(&mut self, connection: ConnectionId, cmd: BleCommand) -> CommandFlow {
+        let pairing_command = matches!(
+            cmd,
+            BleCommand::RequestPairing | BleCommand::Pair { .. } | BleCommand::Authenticate { .. }
+        );
+        if !pairing_command
+            && self
+                .pairing
+                .as_ref()
+                .is_some_and(|p| !p.is_authenticated(connection))
+        {
+            log::warn!("Rejected command from an unpaired client");
+            self.notifier.notify_command_result(&CommandResult::error(
+                cmd.source(),
+                ErrorCode::NotAuthenticated,
+            ));
+            return CommandFlow::Continue;
+        }
+        match cmd {
+            BleCommand::StartGame { white, black } => self.start_game(white, black),
+            BleCommand::CancelGame => self.cancel_game(),
+            BleCommand::SubmitMove { uci } => self.submit_move(&uci),
+            BleCommand::Resign { color } => self.resign(color),
+            BleCommand::TakeBack => self.take_back(),
+            BleCommand::SelectProfile { index } => self.select_profile(index),
+            BleCommand::ReportResult { result } => self.report_result(result),
+            BleCommand::ChoosePromotion { role } => self.choose_promotion(role),
+            BleCommand::StartMode { mode } => self.start_mode(mode),
+            BleCommand::PressClock { color } => self.press_clock(color),
+            BleCommand::SetClock { settings } => self.set_clock(settings),
+            BleCommand::AddConditional { trigger, reply } => self.add_conditional(&trigger, &reply),
+            BleCommand::RequestPairing => self.request_pairing(),
+            BleCommand::Pair { squares } => self.pair(connection, squares),
+            BleCommand::Authenticate { token } => self.authenticate(connection, token),
+            BleCommand::StartCalibration => self.start_calibration(),
+            BleCommand::CalibrationStep => self.calibration_step(),
+            BleCommand::AbortCalibration => self.abort_calibration(),
+            BleCommand::SelfTest => self.self_test(),
+            BleCommand::ChangeSetting { setting } => {
+                self.change_setting(setting);
+                self.notifier
+                    .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+                CommandFlow::Continue
+            }
+            BleCommand::SetLogLevel { module, level } => {
+                log_filter::set_level(module, level);
+                self.notifier
+                    .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+                CommandFlow::Continue
+            }
+        }
+    }
+
+    fn start_calibration(&mut self) -> CommandFlow {
+        let code = if !matches!(
+            self.state,
+            BoardState::Idle | BoardState::Mode { .. } | BoardState::Calibrating { .. }
+        ) {
+            Some(ErrorCode::GameAlreadyInProgress)
+        } else if self.sensor.read_millivolts().is_none() {
+            Some(ErrorCode::InvalidCommand)
+        } else {
+            None
+        };
+        let result = match code {
+            None => {
+                log::info!("Calibration started: clear the board");
+                self.state = BoardState::Calibrating {
+                    calibrator: Calibrator::new(),
+                };
+                self.prev_positions = None;
+                CommandResult::success(CommandSource::MatchControl)
+            }
+            Some(code) => CommandResult::error(CommandSource::MatchControl, code),
+        };
+        self.notifier.notify_command_result(&result);
+        CommandFlow::Tick
+    }
+
+    fn calibration_step(&mut self) -> CommandFlow {
+        let BoardState::Calibrating { ref mut calibrator } = self.state else {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::InvalidCommand,
+            ));
+            return CommandFlow::Continue;
+        };
+        let step = match self.sensor.read_millivolts() {
+            Some(Ok(mv)) => calibrator.step(&mv).map_err(|faults| {
+                log::warn!("Calibration step failed on {} squares", faults.count());
+            }),
+            Some(Err(e)) => {
+                log::warn!("Sensor read failed: {e}");
+                self.stats.record_sensor_error();
+                Err(())
+            }
+            None => Err(()),
+        };
+        let result = match step {
+            Ok(None) => {
+                log::info!("Calibration: set up the starting position");
+                CommandResult::success(CommandSource::MatchControl)
+            }
+            Ok(Some(calibration)) => {
+                log::info!(
+                    "Calibration done: baseline={}mV, threshold={}mV",
+                    calibration.baseline_mv,
+                    calibration.threshold_mv
+                );
+                self.sensor.calibrate(calibration);
+                self.state = BoardState::Idle;
+                if let Err(e) = self.display.show(&BoardFeedback::default()) {
+                    log::warn!("LED clear failed: {e}");
+                }
+                CommandResult::success(CommandSource::MatchControl)
+            }
+            Err(()) => {
+                CommandResult::error(CommandSource::MatchControl, ErrorCode::CalibrationFailed)
+            }
+        };
+        self.notifier.notify_command_result(&result);
+        CommandFlow::Tick
+    }
+
+    fn abort_calibration(&mut self) -> CommandFlow {
+        let result = if matches!(self.state, BoardState::Calibrating { .. }) {
+            log::info!("Calibration aborted");
+            self.state = BoardState::Idle;
+            if let Err(e) = self.display.show(&BoardFeedback::default()) {
+                log::warn!("LED clear failed: {e}");
+            }
+            CommandResult::success(CommandSource::MatchControl)
+        } else {
+            CommandResult::error(CommandSource::MatchControl, ErrorCode::InvalidCommand)
+        };
+        self.notifier.notify_command_result(&result);
+        CommandFlow::Tick
+    }
+
+    /// Check an empty board and show the faulty squares until the next
+    /// display update.
+    fn self_test(&mut self) -> CommandFlow {
+        let code = if !matches!(self.state, BoardState::Idle) {
+            Some(ErrorCode::GameAlreadyInProgress)
+        } else {
+            match self.sensor.read_millivolts() {
+                Some(Ok(mv)) => {
+                    let faults = empty_board_faults(&mv);
+                    if let Err(e) = self.display.show(&self_test_feedback(faults)) {
+                        log::warn!("LED update failed: {e}");
+                    }
+                    let palette = self.display_settings.theme.palette();
+                    if faults.any()
+                        && let Err(e) = self
+                            .display
+                            .set_squares(&self_test_colors(faults, &palette))
+                    {
+                        log::warn!("LED update failed: {e}");
+                    }
+                    if faults.any() {
+                        log::warn!("Self-test failed on {} squares", faults.count());
+                        Some(ErrorCode::SelfTestFailed)
+                    } else {
+                        log::info!("Self-test passed");
+                        None
+                    }
+                }
+                Some(Err(e)) => {
+                    log::warn!("Sensor read failed: {e}");
+                    self.stats.record_sensor_error();
+                    Some(ErrorCode::SelfTestFailed)
+                }
+                None => Some(ErrorCode::InvalidCommand),
+            }
+        };
+        let result = match code {
+            None => CommandResult::success(CommandSource::MatchControl),
+            Some(code) => CommandResult::error(CommandSource::MatchControl, code),
+        };
+        self.notifier.notify_command_result(&result);
+        CommandFlow::Continue
+    }
+
+    fn request_pairing(&mut self) -> CommandFlow {
+        let code = if !matches!(self.state, BoardState::Idle) {
+            Err(ErrorCode::GameAlreadyInProgress)
+        } else {
+            let now = self.clock.now();
+            match self
+                .pairing
+                .as_mut()
+                .map(|pairing| pairing.request_code(now))
+            {
+                Some(Ok(code)) => Ok(code),
+                Some(Err(e)) => Err(pairing_error_code(e)),
+                None => Err(ErrorCode::InvalidCommand),
+            }
+        };
+        let result = match code {
+            Ok(_) => {
+                log::info!("Showing pairing code");
+                CommandResult::success(CommandSource::MatchControl)
+            }
+            Err(code) => CommandResult::error(CommandSource::MatchControl, code),
+        };
+        self.notifier.notify_command_result(&result);
+        CommandFlow::Tick
+    }
+
+    fn pair(&mut self, connection: ConnectionId, squares: Bitboard) -> CommandFlow {
+        let now = self.clock.now();
+        let paired = match &mut self.pairing {
+            Some(pairing) => pairing.pair(connection, squares, now),
+            None => Err(PairingError::WrongCode),
+        };
+        match paired {
+            Ok(token) => {
+                log::info!("Client paired");
+                self.notifier
+                    .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+                self.notifier.notify_pairing_token(token);
+            }
+            Err(e) => {
+                log::warn!("Pairing failed: {e}");
+                self.notifier.notify_command_result(&CommandResult::error(
+                    CommandSource::MatchControl,
+                    pairing_error_code(e),
+                ));
+            }
+        }
+        CommandFlow::Tick
+    }
+
+    fn authenticate(&mut self, connection: ConnectionId, token: Token) -> CommandFlow {
+        let result = if self
+            .pairing
+            .as_mut()
+            .is_some_and(|p| p.authenticate(connection, token))
+        {
+            CommandResult::success(CommandSource::MatchControl)
+        } else {
+            CommandResult::error(CommandSource::MatchControl, ErrorCode::NotAuthenticated)
+        };
+        self.notifier.notify_command_result(&result);
+        CommandFlow::Continue
+    }
+
+    fn start_game(&mut self, white: PlayerType, black: PlayerType) -> CommandFlow {
+        if let BoardState::Mode { mode } = &self.state {
+            log::info!("Leaving {} to start a game", mode.name());
+            self.state = BoardState::Idle;
+            self.prev_positions = None;
+        }
+        if !matches!(self.state, BoardState::Idle) {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::StartGame,
+                ErrorCode::GameAlreadyInProgress,
+            ));
+            return CommandFlow::Continue;
+        }
+        let opponent = match self.find_opponent(white, black) {
+            Ok(opponent) => opponent,
+            Err(code) => {
+                self.notifier
+                    .notify_command_result(&CommandResult::error(CommandSource::StartGame, code));
+                return CommandFlow::Continue;
+            }
+        };
+        self.notifier
+            .notify_command_result(&CommandResult::success(CommandSource::StartGame));
+        self.notifier.update_player_type(Color::White, white);
+        self.notifier.update_player_type(Color::Black, black);
+        self.notifier
+            .notify_game_status(&GameStatus::AwaitingPieces);
+        self.state = BoardState::AwaitingPieces {
+            white,
+            black,
+            placement: Placement::default(),
+            opponent,
+        };
+        log::info!("Waiting for starting position...");
+        CommandFlow::Tick
+    }
+
+    /// Start finding the opponent of an online side, which must face a
+    /// human. `InvalidCommand` if the service is not set up.
+    fn find_opponent(
+        &self,
+        white: PlayerType,
+        black: PlayerType,
+    ) -> Result<Option<OnlineOpponent>, ErrorCode> {
+        let (online, other) = if white.is_online() {
+            (white, black)
+        } else {
+            (black, white)
+        };
+        if !online.is_online() {
+            return Ok(None);
+        }
+        if other != PlayerType::Human {
+            return Err(ErrorCode::InvalidCommand);
+        }
+        let opponent = if online == PlayerType::Lichess {
+            let matchmaking = match self.clock_settings {
+                Some(settings) => Matchmaking::Seek {
+                    minutes: seconds(settings.time_control.initial) / 60,
+                    increment: seconds(settings.time_control.increment),
+                    rated: false,
+                },
+                None => Matchmaking::AcceptChallenge,
+            };
+            self.lichess
+                .as_ref()
+                .map(|find| OnlineOpponent::Lichess(find(matchmaking)))
+        } else {
+            self.relay
+                .as_ref()
+                .map(|find| OnlineOpponent::Relay(find()))
+        };
+        opponent.map(Some).ok_or(ErrorCode::InvalidCommand)
+    }
+
+    fn start_mode(&mut self, selection: ModeSelection) -> CommandFlow {
+        match self.enter_mode(selection) {
+            Ok(()) => {
+                self.notifier
+                    .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+                CommandFlow::Tick
+            }
+            Err(code) => {
+                self.notifier.notify_command_result(&CommandResult::error(
+                    CommandSource::MatchControl,
+                    code,
+                ));
+                CommandFlow::Continue
+            }
+        }
+    }
+
+    /// Start `selection` without a client asking, e.g. the
+    /// [`BootBehavior`](crate::mode::BootBehavior) at power-up. Any
+    /// running mode is replaced; a game is not.
+    pub fn enter_mode(&mut self, selection: ModeSelection) -> Result<(), ErrorCode> {
+        if !matches!(self.state, BoardState::Idle | BoardState::Mode { .. }) {
+            return Err(ErrorCode::GameAlreadyInProgress);
+        }
+        let seed = self.clock.now().as_nanos() as u32;
+        let mode = match selection {
+            ModeSelection::HeatMap => self
+                .last_game
+                .clone()
+                .map(|moves| Box::new(HeatMapMode::new(moves)) as Box<dyn GameMode>),
+            _ => selection.create(seed),
+        };
+        let Some(mode) = mode else {
+            return Err(ErrorCode::InvalidCommand);
+        };
+        log::info!("Starting {}", mode.name());
+        self.state = BoardState::Mode { mode };
+        self.prev_positions = None;
+        Ok(())
+    }
+
+    fn cancel_game(&mut self) -> CommandFlow {
+        if matches!(self.state, BoardState::Idle) {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoGameInProgress,
+            ));
+            return CommandFlow::Continue;
+        }
+        let promotion_pending = matches!(
+            self.state,
+            BoardState::InProgress {
+                promotion_since: Some(_),
+                ..
+            }
+        );
+        if let BoardState::InProgress {
+            session,
+            started_at,
+            ..
+        } = std::mem::replace(&mut self.state, BoardState::Idle)
+        {
+            let elapsed = self.clock.now().saturating_sub(started_at);
+            self.stats
+                .record_game(&GameStatus::Aborted, session.moves(), elapsed);
+        }
+        self.prev_positions = None;
+        self.prev_game_state = None;
+        self.notifier
+            .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+        if promotion_pending {
+            self.notifier.reset_pending_promotion();
+        }
+        self.notifier.notify_game_status(&GameStatus::Idle);
+        self.notifier.reset_player_types();
+        self.notifier.reset_position();
+        self.notifier.reset_last_move();
+        log::info!("Game cancelled");
+        self.release_profile();
+        CommandFlow::Tick
+    }
+
+    fn submit_move(&mut self, uci: &str) -> CommandFlow {
+        let near = self.perspective();
+        let BoardState::InProgress {
+            ref mut session,
+            ref white_tx,
+            ref black_tx,
+            ref mut clock,
+            ..
+        } = self.state
+        else {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::SubmitMove,
+                ErrorCode::NoGameInProgress,
+            ));
+            return CommandFlow::Continue;
+        };
+        let tx = match session.position().turn() {
+            Color::White => white_tx,
+            Color::Black => black_tx,
+        };
+        let Some(tx) = tx else {
+            // Current player is Human: only moves the sensors cannot see
+            // (involving a dead square) may be announced.
+            let announced = parse_uci_move(session.position(), uci)
+                .ok()
+                .filter(|mv| session.announce_move(*mv));
+            let Some(mv) = announced else {
+                self.notifier.notify_command_result(&CommandResult::error(
+                    CommandSource::SubmitMove,
+                    ErrorCode::NotYourTurn,
+                ));
+                return CommandFlow::Continue;
+            };
+            self.notifier
+                .notify_command_result(&CommandResult::success(CommandSource::SubmitMove));
+            publish_move(
+                &mut self.notifier,
+                &mut self.display,
+                session.position(),
+                mv,
+                near,
+            );
+            switch_clock(&mut self.notifier, clock, self.clock.now());
+            return CommandFlow::Tick;
+        };
+        let result = match parse_uci_move(session.position(), uci) {
+            Ok(mv) => {
+                if tx.send(mv).is_err() {
+                    log::warn!("SubmitMove: channel closed, receiver dropped");
+                    CommandResult::error(CommandSource::SubmitMove, ErrorCode::NoGameInProgress)
+                } else {
+                    CommandResult::success(CommandSource::SubmitMove)
+                }
+            }
+            Err(_) => CommandResult::error(CommandSource::SubmitMove, ErrorCode::IllegalMove),
+        };
+        self.notifier.notify_command_result(&result);
+        // Force a tick before processing the next command
+        CommandFlow::Tick
+    }
+
+    fn resign(&mut self, color: Color) -> CommandFlow {
+        let BoardState::InProgress {
+            ref mut session,
+            ref white_tx,
+            ref black_tx,
+            ..
+        } = self.state
+        else {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoGameInProgress,
+            ));
+            return CommandFlow::Continue;
+        };
+        // Check that the side being resigned is Human (not Remote)
+        let is_remote = match color {
+            Color::White => white_tx.is_some(),
+            Color::Black => black_tx.is_some(),
+        };
+        if is_remote {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::CannotResignForRemotePlayer,
+            ));
+        } else if session.resign(color) {
+            log::info!("{color:?} resigns");
+            self.notifier
+                .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+            self.notifier.notify_game_status(&session.game_state());
+        } else {
+            // Should not happen if is_remote check is correct, but handle gracefully
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::InvalidCommand,
+            ));
+        }
+        CommandFlow::Tick
+    }
+
+    fn take_back(&mut self) -> CommandFlow {
+        let BoardState::InProgress {
+            ref mut session,
+            ref mut clock,
+            ..
+        } = self.state
+        else {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoGameInProgress,
+            ));
+            return CommandFlow::Continue;
+        };
+        if session.undo_last_move().is_none() {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::CannotTakeBack,
+            ));
+            return CommandFlow::Continue;
+        }
+        self.notifier
+            .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+        self.notifier.reset_last_move();
+        self.notifier
+            .update_position(&position_fen(session.position()));
+        if let Some(clock) = clock
+            && clock.running().is_some()
+        {
+            let now = self.clock.now();
+            clock.start(session.position().turn(), now);
+            publish_clock(&mut self.notifier, clock, now);
+        }
+        CommandFlow::Tick
+    }
+
+    fn select_profile(&mut self, index: u8) -> CommandFlow {
+        let error = if !matches!(self.state, BoardState::Idle) {
+            Some(ErrorCode::GameAlreadyInProgress)
+        } else if usize::from(index) >= self.profiles.len() {
+            Some(ErrorCode::UnknownProfile)
+        } else {
+            None
+        };
+        if let Some(code) = error {
+            self.notifier
+                .notify_command_result(&CommandResult::error(CommandSource::MatchControl, code));
+            return CommandFlow::Continue;
+        }
+        let profile = self.profiles[usize::from(index)].clone();
+        let previous = match self.selected_profile.take() {
+            Some((_, previous)) => previous,
+            None => Preferences {
+                theme: self.display_settings.theme,
+                assist: self.assist,
+                clock: self.clock_settings,
+                orientation: self.orientation,
+            },
+        };
+        log::info!("Profile selected: {profile}");
+        self.apply_preferences(Preferences {
+            theme: profile.theme,
+            assist: profile.assist,
+            clock: profile.clock.map(|time_control| ClockSettings {
+                time_control,
+                confirm_moves: false,
+            }),
+            orientation: profile.orientation,
+        });
+        self.selected_profile = Some((profile.name, previous));
+        self.notifier
+            .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+        CommandFlow::Continue
+    }
+
+    fn report_result(&mut self, result: ExternalResult) -> CommandFlow {
+        let BoardState::InProgress {
+            ref mut session,
+            ref white_tx,
+            ref black_tx,
+            ..
+        } = self.state
+        else {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoGameInProgress,
+            ));
+            return CommandFlow::Continue;
+        };
+        // Only a game with a remote side can be decided off the board
+        if white_tx.is_none() && black_tx.is_none() {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoRemotePlayer,
+            ));
+            return CommandFlow::Continue;
+        }
+        if session.report_result(result) {
+            log::info!("External result: {result:?}");
+            self.notifier
+                .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+            self.notifier.notify_game_status(&session.game_state());
+        } else {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::InvalidCommand,
+            ));
+        }
+        CommandFlow::Tick
+    }
+
+    fn choose_promotion(&mut self, role: Role) -> CommandFlow {
+        let near = self.perspective();
+        let BoardState::InProgress {
+            ref mut session,
+            ref mut promotion_since,
+            ref mut clock,
+            ..
+        } = self.state
+        else {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoGameInProgress,
+            ));
+            return CommandFlow::Continue;
+        };
+        let Some(mv) = session.choose_promotion(role) else {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoPendingPromotion,
+            ));
+            return CommandFlow::Continue;
+        };
+        *promotion_since = None;
+        self.notifier
+            .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+        self.notifier.reset_pending_promotion();
+        publish_move(
+            &mut self.notifier,
+            &mut self.display,
+            session.position(),
+            mv,
+            near,
+        );
+        switch_clock(&mut self.notifier, clock, self.clock.now());
+        CommandFlow::Tick
+    }
+
+    fn add_conditional(&mut self, trigger: &str, reply: &str) -> CommandFlow {
+        let BoardState::InProgress {
+            ref mut session, ..
+        } = self.state
+        else {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoGameInProgress,
+            ));
+            return CommandFlow::Continue;
+        };
+        let moves = parse_uci_move(session.position(), trigger).and_then(|trigger| {
+            let mut after = session.position().clone();
+            after.play_unchecked(trigger);
+            Ok((trigger, parse_uci_move(&after, reply)?))
+        });
+        let result = match moves {
+            Ok((trigger, reply)) if session.add_conditional(trigger, reply) => {
+                log::info!("Stored conditional: {trigger} -> {reply}");
+                CommandResult::success(CommandSource::MatchControl)
+            }
+            Ok(_) => CommandResult::error(CommandSource::MatchControl, ErrorCode::NotYourTurn),
+            Err(_) => CommandResult::error(CommandSource::MatchControl, ErrorCode::IllegalMove),
+        };
+        self.notifier.notify_command_result(&result);
+        CommandFlow::Continue
+    }
+
+    fn press_clock(&mut self, color: Color) -> CommandFlow {
+        let near = self.perspective();
+        let BoardState::InProgress {
+            ref mut session,
+            ref mut clock,
+            ref mut handshake,
+            ..
+        } = self.state
+        else {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoGameInProgress,
+            ));
+            return CommandFlow::Continue;
+        };
+        if let Some(pending) = handshake {
+            if pending.confirm(color) {
+                log::info!("{color:?} ready (clock pressed)");
+                self.notifier
+                    .notify_game_event(&GameEvent::PlayerReady { color });
+            }
+            self.notifier
+                .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+            start_clock_when_ready(
+                &mut self.notifier,
+                handshake,
+                clock,
+                session.position().turn(),
+                self.clock.now(),
+            );
+            return CommandFlow::Tick;
+        }
+        let Some(mv) = session.confirm_move(color) else {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoPendingMove,
+            ));
+            return CommandFlow::Continue;
+        };
+        self.notifier
+            .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+        publish_move(
+            &mut self.notifier,
+            &mut self.display,
+            session.position(),
+            mv,
+            near,
+        );
+        switch_clock(&mut self.notifier, clock, self.clock.now());
+        CommandFlow::Tick
+    }
+
+    fn set_clock(&mut self, settings: Option<ClockSettings>) -> CommandFlow {
+        if matches!(self.state, BoardState::InProgress { .. }) {
+            self.notifier.notify_command_result(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::GameAlreadyInProgress,
+            ));
+            return CommandFlow::Continue;
+        }
+        match settings {
+            Some(s) => log::info!("Clock set: {:?}", s.time_control),
+            None => log::info!("Clock off"),
+        }
+        self.clock_settings = settings;
+        self.notifier
+            .notify_command_result(&CommandResult::success(CommandSource::MatchControl));
+        CommandFlow::Continue
+    }
+
+    fn tick(&mut self) -> Duration {
+        if !self.await_opponent() {
+            return TICK_INTERVAL;
+        }
+        if let BoardState::AwaitingPieces { white, black, .. } = self.state {
+            let near = self.perspective();
+            let positions = match self.sensor.read_positions() {
+                Ok(p) => oriented_positions(p, near),
+                Err(e) => {
+                    log::warn!("Sensor read failed: {e}");
+                    self.stats.record_sensor_error();
+                    return SENSOR_RETRY_INTERVAL;
+                }
+            };
+            let positions = self.assume_set_up(positions);
+            let placement = setup_placement(&positions);
+            match placement.feedback() {
+                Some(fb) => {
+                    if let BoardState::AwaitingPieces {
+                        placement: last, ..
+                    } = &mut self.state
+                    {
+                        *last = placement;
+                    }
+                    if let Err(e) = self.display.show(&oriented_feedback(&fb, near)) {
+                        log::warn!("LED update failed: {e}");
+                    }
+                }
+                None => {
+                    // Starting position detected — create session
+                    if let Err(e) = self.display.show(&BoardFeedback::default()) {
+                        log::warn!("LED clear failed: {e}");
+                    }
+                    let initial = match self.sensor.read_positions() {
+                        Ok(p) => oriented_positions(p, near),
+                        Err(e) => {
+                            log::error!("Initial sensor read failed: {e}");
+                            self.state = BoardState::Idle;
+                            self.notifier.notify_game_status(&GameStatus::Idle);
+                            return Duration::ZERO;
+                        }
+                    };
+                    let initial = self.assume_set_up(initial);
+                    let opponent = match std::mem::replace(&mut self.state, BoardState::Idle) {
+                        BoardState::AwaitingPieces { opponent, .. } => opponent,
+                        _ => None,
+                    };
+                    self.begin_session(
+                        white,
+                        black,
+                        Chess::default(),
+                        Vec::new(),
+                        initial,
+                        opponent,
+                    );
+                    log::info!("Starting position detected, game started");
+                }
+            }
+        }
+
+        match self.state {
+            BoardState::InProgress { .. } => self.tick_in_progress(),
+            BoardState::Mode { .. } => self.tick_mode(),
+            BoardState::Calibrating { .. } => self.tick_calibrating(),
+            BoardState::Idle => {
+                self.show_pairing_code();
+                TICK_INTERVAL
+            }
+            BoardState::AwaitingPieces { .. } => TICK_INTERVAL,
+        }
+    }
+
+    /// Follow the online opponent being found, if any: seat the board on
+    /// the side the service gave it, or give up and show why if the
+    /// service fails. Whether the game may start.
+    fn await_opponent(&mut self) -> bool {
+        let BoardState::AwaitingPieces {
+            white,
+            black,
+            opponent: Some(opponent),
+            ..
+        } = &mut self.state
+        else {
+            return true;
+        };
+        if opponent.player().status() == PlayerStatus::Error {
+            log::warn!("No online opponent found");
+            let feedback = opponent.player().error_feedback();
+            self.state = BoardState::Idle;
+            self.notifier.reset_player_types();
+            self.notifier.notify_game_status(&GameStatus::Idle);
+            if let Some(feedback) = feedback
+                && let Err(e) = self.display.show(&feedback)
+            {
+                log::warn!("LED update failed: {e}");
+            }
+            return false;
+        }
+        let Some(local) = opponent.local_color() else {
+            return false;
+        };
+        let online = if white.is_online() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        if local == online {
+            log::info!("Online opponent found, the board plays {local:?}");
+            std::mem::swap(white, black);
+            self.notifier.update_player_type(Color::White, *white);
+            self.notifier.update_player_type(Color::Black, *black);
+        }
+        true
+    }
+
+    /// Light the squares of a pending pairing code, and clear them once it
+    /// is used or expires.
+    fn show_pairing_code(&mut self) {
+        let now = self.clock.now();
+        let code = self.pairing.as_mut().and_then(|p| p.code(now));
+        if code.is_none() && !self.pairing_code_shown {
+            return;
+        }
+        let mut fb = BoardFeedback::new();
+        for square in code.unwrap_or_default() {
+            fb.set(square, SquareFeedback::Destination);
+        }
+        if let Err(e) = self.display.show(&fb) {
+            log::warn!("LED update failed: {e}");
+        }
+        self.pairing_code_shown = code.is_some();
+    }
+
+    /// Light the squares that would fail the current calibration step.
+    fn tick_calibrating(&mut self) -> Duration {
+        let BoardState::Calibrating { ref calibrator } = self.state else {
+            return TICK_INTERVAL;
+        };
+        match self.sensor.read_millivolts() {
+            Some(Ok(mv)) => {
+                if let Err(e) = self.display.show(&calibrator.feedback(&mv)) {
+                    log::warn!("LED update failed: {e}");
+                }
+                TICK_INTERVAL
+            }
+            Some(Err(e)) => {
+                log::warn!("Sensor read failed: {e}");
+                self.stats.record_sensor_error();
+                SENSOR_RETRY_INTERVAL
+            }
+            None => TICK_INTERVAL,
+        }
+    }
+
+    fn tick_mode(&mut self) -> Duration {
+        let near = self.perspective();
+        let BoardState::Mode { ref mut mode } = self.state else {
+            return TICK_INTERVAL;
+        };
+
+        let positions = match self.sensor.read_positions() {
+            Ok(p) => oriented_positions(p, near),
+            Err(e) => {
+                log::warn!("Sensor read failed: {e}");
+                self.stats.record_sensor_error();
+                return SENSOR_RETRY_INTERVAL;
+            }
+        };
+
+        log_sensor_changes(self.prev_positions, positions);
+        self.prev_positions = Some(positions);
+
+        let status = mode.tick(positions, self.clock.now());
+        if let Err(e) = self
+            .display
+            .show(&oriented_feedback(&mode.feedback(), near))
+        {
+            log::warn!("LED update failed: {e}");
+        }
+        if status == ModeStatus::Finished {
+            // Leave the final feedback on the display, like a game result.
+            log::info!("{} finished", mode.name());
+            self.state = BoardState::Idle;
+            self.prev_positions = None;
+        }
+
+        TICK_INTERVAL
+    }
+
+    /// Start a game from `start`, with moves read from `initial` on, and
+    /// `opponent` playing the online side.
+    fn begin_session(
+        &mut self,
+        white: PlayerType,
+        black: PlayerType,
+        start: Chess,
+        history: Vec<Move>,
+        initial: ByColor<Bitboard>,
+        opponent: Option<OnlineOpponent>,
+    ) {
+        let near = seat(ByColor { white, black }, self.orientation);
+        let seed = self.clock.now().as_nanos() as u32;
+        let opponent = opponent.map(OnlineOpponent::into_player);
+        let (white_online, black_online) = if white.is_online() {
+            (opponent, None)
+        } else {
+            (None, opponent)
+        };
+        let (white_player, white_tx) =
+            create_player(white, initial, self.matcher, seed, white_online);
+        let (black_player, black_tx) =
+            create_player(black, initial, self.matcher, !seed, black_online);
+        let confirmation = if self
+            .clock_settings
+            .is_some_and(|settings| settings.confirm_moves)
+        {
+            MoveConfirmation::Always
+        } else {
+            self.move_confirmation
+        };
+        let session = GameSession::builder()
+            .position(start)
+            .promotion_policy(PromotionPolicy::GestureSelect)
+            .dead_squares(oriented_squares(self.dead_squares, near))
+            .adjudication(self.adjudication)
+            .assist_level(self.assist)
+            .move_confirmation(confirmation)
+            .history(history)
+            .build(white_player, black_player);
+        let mut clock = self
+            .clock_settings
+            .map(|settings| GameClock::new(settings.time_control));
+        // Remote players are not at the board, so only humans confirm.
+        let mut handshake = clock.as_ref().map(|_| {
+            StartHandshake::new(ByColor {
+                white: white != PlayerType::Human,
+                black: black != PlayerType::Human,
+            })
+        });
+        start_clock_when_ready(
+            &mut self.notifier,
+            &mut handshake,
+            &mut clock,
+            session.position().turn(),
+            self.clock.now(),
+        );
+        self.notifier.notify_game_status(&session.game_state());
+        self.notifier
+            .update_position(&position_fen(session.position()));
+        self.prev_game_state = Some(session.game_state());
+        self.prev_positions = Some(initial);
+        self.gestures = GestureArbiter::new();
+        self.state = BoardState::InProgress {
+            session: Box::new(session),
+            white_tx,
+            black_tx,
+            started_at: self.clock.now(),
+            abort: AbortGesture::new(),
+            promotion_since: None,
+            clock,
+            handshake,
+            players: ByColor { white, black },
+        };
+        self.display
+            .play(oriented_animation(Animation::game_start(), near));
+        self.debounce.invalidate();
+    }
+
+    /// Fill dead squares with the starting position, which cannot be
+    /// checked on the board.
+    fn assume_set_up(&self, positions: ByColor<Bitboard>) -> ByColor<Bitboard> {
+        let dead = oriented_squares(self.dead_squares, self.perspective());
+        let start = Chess::default();
+        let board = start.board();
+        ByColor {
+            white: (positions.white & !dead) | (board.by_color(Color::White) & dead),
+            black: (positions.black & !dead) | (board.by_color(Color::Black) & dead),
+        }
+    }
+
+    fn tick_in_progress(&mut self) -> Duration {
+        let near = self.perspective();
+        let BoardState::InProgress {
+            ref mut session,
+            started_at,
+            ref mut abort,
+            ref mut promotion_since,
+            ref mut clock,
+            ref mut handshake,
+            players,
+            ..
+        } = self.state
+        else {
+            return TICK_INTERVAL;
+        };
+
+        // Check game-over FIRST (handles resign from command processing above)
+        if session.is_game_over() {
+            let status = session.game_state();
+            let elapsed = self.clock.now().saturating_sub(started_at);
+            log::info!("Game over after {}s: {status:?}", elapsed.as_secs());
+            self.stats.record_game(&status, session.moves(), elapsed);
+            self.last_game = Some(session.moves().to_vec());
+            log::info!("Session stats:\n{}", self.stats.report());
+            let result = result_feedback(session.position(), &status);
+            let tournament_game = players.white == PlayerType::Human
+                && players.black == PlayerType::Human
+                && self.tournament.as_ref().is_some_and(|t| !t.is_finished());
+            if promotion_since.is_some() {
+                self.notifier.reset_pending_promotion();
+            }
+            self.state = BoardState::Idle;
+            self.prev_positions = None;
+            self.prev_game_state = None;
+            self.notifier.reset_player_types();
+            if tournament_game {
+                self.record_tournament_result(&status);
+                self.announce_pairing(true, result, near);
+            } else if let Some(fb) = result
+                && let Err(e) = self.display.show(&oriented_feedback(&fb, near))
+            {
+                log::warn!("LED update failed: {e}");
+            }
+            self.release_profile();
+            // Don't reset position/last_move — keep them so the app can read the final state
+            return TICK_INTERVAL;
+        }
+
+        if let Some(loser) = clock.as_ref().and_then(|c| c.flagged(self.clock.now())) {
+            log::info!("{loser:?} ran out of time");
+            self.notifier
+                .notify_game_event(&GameEvent::Flagged { color: loser });
+            session.time_out(loser);
+            let status = session.game_state();
+            self.notifier.notify_game_status(&status);
+            self.prev_game_state = Some(status);
+            return TICK_INTERVAL;
+        }
+
+        let positions = match self.sensor.read_positions() {
+            Ok(p) => oriented_positions(p, near),
+            Err(e) => {
+                log::warn!("Sensor read failed: {e}");
+                self.stats.record_sensor_error();
+                return SENSOR_RETRY_INTERVAL;
+            }
+        };
+
+        log_sensor_changes(self.prev_positions, positions);
+        self.prev_positions = Some(positions);
+
+        match abort.update(session.position(), positions, self.clock.now()) {
+            AbortSignal::Inactive => {}
+            AbortSignal::Prompting { elapsed } => {
+                self.debounce.invalidate();
+                if let Err(e) = self
+                    .display
+                    .show(&oriented_feedback(&prompt_feedback(elapsed), near))
+                {
+                    log::warn!("LED update failed: {e}");
+                }
+                return TICK_INTERVAL;
+            }
+            AbortSignal::Confirmed => {
+                log::info!("Abort confirmed on the board");
+                session.report_result(ExternalResult::Aborted);
+                let status = session.game_state();
+                self.notifier.notify_game_status(&status);
+                self.prev_game_state = Some(status);
+                return TICK_INTERVAL;
+            }
+        }
+
+        let move_pending =
+            session.pending_move().is_some() || session.pending_promotion().is_some();
+        match self.gestures.update(
+            session.position(),
+            positions,
+            move_pending,
+            self.clock.now(),
+        ) {
+            GestureSignal::Inactive => {}
+            GestureSignal::Holding { gesture, .. } => {
+                self.debounce.invalidate();
+                let feedback = gesture_feedback(session.position(), gesture);
+                if let Err(e) = self.display.show(&oriented_feedback(&feedback, near)) {
+                    log::warn!("LED update failed: {e}");
+                }
+                return TICK_INTERVAL;
+            }
+            GestureSignal::Recognized(Gesture::Resign { color }) => {
+                if !session.resign(color) {
+                    log::info!("Ignoring resign gesture for {color:?}, not played on the board");
+                    return TICK_INTERVAL;
+                }
+                log::info!("{color:?} resigns on the board");
+                self.notifier
+                    .notify_game_event(&GameEvent::ResignedOnBoard { color });
+                let status = session.game_state();
+                self.notifier.notify_game_status(&status);
+                self.prev_game_state = Some(status);
+                return TICK_INTERVAL;
+            }
+            GestureSignal::Recognized(Gesture::NewGame) => {
+                log::info!("New game requested on the board");
+                let elapsed = self.clock.now().saturating_sub(started_at);
+                self.stats
+                    .record_game(&GameStatus::Aborted, session.moves(), elapsed);
+                if promotion_since.is_some() {
+                    self.notifier.reset_pending_promotion();
+                }
+                self.notifier
+                    .notify_game_event(&GameEvent::NewGameRequested);
+                self.notifier
+                    .notify_game_status(&GameStatus::AwaitingPieces);
+                // An online game looks for a new opponent.
+                let opponent = self
+                    .find_opponent(players.white, players.black)
+                    .ok()
+                    .flatten();
+                self.state = BoardState::AwaitingPieces {
+                    white: players.white,
+                    black: players.black,
+                    placement: Placement::default(),
+                    opponent,
+                };
+                self.prev_positions = None;
+                self.prev_game_state = None;
+                return TICK_INTERVAL;
+            }
+        }
+
+        let now = self.clock.now();
+        if let Some(pending) = handshake {
+            for color in pending.observe(session.position().board(), positions) {
+                log::info!("{color:?} ready (king touched)");
+                self.notifier
+                    .notify_game_event(&GameEvent::PlayerReady { color });
+            }
+            let feedback = pending.feedback(session.position().board());
+            if !start_clock_when_ready(
+                &mut self.notifier,
+                handshake,
+                clock,
+                session.position().turn(),
+                now,
+            ) {
+                // Hold the game: pieces may still be settling.
+                self.debounce.invalidate();
+                if let Err(e) = self.display.show(&oriented_feedback(&feedback, near)) {
+                    log::warn!("LED update failed: {e}");
+                }
+                return TICK_INTERVAL;
+            }
+        }
+
+        let choice = session.promotion_choice();
+        let result = session.tick(positions);
+        let mut played = result.last_move;
+
+        for event in &result.events {
+            match event {
+                TickEvent::BoardDesync => {
+                    let Some(recovery) = session.recovery() else {
+                        continue;
+                    };
+                    let squares = recovery.missing.white | recovery.missing.black | recovery.extra;
+                    log::info!("Board out of sync on {} squares", squares.count());
+                    self.notifier
+                        .notify_game_event(&GameEvent::BoardOutOfSync { squares });
+                }
+                TickEvent::BoardRestored => {
+                    log::info!("Board back in sync");
+                    self.notifier.notify_game_event(&GameEvent::BoardInSync);
+                }
+                &TickEvent::DrawClaimable { reason } => {
+                    self.notifier
+                        .notify_game_event(&GameEvent::DrawClaimable { reason });
+                }
+                _ => {}
+            }
+        }
+
+        if choice.is_some() && session.promotion_choice() != choice {
+            // A piece picked on the board gets a fresh timeout.
+            *promotion_since = Some(now);
+        }
+        match (session.pending_promotion().copied(), *promotion_since) {
+            (Some(pending), None) => {
+                *promotion_since = Some(now);
+                self.notifier
+                    .update_pending_promotion(session.position().turn(), &pawn_move_uci(&pending));
+            }
+            (Some(_), Some(since)) if now.saturating_sub(since) >= PROMOTION_CHOICE_TIMEOUT => {
+                let role = session.promotion_choice().unwrap_or(Role::Queen);
+                log::info!("No promotion piece chosen by a client, playing {role:?}");
+                played = session.choose_promotion(role);
+                *promotion_since = None;
+                self.notifier.reset_pending_promotion();
+            }
+            (None, Some(_)) => {
+                // Withdrawn on the board
+                *promotion_since = None;
+                self.notifier.reset_pending_promotion();
+            }
+            _ => {}
+        }
+
+        if let Some(mv) = played {
+            publish_move(
+                &mut self.notifier,
+                &mut self.display,
+                session.position(),
+                mv,
+                near,
+            );
+            switch_clock(&mut self.notifier, clock, now);
+        }
+
+        // A played move is shown at once; readings on the way are debounced.
+        let mut feedback = if played.is_some() {
+            self.debounce.force(result.feedback)
+        } else {
+            self.debounce.update(result.feedback, now)
+        }
+        .clone();
+        if session.move_confirmation() == MoveConfirmation::WhenUncertain
+            && let Some(pending) = session.pending_move()
+            && (now.as_millis() / UNCERTAIN_MOVE_BLINK_PHASE.as_millis()).is_multiple_of(2)
+        {
+            feedback.clear(pending.to());
+        }
+        if self.clock_bar
+            && let Some(clock) = clock
+        {
+            overlay_clock_bar(&mut feedback, clock, now);
+        }
+        if let Err(e) = self.display.show(&oriented_feedback(&feedback, near)) {
+            log::warn!("LED update failed: {e}");
+        }
+
+        let current_status = session.game_state();
+        if self.prev_game_state.as_ref() != Some(&current_status) {
+            self.notifier.notify_game_status(&current_status);
+            self.prev_game_state = Some(current_status);
+        }
+
+        TICK_INTERVAL
+    }
+}
+
+/// Start `turn`'s clock once both players are ready, ending the handshake.
+/// Returns whether the clock is running (or there is no handshake).
+fn start_clock_when_ready(
+    notifier: &mut impl BoardNotifier,
+    handshake: &mut Option<StartHandshake>,
+    clock: &mut Option<GameClock>,
+    turn: Color,
+    now: Duration,
+) -> bool {
+    if handshake.as_ref().is_some_and(|h| !h.is_complete()) {
+        return false;
+    }
+    if handshake.take().is_some()
+        && let Some(clock) = clock
+    {
+        log::info!("Both players ready, starting the clock");
+        clock.start(turn, now);
+        notifier.notify_game_event(&GameEvent::ClockStarted);
+        publish_clock(notifier, clock, now);
+    }
+    true
+}
+
+fn pairing_error_code(error: PairingError) -> ErrorCode {
+    match error {
+        PairingError::WrongCode => ErrorCode::PairingFailed,
+        PairingError::Locked => ErrorCode::PairingLocked,
+    }
+}
+
+/// End the mover's turn on the clock, if the game has one.
+fn switch_clock(notifier: &mut impl BoardNotifier, clock: &mut Option<GameClock>, now: Duration) {
+    if let Some(clock) = clock {
+        clock.switch(now);
+        publish_clock(notifier, clock, now);
+    }
+}
+
+fn publish_clock(notifier: &mut impl BoardNotifier, clock: &GameClock, now: Duration) {
+    let remaining = ByColor::new_with(|color| clock.remaining(color, now));
+    notifier.update_clock(remaining, clock.running());
+}
+
+/// Announce a move that was just applied to `position`, on a board with
+/// `near`'s pieces along its first rank.
+fn publish_move(
+    notifier: &mut impl BoardNotifier,
+    display: &mut impl BoardDisplay,
+    position: &Chess,
+    mv: Move,
+    near: Color,
+) {
+    log::info!("Move played: {mv}");
+    // The player who just moved is the opposite of current turn (turn already advanced)
+    let mover = !position.turn();
+    let uci = UciMove::from_move(mv, CastlingMode::Standard).to_string();
+    let checksum = StateChecksum::of(position);
+    notifier.notify_move_played(mover, &uci, checksum);
+    notifier.update_last_move(mover, &uci, checksum);
+    notifier.update_position(&position_fen(position));
+    display.play(oriented_animation(Animation::move_confirm(&mv), near));
+}
+
+/// The side whose pieces a game's player sets up along the board's first
+/// rank: the one human's side when a human plays an engine or a remote
+/// opponent, otherwise `orientation`.
+fn seat(players: ByColor<PlayerType>, orientation: Color) -> Color {
+    match (players.white, players.black) {
+        (PlayerType::Human, PlayerType::Human) => orientation,
+        (PlayerType::Human, _) => Color::White,
+        (_, PlayerType::Human) => Color::Black,
+        _ => orientation,
+    }
+}
+
+/// Whole seconds in `duration`, as Lichess takes them.
+fn seconds(duration: Duration) -> u32 {
+    u32::try_from(duration.as_secs()).unwrap_or(u32::MAX)
+}
+
+/// Sensor squares of a board with `near`'s pieces along its first rank, as
+/// squares of the game.
+fn oriented_squares(squares: Bitboard, near: Color) -> Bitboard {
+    match near {
+        Color::White => squares,
+        Color::Black => squares.rotate_180(),
+    }
+}
+
+fn oriented_positions(positions: ByColor<Bitboard>, near: Color) -> ByColor<Bitboard> {
+    positions.map(|squares| oriented_squares(squares, near))
+}
+
+/// Game feedback as shown on a board with `near`'s pieces along its first
+/// rank.
+fn oriented_feedback(feedback: &BoardFeedback, near: Color) -> Cow<'_, BoardFeedback> {
+    match near {
+        Color::White => Cow::Borrowed(feedback),
+        Color::Black => Cow::Owned(feedback.rotated()),
+    }
+}
+
+fn oriented_animation(animation: Animation, near: Color) -> Animation {
+    match near {
+        Color::White => animation,
+        Color::Black => animation.rotated(),
+    }
+}
+
+/// UCI for a move's origin and destination, without any promotion suffix.
+fn pawn_move_uci(mv: &Move) -> String {
+    match mv.from() {
+        Some(from) => format!("{from}{}", mv.to()),
+        None => mv.to().to_string(),
+    }
+}
+
+fn position_sensors(position: &Chess) -> ByColor<Bitboard> {
+    let board = position.board();
+    ByColor {
+        white: board.by_color(Color::White),
+        black: board.by_color(Color::Black),
+    }
+}
+
+fn position_fen(position: &Chess) -> String {
+    Fen::from_position(position, EnPassantMode::Legal).to_string()
+}
+
+fn log_sensor_changes(prev: Option<ByColor<Bitboard>>, current: ByColor<Bitboard>) {
+    let Some(prev) = prev else { return };
+
+    for sq in current.white & !prev.white {
+        log::debug!("+ {sq} white");
+    }
+    for sq in prev.white & !current.white {
+        log::debug!("- {sq} white");
+    }
+    for sq in current.black & !prev.black {
+        log::debug!("+ {sq} black");
+    }
+    for sq in prev.black & !current.black {
+        log::debug!("- {sq} black");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abort::{ABORT_CONFIRM_TIMEOUT, ABORT_HOLD};
+    use crate::feedback::SquareFeedback;
+    use crate::gestures::{NEW_GAME_HOLD, RESIGN_HOLD};
+    use crate::minigames::{MiniGame, PAWN_PUZZLES};
+    use crate::mode::BootBehavior;
+    use crate::testutil::{
+        CLIENT, CapturingDisplay, Notification, QueuedCommands, RecordingNotifier, ScriptedSensor,
+        Simulation, VirtualClock,
+    };
+    use shakmaty::{Rank, Square};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// The starting position set up with Black's pieces along the sensors'
+    /// first rank.
+    fn black_near() -> Simulation {
+        let board = Chess::default().board().clone();
+        let sensor = ScriptedSensor::from_bitboards(
+            board.by_color(Color::White).rotate_180(),
+            board.by_color(Color::Black).rotate_180(),
+        )
+        .unwrap();
+        Simulation::with_sensor(sensor)
+    }
+
+    /// A started game, set up from the human's side.
+    fn started(white: PlayerType, black: PlayerType) -> Simulation {
+        let mut sim = match seat(ByColor { white, black }, Color::White) {
+            Color::White => Simulation::new(),
+            Color::Black => black_near(),
+        };
+        sim.send(BleCommand::StartGame { white, black });
+        sim.step();
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+        sim.clear_notifications();
+        sim
+    }
+
+    /// A game resumed after 1. e4 with the board as `script` left it.
+    fn resumed_after_e4(script: &str) -> Simulation {
+        let mut sim = Simulation::new();
+        sim.push_script(script).unwrap();
+        sim.step();
+        let position = Chess::default()
+            .play(parse_uci_move(&Chess::default(), "e2e4").unwrap())
+            .unwrap();
+        sim.app_mut()
+            .resume_game(PlayerType::Human, PlayerType::Human, position)
+            .unwrap();
+        sim
+    }
+
+    fn results(sim: &Simulation) -> Vec<CommandResult> {
+        sim.notifications()
+            .iter()
+            .filter_map(|n| match n {
+                Notification::CommandResult(r) => Some(r.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // ── parse_uci_move ──────────────────────────────────────────────
+
+    #[test]
+    fn parse_uci_move_accepts_legal_move() {
+        let mv = parse_uci_move(&Chess::default(), "e2e4").unwrap();
+        assert_eq!(mv.from(), Some(Square::E2));
+        assert_eq!(mv.to(), Square::E4);
+    }
+
+    #[test]
+    fn parse_uci_move_rejects_garbage() {
+        assert!(matches!(
+            parse_uci_move(&Chess::default(), "zz"),
+            Err(MoveParseError::InvalidUci)
+        ));
+    }
+
+    #[test]
+    fn parse_uci_move_rejects_illegal_move() {
+        assert!(matches!(
+            parse_uci_move(&Chess::default(), "e2e5"),
+            Err(MoveParseError::IllegalMove)
+        ));
+    }
+
+    // ── lifecycle ───────────────────────────────────────────────────
+
+    #[test]
+    fn start_game_with_pieces_in_place_goes_straight_to_in_progress() {
+        let mut sim = Simulation::new();
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+        assert_eq!(
+            sim.notifications(),
+            &[
+                Notification::CommandResult(CommandResult::success(CommandSource::StartGame)),
+                Notification::PlayerType(Color::White, PlayerType::Human),
+                Notification::PlayerType(Color::Black, PlayerType::Human),
+                Notification::GameStatus(GameStatus::AwaitingPieces),
+                Notification::GameStatus(GameStatus::InProgress),
+                Notification::Position(
+                    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn awaiting_pieces_shows_setup_feedback_until_board_is_set() {
+        let mut sim = Simulation::with_sensor(
+            crate::testutil::ScriptedSensor::from_bitboards(Bitboard::EMPTY, Bitboard::EMPTY)
+                .unwrap(),
+        );
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+
+        assert_eq!(sim.app().status(), GameStatus::AwaitingPieces);
+        let frame = sim.display().last().expect("setup feedback shown");
+        assert!(!frame.is_empty());
+
+        // Every piece but a stray white one on e4: still not set up.
+        let start = Chess::default();
+        sim.app_mut()
+            .sensor_mut()
+            .load_bitboards(
+                start.board().by_color(Color::White) | Bitboard::from(Square::E4),
+                start.board().by_color(Color::Black),
+            )
+            .unwrap();
+        sim.step();
+
+        assert_eq!(sim.app().status(), GameStatus::AwaitingPieces);
+        let placement = sim.app().setup_placement().expect("still setting up");
+        assert_eq!(placement.extra, Bitboard::from(Square::E4));
+        assert!(placement.missing.white.is_empty() && placement.missing.black.is_empty());
+        assert_eq!(
+            sim.display().last().and_then(|fb| fb.get(Square::E4)),
+            Some(SquareFeedback::Origin)
+        );
+
+        sim.app_mut()
+            .sensor_mut()
+            .load_bitboards(
+                start.board().by_color(Color::White),
+                start.board().by_color(Color::Black),
+            )
+            .unwrap();
+        sim.step();
+
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+    }
+
+    #[test]
+    fn start_game_rejected_while_game_in_progress() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::StartGame,
+                ErrorCode::GameAlreadyInProgress
+            )]
+        );
+    }
+
+    /// Lichess with a game waiting in which the board plays Black.
+    #[derive(Clone)]
+    struct WaitingGame;
+
+    impl LichessTransport for WaitingGame {
+        fn stream(
+            &self,
+            path: &str,
+            _token: &str,
+        ) -> Result<Box<dyn std::io::BufRead + Send>, crate::net::lichess::LichessError> {
+            let body = match path {
+                "/api/stream/event" => {
+                    r#"{"type":"gameStart","game":{"gameId":"g1","color":"black"}}"#
+                }
+                _ => r#"{"type":"gameFull","state":{"moves":"","status":"started"}}"#,
+            };
+            Ok(Box::new(std::io::Cursor::new(body)))
+        }
+
+        fn post(
+            &self,
+            _path: &str,
+            _token: &str,
+            _form: &str,
+        ) -> Result<(), crate::net::lichess::LichessError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn online_players_need_their_service_and_a_human_opponent() {
+        let mut sim = Simulation::new();
+        sim.app_mut()
+            .set_lichess(Lichess::new(WaitingGame, "lip_token"));
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Relay,
+        });
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Lichess,
+            black: PlayerType::Random,
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![
+                CommandResult::error(CommandSource::StartGame, ErrorCode::InvalidCommand),
+                CommandResult::error(CommandSource::StartGame, ErrorCode::InvalidCommand),
+            ]
+        );
+        assert_eq!(sim.app().status(), GameStatus::Idle);
+    }
+
+    #[test]
+    fn lichess_opponent_decides_the_colors_before_the_game_starts() {
+        let mut sim = black_near();
+        sim.app_mut()
+            .set_lichess(Lichess::new(WaitingGame, "lip_token"));
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Lichess,
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sim.app().status() != GameStatus::InProgress {
+            assert!(Instant::now() < deadline, "timed out");
+            sim.step();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(sim.app().perspective(), Color::Black);
+        let notifications = sim.notifications();
+        let swapped = [
+            Notification::PlayerType(Color::White, PlayerType::Lichess),
+            Notification::PlayerType(Color::Black, PlayerType::Human),
+        ];
+        assert!(
+            notifications.windows(2).any(|pair| pair == swapped),
+            "{notifications:?}"
+        );
+    }
+
+    #[test]
+    fn cancel_game_returns_to_idle() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.send(BleCommand::CancelGame);
+        sim.step();
+
+        assert_eq!(sim.app().status(), GameStatus::Idle);
+        assert!(
+            sim.notifications()
+                .contains(&Notification::GameStatus(GameStatus::Idle))
+        );
+        assert!(sim.notifications().contains(&Notification::ResetPosition));
+    }
+
+    #[test]
+    fn cancel_game_when_idle_is_an_error() {
+        let mut sim = Simulation::new();
+        sim.send(BleCommand::CancelGame);
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoGameInProgress
+            )]
+        );
+    }
+
+    // ── moves ───────────────────────────────────────────────────────
+
+    #[test]
+    fn human_move_on_board_is_published() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.push_script("e2 We4.").unwrap();
+        sim.step();
+
+        let n = sim.notifications();
+        assert!(n.contains(&Notification::MovePlayed(Color::White, "e2e4".to_string())));
+        assert!(n.contains(&Notification::LastMove(Color::White, "e2e4".to_string())));
+        assert_eq!(
+            sim.app().notifier().last_checksum,
+            Some(StateChecksum {
+                hash: 0xfd11_4196,
+                plies: 1
+            })
+        );
+        assert_eq!(
+            sim.display().animations(),
+            &[
+                (Duration::ZERO, Animation::game_start()),
+                (TICK_INTERVAL, Animation::MoveConfirm { square: Square::E4 })
+            ]
+        );
+    }
+
+    #[test]
+    fn games_run_without_leds_or_a_client_link() {
+        let mut app = BoardApp::new(
+            ScriptedSensor::new(),
+            None::<CapturingDisplay>,
+            None::<RecordingNotifier>,
+            VirtualClock::new(),
+        );
+        let mut commands = Some(QueuedCommands::default());
+        if let Some(queue) = &mut commands {
+            queue.0.push_back(ClientEvent::Command(
+                0,
+                BleCommand::StartGame {
+                    white: PlayerType::Human,
+                    black: PlayerType::Human,
+                },
+            ));
+        }
+        app.step(&mut commands);
+        app.step(&mut None::<QueuedCommands>);
+        app.sensor_mut().push_script("e2 We4.").unwrap();
+        app.sensor_mut().tick().unwrap();
+        app.step(&mut None::<QueuedCommands>);
+
+        assert_eq!(app.session().unwrap().moves().len(), 1);
+        assert!(!app.notifier().is_connected());
+    }
+
+    #[test]
+    fn knocked_over_piece_is_reported_until_put_back() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.push_script("d1 Wd3.").unwrap();
+        sim.step();
+        assert!(sim.notifications().contains(&Notification::GameEvent(
+            GameEvent::BoardOutOfSync {
+                squares: Bitboard::from(Square::D1) | Bitboard::from(Square::D3)
+            }
+        )));
+        let fb = sim.display().last().unwrap();
+        assert!(fb.get(Square::D1).is_some() && fb.get(Square::D3).is_some());
+
+        sim.clear_notifications();
+        sim.push_script("d3 Wd1.").unwrap();
+        sim.step();
+        assert_eq!(
+            sim.notifications(),
+            &[Notification::GameEvent(GameEvent::BoardInSync)]
+        );
+    }
+
+    #[test]
+    fn check_is_shown_until_a_piece_is_lifted_to_answer_it() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        // 1. e4 f5 2. Qh5+, then Black lifts the g-pawn to block.
+        for script in ["e2 We4.", "f7 Bf5.", "d1 Wh5.", "g7."] {
+            sim.push_script(script).unwrap();
+            sim.step();
+        }
+
+        let display = sim.display();
+        display.assert_shown(Square::E8, SquareFeedback::Check);
+        display.assert_shown(Square::H5, SquareFeedback::Checker);
+        display.assert_shown(Square::G6, SquareFeedback::Destination);
+        assert!(
+            display.first_shown(Square::E8, SquareFeedback::Check)
+                < display.first_shown(Square::G7, SquareFeedback::Origin)
+        );
+        display.assert_never_together(SquareFeedback::Check, SquareFeedback::Origin);
+        assert_eq!(
+            display.history(Square::E8).last().map(|&(_, shown)| shown),
+            Some(None),
+            "check cleared while the reply is made"
+        );
+    }
+
+    #[test]
+    fn submitted_remote_move_is_played() {
+        let mut sim = started(PlayerType::Remote, PlayerType::Human);
+        sim.send(BleCommand::SubmitMove {
+            uci: "e2e4".to_string(),
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::success(CommandSource::SubmitMove)]
+        );
+        assert!(
+            sim.notifications()
+                .contains(&Notification::MovePlayed(Color::White, "e2e4".to_string()))
+        );
+        assert_eq!(sim.app().session().unwrap().position().turn(), Color::Black);
+    }
+
+    #[test]
+    fn conditional_reply_is_guided_after_the_remote_move() {
+        let mut sim = started(PlayerType::Remote, PlayerType::Human);
+        sim.send(BleCommand::AddConditional {
+            trigger: "e2e4".to_string(),
+            reply: "e7e5".to_string(),
+        });
+        sim.step();
+        sim.send(BleCommand::AddConditional {
+            trigger: "e2e4".to_string(),
+            reply: "e2e4".to_string(),
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![
+                CommandResult::success(CommandSource::MatchControl),
+                CommandResult::error(CommandSource::MatchControl, ErrorCode::IllegalMove),
+            ]
+        );
+
+        sim.send(BleCommand::SubmitMove {
+            uci: "e2e4".to_string(),
+        });
+        sim.step();
+        // Black sits at the sensors' first rank, so e2-e4 is d7-d5 there.
+        sim.push_script("d7 Wd5.").unwrap();
+        sim.step();
+
+        let feedback = sim.display().last().unwrap();
+        assert_eq!(feedback.get(Square::D2), Some(SquareFeedback::Origin));
+        assert_eq!(feedback.get(Square::D4), Some(SquareFeedback::Destination));
+    }
+
+    #[test]
+    fn black_against_a_remote_opponent_plays_from_the_near_side() {
+        let mut sim = black_near();
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Remote,
+            black: PlayerType::Human,
+        });
+        sim.step();
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+        assert_eq!(sim.app().perspective(), Color::Black);
+        assert_eq!(
+            sim.display().edge().map(|edge| edge.near),
+            Some(Color::Black)
+        );
+
+        sim.send(BleCommand::SubmitMove {
+            uci: "e2e4".to_string(),
+        });
+        sim.step();
+        sim.step();
+        assert_eq!(
+            sim.display().last().unwrap().get(Square::D5),
+            Some(SquareFeedback::Destination),
+            "e4 is guided on the far side"
+        );
+        sim.push_script("d7 Wd5.").unwrap();
+        sim.step();
+        sim.push_script("d2 Bd4.").unwrap();
+        sim.step();
+
+        assert_eq!(moves_played(&sim), ["e2e4", "e7e5"]);
+        assert_eq!(sim.app().perspective(), Color::Black);
+    }
+
+    #[test]
+    fn orientation_sets_up_games_between_humans() {
+        let mut sim = black_near();
+        sim.app_mut().set_orientation(Color::Black);
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+        assert_eq!(sim.app().perspective(), Color::Black);
+    }
+
+    #[test]
+    fn stored_config_applies_to_the_next_game() {
+        let mut sim = black_near();
+        sim.app_mut().apply_config(&BoardConfig {
+            theme: Theme::HighContrast,
+            clock: crate::chess_clock::TimeControl::parse("3+2"),
+            orientation: Color::Black,
+        });
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+
+        assert_eq!(sim.app().display_settings().theme, Theme::HighContrast);
+        assert_eq!(sim.app().perspective(), Color::Black);
+        assert!(sim.app().game_clock().is_some());
+    }
+
+    #[test]
+    fn submit_move_for_human_side_is_not_your_turn() {
+        let mut sim = started(PlayerType::Human, PlayerType::Remote);
+        sim.send(BleCommand::SubmitMove {
+            uci: "e2e4".to_string(),
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::SubmitMove,
+                ErrorCode::NotYourTurn
+            )]
+        );
+    }
+
+    #[test]
+    fn human_move_onto_dead_square_can_be_announced() {
+        let mut sim = Simulation::new();
+        sim.app_mut().set_dead_squares(Square::E4.into());
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+        sim.clear_notifications();
+
+        sim.send(BleCommand::SubmitMove {
+            uci: "e2e4".to_string(),
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::success(CommandSource::SubmitMove)]
+        );
+        assert!(
+            sim.notifications()
+                .contains(&Notification::MovePlayed(Color::White, "e2e4".to_string()))
+        );
+    }
+
+    #[test]
+    fn setup_ignores_dead_squares() {
+        let mut sim = Simulation::new();
+        sim.app_mut().set_dead_squares(Square::E2.into());
+        sim.push_script("e2.").unwrap();
+        sim.step();
+
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+    }
+
+    #[test]
+    fn submit_illegal_move_is_rejected() {
+        let mut sim = started(PlayerType::Remote, PlayerType::Human);
+        sim.send(BleCommand::SubmitMove {
+            uci: "e2e5".to_string(),
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::SubmitMove,
+                ErrorCode::IllegalMove
+            )]
+        );
+    }
+
+    #[test]
+    fn submit_move_without_game_is_rejected() {
+        let mut sim = Simulation::new();
+        sim.send(BleCommand::SubmitMove {
+            uci: "e2e4".to_string(),
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::SubmitMove,
+                ErrorCode::NoGameInProgress
+            )]
+        );
+    }
+
+    // ── promotion ───────────────────────────────────────────────────
+
+    /// Nine batches ending in a promotion: `1. e4 d5 2. exd5 c6 3. dxc6 Nf6
+    /// 4. cxb7 Nbd7 5. bxa8`.
+    const PROMOTION_LINE: &str = "e2 We4. d7 Bd5. e4 d5 Wd5. c7 Bc6. d5 c6 Wc6. \
+                                  g8 Bf6. c6 b7 Wb7. b8 Bd7. b7 a8 Wa8.";
+
+    /// Play [`PROMOTION_LINE`], leaving the promotion waiting for a piece choice.
+    fn reach_promotion() -> Simulation {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.push_script(PROMOTION_LINE).unwrap();
+        for _ in 0..9 {
+            sim.step();
+        }
+        sim.clear_notifications();
+        sim
+    }
+
+    fn moves_played(sim: &Simulation) -> Vec<String> {
+        sim.notifications()
+            .iter()
+            .filter_map(|n| match n {
+                Notification::MovePlayed(_, uci) => Some(uci.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn promotion_is_published_as_pending() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.push_script(PROMOTION_LINE).unwrap();
+        for _ in 0..9 {
+            sim.step();
+        }
+
+        assert!(
+            sim.notifications()
+                .contains(&Notification::PendingPromotion(Color::White, "b7a8".into()))
+        );
+        assert_eq!(moves_played(&sim).len(), 8);
+    }
+
+    #[test]
+    fn chosen_promotion_piece_is_played() {
+        let mut sim = reach_promotion();
+        sim.send(BleCommand::ChoosePromotion { role: Role::Knight });
+        sim.step();
+
+        assert_eq!(moves_played(&sim), vec!["b7a8n".to_string()]);
+        assert!(
+            sim.notifications()
+                .contains(&Notification::ResetPendingPromotion)
+        );
+        let session = sim.app().session().unwrap();
+        assert_eq!(
+            session.position().board().piece_at(Square::A8),
+            Some(Role::Knight.of(Color::White))
+        );
+    }
+
+    #[test]
+    fn promotion_defaults_to_queen_after_timeout() {
+        let mut sim = reach_promotion();
+        sim.run_for(PROMOTION_CHOICE_TIMEOUT - TICK_INTERVAL * 2);
+        assert!(moves_played(&sim).is_empty());
+
+        sim.run_for(TICK_INTERVAL * 2);
+        assert_eq!(moves_played(&sim), vec!["b7a8q".to_string()]);
+    }
+
+    #[test]
+    fn resumed_game_continues_the_interrupted_move() {
+        // Power went out with the e7 pawn in hand.
+        let mut sim = resumed_after_e4("e2 We4 e7.");
+        sim.step();
+        let frame = sim.display().last().unwrap();
+        assert_eq!(frame.get(Square::E5), Some(SquareFeedback::Destination));
+        assert_eq!(frame.get(Square::E6), Some(SquareFeedback::Destination));
+
+        sim.push_script("Be5.").unwrap();
+        sim.step();
+        assert_eq!(moves_played(&sim), ["e7e5"]);
+        assert_eq!(
+            sim.app_mut()
+                .resume_game(PlayerType::Human, PlayerType::Human, Chess::default()),
+            Err(ErrorCode::GameAlreadyInProgress)
+        );
+    }
+
+    #[test]
+    fn move_finished_while_the_board_was_off_is_played_on_resume() {
+        let mut sim = resumed_after_e4("e2 We4 e7 Be5.");
+        sim.step();
+        assert_eq!(moves_played(&sim), ["e7e5"]);
+
+        let mut sim = resumed_after_e4("e2 We4 e7 Be5.");
+        sim.push_script("e5 Be7.").unwrap();
+        sim.step();
+        assert!(moves_played(&sim).is_empty(), "taken back before resuming");
+    }
+
+    #[test]
+    fn promotion_picked_on_the_board_is_played_after_timeout() {
+        let mut sim = reach_promotion();
+        // Lift the pawn and put it back: queen -> rook.
+        sim.push_script("a8. Wa8.").unwrap();
+        sim.step();
+        sim.step();
+        assert_eq!(
+            sim.app().session().unwrap().promotion_choice(),
+            Some(Role::Rook)
+        );
+
+        sim.run_for(PROMOTION_CHOICE_TIMEOUT);
+        assert_eq!(moves_played(&sim), vec!["b7a8r".to_string()]);
+    }
+
+    #[test]
+    fn choose_promotion_without_pending_is_rejected() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.send(BleCommand::ChoosePromotion { role: Role::Rook });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoPendingPromotion
+            )]
+        );
+    }
+
+    // ── resign ──────────────────────────────────────────────────────
+
+    #[test]
+    fn resign_ends_game_and_returns_to_idle() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.send(BleCommand::Resign {
+            color: Color::White,
+        });
+        sim.step();
+
+        assert_eq!(
+            sim.notifications(),
+            &[
+                Notification::CommandResult(CommandResult::success(CommandSource::MatchControl)),
+                Notification::GameStatus(GameStatus::Resigned {
+                    color: Color::White
+                }),
+                Notification::ResetPlayerTypes,
+            ]
+        );
+        assert_eq!(sim.app().status(), GameStatus::Idle);
+    }
+
+    #[test]
+    fn resign_for_remote_side_is_rejected() {
+        let mut sim = started(PlayerType::Human, PlayerType::Remote);
+        sim.send(BleCommand::Resign {
+            color: Color::Black,
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::CannotResignForRemotePlayer
+            )]
+        );
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+    }
+
+    // ── takeback ────────────────────────────────────────────────────
+
+    #[test]
+    fn takeback_guides_the_pieces_back_before_detecting_moves() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.push_script("e2 We4.").unwrap();
+        sim.run_for(Duration::from_millis(300));
+        sim.clear_notifications();
+
+        sim.send(BleCommand::TakeBack);
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::success(CommandSource::MatchControl)]
+        );
+        assert!(
+            sim.notifications()
+                .contains(&Notification::Position(position_fen(&Chess::default())))
+        );
+        assert!(sim.app().session().unwrap().moves().is_empty());
+        let frame = sim.display().last().unwrap();
+        assert_eq!(frame.get(Square::E2), Some(SquareFeedback::Destination));
+
+        sim.push_script("e4 We2.").unwrap();
+        sim.run_for(Duration::from_millis(300));
+        assert_eq!(sim.display().last().unwrap().get(Square::E2), None);
+        assert!(sim.app().session().unwrap().moves().is_empty());
+
+        sim.push_script("d2 Wd4.").unwrap();
+        sim.run_for(Duration::from_millis(300));
+        assert_eq!(sim.app().session().unwrap().moves().len(), 1);
+    }
+
+    #[test]
+    fn takeback_without_a_move_is_refused() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.send(BleCommand::TakeBack);
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::CannotTakeBack
+            )]
+        );
+    }
+
+    // ── external results ────────────────────────────────────────────
+
+    #[test]
+    fn reported_timeout_ends_game_and_shows_result() {
+        let mut sim = started(PlayerType::Human, PlayerType::Remote);
+        sim.send(BleCommand::ReportResult {
+            result: ExternalResult::Timeout {
+                loser: Color::Black,
+            },
+        });
+        sim.step();
+
+        assert_eq!(
+            sim.notifications(),
+            &[
+                Notification::CommandResult(CommandResult::success(CommandSource::MatchControl)),
+                Notification::GameStatus(GameStatus::Timeout {
+                    loser: Color::Black
+                }),
+                Notification::ResetPlayerTypes,
+            ]
+        );
+        assert_eq!(sim.app().status(), GameStatus::Idle);
+        let frame = sim.display().last().expect("result frame shown");
+        assert_eq!(frame.get(Square::E8), Some(SquareFeedback::Check));
+        assert_eq!(frame.get(Square::E1), Some(SquareFeedback::Victory));
+    }
+
+    #[test]
+    fn report_result_without_remote_side_is_rejected() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.send(BleCommand::ReportResult {
+            result: ExternalResult::Draw,
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoRemotePlayer
+            )]
+        );
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+    }
+
+    #[test]
+    fn report_result_without_game_is_rejected() {
+        let mut sim = Simulation::new();
+        sim.send(BleCommand::ReportResult {
+            result: ExternalResult::Aborted,
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoGameInProgress
+            )]
+        );
+    }
+
+    // ── abort gesture ───────────────────────────────────────────────
+
+    fn clear_board(sim: &mut Simulation) {
+        sim.app_mut()
+            .sensor_mut()
+            .load_bitboards(Bitboard::EMPTY, Bitboard::EMPTY)
+            .unwrap();
+    }
+
+    #[test]
+    fn clearing_board_prompts_then_centre_piece_aborts() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        clear_board(&mut sim);
+        sim.run_for(ABORT_HOLD + TICK_INTERVAL);
+
+        assert_eq!(sim.display().last(), Some(&prompt_feedback(Duration::ZERO)));
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+
+        sim.push_script("We4.").unwrap();
+        sim.step();
+        sim.step();
+
+        assert_eq!(
+            sim.notifications(),
+            &[
+                Notification::GameStatus(GameStatus::Aborted),
+                Notification::ResetPlayerTypes,
+            ]
+        );
+        assert_eq!(sim.app().status(), GameStatus::Idle);
+    }
+
+    #[test]
+    fn restoring_pieces_declines_abort() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        clear_board(&mut sim);
+        sim.run_for(ABORT_HOLD + TICK_INTERVAL);
+
+        let start = Chess::default();
+        sim.app_mut()
+            .sensor_mut()
+            .load_bitboards(
+                start.board().by_color(Color::White),
+                start.board().by_color(Color::Black),
+            )
+            .unwrap();
+        sim.run_for(ABORT_CONFIRM_TIMEOUT);
+
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+        assert!(sim.notifications().is_empty());
+    }
+
+    // ── resign and new-game gestures ────────────────────────────────
+
+    /// The starting position with `squares` lifted.
+    fn start_without(sim: &mut Simulation, squares: Bitboard) {
+        let start = Chess::default();
+        sim.app_mut()
+            .sensor_mut()
+            .load_bitboards(
+                start.board().by_color(Color::White) & !squares,
+                start.board().by_color(Color::Black) & !squares,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn holding_a_king_off_the_board_resigns() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        start_without(&mut sim, Bitboard::from(Square::E8));
+        sim.run_for(RESIGN_HOLD - TICK_INTERVAL);
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+
+        sim.run_for(2 * TICK_INTERVAL);
+
+        assert!(sim.notifications().contains(&Notification::GameEvent(
+            GameEvent::ResignedOnBoard {
+                color: Color::Black
+            }
+        )));
+        assert_eq!(
+            sim.app().status(),
+            GameStatus::Resigned {
+                color: Color::Black
+            }
+        );
+    }
+
+    #[test]
+    fn clearing_both_back_ranks_starts_over_with_the_same_players() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        start_without(
+            &mut sim,
+            Bitboard::from_rank(Rank::First) | Bitboard::from_rank(Rank::Eighth),
+        );
+        sim.run_for(NEW_GAME_HOLD + TICK_INTERVAL);
+
+        assert!(
+            sim.notifications()
+                .contains(&Notification::GameEvent(GameEvent::NewGameRequested))
+        );
+        assert_eq!(sim.app().status(), GameStatus::AwaitingPieces);
+    }
+
+    // ── modes ───────────────────────────────────────────────────────
+
+    fn knights_tour() -> BleCommand {
+        BleCommand::StartMode {
+            mode: ModeSelection::MiniGame(MiniGame::KnightsTour),
+        }
+    }
+
+    #[test]
+    fn started_mode_drives_the_display() {
+        let mut sim = Simulation::new();
+        sim.send(knights_tour());
+        sim.step();
+        assert_eq!(sim.app().mode_name(), Some("knight's tour"));
+        assert_eq!(sim.app().status(), GameStatus::Idle);
+
+        sim.app_mut()
+            .sensor_mut()
+            .load_bitboards(Square::A1.into(), Bitboard::EMPTY)
+            .unwrap();
+        sim.step();
+
+        let frame = sim.display().last().unwrap();
+        assert_eq!(frame.get(Square::A1), Some(SquareFeedback::Origin));
+        assert_eq!(frame.get(Square::B3), Some(SquareFeedback::Destination));
+    }
+
+    #[test]
+    fn finished_mode_returns_to_idle() {
+        let mut sim = Simulation::new();
+        sim.send(BleCommand::StartMode {
+            mode: ModeSelection::Analysis,
+        });
+        sim.step();
+        // Fool's mate
+        sim.push_script("f2 Wf3. e7 Be5. g2 Wg4. d8 Bh4.").unwrap();
+        sim.run_for(Duration::from_millis(300));
+
+        assert_eq!(sim.app().mode_name(), None);
+        let frame = sim.display().last().unwrap();
+        assert_eq!(frame.get(Square::E1), Some(SquareFeedback::Check));
+    }
+
+    #[test]
+    fn heat_map_replays_the_last_finished_game() {
+        let mut sim = Simulation::new();
+        let heat_map = BleCommand::StartMode {
+            mode: ModeSelection::HeatMap,
+        };
+        sim.send(heat_map.clone());
+        sim.step();
+        assert_eq!(
+            results(&sim),
+            [CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::InvalidCommand
+            )]
+        );
+
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+        sim.push_script("f2 Wf3. e7 Be5. g2 Wg4. d8 Bh4.").unwrap();
+        sim.run_for(Duration::from_millis(300));
+        assert_eq!(sim.app().status(), GameStatus::Idle);
+
+        sim.send(heat_map);
+        sim.run_for(Duration::from_secs(5));
+        assert_eq!(sim.app().mode_name(), Some("heat map"));
+        let frame = sim.display().last().unwrap();
+        assert!(frame.get(Square::G5).is_some(), "queen's path");
+        assert_eq!(frame.get(Square::A1), None);
+    }
+
+    #[test]
+    fn daily_puzzle_on_boot_guides_the_setup_and_yields_to_a_game() {
+        let mut sim = Simulation::new();
+        let puzzle = BootBehavior::DailyPuzzle.selection(1).unwrap();
+        sim.app_mut().enter_mode(puzzle).unwrap();
+        sim.step();
+        assert_eq!(sim.app().mode_name(), Some("pawn capture"));
+        assert!(results(&sim).is_empty(), "no client asked");
+        let frame = sim.display().last().unwrap();
+        assert!(PAWN_PUZZLES[1].pawns.contains(Square::G5));
+        assert!(frame.get(Square::G5).is_some(), "pawn to place");
+
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+        assert_eq!(sim.app().mode_name(), None);
+        assert_eq!(BootBehavior::Idle.selection(1), None);
+    }
+
+    #[test]
+    fn start_game_leaves_running_mode() {
+        let mut sim = Simulation::new();
+        sim.send(knights_tour());
+        sim.step();
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+
+        assert_eq!(sim.app().mode_name(), None);
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+    }
+
+    #[test]
+    fn cancel_game_ends_mode() {
+        let mut sim = Simulation::new();
+        sim.send(knights_tour());
+        sim.send(BleCommand::CancelGame);
+        sim.step();
+        sim.step();
+
+        assert_eq!(sim.app().mode_name(), None);
+        assert_eq!(
+            results(&sim),
+            vec![
+                CommandResult::success(CommandSource::MatchControl),
+                CommandResult::success(CommandSource::MatchControl),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_puzzle_is_rejected() {
+        let mut sim = Simulation::new();
+        sim.send(BleCommand::StartMode {
+            mode: ModeSelection::MiniGame(MiniGame::PawnCapture(99)),
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::InvalidCommand
+            )]
+        );
+        assert_eq!(sim.app().mode_name(), None);
+    }
+
+    #[test]
+    fn start_mode_during_game_is_rejected() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.send(knights_tour());
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::GameAlreadyInProgress
+            )]
+        );
+        assert_eq!(sim.app().status(), GameStatus::InProgress);
+    }
+
+    // ── random opponent ─────────────────────────────────────────────
+
+    #[test]
+    fn random_opponent_answers_a_human_move() {
+        let mut sim = started(PlayerType::Human, PlayerType::Random);
+        sim.push_script("e2 We4.").unwrap();
+        sim.step();
+        sim.step();
+
+        let played: Vec<_> = sim
+            .notifications()
+            .iter()
+            .filter_map(|n| match n {
+                Notification::MovePlayed(color, _) => Some(*color),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(played, [Color::White, Color::Black]);
+    }
+
+    #[test]
+    fn soak_random_games_run_to_completion() {
+        let mut sim = Simulation::new();
+        for game in 0..25 {
+            sim.send(BleCommand::StartGame {
+                white: PlayerType::Random,
+                black: PlayerType::Random,
+            });
+            sim.step();
+            let mut steps = 0;
+            while sim.app().status() == GameStatus::InProgress {
+                sim.step();
+                steps += 1;
+                assert!(steps < 2_000, "game {game} did not end");
+            }
+            sim.step();
+            assert_eq!(sim.app().status(), GameStatus::Idle);
+        }
+        assert_eq!(sim.app().stats().games_played(), 25);
+    }
+
+    // ── move confirmation ───────────────────────────────────────────
+
+    #[test]
+    fn feedback_settles_before_it_is_shown() {
+        let mut sim = Simulation::new();
+        sim.app_mut()
+            .set_feedback_settle(Duration::from_millis(100));
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+        sim.step();
+
+        sim.push_script("e2. . .").unwrap();
+        sim.step();
+        assert_eq!(sim.display().last().unwrap().get(Square::E2), None);
+        sim.step();
+        assert_eq!(sim.display().last().unwrap().get(Square::E2), None);
+        sim.step();
+        assert_eq!(
+            sim.display().last().unwrap().get(Square::E2),
+            Some(SquareFeedback::Origin),
+            "shown after holding for 100 ms"
+        );
+
+        // The move itself is shown at once.
+        sim.push_script("We4.").unwrap();
+        sim.step();
+        assert!(sim.display().last().unwrap().is_empty());
+    }
+
+    #[test]
+    fn sensor_glitches_shorter_than_the_settle_time_are_not_shown() {
+        let sensor = ScriptedSensor::new().with_noise_seed(7);
+        let mut sim = Simulation::with_sensor(sensor);
+        sim.app_mut()
+            .set_feedback_settle(Duration::from_millis(100));
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+        sim.step();
+
+        sim.push_script("~e2:1. @200ms ~d7:1.").unwrap();
+        sim.run_for(Duration::from_millis(500));
+
+        sim.display().assert_never("a glitch", |fb| !fb.is_empty());
+        assert!(sim.app().session().unwrap().moves().is_empty());
+    }
+
+    #[test]
+    fn a_slow_move_is_read_once_the_piece_lands() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.push_script("e2. @3s We4.").unwrap();
+        sim.run_for(Duration::from_secs(2));
+        assert_eq!(
+            sim.display().last().unwrap().get(Square::E2),
+            Some(SquareFeedback::Origin)
+        );
+
+        sim.run_for(Duration::from_secs(2));
+
+        assert_eq!(moves_played(&sim), ["e2e4"]);
+    }
+
+    #[test]
+    fn uncertain_move_blinks_until_the_clock_is_pressed() {
+        let mut sim = Simulation::new();
+        sim.app_mut()
+            .set_move_confirmation(MoveConfirmation::WhenUncertain);
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+        sim.push_script("e2 We3.").unwrap();
+
+        let lit: Vec<bool> = (0..12)
+            .map(|_| {
+                sim.step();
+                sim.display().last().unwrap().get(Square::E3).is_some()
+            })
+            .collect();
+        assert!(lit.contains(&true) && lit.contains(&false), "{lit:?}");
+        assert_eq!(sim.app().session().unwrap().position().turn(), Color::White);
+
+        sim.send(BleCommand::PressClock {
+            color: Color::White,
+        });
+        sim.step();
+
+        assert!(
+            sim.notifications()
+                .contains(&Notification::MovePlayed(Color::White, "e2e3".to_string()))
+        );
+    }
+
+    // ── settings ────────────────────────────────────────────────────
+
+    #[test]
+    fn settings_apply_to_the_game_in_progress() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.send(BleCommand::ChangeSetting {
+            setting: Setting::Theme(Theme::HighContrast),
+        });
+        sim.send(BleCommand::ChangeSetting {
+            setting: Setting::Brightness(64),
+        });
+        sim.send(BleCommand::ChangeSetting {
+            setting: Setting::Assist(AssistLevel::Minimal),
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::success(CommandSource::MatchControl); 3]
+        );
+        assert_eq!(
+            sim.display().settings(),
+            Some(&DisplaySettings {
+                brightness: 64,
+                theme: Theme::HighContrast,
+            })
+        );
+
+        sim.push_script("e2.").unwrap();
+        sim.step();
+        assert!(sim.display().last().unwrap().is_empty(), "no move hints");
+    }
+
+    // ── profiles ────────────────────────────────────────────────────
+
+    #[test]
+    fn selected_profile_applies_until_the_game_ends() {
+        let mut sim = Simulation::new();
+        sim.app_mut()
+            .set_profiles(Profile::parse_list("Ann: high-contrast minimal 3+2").unwrap());
+        sim.send(BleCommand::SelectProfile { index: 0 });
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+
+        assert_eq!(sim.app().display_settings().theme, Theme::HighContrast);
+        assert!(sim.app().game_clock().is_some());
+
+        sim.send(BleCommand::Resign {
+            color: Color::White,
+        });
+        sim.step();
+        assert_eq!(sim.app().status(), GameStatus::Idle);
+        assert_eq!(sim.app().display_settings().theme, Theme::Classic);
+        sim.send(BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        });
+        sim.step();
+        assert!(sim.app().game_clock().is_none());
+    }
+
+    #[test]
+    fn profiles_are_selected_only_between_games() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.app_mut()
+            .set_profiles(Profile::parse_list("Ann: black").unwrap());
+        sim.send(BleCommand::SelectProfile { index: 0 });
+        sim.send(BleCommand::CancelGame);
+        sim.step();
+        sim.send(BleCommand::SelectProfile { index: 1 });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![
+                CommandResult::error(
+                    CommandSource::MatchControl,
+                    ErrorCode::GameAlreadyInProgress
+                ),
+                CommandResult::success(CommandSource::MatchControl),
+                CommandResult::error(CommandSource::MatchControl, ErrorCode::UnknownProfile),
+            ]
+        );
+    }
+
+    // ── clock ───────────────────────────────────────────────────────
+
+    /// A clocked game whose players have not confirmed readiness yet.
+    fn clocked_game(confirm_moves: bool, white: PlayerType, black: PlayerType) -> Simulation {
+        let mut sim = Simulation::new();
+        sim.send(BleCommand::SetClock {
+            settings: Some(ClockSettings {
+                time_control: crate::chess_clock::TimeControl {
+                    initial: Duration::from_secs(60),
+                    increment: Duration::from_secs(1),
+                    method: crate::chess_clock::TimingMethod::Fischer,
+                },
+                confirm_moves,
+            }),
+        });
+        sim.send(BleCommand::StartGame { white, black });
+        sim.step();
+        sim.clear_notifications();
+        sim
+    }
+
+    fn clocked(confirm_moves: bool) -> Simulation {
+        let mut sim = clocked_game(confirm_moves, PlayerType::Human, PlayerType::Human);
+        for color in Color::ALL {
+            sim.send(BleCommand::PressClock { color });
+            sim.step();
+        }
+        sim.clear_notifications();
+        sim
+    }
+
+    #[test]
+    fn move_on_board_switches_clock() {
+        let mut sim = clocked(false);
+        sim.push_script("e2 We4.").unwrap();
+        sim.step();
+
+        assert_eq!(
+            sim.app().game_clock().unwrap().running(),
+            Some(Color::Black)
+        );
+    }
+
+    #[test]
+    fn confirmed_move_counts_only_after_clock_press() {
+        let mut sim = clocked(true);
+        sim.push_script("e2 We4.").unwrap();
+        sim.step();
+
+        assert_eq!(sim.app().session().unwrap().position().turn(), Color::White);
+        assert_eq!(
+            sim.app().game_clock().unwrap().running(),
+            Some(Color::White)
+        );
+
+        sim.send(BleCommand::PressClock {
+            color: Color::White,
+        });
+        sim.step();
+
+        assert!(
+            sim.notifications()
+                .contains(&Notification::MovePlayed(Color::White, "e2e4".to_string()))
+        );
+        assert_eq!(
+            sim.app().game_clock().unwrap().running(),
+            Some(Color::Black)
+        );
+    }
+
+    #[test]
+    fn clock_press_without_move_is_rejected() {
+        let mut sim = clocked(true);
+        sim.send(BleCommand::PressClock {
+            color: Color::White,
+        });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::NoPendingMove
+            )]
+        );
+    }
+
+    #[test]
+    fn clock_bar_shows_remaining_time_on_the_edge() {
+        let mut sim = clocked(false);
+        sim.run_for(Duration::from_secs(30));
+
+        let fb = sim.display().last().unwrap();
+        // White has half of 60s left: h1-h4.
+        assert_eq!(fb.get(Square::H4), Some(SquareFeedback::Origin));
+        assert_eq!(fb.get(Square::H5), None);
+        assert_eq!(fb.get(Square::A1), Some(SquareFeedback::Origin));
+    }
+
+    #[test]
+    fn clock_bar_can_be_turned_off() {
+        let mut sim = clocked(false);
+        sim.app_mut().set_clock_bar(false);
+        sim.step();
+
+        assert!(sim.display().last().unwrap().is_empty());
+    }
+
+    #[test]
+    fn edge_shows_turn_and_clock() {
+        let mut sim = clocked(false);
+        sim.push_script("e2 We4.").unwrap();
+        sim.step();
+
+        let edge = *sim.display().edge().unwrap();
+        assert_eq!(edge.turn, Some(Color::Black));
+        assert!(edge.connected);
+        let bars = edge.clock.expect("clocked game");
+        assert!(bars.white.fill > 0 && !bars.white.low);
+
+        sim.app_mut().notifier_mut().connected = false;
+        sim.send(BleCommand::CancelGame);
+        sim.step();
+
+        assert_eq!(sim.display().edge(), Some(&EdgeFeedback::idle(false)));
+
+        sim.app_mut().set_wifi_status(WifiStatus::Connecting);
+        sim.step();
+        assert_eq!(
+            sim.display().edge().unwrap().wifi,
+            Some(WifiStatus::Connecting)
+        );
+    }
+
+    #[test]
+    fn flag_fall_ends_the_game() {
+        let mut sim = clocked(false);
+        sim.run_for(Duration::from_secs(61));
+
+        assert_eq!(
+            events(&sim),
+            [GameEvent::Flagged {
+                color: Color::White
+            }]
+        );
+        assert!(
+            sim.notifications()
+                .contains(&Notification::GameStatus(GameStatus::Timeout {
+                    loser: Color::White
+                }))
+        );
+    }
+
+    /// A [`GameStore`] shared with the test, holding the encoded game.
+    #[derive(Clone, Default)]
+    struct MemoryStore(Rc<RefCell<Option<String>>>);
+
+    impl GameStore for MemoryStore {
+        fn load(&self) -> Option<SavedGame> {
+            SavedGame::decode(self.0.borrow().as_ref()?).ok()
+        }
+
+        fn save(&mut self, game: &SavedGame) -> std::io::Result<()> {
+            *self.0.borrow_mut() = Some(game.encode());
+            Ok(())
+        }
+
+        fn clear(&mut self) -> std::io::Result<()> {
+            *self.0.borrow_mut() = None;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn saved_game_resumes_with_history_and_clock() {
+        let store = MemoryStore::default();
+        let mut sim = clocked(false);
+        sim.app_mut().set_game_store(Box::new(store.clone()));
+        sim.step();
+        assert_eq!(store.load().unwrap().moves.len(), 0);
+        sim.push_script("e2 We4.").unwrap();
+        sim.step();
+        let saved = store.load().unwrap();
+        assert_eq!(saved.moves.len(), 1);
+        let remaining = saved.clock.unwrap().remaining;
+
+        // Power comes back with the e7 pawn in hand.
+        let mut sim = Simulation::new();
+        sim.push_script("e2 We4 e7.").unwrap();
+        sim.step();
+        sim.app_mut().restore_game(saved).unwrap();
+        sim.step();
+
+        let clock = sim.app().game_clock().unwrap();
+        assert_eq!(clock.running(), None, "waits for both players");
+        assert_eq!(
+            clock.remaining(Color::White, Duration::ZERO),
+            remaining.white
+        );
+        assert_eq!(sim.app().session().unwrap().moves().len(), 1);
+
+        for color in Color::ALL {
+            sim.send(BleCommand::PressClock { color });
+            sim.step();
+        }
+        assert_eq!(
+            sim.app().game_clock().unwrap().running(),
+            Some(Color::Black)
+        );
+        let frame = sim.display().last().unwrap();
+        assert_eq!(frame.get(Square::E5), Some(SquareFeedback::Destination));
+    }
+
+    #[test]
+    fn saved_game_is_cleared_when_the_game_ends() {
+        let store = MemoryStore::default();
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.app_mut().set_game_store(Box::new(store.clone()));
+        sim.step();
+        assert!(store.load().is_some());
+
+        sim.send(BleCommand::Resign {
+            color: Color::White,
+        });
+        sim.step();
+        sim.step();
+        assert!(store.0.borrow().is_none());
+    }
+
+    fn events(sim: &Simulation) -> Vec<GameEvent> {
+        sim.notifications()
+            .iter()
+            .filter_map(|n| match n {
+                Notification::GameEvent(event) => Some(*event),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clock_waits_until_both_players_are_ready() {
+        let mut sim = clocked_game(false, PlayerType::Human, PlayerType::Human);
+        sim.run_for(Duration::from_secs(5));
+        assert_eq!(sim.app().game_clock().unwrap().running(), None);
+
+        sim.send(BleCommand::PressClock {
+            color: Color::Black,
+        });
+        sim.step();
+        assert_eq!(sim.app().game_clock().unwrap().running(), None);
+
+        sim.send(BleCommand::PressClock {
+            color: Color::White,
+        });
+        sim.step();
+
+        assert_eq!(
+            events(&sim),
+            [
+                GameEvent::PlayerReady {
+                    color: Color::Black
+                },
+                GameEvent::PlayerReady {
+                    color: Color::White
+                },
+                GameEvent::ClockStarted,
+            ]
+        );
+        assert_eq!(
+            sim.app().game_clock().unwrap().running(),
+            Some(Color::White)
+        );
+    }
+
+    #[test]
+    fn king_touch_confirms_readiness() {
+        let mut sim = clocked_game(false, PlayerType::Human, PlayerType::Human);
+        sim.push_script("e1.We1.").unwrap();
+        sim.step();
+        sim.step();
+
+        assert_eq!(
+            events(&sim),
+            [GameEvent::PlayerReady {
+                color: Color::White
+            }]
+        );
+        // Black's king is still lit, waiting for its player.
+        let fb = sim.display().last().unwrap();
+        assert_eq!(fb.get(Square::E8), Some(SquareFeedback::Origin));
+        assert_eq!(fb.get(Square::E1), None);
+    }
+
+    #[test]
+    fn moves_are_held_until_the_clock_starts() {
+        let mut sim = clocked_game(false, PlayerType::Human, PlayerType::Human);
+        sim.push_script("e2 We4.").unwrap();
+        sim.step();
+
+        assert_eq!(sim.app().session().unwrap().position().turn(), Color::White);
+    }
+
+    #[test]
+    fn remote_players_need_no_confirmation() {
+        let sim = clocked_game(false, PlayerType::Remote, PlayerType::Remote);
+
+        assert_eq!(
+            sim.app().game_clock().unwrap().running(),
+            Some(Color::White)
+        );
+    }
+
+    #[test]
+    fn set_clock_rejected_during_game() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.send(BleCommand::SetClock { settings: None });
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::GameAlreadyInProgress
+            )]
+        );
+    }
+
+    // ── virtual time ────────────────────────────────────────────────
+
+    #[test]
+    fn step_advances_virtual_time_by_tick_interval() {
+        let mut sim = Simulation::new();
+        sim.step();
+        sim.step();
+        assert_eq!(sim.now(), TICK_INTERVAL * 2);
+    }
+
+    #[test]
+    fn run_for_steps_until_deadline() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        let before = sim.display().frames().len();
+        sim.run_for(Duration::from_secs(1));
+
+        assert!(sim.now() >= Duration::from_secs(1));
+        assert_eq!(sim.display().frames().len() - before, 20);
+    }
+
+    #[test]
+    fn scripted_game_reaches_checkmate() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        // Fool's mate
+        sim.push_script("f2 Wf3. e7 Be5. g2 Wg4. d8 Bh4.").unwrap();
+        sim.run_for(Duration::from_millis(200));
+
+        assert!(
+            sim.notifications()
+                .contains(&Notification::GameStatus(GameStatus::Checkmate {
+                    loser: Color::White
+                }))
+        );
+    }
+
+    // ── pairing ─────────────────────────────────────────────────────
+
+    fn paired_sim() -> Simulation {
+        let mut sim = Simulation::new();
+        let mut rng = crate::rng::XorShift32::new(3);
+        sim.app_mut()
+            .set_pairing(Pairing::new(move || rng.next_u32()));
+        sim
+    }
+
+    fn start_human_game() -> BleCommand {
+        BleCommand::StartGame {
+            white: PlayerType::Human,
+            black: PlayerType::Human,
+        }
+    }
+
+    #[test]
+    fn unpaired_clients_cannot_control_the_board() {
+        let mut sim = paired_sim();
+        sim.send(start_human_game());
+        sim.step();
+
+        assert_eq!(sim.app().status(), GameStatus::Idle);
+        assert_eq!(
+            results(&sim),
+            [CommandResult::error(
+                CommandSource::StartGame,
+                ErrorCode::NotAuthenticated
+            )]
+        );
+    }
+
+    #[test]
+    fn pairing_with_the_shown_code_authenticates_the_connection_until_it_closes() {
+        let mut sim = paired_sim();
+        sim.send(BleCommand::RequestPairing);
+        sim.step();
+        let code: Bitboard = sim
+            .display()
+            .last()
+            .unwrap()
+            .squares()
+            .map(|(square, _)| square)
+            .collect();
+        assert_eq!(code.count(), crate::pairing::CODE_SQUARES);
+
+        sim.send(BleCommand::Pair { squares: code });
+        sim.step();
+        assert!(sim.display().last().unwrap().is_empty());
+        let token = sim
+            .notifications()
+            .iter()
+            .find_map(|n| match n {
+                Notification::PairingToken(token) => Some(*token),
+                _ => None,
+            })
+            .unwrap();
+        sim.send(BleCommand::SetClock { settings: None });
+        sim.step();
+
+        sim.send_from(1, start_human_game());
+        sim.disconnect(CLIENT);
+        sim.send(start_human_game());
+        sim.send(BleCommand::Authenticate { token });
+        sim.send(start_human_game());
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            [
+                CommandResult::success(CommandSource::MatchControl),
+                CommandResult::success(CommandSource::MatchControl),
+                CommandResult::success(CommandSource::MatchControl),
+                CommandResult::error(CommandSource::StartGame, ErrorCode::NotAuthenticated),
+                CommandResult::error(CommandSource::StartGame, ErrorCode::NotAuthenticated),
+                CommandResult::success(CommandSource::MatchControl),
+                CommandResult::success(CommandSource::StartGame),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrong_pairing_code_is_rejected() {
+        let mut sim = paired_sim();
+        sim.send(BleCommand::RequestPairing);
+        sim.step();
+        sim.send(BleCommand::Pair {
+            squares: Bitboard::from_rank(shakmaty::Rank::Fourth),
+        });
+        sim.step();
+        sim.send(start_human_game());
+        sim.step();
+
+        assert_eq!(
+            results(&sim)[1..],
+            [
+                CommandResult::error(CommandSource::MatchControl, ErrorCode::PairingFailed),
+                CommandResult::error(CommandSource::StartGame, ErrorCode::NotAuthenticated),
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_wrong_pairing_codes_lock_pairing() {
+        let mut sim = paired_sim();
+        for _ in 0..crate::pairing::MAX_FAILED_PAIRINGS {
+            sim.send(BleCommand::RequestPairing);
+            sim.step();
+            sim.send(BleCommand::Pair {
+                squares: Bitboard::EMPTY,
+            });
+            sim.step();
+        }
+        sim.send(BleCommand::RequestPairing);
+        sim.step();
+        assert_eq!(
+            results(&sim).last(),
+            Some(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::PairingLocked
+            ))
+        );
+
+        sim.run_for(crate::pairing::LOCKOUT);
+        sim.send(BleCommand::RequestPairing);
+        sim.step();
+        assert_eq!(
+            results(&sim).last(),
+            Some(&CommandResult::success(CommandSource::MatchControl))
+        );
+    }
+
+    // ── stats ───────────────────────────────────────────────────────
+
+    #[test]
+    fn finished_games_are_counted() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.push_script("f2 Wf3. e7 Be5. g2 Wg4. d8 Bh4.").unwrap();
+        sim.run_for(Duration::from_millis(300));
+
+        let stats = sim.app().stats();
+        assert_eq!(stats.games_played(), 1);
+        assert_eq!(stats.results().black_wins, 1);
+        assert_eq!(stats.average_plies(), Some(4));
+        assert_eq!(stats.top_openings(1), [("f2f3 e7e5 g2g4 d8h4", 1)]);
+    }
+
+    #[test]
+    fn cancelled_game_counts_as_aborted() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.send(BleCommand::CancelGame);
+        sim.step();
+
+        assert_eq!(sim.app().stats().results().aborted, 1);
+    }
+
+    // ── tournament ──────────────────────────────────────────────────
+
+    fn club_tournament() -> Tournament {
+        let players = ["Ann", "Bo", "Cy", "Di"].map(String::from).to_vec();
+        Tournament::new(players, crate::tournament::Format::RoundRobin).unwrap()
+    }
+
+    #[test]
+    fn finished_games_are_recorded_and_the_next_pairing_is_lit() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.app_mut().start_tournament(club_tournament());
+        sim.push_script("f2 Wf3. e7 Be5. g2 Wg4. d8 Bh4.").unwrap();
+        sim.run_for(Duration::from_millis(300));
+
+        let tournament = sim.app().tournament().unwrap();
+        assert_eq!(tournament.games()[0].result, Some(GameResult::BlackWins));
+        let next = tournament.announcement().unwrap();
+        let frame = sim.display().last().unwrap();
+        for (square, _) in next.squares() {
+            assert!(frame.get(square).is_some(), "{square} not lit");
+        }
+    }
+
+    #[test]
+    fn cancelled_games_are_not_recorded() {
+        let mut sim = started(PlayerType::Human, PlayerType::Human);
+        sim.app_mut().start_tournament(club_tournament());
+        sim.send(BleCommand::CancelGame);
+        sim.step();
+
+        let tournament = sim.app().tournament().unwrap();
+        assert!(tournament.games().iter().all(|game| game.result.is_none()));
+    }
+
+    // ── calibration ─────────────────────────────────────────────────
+
+    fn empty_board_sim() -> Simulation {
+        Simulation::with_sensor(
+            crate::testutil::ScriptedSensor::from_bitboards(Bitboard::EMPTY, Bitboard::EMPTY)
+                .unwrap(),
+        )
+    }
+
+    fn calibration_step(sim: &mut Simulation) {
+        sim.send(BleCommand::CalibrationStep);
+        sim.step();
+    }
+
+    #[test]
+    fn remote_calibration_applies_the_measured_thresholds() {
+        let mut sim = empty_board_sim();
+        sim.send(BleCommand::StartCalibration);
+        sim.step();
+        calibration_step(&mut sim);
+        let start = Chess::default();
+        sim.app_mut()
+            .sensor_mut()
+            .load_bitboards(
+                start.board().by_color(Color::White),
+                start.board().by_color(Color::Black),
+            )
+            .unwrap();
+        sim.step();
+        calibration_step(&mut sim);
+
+        assert_eq!(
+            results(&sim),
+            vec![CommandResult::success(CommandSource::MatchControl); 3]
+        );
+        assert_eq!(
+            sim.app_mut().sensor_mut().calibration(),
+            Some(crate::calibration::Calibration {
+                baseline_mv: 1440,
+                threshold_mv: 200,
+                square_baselines: [1440; 64],
+            })
+        );
+        assert_eq!(sim.app().status(), GameStatus::Idle);
+        assert!(sim.display().last().unwrap().is_empty());
+    }
+
+    #[test]
+    fn calibration_step_fails_on_pieces_left_on_the_board() {
+        let mut sim = Simulation::new();
+        sim.send(BleCommand::StartCalibration);
+        sim.step();
+        assert_eq!(
+            sim.display().last().unwrap().get(Square::E2),
+            Some(SquareFeedback::Capture)
+        );
+        calibration_step(&mut sim);
+
+        assert_eq!(
+            results(&sim).last(),
+            Some(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::CalibrationFailed
+            ))
+        );
+        assert_eq!(sim.app_mut().sensor_mut().calibration(), None);
+
+        sim.send(BleCommand::AbortCalibration);
+        sim.step();
+        calibration_step(&mut sim);
+        assert_eq!(
+            results(&sim).last(),
+            Some(&CommandResult::error(
+                CommandSource::MatchControl,
+                ErrorCode::InvalidCommand
+            ))
+        );
+    }
+
+    #[test]
+    fn self_test_lights_squares_that_do_not_read_empty() {
+        let mut sim = empty_board_sim();
+        sim.send(BleCommand::SelfTest);
+        sim.step();
+        sim.app_mut()
+            .sensor_mut()
+            .load_bitboards(Bitboard::from(Square::D4), Bitboard::EMPTY)
+            .unwrap();
+        sim.send(BleCommand::SelfTest);
+        sim.step();
+
+        assert_eq!(
+            results(&sim),
+            [
+                CommandResult::success(CommandSource::MatchControl),
+                CommandResult::error(CommandSource::MatchControl, ErrorCode::SelfTestFailed),
+            ]
+        );
+        let fb = sim.display().last().unwrap();
+        assert_eq!(
+            fb.squares().collect::<Vec<_>>(),
+            [(Square::D4, SquareFeedback::Capture)]
+        );
+        // The LEDs show the faulty square inside the failure ring.
+        let palette = Theme::default().palette();
+        let [(_, colors)] = sim.display().raw_frames() else {
+            panic!("expected one raw frame for the failure");
+        };
+        assert!(colors.contains(&(Square::D4, palette.capture)));
+        assert!(colors.contains(&(Square::C3, palette.status_failure)));
+        assert_eq!(colors.len(), 13);
+    }
+}
@@ -0,0 +1,332 @@
+//! Deciding between a gesture and a move when the board could be either.
+//!
+//! Gestures and moves are read from the same sensors, and a definition may
+//! well describe something a player does halfway through a move: lifting
+//! the king to tap it is also the start of a king move. [`GestureArbiter`]
+//! sits in front of a [`GestureRecognizer`] and keeps the two apart:
+//!
+//! - A move played restarts every gesture, so none completes from
+//!   readings taken before the position changed.
+//! - While the session is still finishing a move (a pending promotion or
+//!   an uncertain move awaiting confirmation), gestures wait.
+//! - A held gesture whose reading [`could_be_move`] is not shown for
+//!   [`MOVE_PRIORITY`], so move reading carries on; a move finished in
+//!   that time wins. Past it, or once the gesture is recognized, the
+//!   gesture takes over.
+
+use std::time::Duration;
+
+use shakmaty::{Bitboard, Board, ByColor, Chess, Color, Move, Position};
+
+use crate::gestures::{Gesture, GestureRecognizer, GestureSignal};
+use crate::move_index::captured_square;
+
+/// How long a gesture that could be a move in progress stays hidden.
+pub const MOVE_PRIORITY: Duration = Duration::from_secs(2);
+
+/// Whether `sensors` could be `position` partway through a legal move, or
+/// at its end: every piece missing is one the move lifts or captures, and
+/// every piece out of place stands where the move puts one. The position
+/// itself is not a move in progress.
+pub fn could_be_move(position: &Chess, sensors: ByColor<Bitboard>) -> bool {
+    let us = position.turn();
+    let board = position.board();
+    let missing = ByColor::new_with(|color| board.by_color(color) & !sensors[color]);
+    let extra = ByColor::new_with(|color| sensors[color] & !board.by_color(color));
+    let moved = missing.white | missing.black | extra.white | extra.black;
+    if moved.is_empty() || extra[!us].any() {
+        return false;
+    }
+    position.legal_moves().iter().any(|mv| {
+        let (lifts, lands) = footprint(mv, us);
+        let captured = captured_square(mv).map_or(Bitboard::EMPTY, Bitboard::from);
+        (missing[us] & !lifts).is_empty()
+            && (missing[!us] & !captured).is_empty()
+            && (extra[us] & !lands).is_empty()
+    })
+}
+
+/// Squares `mv` lifts the mover's pieces from and puts them on.
+fn footprint(mv: &Move, us: Color) -> (Bitboard, Bitboard) {
+    match (mv.castling_side(), mv.from()) {
+        (Some(side), Some(king)) => (
+            Bitboard::from(king) | Bitboard::from(mv.to()),
+            Bitboard::from(side.king_to(us)) | Bitboard::from(side.rook_to(us)),
+        ),
+        (_, from) => (
+            from.map_or(Bitboard::EMPTY, Bitboard::from),
+            Bitboard::from(mv.to()),
+        ),
+    }
+}
+
+/// A [`GestureRecognizer`] that gives way to moves.
+#[derive(Debug, Clone)]
+pub struct GestureArbiter<G = Gesture> {
+    recognizer: GestureRecognizer<G>,
+    /// The position gestures are under way on.
+    seen: Option<(Board, Color)>,
+    /// When the held gesture was first hidden as a possible move.
+    deferred_since: Option<Duration>,
+}
+
+impl GestureArbiter {
+    /// An arbiter for the board's own gestures.
+    pub fn new() -> Self {
+        Self::with_recognizer(GestureRecognizer::new())
+    }
+}
+
+impl Default for GestureArbiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G: Copy + std::fmt::Debug> GestureArbiter<G> {
+    pub fn with_recognizer(recognizer: GestureRecognizer<G>) -> Self {
+        Self {
+            recognizer,
+            seen: None,
+            deferred_since: None,
+        }
+    }
+
+    /// Advance with a reading of `position`. `move_pending` is whether the
+    /// session is still finishing a move. [`GestureSignal::Inactive`] means
+    /// the reading belongs to move reading.
+    pub fn update(
+        &mut self,
+        position: &Chess,
+        sensors: ByColor<Bitboard>,
+        move_pending: bool,
+        now: Duration,
+    ) -> GestureSignal<G> {
+        let current = (position.board().clone(), position.turn());
+        if self.seen.as_ref() != Some(&current) {
+            if self.seen.is_some() {
+                log::debug!("Position changed, restarting gestures");
+            }
+            self.recognizer.reset();
+            self.deferred_since = None;
+            self.seen = Some(current);
+        }
+        if move_pending {
+            self.recognizer.reset();
+            self.deferred_since = None;
+            return GestureSignal::Inactive;
+        }
+        let signal = self.recognizer.update(position, sensors, now);
+        if !matches!(signal, GestureSignal::Holding { .. }) || !could_be_move(position, sensors) {
+            self.deferred_since = None;
+            return signal;
+        }
+        let since = *self.deferred_since.get_or_insert(now);
+        if now.saturating_sub(since) < MOVE_PRIORITY {
+            GestureSignal::Inactive
+        } else {
+            signal
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gestures::{GestureDef, Pattern, SquareSet, Step};
+    use shakmaty::fen::Fen;
+    use shakmaty::{CastlingMode, Square};
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    /// Lift the white king and put it back within five seconds.
+    const KING_TAP: GestureDef<&str> = GestureDef::new(
+        "tap",
+        &[
+            Step::hold(
+                Pattern::Lifted(SquareSet::King(Color::White)),
+                Duration::ZERO,
+            ),
+            Step::hold(Pattern::InPlace, Duration::ZERO).within(Duration::from_secs(5)),
+        ],
+    );
+
+    /// Hold the white king off the board for three seconds.
+    const KING_HOLD: GestureDef<&str> = GestureDef::new(
+        "hold",
+        &[Step::hold(
+            Pattern::Lifted(SquareSet::King(Color::White)),
+            Duration::from_secs(3),
+        )],
+    );
+
+    fn arbiter(def: GestureDef<&'static str>) -> GestureArbiter<&'static str> {
+        GestureArbiter::with_recognizer(GestureRecognizer::with_definitions(&[def]))
+    }
+
+    /// 1. e4 e5, white to move: the king may step to e2.
+    fn open_king() -> Chess {
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap()
+    }
+
+    fn sensors(position: &Chess) -> ByColor<Bitboard> {
+        ByColor::new_with(|color| position.board().by_color(color))
+    }
+
+    fn lift(mut sensors: ByColor<Bitboard>, square: Square) -> ByColor<Bitboard> {
+        sensors.white.discard(square);
+        sensors.black.discard(square);
+        sensors
+    }
+
+    #[test]
+    fn readings_on_the_way_through_a_move_could_be_a_move() {
+        let position = open_king();
+        let board = sensors(&position);
+        let lifted = lift(board, Square::E1);
+        let landed = ByColor {
+            white: lifted.white | Bitboard::from(Square::E2),
+            ..lifted
+        };
+
+        assert!(could_be_move(&position, lifted));
+        assert!(could_be_move(&position, landed));
+        assert!(!could_be_move(&position, board), "nothing moved");
+        assert!(
+            !could_be_move(&position, lift(board, Square::E8)),
+            "black king lifted on white's turn"
+        );
+        assert!(
+            !could_be_move(
+                &Chess::default(),
+                lift(sensors(&Chess::default()), Square::E1)
+            ),
+            "a boxed-in king cannot move"
+        );
+        let ready_to_castle: Chess = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        let castling = ByColor {
+            white: Bitboard::from(Square::A1) | Bitboard::from(Square::G1),
+            ..sensors(&ready_to_castle)
+        };
+        assert!(
+            could_be_move(&ready_to_castle, castling),
+            "king on g1, rook still in hand"
+        );
+    }
+
+    #[test]
+    fn a_gesture_that_could_be_a_move_waits_for_the_move() {
+        let position = open_king();
+        let mut gestures = arbiter(KING_TAP);
+        let lifted = lift(sensors(&position), Square::E1);
+
+        assert_eq!(
+            gestures.update(&position, lifted, false, Duration::ZERO),
+            GestureSignal::Inactive
+        );
+        assert_eq!(
+            gestures.update(&position, lifted, false, SECOND),
+            GestureSignal::Inactive,
+            "left to move reading"
+        );
+        assert!(matches!(
+            gestures.update(&position, lifted, false, MOVE_PRIORITY),
+            GestureSignal::Holding { gesture: "tap", .. }
+        ));
+        assert_eq!(
+            gestures.update(&position, sensors(&position), false, MOVE_PRIORITY + SECOND),
+            GestureSignal::Recognized("tap")
+        );
+    }
+
+    #[test]
+    fn a_gesture_that_cannot_be_a_move_shows_at_once() {
+        let position = Chess::default();
+        let mut gestures = arbiter(KING_TAP);
+
+        assert!(matches!(
+            gestures.update(
+                &position,
+                lift(sensors(&position), Square::E1),
+                false,
+                Duration::ZERO
+            ),
+            GestureSignal::Holding { .. }
+        ));
+    }
+
+    #[test]
+    fn a_move_played_restarts_gestures() {
+        let position = open_king();
+        let mut gestures = arbiter(KING_TAP);
+        gestures.update(
+            &position,
+            lift(sensors(&position), Square::E1),
+            false,
+            Duration::ZERO,
+        );
+
+        let mv = Move::Normal {
+            role: shakmaty::Role::King,
+            from: Square::E1,
+            capture: None,
+            to: Square::E2,
+            promotion: None,
+        };
+        let moved = position.play(mv).unwrap();
+        assert_eq!(
+            gestures.update(&moved, sensors(&moved), false, SECOND),
+            GestureSignal::Inactive,
+            "the king landing on e2 is a move, not the tap"
+        );
+    }
+
+    #[test]
+    fn a_gesture_held_to_the_end_wins_over_a_possible_move() {
+        let position = open_king();
+        let mut gestures = arbiter(KING_HOLD);
+        let lifted = lift(sensors(&position), Square::E1);
+
+        gestures.update(&position, lifted, false, Duration::ZERO);
+        assert_eq!(
+            gestures.update(&position, lifted, false, 3 * SECOND),
+            GestureSignal::Recognized("hold")
+        );
+    }
+
+    #[test]
+    fn gestures_wait_while_a_move_is_unfinished() {
+        let position = Chess::default();
+        let mut gestures = arbiter(KING_HOLD);
+        let lifted = lift(sensors(&position), Square::E1);
+
+        assert_eq!(
+            gestures.update(&position, lifted, true, Duration::ZERO),
+            GestureSignal::Inactive
+        );
+        assert_eq!(
+            gestures.update(&position, lifted, true, 3 * SECOND),
+            GestureSignal::Inactive
+        );
+        assert!(matches!(
+            gestures.update(&position, lifted, false, 4 * SECOND),
+            GestureSignal::Holding {
+                elapsed: Duration::ZERO,
+                ..
+            }
+        ));
+        assert_eq!(
+            gestures.update(&position, lifted, false, 7 * SECOND),
+            GestureSignal::Recognized("hold"),
+            "the hold counts from when the move was finished"
+        );
+    }
+}
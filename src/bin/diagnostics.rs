@@ -430,7 +430,9 @@ fn main() {
     use esp_idf_svc::hal::adc::oneshot::AdcDriver;
     use esp_idf_svc::hal::peripherals::Peripherals;
     use esp_idf_svc::nvs::{EspNvsPartition, NvsCustom};
-    use unnamed_chess_project::esp32::config::{LedPalette, Rgb8, SensorCalibration, SensorConfig};
+    use unnamed_chess_project::esp32::config::{
+        LedPalette, Rgb8, SensorCalibration, SensorConfig, hardware_revision,
+    };
     use unnamed_chess_project::esp32::{Esp32LedDisplay, Esp32PieceSensor};
 
     esp_idf_svc::sys::link_patches();
@@ -440,7 +442,9 @@ fn main() {
 
     let adc_driver = AdcDriver::new(peripherals.adc1).expect("failed to init ADC1");
 
-    let sensor_config = SensorConfig::default();
+    let revision = hardware_revision();
+    log::info!("Hardware revision {}", revision.name());
+    let sensor_config = SensorConfig::for_revision(revision);
     let mut sensor = Esp32PieceSensor::new(
         &adc_driver,
         peripherals.pins.gpio4,
@@ -458,6 +462,7 @@ fn main() {
     let diag_palette = LedPalette {
         off: Rgb8::new(0, 0, 0),
         destination: Rgb8::new(15, 15, 15),
+        rook_destination: Rgb8::new(0, 0, 0),
         capture: Rgb8::new(15, 0, 15),
         origin: Rgb8::new(0, 0, 0),
         check: Rgb8::new(0, 0, 0),
@@ -480,6 +485,9 @@ fn main() {
     log::info!("--- Step 2: Empty board scan ---");
     let (baseline, noise_floor) = empty_board_scan(&mut sensor, &mut display);
 
+    log::info!("--- Scan timing (keep the board empty) ---");
+    scan_timing(&mut sensor);
+
     log::info!("--- Step 3: Starting position scan ---");
     let weakest_piece = starting_position_scan(&mut sensor, &mut display, baseline, noise_floor);
     log::info!("Weakest piece signal: {weakest_piece}mV deviation from baseline");
@@ -504,6 +512,7 @@ fn main() {
     let cal = SensorCalibration {
         baseline_mv: baseline,
         threshold_mv: threshold,
+        square_baselines: None,
     };
     match cal.save(&cal_partition) {
         Ok(()) => log::info!("  Saved to NVS."),
@@ -514,6 +523,35 @@ fn main() {
     diagnosis_loop(&mut sensor, baseline, threshold);
 }
 
+/// Time full scans at several oversampling levels on the empty board, then
+/// restore the configured level.
+#[cfg(target_os = "espidf")]
+fn scan_timing(sensor: &mut unnamed_chess_project::esp32::Esp32PieceSensor) {
+    use unnamed_chess_project::app::SystemClock;
+    use unnamed_chess_project::scan_bench::{self, ScanVariant};
+
+    let configured = sensor.samples();
+    let report = scan_bench::run(
+        &SystemClock::new(),
+        &ScanVariant::compared_with(configured),
+        sensor,
+        |sensor, variant| sensor.set_samples(variant.samples),
+        |sensor| sensor.read_raw().map(|scan| scan.mv),
+    );
+    sensor.set_samples(configured);
+    match report {
+        Ok(report) => {
+            for line in report.lines() {
+                log::info!("  {line}");
+            }
+            if let Some(quietest) = report.quietest() {
+                log::info!("  Quietest: {quietest} (configured: {configured} samples)");
+            }
+        }
+        Err(e) => log::warn!("  Scan timing failed: {e}"),
+    }
+}
+
 #[cfg(not(target_os = "espidf"))]
 fn main() {
     eprintln!("diagnostics binary is ESP32-only; nothing to do on host");
@@ -0,0 +1,116 @@
+//! Replay a sensor tick log captured from a board and print the moves this
+//! build of the engine detects.
+//!
+//! ```text
+//! replay-log [--matcher strict|settling] <serial-log|recording> [expected-moves]
+//! ```
+//!
+//! The log is either a serial capture with tick lines or a flight recorder
+//! ring file copied off the board's SD card, either of them optionally
+//! packed with `compress::compress` for archiving.
+//!
+//! `--matcher` picks the move-matching strategy (default `strict`), so a
+//! policy change can be tried on the same capture.
+//!
+//! `expected-moves` is a file of UCI moves separated by whitespace (e.g. the
+//! game as it was actually played). When given, the first ply where
+//! detection differs is reported and the exit code is 1. Check out another
+//! revision and run again to bisect a misdetection.
+
+#[cfg(not(target_os = "espidf"))]
+fn main() -> std::process::ExitCode {
+    use std::process::ExitCode;
+
+    use unnamed_chess_project::compress;
+    use unnamed_chess_project::flight_recorder::{is_recording, read_recording};
+    use unnamed_chess_project::player::MatcherKind;
+    use unnamed_chess_project::tick_log::{diff_moves, parse_log, replay};
+
+    const USAGE: &str =
+        "usage: replay-log [--matcher strict|settling] <serial-log|recording> [expected-moves]";
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut matcher = MatcherKind::Strict;
+    if args.first().is_some_and(|a| a == "--matcher") {
+        matcher = match args.get(1).map(String::as_str) {
+            Some("strict") => MatcherKind::Strict,
+            Some("settling") => MatcherKind::Settling,
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        };
+        args.drain(..2);
+    }
+    let (log_path, expected_path) = match args.as_slice() {
+        [log] => (log, None),
+        [log, expected] => (log, Some(expected)),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let read = |path: &str| std::fs::read(path).map_err(|e| eprintln!("cannot read {path}: {e}"));
+    let Ok(mut bytes) = read(log_path) else {
+        return ExitCode::from(2);
+    };
+    if compress::is_compressed(&bytes) {
+        bytes = match compress::decompress(&bytes) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                eprintln!("{log_path}: {e}");
+                return ExitCode::from(2);
+            }
+        };
+    }
+    let ticks = if is_recording(&bytes) {
+        read_recording(&bytes)
+            .map(|frames| frames.into_iter().map(|frame| frame.tick).collect())
+            .map_err(|e| e.to_string())
+    } else {
+        parse_log(&String::from_utf8_lossy(&bytes)).map_err(|e| e.to_string())
+    };
+    let ticks: Vec<_> = match ticks {
+        Ok(ticks) => ticks,
+        Err(e) => {
+            eprintln!("{log_path}: {e}");
+            return ExitCode::from(2);
+        }
+    };
+    let moves = replay(&ticks, matcher);
+    println!("{} ticks, {} moves ({matcher:?})", ticks.len(), moves.len());
+    println!("{}", moves.join(" "));
+
+    let Some(expected_path) = expected_path else {
+        return ExitCode::SUCCESS;
+    };
+    let Ok(expected) = read(expected_path) else {
+        return ExitCode::from(2);
+    };
+    let expected: Vec<String> = String::from_utf8_lossy(&expected)
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    match diff_moves(&expected, &moves) {
+        None => {
+            println!("matches expected moves");
+            ExitCode::SUCCESS
+        }
+        Some(diff) => {
+            let show = |mv: Option<String>| mv.unwrap_or_else(|| "(none)".to_string());
+            println!(
+                "differs at ply {}: expected {}, detected {}",
+                diff.ply + 1,
+                show(diff.expected),
+                show(diff.actual)
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(target_os = "espidf")]
+fn main() {
+    log::error!("replay-log is a desktop tool; run it with `just replay-log`");
+}
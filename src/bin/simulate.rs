@@ -0,0 +1,89 @@
+//! Replay the games of a PGN file through this build's move detection on a
+//! simulated board, and report every game where it loses track.
+//!
+//! ```text
+//! simulate [--matcher strict|settling] <games.pgn>
+//! ```
+//!
+//! Each move is turned into the sensor readings of a player making it (see
+//! `unnamed_chess_project::pgn_replay`) and fed to a game session. A game
+//! diverges at the first ply where the session detects another move or
+//! none; the exit code is 1 if any game did.
+
+#[cfg(not(target_os = "espidf"))]
+fn main() -> std::process::ExitCode {
+    use std::process::ExitCode;
+
+    use unnamed_chess_project::pgn_replay::{parse_pgn, replay_game};
+    use unnamed_chess_project::player::MatcherKind;
+
+    const USAGE: &str = "usage: simulate [--matcher strict|settling] <games.pgn>";
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut matcher = MatcherKind::Strict;
+    if args.first().is_some_and(|a| a == "--matcher") {
+        matcher = match args.get(1).map(String::as_str) {
+            Some("strict") => MatcherKind::Strict,
+            Some("settling") => MatcherKind::Settling,
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        };
+        args.drain(..2);
+    }
+    let [path] = args.as_slice() else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+
+    let text = match std::fs::read(path) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(e) => {
+            eprintln!("cannot read {path}: {e}");
+            return ExitCode::from(2);
+        }
+    };
+    let games = match parse_pgn(&text) {
+        Ok(games) => games,
+        Err(e) => {
+            eprintln!("{path}: {e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut plies = 0;
+    let mut diverged = 0;
+    for (i, game) in games.iter().enumerate() {
+        match replay_game(game, matcher) {
+            Ok(replayed) => plies += replayed,
+            Err(diff) => {
+                diverged += 1;
+                plies += diff.ply;
+                let show = |mv: Option<String>| mv.unwrap_or_else(|| "(none)".to_string());
+                println!(
+                    "game {} ({}): differs at ply {}: expected {}, detected {}",
+                    i + 1,
+                    game.title(),
+                    diff.ply + 1,
+                    show(diff.expected),
+                    show(diff.actual)
+                );
+            }
+        }
+    }
+    println!(
+        "{} games, {plies} plies replayed, {diverged} diverged ({matcher:?})",
+        games.len()
+    );
+    if diverged == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(target_os = "espidf")]
+fn main() {
+    log::error!("simulate is a desktop tool; run it with `just simulate`");
+}
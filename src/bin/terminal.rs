@@ -0,0 +1,512 @@
+//! Interactive board simulator on the desktop.
+//!
+//! ```text
+//! terminal            # full-screen board (feature `tui`)
+//! terminal --plain    # type square toggles and commands, one per line
+//! terminal dev <dir>  # re-run scenario files in <dir> whenever one is saved
+//! ```
+//!
+//! With the `tui` feature the terminal runs a ratatui UI: the board lit
+//! in the colors the LEDs would show, the move list, the clocks, and a log
+//! of detected moves, command output and log records. Arrow keys move a
+//! cursor, space toggles the square under it, `u` takes back a move, `h`
+//! lights up a hint, `:` types a command (see `terminal::HELP`) and `q`
+//! or Esc quits. A running clock ticks in real time.
+//!
+//! `--plain`, and the default without the feature, reads one line per
+//! step from stdin: square toggles or commands, and prints the board
+//! after each.
+//!
+//! `dev` watches `<dir>` for `*.board` scenarios (see
+//! `unnamed_chess_project::scenario`): each is played on a fresh board when
+//! it first appears and whenever it is saved, printing its final FEN and
+//! feedback, then only what changed.
+
+#[cfg(not(target_os = "espidf"))]
+fn main() {
+    use unnamed_chess_project::log_filter::LEVELS;
+
+    // Quiet until `log` turns a module up.
+    LEVELS.set_all(log::LevelFilter::Off);
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [dev, dir] if dev == "dev" => {
+            install_logger(StderrLogger);
+            watch_scenarios(dir);
+        }
+        [plain] if plain == "--plain" => run_plain_terminal(),
+        [] => run_terminal(),
+        _ => {
+            eprintln!("usage: terminal [--plain | dev <dir>]");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Route log records through the level filter to `logger`.
+#[cfg(not(target_os = "espidf"))]
+fn install_logger(logger: impl log::Log + 'static) {
+    use unnamed_chess_project::log_filter::FilteredLogger;
+
+    if let Err(e) = FilteredLogger::install(logger) {
+        eprintln!("terminal: {e}");
+    }
+}
+
+/// Prints log records under the board, for the `log` command.
+#[cfg(not(target_os = "espidf"))]
+struct StderrLogger;
+
+#[cfg(not(target_os = "espidf"))]
+impl log::Log for StderrLogger {
+    fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        eprintln!("{} {}: {}", record.level(), record.target(), record.args());
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(not(target_os = "espidf"))]
+fn watch_scenarios(dir: &str) {
+    use unnamed_chess_project::scenario::{Report, ScenarioWatcher};
+
+    const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(300);
+
+    let mut watcher = ScenarioWatcher::new(dir);
+    println!("watching {dir} for *.board scenarios (Ctrl-C to stop)");
+    loop {
+        let reports = match watcher.poll() {
+            Ok(reports) => reports,
+            Err(e) => {
+                eprintln!("terminal: {dir}: {e}");
+                std::process::exit(1);
+            }
+        };
+        for (path, report) in reports {
+            let name = path.display();
+            match report {
+                Report::New(lines) => println!("{name}:\n  {}", lines.join("\n  ")),
+                Report::Changed(diff) if diff.is_empty() => println!("{name}: unchanged"),
+                Report::Changed(diff) => println!("{name}:\n  {}", diff.join("\n  ")),
+                Report::Failed(e) => println!("{name}: error: {e}"),
+                Report::Removed => println!("{name}: removed"),
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(all(not(target_os = "espidf"), feature = "tui"))]
+fn run_terminal() {
+    let log = tui::LogPane::default();
+    install_logger(log.clone());
+    if let Err(e) = tui::run(log) {
+        eprintln!("terminal: {e}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(all(not(target_os = "espidf"), not(feature = "tui")))]
+fn run_terminal() {
+    run_plain_terminal();
+}
+
+#[cfg(not(target_os = "espidf"))]
+fn run_plain_terminal() {
+    install_logger(StderrLogger);
+    run_interactive_terminal(std::io::stdin().lock(), std::io::stdout());
+}
+
+#[cfg(not(target_os = "espidf"))]
+fn run_interactive_terminal(input: impl std::io::BufRead, mut output: impl std::io::Write) {
+    use unnamed_chess_project::terminal::{HELP, Terminal};
+
+    let mut terminal = Terminal::default();
+    let _ = writeln!(output, "{HELP}\n\n{}", terminal.render());
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if matches!(line.trim(), "quit" | "exit") {
+            break;
+        }
+        let _ = match terminal.execute(&line) {
+            Ok(text) => writeln!(output, "{text}"),
+            Err(e) => writeln!(output, "error: {e}"),
+        };
+    }
+}
+
+#[cfg(all(not(target_os = "espidf"), feature = "tui"))]
+mod tui {
+    use std::collections::VecDeque;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+    use ratatui::layout::{Constraint, Layout};
+    use ratatui::style::{Color as ScreenColor, Modifier, Style};
+    use ratatui::text::{Line, Span};
+    use ratatui::widgets::{Block, Paragraph};
+    use ratatui::{DefaultTerminal, Frame};
+    use shakmaty::san::SanPlus;
+    use shakmaty::{Color, Position, Square};
+    use unnamed_chess_project::board_api::GameStatus;
+    use unnamed_chess_project::feedback::SquareFeedback;
+    use unnamed_chess_project::frame::{LedPalette, Rgb8};
+    use unnamed_chess_project::terminal::{Terminal, TerminalError, format_clock};
+
+    const KEYS: &str = "arrows: move  space: toggle  u: undo  h: hint  :: command  q: quit";
+
+    /// Lines kept in the log pane.
+    const LOG_LEN: usize = 500;
+
+    /// Redraw at least this often, so a running clock ticks.
+    const TICK: Duration = Duration::from_millis(100);
+
+    /// Rank labels and eight squares of three characters, in a border.
+    const BOARD_WIDTH: u16 = 2 + 8 * 3 + 2;
+    /// Eight ranks and the file labels, in a border.
+    const BOARD_HEIGHT: u16 = 8 + 1 + 2;
+
+    const LIGHT_SQUARE: ScreenColor = ScreenColor::Rgb(170, 140, 110);
+    const DARK_SQUARE: ScreenColor = ScreenColor::Rgb(120, 90, 65);
+
+    /// The lines of the log pane, filled by the global logger too.
+    #[derive(Debug, Clone, Default)]
+    pub struct LogPane(Arc<Mutex<VecDeque<String>>>);
+
+    impl LogPane {
+        fn push(&self, line: impl Into<String>) {
+            if let Ok(mut lines) = self.0.lock() {
+                if lines.len() == LOG_LEN {
+                    lines.pop_front();
+                }
+                lines.push_back(line.into());
+            }
+        }
+
+        /// The last `count` lines, oldest first.
+        fn tail(&self, count: usize) -> Vec<String> {
+            self.0
+                .lock()
+                .map(|lines| {
+                    let skip = lines.len().saturating_sub(count);
+                    lines.iter().skip(skip).cloned().collect()
+                })
+                .unwrap_or_default()
+        }
+    }
+
+    impl log::Log for LogPane {
+        fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            self.push(format!(
+                "{} {}: {}",
+                record.level(),
+                record.target(),
+                record.args()
+            ));
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Run the UI until the user quits, restoring the terminal on the way
+    /// out.
+    pub fn run(log: LogPane) -> io::Result<()> {
+        let mut screen = ratatui::try_init()?;
+        let result = event_loop(&mut screen, log);
+        ratatui::restore();
+        result
+    }
+
+    fn event_loop(screen: &mut DefaultTerminal, log: LogPane) -> io::Result<()> {
+        let mut app = App {
+            terminal: Terminal::default(),
+            log,
+            cursor: Square::E2,
+            command: None,
+            palette: LedPalette::default(),
+        };
+        let mut last = Instant::now();
+        loop {
+            screen.draw(|frame| app.draw(frame))?;
+            if event::poll(TICK)?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && !app.handle(key.code)
+            {
+                return Ok(());
+            }
+            // Simulated time keeps up with the wall clock while a clock
+            // runs; `wait` still skips ahead.
+            let now = Instant::now();
+            if app.terminal.clock().is_some_and(|c| c.running().is_some()) {
+                app.terminal.wait(now - last);
+            }
+            last = now;
+        }
+    }
+
+    struct App {
+        terminal: Terminal,
+        log: LogPane,
+        cursor: Square,
+        /// The command being typed after `:`.
+        command: Option<String>,
+        /// The colors the board's LEDs would show.
+        palette: LedPalette,
+    }
+
+    impl App {
+        /// Handle a key press; `false` to quit.
+        fn handle(&mut self, key: KeyCode) -> bool {
+            if let Some(command) = &mut self.command {
+                match key {
+                    KeyCode::Enter => {
+                        let line = std::mem::take(command);
+                        self.command = None;
+                        if matches!(line.trim(), "quit" | "exit") {
+                            return false;
+                        }
+                        self.run_command(&line);
+                    }
+                    KeyCode::Esc => self.command = None,
+                    KeyCode::Backspace => {
+                        command.pop();
+                    }
+                    KeyCode::Char(c) => command.push(c),
+                    _ => {}
+                }
+                return true;
+            }
+            match key {
+                KeyCode::Left => self.move_cursor(-1, 0),
+                KeyCode::Right => self.move_cursor(1, 0),
+                KeyCode::Down => self.move_cursor(0, -1),
+                KeyCode::Up => self.move_cursor(0, 1),
+                KeyCode::Char(' ') => {
+                    let square = self.cursor;
+                    self.act(|terminal| {
+                        terminal.toggle_square(square);
+                        Ok(())
+                    });
+                }
+                KeyCode::Char('u') => {
+                    self.act(Terminal::undo);
+                }
+                KeyCode::Char('h') => {
+                    if let Some(mv) = self.act(Terminal::hint) {
+                        let san = SanPlus::from_move(self.terminal.position().clone(), mv);
+                        self.log.push(format!("hint {san}"));
+                    }
+                }
+                KeyCode::Char(':') => self.command = Some(String::new()),
+                KeyCode::Char('q') | KeyCode::Esc => return false,
+                _ => {}
+            }
+            true
+        }
+
+        fn move_cursor(&mut self, files: i32, ranks: i32) {
+            let (file, rank) = (self.cursor.file(), self.cursor.rank());
+            self.cursor = Square::from_coords(
+                file.offset(files).unwrap_or(file),
+                rank.offset(ranks).unwrap_or(rank),
+            );
+        }
+
+        /// Run a typed command, logging its output unless it is just the
+        /// board, which is on screen anyway.
+        fn run_command(&mut self, line: &str) {
+            self.log.push(format!(":{line}"));
+            if let Some(output) = self.act(|terminal| terminal.execute(line))
+                && output != self.terminal.render()
+            {
+                for line in output.lines() {
+                    self.log.push(line);
+                }
+            }
+        }
+
+        /// Run `action`, logging the moves it led to or its error.
+        fn act<T>(
+            &mut self,
+            action: impl FnOnce(&mut Terminal) -> Result<T, TerminalError>,
+        ) -> Option<T> {
+            let before = self.terminal.moves().to_vec();
+            let mut position = self.terminal.position().clone();
+            let result = action(&mut self.terminal);
+            if let Some(played) = self.terminal.moves().strip_prefix(before.as_slice()) {
+                for mv in played {
+                    let san = SanPlus::from_move_and_play_unchecked(&mut position, *mv);
+                    self.log.push(format!("played {san}"));
+                }
+            }
+            result
+                .map_err(|e| self.log.push(format!("error: {e}")))
+                .ok()
+        }
+
+        fn draw(&self, frame: &mut Frame<'_>) {
+            let [main, log, footer] = Layout::vertical([
+                Constraint::Length(BOARD_HEIGHT),
+                Constraint::Min(3),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+            let [board, side] =
+                Layout::horizontal([Constraint::Length(BOARD_WIDTH), Constraint::Min(20)])
+                    .areas(main);
+            let [game, moves] =
+                Layout::vertical([Constraint::Length(5), Constraint::Min(3)]).areas(side);
+            frame.render_widget(self.board(), board);
+            frame.render_widget(self.game(), game);
+            frame.render_widget(self.moves(moves.height.saturating_sub(2)), moves);
+            let lines: Vec<Line<'_>> = self
+                .log
+                .tail(usize::from(log.height.saturating_sub(2)))
+                .into_iter()
+                .map(Line::from)
+                .collect();
+            frame.render_widget(
+                Paragraph::new(lines).block(Block::bordered().title(" log ")),
+                log,
+            );
+            let footer_line = match &self.command {
+                Some(command) => Line::raw(format!(":{command}_")),
+                None => Line::styled(KEYS, Style::new().add_modifier(Modifier::DIM)),
+            };
+            frame.render_widget(footer_line, footer);
+        }
+
+        /// The shown board as [`Terminal::render`] has it, each square lit
+        /// in its LED color.
+        fn board(&self) -> Paragraph<'static> {
+            let view = self.terminal.view();
+            let mut lines = Vec::new();
+            for rank in (0..8).rev() {
+                let mut spans = vec![Span::raw(format!("{} ", rank + 1))];
+                for file in 0..8 {
+                    let square = Square::new(rank * 8 + file);
+                    let c = view.square_char(square);
+                    let shown = if c == '.' { ' ' } else { c };
+                    let mut style = self.square_style(square, c, view.feedback.get(square));
+                    let text = if square == self.cursor {
+                        style = style.add_modifier(Modifier::UNDERLINED);
+                        format!("[{shown}]")
+                    } else {
+                        format!(" {shown} ")
+                    };
+                    spans.push(Span::styled(text, style));
+                }
+                lines.push(Line::from(spans));
+            }
+            lines.push(Line::raw("   a  b  c  d  e  f  g  h"));
+            let title = if let Some(playback) = self.terminal.playback() {
+                format!(
+                    " recording {}/{} ",
+                    playback.cursor() + 1,
+                    playback.frames().len()
+                )
+            } else if let Some(snapshot) = self.terminal.rewound() {
+                format!(" reading {} ", snapshot.tick)
+            } else if self.terminal.is_setting_up() {
+                " setting up ".to_string()
+            } else {
+                " board ".to_string()
+            };
+            Paragraph::new(lines).block(Block::bordered().title(title))
+        }
+
+        fn square_style(&self, square: Square, c: char, lit: Option<SquareFeedback>) -> Style {
+            let background = match lit {
+                Some(feedback) => led_color(self.palette.square(feedback)),
+                None if square.is_light() => LIGHT_SQUARE,
+                None => DARK_SQUARE,
+            };
+            let foreground = match c {
+                '?' => ScreenColor::LightRed,
+                c if c.is_ascii_uppercase() => ScreenColor::White,
+                _ => ScreenColor::Black,
+            };
+            Style::new()
+                .bg(background)
+                .fg(foreground)
+                .add_modifier(Modifier::BOLD)
+        }
+
+        /// Whose move or how the game ended, then the clocks.
+        fn game(&self) -> Paragraph<'static> {
+            let status = match self.terminal.status() {
+                GameStatus::InProgress => {
+                    let turn = self.terminal.position().turn();
+                    format!("{} to move", turn.fold_wb("White", "Black"))
+                }
+                status => format!("{status:?}"),
+            };
+            let mut lines = vec![Line::raw(status)];
+            match self.terminal.clock() {
+                Some(clock) => {
+                    for color in [Color::White, Color::Black] {
+                        let remaining = clock.remaining(color, self.terminal.now());
+                        let style = if clock.running() == Some(color) {
+                            Style::new().add_modifier(Modifier::REVERSED)
+                        } else if remaining.is_zero() {
+                            Style::new().fg(ScreenColor::LightRed)
+                        } else {
+                            Style::new()
+                        };
+                        let name = color.fold_wb("White", "Black");
+                        lines.push(Line::styled(
+                            format!("{name} {}", format_clock(remaining)),
+                            style,
+                        ));
+                    }
+                }
+                None => lines.push(Line::raw("no clock (:clock 5+3)")),
+            }
+            Paragraph::new(lines).block(Block::bordered().title(" game "))
+        }
+
+        /// The last `count` full moves, one per line.
+        fn moves(&self, count: u16) -> Paragraph<'static> {
+            let mut lines: Vec<String> = Vec::new();
+            for word in self.terminal.move_list().split_whitespace() {
+                match lines.last_mut() {
+                    // Move numbers end in `.`, SAN never does.
+                    Some(line) if !word.ends_with('.') => {
+                        line.push(' ');
+                        line.push_str(word);
+                    }
+                    _ => lines.push(word.to_string()),
+                }
+            }
+            let shown = lines.split_off(lines.len().saturating_sub(usize::from(count)));
+            let shown: Vec<Line<'_>> = shown.into_iter().map(Line::from).collect();
+            Paragraph::new(shown).block(Block::bordered().title(" moves "))
+        }
+    }
+
+    /// `color` brightened for the screen; the board drives its LEDs dim.
+    fn led_color(color: Rgb8) -> ScreenColor {
+        let max = u16::from(color.r.max(color.g).max(color.b)).max(1);
+        let scale = |c: u8| (u16::from(c) * 255 / max) as u8;
+        ScreenColor::Rgb(scale(color.r), scale(color.g), scale(color.b))
+    }
+}
+
+#[cfg(target_os = "espidf")]
+fn main() {
+    log::error!("terminal is a desktop tool; run it with `just terminal`");
+}
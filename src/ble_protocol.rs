@@ -1,6 +1,16 @@
-use shakmaty::Color;
+use std::time::Duration;
 
+use log::LevelFilter;
+use shakmaty::{Bitboard, Color, Role, Square};
+
+use crate::adjudication::DrawReason;
 use crate::board_api;
+use crate::chess_clock::{ClockSettings, TimeControl, TimingMethod};
+use crate::log_filter::LogModule;
+use crate::minigames::MiniGame;
+use crate::mode::ModeSelection;
+use crate::pairing::{CODE_SQUARES, Token};
+use crate::settings::{AssistLevel, Setting, Theme};
 
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum ProtocolError {
@@ -12,6 +22,26 @@ pub enum ProtocolError {
     UnknownAction(u8),
     #[error("unknown color byte: 0x{0:02x}")]
     UnknownColor(u8),
+    #[error("unknown game result byte: 0x{0:02x}")]
+    UnknownResult(u8),
+    #[error("unknown promotion piece byte: 0x{0:02x}")]
+    UnknownPromotion(u8),
+    #[error("unknown mode byte: 0x{0:02x}")]
+    UnknownMode(u8),
+    #[error("invalid square byte: 0x{0:02x}")]
+    InvalidSquare(u8),
+    #[error("unknown calibration operation byte: 0x{0:02x}")]
+    UnknownCalibrationOp(u8),
+    #[error("unknown setting byte: 0x{0:02x}")]
+    UnknownSetting(u8),
+    #[error("invalid setting value byte: 0x{0:02x}")]
+    InvalidSettingValue(u8),
+    #[error("unknown timing method: {0}")]
+    UnknownTimingMethod(u8),
+    #[error("unknown log module byte: 0x{0:02x}")]
+    UnknownLogModule(u8),
+    #[error("invalid log level byte: 0x{0:02x}")]
+    InvalidLogLevel(u8),
 }
 
 /// Sentinel byte indicating a player slot has not yet been configured.
@@ -30,6 +60,9 @@ pub fn encode_player_type(pt: board_api::PlayerType) -> u8 {
     match pt {
         board_api::PlayerType::Human => 0x00,
         board_api::PlayerType::Remote => 0x01,
+        board_api::PlayerType::Random => 0x02,
+        board_api::PlayerType::Lichess => 0x03,
+        board_api::PlayerType::Relay => 0x04,
     }
 }
 
@@ -38,6 +71,9 @@ pub fn decode_player_type(byte: u8) -> Result<board_api::PlayerType, ProtocolErr
     match byte {
         0x00 => Ok(board_api::PlayerType::Human),
         0x01 => Ok(board_api::PlayerType::Remote),
+        0x02 => Ok(board_api::PlayerType::Random),
+        0x03 => Ok(board_api::PlayerType::Lichess),
+        0x04 => Ok(board_api::PlayerType::Relay),
         other => Err(ProtocolError::UnknownPlayerType(other)),
     }
 }
@@ -63,6 +99,22 @@ pub fn encode_color(color: Color) -> u8 {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Promotion piece helpers
+// ---------------------------------------------------------------------------
+
+/// Parse a promotion piece byte: `0x00` = queen, `0x01` = rook,
+/// `0x02` = bishop, `0x03` = knight.
+pub fn parse_promotion_role(byte: u8) -> Result<Role, ProtocolError> {
+    match byte {
+        0x00 => Ok(Role::Queen),
+        0x01 => Ok(Role::Rook),
+        0x02 => Ok(Role::Bishop),
+        0x03 => Ok(Role::Knight),
+        other => Err(ProtocolError::UnknownPromotion(other)),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // GameStatus encoding
 // ---------------------------------------------------------------------------
@@ -76,6 +128,9 @@ pub fn encode_color(color: Color) -> u8 {
 /// - `[0x03, color]`    – Checkmate (color = loser)
 /// - `[0x04]`           – Stalemate
 /// - `[0x05, color]`    – Resigned (color = resigning side)
+/// - `[0x06, color]`    – Timeout (color = loser)
+/// - `[0x07]`           – Draw
+/// - `[0x08]`           – Aborted
 pub fn encode_game_status(status: &board_api::GameStatus) -> Vec<u8> {
     match status {
         board_api::GameStatus::Idle => vec![0x00],
@@ -84,6 +139,46 @@ pub fn encode_game_status(status: &board_api::GameStatus) -> Vec<u8> {
         board_api::GameStatus::Checkmate { loser } => vec![0x03, encode_color(*loser)],
         board_api::GameStatus::Stalemate => vec![0x04],
         board_api::GameStatus::Resigned { color } => vec![0x05, encode_color(*color)],
+        board_api::GameStatus::Timeout { loser } => vec![0x06, encode_color(*loser)],
+        board_api::GameStatus::Draw => vec![0x07],
+        board_api::GameStatus::Aborted => vec![0x08],
+    }
+}
+
+/// Encode a [`board_api::GameEvent`] to its wire bytes.
+///
+/// Wire format:
+/// - `[0x00, color]`    – PlayerReady
+/// - `[0x01]`           – ClockStarted
+/// - `[0x02, squares]`  – BoardOutOfSync, `squares` as a u64 LE bitboard
+///   (bit `0` = a1 … bit `63` = h8)
+/// - `[0x03]`           – BoardInSync
+/// - `[0x04, color]`    – Flagged (color = side out of time)
+/// - `[0x05, reason]`   – DrawClaimable (reason: `0x00` repetition,
+///   `0x01` 50-move rule)
+/// - `[0x06, color]`    – ResignedOnBoard (color = resigning side)
+/// - `[0x07]`           – NewGameRequested
+pub fn encode_game_event(event: &board_api::GameEvent) -> Vec<u8> {
+    match event {
+        board_api::GameEvent::PlayerReady { color } => vec![0x00, encode_color(*color)],
+        board_api::GameEvent::ClockStarted => vec![0x01],
+        board_api::GameEvent::BoardOutOfSync { squares } => {
+            let mut bytes = vec![0x02];
+            bytes.extend_from_slice(&u64::from(*squares).to_le_bytes());
+            bytes
+        }
+        board_api::GameEvent::BoardInSync => vec![0x03],
+        board_api::GameEvent::Flagged { color } => vec![0x04, encode_color(*color)],
+        board_api::GameEvent::DrawClaimable { reason } => {
+            let reason = match reason {
+                DrawReason::ThreefoldRepetition | DrawReason::FivefoldRepetition => 0x00,
+                DrawReason::FiftyMoves | DrawReason::SeventyFiveMoves => 0x01,
+                DrawReason::DeadPosition => 0x02,
+            };
+            vec![0x05, reason]
+        }
+        board_api::GameEvent::ResignedOnBoard { color } => vec![0x06, encode_color(*color)],
+        board_api::GameEvent::NewGameRequested => vec![0x07],
     }
 }
 
@@ -104,9 +199,76 @@ pub enum BleCommand {
     Resign {
         color: Color,
     },
+    ReportResult {
+        result: board_api::ExternalResult,
+    },
+    ChoosePromotion {
+        role: Role,
+    },
+    StartMode {
+        mode: ModeSelection,
+    },
+    /// A player pressed their side of the clock.
+    PressClock {
+        color: Color,
+    },
+    /// Clock for the next game; `None` plays without a clock.
+    SetClock {
+        settings: Option<ClockSettings>,
+    },
+    /// Correspondence: if the remote opponent plays `trigger`, guide the
+    /// human to play `reply`.
+    AddConditional {
+        trigger: String,
+        reply: String,
+    },
+    /// Show a pairing code on the board.
+    RequestPairing,
+    /// Pair with the squares of the code shown on the board.
+    Pair {
+        squares: Bitboard,
+    },
+    /// Authenticate this connection with a token from an earlier pairing.
+    Authenticate {
+        token: Token,
+    },
+    /// Start sensor calibration (see [`crate::calibration`]).
+    StartCalibration,
+    /// Measure the current calibration step.
+    CalibrationStep,
+    /// Stop calibrating and keep the previous calibration.
+    AbortCalibration,
+    /// Check every square of an empty board.
+    SelfTest,
+    /// Change a preference, applied at once.
+    ChangeSetting {
+        setting: Setting,
+    },
+    /// Change how much a part of the firmware logs, or all of it if
+    /// `module` is `None`, until the next boot.
+    SetLogLevel {
+        module: Option<LogModule>,
+        level: LevelFilter,
+    },
+    /// Take back the last move; the board guides the pieces back.
+    TakeBack,
+    /// Apply a stored player profile until the next game ends (see
+    /// [`crate::profiles`]).
+    SelectProfile {
+        index: u8,
+    },
 }
 
 impl BleCommand {
+    /// The characteristic this command arrives on, echoed in its result.
+    pub fn source(&self) -> CommandSource {
+        match self {
+            BleCommand::StartGame { .. } => CommandSource::StartGame,
+            BleCommand::SubmitMove { .. } => CommandSource::SubmitMove,
+            _ => CommandSource::MatchControl,
+        }
+    }
+
     /// Parse a Start Game characteristic write.
     ///
     /// Format: `[white: u8, black: u8]`
@@ -139,6 +301,36 @@ impl BleCommand {
     /// Format: `[action: u8, ...]`
     /// - action `0x00` = resign → `[0x00, color: u8]`
     /// - action `0x01` = cancel game → `[0x01]`
+    /// - action `0x02` = report external result → `[0x02, result: u8, color?: u8]`
+    ///   - result `0x00` = resigned (color = resigning side)
+    ///   - result `0x01` = timeout (color = loser)
+    ///   - result `0x02` = draw (no color)
+    ///   - result `0x03` = aborted (no color)
+    /// - action `0x03` = choose promotion piece → `[0x03, piece: u8]`
+    ///   (see [`parse_promotion_role`])
+    /// - action `0x04` = start mode → `[0x04, mode: u8, arg?: u8]`
+    ///   (see [`parse_mode_selection`])
+    /// - action `0x05` = press clock → `[0x05, color: u8]`
+    /// - action `0x06` = set clock → `[0x06, minutes: u8, increment_secs: u8, flags: u8]`
+    ///   (see [`parse_clock_settings`])
+    /// - action `0x07` = add conditional move →
+    ///   `[0x07, len: u8, trigger_uci..., len: u8, reply_uci...]`
+    /// - action `0x08` = request pairing code → `[0x08]`
+    /// - action `0x09` = pair → `[0x09, square: u8, square: u8, square: u8]`
+    ///   (square index `0` = a1 … `63` = h8, in any order)
+    /// - action `0x0A` = authenticate → `[0x0A, token: u64 LE]`
+    /// - action `0x0B` = calibration → `[0x0B, op: u8]`
+    ///   - op `0x00` = start
+    ///   - op `0x01` = measure the current step
+    ///   - op `0x02` = abort
+    /// - action `0x0C` = self-test → `[0x0C]`
+    /// - action `0x0D` = change setting → `[0x0D, setting: u8, value: u8]`
+    ///   (see [`parse_setting`])
+    /// - action `0x0E` = set log level → `[0x0E, module: u8, level: u8]`
+    ///   (see [`parse_log_level`])
+    /// - action `0x0F` = take back the last move → `[0x0F]`
+    /// - action `0x10` = select player profile → `[0x10, index: u8]`
+    ///   (in the order of the config page)
     pub fn parse_match_control(bytes: &[u8]) -> Result<Self, ProtocolError> {
         if bytes.is_empty() {
             return Err(ProtocolError::InsufficientData { needed: 1, got: 0 });
@@ -157,11 +349,251 @@ impl BleCommand {
                 Ok(BleCommand::Resign { color })
             }
             0x01 => Ok(BleCommand::CancelGame),
+            0x02 => {
+                let result = parse_external_result(bytes)?;
+                Ok(BleCommand::ReportResult { result })
+            }
+            0x03 => {
+                // Choose promotion: [0x03, piece: u8]
+                if bytes.len() < 2 {
+                    return Err(ProtocolError::InsufficientData {
+                        needed: 2,
+                        got: bytes.len(),
+                    });
+                }
+                let role = parse_promotion_role(bytes[1])?;
+                Ok(BleCommand::ChoosePromotion { role })
+            }
+            0x04 => {
+                let mode = parse_mode_selection(bytes)?;
+                Ok(BleCommand::StartMode { mode })
+            }
+            0x05 => {
+                // Press clock: [0x05, color: u8]
+                if bytes.len() < 2 {
+                    return Err(ProtocolError::InsufficientData {
+                        needed: 2,
+                        got: bytes.len(),
+                    });
+                }
+                let color = parse_color(bytes[1])?;
+                Ok(BleCommand::PressClock { color })
+            }
+            0x06 => {
+                let settings = parse_clock_settings(bytes)?;
+                Ok(BleCommand::SetClock { settings })
+            }
+            0x07 => {
+                let (trigger, next) = read_length_prefixed_string(bytes, 1)?;
+                let (reply, _) = read_length_prefixed_string(bytes, next)?;
+                if trigger.is_empty() || reply.is_empty() {
+                    return Err(ProtocolError::InsufficientData { needed: 1, got: 0 });
+                }
+                Ok(BleCommand::AddConditional { trigger, reply })
+            }
+            0x08 => Ok(BleCommand::RequestPairing),
+            0x09 => {
+                let Some(code) = bytes.get(1..1 + CODE_SQUARES) else {
+                    return Err(ProtocolError::InsufficientData {
+                        needed: 1 + CODE_SQUARES,
+                        got: bytes.len(),
+                    });
+                };
+                let mut squares = Bitboard::EMPTY;
+                for &byte in code {
+                    if byte >= 64 {
+                        return Err(ProtocolError::InvalidSquare(byte));
+                    }
+                    squares.add(Square::new(u32::from(byte)));
+                }
+                Ok(BleCommand::Pair { squares })
+            }
+            0x0A => {
+                let Some(token) = bytes.get(1..9).and_then(|token| token.try_into().ok()) else {
+                    return Err(ProtocolError::InsufficientData {
+                        needed: 9,
+                        got: bytes.len(),
+                    });
+                };
+                let token = Token::from_le_bytes(token);
+                Ok(BleCommand::Authenticate { token })
+            }
+            0x0B => {
+                let Some(&op) = bytes.get(1) else {
+                    return Err(ProtocolError::InsufficientData {
+                        needed: 2,
+                        got: bytes.len(),
+                    });
+                };
+                match op {
+                    0x00 => Ok(BleCommand::StartCalibration),
+                    0x01 => Ok(BleCommand::CalibrationStep),
+                    0x02 => Ok(BleCommand::AbortCalibration),
+                    other => Err(ProtocolError::UnknownCalibrationOp(other)),
+                }
+            }
+            0x0C => Ok(BleCommand::SelfTest),
+            0x0D => {
+                let setting = parse_setting(bytes)?;
+                Ok(BleCommand::ChangeSetting { setting })
+            }
+            0x0E => {
+                let (module, level) = parse_log_level(bytes)?;
+                Ok(BleCommand::SetLogLevel { module, level })
+            }
+            0x0F => Ok(BleCommand::TakeBack),
+            0x10 => {
+                let Some(&index) = bytes.get(1) else {
+                    return Err(ProtocolError::InsufficientData {
+                        needed: 2,
+                        got: bytes.len(),
+                    });
+                };
+                Ok(BleCommand::SelectProfile { index })
+            }
             other => Err(ProtocolError::UnknownAction(other)),
         }
     }
 }
 
+/// Parse a Change Setting Match Control write: `[0x0D, setting: u8, value: u8]`.
+///
+/// - setting `0x00` = brightness, value `0`–`255`
+/// - setting `0x01` = theme, value `0x00` classic or `0x01` high contrast
+/// - setting `0x02` = assist level, value `0x00` full or `0x01` minimal
+pub fn parse_setting(bytes: &[u8]) -> Result<Setting, ProtocolError> {
+    let Some(&[setting, value]) = bytes.get(1..3) else {
+        return Err(ProtocolError::InsufficientData {
+            needed: 3,
+            got: bytes.len(),
+        });
+    };
+    match (setting, value) {
+        (0x00, level) => Ok(Setting::Brightness(level)),
+        (0x01, 0x00) => Ok(Setting::Theme(Theme::Classic)),
+        (0x01, 0x01) => Ok(Setting::Theme(Theme::HighContrast)),
+        (0x02, 0x00) => Ok(Setting::Assist(AssistLevel::Full)),
+        (0x02, 0x01) => Ok(Setting::Assist(AssistLevel::Minimal)),
+        (0x01 | 0x02, other) => Err(ProtocolError::InvalidSettingValue(other)),
+        (other, _) => Err(ProtocolError::UnknownSetting(other)),
+    }
+}
+
+/// Parse a Set Log Level Match Control write: `[0x0E, module: u8, level: u8]`.
+///
+/// - module `0x00` = sensor, `0x01` = engine, `0x02` = net, `0x03` =
+///   display, `0xFF` = everything
+/// - level `0x00` = off, `0x01` = error, `0x02` = warn, `0x03` = info,
+///   `0x04` = debug, `0x05` = trace
+pub fn parse_log_level(bytes: &[u8]) -> Result<(Option<LogModule>, LevelFilter), ProtocolError> {
+    let Some(&[module, level]) = bytes.get(1..3) else {
+        return Err(ProtocolError::InsufficientData {
+            needed: 3,
+            got: bytes.len(),
+        });
+    };
+    let module = match module {
+        0xFF => None,
+        index => Some(
+            *LogModule::ALL
+                .get(usize::from(index))
+                .ok_or(ProtocolError::UnknownLogModule(index))?,
+        ),
+    };
+    let level = LevelFilter::iter()
+        .nth(usize::from(level))
+        .ok_or(ProtocolError::InvalidLogLevel(level))?;
+    Ok((module, level))
+}
+
+/// Parse a Start Mode Match Control write: `[0x04, mode: u8, arg?: u8]`.
+///
+/// - mode `0x00` = coordinate training
+/// - mode `0x01` = knight's tour
+/// - mode `0x02` = pawn capture puzzle (arg = puzzle index)
+/// - mode `0x03` = analysis
+/// - mode `0x04` = checkers
+/// - mode `0x05` = heat map of the last finished game
+/// - mode `0x06` = notation training
+pub fn parse_mode_selection(bytes: &[u8]) -> Result<ModeSelection, ProtocolError> {
+    let insufficient = |needed| ProtocolError::InsufficientData {
+        needed,
+        got: bytes.len(),
+    };
+    let kind = *bytes.get(1).ok_or_else(|| insufficient(2))?;
+    match kind {
+        0x00 => Ok(ModeSelection::MiniGame(MiniGame::CoordinateTraining)),
+        0x01 => Ok(ModeSelection::MiniGame(MiniGame::KnightsTour)),
+        0x02 => {
+            let index = *bytes.get(2).ok_or_else(|| insufficient(3))?;
+            Ok(ModeSelection::MiniGame(MiniGame::PawnCapture(
+                index as usize,
+            )))
+        }
+        0x03 => Ok(ModeSelection::Analysis),
+        0x04 => Ok(ModeSelection::Checkers),
+        0x05 => Ok(ModeSelection::HeatMap),
+        0x06 => Ok(ModeSelection::MiniGame(MiniGame::NotationTraining)),
+        other => Err(ProtocolError::UnknownMode(other)),
+    }
+}
+
+/// Parse a Set Clock Match Control write:
+/// `[0x06, minutes: u8, increment_secs: u8, flags: u8]`.
+///
+/// `minutes = 0` turns the clock off. Flag bit 0 holds moves until the
+/// mover presses their clock; bits 1-2 pick the [`TimingMethod`] applied
+/// to `increment_secs` (`0` Fischer, `1` Bronstein, `2` simple delay).
+pub fn parse_clock_settings(bytes: &[u8]) -> Result<Option<ClockSettings>, ProtocolError> {
+    if bytes.len() < 4 {
+        return Err(ProtocolError::InsufficientData {
+            needed: 4,
+            got: bytes.len(),
+        });
+    }
+    let (minutes, increment, flags) = (bytes[1], bytes[2], bytes[3]);
+    if minutes == 0 {
+        return Ok(None);
+    }
+    let method = match (flags >> 1) & 0x03 {
+        0 => TimingMethod::Fischer,
+        1 => TimingMethod::Bronstein,
+        2 => TimingMethod::Delay,
+        other => return Err(ProtocolError::UnknownTimingMethod(other)),
+    };
+    Ok(Some(ClockSettings {
+        time_control: TimeControl {
+            initial: Duration::from_secs(u64::from(minutes) * 60),
+            increment: Duration::from_secs(u64::from(increment)),
+            method,
+        },
+        confirm_moves: flags & 0x01 != 0,
+    }))
+}
+
+/// Parse a Report Result Match Control write: `[0x02, result: u8, color?: u8]`.
+fn parse_external_result(bytes: &[u8]) -> Result<board_api::ExternalResult, ProtocolError> {
+    let insufficient = |needed| ProtocolError::InsufficientData {
+        needed,
+        got: bytes.len(),
+    };
+    let kind = *bytes.get(1).ok_or_else(|| insufficient(2))?;
+    let color = || {
+        bytes
+            .get(2)
+            .copied()
+            .ok_or_else(|| insufficient(3))
+            .and_then(parse_color)
+    };
+    match kind {
+        0x00 => Ok(board_api::ExternalResult::Resigned { color: color()? }),
+        0x01 => Ok(board_api::ExternalResult::Timeout { loser: color()? }),
+        0x02 => Ok(board_api::ExternalResult::Draw),
+        0x03 => Ok(board_api::ExternalResult::Aborted),
+        other => Err(ProtocolError::UnknownResult(other)),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // CommandResult
 // ---------------------------------------------------------------------------
@@ -183,6 +615,16 @@ pub enum ErrorCode {
     IllegalMove = 0x03,
     CannotResignForRemotePlayer = 0x04,
     InvalidCommand = 0x05,
+    NoRemotePlayer = 0x06,
+    NoPendingPromotion = 0x07,
+    NoPendingMove = 0x08,
+    PairingFailed = 0x09,
+    NotAuthenticated = 0x0A,
+    CalibrationFailed = 0x0B,
+    SelfTestFailed = 0x0C,
+    CannotTakeBack = 0x0D,
+    UnknownProfile = 0x0E,
+    PairingLocked = 0x0F,
 }
 
 /// The result of processing a BLE command.
@@ -244,6 +686,22 @@ pub fn encode_move(color: Color, uci: &str) -> Vec<u8> {
     out
 }
 
+/// Encode a played move followed by the checksum of the position after it.
+///
+/// Format: `[color: u8, uci_len: u8, uci_bytes..., hash: u32 LE, plies: u16 LE]`.
+/// The prefix is the same as [`encode_move`], so a reader that stops after
+/// the UCI still understands it.
+pub fn encode_move_with_checksum(
+    color: Color,
+    uci: &str,
+    checksum: board_api::StateChecksum,
+) -> Vec<u8> {
+    let mut out = encode_move(color, uci);
+    out.extend_from_slice(&checksum.hash.to_le_bytes());
+    out.extend_from_slice(&checksum.plies.to_le_bytes());
+    out
+}
+
 // ---------------------------------------------------------------------------
 // Internal helpers
 // ---------------------------------------------------------------------------
@@ -296,6 +754,9 @@ pub mod uuids {
     pub const POSITION: &str = "3d6343a2-1018-44ea-8fc2-3568d7216866";
     pub const LAST_MOVE: &str = "3d6343a2-1019-44ea-8fc2-3568d7216866";
     pub const MOVE_PLAYED: &str = "3d6343a2-101a-44ea-8fc2-3568d7216866";
+    pub const PENDING_PROMOTION: &str = "3d6343a2-101b-44ea-8fc2-3568d7216866";
+    pub const GAME_EVENT: &str = "3d6343a2-101c-44ea-8fc2-3568d7216866";
+    pub const PAIRING_TOKEN: &str = "3d6343a2-101d-44ea-8fc2-3568d7216866";
 }
 
 // ---------------------------------------------------------------------------
@@ -347,7 +808,13 @@ mod tests {
 
     #[test]
     fn player_type_encode_decode_roundtrip() {
-        for pt in [board_api::PlayerType::Human, board_api::PlayerType::Remote] {
+        for pt in [
+            board_api::PlayerType::Human,
+            board_api::PlayerType::Remote,
+            board_api::PlayerType::Random,
+            board_api::PlayerType::Lichess,
+            board_api::PlayerType::Relay,
+        ] {
             let encoded = encode_player_type(pt);
             let decoded = decode_player_type(encoded).expect("roundtrip should succeed");
             assert_eq!(decoded, pt);
@@ -464,6 +931,102 @@ mod tests {
         );
     }
 
+    #[test]
+    fn encode_game_status_timeout() {
+        assert_eq!(
+            encode_game_status(&board_api::GameStatus::Timeout {
+                loser: Color::Black
+            }),
+            vec![0x06, 0x01]
+        );
+    }
+
+    #[test]
+    fn encode_game_status_draw() {
+        assert_eq!(encode_game_status(&board_api::GameStatus::Draw), vec![0x07]);
+    }
+
+    #[test]
+    fn encode_game_status_aborted() {
+        assert_eq!(
+            encode_game_status(&board_api::GameStatus::Aborted),
+            vec![0x08]
+        );
+    }
+
+    // --- encode_game_event ---
+
+    #[test]
+    fn encode_game_event_player_ready() {
+        assert_eq!(
+            encode_game_event(&board_api::GameEvent::PlayerReady {
+                color: Color::Black
+            }),
+            vec![0x00, 0x01]
+        );
+    }
+
+    #[test]
+    fn encode_game_event_clock_started() {
+        assert_eq!(
+            encode_game_event(&board_api::GameEvent::ClockStarted),
+            vec![0x01]
+        );
+    }
+
+    #[test]
+    fn encode_game_event_board_sync() {
+        let squares = Bitboard::from(Square::A1) | Bitboard::from(Square::H8);
+        assert_eq!(
+            encode_game_event(&board_api::GameEvent::BoardOutOfSync { squares }),
+            vec![0x02, 0x01, 0, 0, 0, 0, 0, 0, 0x80]
+        );
+        assert_eq!(
+            encode_game_event(&board_api::GameEvent::BoardInSync),
+            vec![0x03]
+        );
+    }
+
+    #[test]
+    fn encode_game_event_flagged() {
+        assert_eq!(
+            encode_game_event(&board_api::GameEvent::Flagged {
+                color: Color::White
+            }),
+            vec![0x04, 0x00]
+        );
+    }
+
+    #[test]
+    fn encode_game_event_gestures() {
+        assert_eq!(
+            encode_game_event(&board_api::GameEvent::ResignedOnBoard {
+                color: Color::Black
+            }),
+            vec![0x06, 0x01]
+        );
+        assert_eq!(
+            encode_game_event(&board_api::GameEvent::NewGameRequested),
+            vec![0x07]
+        );
+    }
+
+    #[test]
+    fn encode_game_event_draw_claimable() {
+        assert_eq!(
+            encode_game_event(&board_api::GameEvent::DrawClaimable {
+                reason: DrawReason::ThreefoldRepetition
+            }),
+            vec![0x05, 0x00]
+        );
+        assert_eq!(
+            encode_game_event(&board_api::GameEvent::DrawClaimable {
+                reason: DrawReason::FiftyMoves
+            }),
+            vec![0x05, 0x01]
+        );
+    }
+
     // --- BleCommand::parse_start_game ---
 
     #[test]
@@ -636,8 +1199,386 @@ mod tests {
 
     #[test]
     fn reject_unknown_action() {
-        let result = BleCommand::parse_match_control(&[0x02, 0x00]);
-        assert!(matches!(result, Err(ProtocolError::UnknownAction(0x02))));
+        let result = BleCommand::parse_match_control(&[0x11, 0x00]);
+        assert!(matches!(result, Err(ProtocolError::UnknownAction(0x11))));
+    }
+
+    #[test]
+    fn parse_take_back() {
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0F]),
+            Ok(BleCommand::TakeBack)
+        );
+    }
+
+    #[test]
+    fn parse_select_profile() {
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x10, 0x02]),
+            Ok(BleCommand::SelectProfile { index: 2 })
+        );
+        assert!(matches!(
+            BleCommand::parse_match_control(&[0x10]),
+            Err(ProtocolError::InsufficientData { needed: 2, got: 1 })
+        ));
+    }
+
+    #[test]
+    fn parse_pairing_actions() {
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x08]),
+            Ok(BleCommand::RequestPairing)
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x09, 63, 0, 28]),
+            Ok(BleCommand::Pair {
+                squares: Bitboard::from(Square::H8) | Square::A1 | Square::E4
+            })
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x09, 0, 64, 1]),
+            Err(ProtocolError::InvalidSquare(64))
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0A, 1, 0, 0, 0, 0, 0, 0, 0x80]),
+            Ok(BleCommand::Authenticate {
+                token: 0x8000_0000_0000_0001
+            })
+        );
+        assert!(matches!(
+            BleCommand::parse_match_control(&[0x0A, 1, 2]),
+            Err(ProtocolError::InsufficientData { needed: 9, got: 3 })
+        ));
+    }
+
+    #[test]
+    fn parse_calibration_and_self_test_actions() {
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0B, 0x00]),
+            Ok(BleCommand::StartCalibration)
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0B, 0x01]),
+            Ok(BleCommand::CalibrationStep)
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0B, 0x02]),
+            Ok(BleCommand::AbortCalibration)
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0B, 0x03]),
+            Err(ProtocolError::UnknownCalibrationOp(0x03))
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0C]),
+            Ok(BleCommand::SelfTest)
+        );
+    }
+
+    #[test]
+    fn parse_change_setting() {
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0D, 0x00, 0x80]),
+            Ok(BleCommand::ChangeSetting {
+                setting: Setting::Brightness(0x80)
+            })
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0D, 0x01, 0x01]),
+            Ok(BleCommand::ChangeSetting {
+                setting: Setting::Theme(Theme::HighContrast)
+            })
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0D, 0x02, 0x01]),
+            Ok(BleCommand::ChangeSetting {
+                setting: Setting::Assist(AssistLevel::Minimal)
+            })
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0D, 0x02, 0x07]),
+            Err(ProtocolError::InvalidSettingValue(0x07))
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0D, 0x03, 0x00]),
+            Err(ProtocolError::UnknownSetting(0x03))
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0D, 0x00]),
+            Err(ProtocolError::InsufficientData { needed: 3, got: 2 })
+        );
+    }
+
+    #[test]
+    fn parse_set_log_level() {
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0E, 0x00, 0x05]),
+            Ok(BleCommand::SetLogLevel {
+                module: Some(LogModule::Sensor),
+                level: LevelFilter::Trace
+            })
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0E, 0xFF, 0x03]),
+            Ok(BleCommand::SetLogLevel {
+                module: None,
+                level: LevelFilter::Info
+            })
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0E, 0x04, 0x03]),
+            Err(ProtocolError::UnknownLogModule(0x04))
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x0E, 0x02, 0x06]),
+            Err(ProtocolError::InvalidLogLevel(0x06))
+        );
+    }
+
+    #[test]
+    fn parse_report_result_resigned() {
+        let result = BleCommand::parse_match_control(&[0x02, 0x00, 0x01]);
+        assert_eq!(
+            result,
+            Ok(BleCommand::ReportResult {
+                result: board_api::ExternalResult::Resigned {
+                    color: Color::Black
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn parse_report_result_timeout() {
+        let result = BleCommand::parse_match_control(&[0x02, 0x01, 0x00]);
+        assert_eq!(
+            result,
+            Ok(BleCommand::ReportResult {
+                result: board_api::ExternalResult::Timeout {
+                    loser: Color::White
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn parse_report_result_draw() {
+        let result = BleCommand::parse_match_control(&[0x02, 0x02]);
+        assert_eq!(
+            result,
+            Ok(BleCommand::ReportResult {
+                result: board_api::ExternalResult::Draw
+            })
+        );
+    }
+
+    #[test]
+    fn parse_report_result_aborted() {
+        let result = BleCommand::parse_match_control(&[0x02, 0x03]);
+        assert_eq!(
+            result,
+            Ok(BleCommand::ReportResult {
+                result: board_api::ExternalResult::Aborted
+            })
+        );
+    }
+
+    #[test]
+    fn reject_report_result_missing_kind() {
+        let result = BleCommand::parse_match_control(&[0x02]);
+        assert!(matches!(
+            result,
+            Err(ProtocolError::InsufficientData { needed: 2, got: 1 })
+        ));
+    }
+
+    #[test]
+    fn reject_report_result_missing_color() {
+        let result = BleCommand::parse_match_control(&[0x02, 0x01]);
+        assert!(matches!(
+            result,
+            Err(ProtocolError::InsufficientData { needed: 3, got: 2 })
+        ));
+    }
+
+    #[test]
+    fn reject_report_result_unknown_kind() {
+        let result = BleCommand::parse_match_control(&[0x02, 0x09]);
+        assert!(matches!(result, Err(ProtocolError::UnknownResult(0x09))));
+    }
+
+    #[test]
+    fn parse_choose_promotion() {
+        let result = BleCommand::parse_match_control(&[0x03, 0x03]);
+        assert_eq!(
+            result,
+            Ok(BleCommand::ChoosePromotion { role: Role::Knight })
+        );
+    }
+
+    #[test]
+    fn reject_choose_promotion_missing_piece() {
+        let result = BleCommand::parse_match_control(&[0x03]);
+        assert!(matches!(
+            result,
+            Err(ProtocolError::InsufficientData { needed: 2, got: 1 })
+        ));
+    }
+
+    #[test]
+    fn parse_promotion_role_rejects_king() {
+        assert_eq!(
+            parse_promotion_role(0x04),
+            Err(ProtocolError::UnknownPromotion(0x04))
+        );
+    }
+
+    #[test]
+    fn parse_start_mode_knights_tour() {
+        let result = BleCommand::parse_match_control(&[0x04, 0x01]);
+        assert_eq!(
+            result,
+            Ok(BleCommand::StartMode {
+                mode: ModeSelection::MiniGame(MiniGame::KnightsTour)
+            })
+        );
+    }
+
+    #[test]
+    fn parse_start_mode_pawn_puzzle() {
+        let result = BleCommand::parse_match_control(&[0x04, 0x02, 0x01]);
+        assert_eq!(
+            result,
+            Ok(BleCommand::StartMode {
+                mode: ModeSelection::MiniGame(MiniGame::PawnCapture(1))
+            })
+        );
+    }
+
+    #[test]
+    fn parse_start_mode_heat_map() {
+        let result = BleCommand::parse_match_control(&[0x04, 0x05]);
+        assert_eq!(
+            result,
+            Ok(BleCommand::StartMode {
+                mode: ModeSelection::HeatMap
+            })
+        );
+    }
+
+    #[test]
+    fn parse_start_mode_notation_training() {
+        let result = BleCommand::parse_match_control(&[0x04, 0x06]);
+        assert_eq!(
+            result,
+            Ok(BleCommand::StartMode {
+                mode: ModeSelection::MiniGame(MiniGame::NotationTraining)
+            })
+        );
+    }
+
+    #[test]
+    fn reject_start_mode_puzzle_without_index() {
+        let result = BleCommand::parse_match_control(&[0x04, 0x02]);
+        assert!(matches!(
+            result,
+            Err(ProtocolError::InsufficientData { needed: 3, got: 2 })
+        ));
+    }
+
+    #[test]
+    fn parse_start_mode_checkers() {
+        let result = BleCommand::parse_match_control(&[0x04, 0x04]);
+        assert_eq!(
+            result,
+            Ok(BleCommand::StartMode {
+                mode: ModeSelection::Checkers
+            })
+        );
+    }
+
+    #[test]
+    fn parse_press_clock() {
+        let result = BleCommand::parse_match_control(&[0x05, 0x01]);
+        assert_eq!(
+            result,
+            Ok(BleCommand::PressClock {
+                color: Color::Black
+            })
+        );
+    }
+
+    #[test]
+    fn parse_set_clock_with_confirmation() {
+        let result = BleCommand::parse_match_control(&[0x06, 5, 3, 0x01]);
+        assert_eq!(
+            result,
+            Ok(BleCommand::SetClock {
+                settings: Some(ClockSettings {
+                    time_control: TimeControl {
+                        initial: Duration::from_secs(300),
+                        increment: Duration::from_secs(3),
+                        method: TimingMethod::Fischer,
+                    },
+                    confirm_moves: true,
+                })
+            })
+        );
+    }
+
+    #[test]
+    fn parse_set_clock_timing_method() {
+        let result = BleCommand::parse_match_control(&[0x06, 5, 3, 0x04]);
+        let Ok(BleCommand::SetClock {
+            settings: Some(settings),
+        }) = result
+        else {
+            panic!("unexpected {result:?}");
+        };
+        assert_eq!(settings.time_control.method, TimingMethod::Delay);
+        assert!(!settings.confirm_moves);
+
+        assert_eq!(
+            BleCommand::parse_match_control(&[0x06, 5, 3, 0x06]),
+            Err(ProtocolError::UnknownTimingMethod(3))
+        );
+    }
+
+    #[test]
+    fn parse_set_clock_off() {
+        let result = BleCommand::parse_match_control(&[0x06, 0, 0, 0]);
+        assert_eq!(result, Ok(BleCommand::SetClock { settings: None }));
+    }
+
+    #[test]
+    fn reject_set_clock_too_short() {
+        let result = BleCommand::parse_match_control(&[0x06, 5]);
+        assert_eq!(
+            result,
+            Err(ProtocolError::InsufficientData { needed: 4, got: 2 })
+        );
+    }
+
+    #[test]
+    fn parse_add_conditional() {
+        let result = BleCommand::parse_match_control(b"\x07\x04e7e5\x04g1f3");
+        assert_eq!(
+            result,
+            Ok(BleCommand::AddConditional {
+                trigger: "e7e5".to_string(),
+                reply: "g1f3".to_string(),
+            })
+        );
+        assert_eq!(
+            BleCommand::parse_match_control(b"\x07\x04e7e5"),
+            Err(ProtocolError::InsufficientData { needed: 7, got: 6 })
+        );
+    }
+
+    #[test]
+    fn reject_unknown_mode() {
+        let result = BleCommand::parse_match_control(&[0x04, 0x09]);
+        assert!(matches!(result, Err(ProtocolError::UnknownMode(0x09))));
     }
 
     #[test]
@@ -724,6 +1665,19 @@ mod tests {
         assert_eq!(result.encode(), vec![0x01, 0x00, 0x05]);
     }
 
+    #[test]
+    fn encode_error_no_remote_player() {
+        let result = CommandResult::error(CommandSource::MatchControl, ErrorCode::NoRemotePlayer);
+        assert_eq!(result.encode(), vec![0x01, 0x01, 0x06]);
+    }
+
+    #[test]
+    fn encode_error_no_pending_promotion() {
+        let result =
+            CommandResult::error(CommandSource::MatchControl, ErrorCode::NoPendingPromotion);
+        assert_eq!(result.encode(), vec![0x01, 0x01, 0x07]);
+    }
+
     #[test]
     fn command_result_ok_field_set_correctly() {
         let ok = CommandResult::success(CommandSource::StartGame);
@@ -755,6 +1709,21 @@ mod tests {
         assert_eq!(encoded, vec![0x00, 5, b'e', b'7', b'e', b'8', b'q']);
     }
 
+    #[test]
+    fn encode_move_with_checksum_appends_hash_and_plies() {
+        let checksum = board_api::StateChecksum {
+            hash: 0xfd11_4196,
+            plies: 1,
+        };
+        let encoded = encode_move_with_checksum(Color::White, "e2e4", checksum);
+        assert_eq!(
+            encoded,
+            vec![
+                0x00, 4, b'e', b'2', b'e', b'4', 0x96, 0x41, 0x11, 0xfd, 0x01, 0x00
+            ]
+        );
+    }
+
     // --- UUID correctness ---
 
     #[test]
@@ -814,4 +1783,29 @@ mod tests {
     fn uuid_move_played() {
         assert_eq!(uuids::MOVE_PLAYED, "3d6343a2-101a-44ea-8fc2-3568d7216866");
     }
+
+    #[test]
+    fn uuid_game_event() {
+        assert_eq!(uuids::GAME_EVENT, "3d6343a2-101c-44ea-8fc2-3568d7216866");
+    }
+
+    // --- Malformed writes ---
+
+    #[test]
+    fn malformed_writes_are_errors_not_panics() {
+        use crate::testutil::malformed_inputs;
+
+        for action in 0..=u8::MAX {
+            for bytes in malformed_inputs(&[action, 0x01, 0x05, 0x02, 0x04, b'e', b'2'], 64, 24) {
+                let _ = BleCommand::parse_match_control(&bytes);
+                let _ = parse_mode_selection(&bytes);
+                let _ = parse_clock_settings(&bytes);
+                let _ = parse_setting(&bytes);
+            }
+        }
+        for bytes in malformed_inputs(&[4, b'e', b'2', b'e', b'4'], 512, 300) {
+            let _ = BleCommand::parse_start_game(&bytes);
+            let _ = BleCommand::parse_submit_move(&bytes);
+        }
+    }
 }
@@ -1,4 +1,7 @@
-use shakmaty::Color;
+use shakmaty::zobrist::Zobrist64;
+use shakmaty::{Bitboard, Chess, Color, EnPassantMode, Position};
+
+use crate::adjudication::DrawReason;
 
 /// The game lifecycle state.
 ///
@@ -17,17 +20,110 @@ pub enum GameStatus {
     Stalemate,
     /// A player resigned.
     Resigned { color: Color },
+    /// A player ran out of time on an external clock.
+    Timeout { loser: Color },
+    /// Drawn by agreement, by the remote side, or by automatic adjudication
+    /// (see [`crate::adjudication`]).
+    Draw,
+    /// Abandoned without a result.
+    Aborted,
 }
 
 impl GameStatus {
     pub fn is_terminal(&self) -> bool {
         matches!(
             self,
-            GameStatus::Checkmate { .. } | GameStatus::Stalemate | GameStatus::Resigned { .. }
+            GameStatus::Checkmate { .. }
+                | GameStatus::Stalemate
+                | GameStatus::Resigned { .. }
+                | GameStatus::Timeout { .. }
+                | GameStatus::Draw
+                | GameStatus::Aborted
         )
     }
 }
 
+/// A game result decided outside the board.
+///
+/// Reported by the client acting as the remote player when the game ends
+/// on its side, e.g. an online opponent resigns or a server flags a player.
+///
+/// Defined in `docs/board-api.md`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalResult {
+    Resigned { color: Color },
+    Timeout { loser: Color },
+    Draw,
+    Aborted,
+}
+
+impl ExternalResult {
+    /// The terminal [`GameStatus`] this result ends the game with.
+    pub fn status(self) -> GameStatus {
+        match self {
+            ExternalResult::Resigned { color } => GameStatus::Resigned { color },
+            ExternalResult::Timeout { loser } => GameStatus::Timeout { loser },
+            ExternalResult::Draw => GameStatus::Draw,
+            ExternalResult::Aborted => GameStatus::Aborted,
+        }
+    }
+}
+
+/// A one-off occurrence during a game, as opposed to a state change.
+///
+/// Defined in `docs/board-api.md`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    /// A player confirmed they are ready to start.
+    PlayerReady { color: Color },
+    /// Both players are ready; the side to move's clock is running.
+    ClockStarted,
+    /// The pieces stopped matching the game in a way no move explains;
+    /// `squares` need a piece put back or taken off.
+    BoardOutOfSync { squares: Bitboard },
+    /// The pieces match the game again.
+    BoardInSync,
+    /// `color`'s time ran out. The game status follows.
+    Flagged { color: Color },
+    /// The player to move may claim a draw; lifting both kings and putting
+    /// them back claims it.
+    DrawClaimable { reason: DrawReason },
+    /// `color` resigned by laying their king down (see
+    /// [`crate::gestures`]). The game status follows.
+    ResignedOnBoard { color: Color },
+    /// The players cleared both back ranks for a new game with the same
+    /// players; the status goes back to `AwaitingPieces`.
+    NewGameRequested,
+}
+
+/// Compact fingerprint of a position, sent with each move so a client
+/// tracking the game from `MovePlayed` can tell it no longer agrees with
+/// the board and re-read the position.
+///
+/// Defined in `docs/board-api.md`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateChecksum {
+    /// Low 32 bits of the position's Polyglot Zobrist hash.
+    pub hash: u32,
+    /// Half-moves since the start of the game, from the FEN move number.
+    pub plies: u16,
+}
+
+impl StateChecksum {
+    pub fn of(position: &Chess) -> Self {
+        let hash: u64 = position
+            .zobrist_hash::<Zobrist64>(EnPassantMode::Legal)
+            .into();
+        let plies = (position.fullmoves().get() - 1)
+            .saturating_mul(2)
+            .saturating_add(u32::from(position.turn().is_black()));
+        Self {
+            hash: hash as u32,
+            plies: u16::try_from(plies).unwrap_or(u16::MAX),
+        }
+    }
+}
+
 /// Determines how moves arrive for a given side.
 ///
 /// Defined in `docs/board-api.md`.
@@ -37,6 +133,19 @@ pub enum PlayerType {
     Human,
     /// Delivered via SubmitMove.
     Remote,
+    /// Random legal moves chosen by the board, for beginners.
+    Random,
+    /// An opponent on Lichess (see [`crate::net::lichess`]).
+    Lichess,
+    /// Another board, met through the relay (see [`crate::net::relay`]).
+    Relay,
+}
+
+impl PlayerType {
+    /// Whether the board finds this opponent online before the game.
+    pub fn is_online(self) -> bool {
+        matches!(self, PlayerType::Lichess | PlayerType::Relay)
+    }
 }
 
 /// Typed errors for Board API operations.
@@ -54,6 +163,10 @@ pub enum BoardApiError {
     IllegalMove,
     #[error("cannot resign for remote player")]
     CannotResignForRemotePlayer,
+    #[error("no remote player in this game")]
+    NoRemotePlayer,
+    #[error("no promotion waiting for a piece choice")]
+    NoPendingPromotion,
 }
 
 #[cfg(test)]
@@ -101,6 +214,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn external_results_are_terminal() {
+        assert!(
+            GameStatus::Timeout {
+                loser: Color::White
+            }
+            .is_terminal()
+        );
+        assert!(GameStatus::Draw.is_terminal());
+        assert!(GameStatus::Aborted.is_terminal());
+    }
+
+    #[test]
+    fn external_result_maps_to_status() {
+        assert_eq!(
+            ExternalResult::Resigned {
+                color: Color::Black
+            }
+            .status(),
+            GameStatus::Resigned {
+                color: Color::Black
+            }
+        );
+        assert_eq!(
+            ExternalResult::Timeout {
+                loser: Color::White
+            }
+            .status(),
+            GameStatus::Timeout {
+                loser: Color::White
+            }
+        );
+        assert_eq!(ExternalResult::Draw.status(), GameStatus::Draw);
+        assert_eq!(ExternalResult::Aborted.status(), GameStatus::Aborted);
+    }
+
     #[test]
     fn checkmate_carries_loser() {
         let status = GameStatus::Checkmate {
@@ -139,10 +288,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn checksum_is_the_polyglot_hash_and_ply_count() {
+        let start = StateChecksum::of(&Chess::default());
+        assert_eq!(
+            start,
+            StateChecksum {
+                hash: 0x1691_fc9c,
+                plies: 0
+            }
+        );
+
+        let mut after = Chess::default();
+        let mv = "e2e4"
+            .parse::<shakmaty::uci::UciMove>()
+            .unwrap()
+            .to_move(&after)
+            .unwrap();
+        after.play_unchecked(mv);
+        assert_eq!(
+            StateChecksum::of(&after),
+            StateChecksum {
+                hash: 0xfd11_4196,
+                plies: 1
+            }
+        );
+    }
+
     #[test]
     fn player_type_debug() {
         assert_eq!(format!("{:?}", PlayerType::Human), "Human");
         assert_eq!(format!("{:?}", PlayerType::Remote), "Remote");
+        assert_eq!(format!("{:?}", PlayerType::Random), "Random");
     }
 
     #[test]
@@ -161,5 +338,9 @@ mod tests {
             BoardApiError::CannotResignForRemotePlayer.to_string(),
             "cannot resign for remote player"
         );
+        assert_eq!(
+            BoardApiError::NoRemotePlayer.to_string(),
+            "no remote player in this game"
+        );
     }
 }
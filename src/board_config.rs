@@ -0,0 +1,548 @@
+//! Board preferences kept across restarts, and the web page that edits them.
+//!
+//! [`BoardConfig`] holds what the owner sets once: the LED theme, the clock
+//! for new games and which side sits at the near edge. The firmware stores
+//! it in NVS ([`BoardConfig::to_bytes`]) and applies it at boot with
+//! [`crate::app::BoardApp::apply_config`].
+//!
+//! `esp32::web_config` serves [`page`] on the board's address, and on the
+//! setup access point while WiFi is unprovisioned, so the board can be set
+//! up from a phone. A submitted form becomes a [`ConfigUpdate`]: the new
+//! config plus WiFi credentials, a Lichess token and a relay passphrase
+//! when those fields were filled in, a new [`Tournament`] when players
+//! were entered, and the player [`Profile`]s listed. Blank fields keep
+//! what is stored; the password, token and passphrase are never shown
+//! again. The page shows the running tournament's crosstable.
+
+use shakmaty::Color;
+
+use crate::chess_clock::{ClockSettings, TimeControl, TimingMethod};
+use crate::net::lichess::MAX_TOKEN_LEN;
+use crate::net::relay::{MAX_PASSPHRASE_LEN, MIN_PASSPHRASE_LEN};
+use crate::profiles::{MAX_PROFILES, Profile, ProfileError};
+use crate::settings::Theme;
+use crate::tournament::{Format, MAX_NAME_LEN, MAX_PLAYERS, Tournament, TournamentError};
+use crate::wifi::{CredentialError, MAX_PASSWORD_LEN, MAX_SSID_LEN, WifiCredentials, url_decode};
+
+/// Format version of [`BoardConfig::to_bytes`].
+const VERSION: u8 = 1;
+
+/// Length of [`BoardConfig::to_bytes`].
+pub const CONFIG_LEN: usize = 11;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ConfigError {
+    #[error(transparent)]
+    Wifi(#[from] CredentialError),
+    #[error("Lichess token must be at most {MAX_TOKEN_LEN} visible characters")]
+    Token,
+    #[error(
+        "relay passphrase must be {MIN_PASSPHRASE_LEN} to {MAX_PASSPHRASE_LEN} characters on one line"
+    )]
+    Passphrase,
+    #[error("unknown theme {0:?}")]
+    Theme(String),
+    #[error("clock must be off or MINUTES+INCREMENT (e.g. 5+3), not {0:?}")]
+    Clock(String),
+    #[error("near side must be white or black, not {0:?}")]
+    Orientation(String),
+    #[error(transparent)]
+    Tournament(#[from] TournamentError),
+    #[error(transparent)]
+    Profile(#[from] ProfileError),
+    #[error("stored config is {0} bytes, expected {CONFIG_LEN}")]
+    Length(usize),
+    #[error("stored config has unknown version {0}")]
+    Version(u8),
+    #[error("stored config is corrupt")]
+    Corrupt,
+}
+
+/// Preferences the owner sets once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardConfig {
+    pub theme: Theme,
+    /// Clock for new games; `None` plays without one.
+    pub clock: Option<TimeControl>,
+    /// The side sitting at the near edge of the board.
+    pub orientation: Color,
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            clock: None,
+            orientation: Color::White,
+        }
+    }
+}
+
+impl BoardConfig {
+    /// The clock for new games, moves counting without a press.
+    pub fn clock_settings(&self) -> Option<ClockSettings> {
+        self.clock.map(|time_control| ClockSettings {
+            time_control,
+            confirm_moves: false,
+        })
+    }
+
+    /// `[version, theme, orientation, clock?, initial_secs: u32 LE,
+    /// increment_secs: u16 LE, method]`; the clock fields are zero when
+    /// there is no clock.
+    pub fn to_bytes(&self) -> [u8; CONFIG_LEN] {
+        let mut bytes = [0; CONFIG_LEN];
+        bytes[0] = VERSION;
+        bytes[1] = Theme::ALL
+            .iter()
+            .position(|&theme| theme == self.theme)
+            .unwrap_or(0) as u8;
+        bytes[2] = u8::from(self.orientation == Color::Black);
+        if let Some(clock) = self.clock {
+            bytes[3] = 1;
+            let initial = u32::try_from(clock.initial.as_secs()).unwrap_or(u32::MAX);
+            let increment = u16::try_from(clock.increment.as_secs()).unwrap_or(u16::MAX);
+            bytes[4..8].copy_from_slice(&initial.to_le_bytes());
+            bytes[8..10].copy_from_slice(&increment.to_le_bytes());
+            bytes[10] = clock.method as u8;
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ConfigError> {
+        let bytes: &[u8; CONFIG_LEN] = bytes
+            .try_into()
+            .map_err(|_| ConfigError::Length(bytes.len()))?;
+        if bytes[0] != VERSION {
+            return Err(ConfigError::Version(bytes[0]));
+        }
+        let theme = *Theme::ALL
+            .get(usize::from(bytes[1]))
+            .ok_or(ConfigError::Corrupt)?;
+        let orientation = match bytes[2] {
+            0 => Color::White,
+            1 => Color::Black,
+            _ => return Err(ConfigError::Corrupt),
+        };
+        let clock = match bytes[3] {
+            0 => None,
+            1 => {
+                let initial = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+                let increment = u16::from_le_bytes([bytes[8], bytes[9]]);
+                let method = match bytes[10] {
+                    0 => TimingMethod::Fischer,
+                    1 => TimingMethod::Bronstein,
+                    2 => TimingMethod::Delay,
+                    _ => return Err(ConfigError::Corrupt),
+                };
+                if initial == 0 {
+                    return Err(ConfigError::Corrupt);
+                }
+                Some(TimeControl {
+                    initial: std::time::Duration::from_secs(u64::from(initial)),
+                    increment: std::time::Duration::from_secs(u64::from(increment)),
+                    method,
+                })
+            }
+            _ => return Err(ConfigError::Corrupt),
+        };
+        Ok(Self {
+            theme,
+            clock,
+            orientation,
+        })
+    }
+}
+
+/// A submitted config form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigUpdate {
+    pub config: BoardConfig,
+    /// New network, if a name was entered.
+    pub wifi: Option<WifiCredentials>,
+    /// New Lichess token, if one was entered.
+    pub lichess_token: Option<String>,
+    /// New relay passphrase, if one was entered.
+    pub relay_passphrase: Option<String>,
+    /// New tournament, if players were entered.
+    pub tournament: Option<Tournament>,
+    /// The player profiles, if the form listed them (an empty list
+    /// removes them all).
+    pub profiles: Option<Vec<Profile>>,
+}
+
+impl ConfigUpdate {
+    /// Read the urlencoded form of [`page`]. Fields left out keep their
+    /// value in `current`.
+    pub fn from_form(body: &str, current: BoardConfig) -> Result<Self, ConfigError> {
+        let mut config = current;
+        let mut ssid = String::new();
+        let mut password = String::new();
+        let mut lichess_token = None;
+        let mut relay_passphrase = None;
+        let mut players = Vec::new();
+        let mut format = Format::RoundRobin;
+        let mut profiles = None;
+        for pair in body.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = url_decode(value);
+            let value = value.trim();
+            match key {
+                "ssid" => ssid = value.to_string(),
+                "password" => password = value.to_string(),
+                "lichess_token" if !value.is_empty() => {
+                    if value.len() > MAX_TOKEN_LEN || !value.bytes().all(|b| b.is_ascii_graphic()) {
+                        return Err(ConfigError::Token);
+                    }
+                    lichess_token = Some(value.to_string());
+                }
+                "relay_passphrase" if !value.is_empty() => {
+                    if value.chars().count() < MIN_PASSPHRASE_LEN
+                        || value.len() > MAX_PASSPHRASE_LEN
+                        || value.chars().any(char::is_control)
+                    {
+                        return Err(ConfigError::Passphrase);
+                    }
+                    relay_passphrase = Some(value.to_string());
+                }
+                "theme" => {
+                    config.theme =
+                        Theme::from_name(value).ok_or_else(|| ConfigError::Theme(value.into()))?;
+                }
+                "clock" => {
+                    config.clock = match value {
+                        "" | "off" => None,
+                        _ => Some(
+                            TimeControl::parse(value)
+                                .ok_or_else(|| ConfigError::Clock(value.into()))?,
+                        ),
+                    };
+                }
+                "orientation" => {
+                    config.orientation = match value {
+                        "white" => Color::White,
+                        "black" => Color::Black,
+                        _ => return Err(ConfigError::Orientation(value.into())),
+                    };
+                }
+                "players" => {
+                    players = value
+                        .lines()
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                }
+                "format" if !value.is_empty() => format = Format::parse(value)?,
+                "profiles" => profiles = Some(Profile::parse_list(value)?),
+                _ => {}
+            }
+        }
+        let wifi = if ssid.is_empty() {
+            None
+        } else {
+            Some(WifiCredentials::new(&ssid, &password)?)
+        };
+        let tournament = if players.is_empty() {
+            None
+        } else {
+            Some(Tournament::new(players, format)?)
+        };
+        Ok(Self {
+            config,
+            wifi,
+            lichess_token,
+            relay_passphrase,
+            tournament,
+            profiles,
+        })
+    }
+}
+
+/// What the page reports beside the config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageStatus {
+    /// The network the board joins, if any.
+    pub network: Option<String>,
+    pub lichess_linked: bool,
+    /// A relay passphrase is stored.
+    pub relay_joined: bool,
+    /// Outcome of the last submission.
+    pub message: Option<String>,
+    /// [`Tournament::crosstable`] of the tournament being played, if any.
+    pub crosstable: Option<String>,
+    /// The stored player profiles, listed for editing.
+    pub profiles: Vec<Profile>,
+}
+
+/// The config page, filled in with `config`.
+pub fn page(config: &BoardConfig, status: &PageStatus) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+<meta name=\"viewport\" content=\"width=device-width\"><title>Chess board</title></head>\
+<body><h1>Chess board</h1>",
+    );
+    if let Some(message) = &status.message {
+        html += &format!("<p><strong>{}</strong></p>", escape(message));
+    }
+    html += "<form method=\"post\" action=\"/\"><h2>WiFi</h2>";
+    html += &match &status.network {
+        Some(network) => format!("<p>Joins {}. Leave blank to keep it.</p>", escape(network)),
+        None => "<p>No network set.</p>".to_string(),
+    };
+    html += &format!(
+        "<p><label>Network <input name=\"ssid\" maxlength=\"{MAX_SSID_LEN}\"></label></p>\
+<p><label>Password <input name=\"password\" type=\"password\" maxlength=\"{MAX_PASSWORD_LEN}\"></label></p>\
+<h2>Lichess</h2><p>{}</p>\
+<p><label>API token <input name=\"lichess_token\" type=\"password\" maxlength=\"{MAX_TOKEN_LEN}\"></label></p>\
+<h2>Relay</h2><p>{} Boards with the same passphrase play each other.</p>\
+<p><label>Passphrase <input name=\"relay_passphrase\" type=\"password\" minlength=\"{MIN_PASSPHRASE_LEN}\" maxlength=\"{MAX_PASSPHRASE_LEN}\"></label></p>\
+<h2>Board</h2><p><label>Theme <select name=\"theme\">",
+        if status.lichess_linked {
+            "A token is stored. Leave blank to keep it."
+        } else {
+            "No token stored."
+        },
+        if status.relay_joined {
+            "A passphrase is stored. Leave blank to keep it."
+        } else {
+            "No passphrase stored."
+        }
+    );
+    for theme in Theme::ALL {
+        html += &option(theme.name(), theme == config.theme);
+    }
+    html += &format!(
+        "</select></label></p>\
+<p><label>Clock <input name=\"clock\" value=\"{}\" placeholder=\"off, or 5+3\"></label></p>\
+<p><label>Near side <select name=\"orientation\">",
+        config
+            .clock
+            .map_or("off".to_string(), |clock| clock.to_string())
+    );
+    html += &option("white", config.orientation == Color::White);
+    html += &option("black", config.orientation == Color::Black);
+    html += "</select></label></p><h2>Tournament</h2>";
+    html += &match &status.crosstable {
+        Some(crosstable) => format!(
+            "<pre>{}</pre><p><a href=\"/crosstable\">Crosstable as text</a>. \
+Entering players starts a new tournament.</p>",
+            escape(crosstable)
+        ),
+        None => "<p>No tournament running.</p>".to_string(),
+    };
+    html += &format!(
+        "<p><label>Players, one per line, in seed order \
+<textarea name=\"players\" rows=\"4\" placeholder=\"up to {MAX_PLAYERS}, {MAX_NAME_LEN} characters each\">\
+</textarea></label></p>\
+<p><label>Format <input name=\"format\" value=\"round-robin\" placeholder=\"round-robin, or swiss 5\"></label></p>\
+<h2>Players</h2><p>Up to {MAX_PROFILES} profiles, one per line, applied when a player is \
+selected for a game: NAME: theme, full or minimal assist, clock (off or 5+3), near side.</p>\
+<p><textarea name=\"profiles\" rows=\"4\" placeholder=\"Ann: high-contrast minimal 5+3 black\">"
+    );
+    for profile in &status.profiles {
+        html += &escape(&format!("{profile}\n"));
+    }
+    html += "</textarea></p><p><button>Save</button></p></form></body></html>";
+    html
+}
+
+fn option(value: &str, selected: bool) -> String {
+    let selected = if selected { " selected" } else { "" };
+    format!("<option value=\"{value}\"{selected}>{value}</option>")
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::malformed_inputs;
+    use std::time::Duration;
+
+    fn rapid() -> BoardConfig {
+        BoardConfig {
+            theme: Theme::HighContrast,
+            clock: TimeControl::parse("10+5b"),
+            orientation: Color::Black,
+        }
+    }
+
+    #[test]
+    fn config_round_trips_through_storage() {
+        for config in [BoardConfig::default(), rapid()] {
+            assert_eq!(BoardConfig::from_bytes(&config.to_bytes()), Ok(config));
+        }
+        assert_eq!(
+            BoardConfig::from_bytes(&[VERSION]),
+            Err(ConfigError::Length(1))
+        );
+        let mut future = rapid().to_bytes();
+        future[0] = 2;
+        assert_eq!(
+            BoardConfig::from_bytes(&future),
+            Err(ConfigError::Version(2))
+        );
+    }
+
+    #[test]
+    fn the_form_updates_what_was_filled_in() {
+        let update = ConfigUpdate::from_form(
+            "ssid=Home+Net&password=p%40ss&lichess_token=lip_abc\
+&relay_passphrase=correct+horse+battery&theme=high-contrast&clock=10%2B5b&orientation=black",
+            BoardConfig::default(),
+        )
+        .unwrap();
+
+        assert_eq!(update.config, rapid());
+        assert_eq!(
+            update.wifi,
+            Some(WifiCredentials::new("Home Net", "p@ss").unwrap())
+        );
+        assert_eq!(update.lichess_token.as_deref(), Some("lip_abc"));
+        assert_eq!(
+            update.relay_passphrase.as_deref(),
+            Some("correct horse battery")
+        );
+        assert_eq!(update.tournament, None);
+
+        let kept = ConfigUpdate::from_form(
+            "ssid=&password=&lichess_token=&relay_passphrase=&clock=off",
+            rapid(),
+        )
+        .unwrap();
+        assert_eq!(kept.wifi, None);
+        assert_eq!(kept.lichess_token, None);
+        assert_eq!(kept.relay_passphrase, None);
+        assert_eq!(
+            kept.config,
+            BoardConfig {
+                clock: None,
+                ..rapid()
+            }
+        );
+    }
+
+    #[test]
+    fn bad_fields_are_rejected() {
+        let current = BoardConfig::default();
+        let cases = [
+            ("clock=soon", ConfigError::Clock("soon".into())),
+            ("theme=neon", ConfigError::Theme("neon".into())),
+            ("orientation=red", ConfigError::Orientation("red".into())),
+            ("lichess_token=a+b", ConfigError::Token),
+            ("relay_passphrase=too+short", ConfigError::Passphrase),
+            (
+                "ssid=x&password=0123456789012345678901234567890123456789012345678901234567890123456789",
+                ConfigError::Wifi(CredentialError::Password),
+            ),
+            (
+                "players=Ann&format=round-robin",
+                ConfigError::Tournament(TournamentError::PlayerCount(1)),
+            ),
+            (
+                "players=Ann%0D%0ABo&format=knockout",
+                ConfigError::Tournament(TournamentError::Format("knockout".into())),
+            ),
+        ];
+        for (form, error) in cases {
+            assert_eq!(ConfigUpdate::from_form(form, current), Err(error), "{form}");
+        }
+    }
+
+    #[test]
+    fn entered_players_start_a_tournament() {
+        let update = ConfigUpdate::from_form(
+            "players=Ann+Lee%0D%0A%0D%0ABo%0D%0ACy+&format=swiss+2",
+            BoardConfig::default(),
+        )
+        .unwrap();
+
+        let tournament = update.tournament.unwrap();
+        assert_eq!(tournament.players(), ["Ann Lee", "Bo", "Cy"]);
+        assert_eq!(tournament.format(), Format::Swiss { rounds: 2 });
+    }
+
+    #[test]
+    fn listed_profiles_replace_the_stored_ones() {
+        let update = ConfigUpdate::from_form(
+            "profiles=Ann%3A+minimal+5%2B3%0D%0ABo%3A+black%0D%0A",
+            BoardConfig::default(),
+        )
+        .unwrap();
+        let names: Vec<_> = update
+            .profiles
+            .unwrap()
+            .into_iter()
+            .map(|profile| profile.name)
+            .collect();
+        assert_eq!(names, ["Ann", "Bo"]);
+
+        let cleared = ConfigUpdate::from_form("profiles=", BoardConfig::default()).unwrap();
+        assert_eq!(cleared.profiles, Some(Vec::new()));
+        let kept = ConfigUpdate::from_form("theme=classic", BoardConfig::default()).unwrap();
+        assert_eq!(kept.profiles, None);
+        assert_eq!(
+            ConfigUpdate::from_form("profiles=Ann%3A+neon", BoardConfig::default()),
+            Err(ConfigError::Profile(ProfileError::Preference(
+                "neon".into()
+            )))
+        );
+    }
+
+    #[test]
+    fn the_page_shows_the_config_and_hides_secrets() {
+        let status = PageStatus {
+            network: Some("<Home>".into()),
+            lichess_linked: true,
+            relay_joined: false,
+            message: Some("Saved".into()),
+            crosstable: Some("1. Ann <3>".into()),
+            profiles: Profile::parse_list("Ann: black").unwrap(),
+        };
+        let html = page(&rapid(), &status);
+
+        assert!(html.contains("<option value=\"high-contrast\" selected>"));
+        assert!(html.contains("<option value=\"black\" selected>"));
+        assert!(html.contains("value=\"10+5b\""));
+        assert!(html.contains("Joins &lt;Home&gt;."));
+        assert!(html.contains("A token is stored."));
+        assert!(html.contains("No passphrase stored."));
+        assert!(html.contains("<pre>1. Ann &lt;3&gt;</pre>"));
+        assert!(html.contains(">Ann: classic full off black\n</textarea>"));
+        assert!(page(&BoardConfig::default(), &PageStatus::default()).contains("value=\"off\""));
+    }
+
+    #[test]
+    fn malformed_input_does_not_panic() {
+        for bytes in malformed_inputs(&rapid().to_bytes(), 256, 24) {
+            let _ = BoardConfig::from_bytes(&bytes);
+        }
+        let form = b"ssid=a&password=b&theme=classic&clock=5%2B3&orientation=white";
+        for bytes in malformed_inputs(form, 256, 80) {
+            let _ = ConfigUpdate::from_form(&String::from_utf8_lossy(&bytes), rapid());
+        }
+        assert_eq!(
+            BoardConfig::default().clock_settings(),
+            None,
+            "no clock by default"
+        );
+        assert_eq!(
+            rapid().clock_settings().map(|c| c.time_control.increment),
+            Some(Duration::from_secs(5))
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! Staged startup with progress on the LEDs.
+//!
+//! The firmware brings the board up in [`BootStage`] order and records how
+//! each stage went in a [`BootReport`]. Only the sensors are essential:
+//! without LEDs the board still plays and reports moves to clients,
+//! without BLE or WiFi it plays offline, and without NVS it starts a fresh
+//! game. Once the LEDs are up, [`BootReport::colors`] shows one square per
+//! stage along the first rank, so a board that comes up degraded says
+//! which part failed.
+
+use std::time::Duration;
+
+use shakmaty::Square;
+
+use crate::frame::{LedPalette, Rgb8};
+
+/// How long the progress stays up after a degraded boot, before the game
+/// takes over the LEDs.
+pub const DEGRADED_HOLD: Duration = Duration::from_secs(3);
+
+/// Startup steps, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootStage {
+    /// Hardware revision and sensor calibration.
+    Config,
+    Sensors,
+    Leds,
+    /// The BLE link to the companion app.
+    Clients,
+    /// Resuming a game saved before the last power cut.
+    GameRestore,
+    /// WiFi, for online play and network time.
+    Network,
+}
+
+impl BootStage {
+    pub const ALL: [Self; 6] = [
+        Self::Config,
+        Self::Sensors,
+        Self::Leds,
+        Self::Clients,
+        Self::GameRestore,
+        Self::Network,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Config => "config",
+            Self::Sensors => "sensors",
+            Self::Leds => "LEDs",
+            Self::Clients => "BLE",
+            Self::GameRestore => "game restore",
+            Self::Network => "network",
+        }
+    }
+
+    /// The square showing this stage's outcome.
+    pub fn square(self) -> Square {
+        Square::ALL[self as usize]
+    }
+
+    /// Blink code for a stage the board cannot run without, after the
+    /// [`crate::pins::PinError`] codes.
+    pub fn failure_code(self) -> u8 {
+        6 + self as u8
+    }
+}
+
+/// How a stage went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StageOutcome {
+    Ready,
+    /// Came up with a fallback, e.g. the revision preset instead of a
+    /// saved calibration.
+    Degraded(String),
+    /// Did not come up; the board runs without it.
+    Failed(String),
+}
+
+/// Outcomes of the stages run so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BootReport {
+    outcomes: Vec<(BootStage, StageOutcome)>,
+}
+
+impl BootReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record and log how `stage` went.
+    pub fn record(&mut self, stage: BootStage, outcome: StageOutcome) {
+        match &outcome {
+            StageOutcome::Ready => log::info!("Boot: {} ready", stage.name()),
+            StageOutcome::Degraded(reason) => {
+                log::warn!("Boot: {} degraded: {reason}", stage.name());
+            }
+            StageOutcome::Failed(reason) => {
+                log::error!("Boot: {} failed: {reason}", stage.name());
+            }
+        }
+        self.outcomes.retain(|(recorded, _)| *recorded != stage);
+        self.outcomes.push((stage, outcome));
+    }
+
+    pub fn outcome(&self, stage: BootStage) -> Option<&StageOutcome> {
+        self.outcomes
+            .iter()
+            .find(|(recorded, _)| *recorded == stage)
+            .map(|(_, outcome)| outcome)
+    }
+
+    /// Whether every stage run so far came up fully.
+    pub fn is_ready(&self) -> bool {
+        self.outcomes
+            .iter()
+            .all(|(_, outcome)| *outcome == StageOutcome::Ready)
+    }
+
+    /// Progress for [`crate::BoardDisplay::set_squares`]: each stage's
+    /// square in the success, stalemate or failure color, and the first
+    /// stage not yet run as pending.
+    pub fn colors(&self, palette: &LedPalette) -> Vec<(Square, Rgb8)> {
+        let mut colors: Vec<(Square, Rgb8)> = self
+            .outcomes
+            .iter()
+            .map(|(stage, outcome)| {
+                let color = match outcome {
+                    StageOutcome::Ready => palette.status_success,
+                    StageOutcome::Degraded(_) => palette.stalemate,
+                    StageOutcome::Failed(_) => palette.status_failure,
+                };
+                (stage.square(), color)
+            })
+            .collect();
+        if let Some(next) = BootStage::ALL
+            .into_iter()
+            .find(|&stage| self.outcome(stage).is_none())
+        {
+            colors.push((next.square(), palette.status_pending));
+        }
+        colors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_lights_one_square_per_stage() {
+        let palette = LedPalette::default();
+        let mut report = BootReport::new();
+        report.record(
+            BootStage::Config,
+            StageOutcome::Degraded("no calibration".into()),
+        );
+        report.record(BootStage::Sensors, StageOutcome::Ready);
+        report.record(BootStage::Leds, StageOutcome::Ready);
+
+        assert_eq!(
+            report.colors(&palette),
+            [
+                (Square::A1, palette.stalemate),
+                (Square::B1, palette.status_success),
+                (Square::C1, palette.status_success),
+                (Square::D1, palette.status_pending),
+            ]
+        );
+        assert!(!report.is_ready());
+
+        report.record(BootStage::Clients, StageOutcome::Failed("no radio".into()));
+        assert_eq!(
+            report.colors(&palette)[3],
+            (Square::D1, palette.status_failure)
+        );
+        assert_eq!(
+            report.outcome(BootStage::Clients),
+            Some(&StageOutcome::Failed("no radio".into()))
+        );
+    }
+
+    #[test]
+    fn rerunning_a_stage_replaces_its_outcome() {
+        let mut report = BootReport::new();
+        report.record(BootStage::Network, StageOutcome::Failed("timeout".into()));
+        report.record(BootStage::Network, StageOutcome::Ready);
+
+        assert!(report.is_ready());
+        assert_eq!(report.colors(&LedPalette::default()).len(), 2);
+    }
+}
@@ -0,0 +1,134 @@
+//! Fixed-capacity lists for the paths that run on every sensor reading.
+//!
+//! The session builds a few short lists per reading: the tick's events and
+//! the inference lines with their pending moves. On a board left running
+//! for days that churn fragments the ESP32 heap, so with the `heapless`
+//! feature (enabled by the firmware recipes in the justfile) [`BoundedVec`]
+//! stores its items inline. Without the feature it is backed by a `Vec`,
+//! but the capacity is enforced the same way, so host tests see exactly
+//! what the firmware does when a list fills up.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// A list of at most `N` items.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BoundedVec<T, const N: usize> {
+    #[cfg(feature = "heapless")]
+    items: heapless::Vec<T, N>,
+    #[cfg(not(feature = "heapless"))]
+    items: Vec<T>,
+}
+
+impl<T, const N: usize> BoundedVec<T, N> {
+    pub const CAPACITY: usize = N;
+
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "heapless")]
+            items: heapless::Vec::new(),
+            #[cfg(not(feature = "heapless"))]
+            items: Vec::new(),
+        }
+    }
+
+    /// Append `item`, handing it back when the list is full.
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        #[cfg(feature = "heapless")]
+        return self.items.push(item);
+        #[cfg(not(feature = "heapless"))]
+        {
+            if self.items.len() >= N {
+                return Err(item);
+            }
+            self.items.push(item);
+            Ok(())
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.items.len() >= N
+    }
+
+    /// Remove the first `count` items, keeping the order of the rest.
+    pub fn remove_prefix(&mut self, count: usize) {
+        let count = count.min(self.items.len());
+        self.items.drain(..count);
+    }
+}
+
+impl<T, const N: usize> Default for BoundedVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Deref for BoundedVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+impl<T, const N: usize> DerefMut for BoundedVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.items
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for BoundedVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, const N: usize> IntoIterator for BoundedVec<T, N> {
+    type Item = T;
+    #[cfg(feature = "heapless")]
+    type IntoIter = heapless::vec::IntoIter<T, N, usize>;
+    #[cfg(not(feature = "heapless"))]
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a BoundedVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_hands_back_items_past_capacity() {
+        let mut list = BoundedVec::<u8, 2>::new();
+
+        assert_eq!(list.push(1), Ok(()));
+        assert_eq!(list.push(2), Ok(()));
+        assert!(list.is_full());
+        assert_eq!(list.push(3), Err(3));
+        assert_eq!(*list, [1, 2]);
+    }
+
+    #[test]
+    fn remove_prefix_keeps_the_rest_in_order() {
+        let mut list = BoundedVec::<u8, 4>::new();
+        for item in 1..=4 {
+            list.push(item).unwrap();
+        }
+
+        list.remove_prefix(3);
+        assert_eq!(*list, [4]);
+        list.remove_prefix(5);
+        assert!(list.is_empty());
+    }
+}
@@ -0,0 +1,335 @@
+//! Sensor calibration and self-test from raw readings.
+//!
+//! The same procedure the `diagnostics` binary runs on the serial console,
+//! driven by client commands instead: with the board empty, a step measures
+//! the baseline and noise floor; with the starting position set up, a second
+//! step measures the weakest piece and places the threshold midway between
+//! it and the noise. Between steps the board lights the squares that would
+//! fail, so the operator can fix them before stepping.
+//!
+//! Sensors sit at slightly different resting outputs, so the empty-board
+//! step also keeps every square's own reading. [`classify`] measures each
+//! piece from its square's baseline, which leaves the whole threshold for
+//! telling pieces from noise.
+//!
+//! [`empty_board_faults`] is the self-test: on an empty board every square
+//! should read close to the others.
+
+use shakmaty::{Bitboard, ByColor, Square};
+
+use crate::feedback::{BoardFeedback, SquareFeedback, StatusKind};
+use crate::frame::{LedPalette, Rgb8, STATUS_RING};
+
+/// Largest difference from the average an empty square may read.
+pub const EMPTY_MARGIN_MV: u16 = 100;
+
+/// Lowest piece detection threshold, however quiet the sensors.
+pub const MIN_THRESHOLD_MV: u16 = 10;
+
+/// Result of a calibration: what the sensor reads as "no piece" and how far
+/// from it a reading must be to count as one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+    /// Average empty-board reading.
+    pub baseline_mv: u16,
+    pub threshold_mv: u16,
+    /// Each square's empty-board reading, indexed by [`Square`].
+    pub square_baselines: [u16; 64],
+}
+
+/// Calibration step in progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Waiting for an empty board.
+    EmptyBoard,
+    /// Waiting for the starting position.
+    StartingPosition { baseline_mv: u16, noise_mv: u16 },
+}
+
+/// Walks through calibration one step at a time.
+#[derive(Debug, Clone)]
+pub struct Calibrator {
+    stage: Stage,
+    /// Readings of the empty board, once measured.
+    square_baselines: [u16; 64],
+}
+
+impl Default for Calibrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Calibrator {
+    pub fn new() -> Self {
+        Self {
+            stage: Stage::EmptyBoard,
+            square_baselines: [0; 64],
+        }
+    }
+
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    /// Squares that would fail the current step with readings `mv`.
+    pub fn faults(&self, mv: &[u16; 64]) -> Bitboard {
+        match self.stage {
+            Stage::EmptyBoard => empty_board_faults(mv),
+            Stage::StartingPosition { noise_mv, .. } => {
+                starting_position(mv, &self.square_baselines, piece_threshold(noise_mv)).0
+            }
+        }
+    }
+
+    /// Guidance for the current step: failing squares lit, with the
+    /// pending status colour.
+    pub fn feedback(&self, mv: &[u16; 64]) -> BoardFeedback {
+        let mut fb = BoardFeedback::with_status(StatusKind::Pending);
+        for square in self.faults(mv) {
+            fb.set(square, SquareFeedback::Capture);
+        }
+        fb
+    }
+
+    /// Measure the current step with readings `mv`.
+    ///
+    /// Returns the calibration once the last step passed, `None` after an
+    /// earlier one, and the failing squares if the board is not ready.
+    pub fn step(&mut self, mv: &[u16; 64]) -> Result<Option<Calibration>, Bitboard> {
+        let faults = self.faults(mv);
+        if faults.any() {
+            return Err(faults);
+        }
+        match self.stage {
+            Stage::EmptyBoard => {
+                let baseline_mv = average(mv);
+                let noise_mv = mv
+                    .iter()
+                    .map(|&v| v.abs_diff(baseline_mv))
+                    .max()
+                    .unwrap_or(0);
+                log::info!("Baseline: {baseline_mv}mV, noise floor: {noise_mv}mV");
+                self.square_baselines = *mv;
+                self.stage = Stage::StartingPosition {
+                    baseline_mv,
+                    noise_mv,
+                };
+                Ok(None)
+            }
+            Stage::StartingPosition {
+                baseline_mv,
+                noise_mv,
+            } => {
+                let (_, weakest) =
+                    starting_position(mv, &self.square_baselines, piece_threshold(noise_mv));
+                let threshold_mv = noise_mv.midpoint(weakest);
+                log::info!(
+                    "Weakest piece: {weakest}mV, threshold: {threshold_mv}mV (midpoint of {noise_mv} and {weakest})"
+                );
+                Ok(Some(Calibration {
+                    baseline_mv,
+                    threshold_mv,
+                    square_baselines: self.square_baselines,
+                }))
+            }
+        }
+    }
+}
+
+/// Self-test result on the board: failing squares lit, with the success or
+/// failure status colour.
+pub fn self_test_feedback(faults: Bitboard) -> BoardFeedback {
+    let status = if faults.any() {
+        StatusKind::Failure
+    } else {
+        StatusKind::Success
+    };
+    let mut fb = BoardFeedback::with_status(status);
+    for square in faults {
+        fb.set(square, SquareFeedback::Capture);
+    }
+    fb
+}
+
+/// Self-test failure as raw colors: the failure status ring with the
+/// failing squares over it, which [`self_test_feedback`] cannot show
+/// together since a status replaces square feedback.
+pub fn self_test_colors(faults: Bitboard, palette: &LedPalette) -> Vec<(Square, Rgb8)> {
+    let ring = STATUS_RING
+        .into_iter()
+        .filter(|&square| !faults.contains(square))
+        .map(|square| (square, palette.status_failure));
+    ring.chain(faults.into_iter().map(|square| (square, palette.capture)))
+        .collect()
+}
+
+/// Squares of an empty board reading too far from the others.
+pub fn empty_board_faults(mv: &[u16; 64]) -> Bitboard {
+    let avg = average(mv);
+    squares()
+        .filter(|&sq| mv[sq as usize].abs_diff(avg) > EMPTY_MARGIN_MV)
+        .collect()
+}
+
+fn average(mv: &[u16; 64]) -> u16 {
+    (mv.iter().map(|&v| u32::from(v)).sum::<u32>() / 64) as u16
+}
+
+/// Deviation a piece must reach, well above the noise.
+fn piece_threshold(noise_mv: u16) -> u16 {
+    noise_mv.saturating_mul(3).max(MIN_THRESHOLD_MV)
+}
+
+/// Pieces in readings `mv`: a square is occupied when it reads more than
+/// `threshold_mv` from its baseline, by a white piece above it and a black
+/// one below. Squares in `dead` always read empty.
+pub fn classify(
+    mv: &[u16; 64],
+    square_baselines: &[u16; 64],
+    threshold_mv: u16,
+    dead: Bitboard,
+) -> ByColor<Bitboard> {
+    let mut pieces = ByColor::<Bitboard>::default();
+    for sq in squares().filter(|&sq| !dead.contains(sq)) {
+        let reading = mv[sq as usize];
+        let baseline_mv = square_baselines[sq as usize];
+        if reading.abs_diff(baseline_mv) > threshold_mv {
+            if reading > baseline_mv {
+                pieces.white.add(sq);
+            } else {
+                pieces.black.add(sq);
+            }
+        }
+    }
+    pieces
+}
+
+/// Failing squares of the starting position, and the weakest piece signal.
+///
+/// White pieces read above their square's baseline and black ones below.
+fn starting_position(
+    mv: &[u16; 64],
+    square_baselines: &[u16; 64],
+    threshold: u16,
+) -> (Bitboard, u16) {
+    let mut faults = Bitboard::EMPTY;
+    let mut weakest = u16::MAX;
+    for sq in squares() {
+        let reading = mv[sq as usize];
+        let baseline_mv = square_baselines[sq as usize];
+        let deviation = reading.abs_diff(baseline_mv);
+        let rank = sq.rank() as u32;
+        let expected_above = match rank {
+            0 | 1 => Some(true),
+            6 | 7 => Some(false),
+            _ => None,
+        };
+        let ok = match expected_above {
+            Some(above) => deviation >= threshold && (reading > baseline_mv) == above,
+            None => deviation <= threshold,
+        };
+        if !ok {
+            faults.add(sq);
+        } else if expected_above.is_some() {
+            weakest = weakest.min(deviation);
+        }
+    }
+    (faults, weakest)
+}
+
+fn squares() -> impl Iterator<Item = Square> {
+    (0..64).map(Square::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASELINE: u16 = 1440;
+
+    fn empty() -> [u16; 64] {
+        let mut mv = [BASELINE; 64];
+        mv[10] += 5;
+        mv[20] -= 5;
+        mv
+    }
+
+    fn starting_position() -> [u16; 64] {
+        let mut mv = empty();
+        for (i, v) in mv.iter_mut().enumerate() {
+            match i / 8 {
+                0 | 1 => *v = BASELINE + 400,
+                6 | 7 => *v = BASELINE - 300,
+                _ => {}
+            }
+        }
+        mv
+    }
+
+    #[test]
+    fn two_steps_measure_baseline_and_threshold() {
+        let mut calibrator = Calibrator::new();
+        assert_eq!(calibrator.step(&empty()), Ok(None));
+        assert_eq!(
+            calibrator.stage(),
+            Stage::StartingPosition {
+                baseline_mv: BASELINE,
+                noise_mv: 5
+            }
+        );
+
+        assert_eq!(
+            calibrator.step(&starting_position()),
+            Ok(Some(Calibration {
+                baseline_mv: BASELINE,
+                threshold_mv: 152,
+                square_baselines: empty(),
+            }))
+        );
+    }
+
+    #[test]
+    fn pieces_are_measured_from_their_own_square() {
+        let mut baselines = [BASELINE; 64];
+        baselines[Square::E4 as usize] = BASELINE + 80;
+        let mut mv = baselines;
+        mv[Square::E4 as usize] += 60;
+        mv[Square::D4 as usize] += 120;
+        mv[Square::D5 as usize] -= 120;
+        mv[Square::A1 as usize] += 300;
+
+        let pieces = classify(&mv, &baselines, 100, Bitboard::from(Square::A1));
+
+        assert_eq!(pieces.white, Bitboard::from(Square::D4));
+        assert_eq!(pieces.black, Bitboard::from(Square::D5));
+    }
+
+    #[test]
+    fn pieces_left_on_an_empty_board_fail_the_step() {
+        let mut mv = empty();
+        mv[Square::E4 as usize] = BASELINE + 600;
+        let mut calibrator = Calibrator::new();
+
+        assert_eq!(calibrator.step(&mv), Err(Bitboard::from(Square::E4)));
+        assert_eq!(calibrator.stage(), Stage::EmptyBoard);
+        assert_eq!(
+            calibrator.feedback(&mv).get(Square::E4),
+            Some(SquareFeedback::Capture)
+        );
+    }
+
+    #[test]
+    fn missing_or_reversed_pieces_fail_the_starting_position() {
+        let mut calibrator = Calibrator::new();
+        calibrator.step(&empty()).unwrap();
+        let mut mv = starting_position();
+        mv[Square::E2 as usize] = BASELINE;
+        mv[Square::D8 as usize] = BASELINE + 300;
+
+        assert_eq!(
+            calibrator.step(&mv),
+            Err(Bitboard::from(Square::E2) | Square::D8)
+        );
+    }
+}
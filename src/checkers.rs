@@ -0,0 +1,515 @@
+//! Checkers (English draughts) on the chess board's sensors.
+//!
+//! Pieces stand on the dark squares; black moves first. Captures are
+//! forced and a capturing piece must keep jumping while it can. A man
+//! reaching the far rank is crowned, which ends its move. Only occupancy
+//! is used to follow the game, so pieces need not be told apart by color:
+//! a move is recognised once the board settles into the occupancy one
+//! legal move produces.
+
+use std::time::Duration;
+
+use shakmaty::{Bitboard, ByColor, Color, File, Rank, Square};
+
+use crate::feedback::{BoardFeedback, SquareFeedback};
+use crate::mode::{GameMode, ModeStatus};
+
+/// The 32 playable (dark) squares.
+pub const DARK_SQUARES: Bitboard = Bitboard(0xAA55_AA55_AA55_AA55);
+
+/// A complete move: a step, or a full chain of jumps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckersMove {
+    pub from: Square,
+    pub to: Square,
+    /// Opponent pieces jumped, empty for a simple step.
+    pub captured: Bitboard,
+}
+
+impl CheckersMove {
+    pub fn is_capture(&self) -> bool {
+        self.captured.any()
+    }
+}
+
+/// A checkers position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Draughts {
+    pieces: ByColor<Bitboard>,
+    kings: Bitboard,
+    turn: Color,
+}
+
+impl Default for Draughts {
+    /// The standard opening: twelve men each on the first three ranks of
+    /// each side, black to move.
+    fn default() -> Self {
+        let ranks = |rs: [Rank; 3]| {
+            rs.into_iter()
+                .fold(Bitboard::EMPTY, |bb, r| bb | Bitboard::from_rank(r))
+                & DARK_SQUARES
+        };
+        Self {
+            pieces: ByColor {
+                white: ranks([Rank::First, Rank::Second, Rank::Third]),
+                black: ranks([Rank::Sixth, Rank::Seventh, Rank::Eighth]),
+            },
+            kings: Bitboard::EMPTY,
+            turn: Color::Black,
+        }
+    }
+}
+
+impl Draughts {
+    /// A position from explicit piece sets (for puzzles and tests).
+    pub fn new(pieces: ByColor<Bitboard>, kings: Bitboard, turn: Color) -> Self {
+        Self {
+            pieces,
+            kings,
+            turn,
+        }
+    }
+
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    pub fn pieces(&self) -> ByColor<Bitboard> {
+        self.pieces
+    }
+
+    pub fn kings(&self) -> Bitboard {
+        self.kings
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.pieces.white | self.pieces.black
+    }
+
+    /// Legal moves for the side to move. Captures are forced, so if any
+    /// jump exists only jumps are returned.
+    pub fn legal_moves(&self) -> Vec<CheckersMove> {
+        let own = *self.pieces.get(self.turn);
+        let mut jumps = Vec::new();
+        for from in own {
+            self.collect_jumps(from, from, Bitboard::EMPTY, &mut jumps);
+        }
+        if !jumps.is_empty() {
+            return jumps;
+        }
+        let empty = !self.occupied() & DARK_SQUARES;
+        own.into_iter()
+            .flat_map(|from| {
+                self.directions(from)
+                    .iter()
+                    .filter_map(move |&(df, dr)| diagonal(from, df, dr))
+                    .filter(|to| empty.contains(*to))
+                    .map(move |to| CheckersMove {
+                        from,
+                        to,
+                        captured: Bitboard::EMPTY,
+                    })
+            })
+            .collect()
+    }
+
+    /// Play a move returned by [`Self::legal_moves`].
+    pub fn play(&mut self, mv: &CheckersMove) {
+        let turn = self.turn;
+        let was_king = self.kings.contains(mv.from);
+        self.pieces.get_mut(turn).discard(mv.from);
+        self.pieces.get_mut(turn).add(mv.to);
+        *self.pieces.get_mut(!turn) &= !mv.captured;
+        self.kings &= !(mv.captured | Bitboard::from(mv.from));
+        if was_king || mv.to.rank() == crown_rank(turn) {
+            self.kings.add(mv.to);
+        }
+        self.turn = !turn;
+    }
+
+    /// Board occupancy after `mv`.
+    pub fn occupied_after(&self, mv: &CheckersMove) -> Bitboard {
+        (self.occupied().without(mv.from) & !mv.captured).with(mv.to)
+    }
+
+    fn directions(&self, from: Square) -> &'static [(i32, i32)] {
+        const UP: &[(i32, i32)] = &[(-1, 1), (1, 1)];
+        const DOWN: &[(i32, i32)] = &[(-1, -1), (1, -1)];
+        const ALL: &[(i32, i32)] = &[(-1, 1), (1, 1), (-1, -1), (1, -1)];
+        if self.kings.contains(from) {
+            ALL
+        } else {
+            match self.turn {
+                Color::White => UP,
+                Color::Black => DOWN,
+            }
+        }
+    }
+
+    /// Extend a jump chain for the piece that started on `origin` and is
+    /// now on `at`, pushing every maximal chain into `out`.
+    fn collect_jumps(
+        &self,
+        origin: Square,
+        at: Square,
+        captured: Bitboard,
+        out: &mut Vec<CheckersMove>,
+    ) {
+        let opponent = *self.pieces.get(!self.turn) & !captured;
+        // The moving piece has left its origin square.
+        let empty = !self.occupied().without(origin) & DARK_SQUARES;
+        let mut extended = false;
+        let crowned =
+            !self.kings.contains(origin) && at != origin && at.rank() == crown_rank(self.turn);
+        if !crowned {
+            for &(df, dr) in self.directions(origin) {
+                let (Some(over), Some(land)) = (diagonal(at, df, dr), diagonal(at, 2 * df, 2 * dr))
+                else {
+                    continue;
+                };
+                if opponent.contains(over) && empty.contains(land) {
+                    extended = true;
+                    self.collect_jumps(origin, land, captured.with(over), out);
+                }
+            }
+        }
+        if !extended && captured.any() {
+            out.push(CheckersMove {
+                from: origin,
+                to: at,
+                captured,
+            });
+        }
+    }
+}
+
+fn crown_rank(color: Color) -> Rank {
+    match color {
+        Color::White => Rank::Eighth,
+        Color::Black => Rank::First,
+    }
+}
+
+fn diagonal(sq: Square, df: i32, dr: i32) -> Option<Square> {
+    let file = sq.file() as i32 + df;
+    let rank = sq.rank() as i32 + dr;
+    if !(0..8).contains(&file) || !(0..8).contains(&rank) {
+        return None;
+    }
+    Some(Square::from_coords(
+        File::new(file as u32),
+        Rank::new(rank as u32),
+    ))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Setup,
+    Playing,
+    /// The side to move had no legal move.
+    Over {
+        winner: Color,
+    },
+}
+
+/// Checkers as a [`GameMode`], followed from board occupancy.
+#[derive(Debug, Clone)]
+pub struct CheckersMode {
+    game: Draughts,
+    phase: Phase,
+    occupied: Bitboard,
+}
+
+impl CheckersMode {
+    pub fn new() -> Self {
+        Self::from_position(Draughts::default())
+    }
+
+    /// Start from a custom position; the board must be set up to match.
+    pub fn from_position(game: Draughts) -> Self {
+        Self {
+            game,
+            phase: Phase::Setup,
+            occupied: Bitboard::EMPTY,
+        }
+    }
+
+    pub fn game(&self) -> &Draughts {
+        &self.game
+    }
+
+    pub fn winner(&self) -> Option<Color> {
+        match self.phase {
+            Phase::Over { winner } => Some(winner),
+            _ => None,
+        }
+    }
+
+    fn play_detected(&mut self) {
+        let occupied = self.occupied;
+        if occupied == self.game.occupied() {
+            return;
+        }
+        let Some(mv) = self
+            .game
+            .legal_moves()
+            .into_iter()
+            .find(|mv| self.game.occupied_after(mv) == occupied)
+        else {
+            return;
+        };
+        log::info!(
+            "Checkers: {:?} {} -> {} ({} captured)",
+            self.game.turn(),
+            mv.from,
+            mv.to,
+            mv.captured.count()
+        );
+        self.game.play(&mv);
+        if self.game.legal_moves().is_empty() {
+            let winner = !self.game.turn();
+            log::info!("Checkers: {winner:?} wins");
+            self.phase = Phase::Over { winner };
+        }
+    }
+
+    fn playing_feedback(&self) -> BoardFeedback {
+        let mut fb = BoardFeedback::new();
+        let expected = self.game.occupied();
+        let moves = self.game.legal_moves();
+        let lifted = expected & !self.occupied;
+        for from in lifted & *self.game.pieces.get(self.game.turn()) {
+            let from_moves: Vec<_> = moves.iter().filter(|mv| mv.from == from).collect();
+            if from_moves.is_empty() {
+                // Wrong piece, e.g. while a capture is forced elsewhere.
+                fb.set(from, SquareFeedback::Check);
+                for mv in &moves {
+                    fb.set(mv.from, SquareFeedback::Origin);
+                }
+                continue;
+            }
+            fb.set(from, SquareFeedback::Origin);
+            for mv in from_moves {
+                let kind = if mv.is_capture() {
+                    SquareFeedback::Capture
+                } else {
+                    SquareFeedback::Destination
+                };
+                fb.set(mv.to, kind);
+            }
+        }
+        let landing = moves.iter().fold(Bitboard::EMPTY, |bb, mv| bb.with(mv.to));
+        for sq in self.occupied & !expected & !landing {
+            fb.set(sq, SquareFeedback::Check);
+        }
+        fb
+    }
+}
+
+impl Default for CheckersMode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameMode for CheckersMode {
+    fn name(&self) -> &'static str {
+        "checkers"
+    }
+
+    fn tick(&mut self, positions: ByColor<Bitboard>, _now: Duration) -> ModeStatus {
+        self.occupied = positions.white | positions.black;
+        match self.phase {
+            Phase::Setup => {
+                if self.occupied == self.game.occupied() {
+                    log::info!("Checkers: board set up, {:?} to move", self.game.turn());
+                    self.phase = Phase::Playing;
+                }
+            }
+            Phase::Playing => self.play_detected(),
+            Phase::Over { .. } => {}
+        }
+        match self.phase {
+            Phase::Over { .. } => ModeStatus::Finished,
+            _ => ModeStatus::Running,
+        }
+    }
+
+    fn feedback(&self) -> BoardFeedback {
+        match self.phase {
+            Phase::Setup => {
+                let mut fb = BoardFeedback::new();
+                let expected = self.game.pieces();
+                for sq in expected.white & !self.occupied {
+                    fb.set(sq, SquareFeedback::Destination);
+                }
+                for sq in expected.black & !self.occupied {
+                    fb.set(sq, SquareFeedback::Capture);
+                }
+                for sq in self.occupied & !self.game.occupied() {
+                    fb.set(sq, SquareFeedback::Check);
+                }
+                fb
+            }
+            Phase::Playing => self.playing_feedback(),
+            Phase::Over { winner } => {
+                let mut fb = BoardFeedback::new();
+                for sq in *self.game.pieces().get(winner) {
+                    fb.set(sq, SquareFeedback::Victory);
+                }
+                fb
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bb(squares: &[Square]) -> Bitboard {
+        squares.iter().copied().collect()
+    }
+
+    fn position(white: &[Square], black: &[Square], turn: Color) -> Draughts {
+        Draughts::new(
+            ByColor {
+                white: bb(white),
+                black: bb(black),
+            },
+            Bitboard::EMPTY,
+            turn,
+        )
+    }
+
+    fn occupancy(occupied: Bitboard) -> ByColor<Bitboard> {
+        ByColor {
+            white: occupied,
+            black: Bitboard::EMPTY,
+        }
+    }
+
+    #[test]
+    fn opening_has_seven_moves_for_black() {
+        let game = Draughts::default();
+        assert_eq!(game.pieces().white.count(), 12);
+        assert_eq!(game.pieces().black.count(), 12);
+        assert_eq!(game.legal_moves().len(), 7);
+        assert!((game.occupied() & !DARK_SQUARES).is_empty());
+    }
+
+    #[test]
+    fn capture_is_forced() {
+        // Black d4 can jump white c3; black h6 could otherwise step.
+        let game = position(&[Square::C3], &[Square::D4, Square::H6], Color::Black);
+
+        let moves = game.legal_moves();
+
+        assert_eq!(
+            moves,
+            vec![CheckersMove {
+                from: Square::D4,
+                to: Square::B2,
+                captured: Square::C3.into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn multi_jump_continues_to_the_end() {
+        let game = position(
+            &[Square::A1],
+            &[Square::B2, Square::D4, Square::H8],
+            Color::White,
+        );
+
+        let moves = game.legal_moves();
+
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].to, Square::E5);
+        assert_eq!(moves[0].captured, bb(&[Square::B2, Square::D4]));
+    }
+
+    #[test]
+    fn man_reaching_far_rank_is_crowned_and_stops() {
+        let mut game = position(&[Square::B6], &[Square::C7, Square::E7], Color::White);
+
+        let moves = game.legal_moves();
+        assert_eq!(moves.len(), 1, "{moves:?}");
+        assert_eq!(moves[0].to, Square::D8);
+
+        game.play(&moves[0]);
+        assert!(game.kings().contains(Square::D8));
+    }
+
+    #[test]
+    fn kings_move_backwards() {
+        let game = Draughts::new(
+            ByColor {
+                white: Square::D4.into(),
+                black: Square::H8.into(),
+            },
+            Square::D4.into(),
+            Color::White,
+        );
+
+        let targets: Bitboard = game.legal_moves().iter().map(|mv| mv.to).collect();
+
+        assert_eq!(
+            targets,
+            bb(&[Square::C3, Square::E3, Square::C5, Square::E5])
+        );
+    }
+
+    #[test]
+    fn mode_follows_moves_from_occupancy() {
+        let mut mode = CheckersMode::new();
+        let start = Draughts::default().occupied();
+        mode.tick(occupancy(start), Duration::ZERO);
+
+        // Black steps b6-a5.
+        mode.tick(occupancy(start.without(Square::B6)), Duration::ZERO);
+        let fb = mode.feedback();
+        assert_eq!(fb.get(Square::B6), Some(SquareFeedback::Origin));
+        assert_eq!(fb.get(Square::A5), Some(SquareFeedback::Destination));
+        assert_eq!(fb.get(Square::C5), Some(SquareFeedback::Destination));
+
+        mode.tick(
+            occupancy(start.without(Square::B6).with(Square::A5)),
+            Duration::ZERO,
+        );
+        assert_eq!(mode.game().turn(), Color::White);
+    }
+
+    #[test]
+    fn lifting_a_piece_that_cannot_move_shows_forced_capture() {
+        let game = position(&[Square::C3], &[Square::D4, Square::H6], Color::Black);
+        let mut mode = CheckersMode::from_position(game.clone());
+        mode.tick(occupancy(game.occupied()), Duration::ZERO);
+
+        mode.tick(
+            occupancy(game.occupied().without(Square::H6)),
+            Duration::ZERO,
+        );
+
+        let fb = mode.feedback();
+        assert_eq!(fb.get(Square::H6), Some(SquareFeedback::Check));
+        assert_eq!(fb.get(Square::D4), Some(SquareFeedback::Origin));
+    }
+
+    #[test]
+    fn capturing_the_last_piece_wins() {
+        let game = position(&[Square::C3], &[Square::D4], Color::Black);
+        let mut mode = CheckersMode::from_position(game);
+        mode.tick(occupancy(bb(&[Square::C3, Square::D4])), Duration::ZERO);
+
+        let status = mode.tick(occupancy(Square::B2.into()), Duration::ZERO);
+
+        assert_eq!(status, ModeStatus::Finished);
+        assert_eq!(mode.winner(), Some(Color::Black));
+        assert_eq!(
+            mode.feedback().get(Square::B2),
+            Some(SquareFeedback::Victory)
+        );
+    }
+}
@@ -0,0 +1,471 @@
+//! On-board chess clock.
+//!
+//! Time is driven by the caller's monotonic clock (see [`crate::app::Clock`]),
+//! so the clock is a plain value that tests can step through virtual time.
+
+use std::fmt;
+use std::time::Duration;
+
+use shakmaty::{Bitboard, Board, ByColor, Color, File, Rank, Square};
+
+use crate::feedback::{BoardFeedback, SquareFeedback};
+
+/// Remaining time below which a clock bar is drawn as a warning.
+pub const LOW_TIME: Duration = Duration::from_secs(10);
+
+/// How a side's per-move [`TimeControl::increment`] is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimingMethod {
+    /// Added after every move.
+    #[default]
+    Fischer,
+    /// The time spent on a move is given back after it, up to the
+    /// increment, so a side never gains time.
+    Bronstein,
+    /// Simple (US) delay: each turn the clock waits out the increment
+    /// before it starts counting down.
+    Delay,
+}
+
+/// Initial time per side plus a per-move increment or delay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeControl {
+    pub initial: Duration,
+    pub increment: Duration,
+    pub method: TimingMethod,
+}
+
+impl TimeControl {
+    /// Parse `MINUTES+INCREMENT`, e.g. `5+3`, with an optional `b`
+    /// (Bronstein) or `d` (simple delay) suffix.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (minutes, increment) = s.split_once('+')?;
+        let minutes: f64 = minutes.parse().ok()?;
+        let initial = Duration::try_from_secs_f64(minutes * 60.0).ok()?;
+        let (increment, method) = match increment.as_bytes().last()? {
+            b'b' => (&increment[..increment.len() - 1], TimingMethod::Bronstein),
+            b'd' => (&increment[..increment.len() - 1], TimingMethod::Delay),
+            _ => (increment, TimingMethod::Fischer),
+        };
+        let increment = Duration::from_secs(increment.parse().ok()?);
+        (!initial.is_zero()).then_some(Self {
+            initial,
+            increment,
+            method,
+        })
+    }
+}
+
+/// The form [`TimeControl::parse`] reads.
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let minutes = self.initial.as_secs_f64() / 60.0;
+        let suffix = match self.method {
+            TimingMethod::Fischer => "",
+            TimingMethod::Bronstein => "b",
+            TimingMethod::Delay => "d",
+        };
+        write!(f, "{minutes}+{}{suffix}", self.increment.as_secs())
+    }
+}
+
+/// How a game uses the clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSettings {
+    pub time_control: TimeControl,
+    /// Tournament rules: a move on the board only counts once the player
+    /// presses their clock.
+    pub confirm_moves: bool,
+}
+
+/// Two-sided countdown clock.
+#[derive(Debug, Clone)]
+pub struct GameClock {
+    control: TimeControl,
+    /// Remaining time at the moment each side's clock last stopped.
+    remaining: ByColor<Duration>,
+    /// The side whose time is running, and since when.
+    running: Option<(Color, Duration)>,
+}
+
+impl GameClock {
+    /// A stopped clock with the initial time on both sides.
+    pub fn new(control: TimeControl) -> Self {
+        Self {
+            control,
+            remaining: ByColor {
+                white: control.initial,
+                black: control.initial,
+            },
+            running: None,
+        }
+    }
+
+    /// A stopped clock continuing a game with `remaining` on each side,
+    /// e.g. after a restart.
+    pub fn resume(control: TimeControl, remaining: ByColor<Duration>) -> Self {
+        Self {
+            control,
+            remaining,
+            running: None,
+        }
+    }
+
+    pub fn time_control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// The side whose time is running, if any.
+    pub fn running(&self) -> Option<Color> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// Start (or restart) `color`'s time, stopping the other side.
+    pub fn start(&mut self, color: Color, now: Duration) {
+        self.stop(now);
+        self.running = Some((color, now));
+    }
+
+    /// Stop the running side without adding an increment.
+    pub fn stop(&mut self, now: Duration) {
+        if let Some((color, _)) = self.running {
+            self.remaining[color] = self.remaining(color, now);
+            self.running = None;
+        }
+    }
+
+    /// End the running side's turn: it gets its increment (see
+    /// [`TimingMethod`]) and the opponent's time starts. Does nothing while
+    /// stopped.
+    pub fn switch(&mut self, now: Duration) {
+        let Some((color, since)) = self.running else {
+            return;
+        };
+        self.stop(now);
+        if !self.remaining[color].is_zero() {
+            self.remaining[color] += match self.control.method {
+                TimingMethod::Fischer => self.control.increment,
+                TimingMethod::Bronstein => now.saturating_sub(since).min(self.control.increment),
+                TimingMethod::Delay => Duration::ZERO,
+            };
+        }
+        self.running = Some((color.other(), now));
+    }
+
+    /// Time left for `color` at `now`.
+    pub fn remaining(&self, color: Color, now: Duration) -> Duration {
+        match self.running {
+            Some((running, since)) if running == color => {
+                let mut used = now.saturating_sub(since);
+                if self.control.method == TimingMethod::Delay {
+                    used = used.saturating_sub(self.control.increment);
+                }
+                self.remaining[color].saturating_sub(used)
+            }
+            _ => self.remaining[color],
+        }
+    }
+
+    /// The side that has run out of time, if any.
+    pub fn flagged(&self, now: Duration) -> Option<Color> {
+        self.running()
+            .filter(|&color| self.remaining(color, now).is_zero())
+    }
+}
+
+/// Start ritual for clocked games: White's time only starts once both
+/// players have confirmed they are ready, so nobody loses time while the
+/// pieces are still being straightened after setup.
+///
+/// A player confirms by lifting their king and putting it back, or by
+/// pressing their side of the clock.
+#[derive(Debug, Clone)]
+pub struct StartHandshake {
+    ready: ByColor<bool>,
+    /// Whether each king has been seen off its square since the game began.
+    lifted: ByColor<bool>,
+}
+
+impl StartHandshake {
+    /// Wait for the sides not already `ready` (e.g. remote players, who
+    /// are not at the board).
+    pub fn new(ready: ByColor<bool>) -> Self {
+        Self {
+            ready,
+            lifted: ByColor::default(),
+        }
+    }
+
+    pub fn is_ready(&self, color: Color) -> bool {
+        self.ready[color]
+    }
+
+    /// Both players are ready.
+    pub fn is_complete(&self) -> bool {
+        self.ready.white && self.ready.black
+    }
+
+    /// Mark `color` ready. Returns `false` if it already was.
+    pub fn confirm(&mut self, color: Color) -> bool {
+        !std::mem::replace(&mut self.ready[color], true)
+    }
+
+    /// Watch for king touches. Returns the sides that became ready.
+    pub fn observe(
+        &mut self,
+        board: &Board,
+        sensors: ByColor<Bitboard>,
+    ) -> impl Iterator<Item = Color> {
+        let mut confirmed = ByColor::<bool>::default();
+        for color in Color::ALL {
+            let Some(king) = board.king_of(color) else {
+                continue;
+            };
+            if self.ready[color] {
+                continue;
+            }
+            if !sensors[color].contains(king) {
+                self.lifted[color] = true;
+            } else if self.lifted[color] && self.confirm(color) {
+                confirmed[color] = true;
+            }
+        }
+        Color::ALL
+            .into_iter()
+            .filter(move |&color| confirmed[color])
+    }
+
+    /// Light the kings of the players still to confirm.
+    pub fn feedback(&self, board: &Board) -> BoardFeedback {
+        let mut feedback = BoardFeedback::new();
+        for color in Color::ALL {
+            if !self.ready[color]
+                && let Some(king) = board.king_of(color)
+            {
+                feedback.set(king, SquareFeedback::Origin);
+            }
+        }
+        feedback
+    }
+}
+
+/// Draw each side's remaining time as a bar on the board edge, for boards
+/// without a screen.
+///
+/// White's bar runs up the h-file from h1 and black's down the a-file
+/// from a8, so each bar sits on its player's right. Bar length is
+/// proportional to the initial time in whole seconds; a side below
+/// [`LOW_TIME`] shows [`SquareFeedback::Check`]. Squares that already carry
+/// game feedback are left alone.
+pub fn overlay_clock_bar(feedback: &mut BoardFeedback, clock: &GameClock, now: Duration) {
+    if feedback.status().is_some() {
+        return;
+    }
+    let initial = clock.time_control().initial.as_secs().max(1);
+    for color in Color::ALL {
+        let remaining = clock.remaining(color, now);
+        let secs = remaining.as_secs();
+        let len = (secs * 8).div_ceil(initial).min(8) as u32;
+        let kind = if remaining < LOW_TIME {
+            SquareFeedback::Check
+        } else {
+            SquareFeedback::Origin
+        };
+        for i in 0..len {
+            let square = match color {
+                Color::White => Square::from_coords(File::H, Rank::new(i)),
+                Color::Black => Square::from_coords(File::A, Rank::new(7 - i)),
+            };
+            if feedback.get(square).is_none() {
+                feedback.set(square, kind);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLITZ: TimeControl = TimeControl {
+        initial: Duration::from_secs(180),
+        increment: Duration::from_secs(2),
+        method: TimingMethod::Fischer,
+    };
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn time_controls_read_back_as_written() {
+        assert_eq!(TimeControl::parse("3+2"), Some(BLITZ));
+        for text in ["3+2", "0.5+0", "15+10b", "5+3d"] {
+            let control = TimeControl::parse(text).unwrap();
+            assert_eq!(control.to_string(), text);
+        }
+        assert_eq!(TimeControl::parse("0+5"), None);
+        assert_eq!(TimeControl::parse("5"), None);
+    }
+
+    #[test]
+    fn only_the_running_side_loses_time() {
+        let mut clock = GameClock::new(BLITZ);
+        clock.start(Color::White, secs(10));
+
+        assert_eq!(clock.remaining(Color::White, secs(40)), secs(150));
+        assert_eq!(clock.remaining(Color::Black, secs(40)), secs(180));
+    }
+
+    #[test]
+    fn switch_adds_increment_and_starts_opponent() {
+        let mut clock = GameClock::new(BLITZ);
+        clock.start(Color::White, secs(0));
+
+        clock.switch(secs(30));
+
+        assert_eq!(clock.running(), Some(Color::Black));
+        assert_eq!(clock.remaining(Color::White, secs(60)), secs(152));
+        assert_eq!(clock.remaining(Color::Black, secs(60)), secs(150));
+    }
+
+    #[test]
+    fn bronstein_gives_back_time_used_up_to_the_increment() {
+        let mut clock = GameClock::new(TimeControl {
+            method: TimingMethod::Bronstein,
+            ..BLITZ
+        });
+        clock.start(Color::White, secs(0));
+
+        clock.switch(secs(1));
+        clock.switch(secs(31));
+
+        assert_eq!(clock.remaining(Color::White, secs(31)), secs(180));
+        assert_eq!(clock.remaining(Color::Black, secs(31)), secs(152));
+    }
+
+    #[test]
+    fn delay_holds_the_countdown_at_the_start_of_each_turn() {
+        let mut clock = GameClock::new(TimeControl {
+            method: TimingMethod::Delay,
+            ..BLITZ
+        });
+        clock.start(Color::White, secs(0));
+
+        assert_eq!(clock.remaining(Color::White, secs(2)), secs(180));
+        assert_eq!(clock.remaining(Color::White, secs(12)), secs(170));
+        clock.switch(secs(12));
+
+        assert_eq!(clock.remaining(Color::White, secs(20)), secs(170));
+        assert_eq!(clock.remaining(Color::Black, secs(20)), secs(174));
+        assert_eq!(clock.flagged(secs(194)), Some(Color::Black));
+    }
+
+    #[test]
+    fn switch_while_stopped_does_nothing() {
+        let mut clock = GameClock::new(BLITZ);
+
+        clock.switch(secs(30));
+
+        assert_eq!(clock.running(), None);
+        assert_eq!(clock.remaining(Color::White, secs(30)), secs(180));
+    }
+
+    #[test]
+    fn flag_falls_at_zero() {
+        let mut clock = GameClock::new(BLITZ);
+        clock.start(Color::Black, secs(0));
+
+        assert_eq!(clock.flagged(secs(179)), None);
+        assert_eq!(clock.flagged(secs(180)), Some(Color::Black));
+        assert_eq!(clock.remaining(Color::Black, secs(500)), Duration::ZERO);
+    }
+
+    #[test]
+    fn stop_freezes_remaining_time() {
+        let mut clock = GameClock::new(BLITZ);
+        clock.start(Color::White, secs(0));
+
+        clock.stop(secs(20));
+
+        assert_eq!(clock.remaining(Color::White, secs(100)), secs(160));
+        assert_eq!(clock.flagged(secs(1000)), None);
+    }
+
+    #[test]
+    fn clock_bar_is_proportional_to_remaining_time() {
+        let mut clock = GameClock::new(BLITZ);
+        clock.start(Color::White, secs(0));
+        let mut fb = BoardFeedback::new();
+
+        // White has 90 of 180 seconds left: half the h-file.
+        overlay_clock_bar(&mut fb, &clock, secs(90));
+
+        let white: Vec<_> = (0..8)
+            .map(|r| fb.get(Square::from_coords(File::H, Rank::new(r))))
+            .collect();
+        assert_eq!(white[..4], [Some(SquareFeedback::Origin); 4]);
+        assert_eq!(white[4..], [None; 4]);
+        assert_eq!(
+            fb.get(Square::A1),
+            Some(SquareFeedback::Origin),
+            "black full"
+        );
+    }
+
+    #[test]
+    fn clock_bar_warns_on_low_time_and_keeps_game_feedback() {
+        let mut clock = GameClock::new(BLITZ);
+        clock.start(Color::White, secs(0));
+        let mut fb = BoardFeedback::new();
+        fb.set(Square::A8, SquareFeedback::Destination);
+
+        overlay_clock_bar(&mut fb, &clock, secs(175));
+
+        assert_eq!(fb.get(Square::H1), Some(SquareFeedback::Check));
+        assert_eq!(fb.get(Square::H2), None);
+        assert_eq!(fb.get(Square::A8), Some(SquareFeedback::Destination));
+    }
+
+    fn start_sensors() -> ByColor<Bitboard> {
+        let board = Board::default();
+        ByColor {
+            white: board.by_color(Color::White),
+            black: board.by_color(Color::Black),
+        }
+    }
+
+    #[test]
+    fn king_touch_confirms_readiness() {
+        let board = Board::default();
+        let mut handshake = StartHandshake::new(ByColor::default());
+        let mut lifted = start_sensors();
+        lifted.black.discard(Square::E8);
+
+        assert_eq!(handshake.observe(&board, start_sensors()).next(), None);
+        assert_eq!(handshake.observe(&board, lifted).next(), None);
+        assert!(
+            handshake
+                .observe(&board, start_sensors())
+                .eq([Color::Black])
+        );
+
+        assert!(handshake.is_ready(Color::Black));
+        assert!(!handshake.is_complete());
+        let feedback = handshake.feedback(&board);
+        assert_eq!(feedback.get(Square::E1), Some(SquareFeedback::Origin));
+        assert_eq!(feedback.get(Square::E8), None);
+    }
+
+    #[test]
+    fn clock_press_confirms_readiness() {
+        let mut handshake = StartHandshake::new(ByColor {
+            white: false,
+            black: true,
+        });
+
+        assert!(handshake.confirm(Color::White));
+        assert!(!handshake.confirm(Color::White), "already ready");
+        assert!(handshake.is_complete());
+    }
+}
@@ -0,0 +1,217 @@
+//! Color-vision deficiency checks for LED palettes.
+//!
+//! Simulates protanopia, deuteranopia, and tritanopia (Machado et al. 2009,
+//! severity 1.0) on palette colors and measures how far apart feedback types
+//! that appear on the board together remain, as CIE76 ΔE in L*a*b*.
+//!
+//! WS2812 channel values drive PWM duty cycle, so they are already linear
+//! light; no sRGB gamma decoding is applied before simulation.
+
+use crate::feedback::SquareFeedback;
+use crate::frame::{LedPalette, Rgb8};
+
+/// Minimum ΔE between feedback types that must remain distinguishable.
+///
+/// ΔE ≈ 2 is a just-noticeable difference on a calibrated monitor; LEDs
+/// diffused through the board surface need a much wider margin.
+pub const MIN_DELTA_E: f32 = 20.0;
+
+/// Feedback types that are shown on the board at the same time and
+/// therefore must not be confused with each other.
+///
+/// `Check` and `Checker` intentionally share a color, and `Victory` only
+/// appears alongside the loser's `Check` rank, so those pairs are omitted.
+pub const CO_OCCURRING: &[(SquareFeedback, SquareFeedback)] = {
+    use SquareFeedback::*;
+    &[
+        (Destination, Capture),
+        (Destination, Origin),
+        (Capture, Origin),
+        (RookDestination, Destination),
+        (RookDestination, Capture),
+        (RookDestination, Origin),
+        (Check, Destination),
+        (Check, Capture),
+        (Check, Origin),
+        (Checker, Destination),
+        (Checker, Capture),
+        (Checker, Origin),
+        (Victory, Check),
+    ]
+};
+
+/// Type of color vision to simulate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorVision {
+    Normal,
+    /// Missing L (red) cones.
+    Protanopia,
+    /// Missing M (green) cones.
+    Deuteranopia,
+    /// Missing S (blue) cones.
+    Tritanopia,
+}
+
+impl ColorVision {
+    pub const ALL: [ColorVision; 4] = [
+        ColorVision::Normal,
+        ColorVision::Protanopia,
+        ColorVision::Deuteranopia,
+        ColorVision::Tritanopia,
+    ];
+
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorVision::Normal => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            ColorVision::Protanopia => [
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ],
+            ColorVision::Deuteranopia => [
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ],
+            ColorVision::Tritanopia => [
+                [1.255_528, -0.076_749, -0.178_779],
+                [-0.078_411, 0.930_809, 0.147_602],
+                [0.004_733, 0.691_367, 0.303_900],
+            ],
+        }
+    }
+
+    /// Simulate how `rgb` (linear, each channel in 0.0–1.0) is perceived.
+    pub fn simulate(self, rgb: [f32; 3]) -> [f32; 3] {
+        let m = self.matrix();
+        let row = |r: [f32; 3]| (r[0] * rgb[0] + r[1] * rgb[1] + r[2] * rgb[2]).clamp(0.0, 1.0);
+        [row(m[0]), row(m[1]), row(m[2])]
+    }
+}
+
+/// Two feedback types that a viewer cannot reliably tell apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Confusion {
+    pub vision: ColorVision,
+    pub a: SquareFeedback,
+    pub b: SquareFeedback,
+    pub delta_e: f32,
+}
+
+/// Find every [`CO_OCCURRING`] pair that falls below [`MIN_DELTA_E`] for any
+/// [`ColorVision`].
+///
+/// Colors are normalized by the palette's brightest channel, so a dim
+/// palette is judged by its hues and relative brightness rather than
+/// rejected for being dark.
+pub fn confusions(palette: &LedPalette) -> Vec<Confusion> {
+    let peak = CO_OCCURRING
+        .iter()
+        .flat_map(|&(a, b)| [palette.square(a), palette.square(b)])
+        .map(|c| c.r.max(c.g).max(c.b))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let mut found = Vec::new();
+    for vision in ColorVision::ALL {
+        for &(a, b) in CO_OCCURRING {
+            let lab_a = to_lab(vision.simulate(normalize(palette.square(a), peak)));
+            let lab_b = to_lab(vision.simulate(normalize(palette.square(b), peak)));
+            let delta_e = distance(lab_a, lab_b);
+            if delta_e < MIN_DELTA_E {
+                found.push(Confusion {
+                    vision,
+                    a,
+                    b,
+                    delta_e,
+                });
+            }
+        }
+    }
+    found
+}
+
+fn normalize(c: Rgb8, peak: u8) -> [f32; 3] {
+    let p = f32::from(peak);
+    [f32::from(c.r) / p, f32::from(c.g) / p, f32::from(c.b) / p]
+}
+
+/// Linear RGB → CIE L*a*b* (D65 white point).
+fn to_lab([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let x = 0.4124 * r + 0.3576 * g + 0.1805 * b;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = 0.0193 * r + 0.1192 * g + 0.9505 * b;
+    let f = |t: f32| {
+        if t > 0.008_856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x / 0.9505), f(y), f(z / 1.089));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f32>()
+        .sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_palette_is_distinguishable_for_all_color_vision() {
+        let found = confusions(&LedPalette::default());
+        assert!(found.is_empty(), "indistinguishable feedback: {found:#?}");
+    }
+
+    #[test]
+    fn red_green_pair_is_flagged_for_deuteranopia() {
+        let palette = LedPalette {
+            destination: Rgb8::new(0, 20, 0),
+            capture: Rgb8::new(20, 10, 0),
+            ..LedPalette::default()
+        };
+
+        let found = confusions(&palette);
+
+        assert!(found.iter().any(|c| c.vision == ColorVision::Deuteranopia
+            && c.a == SquareFeedback::Destination
+            && c.b == SquareFeedback::Capture));
+        assert!(!found.iter().any(|c| c.vision == ColorVision::Normal));
+    }
+
+    #[test]
+    fn identical_colors_are_flagged_for_normal_vision() {
+        let palette = LedPalette {
+            origin: Rgb8::new(0, 20, 0),
+            ..LedPalette::default()
+        };
+
+        assert!(confusions(&palette).iter().any(|c| {
+            c.vision == ColorVision::Normal
+                && c.a == SquareFeedback::Destination
+                && c.b == SquareFeedback::Origin
+        }));
+    }
+
+    #[test]
+    fn normal_vision_is_identity() {
+        let c = [0.2, 0.5, 0.9];
+        assert_eq!(ColorVision::Normal.simulate(c), c);
+    }
+
+    #[test]
+    fn lab_of_white_is_neutral() {
+        let [l, a, b] = to_lab([1.0, 1.0, 1.0]);
+        assert!((l - 100.0).abs() < 0.5);
+        assert!(a.abs() < 0.5);
+        assert!(b.abs() < 0.5);
+    }
+}
@@ -0,0 +1,298 @@
+//! LZ4 block compression for logs and recordings.
+//!
+//! Sensor data is highly repetitive (the same squares lifted and replaced,
+//! the same small time deltas), so a fast LZ77 coder stretches flash and SD
+//! space considerably without a dependency. [`compress_block`] writes the
+//! LZ4 block format with greedy matching over a 4-byte hash table.
+//! [`compress`] adds a small frame with
+//! the original length for standalone files such as archived serial
+//! captures; `replay-log` reads those transparently.
+
+const MIN_MATCH: usize = 4;
+/// The last literals and the distance from the end the last match must
+/// start at, as the block format requires.
+const LAST_LITERALS: usize = 5;
+const MF_LIMIT: usize = 12;
+const MAX_OFFSET: usize = u16::MAX as usize;
+const HASH_BITS: u32 = 12;
+
+/// Marks a [`compress`]ed file.
+const MAGIC: [u8; 4] = *b"UCLZ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum DecompressError {
+    #[error("compressed data ends early")]
+    Truncated,
+    #[error("match offset {offset} reaches before the start")]
+    BadOffset { offset: usize },
+    #[error("decompressed to {actual} bytes, expected {expected}")]
+    LengthMismatch { expected: usize, actual: usize },
+    #[error("not compressed data")]
+    NotCompressed,
+}
+
+/// Longest LZ4 block `len` bytes can compress to (incompressible input).
+pub const fn max_compressed_len(len: usize) -> usize {
+    len + len / 255 + 16
+}
+
+/// Compress `input` as one LZ4 block.
+pub fn compress_block(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() / 2 + 16);
+    BlockCompressor::new().compress_into(input, &mut out);
+    out
+}
+
+/// LZ4 block compressor keeping its hash table between blocks, for callers
+/// compressing on every reading (the flight recorder): with an output
+/// buffer of [`max_compressed_len`] it does not allocate.
+#[derive(Debug, Clone)]
+pub struct BlockCompressor {
+    table: Box<[u32]>,
+}
+
+impl Default for BlockCompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockCompressor {
+    pub fn new() -> Self {
+        Self {
+            table: vec![0; 1 << HASH_BITS].into_boxed_slice(),
+        }
+    }
+
+    /// Compress `input` as one LZ4 block, replacing the contents of `out`.
+    pub fn compress_into(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        out.clear();
+        let table = &mut self.table;
+        table.fill(0);
+        let mut anchor = 0;
+        let mut pos = 0;
+        let match_limit = input.len().saturating_sub(MF_LIMIT);
+        while pos < match_limit {
+            let hash = hash4(&input[pos..]);
+            let candidate = table[hash] as usize;
+            table[hash] = pos as u32;
+            if candidate >= pos
+                || pos - candidate > MAX_OFFSET
+                || input[candidate..candidate + MIN_MATCH] != input[pos..pos + MIN_MATCH]
+            {
+                pos += 1;
+                continue;
+            }
+            let max_len = input.len() - LAST_LITERALS - pos;
+            let len = MIN_MATCH
+                + input[candidate + MIN_MATCH..]
+                    .iter()
+                    .zip(&input[pos + MIN_MATCH..pos + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+            write_sequence(out, &input[anchor..pos], Some((pos - candidate, len)));
+            pos += len;
+            anchor = pos;
+        }
+        write_sequence(out, &input[anchor..], None);
+    }
+}
+
+/// Decompress one LZ4 block of `expected_len` bytes.
+///
+/// `expected_len` comes from untrusted headers, so it only bounds the
+/// output: nothing is allocated beyond what `input` can expand to, and
+/// decoding stops as soon as the output would grow past it.
+pub fn decompress_block(input: &[u8], expected_len: usize) -> Result<Vec<u8>, DecompressError> {
+    let too_long = |actual| DecompressError::LengthMismatch {
+        expected: expected_len,
+        actual,
+    };
+    let mut out = Vec::with_capacity(expected_len.min(input.len().saturating_mul(255)));
+    let mut input = input;
+    loop {
+        let (&token, rest) = input.split_first().ok_or(DecompressError::Truncated)?;
+        input = rest;
+        let literals = read_length(&mut input, usize::from(token >> 4))?;
+        let literal_bytes = input.get(..literals).ok_or(DecompressError::Truncated)?;
+        if out.len() + literals > expected_len {
+            return Err(too_long(out.len() + literals));
+        }
+        out.extend_from_slice(literal_bytes);
+        input = &input[literals..];
+        if input.is_empty() {
+            break;
+        }
+        let offset = match input {
+            [lo, hi, rest @ ..] => {
+                input = rest;
+                usize::from(u16::from_le_bytes([*lo, *hi]))
+            }
+            _ => return Err(DecompressError::Truncated),
+        };
+        if offset == 0 || offset > out.len() {
+            return Err(DecompressError::BadOffset { offset });
+        }
+        let len = read_length(&mut input, usize::from(token & 0x0f))? + MIN_MATCH;
+        if out.len() + len > expected_len {
+            return Err(too_long(out.len() + len));
+        }
+        let start = out.len() - offset;
+        // Byte by byte: a match may overlap the bytes it produces.
+        for i in 0..len {
+            out.push(out[start + i]);
+        }
+    }
+    if out.len() != expected_len {
+        return Err(DecompressError::LengthMismatch {
+            expected: expected_len,
+            actual: out.len(),
+        });
+    }
+    Ok(out)
+}
+
+/// Compress `input` with a header holding its length.
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.extend_from_slice(&(input.len() as u32).to_le_bytes());
+    out.extend(compress_block(input));
+    out
+}
+
+/// Whether `bytes` were produced by [`compress`].
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Reverse [`compress`].
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    let Some(rest) = bytes.strip_prefix(&MAGIC) else {
+        return Err(DecompressError::NotCompressed);
+    };
+    let (len, block) = rest
+        .split_first_chunk::<4>()
+        .ok_or(DecompressError::Truncated)?;
+    let len = u32::from_le_bytes(*len);
+    decompress_block(block, len as usize)
+}
+
+fn hash4(bytes: &[u8]) -> usize {
+    let word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    (word.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+fn write_sequence(out: &mut Vec<u8>, literals: &[u8], matched: Option<(usize, usize)>) {
+    let match_len = matched.map_or(0, |(_, len)| len - MIN_MATCH);
+    let token = (literals.len().min(15) << 4 | match_len.min(15)) as u8;
+    out.push(token);
+    write_length(out, literals.len());
+    out.extend_from_slice(literals);
+    if let Some((offset, _)) = matched {
+        out.extend_from_slice(&(offset as u16).to_le_bytes());
+        write_length(out, match_len);
+    }
+}
+
+/// Extra length bytes for a nibble that saturated at 15.
+fn write_length(out: &mut Vec<u8>, len: usize) {
+    if len < 15 {
+        return;
+    }
+    let mut rest = len - 15;
+    while rest >= 255 {
+        out.push(255);
+        rest -= 255;
+    }
+    out.push(rest as u8);
+}
+
+fn read_length(input: &mut &[u8], nibble: usize) -> Result<usize, DecompressError> {
+    let mut len = nibble;
+    if nibble == 15 {
+        loop {
+            let (&byte, rest) = input.split_first().ok_or(DecompressError::Truncated)?;
+            *input = rest;
+            len = len.saturating_add(usize::from(byte));
+            if byte != 255 {
+                break;
+            }
+        }
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(input: &[u8]) -> Vec<u8> {
+        let packed = compress_block(input);
+        assert_eq!(decompress_block(&packed, input.len()).unwrap(), input);
+        packed
+    }
+
+    #[test]
+    fn repetitive_data_shrinks() {
+        let log: Vec<u8> = (0..200)
+            .flat_map(|i| format!("tick {i} 000000000000ffff ffff000000000000\n").into_bytes())
+            .collect();
+
+        let packed = round_trip(&log);
+
+        assert!(
+            packed.len() * 4 < log.len(),
+            "{} -> {}",
+            log.len(),
+            packed.len()
+        );
+    }
+
+    #[test]
+    fn edge_cases_round_trip() {
+        round_trip(b"");
+        round_trip(b"abc");
+        round_trip(&[7; 1000]);
+        let noise: Vec<u8> = (0u32..5000)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        round_trip(&noise);
+    }
+
+    #[test]
+    fn framed_data_round_trips_and_rejects_corruption() {
+        let data = b"e2e4 e7e5 e2e4 e7e5 e2e4 e7e5 e2e4 e7e5".repeat(10);
+        let packed = compress(&data);
+
+        assert!(is_compressed(&packed));
+        assert_eq!(decompress(&packed).unwrap(), data);
+        assert_eq!(
+            decompress(&packed[..packed.len() - 3]),
+            Err(DecompressError::Truncated)
+        );
+        assert_eq!(decompress(&data), Err(DecompressError::NotCompressed));
+    }
+
+    #[test]
+    fn malformed_input_is_an_error_not_a_panic() {
+        let packed = compress(&b"e2e4 e7e5 g1f3 b8c6 ".repeat(8));
+        for bytes in crate::testutil::malformed_inputs(&packed, 2000, 64) {
+            let _ = decompress(&bytes);
+        }
+
+        // A header claiming 4 GiB must not be trusted for the allocation.
+        let mut huge = MAGIC.to_vec();
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        huge.extend(compress_block(b"abcd"));
+        assert!(matches!(
+            decompress(&huge),
+            Err(DecompressError::LengthMismatch { actual: 4, .. })
+        ));
+        // A match expanding past the expected length stops right there.
+        let runs = compress_block(&[7; 10_000]);
+        assert!(matches!(
+            decompress_block(&runs, 100),
+            Err(DecompressError::LengthMismatch { expected: 100, .. })
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+//! Hysteresis for sensor readings and game feedback.
+//!
+//! A piece slid across a square, or a Hall sensor near its threshold, can
+//! flicker between readings for a tick or two. [`SensorDebouncer`] passes a
+//! new reading on only once it has held, so move detection never sees it.
+//!
+//! A fast two-handed move passes through several readings within a few
+//! ticks, each with its own feedback (destinations, recovery, capture
+//! options). [`FeedbackDebounce`] keeps showing the last stable feedback
+//! until a new one has held for a threshold, so those intermediate states
+//! never reach the LEDs.
+
+use std::time::Duration;
+
+use shakmaty::{Bitboard, ByColor};
+
+use crate::PieceSensor;
+use crate::app::Clock;
+use crate::calibration::Calibration;
+use crate::feedback::BoardFeedback;
+
+/// How long a sensor reading must hold before it is passed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stability {
+    /// The same reading this many times in a row; 0 and 1 pass every
+    /// reading through.
+    Readings(u32),
+    /// The same reading for this long, however often the sensor is read.
+    Window(Duration),
+}
+
+/// Wraps a sensor and reports its last stable reading.
+///
+/// The first reading is passed on at once. Read errors are passed on and
+/// leave the pending reading untouched.
+#[derive(Debug)]
+pub struct SensorDebouncer<S, C> {
+    inner: S,
+    clock: C,
+    stability: Stability,
+    /// Reading passed on.
+    stable: Option<ByColor<Bitboard>>,
+    /// Reading waiting to become stable, how often it was read, and since
+    /// when.
+    candidate: Option<(ByColor<Bitboard>, u32, Duration)>,
+}
+
+impl<S, C> SensorDebouncer<S, C> {
+    pub fn new(inner: S, clock: C, stability: Stability) -> Self {
+        Self {
+            inner,
+            clock,
+            stability,
+            stable: None,
+            candidate: None,
+        }
+    }
+
+    fn is_stable(&self, count: u32, held: Duration) -> bool {
+        match self.stability {
+            Stability::Readings(readings) => count >= readings,
+            Stability::Window(window) => held >= window,
+        }
+    }
+}
+
+impl<S: PieceSensor, C: Clock> PieceSensor for SensorDebouncer<S, C> {
+    type Error = S::Error;
+
+    fn read_positions(&mut self) -> Result<ByColor<Bitboard>, Self::Error> {
+        let reading = self.inner.read_positions()?;
+        let stable = match self.stable {
+            Some(stable) if stable != reading => stable,
+            _ => {
+                self.stable = Some(reading);
+                self.candidate = None;
+                return Ok(reading);
+            }
+        };
+        let now = self.clock.now();
+        let (count, since) = match self.candidate {
+            Some((candidate, count, since)) if candidate == reading => (count + 1, since),
+            _ => (1, now),
+        };
+        if self.is_stable(count, now.saturating_sub(since)) {
+            self.stable = Some(reading);
+            self.candidate = None;
+            return Ok(reading);
+        }
+        self.candidate = Some((reading, count, since));
+        Ok(stable)
+    }
+
+    fn read_millivolts(&mut self) -> Option<Result<[u16; 64], Self::Error>> {
+        self.inner.read_millivolts()
+    }
+
+    fn calibrate(&mut self, calibration: Calibration) {
+        self.inner.calibrate(calibration);
+    }
+}
+
+/// Shows feedback only once it has been stable for a threshold.
+#[derive(Debug, Clone)]
+pub struct FeedbackDebounce {
+    threshold: Duration,
+    /// Feedback on the display.
+    shown: BoardFeedback,
+    /// Cleared after the display showed something else.
+    shown_valid: bool,
+    /// Feedback waiting to become stable, and since when.
+    candidate: Option<(BoardFeedback, Duration)>,
+}
+
+impl FeedbackDebounce {
+    /// A zero threshold shows every feedback at once.
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            shown: BoardFeedback::new(),
+            shown_valid: false,
+            candidate: None,
+        }
+    }
+
+    /// Feed the latest feedback and get what to display.
+    ///
+    /// Feedback is shown at once if nothing is shown (see
+    /// [`Self::invalidate`]), otherwise after it has been fed unchanged for
+    /// the threshold.
+    pub fn update(&mut self, feedback: BoardFeedback, now: Duration) -> &BoardFeedback {
+        if self.shown_valid && self.shown == feedback {
+            self.candidate = None;
+            return &self.shown;
+        }
+        let since = match &self.candidate {
+            Some((candidate, since)) if *candidate == feedback => *since,
+            _ => now,
+        };
+        if !self.shown_valid || now.saturating_sub(since) >= self.threshold {
+            return self.force(feedback);
+        }
+        self.candidate = Some((feedback, since));
+        &self.shown
+    }
+
+    /// Show `feedback` at once, e.g. the position right after a move.
+    pub fn force(&mut self, feedback: BoardFeedback) -> &BoardFeedback {
+        self.candidate = None;
+        self.shown = feedback;
+        self.shown_valid = true;
+        &self.shown
+    }
+
+    /// Forget what is shown, after the display showed something else.
+    pub fn invalidate(&mut self) {
+        self.shown_valid = false;
+        self.candidate = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feedback::SquareFeedback;
+    use crate::testutil::VirtualClock;
+    use shakmaty::Square;
+
+    /// Returns each queued occupancy in turn, as white pieces.
+    struct Fixed(Vec<Bitboard>);
+
+    impl PieceSensor for Fixed {
+        type Error = String;
+
+        fn read_positions(&mut self) -> Result<ByColor<Bitboard>, String> {
+            if self.0.is_empty() {
+                return Err("no reading".into());
+            }
+            Ok(ByColor {
+                white: self.0.remove(0),
+                black: Bitboard::EMPTY,
+            })
+        }
+    }
+
+    fn bb(squares: &[Square]) -> Bitboard {
+        squares.iter().copied().collect()
+    }
+
+    fn lit(square: Square) -> BoardFeedback {
+        let mut fb = BoardFeedback::new();
+        fb.set(square, SquareFeedback::Destination);
+        fb
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn flickering_reading_is_never_passed_on() {
+        let (start, lifted) = (bb(&[Square::E2]), Bitboard::EMPTY);
+        let readings = vec![start, lifted, start, lifted, lifted, lifted];
+        let mut sensor =
+            SensorDebouncer::new(Fixed(readings), VirtualClock::new(), Stability::Readings(3));
+
+        let passed: Vec<Bitboard> = (0..6)
+            .map(|_| sensor.read_positions().unwrap().white)
+            .collect();
+
+        assert_eq!(passed, [start, start, start, start, start, lifted]);
+    }
+
+    #[test]
+    fn window_counts_time_not_readings() {
+        let (start, lifted) = (bb(&[Square::E2]), Bitboard::EMPTY);
+        let clock = VirtualClock::new();
+        let readings = vec![start, lifted, lifted, lifted];
+        let mut sensor =
+            SensorDebouncer::new(Fixed(readings), clock.clone(), Stability::Window(ms(100)));
+
+        assert_eq!(sensor.read_positions().unwrap().white, start);
+        assert_eq!(sensor.read_positions().unwrap().white, start);
+        clock.advance(ms(60));
+        assert_eq!(sensor.read_positions().unwrap().white, start);
+        clock.advance(ms(60));
+        assert_eq!(sensor.read_positions().unwrap().white, lifted);
+        assert!(sensor.read_positions().is_err());
+    }
+
+    #[test]
+    fn single_reading_stability_passes_everything() {
+        let readings = vec![bb(&[Square::E2]), bb(&[Square::E4])];
+        let mut sensor =
+            SensorDebouncer::new(Fixed(readings), VirtualClock::new(), Stability::Readings(1));
+
+        sensor.read_positions().unwrap();
+        assert_eq!(sensor.read_positions().unwrap().white, bb(&[Square::E4]));
+    }
+
+    #[test]
+    fn short_lived_feedback_is_never_shown() {
+        let mut debounce = FeedbackDebounce::new(ms(100));
+        assert_eq!(debounce.update(lit(Square::E2), ms(0)), &lit(Square::E2));
+
+        assert_eq!(debounce.update(lit(Square::E3), ms(50)), &lit(Square::E2));
+        assert_eq!(debounce.update(lit(Square::E4), ms(100)), &lit(Square::E2));
+        assert_eq!(debounce.update(lit(Square::E4), ms(150)), &lit(Square::E2));
+        assert_eq!(debounce.update(lit(Square::E4), ms(200)), &lit(Square::E4));
+    }
+
+    #[test]
+    fn returning_to_the_shown_feedback_cancels_the_candidate() {
+        let mut debounce = FeedbackDebounce::new(ms(100));
+        debounce.update(lit(Square::E2), ms(0));
+        debounce.update(lit(Square::E3), ms(50));
+        debounce.update(lit(Square::E2), ms(100));
+
+        assert_eq!(debounce.update(lit(Square::E3), ms(150)), &lit(Square::E2));
+    }
+
+    #[test]
+    fn zero_threshold_and_forced_feedback_show_at_once() {
+        let mut debounce = FeedbackDebounce::new(Duration::ZERO);
+        debounce.update(lit(Square::E2), ms(0));
+        assert_eq!(debounce.update(lit(Square::E3), ms(0)), &lit(Square::E3));
+
+        let mut debounce = FeedbackDebounce::new(ms(100));
+        debounce.update(lit(Square::E2), ms(0));
+        assert_eq!(debounce.force(lit(Square::E4)), &lit(Square::E4));
+        debounce.invalidate();
+        assert_eq!(debounce.update(lit(Square::E5), ms(10)), &lit(Square::E5));
+    }
+}
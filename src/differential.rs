@@ -0,0 +1,212 @@
+//! Differential validation of two sensor paths.
+//!
+//! During hardware bring-up a board may carry both the analog Hall sensors
+//! and a digital (switch + shift register) path. [`DifferentialSensor`]
+//! reads both every tick, passes the primary reading through unchanged, and
+//! logs the squares where the two disagree about occupancy. Per-square
+//! counts help pick thresholds and spot wiring errors (e.g. a consistently
+//! disagreeing square, or a mirrored row).
+//!
+//! This is host-only scaffolding for now: no board revision carries the
+//! 74HC165 path and `esp32` has no driver for it, so nothing in the
+//! firmware's sensor read path wraps the sensor in a [`DifferentialSensor`]
+//! and the comparison only runs in tests and host tools. Wiring it in takes
+//! a `PieceSensor` for the shift registers, their pins in
+//! `esp32::config::PIN_ASSIGNMENTS`, and `main.rs` passing that sensor as
+//! the secondary.
+
+use shakmaty::{Bitboard, ByColor, Square};
+
+use crate::PieceSensor;
+use crate::calibration::Calibration;
+
+/// Reads a primary and a secondary sensor and compares their occupancy.
+///
+/// Colors are not compared: digital switch paths usually cannot tell
+/// piece colors apart.
+#[derive(Debug)]
+pub struct DifferentialSensor<P, S> {
+    primary: P,
+    secondary: S,
+    /// Squares that disagreed on the last comparison.
+    current: Bitboard,
+    /// Per-square number of readings in disagreement, indexed by [`Square`].
+    counts: [u32; 64],
+    readings: u32,
+}
+
+impl<P, S> DifferentialSensor<P, S> {
+    pub fn new(primary: P, secondary: S) -> Self {
+        Self {
+            primary,
+            secondary,
+            current: Bitboard::EMPTY,
+            counts: [0; 64],
+            readings: 0,
+        }
+    }
+
+    /// Squares that disagreed on the most recent reading.
+    pub fn disagreeing(&self) -> Bitboard {
+        self.current
+    }
+
+    /// How many readings `square` has disagreed in so far.
+    pub fn disagreements(&self, square: Square) -> u32 {
+        self.counts[square as usize]
+    }
+
+    /// Number of readings compared so far.
+    pub fn readings(&self) -> u32 {
+        self.readings
+    }
+
+    /// Log every square that has disagreed at least once.
+    pub fn log_summary(&self) {
+        let squares: Vec<String> = Square::ALL
+            .into_iter()
+            .filter(|&sq| self.counts[sq as usize] > 0)
+            .map(|sq| format!("{sq} ({})", self.counts[sq as usize]))
+            .collect();
+        if squares.is_empty() {
+            log::info!("Sensor paths agreed on all {} readings", self.readings);
+        } else {
+            log::info!(
+                "Sensor paths disagreed over {} readings: {}",
+                self.readings,
+                squares.join(", ")
+            );
+        }
+    }
+
+    fn compare(&mut self, primary: Bitboard, secondary: Bitboard) {
+        let disagreeing = primary ^ secondary;
+        self.readings += 1;
+        for sq in disagreeing {
+            self.counts[sq as usize] += 1;
+        }
+        // Log transitions only, so a stuck square does not flood the log.
+        if disagreeing != self.current {
+            for sq in disagreeing & !self.current {
+                let (p, s) = (primary.contains(sq), secondary.contains(sq));
+                log::warn!("Sensor mismatch on {sq}: primary={p} secondary={s}");
+            }
+            for sq in self.current & !disagreeing {
+                log::info!("Sensor paths agree again on {sq}");
+            }
+            self.current = disagreeing;
+        }
+    }
+}
+
+impl<P, S> PieceSensor for DifferentialSensor<P, S>
+where
+    P: PieceSensor,
+    S: PieceSensor,
+{
+    type Error = P::Error;
+
+    fn read_positions(&mut self) -> Result<ByColor<Bitboard>, Self::Error> {
+        let primary = self.primary.read_positions()?;
+        match self.secondary.read_positions() {
+            Ok(secondary) => self.compare(
+                primary.white | primary.black,
+                secondary.white | secondary.black,
+            ),
+            Err(e) => log::warn!("Secondary sensor read failed: {e}"),
+        }
+        Ok(primary)
+    }
+
+    fn read_millivolts(&mut self) -> Option<Result<[u16; 64], Self::Error>> {
+        self.primary.read_millivolts()
+    }
+
+    fn calibrate(&mut self, calibration: Calibration) {
+        self.primary.calibrate(calibration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns each queued occupancy in turn, as white pieces.
+    struct Fixed(Vec<Bitboard>);
+
+    impl PieceSensor for Fixed {
+        type Error = String;
+
+        fn read_positions(&mut self) -> Result<ByColor<Bitboard>, String> {
+            if self.0.is_empty() {
+                return Err("no reading".into());
+            }
+            Ok(ByColor {
+                white: self.0.remove(0),
+                black: Bitboard::EMPTY,
+            })
+        }
+    }
+
+    fn bb(squares: &[Square]) -> Bitboard {
+        squares.iter().copied().collect()
+    }
+
+    #[test]
+    fn agreement_passes_primary_through() {
+        let reading = bb(&[Square::E2, Square::E7]);
+        let mut sensor = DifferentialSensor::new(Fixed(vec![reading]), Fixed(vec![reading]));
+
+        let positions = sensor.read_positions().unwrap();
+
+        assert_eq!(positions.white, reading);
+        assert!(sensor.disagreeing().is_empty());
+    }
+
+    #[test]
+    fn mismatches_are_counted_per_square() {
+        let primary = vec![bb(&[Square::E2]), bb(&[Square::E2])];
+        let secondary = vec![bb(&[Square::E2, Square::A1]), bb(&[Square::A1])];
+        let mut sensor = DifferentialSensor::new(Fixed(primary), Fixed(secondary));
+
+        sensor.read_positions().unwrap();
+        sensor.read_positions().unwrap();
+
+        assert_eq!(sensor.disagreements(Square::A1), 2);
+        assert_eq!(sensor.disagreements(Square::E2), 1);
+        assert_eq!(sensor.disagreeing(), bb(&[Square::A1, Square::E2]));
+        assert_eq!(sensor.readings(), 2);
+    }
+
+    #[test]
+    fn secondary_failure_still_returns_primary() {
+        let reading = bb(&[Square::D4]);
+        let mut sensor = DifferentialSensor::new(Fixed(vec![reading]), Fixed(vec![]));
+
+        assert_eq!(sensor.read_positions().unwrap().white, reading);
+        assert_eq!(sensor.readings(), 0);
+    }
+
+    #[test]
+    fn colors_are_not_compared() {
+        let primary = Fixed(vec![bb(&[Square::E2])]);
+        let mut sensor = DifferentialSensor::new(primary, BlackOnly(bb(&[Square::E2])));
+
+        sensor.read_positions().unwrap();
+
+        assert!(sensor.disagreeing().is_empty());
+    }
+
+    struct BlackOnly(Bitboard);
+
+    impl PieceSensor for BlackOnly {
+        type Error = String;
+
+        fn read_positions(&mut self) -> Result<ByColor<Bitboard>, String> {
+            Ok(ByColor {
+                white: Bitboard::EMPTY,
+                black: self.0,
+            })
+        }
+    }
+}
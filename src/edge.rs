@@ -0,0 +1,236 @@
+//! Edge notification LEDs.
+//!
+//! Many builds have a ring of LEDs around the playing surface, separate
+//! from the square LEDs. It is driven as a second logical display
+//! ([`crate::EdgeDisplay`]) with its own channel: [`EdgeFeedback`] says
+//! whose turn it is, how much time each side has left, whether a client
+//! is connected and the WiFi status, and [`EdgeLayout`] composites that
+//! onto the ring.
+
+use std::time::Duration;
+
+use shakmaty::{ByColor, Color};
+
+use crate::chess_clock::{GameClock, LOW_TIME};
+use crate::frame::{LedPalette, Rgb8};
+use crate::wifi::WifiStatus;
+
+/// Remaining time of one side as a fraction of the initial time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockBar {
+    /// Fraction remaining, out of 255.
+    pub fill: u8,
+    /// Below [`LOW_TIME`].
+    pub low: bool,
+}
+
+/// What the edge LEDs show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeFeedback {
+    /// Side to move during a game.
+    pub turn: Option<Color>,
+    /// Remaining time in a clocked game.
+    pub clock: Option<ByColor<ClockBar>>,
+    /// A client is connected.
+    pub connected: bool,
+    /// WiFi status, on boards that use WiFi.
+    pub wifi: Option<WifiStatus>,
+    /// Side set up along the board's first rank, whose half of the ring
+    /// comes first.
+    pub near: Color,
+}
+
+impl EdgeFeedback {
+    /// Nothing but the connection status, e.g. while no game runs.
+    pub fn idle(connected: bool) -> Self {
+        Self {
+            turn: None,
+            clock: None,
+            connected,
+            wifi: None,
+            near: Color::White,
+        }
+    }
+
+    /// Turn indicator and, if the game is clocked, clock bars.
+    pub fn game(turn: Color, clock: Option<&GameClock>, now: Duration, connected: bool) -> Self {
+        let clock = clock.map(|clock| {
+            let initial = clock.time_control().initial.as_millis().max(1);
+            ByColor::new_with(|color| {
+                let remaining = clock.remaining(color, now);
+                ClockBar {
+                    fill: (remaining.as_millis() * 255 / initial).min(255) as u8,
+                    low: remaining < LOW_TIME,
+                }
+            })
+        });
+        Self {
+            turn: Some(turn),
+            clock,
+            connected,
+            wifi: None,
+            near: Color::White,
+        }
+    }
+}
+
+/// How the edge LEDs are arranged.
+///
+/// The first LED shows the connection status and, if the board uses WiFi,
+/// the second one the WiFi status. The rest are split into
+/// the near side's half (along rank 1) followed by the far side's half
+/// (along rank 8); White is near unless [`EdgeFeedback::near`] says
+/// otherwise.
+/// Each half shows its side's clock bar, or is fully lit for the side to
+/// move in a game without a clock; the side to move's half is drawn in the
+/// destination color, the other in the origin color, and a side low on time
+/// in the check color.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EdgeLayout {
+    /// Number of edge LEDs; zero for boards without an edge ring.
+    pub leds: usize,
+}
+
+impl EdgeLayout {
+    pub const fn new(leds: usize) -> Self {
+        Self { leds }
+    }
+
+    /// Colors for each edge LED, in strip order.
+    pub fn render(&self, edge: &EdgeFeedback, palette: &LedPalette) -> Vec<Rgb8> {
+        let mut out = vec![palette.off; self.leds];
+        self.render_into(edge, palette, &mut out);
+        out
+    }
+
+    /// [`Self::render`] into `out`, which holds one color per edge LED, for
+    /// displays that redraw every tick without allocating.
+    pub fn render_into(&self, edge: &EdgeFeedback, palette: &LedPalette, out: &mut [Rgb8]) {
+        out.fill(palette.off);
+        let Some((status, halves)) = out.split_first_mut() else {
+            return;
+        };
+        *status = if edge.connected {
+            palette.status_success
+        } else {
+            palette.status_pending
+        };
+        if let Some(wifi) = edge.wifi
+            && let Some(led) = halves.first_mut()
+        {
+            *led = match wifi {
+                WifiStatus::Connected => palette.status_success,
+                WifiStatus::Connecting => palette.status_pending,
+                WifiStatus::Provisioning => palette.status_failure,
+            };
+        }
+        let skip = usize::from(edge.wifi.is_some()).min(halves.len());
+        let halves = &mut halves[skip..];
+        let Some(turn) = edge.turn else {
+            return;
+        };
+        let near_len = halves.len() / 2;
+        let (near, far) = halves.split_at_mut(near_len);
+        for (color, segment) in [(edge.near, near), (!edge.near, far)] {
+            let (lit, low) = match edge.clock {
+                Some(bars) => {
+                    let bar = bars[color];
+                    let lit = (usize::from(bar.fill) * segment.len()).div_ceil(255);
+                    (lit, bar.low)
+                }
+                None if color == turn => (segment.len(), false),
+                None => (0, false),
+            };
+            let rgb = if low {
+                palette.check
+            } else if color == turn {
+                palette.destination
+            } else {
+                palette.origin
+            };
+            segment[..lit].fill(rgb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess_clock::{TimeControl, TimingMethod};
+
+    #[test]
+    fn halves_show_clock_bars_and_turn() {
+        let palette = LedPalette::default();
+        let mut clock = GameClock::new(TimeControl {
+            initial: Duration::from_secs(100),
+            increment: Duration::ZERO,
+            method: TimingMethod::Fischer,
+        });
+        clock.start(Color::Black, Duration::ZERO);
+        let edge = EdgeFeedback::game(Color::Black, Some(&clock), Duration::from_secs(50), true);
+
+        let leds = EdgeLayout::new(9).render(&edge, &palette);
+
+        assert_eq!(leds[0], palette.status_success);
+        assert_eq!(leds[1..5], [palette.origin; 4], "white full");
+        assert_eq!(leds[5..7], [palette.destination; 2], "black half");
+        assert_eq!(leds[7..], [palette.off; 2]);
+    }
+
+    #[test]
+    fn black_half_comes_first_when_black_is_near() {
+        let palette = LedPalette::default();
+        let edge = EdgeFeedback {
+            near: Color::Black,
+            ..EdgeFeedback::game(Color::White, None, Duration::ZERO, true)
+        };
+
+        let leds = EdgeLayout::new(5).render(&edge, &palette);
+
+        assert_eq!(leds[1..3], [palette.off; 2], "black half");
+        assert_eq!(leds[3..], [palette.destination; 2], "white half");
+    }
+
+    #[test]
+    fn without_clock_only_the_side_to_move_is_lit() {
+        let palette = LedPalette::default();
+        let edge = EdgeFeedback::game(Color::White, None, Duration::ZERO, false);
+
+        let leds = EdgeLayout::new(5).render(&edge, &palette);
+
+        assert_eq!(
+            leds,
+            [
+                palette.status_pending,
+                palette.destination,
+                palette.destination,
+                palette.off,
+                palette.off,
+            ]
+        );
+        assert!(EdgeLayout::default().render(&edge, &palette).is_empty());
+    }
+
+    #[test]
+    fn wifi_status_takes_the_second_led() {
+        let palette = LedPalette::default();
+        let edge = EdgeFeedback {
+            wifi: Some(WifiStatus::Provisioning),
+            ..EdgeFeedback::game(Color::White, None, Duration::ZERO, true)
+        };
+
+        let leds = EdgeLayout::new(6).render(&edge, &palette);
+
+        assert_eq!(
+            leds,
+            [
+                palette.status_success,
+                palette.status_failure,
+                palette.destination,
+                palette.destination,
+                palette.off,
+                palette.off,
+            ]
+        );
+    }
+}
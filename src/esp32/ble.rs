@@ -2,18 +2,32 @@ use std::sync::mpsc;
 
 use esp32_nimble::utilities::mutex::Mutex;
 use esp32_nimble::{
-    BLEAdvertisementData, BLECharacteristic, BLEDevice, BLEServer, NimbleProperties, uuid128,
+    BLEAdvertisementData, BLECharacteristic, BLEDevice, BLEServer, NimbleProperties, OnWriteArgs,
+    uuid128,
 };
 use shakmaty::Color;
 
+use crate::access::{Access, ConnectionId, ConnectionLimits, Connections};
+use crate::app::{BoardNotifier, ClientEvent, CommandQueue};
 use crate::ble_protocol::{self, BleCommand, CommandResult, CommandSource, UNSET_BYTE, uuids};
 use crate::board_api;
+use crate::pairing::Token;
 
 use alloc::sync::Arc;
 extern crate alloc;
 
 type ChrHandle = Arc<Mutex<BLECharacteristic>>;
 
+/// Open connections, updated from the connect/disconnect callbacks.
+type SharedConnections = Arc<Mutex<Connections>>;
+
+/// Connections closed since the app last looked. Kept apart from the
+/// bounded command channel so a full channel cannot drop a disconnect.
+type SharedDisconnects = Arc<Mutex<Vec<ConnectionId>>>;
+
+/// Commands with the connection that wrote them.
+type CommandSender = mpsc::SyncSender<(ConnectionId, BleCommand)>;
+
 #[derive(Debug, thiserror::Error)]
 pub enum BleError {
     #[error("NimBLE error: {0}")]
@@ -21,6 +35,7 @@ pub enum BleError {
 }
 
 const ATT_ERR_REQUEST_NOT_SUPPORTED: u8 = 0x06;
+const ATT_ERR_WRITE_NOT_PERMITTED: u8 = 0x03;
 
 // ---------------------------------------------------------------------------
 // Handle wrappers
@@ -80,6 +95,19 @@ impl PositionHandle {
 struct LastMoveHandle(ChrHandle);
 
 impl LastMoveHandle {
+    fn update(&self, color: Color, uci: &str, checksum: board_api::StateChecksum) {
+        let encoded = ble_protocol::encode_move_with_checksum(color, uci, checksum);
+        self.0.lock().set_value(&encoded).notify();
+    }
+
+    fn reset(&self) {
+        self.0.lock().set_value(&[]).notify();
+    }
+}
+
+struct PendingPromotionHandle(ChrHandle);
+
+impl PendingPromotionHandle {
     fn update(&self, color: Color, uci: &str) {
         let encoded = ble_protocol::encode_move(color, uci);
         self.0.lock().set_value(&encoded).notify();
@@ -90,15 +118,39 @@ impl LastMoveHandle {
     }
 }
 
+struct GameEventHandle(ChrHandle);
+
+impl GameEventHandle {
+    fn notify(&self, event: &board_api::GameEvent) {
+        let encoded = ble_protocol::encode_game_event(event);
+        self.0.lock().set_value(&encoded).notify();
+    }
+}
+
 struct MovePlayedHandle(ChrHandle);
 
 impl MovePlayedHandle {
-    fn notify(&self, color: Color, uci: &str) {
-        let encoded = ble_protocol::encode_move(color, uci);
+    fn notify(&self, color: Color, uci: &str, checksum: board_api::StateChecksum) {
+        let encoded = ble_protocol::encode_move_with_checksum(color, uci, checksum);
         self.0.lock().set_value(&encoded).notify();
     }
 }
 
+struct PairingTokenHandle(ChrHandle, SharedConnections);
+
+impl PairingTokenHandle {
+    /// Only controllers can pair, so spectators never see the token.
+    fn notify(&self, token: Token) {
+        let controllers: Vec<u16> = self.1.lock().ids(Access::Controller).collect();
+        let chr = self.0.lock();
+        for handle in controllers {
+            if let Err(e) = chr.notify_with(&token.to_le_bytes(), handle) {
+                log::warn!("Pairing token not sent to connection {handle}: {e:?}");
+            }
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // GameHandles — internal bundle returned from register_game_service
 // ---------------------------------------------------------------------------
@@ -111,6 +163,9 @@ struct GameHandles {
     position: PositionHandle,
     last_move: LastMoveHandle,
     move_played: MovePlayedHandle,
+    pending_promotion: PendingPromotionHandle,
+    game_event: GameEventHandle,
+    pairing_token: PairingTokenHandle,
 }
 
 // ---------------------------------------------------------------------------
@@ -118,7 +173,8 @@ struct GameHandles {
 // ---------------------------------------------------------------------------
 
 pub struct BleCommands {
-    rx: mpsc::Receiver<BleCommand>,
+    rx: mpsc::Receiver<(ConnectionId, BleCommand)>,
+    disconnects: SharedDisconnects,
 }
 
 impl std::fmt::Debug for BleCommands {
@@ -127,9 +183,15 @@ impl std::fmt::Debug for BleCommands {
     }
 }
 
-impl BleCommands {
-    pub fn try_recv(&self) -> Option<BleCommand> {
-        self.rx.try_recv().ok()
+impl CommandQueue for BleCommands {
+    /// Disconnects come first, so a later connection that reuses a handle
+    /// never inherits the authentication of the one that left.
+    fn try_recv(&mut self) -> Option<ClientEvent> {
+        if let Some(connection) = self.disconnects.lock().pop() {
+            return Some(ClientEvent::Disconnected(connection));
+        }
+        let (connection, cmd) = self.rx.try_recv().ok()?;
+        Some(ClientEvent::Command(connection, cmd))
     }
 }
 
@@ -141,6 +203,10 @@ pub struct BleNotifier {
     position: PositionHandle,
     last_move: LastMoveHandle,
     move_played: MovePlayedHandle,
+    pending_promotion: PendingPromotionHandle,
+    game_event: GameEventHandle,
+    pairing_token: PairingTokenHandle,
+    connections: SharedConnections,
 }
 
 impl std::fmt::Debug for BleNotifier {
@@ -149,19 +215,19 @@ impl std::fmt::Debug for BleNotifier {
     }
 }
 
-impl BleNotifier {
+impl BoardNotifier for BleNotifier {
     /// Update the game status characteristic and notify subscribers.
-    pub fn notify_game_status(&self, status: &board_api::GameStatus) {
+    fn notify_game_status(&mut self, status: &board_api::GameStatus) {
         self.game_status.notify(status);
     }
 
     /// Notify the command result characteristic.
-    pub fn notify_command_result(&self, result: &CommandResult) {
+    fn notify_command_result(&mut self, result: &CommandResult) {
         self.command_result.notify(result);
     }
 
     /// Update a player type characteristic (read+notify) after a game starts.
-    pub fn update_player_type(&self, color: Color, pt: board_api::PlayerType) {
+    fn update_player_type(&mut self, color: Color, pt: board_api::PlayerType) {
         match color {
             Color::White => self.white_player.update(pt),
             Color::Black => self.black_player.update(pt),
@@ -169,35 +235,60 @@ impl BleNotifier {
     }
 
     /// Notify the MovePlayed characteristic (notify-only) when a move is played.
-    pub fn notify_move_played(&self, color: Color, uci: &str) {
-        self.move_played.notify(color, uci);
+    fn notify_move_played(&mut self, color: Color, uci: &str, checksum: board_api::StateChecksum) {
+        self.move_played.notify(color, uci, checksum);
     }
 
     /// Update the Position (FEN) characteristic and notify subscribers.
-    pub fn update_position(&self, fen: &str) {
+    fn update_position(&mut self, fen: &str) {
         self.position.update(fen);
     }
 
     /// Update the LastMove characteristic and notify subscribers.
-    pub fn update_last_move(&self, color: Color, uci: &str) {
-        self.last_move.update(color, uci);
+    fn update_last_move(&mut self, color: Color, uci: &str, checksum: board_api::StateChecksum) {
+        self.last_move.update(color, uci, checksum);
     }
 
     /// Reset both player type characteristics to UNSET_BYTE (called after game ends).
-    pub fn reset_player_types(&self) {
+    fn reset_player_types(&mut self) {
         self.white_player.reset();
         self.black_player.reset();
     }
 
     /// Clear the Position characteristic (called when game ends).
-    pub fn reset_position(&self) {
+    fn reset_position(&mut self) {
         self.position.reset();
     }
 
     /// Clear the LastMove characteristic (called when game ends).
-    pub fn reset_last_move(&self) {
+    fn reset_last_move(&mut self) {
         self.last_move.reset();
     }
+
+    /// Update the PendingPromotion characteristic and notify subscribers.
+    fn update_pending_promotion(&mut self, color: Color, uci: &str) {
+        self.pending_promotion.update(color, uci);
+    }
+
+    /// Clear the PendingPromotion characteristic once a piece is chosen.
+    fn reset_pending_promotion(&mut self) {
+        self.pending_promotion.reset();
+    }
+
+    /// Notify the GameEvent characteristic (notify-only).
+    fn notify_game_event(&mut self, event: &board_api::GameEvent) {
+        self.game_event.notify(event);
+    }
+
+    /// Whether a controller is connected; spectators do not count.
+    fn is_connected(&self) -> bool {
+        self.connections.lock().count(Access::Controller) > 0
+    }
+
+    /// Notify the PairingToken characteristic (notify-only).
+    fn notify_pairing_token(&mut self, token: Token) {
+        self.pairing_token.notify(token);
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -205,12 +296,17 @@ impl BleNotifier {
 // ---------------------------------------------------------------------------
 
 /// Returns [`BleCommands`] (inbound) and [`BleNotifier`] (outbound).
-pub fn start_ble() -> Result<(BleCommands, BleNotifier), BleError> {
+///
+/// Clients are admitted up to `limits` (see [`crate::access`]); writes from
+/// spectators are rejected with an ATT error.
+pub fn start_ble(limits: ConnectionLimits) -> Result<(BleCommands, BleNotifier), BleError> {
     let device = BLEDevice::take();
 
-    let (tx, rx) = mpsc::sync_channel::<BleCommand>(8);
+    let (tx, rx) = mpsc::sync_channel(8);
+    let disconnects = SharedDisconnects::default();
 
     let server = device.get_server();
+    let connections: SharedConnections = Arc::new(Mutex::new(Connections::new(limits)));
 
     let GameHandles {
         white_player,
@@ -220,19 +316,47 @@ pub fn start_ble() -> Result<(BleCommands, BleNotifier), BleError> {
         position,
         last_move,
         move_played,
-    } = register_game_service(server, &tx);
+        pending_promotion,
+        game_event,
+        pairing_token,
+    } = register_game_service(server, &tx, &connections);
 
     {
         let cmd_result = command_result.clone();
-        server.on_connect(move |_server, desc| {
-            log::info!("BLE client connected: {:?}", desc);
-            cmd_result.reset();
+        let connections = connections.clone();
+        server.on_connect(move |server, desc| {
+            let handle = desc.conn_handle();
+            let admitted = connections.lock().connect(handle);
+            match admitted {
+                Ok(access) => {
+                    log::info!("BLE client connected as {access:?}: {:?}", desc);
+                    if access == Access::Controller {
+                        cmd_result.reset();
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Refusing BLE client: {e}");
+                    if let Err(e) = server.disconnect(handle) {
+                        log::warn!("BLE disconnect failed: {e:?}");
+                    }
+                }
+            }
+            // Advertising stops on connect; keep it up for the next client.
+            if let Err(e) = BLEDevice::take().get_advertising().lock().start() {
+                log::warn!("BLE advertising restart failed: {e:?}");
+            }
         });
     }
 
-    server.on_disconnect(|_desc, reason| {
-        log::info!("BLE client disconnected ({:?})", reason);
-    });
+    {
+        let connections = connections.clone();
+        let disconnects = disconnects.clone();
+        server.on_disconnect(move |desc, reason| {
+            log::info!("BLE client disconnected ({:?})", reason);
+            connections.lock().disconnect(desc.conn_handle());
+            disconnects.lock().push(desc.conn_handle());
+        });
+    }
 
     let advertising = device.get_advertising();
     advertising.lock().set_data(
@@ -245,7 +369,7 @@ pub fn start_ble() -> Result<(BleCommands, BleNotifier), BleError> {
     log::info!("BLE server started");
 
     Ok((
-        BleCommands { rx },
+        BleCommands { rx, disconnects },
         BleNotifier {
             game_status,
             command_result,
@@ -254,6 +378,10 @@ pub fn start_ble() -> Result<(BleCommands, BleNotifier), BleError> {
             position,
             last_move,
             move_played,
+            pending_promotion,
+            game_event,
+            pairing_token,
+            connections,
         },
     ))
 }
@@ -262,7 +390,23 @@ pub fn start_ble() -> Result<(BleCommands, BleNotifier), BleError> {
 // register_game_service
 // ---------------------------------------------------------------------------
 
-fn register_game_service(server: &mut BLEServer, tx: &mpsc::SyncSender<BleCommand>) -> GameHandles {
+/// Whether the writing connection may send commands; rejects the write
+/// if not.
+fn writable(connections: &SharedConnections, args: &mut OnWriteArgs) -> bool {
+    let allowed = connections.lock().check_write(args.desc().conn_handle());
+    if let Err(e) = allowed {
+        log::warn!("Rejected BLE write: {e}");
+        args.reject_with_error_code(ATT_ERR_WRITE_NOT_PERMITTED);
+        return false;
+    }
+    true
+}
+
+fn register_game_service(
+    server: &mut BLEServer,
+    tx: &CommandSender,
+    connections: &SharedConnections,
+) -> GameHandles {
     let game_svc = server.create_service(uuid128!(uuids::GAME_SERVICE));
     let mut svc = game_svc.lock();
 
@@ -285,10 +429,14 @@ fn register_game_service(server: &mut BLEServer, tx: &mpsc::SyncSender<BleComman
         svc.create_characteristic(uuid128!(uuids::START_GAME), NimbleProperties::WRITE);
     {
         let tx = tx.clone();
+        let connections = connections.clone();
         start_game_chr.lock().on_write(move |args| {
+            if !writable(&connections, args) {
+                return;
+            }
             match BleCommand::parse_start_game(args.recv_data()) {
                 Ok(cmd) => {
-                    if let Err(e) = tx.try_send(cmd) {
+                    if let Err(e) = tx.try_send((args.desc().conn_handle(), cmd)) {
                         log::warn!("BLE command channel full (start_game): {e}");
                     }
                 }
@@ -300,15 +448,19 @@ fn register_game_service(server: &mut BLEServer, tx: &mpsc::SyncSender<BleComman
         });
     }
 
-    // Match Control — write; carries resign / cancel actions.
+    // Match Control — write; carries resign / cancel / result / promotion actions.
     let match_control_chr =
         svc.create_characteristic(uuid128!(uuids::MATCH_CONTROL), NimbleProperties::WRITE);
     {
         let tx = tx.clone();
+        let connections = connections.clone();
         match_control_chr.lock().on_write(move |args| {
+            if !writable(&connections, args) {
+                return;
+            }
             match BleCommand::parse_match_control(args.recv_data()) {
                 Ok(cmd) => {
-                    if let Err(e) = tx.try_send(cmd) {
+                    if let Err(e) = tx.try_send((args.desc().conn_handle(), cmd)) {
                         log::warn!("BLE command channel full (match_control): {e}");
                     }
                 }
@@ -325,10 +477,14 @@ fn register_game_service(server: &mut BLEServer, tx: &mpsc::SyncSender<BleComman
         svc.create_characteristic(uuid128!(uuids::SUBMIT_MOVE), NimbleProperties::WRITE);
     {
         let tx = tx.clone();
+        let connections = connections.clone();
         submit_move_chr.lock().on_write(move |args| {
+            if !writable(&connections, args) {
+                return;
+            }
             match BleCommand::parse_submit_move(args.recv_data()) {
                 Ok(cmd) => {
-                    if let Err(e) = tx.try_send(cmd) {
+                    if let Err(e) = tx.try_send((args.desc().conn_handle(), cmd)) {
                         log::warn!("BLE command channel full (submit_move): {e}");
                     }
                 }
@@ -378,6 +534,23 @@ fn register_game_service(server: &mut BLEServer, tx: &mpsc::SyncSender<BleComman
     let move_played_chr =
         svc.create_characteristic(uuid128!(uuids::MOVE_PLAYED), NimbleProperties::NOTIFY);
 
+    // Pending Promotion — read+notify; encoded move bytes (empty when none).
+    let pending_promotion_chr = svc.create_characteristic(
+        uuid128!(uuids::PENDING_PROMOTION),
+        NimbleProperties::READ | NimbleProperties::NOTIFY,
+    );
+    pending_promotion_chr.lock().set_value(&[]);
+
+    // Game Event — notify-only; fired for one-off events such as a player
+    // confirming readiness.
+    let game_event_chr =
+        svc.create_characteristic(uuid128!(uuids::GAME_EVENT), NimbleProperties::NOTIFY);
+
+    // Pairing Token — notify-only; the token for a client that just paired,
+    // as a u64 LE.
+    let pairing_token_chr =
+        svc.create_characteristic(uuid128!(uuids::PAIRING_TOKEN), NimbleProperties::NOTIFY);
+
     GameHandles {
         white_player: PlayerTypeHandle(white_player_chr),
         black_player: PlayerTypeHandle(black_player_chr),
@@ -386,5 +559,8 @@ fn register_game_service(server: &mut BLEServer, tx: &mpsc::SyncSender<BleComman
         position: PositionHandle(position_chr),
         last_move: LastMoveHandle(last_move_chr),
         move_played: MovePlayedHandle(move_played_chr),
+        pending_promotion: PendingPromotionHandle(pending_promotion_chr),
+        game_event: GameEventHandle(game_event_chr),
+        pairing_token: PairingTokenHandle(pairing_token_chr, connections.clone()),
     }
 }
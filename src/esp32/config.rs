@@ -1,38 +1,73 @@
-/// Simple RGB color for WS2812 LEDs.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Rgb8 {
-    pub r: u8,
-    pub g: u8,
-    pub b: u8,
-}
-
-impl Rgb8 {
-    pub const fn new(r: u8, g: u8, b: u8) -> Self {
-        Self { r, g, b }
-    }
-}
+use crate::access::ConnectionLimits;
+use crate::debounce::Stability;
+pub use crate::frame::{LedPalette, Rgb8};
+use crate::hardware::HardwareRevision;
+use crate::mode::BootBehavior;
+use crate::pins::{PinAssignment, PinMode};
+use crate::ws2812::SquareLeds;
+use shakmaty::Bitboard;
 
 /// Sensor configuration for ADC thresholds and timing.
 #[derive(Debug, Clone, Copy)]
 pub struct SensorConfig {
     /// Resting ADC output (mV) with no magnet. DRV5055A3 is ratiometric at VCC/2.
     pub baseline_mv: u16,
+    /// Resting output of each square's sensor, indexed by `Square`; all
+    /// `baseline_mv` until the board is calibrated.
+    pub square_baselines: [u16; 64],
     /// Minimum deviation from baseline (mV) to register a piece.
     pub threshold_mv: u16,
     /// Delay (ms) after switching mux address lines to let the analog signal settle.
     pub settle_delay_ms: u32,
+    /// ADC samples averaged into each square's reading.
+    pub samples: u8,
+    /// Squares with a failed sensor. They always read empty and the game
+    /// infers their occupancy instead.
+    pub dead_squares: Bitboard,
 }
 
 impl Default for SensorConfig {
     fn default() -> Self {
+        Self::for_revision(HardwareRevision::default())
+    }
+}
+
+impl SensorConfig {
+    /// Uncalibrated starting point for boards of `revision`.
+    pub fn for_revision(revision: HardwareRevision) -> Self {
+        let preset = revision.preset();
         Self {
-            baseline_mv: 1440,
-            threshold_mv: 100,
-            settle_delay_ms: 2,
+            baseline_mv: preset.baseline_mv,
+            square_baselines: [preset.baseline_mv; 64],
+            threshold_mv: preset.threshold_mv,
+            settle_delay_ms: preset.settle_delay_ms,
+            samples: preset.samples,
+            dead_squares: Bitboard::EMPTY,
         }
     }
 }
 
+/// Hardware revision of this board (see [`crate::hardware`]), set at build
+/// time with the `HARDWARE_REVISION` environment variable (e.g. `rev1-thick`
+/// in `.env`). Unset, boards are taken to be the first revision.
+pub const HARDWARE_REVISION: Option<&str> = option_env!("HARDWARE_REVISION");
+
+/// The configured [`HARDWARE_REVISION`], falling back to the default for an
+/// unknown name.
+pub fn hardware_revision() -> HardwareRevision {
+    let Some(name) = HARDWARE_REVISION else {
+        return HardwareRevision::default();
+    };
+    HardwareRevision::from_name(name).unwrap_or_else(|| {
+        let fallback = HardwareRevision::default();
+        log::warn!(
+            "Unknown hardware revision '{name}', using {}",
+            fallback.name()
+        );
+        fallback
+    })
+}
+
 /// Per-board sensor calibration derived from the diagnostics binary.
 ///
 /// Stored in NVS and loaded by the production firmware to replace
@@ -41,6 +76,21 @@ impl Default for SensorConfig {
 pub struct SensorCalibration {
     pub baseline_mv: u16,
     pub threshold_mv: u16,
+    /// Each square's empty-board reading; `None` for calibrations that
+    /// only measured the average.
+    pub square_baselines: Option<[u16; 64]>,
+}
+
+impl SensorCalibration {
+    /// `config` with this calibration applied.
+    pub fn apply(&self, config: SensorConfig) -> SensorConfig {
+        SensorConfig {
+            baseline_mv: self.baseline_mv,
+            threshold_mv: self.threshold_mv,
+            square_baselines: self.square_baselines.unwrap_or([self.baseline_mv; 64]),
+            ..config
+        }
+    }
 }
 
 /// Error type for NVS calibration load/save operations.
@@ -51,6 +101,8 @@ pub struct CalibrationError(#[from] pub esp_idf_svc::sys::EspError);
 const CAL_NAMESPACE: &str = "cal";
 const KEY_CAL_BASELINE: &str = "cal_baseline";
 const KEY_CAL_THRESHOLD: &str = "cal_threshold";
+/// The 64 square baselines as little-endian `u16`s.
+const KEY_CAL_SQUARES: &str = "cal_squares";
 
 #[cfg(target_os = "espidf")]
 impl SensorCalibration {
@@ -79,9 +131,23 @@ impl SensorCalibration {
             .map_err(CalibrationError)?
             .unwrap_or(SensorConfig::default().threshold_mv);
 
+        let mut buf = [0u8; 128];
+        let square_baselines = nvs
+            .get_blob(KEY_CAL_SQUARES, &mut buf)
+            .map_err(CalibrationError)?
+            .filter(|blob| blob.len() == buf.len())
+            .map(|blob| {
+                let mut baselines = [0u16; 64];
+                for (baseline, bytes) in baselines.iter_mut().zip(blob.chunks_exact(2)) {
+                    *baseline = u16::from_le_bytes([bytes[0], bytes[1]]);
+                }
+                baselines
+            });
+
         Ok(Some(SensorCalibration {
             baseline_mv,
             threshold_mv,
+            square_baselines,
         }))
     }
 
@@ -97,49 +163,103 @@ impl SensorCalibration {
             .map_err(CalibrationError)?;
         nvs.set_u16(KEY_CAL_THRESHOLD, self.threshold_mv)
             .map_err(CalibrationError)?;
+        match self.square_baselines {
+            Some(baselines) => {
+                let blob: Vec<u8> = baselines.iter().flat_map(|mv| mv.to_le_bytes()).collect();
+                nvs.set_blob(KEY_CAL_SQUARES, &blob)
+                    .map_err(CalibrationError)?;
+            }
+            // Baselines from an earlier calibration no longer match.
+            None => {
+                nvs.remove(KEY_CAL_SQUARES).map_err(CalibrationError)?;
+            }
+        }
         Ok(())
     }
 }
 
+/// How the square LEDs are wired (see [`crate::ws2812::SquareLeds`]).
+pub const SQUARE_LEDS: SquareLeds = SquareLeds::Pair;
+
+/// Edge notification LEDs chained after the square LEDs on the same
+/// strip; 0 for boards without an edge ring.
+pub const EDGE_LEDS: usize = 0;
+
+/// How long game feedback must hold before the LEDs show it; hides the
+/// intermediate readings of fast two-handed moves (two sensor ticks).
+pub const FEEDBACK_SETTLE: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// How long a sensor reading must hold before move detection sees it;
+/// filters pieces slid across a square and Hall sensors near their
+/// threshold (one extra sensor tick).
+pub const SENSOR_STABILITY: Stability = Stability::Readings(2);
+
+/// Flight recorder ring file. Recording is on when the file can be opened,
+/// i.e. when an SD card is mounted at `/sdcard`.
+pub const FLIGHT_RECORDER_PATH: &str = "/sdcard/flight.rec";
+
+/// Flight recorder size in 512-byte blocks (16 MiB, thousands of games).
+/// The file is formatted once, on the first boot with the card inserted.
+pub const FLIGHT_RECORDER_BLOCKS: u32 = 1 << 15;
+
+/// Root certificate (PEM) for a custom relay server, set at build time with
+/// the `RELAY_CA_PEM` environment variable (e.g. in `.env`). Unset, relays
+/// must chain to the bundled roots like the official backends.
+pub const RELAY_CA_PEM: Option<&str> = option_env!("RELAY_CA_PEM");
+
+/// Base URL of the rendezvous server for board-to-board games (see
+/// `net::relay`), set at build time with the `RELAY_URL` environment
+/// variable. Unset, the board has no relay.
+pub const RELAY_URL: Option<&str> = option_env!("RELAY_URL");
+
 /// Display configuration for LED colors.
 #[derive(Debug, Clone)]
 pub struct DisplayConfig {
     pub palette: LedPalette,
 }
 
-/// LED colors for each feedback type.
-///
-/// All values are RGB8 with conservative brightness to avoid
-/// washing out through the board surface.
-#[derive(Debug, Clone, Copy)]
-pub struct LedPalette {
-    pub off: Rgb8,
-    pub destination: Rgb8,
-    pub capture: Rgb8,
-    pub origin: Rgb8,
-    pub check: Rgb8,
-    pub checker: Rgb8,
-    pub victory: Rgb8,
-    pub stalemate: Rgb8,
-    pub status_pending: Rgb8,
-    pub status_success: Rgb8,
-    pub status_failure: Rgb8,
-}
+/// What the board does at power-up: offer the day's puzzle, so picking the
+/// board up always gives something to do. The day comes from the system
+/// clock, which counts from the epoch until WiFi syncs it, so boards
+/// without network time start on the first puzzle.
+pub const BOOT_BEHAVIOR: BootBehavior = BootBehavior::DailyPuzzle;
 
-impl Default for LedPalette {
-    fn default() -> Self {
-        Self {
-            off: Rgb8::new(0, 0, 0),
-            destination: Rgb8::new(0, 20, 0),
-            capture: Rgb8::new(20, 10, 0),
-            origin: Rgb8::new(0, 0, 20),
-            check: Rgb8::new(20, 0, 0),
-            checker: Rgb8::new(20, 0, 0),
-            victory: Rgb8::new(0, 20, 0),
-            stalemate: Rgb8::new(20, 15, 0),
-            status_pending: Rgb8::new(0, 0, 20),
-            status_success: Rgb8::new(0, 20, 0),
-            status_failure: Rgb8::new(20, 0, 0),
-        }
-    }
-}
+/// Clients admitted at once over BLE (see [`crate::access`]): one
+/// controller and a few read-only spectators, e.g. a club stream. NimBLE
+/// allows one connection more (`CONFIG_BT_NIMBLE_MAX_CONNECTIONS`), so an
+/// extra client is refused cleanly instead of timing out.
+pub const CONNECTION_LIMITS: ConnectionLimits = ConnectionLimits {
+    controllers: 1,
+    spectators: 3,
+};
+
+/// Keep the board on WiFi (see [`crate::wifi`]). Without stored
+/// credentials it opens the setup access point at boot.
+pub const WIFI_ENABLED: bool = true;
+
+/// Shared I2C bus speed (see [`crate::i2c_bus`]); every device on it must
+/// support it.
+pub const I2C_BAUDRATE_HZ: u32 = 400_000;
+
+/// How long a device may hold SCL low (clock stretching) before a transfer
+/// times out and counts as a bus failure.
+pub const I2C_CLOCK_STRETCH_LIMIT: std::time::Duration = std::time::Duration::from_millis(1);
+
+/// How long one I2C transfer may take, including waiting for the
+/// controller.
+pub const I2C_TRANSFER_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Every GPIO the firmware claims, checked with [`crate::pins::validate`]
+/// at boot before any driver starts. Keep it in step with the pins
+/// `main` hands to the drivers.
+pub const PIN_ASSIGNMENTS: &[PinAssignment] = &[
+    PinAssignment::new("LED data", 2, PinMode::Output),
+    PinAssignment::new("mux 0 signal", 4, PinMode::Analog),
+    PinAssignment::new("mux 1 signal", 5, PinMode::Analog),
+    PinAssignment::new("mux 2 signal", 6, PinMode::Analog),
+    PinAssignment::new("mux 3 signal", 7, PinMode::Analog),
+    PinAssignment::new("mux S0", 9, PinMode::Output),
+    PinAssignment::new("mux S1", 10, PinMode::Output),
+    PinAssignment::new("mux S2", 11, PinMode::Output),
+    PinAssignment::new("mux S3", 12, PinMode::Output),
+];
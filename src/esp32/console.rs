@@ -0,0 +1,44 @@
+//! The serial console as a [`log::Log`], behind
+//! [`crate::log_filter::FilteredLogger`].
+//!
+//! `EspLogger` filters by ESP-IDF's per-tag levels, which cannot be raised
+//! for a whole Rust module at once; this logger prints every record it is
+//! given and leaves the filtering to [`crate::log_filter`]. Lines follow
+//! ESP-IDF's `I (ms) tag: message` layout so monitors colour them the same.
+
+use std::io::Write;
+
+use esp_idf_svc::sys::esp_log_timestamp;
+use log::{Level, Log, Metadata, Record};
+
+#[derive(Debug, Default)]
+pub struct ConsoleLogger;
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let letter = match record.level() {
+            Level::Error => 'E',
+            Level::Warn => 'W',
+            Level::Info => 'I',
+            Level::Debug => 'D',
+            Level::Trace => 'V',
+        };
+        // SAFETY: reads the tick counter; callable from any task.
+        let ms = unsafe { esp_log_timestamp() };
+        let mut out = std::io::stdout().lock();
+        let _ = writeln!(
+            out,
+            "{letter} ({ms}) {}: {}",
+            record.target(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stdout().flush();
+    }
+}
@@ -4,19 +4,30 @@ use esp_idf_svc::hal::gpio::OutputPin;
 use esp_idf_svc::hal::rmt::config::{MemoryAccess, TransmitConfig, TxChannelConfig};
 use esp_idf_svc::hal::rmt::encoder::{BytesEncoder, BytesEncoderConfig, RawEncoder};
 use esp_idf_svc::hal::rmt::{PinState, Symbol, TxChannelDriver};
+use esp_idf_svc::hal::temp_sensor::TempSensorDriver;
 use esp_idf_svc::hal::units::FromValueType;
 
-use crate::BoardDisplay;
-use crate::esp32::config::{LedPalette, Rgb8};
-use crate::feedback::{BoardFeedback, SquareFeedback, StatusKind};
+use std::time::Instant;
 
-const NUM_LEDS: usize = 128;
-const LEDS_PER_ROW: usize = 16;
+use shakmaty::Square;
+
+use crate::animation::{Animation, Animator};
+use crate::edge::{EdgeFeedback, EdgeLayout};
+use crate::feedback::BoardFeedback;
+use crate::frame::{Frame, LedPalette, Rgb8};
+use crate::power::CurrentLimit;
+use crate::settings::DisplaySettings;
+use crate::thermal::{ThermalConfig, ThermalThrottle};
+use crate::ws2812::{self, SquareLeds, brightness_level};
+use crate::{BoardDisplay, EdgeDisplay};
 
 /// RMT tick resolution for WS2812 timing. 10 MHz gives 100ns per tick,
 /// sufficient for the ~300-900ns pulse widths in the WS2812 protocol.
 const RMT_RESOLUTION_HZ: u32 = 10_000_000;
 
+/// How often the temperature is sampled while frames are being shown.
+const THERMAL_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum LedDisplayError {
     #[error("LED driver initialization failed: {0}")]
@@ -27,52 +38,79 @@ pub enum LedDisplayError {
 
 /// WS2812 LED strip driven via the ESP32 RMT peripheral.
 ///
-/// 128 LEDs in a snake pattern: 16 per row (2 per square), 8 rows.
-/// Maps [`SquareFeedback`] variants to colors via a configurable [`LedPalette`].
+/// Square LEDs come first, wired as [`SquareLeds::Pair`] (128 LEDs)
+/// unless [`Self::with_square_leds`] says otherwise. The brightness setting
+/// goes through [`brightness_level`]'s gamma curve. Feedback is rendered to a [`crate::frame::Frame`] through a configurable
+/// [`LedPalette`] and the [`Animator`], then expanded to LEDs. Edge LEDs,
+/// if the board has them, are chained after the square LEDs (see
+/// [`Self::with_edge`]).
 pub struct Esp32LedDisplay<'d> {
     channel: TxChannelDriver<'d>,
     encoder: BytesEncoder,
-    buffer: [Rgb8; NUM_LEDS],
+    square_leds: SquareLeds,
+    /// Square LEDs followed by edge LEDs.
+    buffer: Vec<Rgb8>,
+    /// `buffer` as GRB bytes for the strip, reused between frames.
+    grb_bytes: Vec<u8>,
+    edge: EdgeLayout,
+    /// Edge colors before scaling, reused between frames.
+    edge_colors: Vec<Rgb8>,
     palette: LedPalette,
+    /// User brightness out of 255, before thermal throttling.
+    brightness: u8,
+    animator: Animator,
+    started: Instant,
+    thermal: Option<ThermalMonitor<'d>>,
+    current_limit: CurrentLimit,
 }
 
-/// Map a board square to its two LED indices in the snake-wired strip.
-///
-/// Each square has 2 LEDs. Within a row of 16 LEDs, the square at
-/// column `c` maps to indices `base + c` and `base + 15 - c`, where
-/// `base = row * 16`. This creates paired LEDs that converge toward
-/// the center of each row.
-fn leds_for_square(sq: shakmaty::Square) -> (usize, usize) {
-    let row = sq.rank() as usize;
-    let col = sq.file() as usize;
-    let base = row * LEDS_PER_ROW;
-    (base + col, base + 15 - col)
+/// Internal temperature sensor plus throttle state.
+struct ThermalMonitor<'d> {
+    sensor: TempSensorDriver<'d>,
+    throttle: ThermalThrottle,
+    last_sample: Option<Instant>,
 }
 
-/// Build WS2812 bit symbols for the RMT encoder.
-///
-/// WS2812 protocol: each bit is a high pulse followed by a low pulse.
-/// - Bit 0: ~400ns high, ~850ns low
-/// - Bit 1: ~800ns high, ~450ns low
+impl ThermalMonitor<'_> {
+    /// Brightness for the next frame, sampling the sensor if due.
+    fn level(&mut self) -> u8 {
+        let due = self
+            .last_sample
+            .is_none_or(|t| t.elapsed() >= THERMAL_SAMPLE_INTERVAL);
+        if due {
+            self.last_sample = Some(Instant::now());
+            match self.sensor.get_celsius() {
+                Ok(c) => return self.throttle.update([c]),
+                Err(e) => log::warn!("Temperature read failed: {e}"),
+            }
+        }
+        self.throttle.level()
+    }
+}
+
+/// User brightness dimmed further by the thermal throttle.
+fn combined_level(brightness: u8, thermal: u8) -> u8 {
+    (u16::from(brightness) * u16::from(thermal) / 255) as u8
+}
+
+/// Build WS2812 bit symbols for the RMT encoder, from the timings in
+/// [`crate::ws2812`]. The encoder sends the same symbol stream as
+/// [`ws2812::Ws2812Encoder`], which tests pin on the host.
 fn ws2812_bytes_encoder_config() -> Result<BytesEncoderConfig, LedDisplayError> {
     let resolution = RMT_RESOLUTION_HZ.Hz();
-    let bit0 = Symbol::new_with(
-        resolution,
-        PinState::High,
-        Duration::from_nanos(400),
-        PinState::Low,
-        Duration::from_nanos(850),
-    )
-    .map_err(|e| LedDisplayError::DriverInit(format!("bit0 symbol: {e}")))?;
-
-    let bit1 = Symbol::new_with(
-        resolution,
-        PinState::High,
-        Duration::from_nanos(800),
-        PinState::Low,
-        Duration::from_nanos(450),
-    )
-    .map_err(|e| LedDisplayError::DriverInit(format!("bit1 symbol: {e}")))?;
+    let symbol = |pulse: ws2812::Pulse| {
+        Symbol::new_with(
+            resolution,
+            PinState::High,
+            pulse.high,
+            PinState::Low,
+            pulse.low,
+        )
+    };
+    let bit0 = symbol(ws2812::BIT0)
+        .map_err(|e| LedDisplayError::DriverInit(format!("bit0 symbol: {e}")))?;
+    let bit1 = symbol(ws2812::BIT1)
+        .map_err(|e| LedDisplayError::DriverInit(format!("bit1 symbol: {e}")))?;
 
     Ok(BytesEncoderConfig {
         bit0,
@@ -122,30 +160,90 @@ impl<'d> Esp32LedDisplay<'d> {
         Ok(Self {
             channel,
             encoder,
-            buffer: [palette.off; NUM_LEDS],
+            square_leds: SquareLeds::Pair,
+            buffer: vec![palette.off; SquareLeds::Pair.count()],
+            grb_bytes: Vec::with_capacity(SquareLeds::Pair.count() * 3),
+            edge: EdgeLayout::default(),
+            edge_colors: Vec::new(),
             palette,
+            brightness: u8::MAX,
+            animator: Animator::new(),
+            started: Instant::now(),
+            thermal: None,
+            current_limit: CurrentLimit::default(),
         })
     }
 
-    fn color_for(&self, feedback: SquareFeedback) -> Rgb8 {
-        match feedback {
-            SquareFeedback::Destination => self.palette.destination,
-            SquareFeedback::Capture => self.palette.capture,
-            SquareFeedback::Origin => self.palette.origin,
-            SquareFeedback::Check => self.palette.check,
-            SquareFeedback::Checker => self.palette.checker,
-            SquareFeedback::Victory => self.palette.victory,
-            SquareFeedback::Stalemate => self.palette.stalemate,
+    /// Crossfade between feedback frames over `duration` instead of the default.
+    pub fn with_fade(mut self, duration: Duration) -> Self {
+        self.animator = Animator::with_fade(duration);
+        self
+    }
+
+    /// Drive square LEDs wired as `leds`. Also sets the current budget's
+    /// LEDs per square, so call it before [`Self::with_current_limit`].
+    pub fn with_square_leds(mut self, leds: SquareLeds) -> Self {
+        self.buffer
+            .resize(leds.count() + self.edge.leds, self.palette.off);
+        self.grb_bytes.reserve(self.buffer.len() * 3);
+        self.square_leds = leds;
+        self.current_limit.leds_per_square = leds.per_square() as u32;
+        self
+    }
+
+    /// Drive `edge` LEDs chained after the square LEDs on the same strip.
+    pub fn with_edge(mut self, edge: EdgeLayout) -> Self {
+        self.buffer
+            .resize(self.square_leds.count() + edge.leds, self.palette.off);
+        self.grb_bytes.reserve(edge.leds * 3);
+        self.edge = edge;
+        self.edge_colors = vec![self.palette.off; edge.leds];
+        self
+    }
+
+    /// Replace the default LED current budget (e.g. on a stronger supply).
+    pub fn with_current_limit(mut self, limit: CurrentLimit) -> Self {
+        self.current_limit = limit;
+        self
+    }
+
+    /// Dim the LEDs when the enabled internal temperature sensor runs hot.
+    pub fn with_thermal_throttle(
+        mut self,
+        sensor: TempSensorDriver<'d>,
+        config: ThermalConfig,
+    ) -> Self {
+        self.thermal = Some(ThermalMonitor {
+            sensor,
+            throttle: ThermalThrottle::new(config),
+            last_sample: None,
+        });
+        self
+    }
+
+    /// Dim `frame` for brightness, heat and current, then send it.
+    fn write_frame(&mut self, mut frame: Frame) -> Result<(), LedDisplayError> {
+        let thermal = self.thermal.as_mut().map_or(u8::MAX, ThermalMonitor::level);
+        frame = frame.scaled(combined_level(brightness_level(self.brightness), thermal));
+        let frame = self.current_limit.limit(frame);
+        for (sq, color) in frame.iter() {
+            for led in self.square_leds.indices(sq) {
+                self.buffer[led] = color;
+            }
         }
+
+        self.flush()
     }
 
     fn flush(&mut self) -> Result<(), LedDisplayError> {
-        let grb_bytes: Vec<u8> = self.buffer.iter().flat_map(|c| [c.g, c.r, c.b]).collect();
+        self.grb_bytes.clear();
+        self.grb_bytes
+            .extend(self.buffer.iter().flat_map(|c| [c.g, c.r, c.b]));
 
         send_and_wait(
             &mut self.channel,
             &mut self.encoder,
-            &grb_bytes,
+            &self.grb_bytes,
             &TransmitConfig::default(),
             Some(Duration::from_millis(1000)),
         )
@@ -155,38 +253,55 @@ impl<'d> Esp32LedDisplay<'d> {
     }
 }
 
-/// Squares forming a hollow 4×4 ring in the board center (c3–f6, excluding d4–e5).
-const STATUS_RING: [shakmaty::Square; 12] = {
-    use shakmaty::Square::*;
-    [C3, D3, E3, F3, C4, F4, C5, F5, C6, D6, E6, F6]
-};
-
 impl BoardDisplay for Esp32LedDisplay<'_> {
     type Error = LedDisplayError;
 
     fn show(&mut self, feedback: &BoardFeedback) -> Result<(), Self::Error> {
-        self.buffer.fill(self.palette.off);
-
-        if let Some(status) = feedback.status() {
-            let color = match status {
-                StatusKind::Pending => self.palette.status_pending,
-                StatusKind::Success => self.palette.status_success,
-                StatusKind::Failure => self.palette.status_failure,
-            };
-            for sq in STATUS_RING {
-                let (led1, led2) = leds_for_square(sq);
-                self.buffer[led1] = color;
-                self.buffer[led2] = color;
-            }
-        } else {
-            for (sq, fb) in feedback.squares() {
-                let color = self.color_for(fb);
-                let (led1, led2) = leds_for_square(sq);
-                self.buffer[led1] = color;
-                self.buffer[led2] = color;
-            }
+        let frame = self
+            .animator
+            .render(feedback, &self.palette, self.started.elapsed());
+        self.write_frame(frame)
+    }
+
+    fn set_squares(&mut self, squares: &[(Square, Rgb8)]) -> Result<(), Self::Error> {
+        let mut frame = Frame::filled(self.palette.off);
+        for &(square, color) in squares {
+            frame.set(square, color);
         }
+        self.write_frame(frame)
+    }
 
-        self.flush()
+    fn play(&mut self, animation: Animation) {
+        self.animator.play(animation, self.started.elapsed());
+    }
+
+    fn apply_settings(&mut self, settings: &DisplaySettings) {
+        self.palette = settings.theme.palette();
+        self.brightness = settings.brightness;
+    }
+}
+
+impl EdgeDisplay for Esp32LedDisplay<'_> {
+    type Error = LedDisplayError;
+
+    fn show_edge(&mut self, edge: &EdgeFeedback) -> Result<(), Self::Error> {
+        if self.edge.leds == 0 {
+            return Ok(());
+        }
+        let thermal = self
+            .thermal
+            .as_ref()
+            .map_or(u8::MAX, |thermal| thermal.throttle.level());
+        let level = combined_level(brightness_level(self.brightness), thermal);
+        self.edge
+            .render_into(edge, &self.palette, &mut self.edge_colors);
+        let tail = &mut self.buffer[self.square_leds.count()..];
+        let mut changed = false;
+        for (led, &color) in tail.iter_mut().zip(&self.edge_colors) {
+            let color = color.scale(level);
+            changed |= *led != color;
+            *led = color;
+        }
+        if changed { self.flush() } else { Ok(()) }
     }
 }
@@ -0,0 +1,115 @@
+//! The ESP32 I2C controller as an [`I2cBus`], shared through
+//! [`crate::i2c_bus::I2cBusManager`].
+
+use esp_idf_svc::hal::delay::{Ets, TickType};
+use esp_idf_svc::hal::gpio::{InputPin, OutputPin};
+use esp_idf_svc::hal::i2c::{APBTickType, I2c, I2cConfig, I2cDriver};
+use esp_idf_svc::hal::units::Hertz;
+use esp_idf_svc::sys::{
+    EspError, esp, gpio_get_level, gpio_mode_t_GPIO_MODE_INPUT_OUTPUT_OD, gpio_set_direction,
+    gpio_set_level, i2c_mode_t_I2C_MODE_MASTER, i2c_reset_rx_fifo, i2c_reset_tx_fifo, i2c_set_pin,
+};
+
+use crate::esp32::config::{I2C_BAUDRATE_HZ, I2C_CLOCK_STRETCH_LIMIT, I2C_TRANSFER_TIMEOUT};
+use crate::i2c_bus::I2cBus;
+
+/// Half an SCL period while clocking a stuck bus free by hand (100 kHz).
+const RECOVERY_HALF_PERIOD_US: u32 = 5;
+
+/// A device stopped mid-byte releases SDA within nine clocks.
+const RECOVERY_CLOCKS: usize = 9;
+
+pub struct EspI2cBus<'d> {
+    driver: I2cDriver<'d>,
+    sda: i32,
+    scl: i32,
+    timeout: u32,
+}
+
+impl<'d> EspI2cBus<'d> {
+    pub fn new(
+        i2c: impl I2c + 'd,
+        sda: impl InputPin + OutputPin + 'd,
+        scl: impl InputPin + OutputPin + 'd,
+    ) -> Result<Self, EspError> {
+        let (sda_pin, scl_pin) = (sda.pin() as i32, scl.pin() as i32);
+        let config = I2cConfig::new()
+            .baudrate(Hertz(I2C_BAUDRATE_HZ))
+            .timeout(APBTickType::from(I2C_CLOCK_STRETCH_LIMIT));
+        Ok(Self {
+            driver: I2cDriver::new(i2c, sda, scl, &config)?,
+            sda: sda_pin,
+            scl: scl_pin,
+            timeout: TickType::from(I2C_TRANSFER_TIMEOUT).ticks(),
+        })
+    }
+}
+
+impl I2cBus for EspI2cBus<'_> {
+    type Error = EspError;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), EspError> {
+        self.driver.write(address, bytes, self.timeout)
+    }
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), EspError> {
+        self.driver.write_read(address, bytes, buffer, self.timeout)
+    }
+
+    /// Takes both lines over as open-drain GPIOs, clocks SCL until the
+    /// device lets go of SDA and sends a STOP, then hands the lines back to
+    /// the controller with its FIFOs cleared.
+    fn recover(&mut self) -> Result<(), EspError> {
+        let (sda, scl) = (self.sda, self.scl);
+        let pause = || Ets::delay_us(RECOVERY_HALF_PERIOD_US);
+        // SAFETY: the pins belong to this bus and the driver is idle while
+        // `&mut self` is held; `i2c_set_pin` routes them back before the
+        // next transfer.
+        unsafe {
+            esp!(gpio_set_direction(
+                sda,
+                gpio_mode_t_GPIO_MODE_INPUT_OUTPUT_OD
+            ))?;
+            esp!(gpio_set_direction(
+                scl,
+                gpio_mode_t_GPIO_MODE_INPUT_OUTPUT_OD
+            ))?;
+            esp!(gpio_set_level(sda, 1))?;
+            esp!(gpio_set_level(scl, 1))?;
+            pause();
+            for _ in 0..RECOVERY_CLOCKS {
+                if gpio_get_level(sda) != 0 {
+                    break;
+                }
+                esp!(gpio_set_level(scl, 0))?;
+                pause();
+                esp!(gpio_set_level(scl, 1))?;
+                pause();
+            }
+            // STOP: SDA rises while SCL is high.
+            esp!(gpio_set_level(scl, 0))?;
+            esp!(gpio_set_level(sda, 0))?;
+            pause();
+            esp!(gpio_set_level(scl, 1))?;
+            pause();
+            esp!(gpio_set_level(sda, 1))?;
+            pause();
+
+            let port = self.driver.port();
+            esp!(i2c_set_pin(
+                port,
+                sda,
+                scl,
+                true,
+                true,
+                i2c_mode_t_I2C_MODE_MASTER
+            ))?;
+            esp!(i2c_reset_tx_fifo(port))?;
+            esp!(i2c_reset_rx_fifo(port))?;
+        }
+        if unsafe { gpio_get_level(sda) } == 0 {
+            log::error!("I2C SDA still held low after recovery");
+        }
+        Ok(())
+    }
+}
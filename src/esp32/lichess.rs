@@ -0,0 +1,117 @@
+//! Lichess over the board's WiFi: HTTPS transport and NVS token storage
+//! for [`crate::net::lichess`].
+
+use std::io::{self, BufRead, BufReader, Read};
+
+use esp_idf_svc::http::Method;
+use esp_idf_svc::http::client::EspHttpConnection;
+use esp_idf_svc::nvs::{EspNvs, EspNvsPartition, NvsDefault};
+
+use super::tls::{self, Backend, TlsError};
+use crate::net::lichess::{LichessError, LichessTransport, MAX_TOKEN_LEN, TokenStore};
+
+const LICHESS_URL: &str = "https://lichess.org";
+
+const TOKEN_NAMESPACE: &str = "lichess";
+const KEY_TOKEN: &str = "token";
+
+/// The Lichess token in the default NVS partition.
+pub struct NvsTokenStore {
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl NvsTokenStore {
+    pub fn new(partition: EspNvsPartition<NvsDefault>) -> io::Result<Self> {
+        let nvs = EspNvs::new(partition, TOKEN_NAMESPACE, true).map_err(io::Error::other)?;
+        Ok(Self { nvs })
+    }
+}
+
+impl TokenStore for NvsTokenStore {
+    fn load(&self) -> Option<String> {
+        let mut buf = [0; MAX_TOKEN_LEN + 1];
+        match self.nvs.get_str(KEY_TOKEN, &mut buf) {
+            Ok(token) => token.map(str::to_string),
+            Err(e) => {
+                log::warn!("Lichess token unreadable: {e}");
+                None
+            }
+        }
+    }
+
+    fn save(&mut self, token: &str) -> io::Result<()> {
+        if token.len() > MAX_TOKEN_LEN {
+            return Err(io::Error::other("Lichess token too long"));
+        }
+        self.nvs.set_str(KEY_TOKEN, token).map_err(io::Error::other)
+    }
+}
+
+/// Each request on its own verified HTTPS connection.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EspLichess;
+
+impl EspLichess {
+    fn request(
+        method: Method,
+        path: &str,
+        token: &str,
+        form: &str,
+    ) -> Result<EspHttpConnection, LichessError> {
+        let auth = format!("Bearer {token}");
+        let length = form.len().to_string();
+        let mut headers = vec![("Authorization", auth.as_str())];
+        if method == Method::Post {
+            headers.push(("Content-Type", "application/x-www-form-urlencoded"));
+            headers.push(("Content-Length", length.as_str()));
+        }
+        let mut connection = tls::connect(
+            Backend::Official,
+            method,
+            &format!("{LICHESS_URL}{path}"),
+            &headers,
+        )?;
+        let mut body = form.as_bytes();
+        while !body.is_empty() {
+            let written = connection.write(body).map_err(io::Error::other)?;
+            body = &body[written..];
+        }
+        connection.initiate_response().map_err(io::Error::other)?;
+        match connection.status() {
+            200..=299 => Ok(connection),
+            401 => Err(LichessError::Unauthorized),
+            status => Err(LichessError::Status(status)),
+        }
+    }
+}
+
+impl LichessTransport for EspLichess {
+    fn stream(&self, path: &str, token: &str) -> Result<Box<dyn BufRead + Send>, LichessError> {
+        let connection = Self::request(Method::Get, path, token, "")?;
+        Ok(Box::new(BufReader::new(Body(connection))))
+    }
+
+    fn post(&self, path: &str, token: &str, form: &str) -> Result<(), LichessError> {
+        let connection = Self::request(Method::Post, path, token, form)?;
+        io::copy(&mut Body(connection), &mut io::sink())?;
+        Ok(())
+    }
+}
+
+impl From<TlsError> for LichessError {
+    fn from(error: TlsError) -> Self {
+        match error {
+            TlsError::Certificate(e) => LichessError::Certificate(e),
+            e => LichessError::Io(io::Error::other(e)),
+        }
+    }
+}
+
+/// A response body as [`Read`].
+struct Body(EspHttpConnection);
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf).map_err(io::Error::other)
+    }
+}
@@ -1,10 +1,29 @@
 pub mod ble;
 pub mod config;
+mod console;
 mod display;
+mod i2c;
+mod lichess;
+mod pairing;
+mod profiles;
+mod relay;
+mod saved_game;
 mod sensor;
+pub mod tls;
+mod tournament;
+mod web_config;
 mod wifi;
 
 pub use ble::{BleCommands, BleError, BleNotifier, start_ble};
+pub use console::ConsoleLogger;
 pub use display::{Esp32LedDisplay, LedDisplayError};
+pub use i2c::EspI2cBus;
+pub use lichess::{EspLichess, NvsTokenStore};
+pub use pairing::NvsPairingStore;
+pub use profiles::NvsProfileStore;
+pub use relay::{EspRelay, NvsPassphraseStore};
+pub use saved_game::NvsGameStore;
 pub use sensor::{Esp32PieceSensor, RawScan, SensorError};
-pub use wifi::{WifiConnection, WifiError};
+pub use tournament::NvsTournamentStore;
+pub use web_config::{ConfigServer, NvsConfigStore};
+pub use wifi::{WifiConnection, WifiError, WifiManager};
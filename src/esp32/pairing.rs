@@ -0,0 +1,57 @@
+//! Tokens of paired clients in the default NVS partition, for
+//! [`crate::pairing`].
+
+use std::io;
+
+use esp_idf_svc::nvs::{EspNvs, EspNvsPartition, NvsDefault};
+
+use crate::pairing::{MAX_TOKENS, Pairing, PairingStore, Token};
+
+const PAIRING_NAMESPACE: &str = "pairing";
+const KEY_TOKENS: &str = "tokens";
+/// The version line and a line of 16 hex digits per token.
+const MAX_TOKENS_LEN: usize = 16 + 17 * MAX_TOKENS;
+
+/// Saves the tokens as a blob, rewritten whenever a client pairs.
+pub struct NvsPairingStore {
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl NvsPairingStore {
+    pub fn new(partition: EspNvsPartition<NvsDefault>) -> io::Result<Self> {
+        let nvs = EspNvs::new(partition, PAIRING_NAMESPACE, true).map_err(io::Error::other)?;
+        Ok(Self { nvs })
+    }
+}
+
+impl PairingStore for NvsPairingStore {
+    fn load(&self) -> Vec<Token> {
+        let mut buf = vec![0; MAX_TOKENS_LEN];
+        let bytes = match self.nvs.get_raw(KEY_TOKENS, &mut buf) {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => return Vec::new(),
+            Err(e) => {
+                log::warn!("Pairing tokens unreadable: {e}");
+                return Vec::new();
+            }
+        };
+        let tokens = std::str::from_utf8(bytes)
+            .ok()
+            .and_then(Pairing::decode_tokens);
+        if tokens.is_none() {
+            log::warn!("Pairing tokens ignored: not a token list");
+        }
+        tokens.unwrap_or_default()
+    }
+
+    fn save(&mut self, tokens: &[Token]) -> io::Result<()> {
+        let text = Pairing::encode_tokens(tokens);
+        if text.len() > MAX_TOKENS_LEN {
+            return Err(io::Error::other("pairing tokens too large to save"));
+        }
+        self.nvs
+            .set_raw(KEY_TOKENS, text.as_bytes())
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+}
@@ -0,0 +1,58 @@
+//! Player profiles in the default NVS partition, for
+//! [`crate::profiles`].
+
+use std::io;
+
+use esp_idf_svc::nvs::{EspNvs, EspNvsPartition, NvsDefault};
+
+use crate::profiles::{Profile, ProfileStore};
+
+const PROFILES_NAMESPACE: &str = "profiles";
+const KEY_LIST: &str = "list";
+/// Room for every profile with a long name and every preference spelled out.
+const MAX_LIST_LEN: usize = 1024;
+
+/// Saves the profiles as a blob, rewritten whenever the config page
+/// changes them.
+pub struct NvsProfileStore {
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl NvsProfileStore {
+    pub fn new(partition: EspNvsPartition<NvsDefault>) -> io::Result<Self> {
+        let nvs = EspNvs::new(partition, PROFILES_NAMESPACE, true).map_err(io::Error::other)?;
+        Ok(Self { nvs })
+    }
+}
+
+impl ProfileStore for NvsProfileStore {
+    fn load(&self) -> Vec<Profile> {
+        let mut buf = vec![0; MAX_LIST_LEN];
+        let bytes = match self.nvs.get_raw(KEY_LIST, &mut buf) {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => return Vec::new(),
+            Err(e) => {
+                log::warn!("Profiles unreadable: {e}");
+                return Vec::new();
+            }
+        };
+        let Ok(text) = std::str::from_utf8(bytes) else {
+            log::warn!("Profiles ignored: not UTF-8");
+            return Vec::new();
+        };
+        Profile::decode(text)
+            .inspect_err(|e| log::warn!("Profiles ignored: {e}"))
+            .unwrap_or_default()
+    }
+
+    fn save(&mut self, profiles: &[Profile]) -> io::Result<()> {
+        let text = Profile::encode(profiles);
+        if text.len() > MAX_LIST_LEN {
+            return Err(io::Error::other("profiles too large to save"));
+        }
+        self.nvs
+            .set_raw(KEY_LIST, text.as_bytes())
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+}
@@ -0,0 +1,126 @@
+//! The board-to-board relay over the board's WiFi: HTTPS transport and
+//! NVS passphrase storage for [`crate::net::relay`].
+
+use std::io::{self, BufRead, BufReader, Read};
+
+use esp_idf_svc::http::Method;
+use esp_idf_svc::http::client::EspHttpConnection;
+use esp_idf_svc::nvs::{EspNvs, EspNvsPartition, NvsDefault};
+
+use super::tls::{self, Backend, TlsError};
+use crate::net::relay::{MAX_PASSPHRASE_LEN, PassphraseStore, RelayError, RelayTransport};
+
+const PASSPHRASE_NAMESPACE: &str = "relay";
+const KEY_PASSPHRASE: &str = "passphrase";
+
+/// The relay passphrase in the default NVS partition.
+pub struct NvsPassphraseStore {
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl NvsPassphraseStore {
+    pub fn new(partition: EspNvsPartition<NvsDefault>) -> io::Result<Self> {
+        let nvs = EspNvs::new(partition, PASSPHRASE_NAMESPACE, true).map_err(io::Error::other)?;
+        Ok(Self { nvs })
+    }
+}
+
+impl PassphraseStore for NvsPassphraseStore {
+    fn load(&self) -> Option<String> {
+        let mut buf = [0; MAX_PASSPHRASE_LEN + 1];
+        match self.nvs.get_str(KEY_PASSPHRASE, &mut buf) {
+            Ok(passphrase) => passphrase.map(str::to_string),
+            Err(e) => {
+                log::warn!("Relay passphrase unreadable: {e}");
+                None
+            }
+        }
+    }
+
+    fn save(&mut self, passphrase: &str) -> io::Result<()> {
+        if passphrase.len() > MAX_PASSPHRASE_LEN {
+            return Err(io::Error::other("relay passphrase too long"));
+        }
+        self.nvs
+            .set_str(KEY_PASSPHRASE, passphrase)
+            .map_err(io::Error::other)
+    }
+}
+
+/// Each request on its own HTTPS connection to the rendezvous server at
+/// `base` (e.g. [`super::config::RELAY_URL`]), verified as a
+/// [`Backend::Relay`].
+#[derive(Debug, Clone, Copy)]
+pub struct EspRelay {
+    base: &'static str,
+}
+
+impl EspRelay {
+    pub fn new(base: &'static str) -> Self {
+        Self {
+            base: base.trim_end_matches('/'),
+        }
+    }
+
+    fn request(
+        &self,
+        method: Method,
+        room: &str,
+        line: &str,
+    ) -> Result<EspHttpConnection, RelayError> {
+        let body = if method == Method::Post {
+            format!("{line}\n")
+        } else {
+            String::new()
+        };
+        let length = body.len().to_string();
+        let mut headers = vec![];
+        if method == Method::Post {
+            headers.push(("Content-Type", "text/plain"));
+            headers.push(("Content-Length", length.as_str()));
+        }
+        let url = format!("{}/rooms/{room}", self.base);
+        let mut connection = tls::connect(Backend::Relay, method, &url, &headers)?;
+        let mut body = body.as_bytes();
+        while !body.is_empty() {
+            let written = connection.write(body).map_err(io::Error::other)?;
+            body = &body[written..];
+        }
+        connection.initiate_response().map_err(io::Error::other)?;
+        match connection.status() {
+            200..=299 => Ok(connection),
+            status => Err(RelayError::Status(status)),
+        }
+    }
+}
+
+impl RelayTransport for EspRelay {
+    fn stream(&self, room: &str) -> Result<Box<dyn BufRead + Send>, RelayError> {
+        let connection = self.request(Method::Get, room, "")?;
+        Ok(Box::new(BufReader::new(Body(connection))))
+    }
+
+    fn post(&self, room: &str, line: &str) -> Result<(), RelayError> {
+        let connection = self.request(Method::Post, room, line)?;
+        io::copy(&mut Body(connection), &mut io::sink())?;
+        Ok(())
+    }
+}
+
+impl From<TlsError> for RelayError {
+    fn from(error: TlsError) -> Self {
+        match error {
+            TlsError::Certificate(e) => RelayError::Certificate(e),
+            e => RelayError::Io(io::Error::other(e)),
+        }
+    }
+}
+
+/// A response body as [`Read`].
+struct Body(EspHttpConnection);
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf).map_err(io::Error::other)
+    }
+}
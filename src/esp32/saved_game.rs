@@ -0,0 +1,61 @@
+//! The game in progress in the default NVS partition, for
+//! [`crate::saved_game`].
+
+use std::io;
+
+use esp_idf_svc::nvs::{EspNvs, EspNvsPartition, NvsDefault};
+
+use crate::saved_game::{GameStore, SavedGame};
+
+const GAME_NAMESPACE: &str = "game";
+const KEY_SAVED: &str = "saved";
+/// Room for a long game: each move takes five or six bytes.
+const MAX_SAVED_LEN: usize = 4096;
+
+/// Saves the game as a blob, rewritten after every move; NVS spreads the
+/// writes over its pages.
+pub struct NvsGameStore {
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl NvsGameStore {
+    pub fn new(partition: EspNvsPartition<NvsDefault>) -> io::Result<Self> {
+        let nvs = EspNvs::new(partition, GAME_NAMESPACE, true).map_err(io::Error::other)?;
+        Ok(Self { nvs })
+    }
+}
+
+impl GameStore for NvsGameStore {
+    fn load(&self) -> Option<SavedGame> {
+        let mut buf = vec![0; MAX_SAVED_LEN];
+        let bytes = match self.nvs.get_raw(KEY_SAVED, &mut buf) {
+            Ok(bytes) => bytes?,
+            Err(e) => {
+                log::warn!("Saved game unreadable: {e}");
+                return None;
+            }
+        };
+        let text = std::str::from_utf8(bytes).ok()?;
+        SavedGame::decode(text)
+            .inspect_err(|e| log::warn!("Saved game ignored: {e}"))
+            .ok()
+    }
+
+    fn save(&mut self, game: &SavedGame) -> io::Result<()> {
+        let text = game.encode();
+        if text.len() > MAX_SAVED_LEN {
+            return Err(io::Error::other("game too long to save"));
+        }
+        self.nvs
+            .set_raw(KEY_SAVED, text.as_bytes())
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.nvs
+            .remove(KEY_SAVED)
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+}
@@ -1,10 +1,12 @@
 use crate::PieceSensor;
-use crate::esp32::config::SensorConfig;
+use crate::calibration::{self, Calibration};
+use crate::esp32::config::{SensorCalibration, SensorConfig};
 use esp_idf_svc::hal::adc::attenuation;
 use esp_idf_svc::hal::adc::oneshot::config::AdcChannelConfig;
 use esp_idf_svc::hal::adc::oneshot::{AdcChannelDriver, AdcDriver};
 use esp_idf_svc::hal::adc::{AdcChannel, AdcUnit};
 use esp_idf_svc::hal::gpio::{ADCPin, Output, OutputPin, PinDriver};
+use esp_idf_svc::nvs::{EspNvsPartition, NvsCustom};
 use shakmaty::{Bitboard, ByColor, File, Rank, Square};
 
 const NUM_MUXES: usize = 4;
@@ -81,6 +83,8 @@ pub struct Esp32PieceSensor<'a> {
     mux_channels: [MuxChannel<'a>; NUM_MUXES],
     address_lines: MuxAddressLines,
     config: SensorConfig,
+    /// Where calibrations applied at runtime are saved.
+    calibration_store: Option<EspNvsPartition<NvsCustom>>,
 }
 
 /// Type-erased ADC channel that can read millivolt values.
@@ -114,6 +118,16 @@ impl<'a> MuxChannel<'a> {
             channel,
         })
     }
+
+    /// Mean of `samples` reads (at least one), to average out ADC noise.
+    fn read_averaged(&mut self, channel: u8, samples: u8) -> Result<u16, SensorError> {
+        let samples = u32::from(samples.max(1));
+        let mut sum = 0u32;
+        for _ in 0..samples {
+            sum += u32::from(self.read(channel)?);
+        }
+        Ok((sum / samples) as u16)
+    }
 }
 
 /// Map a mux index (0..3) and mux channel (0..15) to a board square.
@@ -196,10 +210,29 @@ impl<'a> Esp32PieceSensor<'a> {
             mux_channels,
             address_lines,
             config,
+            calibration_store: None,
         })
     }
 
-    /// Perform a full 64-square scan and return raw millivolt readings.
+    /// Save calibrations applied through [`PieceSensor::calibrate`] to
+    /// `partition`, so they survive a reboot.
+    pub fn with_calibration_store(mut self, partition: EspNvsPartition<NvsCustom>) -> Self {
+        self.calibration_store = Some(partition);
+        self
+    }
+
+    /// ADC reads averaged into each reading.
+    pub fn samples(&self) -> u8 {
+        self.config.samples
+    }
+
+    /// Average `samples` ADC reads into each reading from now on.
+    pub fn set_samples(&mut self, samples: u8) {
+        self.config.samples = samples;
+    }
+
+    /// Perform a full 64-square scan and return raw millivolt readings,
+    /// each the average of [`SensorConfig::samples`] ADC reads.
     ///
     /// This is the low-level scan primitive. [`PieceSensor::read_positions`]
     /// delegates to this method and applies thresholding.
@@ -211,9 +244,8 @@ impl<'a> Esp32PieceSensor<'a> {
             esp_idf_svc::hal::delay::FreeRtos::delay_ms(self.config.settle_delay_ms);
 
             for (mux_idx, mux) in self.mux_channels.iter_mut().enumerate() {
-                let mv = mux.read(channel)?;
                 let sq = square_for(mux_idx, channel);
-                scan.mv[sq as usize] = mv;
+                scan.mv[sq as usize] = mux.read_averaged(channel, self.config.samples)?;
             }
         }
 
@@ -226,22 +258,31 @@ impl PieceSensor for Esp32PieceSensor<'_> {
 
     fn read_positions(&mut self) -> Result<ByColor<Bitboard>, SensorError> {
         let scan = self.read_raw()?;
-        let mut white = Bitboard::EMPTY;
-        let mut black = Bitboard::EMPTY;
-
-        for sq_idx in 0..64u32 {
-            let mv = scan.mv[sq_idx as usize];
-            let deviation = mv.abs_diff(self.config.baseline_mv);
-            if deviation > self.config.threshold_mv {
-                let sq = Square::new(sq_idx);
-                if mv > self.config.baseline_mv {
-                    white.toggle(sq);
-                } else {
-                    black.toggle(sq);
-                }
-            }
-        }
+        Ok(calibration::classify(
+            &scan.mv,
+            &self.config.square_baselines,
+            self.config.threshold_mv,
+            self.config.dead_squares,
+        ))
+    }
+
+    fn read_millivolts(&mut self) -> Option<Result<[u16; 64], SensorError>> {
+        Some(self.read_raw().map(|scan| scan.mv))
+    }
 
-        Ok(ByColor { white, black })
+    fn calibrate(&mut self, calibration: Calibration) {
+        let stored = SensorCalibration {
+            baseline_mv: calibration.baseline_mv,
+            threshold_mv: calibration.threshold_mv,
+            square_baselines: Some(calibration.square_baselines),
+        };
+        self.config = stored.apply(self.config);
+        let Some(partition) = &self.calibration_store else {
+            return;
+        };
+        match stored.save(partition) {
+            Ok(()) => log::info!("Calibration saved to NVS"),
+            Err(e) => log::error!("Failed to save calibration: {e}"),
+        }
     }
 }
@@ -0,0 +1,110 @@
+//! HTTPS client settings for online backends.
+//!
+//! Every outbound connection verifies the server certificate: official
+//! backends against the root bundle compiled into the firmware
+//! (`CONFIG_MBEDTLS_CERTIFICATE_BUNDLE` in `sdkconfig.defaults`), a custom
+//! relay against [`RELAY_CA_PEM`] when it is set. There is deliberately no
+//! way to turn verification off.
+//!
+//! When verification fails, [`connect`] reads mbedTLS's verification flags
+//! back from the client and reports them as a
+//! [`crate::tls::CertificateError`], which the remote player shows on the
+//! board.
+
+use std::ffi::{CStr, CString, c_int};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use esp_idf_svc::handle::RawHandle;
+use esp_idf_svc::http::Method;
+use esp_idf_svc::http::client::{Configuration, EspHttpConnection};
+use esp_idf_svc::sys::{
+    ESP_OK, EspError, esp_crt_bundle_attach, esp_http_client_get_and_clear_last_tls_error,
+};
+use esp_idf_svc::tls::X509;
+
+use super::config::RELAY_CA_PEM;
+use crate::tls::CertificateError;
+
+/// Give up on a request after this long.
+pub const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Which roots a server certificate must chain to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Lichess, telemetry: the bundled roots.
+    Official,
+    /// A user-run relay: [`RELAY_CA_PEM`] if set, else the bundled roots.
+    Relay,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+    #[error(transparent)]
+    Certificate(#[from] CertificateError),
+    #[error("RELAY_CA_PEM contains a NUL byte")]
+    RelayCertificate,
+    #[error("HTTPS request failed: {0}")]
+    Esp(#[from] EspError),
+}
+
+/// Client configuration for `backend`.
+pub fn https_configuration(backend: Backend) -> Result<Configuration, TlsError> {
+    let relay_root = match backend {
+        Backend::Relay => RELAY_CA_PEM.map(relay_certificate).transpose()?,
+        Backend::Official => None,
+    };
+    Ok(Configuration {
+        timeout: Some(HTTP_TIMEOUT),
+        use_global_ca_store: false,
+        crt_bundle_attach: relay_root.is_none().then_some(esp_crt_bundle_attach),
+        server_certificate: relay_root,
+        ..Default::default()
+    })
+}
+
+/// Open a verified HTTPS connection for `backend` and send the request
+/// line and `headers`, which runs the TLS handshake.
+///
+/// A server certificate that does not verify is reported as
+/// [`TlsError::Certificate`].
+pub fn connect(
+    backend: Backend,
+    method: Method,
+    url: &str,
+    headers: &[(&str, &str)],
+) -> Result<EspHttpConnection, TlsError> {
+    let mut connection = EspHttpConnection::new(&https_configuration(backend)?)?;
+    if let Err(e) = connection.initiate_request(method, url, headers) {
+        return Err(certificate_error(&connection).map_or(TlsError::Esp(e), TlsError::Certificate));
+    }
+    Ok(connection)
+}
+
+/// The certificate problem behind the last failed handshake, if that was
+/// why it failed.
+fn certificate_error(connection: &EspHttpConnection) -> Option<CertificateError> {
+    let mut code: c_int = 0;
+    let mut flags: c_int = 0;
+    // SAFETY: the handle belongs to `connection`, which outlives the call,
+    // and both out-pointers are valid.
+    let err = unsafe {
+        esp_http_client_get_and_clear_last_tls_error(connection.handle(), &mut code, &mut flags)
+    };
+    if err != ESP_OK {
+        return None;
+    }
+    CertificateError::from_verify_flags(flags as u32)
+}
+
+/// The relay root as mbedTLS wants it: NUL-terminated and living forever,
+/// so it is converted once.
+fn relay_certificate(pem: &'static str) -> Result<X509<'static>, TlsError> {
+    static ROOT: OnceLock<Option<&'static CStr>> = OnceLock::new();
+    let root = ROOT.get_or_init(|| {
+        CString::new(pem)
+            .ok()
+            .map(|pem| &*Box::leak(pem.into_boxed_c_str()))
+    });
+    root.map(X509::pem).ok_or(TlsError::RelayCertificate)
+}
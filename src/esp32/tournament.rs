@@ -0,0 +1,53 @@
+//! The club tournament in the default NVS partition, for
+//! [`crate::tournament`].
+
+use std::io;
+
+use esp_idf_svc::nvs::{EspNvs, EspNvsPartition, NvsDefault};
+
+use crate::tournament::{Tournament, TournamentStore};
+
+const TOURNAMENT_NAMESPACE: &str = "tournament";
+const KEY_TABLE: &str = "table";
+/// Room for a full round robin of sixteen players with long names.
+const MAX_TABLE_LEN: usize = 4096;
+
+/// Saves the tournament as a blob, rewritten after every result.
+pub struct NvsTournamentStore {
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl NvsTournamentStore {
+    pub fn new(partition: EspNvsPartition<NvsDefault>) -> io::Result<Self> {
+        let nvs = EspNvs::new(partition, TOURNAMENT_NAMESPACE, true).map_err(io::Error::other)?;
+        Ok(Self { nvs })
+    }
+}
+
+impl TournamentStore for NvsTournamentStore {
+    fn load(&self) -> Option<Tournament> {
+        let mut buf = vec![0; MAX_TABLE_LEN];
+        let bytes = match self.nvs.get_raw(KEY_TABLE, &mut buf) {
+            Ok(bytes) => bytes?,
+            Err(e) => {
+                log::warn!("Tournament unreadable: {e}");
+                return None;
+            }
+        };
+        let text = std::str::from_utf8(bytes).ok()?;
+        Tournament::decode(text)
+            .inspect_err(|e| log::warn!("Tournament ignored: {e}"))
+            .ok()
+    }
+
+    fn save(&mut self, tournament: &Tournament) -> io::Result<()> {
+        let text = tournament.encode();
+        if text.len() > MAX_TABLE_LEN {
+            return Err(io::Error::other("tournament too large to save"));
+        }
+        self.nvs
+            .set_raw(KEY_TABLE, text.as_bytes())
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+}
@@ -0,0 +1,160 @@
+//! The config page of [`crate::board_config`] over HTTP, and the stored
+//! [`BoardConfig`] in the default NVS partition.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use esp_idf_svc::http::Method;
+use esp_idf_svc::http::server::{Configuration as HttpConfiguration, EspHttpServer};
+use esp_idf_svc::io::{Read, Write};
+use esp_idf_svc::nvs::{EspNvs, EspNvsPartition, NvsDefault};
+use esp_idf_svc::sys::EspError;
+
+use crate::board_config::{BoardConfig, CONFIG_LEN, ConfigUpdate, PageStatus, page};
+
+const CONFIG_NAMESPACE: &str = "board";
+const KEY_CONFIG: &str = "config";
+/// Longest form accepted: every field at its limit, fully percent-encoded.
+const MAX_FORM_LEN: usize = 2048;
+
+/// Saves the [`BoardConfig`] as a blob.
+pub struct NvsConfigStore {
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl NvsConfigStore {
+    pub fn new(partition: EspNvsPartition<NvsDefault>) -> io::Result<Self> {
+        let nvs = EspNvs::new(partition, CONFIG_NAMESPACE, true).map_err(io::Error::other)?;
+        Ok(Self { nvs })
+    }
+
+    pub fn load(&self) -> Option<BoardConfig> {
+        let mut buf = [0; CONFIG_LEN];
+        let bytes = match self.nvs.get_raw(KEY_CONFIG, &mut buf) {
+            Ok(bytes) => bytes?,
+            Err(e) => {
+                log::warn!("Board config unreadable: {e}");
+                return None;
+            }
+        };
+        BoardConfig::from_bytes(bytes)
+            .inspect_err(|e| log::warn!("Board config ignored: {e}"))
+            .ok()
+    }
+
+    pub fn save(&mut self, config: &BoardConfig) -> io::Result<()> {
+        self.nvs
+            .set_raw(KEY_CONFIG, &config.to_bytes())
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+}
+
+/// What the handlers share with the main loop.
+struct PageState {
+    config: BoardConfig,
+    status: PageStatus,
+    submitted: Option<ConfigUpdate>,
+}
+
+/// Serves the config page at `/` on every interface: the board's address
+/// on the home network, and `http://192.168.71.1/` on the setup access
+/// point. Submissions wait in [`Self::take_update`] for the main loop.
+pub struct ConfigServer {
+    _server: EspHttpServer<'static>,
+    state: Arc<Mutex<PageState>>,
+}
+
+impl ConfigServer {
+    /// Start serving; WiFi must be started first.
+    pub fn start(config: BoardConfig, status: PageStatus) -> Result<Self, EspError> {
+        let state = Arc::new(Mutex::new(PageState {
+            config,
+            status,
+            submitted: None,
+        }));
+        let mut server = EspHttpServer::new(&HttpConfiguration::default())?;
+        let shown = state.clone();
+        server.fn_handler("/", Method::Get, move |req| {
+            let html = shown
+                .lock()
+                .map(|state| page(&state.config, &state.status))
+                .unwrap_or_default();
+            req.into_ok_response()?.write_all(html.as_bytes())
+        })?;
+        let exported = state.clone();
+        server.fn_handler("/crosstable", Method::Get, move |req| {
+            let crosstable = exported
+                .lock()
+                .ok()
+                .and_then(|state| state.status.crosstable.clone())
+                .unwrap_or_else(|| "No tournament running.\n".to_string());
+            req.into_response(200, None, &[("Content-Type", "text/plain; charset=utf-8")])?
+                .write_all(crosstable.as_bytes())
+        })?;
+        let submitted = state.clone();
+        server.fn_handler("/", Method::Post, move |mut req| {
+            let mut body = vec![0; MAX_FORM_LEN];
+            let mut len = 0;
+            while len < body.len() {
+                let read = req.read(&mut body[len..])?;
+                if read == 0 {
+                    break;
+                }
+                len += read;
+            }
+            let form = String::from_utf8_lossy(&body[..len]);
+            let html = match submitted.lock() {
+                Ok(mut state) => {
+                    match ConfigUpdate::from_form(&form, state.config) {
+                        Ok(update) => {
+                            log::info!("Board config submitted");
+                            state.config = update.config;
+                            if let Some(wifi) = &update.wifi {
+                                state.status.network = Some(wifi.ssid().to_string());
+                            }
+                            state.status.lichess_linked |= update.lichess_token.is_some();
+                            state.status.relay_joined |= update.relay_passphrase.is_some();
+                            if let Some(tournament) = &update.tournament {
+                                state.status.crosstable = Some(tournament.crosstable());
+                            }
+                            if let Some(profiles) = &update.profiles {
+                                state.status.profiles = profiles.clone();
+                            }
+                            state.status.message = Some("Saved.".to_string());
+                            state.submitted = Some(update);
+                        }
+                        Err(e) => state.status.message = Some(e.to_string()),
+                    }
+                    page(&state.config, &state.status)
+                }
+                Err(_) => String::new(),
+            };
+            req.into_ok_response()?.write_all(html.as_bytes())
+        })?;
+        log::info!("Config page served");
+        Ok(Self {
+            _server: server,
+            state,
+        })
+    }
+
+    /// The last form submitted since the previous call.
+    pub fn take_update(&self) -> Option<ConfigUpdate> {
+        self.state.lock().ok()?.submitted.take()
+    }
+
+    /// Show `crosstable` on the page and at `/crosstable`.
+    pub fn set_crosstable(&self, crosstable: Option<String>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.status.crosstable = crosstable;
+        }
+    }
+
+    /// Report the outcome of applying an update on the page.
+    pub fn report(&self, message: impl Into<String>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.status.message = Some(message.into());
+        }
+    }
+}
@@ -1,9 +1,26 @@
+//! WiFi on the board: the [`WifiManager`] that keeps the board online,
+//! and a one-shot blocking [`WifiConnection`] for tools.
+
 use core::convert::TryInto;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
 
 use esp_idf_svc::eventloop::EspSystemEventLoop;
 use esp_idf_svc::hal::modem::WifiModemPeripheral;
-use esp_idf_svc::nvs::EspDefaultNvsPartition;
-use esp_idf_svc::wifi::{AuthMethod, BlockingWifi, ClientConfiguration, Configuration, EspWifi};
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs, NvsDefault};
+use esp_idf_svc::wifi::{
+    AccessPointConfiguration, AuthMethod, BlockingWifi, ClientConfiguration, Configuration, EspWifi,
+};
+
+use crate::wifi::{WifiAction, WifiCredentials, WifiEvent, WifiLink, WifiStatus};
+
+/// Name of the open setup access point. `ConfigServer` serves its page at
+/// the access point's address, `http://192.168.71.1/`.
+const PORTAL_SSID: &str = "ChessBoard-Setup";
+
+const CREDENTIALS_NAMESPACE: &str = "wifi";
+const KEY_SSID: &str = "ssid";
+const KEY_PASSWORD: &str = "password";
 
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum WifiError {
@@ -19,6 +36,161 @@ pub enum WifiError {
     WaitForIp(String),
 }
 
+/// Keeps the board on the stored network.
+///
+/// [`WifiLink`] decides what to do; this carries it out on the radio
+/// without blocking the game loop. While the link asks for provisioning,
+/// the board also runs the open [`PORTAL_SSID`] access point, where the
+/// config page takes new credentials for [`Self::provision`].
+pub struct WifiManager<'d> {
+    wifi: EspWifi<'d>,
+    nvs: EspNvs<NvsDefault>,
+    link: WifiLink,
+    credentials: Option<WifiCredentials>,
+    portal_open: bool,
+}
+
+impl<'d> WifiManager<'d> {
+    /// Start the radio with the stored credentials, if any. Connecting
+    /// happens in [`Self::poll`].
+    pub fn start(
+        modem: impl WifiModemPeripheral + 'd,
+        sys_loop: EspSystemEventLoop,
+        nvs: EspDefaultNvsPartition,
+    ) -> Result<Self, WifiError> {
+        let wifi = EspWifi::new(modem, sys_loop, Some(nvs.clone()))
+            .map_err(|e| WifiError::DriverInit(e.to_string()))?;
+        let nvs = EspNvs::new(nvs, CREDENTIALS_NAMESPACE, true)
+            .map_err(|e| WifiError::Configuration(e.to_string()))?;
+        let credentials = load_credentials(&nvs);
+        let mut manager = Self {
+            wifi,
+            nvs,
+            link: WifiLink::new(credentials.is_some()),
+            credentials,
+            portal_open: false,
+        };
+        manager.configure()?;
+        manager
+            .wifi
+            .start()
+            .map_err(|e| WifiError::Start(e.to_string()))?;
+        log::info!("WiFi started");
+        Ok(manager)
+    }
+
+    /// Hear when the network comes and goes.
+    pub fn subscribe(&mut self) -> Receiver<WifiEvent> {
+        self.link.subscribe()
+    }
+
+    /// Advance the connection; call every loop iteration. Returns the
+    /// status for the WiFi LED.
+    pub fn poll(&mut self, now: Duration) -> WifiStatus {
+        let up = self.wifi.is_connected().unwrap_or(false)
+            && self.wifi.sta_netif().is_up().unwrap_or(false);
+        match self.link.update(up, now) {
+            Some(WifiAction::Connect) => {
+                // Fails while an earlier attempt is still under way.
+                if let Err(e) = self.wifi.connect() {
+                    log::debug!("WiFi connect: {e}");
+                }
+            }
+            Some(WifiAction::OpenPortal) => {
+                self.portal_open = true;
+                match self.configure() {
+                    Ok(()) => log::info!("WiFi setup portal open on '{PORTAL_SSID}'"),
+                    Err(e) => log::error!("WiFi setup portal failed: {e}"),
+                }
+            }
+            Some(WifiAction::ClosePortal) => {
+                self.portal_open = false;
+                if let Err(e) = self.configure() {
+                    log::warn!("{e}");
+                }
+                log::info!("WiFi setup portal closed");
+            }
+            None => {}
+        }
+        self.link.status()
+    }
+
+    /// The network the board joins, if one is stored.
+    pub fn network(&self) -> Option<&str> {
+        self.credentials.as_ref().map(WifiCredentials::ssid)
+    }
+
+    /// Store new credentials and join that network.
+    pub fn provision(&mut self, credentials: WifiCredentials) {
+        let saved = self
+            .nvs
+            .set_str(KEY_SSID, credentials.ssid())
+            .and_then(|()| self.nvs.set_str(KEY_PASSWORD, credentials.password()));
+        if let Err(e) = saved {
+            log::warn!("WiFi credentials not saved: {e}");
+        }
+        log::info!("WiFi credentials received for {}", credentials.ssid());
+        self.credentials = Some(credentials);
+        if let Err(e) = self.configure() {
+            log::warn!("{e}");
+        }
+        self.link.provisioned();
+    }
+
+    /// Apply the stored network, alongside the setup access point while
+    /// the portal is open.
+    fn configure(&mut self) -> Result<(), WifiError> {
+        let client = match &self.credentials {
+            Some(credentials) => client_configuration(credentials)?,
+            None => ClientConfiguration::default(),
+        };
+        let config = if self.portal_open {
+            let access_point = AccessPointConfiguration {
+                ssid: PORTAL_SSID.try_into().unwrap_or_default(),
+                auth_method: AuthMethod::None,
+                ..Default::default()
+            };
+            Configuration::Mixed(client, access_point)
+        } else {
+            Configuration::Client(client)
+        };
+        self.wifi
+            .set_configuration(&config)
+            .map_err(|e| WifiError::Configuration(e.to_string()))
+    }
+}
+
+fn load_credentials(nvs: &EspNvs<NvsDefault>) -> Option<WifiCredentials> {
+    let mut ssid = [0; crate::wifi::MAX_SSID_LEN + 1];
+    let mut password = [0; crate::wifi::MAX_PASSWORD_LEN + 1];
+    let ssid = nvs.get_str(KEY_SSID, &mut ssid).ok()??;
+    let password = nvs
+        .get_str(KEY_PASSWORD, &mut password)
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    WifiCredentials::new(ssid, password).ok()
+}
+
+fn client_configuration(credentials: &WifiCredentials) -> Result<ClientConfiguration, WifiError> {
+    let auth_method = if credentials.password().is_empty() {
+        AuthMethod::None
+    } else {
+        AuthMethod::WPA2Personal
+    };
+    Ok(ClientConfiguration {
+        ssid: credentials.ssid().try_into().map_err(|_| {
+            WifiError::Configuration(format!("SSID too long: {}", credentials.ssid()))
+        })?,
+        auth_method,
+        password: credentials
+            .password()
+            .try_into()
+            .map_err(|_| WifiError::Configuration("password too long".to_string()))?,
+        ..Default::default()
+    })
+}
+
 /// Blocking WiFi connection. Dropping disconnects.
 pub struct WifiConnection<'d> {
     _wifi: BlockingWifi<EspWifi<'d>>,
@@ -0,0 +1,326 @@
+//! JSON Lines event export for stream overlays.
+//!
+//! [`JsonlExporter`] wraps the board's [`BoardNotifier`] and, besides
+//! forwarding every update, writes the ones an overlay cares about as one
+//! JSON object per line with a millisecond timestamp from the app clock.
+//! On the board the lines go to the serial console next to the log, so an
+//! overlay reads the port and keeps the lines starting with `{`.
+//!
+//! Every line carries `"v"` ([`SCHEMA_VERSION`]), `"ts"` (milliseconds)
+//! and `"type"`:
+//!
+//! | type       | fields                                                   |
+//! |------------|----------------------------------------------------------|
+//! | `status`   | `status` (snake case), `loser` or `color` when relevant  |
+//! | `player`   | `color`, `player` (`human`, `remote`, `random`)          |
+//! | `move`     | `color`, `uci`, `hash` and `ply` (see [`StateChecksum`]) |
+//! | `position` | `fen`                                                    |
+//! | `event`    | `event` (`player_ready` with `color`, `clock_started`,   |
+//! |            | `board_out_of_sync` with `squares`, `board_in_sync`,     |
+//! |            | `draw_claimable` with `reason`, `resigned_on_board` with |
+//! |            | `color`, `new_game_requested`)                           |
+//! | `clock`    | `white_ms`, `black_ms`, `running` (color or `null`)      |
+
+use std::fmt::Write as _;
+use std::io::Write;
+use std::time::Duration;
+
+use shakmaty::{ByColor, Color};
+
+use crate::adjudication::DrawReason;
+use crate::app::{BoardNotifier, Clock};
+use crate::ble_protocol::CommandResult;
+use crate::board_api::{GameEvent, GameStatus, PlayerType, StateChecksum};
+use crate::pairing::Token;
+
+/// Bumped whenever a field changes meaning or disappears.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A [`BoardNotifier`] that also exports game updates as JSON Lines.
+#[derive(Debug)]
+pub struct JsonlExporter<N, C, W> {
+    inner: N,
+    clock: C,
+    out: W,
+}
+
+impl<N, C: Clock, W: Write> JsonlExporter<N, C, W> {
+    pub fn new(inner: N, clock: C, out: W) -> Self {
+        Self { inner, clock, out }
+    }
+
+    pub fn inner(&self) -> &N {
+        &self.inner
+    }
+
+    pub fn out(&self) -> &W {
+        &self.out
+    }
+
+    /// Write one line of type `kind`; `fields` are appended after the
+    /// common ones and must start with a comma.
+    fn export(&mut self, kind: &str, fields: &str) {
+        let ts = self.clock.now().as_millis();
+        let line = format!("{{\"v\":{SCHEMA_VERSION},\"ts\":{ts},\"type\":\"{kind}\"{fields}}}");
+        if let Err(e) = writeln!(self.out, "{line}") {
+            log::warn!("Event export failed: {e}");
+        }
+    }
+}
+
+impl<N: BoardNotifier, C: Clock, W: Write> BoardNotifier for JsonlExporter<N, C, W> {
+    fn notify_game_status(&mut self, status: &GameStatus) {
+        self.inner.notify_game_status(status);
+        self.export("status", &status_fields(status));
+    }
+
+    fn notify_command_result(&mut self, result: &CommandResult) {
+        self.inner.notify_command_result(result);
+    }
+
+    fn update_player_type(&mut self, color: Color, player_type: PlayerType) {
+        self.inner.update_player_type(color, player_type);
+        let player = match player_type {
+            PlayerType::Human => "human",
+            PlayerType::Remote => "remote",
+            PlayerType::Random => "random",
+            PlayerType::Lichess => "lichess",
+            PlayerType::Relay => "relay",
+        };
+        self.export(
+            "player",
+            &format!(",\"color\":{},\"player\":\"{player}\"", color_json(color)),
+        );
+    }
+
+    fn notify_move_played(&mut self, color: Color, uci: &str, checksum: StateChecksum) {
+        self.inner.notify_move_played(color, uci, checksum);
+        self.export(
+            "move",
+            &format!(
+                ",\"color\":{},\"uci\":{},\"hash\":\"{:08x}\",\"ply\":{}",
+                color_json(color),
+                string_json(uci),
+                checksum.hash,
+                checksum.plies
+            ),
+        );
+    }
+
+    fn update_position(&mut self, fen: &str) {
+        self.inner.update_position(fen);
+        self.export("position", &format!(",\"fen\":{}", string_json(fen)));
+    }
+
+    fn update_last_move(&mut self, color: Color, uci: &str, checksum: StateChecksum) {
+        // Already exported as a `move`.
+        self.inner.update_last_move(color, uci, checksum);
+    }
+
+    fn reset_player_types(&mut self) {
+        self.inner.reset_player_types();
+    }
+
+    fn reset_position(&mut self) {
+        self.inner.reset_position();
+    }
+
+    fn reset_last_move(&mut self) {
+        self.inner.reset_last_move();
+    }
+
+    fn update_pending_promotion(&mut self, color: Color, uci: &str) {
+        self.inner.update_pending_promotion(color, uci);
+    }
+
+    fn reset_pending_promotion(&mut self) {
+        self.inner.reset_pending_promotion();
+    }
+
+    fn notify_game_event(&mut self, event: &GameEvent) {
+        self.inner.notify_game_event(event);
+        let fields = match *event {
+            GameEvent::PlayerReady { color } => {
+                format!(
+                    ",\"event\":\"player_ready\",\"color\":{}",
+                    color_json(color)
+                )
+            }
+            GameEvent::ClockStarted => ",\"event\":\"clock_started\"".to_string(),
+            GameEvent::BoardOutOfSync { squares } => {
+                let mut list = String::new();
+                for (i, square) in squares.into_iter().enumerate() {
+                    let sep = if i == 0 { "" } else { "," };
+                    let _ = write!(list, "{sep}\"{square}\"");
+                }
+                format!(",\"event\":\"board_out_of_sync\",\"squares\":[{list}]")
+            }
+            GameEvent::BoardInSync => ",\"event\":\"board_in_sync\"".to_string(),
+            GameEvent::Flagged { color } => {
+                format!(",\"event\":\"flagged\",\"color\":{}", color_json(color))
+            }
+            GameEvent::DrawClaimable { reason } => {
+                let reason = match reason {
+                    DrawReason::ThreefoldRepetition | DrawReason::FivefoldRepetition => {
+                        "repetition"
+                    }
+                    DrawReason::FiftyMoves | DrawReason::SeventyFiveMoves => "fifty_moves",
+                    DrawReason::DeadPosition => "dead_position",
+                };
+                format!(",\"event\":\"draw_claimable\",\"reason\":\"{reason}\"")
+            }
+            GameEvent::ResignedOnBoard { color } => {
+                format!(
+                    ",\"event\":\"resigned_on_board\",\"color\":{}",
+                    color_json(color)
+                )
+            }
+            GameEvent::NewGameRequested => ",\"event\":\"new_game_requested\"".to_string(),
+        };
+        self.export("event", &fields);
+    }
+
+    fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    fn update_clock(&mut self, remaining: ByColor<Duration>, running: Option<Color>) {
+        self.inner.update_clock(remaining, running);
+        self.export(
+            "clock",
+            &format!(
+                ",\"white_ms\":{},\"black_ms\":{},\"running\":{}",
+                remaining.white.as_millis(),
+                remaining.black.as_millis(),
+                running.map_or("null", color_json)
+            ),
+        );
+    }
+
+    fn notify_pairing_token(&mut self, token: Token) {
+        // A secret; never exported.
+        self.inner.notify_pairing_token(token);
+    }
+}
+
+fn color_json(color: Color) -> &'static str {
+    match color {
+        Color::White => "\"white\"",
+        Color::Black => "\"black\"",
+    }
+}
+
+fn string_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn status_fields(status: &GameStatus) -> String {
+    let (name, extra) = match *status {
+        GameStatus::Idle => ("idle", None),
+        GameStatus::AwaitingPieces => ("awaiting_pieces", None),
+        GameStatus::InProgress => ("in_progress", None),
+        GameStatus::Checkmate { loser } => ("checkmate", Some(("loser", loser))),
+        GameStatus::Stalemate => ("stalemate", None),
+        GameStatus::Resigned { color } => ("resigned", Some(("color", color))),
+        GameStatus::Timeout { loser } => ("timeout", Some(("loser", loser))),
+        GameStatus::Draw => ("draw", None),
+        GameStatus::Aborted => ("aborted", None),
+    };
+    let mut fields = format!(",\"status\":\"{name}\"");
+    if let Some((key, color)) = extra {
+        let _ = write!(fields, ",\"{key}\":{}", color_json(color));
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{RecordingNotifier, VirtualClock};
+    use shakmaty::{Bitboard, Square};
+
+    fn exporter() -> (
+        JsonlExporter<RecordingNotifier, VirtualClock, Vec<u8>>,
+        VirtualClock,
+    ) {
+        let clock = VirtualClock::new();
+        let exporter = JsonlExporter::new(RecordingNotifier::default(), clock.clone(), Vec::new());
+        (exporter, clock)
+    }
+
+    fn lines(exporter: &JsonlExporter<RecordingNotifier, VirtualClock, Vec<u8>>) -> Vec<String> {
+        String::from_utf8(exporter.out().clone())
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn updates_are_exported_with_timestamps_and_forwarded() {
+        let (mut exporter, clock) = exporter();
+        clock.advance(Duration::from_millis(1500));
+        let checksum = StateChecksum {
+            hash: 0xfd11_4196,
+            plies: 1,
+        };
+        exporter.notify_move_played(Color::White, "e2e4", checksum);
+        exporter.update_last_move(Color::White, "e2e4", checksum);
+        clock.advance(Duration::from_millis(20));
+        exporter.notify_game_status(&GameStatus::Checkmate {
+            loser: Color::Black,
+        });
+
+        assert_eq!(
+            lines(&exporter),
+            [
+                r#"{"v":1,"ts":1500,"type":"move","color":"white","uci":"e2e4","hash":"fd114196","ply":1}"#,
+                r#"{"v":1,"ts":1520,"type":"status","status":"checkmate","loser":"black"}"#,
+            ]
+        );
+        assert_eq!(exporter.inner().notifications.len(), 3);
+    }
+
+    #[test]
+    fn clock_and_events_are_exported() {
+        let (mut exporter, _) = exporter();
+        exporter.notify_game_event(&GameEvent::ClockStarted);
+        exporter.notify_game_event(&GameEvent::BoardOutOfSync {
+            squares: Bitboard::from(Square::D1) | Bitboard::from(Square::D3),
+        });
+        exporter.update_clock(
+            ByColor {
+                white: Duration::from_millis(59_250),
+                black: Duration::from_secs(60),
+            },
+            Some(Color::Black),
+        );
+
+        assert_eq!(
+            lines(&exporter),
+            [
+                r#"{"v":1,"ts":0,"type":"event","event":"clock_started"}"#,
+                r#"{"v":1,"ts":0,"type":"event","event":"board_out_of_sync","squares":["d1","d3"]}"#,
+                r#"{"v":1,"ts":0,"type":"clock","white_ms":59250,"black_ms":60000,"running":"black"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(string_json("a\"b\\c\n"), r#""a\"b\\c\u000a""#);
+    }
+}
@@ -1,6 +1,8 @@
+use crate::board_api::GameStatus;
+use crate::move_index::MoveIndex;
+
 use shakmaty::{
-    Bitboard, ByColor, CastlingSide, Chess, Color, File, Move, MoveList, Position, Rank, Role,
-    Square,
+    Bitboard, Board, ByColor, CastlingSide, Chess, Color, File, Move, Position, Rank, Role, Square,
 };
 
 /// Type of visual feedback for an individual square
@@ -8,6 +10,9 @@ use shakmaty::{
 pub enum SquareFeedback {
     /// Place your piece here (legal destination or move completion)
     Destination,
+    /// Where the rook goes when castling, shown next to the king's
+    /// destination
+    RookDestination,
     /// Placing here captures an opponent piece
     Capture,
     /// Lift this piece to move or capture (origin of move)
@@ -18,7 +23,8 @@ pub enum SquareFeedback {
     Checker,
     /// Winning piece (delivered checkmate)
     Victory,
-    /// King in stalemate (neither side wins)
+    /// King in stalemate (neither side wins); also marks a dead-sensor
+    /// square whose move must be announced
     Stalemate,
 }
 
@@ -85,6 +91,12 @@ impl BoardFeedback {
         self.squares[square as usize] = Some(feedback);
     }
 
+    /// Remove feedback for a specific square
+    #[inline]
+    pub fn clear(&mut self, square: Square) {
+        self.squares[square as usize] = None;
+    }
+
     /// Check if any feedback exists
     ///
     /// Returns true if there are no feedback squares or status to display.
@@ -93,6 +105,19 @@ impl BoardFeedback {
         self.status.is_none() && self.squares.iter().all(|s| s.is_none())
     }
 
+    /// The same feedback on a board turned 180°, as shown when Black's
+    /// pieces are set up along the board's first rank.
+    pub fn rotated(&self) -> Self {
+        let mut rotated = Self {
+            squares: [None; 64],
+            status: self.status,
+        };
+        for (square, feedback) in self.squares() {
+            rotated.set(square.rotate_180(), feedback);
+        }
+        rotated
+    }
+
     /// Return a copy with the given status merged in (overwrites any existing status).
     pub fn with_merged_status(mut self, kind: StatusKind) -> Self {
         self.status = Some(kind);
@@ -131,11 +156,17 @@ pub enum GameOutcome {
 /// for pieces that were never physically present in this interaction cycle).
 ///
 /// Delegates to `compute_state_feedback` for recovery and unresolved multi-piece scenarios.
+///
+/// Runs every tick, so it does not allocate: the feedback is a fixed-size
+/// value and the legal moves are indexed on the stack.
 pub fn compute_feedback(
     position: &Chess,
     curr_sensors: ByColor<Bitboard>,
     reference_sensors: ByColor<Bitboard>,
 ) -> BoardFeedback {
+    if !has_both_kings(position.board()) {
+        return corrupted_feedback(position);
+    }
     let curr_combined = curr_sensors.white | curr_sensors.black;
 
     let turn = position.turn();
@@ -154,7 +185,7 @@ pub fn compute_feedback(
         | (expected_board.by_color(Color::Black) & curr_sensors.white);
     let in_recovery = !occupancy_diff.is_empty() || !wrong_color.is_empty();
 
-    let legal_moves = position.legal_moves();
+    let legal_moves = MoveIndex::new(position);
 
     // Game outcome
     let outcome = compute_outcome(position, &legal_moves);
@@ -170,6 +201,18 @@ pub fn compute_feedback(
         return fb;
     }
 
+    // Drop: a single piece of the side to move appeared from the hand.
+    let placed = curr_sensors[turn] & !expected_board.occupied();
+    if !legal_moves.drops().is_empty()
+        && lifted.is_empty()
+        && captured.is_empty()
+        && wrong_color.is_empty()
+        && occupancy_diff == placed
+        && let Some(placed) = placed.single_square()
+    {
+        return show_drop_feedback(&legal_moves, placed);
+    }
+
     // Recovery: board diverges from expected position
     if in_recovery {
         return recovery_feedback(expected_board, &curr_sensors);
@@ -179,7 +222,7 @@ pub fn compute_feedback(
     let captured_sq = captured.single_square();
 
     match (captured_sq, lifted_sq) {
-        (None, Some(from)) => show_destinations_for(&legal_moves, from),
+        (None, Some(from)) => show_destinations_for(&legal_moves, from, lifted),
         (Some(to), None) => {
             let fb = show_capture_options(&legal_moves, to);
             if fb.is_empty() {
@@ -210,7 +253,10 @@ pub fn compute_feedback(
 /// Used directly for non-interactive player turns, and as a fallback from
 /// `compute_feedback` when the board diverges from expected position.
 pub fn compute_state_feedback(position: &Chess, curr_sensors: ByColor<Bitboard>) -> BoardFeedback {
-    let legal_moves = position.legal_moves();
+    if !has_both_kings(position.board()) {
+        return corrupted_feedback(position);
+    }
+    let legal_moves = MoveIndex::new(position);
 
     if let Some(outcome) = compute_outcome(position, &legal_moves) {
         return show_outcome_feedback(outcome);
@@ -234,31 +280,128 @@ pub fn compute_state_feedback(position: &Chess, curr_sensors: ByColor<Bitboard>)
     BoardFeedback::default()
 }
 
-fn compute_outcome(position: &Chess, legal_moves: &MoveList) -> Option<GameOutcome> {
+/// Whether the board disagrees with `position` in a way no move in
+/// progress explains: a piece on an empty square or of the wrong color,
+/// or a missing piece the side to move cannot have lifted or captured. A
+/// castling king waiting for its rook is not out of sync.
+///
+/// This is the case where [`compute_feedback`] shows recovery guidance.
+pub fn is_desynced(
+    position: &Chess,
+    curr_sensors: ByColor<Bitboard>,
+    reference_sensors: ByColor<Bitboard>,
+) -> bool {
+    if !has_both_kings(position.board()) {
+        return true;
+    }
+    let curr_combined = curr_sensors.white | curr_sensors.black;
+    let turn = position.turn();
+    let expected_board = position.board();
+    let lifted = expected_board.by_color(turn) & !curr_combined & reference_sensors[turn];
+    let captured =
+        expected_board.by_color(turn.other()) & !curr_combined & reference_sensors[turn.other()];
+    let occupancy_diff = (expected_board.occupied() ^ curr_combined) & !lifted & !captured;
+    let wrong_color = (expected_board.by_color(Color::White) & curr_sensors.black)
+        | (expected_board.by_color(Color::Black) & curr_sensors.white);
+    if occupancy_diff.is_empty() && wrong_color.is_empty() {
+        return false;
+    }
+    let legal_moves = MoveIndex::new(position);
+    let placed = curr_sensors[turn] & !expected_board.occupied();
+    let dropping = !legal_moves.drops().is_empty()
+        && lifted.is_empty()
+        && captured.is_empty()
+        && wrong_color.is_empty()
+        && occupancy_diff == placed
+        && placed.single_square().is_some();
+    !dropping && detect_castle_guidance(position, &curr_sensors, &legal_moves).is_none()
+}
+
+/// Whether `board` has exactly one king per side.
+///
+/// Move generation assumes it and panics otherwise, so a position that
+/// lost a king (e.g. one built with an unchecked king capture) must be
+/// caught before its legal moves are asked for.
+pub fn has_both_kings(board: &Board) -> bool {
+    Color::ALL
+        .into_iter()
+        .all(|color| (board.kings() & board.by_color(color)).count() == 1)
+}
+
+/// Feedback for a position that breaks [`has_both_kings`]: nothing to
+/// guide towards, so only the failure status is lit.
+fn corrupted_feedback(position: &Chess) -> BoardFeedback {
+    log::error!(
+        "Position {} does not have one king per side",
+        position.board()
+    );
+    BoardFeedback::with_status(StatusKind::Failure)
+}
+
+/// Feedback for a finished game.
+///
+/// Checkmate and stalemate are derived from the position. Results decided
+/// off the board reuse the same patterns: a loss (resignation, timeout)
+/// lights the winner's back rank as [`SquareFeedback::Victory`] and the
+/// loser's as [`SquareFeedback::Check`]; a draw lights both back ranks as
+/// [`SquareFeedback::Stalemate`]; an aborted game clears the board.
+///
+/// Returns `None` while the game is not over.
+pub fn result_feedback(position: &Chess, status: &GameStatus) -> Option<BoardFeedback> {
+    let king_of = |color: Color| position.board().king_of(color);
+    match *status {
+        GameStatus::Checkmate { .. } | GameStatus::Stalemate
+            if !has_both_kings(position.board()) =>
+        {
+            Some(corrupted_feedback(position))
+        }
+        GameStatus::Checkmate { .. } | GameStatus::Stalemate => {
+            compute_outcome(position, &MoveIndex::new(position)).map(show_outcome_feedback)
+        }
+        GameStatus::Resigned { color: loser } | GameStatus::Timeout { loser } => {
+            let mut fb = BoardFeedback::new();
+            fill_rank(&mut fb, back_rank(loser.other()), SquareFeedback::Victory);
+            fill_rank(&mut fb, back_rank(loser), SquareFeedback::Check);
+            if let Some(king) = king_of(loser) {
+                fb.set(king, SquareFeedback::Check);
+            }
+            if let Some(king) = king_of(loser.other()) {
+                fb.set(king, SquareFeedback::Victory);
+            }
+            Some(fb)
+        }
+        GameStatus::Draw => {
+            let mut fb = BoardFeedback::new();
+            fill_rank(&mut fb, Rank::First, SquareFeedback::Stalemate);
+            fill_rank(&mut fb, Rank::Eighth, SquareFeedback::Stalemate);
+            for king in position.board().kings() {
+                fb.set(king, SquareFeedback::Stalemate);
+            }
+            Some(fb)
+        }
+        GameStatus::Aborted => Some(BoardFeedback::new()),
+        GameStatus::Idle | GameStatus::AwaitingPieces | GameStatus::InProgress => None,
+    }
+}
+
+fn compute_outcome(position: &Chess, legal_moves: &MoveIndex) -> Option<GameOutcome> {
     if !legal_moves.is_empty() {
         return None;
     }
 
+    let board = position.board();
+    let (Some(white_king), Some(black_king)) =
+        (board.king_of(Color::White), board.king_of(Color::Black))
+    else {
+        return None;
+    };
     if position.is_check() {
-        let king_square = position.our(Role::King).first().expect("king must exist");
         Some(GameOutcome::Checkmate {
-            king_square,
+            king_square: position.turn().fold_wb(white_king, black_king),
             checkers: position.checkers(),
             loser: position.turn(),
         })
     } else {
-        let white_king = position
-            .board()
-            .by_role(Role::King)
-            .intersect(position.board().by_color(Color::White))
-            .first()
-            .expect("white king must exist");
-        let black_king = position
-            .board()
-            .by_role(Role::King)
-            .intersect(position.board().by_color(Color::Black))
-            .first()
-            .expect("black king must exist");
         Some(GameOutcome::Stalemate {
             white_king,
             black_king,
@@ -294,22 +437,34 @@ fn recovery_feedback(
 fn detect_castle_guidance(
     position: &Chess,
     curr_sensors: &ByColor<Bitboard>,
-    legal_moves: &MoveList,
+    legal_moves: &MoveIndex,
 ) -> Option<BoardFeedback> {
     let turn = position.turn();
     let expected_our = position.board().by_color(turn);
     let our_current = curr_sensors[turn];
     let newly_placed = our_current & !expected_our;
+    let king = position.our(Role::King).first()?;
 
-    for mv in legal_moves {
+    for mv in legal_moves.moves_from(king) {
         if let Move::Castle { king, rook } = *mv {
             let side = CastlingSide::from_king_side(king < rook);
             let king_target = side.king_to(turn);
+            let rook_target = side.rook_to(turn);
+            let mut fb = BoardFeedback::new();
             if newly_placed.contains(king_target) {
-                let rook_target = side.rook_to(turn);
-                let mut fb = BoardFeedback::new();
-                fb.set(rook, SquareFeedback::Origin);
-                fb.set(rook_target, SquareFeedback::Destination);
+                // King placed first: the rook follows.
+                if our_current.contains(rook) {
+                    fb.set(rook, SquareFeedback::Origin);
+                }
+                fb.set(rook_target, SquareFeedback::RookDestination);
+                return Some(fb);
+            }
+            if newly_placed.contains(rook_target)
+                && !our_current.contains(king)
+                && !our_current.contains(rook)
+            {
+                // Rook placed while the king is in hand: the king follows.
+                fb.set(king_target, SquareFeedback::Destination);
                 return Some(fb);
             }
         }
@@ -317,13 +472,14 @@ fn detect_castle_guidance(
     None
 }
 
-fn resolve_lifted_piece(legal_moves: &MoveList, lifted: Bitboard) -> Option<Square> {
+fn resolve_lifted_piece(legal_moves: &MoveIndex, lifted: Bitboard) -> Option<Square> {
     lifted.single_square().or_else(|| {
         if lifted.count() != 2 {
             return None;
         }
-        legal_moves
-            .iter()
+        lifted
+            .into_iter()
+            .flat_map(|square| legal_moves.moves_from(square))
             .find(|mv| {
                 matches!(mv, Move::Castle { king, rook } if lifted.contains(*king) && lifted.contains(*rook))
             })
@@ -374,22 +530,49 @@ fn fill_rank(fb: &mut BoardFeedback, rank: Rank, feedback: SquareFeedback) {
     }
 }
 
-fn show_destinations_for(legal_moves: &[Move], from: Square) -> BoardFeedback {
+/// Destinations of the piece lifted from `from`. A castle shows the king's
+/// destination, and the rook (unless already lifted) with its destination,
+/// which takes precedence over a plain king move to the same square.
+fn show_destinations_for(legal_moves: &MoveIndex, from: Square, lifted: Bitboard) -> BoardFeedback {
     let mut fb = BoardFeedback::new();
+    let mut rook_fb = BoardFeedback::new();
     fb.set(from, SquareFeedback::Origin);
-    for mv in legal_moves.iter().filter(|mv| mv.from() == Some(from)) {
+    for mv in legal_moves.moves_from(from) {
         let (sq, kind) = classify_move(mv);
         fb.set(sq, kind);
+        if let Move::Castle { king, rook } = *mv {
+            let side = CastlingSide::from_king_side(king < rook);
+            let rook_to = Square::from_coords(side.rook_to_file(), rook.rank());
+            if !lifted.contains(rook) {
+                rook_fb.set(rook, SquareFeedback::Origin);
+            }
+            rook_fb.set(rook_to, SquareFeedback::RookDestination);
+        }
+    }
+    for (sq, kind) in rook_fb.squares() {
+        fb.set(sq, kind);
     }
     fb
 }
 
-fn show_capture_options(legal_moves: &[Move], captured_sq: Square) -> BoardFeedback {
+/// Legal drop squares; a piece dropped anywhere else is marked for removal.
+fn show_drop_feedback(legal_moves: &MoveIndex, placed: Square) -> BoardFeedback {
     let mut fb = BoardFeedback::new();
-    for mv in legal_moves
-        .iter()
-        .filter(|mv| captures_square(mv, captured_sq))
-    {
+    let drop_squares = legal_moves.drop_squares();
+    if drop_squares.contains(placed) {
+        fb.set(placed, SquareFeedback::Destination);
+        return fb;
+    }
+    for sq in drop_squares {
+        fb.set(sq, SquareFeedback::Destination);
+    }
+    fb.set(placed, SquareFeedback::Capture);
+    fb
+}
+
+fn show_capture_options(legal_moves: &MoveIndex, captured_sq: Square) -> BoardFeedback {
+    let mut fb = BoardFeedback::new();
+    for mv in legal_moves.moves_capturing(captured_sq) {
         fb.set(mv.to(), SquareFeedback::Destination);
         if let Some(from) = mv.from() {
             fb.set(from, SquareFeedback::Origin);
@@ -399,15 +582,16 @@ fn show_capture_options(legal_moves: &[Move], captured_sq: Square) -> BoardFeedb
 }
 
 fn show_capture_completion(
-    legal_moves: &[Move],
+    legal_moves: &MoveIndex,
     from: Square,
     captured_sq: Square,
 ) -> BoardFeedback {
     let mut fb = BoardFeedback::new();
     fb.set(from, SquareFeedback::Origin);
     for mv in legal_moves
+        .moves_capturing(captured_sq)
         .iter()
-        .filter(|mv| mv.from() == Some(from) && captures_square(mv, captured_sq))
+        .filter(|mv| mv.from() == Some(from))
     {
         fb.set(mv.to(), SquareFeedback::Destination);
     }
@@ -416,8 +600,9 @@ fn show_capture_completion(
 
 fn show_check_feedback(position: &Chess) -> BoardFeedback {
     let mut fb = BoardFeedback::new();
-    let king_square = position.our(Role::King).first().expect("king must exist");
-    fb.set(king_square, SquareFeedback::Check);
+    if let Some(king_square) = position.board().king_of(position.turn()) {
+        fb.set(king_square, SquareFeedback::Check);
+    }
     for sq in position.checkers() {
         fb.set(sq, SquareFeedback::Checker);
     }
@@ -436,18 +621,6 @@ fn classify_move(mv: &Move) -> (Square, SquareFeedback) {
     }
 }
 
-fn captures_square(mv: &Move, captured_sq: Square) -> bool {
-    match mv {
-        Move::Normal {
-            capture: Some(_),
-            to,
-            ..
-        } => captured_sq == *to,
-        Move::EnPassant { from, to } => Square::from_coords(to.file(), from.rank()) == captured_sq,
-        _ => false,
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -479,6 +652,28 @@ mod tests {
 
     // --- BoardFeedback struct tests ---
 
+    #[test]
+    fn drops_highlight_legal_drop_squares() {
+        let drop = |to| Move::Put {
+            role: Role::Knight,
+            to,
+        };
+        let index = MoveIndex::from_moves(&[drop(Square::C3), drop(Square::F3)]);
+
+        let misplaced = show_drop_feedback(&index, Square::H5);
+        assert_eq!(misplaced.get(Square::H5), Some(SquareFeedback::Capture));
+        assert_eq!(misplaced.get(Square::C3), Some(SquareFeedback::Destination));
+        assert_eq!(misplaced.get(Square::F3), Some(SquareFeedback::Destination));
+
+        let placed = show_drop_feedback(&index, Square::F3);
+        assert_eq!(placed.squares().count(), 1);
+        assert_eq!(placed.get(Square::F3), Some(SquareFeedback::Destination));
+        assert_eq!(
+            classify_move(&drop(Square::C3)),
+            (Square::C3, SquareFeedback::Destination)
+        );
+    }
+
     #[test]
     fn no_feedback_when_nothing_happening() {
         let position = Chess::default();
@@ -489,6 +684,34 @@ mod tests {
         assert!(fb.is_empty());
     }
 
+    #[test]
+    fn position_without_a_king_lights_the_failure_status() {
+        let mut position = position_from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        // A king capture nobody checked; move generation would panic.
+        position.play_unchecked(Move::Normal {
+            role: Role::Rook,
+            from: Square::A1,
+            capture: Some(Role::King),
+            to: Square::E8,
+            promotion: None,
+        });
+        assert!(!has_both_kings(position.board()));
+        let sensors = sensors_from_position(&position);
+
+        let failure = BoardFeedback::with_status(StatusKind::Failure);
+        assert_eq!(compute_feedback(&position, sensors, sensors), failure);
+        assert_eq!(compute_state_feedback(&position, sensors), failure);
+        assert_eq!(
+            result_feedback(
+                &position,
+                &GameStatus::Checkmate {
+                    loser: Color::Black
+                }
+            ),
+            Some(failure)
+        );
+    }
+
     #[test]
     fn with_status_returns_status() {
         let feedback = BoardFeedback::with_status(StatusKind::Pending);
@@ -716,6 +939,42 @@ mod tests {
         assert_eq!(fb.get(Square::H8), Some(SquareFeedback::Stalemate));
     }
 
+    #[test]
+    fn result_feedback_for_resignation() {
+        let fb = result_feedback(
+            &Chess::default(),
+            &GameStatus::Resigned {
+                color: Color::White,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(fb.get(Square::E1), Some(SquareFeedback::Check));
+        assert_eq!(fb.get(Square::A1), Some(SquareFeedback::Check));
+        assert_eq!(fb.get(Square::E8), Some(SquareFeedback::Victory));
+        assert_eq!(fb.get(Square::H8), Some(SquareFeedback::Victory));
+    }
+
+    #[test]
+    fn result_feedback_for_draw() {
+        let fb = result_feedback(&Chess::default(), &GameStatus::Draw).unwrap();
+
+        assert_eq!(fb.get(Square::A1), Some(SquareFeedback::Stalemate));
+        assert_eq!(fb.get(Square::H8), Some(SquareFeedback::Stalemate));
+        assert_eq!(fb.get(Square::E4), None);
+    }
+
+    #[test]
+    fn result_feedback_for_aborted_game_is_empty() {
+        let fb = result_feedback(&Chess::default(), &GameStatus::Aborted).unwrap();
+        assert!(fb.is_empty());
+    }
+
+    #[test]
+    fn result_feedback_none_while_in_progress() {
+        assert!(result_feedback(&Chess::default(), &GameStatus::InProgress).is_none());
+    }
+
     // --- Recovery ---
 
     #[test]
@@ -832,6 +1091,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn lifted_king_shows_castle_destination_not_rook_origin() {
+        let position = position_from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1");
+        let reference = sensors_from_position(&position);
+        let mut curr = reference;
+        curr.white.toggle(Square::E1);
+
+        let fb = compute_feedback(&position, curr, reference);
+
+        assert_eq!(fb.get(Square::E1), Some(SquareFeedback::Origin));
+        assert_eq!(fb.get(Square::G1), Some(SquareFeedback::Destination));
+        assert_eq!(fb.get(Square::C1), Some(SquareFeedback::Destination));
+        assert_eq!(fb.get(Square::H1), Some(SquareFeedback::Origin));
+        assert_eq!(fb.get(Square::A1), Some(SquareFeedback::Origin));
+        assert_eq!(fb.get(Square::F1), Some(SquareFeedback::RookDestination));
+        assert_eq!(fb.get(Square::D1), Some(SquareFeedback::RookDestination));
+    }
+
+    #[test]
+    fn lifted_king_and_rook_show_both_castle_destinations() {
+        let position = position_from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1");
+        let reference = sensors_from_position(&position);
+        let mut curr = reference;
+        curr.white.toggle(Square::E1);
+        curr.white.toggle(Square::H1);
+
+        let fb = compute_feedback(&position, curr, reference);
+
+        assert_eq!(fb.get(Square::G1), Some(SquareFeedback::Destination));
+        assert_eq!(fb.get(Square::F1), Some(SquareFeedback::RookDestination));
+        assert_eq!(fb.get(Square::H1), None);
+        assert_eq!(
+            fb.get(Square::A1),
+            Some(SquareFeedback::Origin),
+            "queenside rook is still down"
+        );
+    }
+
     #[test]
     fn mid_castle_shows_rook_guidance() {
         // White can castle kingside — king placed on g1 but rook still on h1
@@ -846,6 +1143,67 @@ mod tests {
         let fb = compute_feedback(&position, curr, sensors_from_position(&position));
 
         assert_eq!(fb.get(Square::H1), Some(SquareFeedback::Origin));
-        assert_eq!(fb.get(Square::F1), Some(SquareFeedback::Destination));
+        assert_eq!(fb.get(Square::F1), Some(SquareFeedback::RookDestination));
+
+        // Rook lifted on its way: only its destination remains.
+        curr.white.toggle(Square::H1);
+        let fb = compute_feedback(&position, curr, sensors_from_position(&position));
+        assert_eq!(fb.get(Square::H1), None);
+        assert_eq!(fb.get(Square::F1), Some(SquareFeedback::RookDestination));
+    }
+
+    #[test]
+    fn feedback_does_not_allocate() {
+        let castling = position_from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1");
+        let capture =
+            position_from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2");
+        let mate =
+            position_from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+        let mut lifted = sensors_from_position(&capture);
+        lifted.white.toggle(Square::E4);
+        let mut taking = lifted;
+        taking.black.toggle(Square::D5);
+        let mut castle = sensors_from_position(&castling);
+        castle.white.toggle(Square::E1);
+        let mut stray = starting_sensors();
+        stray.white.toggle(Square::E5);
+
+        let cases = [
+            (&capture, lifted, sensors_from_position(&capture)),
+            (&capture, taking, sensors_from_position(&capture)),
+            (&castling, castle, sensors_from_position(&castling)),
+            (
+                &mate,
+                sensors_from_position(&mate),
+                sensors_from_position(&mate),
+            ),
+            (&Chess::default(), stray, starting_sensors()),
+        ];
+        for (position, curr, reference) in cases {
+            let fb = compute_feedback(position, curr, reference);
+            assert!(fb.squares().next().is_some() || fb.status().is_some());
+            assert_eq!(
+                crate::testutil::allocations_during(|| compute_feedback(position, curr, reference)),
+                0,
+                "{}",
+                Fen::from_position(position, shakmaty::EnPassantMode::Legal)
+            );
+        }
+    }
+
+    #[test]
+    fn rook_placed_first_guides_the_king() {
+        let position =
+            position_from_fen("rnbqkbnr/pppppppp/8/8/8/5NP1/PPPPPPBP/RNBQK2R w KQkq - 0 1");
+        let reference = sensors_from_position(&position);
+        let mut curr = reference;
+        curr.white.toggle(Square::E1); // king in hand
+        curr.white.toggle(Square::H1);
+        curr.white.toggle(Square::F1); // rook already on f1
+
+        let fb = compute_feedback(&position, curr, reference);
+
+        assert_eq!(fb.get(Square::G1), Some(SquareFeedback::Destination));
+        assert_eq!(fb.squares().count(), 1);
     }
 }
@@ -0,0 +1,582 @@
+//! Flight recorder: raw sensor readings kept on storage for offline analysis.
+//!
+//! A serial capture only exists when someone was watching. The flight
+//! recorder instead keeps the last weeks of readings in a fixed-size ring
+//! file (e.g. on an SD card), so a misdetection a user reports days later
+//! can still be replayed with `replay-log`.
+//!
+//! The file is a header block followed by `blocks` slots of [`BLOCK_SIZE`]
+//! bytes, one SD sector each. A slot holds one self-contained block of
+//! frames, LZ4-compressed (see [`crate::compress`]):
+//! `[seq: u32][len: u16][raw_len: u16][compressed frames...]`, with
+//! `seq == 0` for an empty slot. The first frame of a block is absolute (`time_ms`, `read` as
+//! varints, then the white and black bitboards); each following frame is
+//! the varint time and read deltas and the squares that changed, one byte
+//! each (`square | color << 6`). A typical move costs a few bytes per
+//! reading before compression, which then folds the repeated lift and
+//! replace patterns. When the ring is full the oldest block is overwritten.
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::time::Duration;
+
+use shakmaty::{Bitboard, ByColor, Color, Square};
+
+use crate::PieceSensor;
+use crate::app::Clock;
+use crate::calibration::Calibration;
+use crate::compress::{self, BlockCompressor};
+use crate::tick_log::Tick;
+
+/// Size of the header and of each slot.
+pub const BLOCK_SIZE: usize = 512;
+
+/// How long readings may wait in memory before their block is written.
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+const MAGIC: [u8; 4] = *b"UCFR";
+const VERSION: u8 = 2;
+const BLOCK_HEADER: usize = 8;
+const PAYLOAD_SIZE: usize = BLOCK_SIZE - BLOCK_HEADER;
+/// Bounds the frames per block, and with it the work to compress one.
+const MAX_RAW_SIZE: usize = 4096;
+/// Longest encoding of one frame: two varints, the change count and every
+/// square changed.
+const MAX_FRAME_SIZE: usize = 10 + 10 + 2 + 128;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecorderError {
+    #[error("storage: {0}")]
+    Io(#[from] io::Error),
+    #[error("not a flight recording")]
+    NotARecording,
+    #[error("block {slot}: corrupt frame data")]
+    Corrupt { slot: u32 },
+}
+
+/// One recorded reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordedFrame {
+    /// Time since boot when the reading was first seen.
+    pub time: Duration,
+    pub tick: Tick,
+}
+
+/// Writes frames into a ring file.
+#[derive(Debug)]
+pub struct FlightRecorder<F> {
+    file: F,
+    blocks: u32,
+    /// Slot of the block being filled.
+    slot: u32,
+    /// Sequence number of the block being filled.
+    seq: u32,
+    block: BlockEncoder,
+    /// The block has frames not yet written.
+    dirty: bool,
+    last_flush: Duration,
+}
+
+impl<F: Read + Write + Seek> FlightRecorder<F> {
+    /// Continue the ring in `file`, or format it with `blocks` slots if it
+    /// does not hold a ring of that size.
+    pub fn open(mut file: F, blocks: u32) -> Result<Self, RecorderError> {
+        let blocks = blocks.max(1);
+        let latest = match read_header(&mut file) {
+            Ok(existing) if existing == blocks => latest_block(&mut file, blocks)?,
+            _ => {
+                format(&mut file, blocks)?;
+                None
+            }
+        };
+        let (slot, seq) = match latest {
+            Some((slot, seq)) => ((slot + 1) % blocks, seq.wrapping_add(1).max(1)),
+            None => (0, 1),
+        };
+        Ok(Self {
+            file,
+            blocks,
+            slot,
+            seq,
+            block: BlockEncoder::new(),
+            dirty: false,
+            last_flush: Duration::ZERO,
+        })
+    }
+
+    /// Append a reading. Full blocks are written right away, others once
+    /// [`FLUSH_INTERVAL`] has passed (see [`Self::poll`]).
+    pub fn record(&mut self, frame: RecordedFrame) -> Result<(), RecorderError> {
+        if !self.block.push(frame) {
+            self.write_block()?;
+            self.slot = (self.slot + 1) % self.blocks;
+            self.seq = self.seq.wrapping_add(1).max(1);
+            self.block.clear();
+            self.block.push(frame);
+        }
+        self.dirty = true;
+        self.poll(frame.time)
+    }
+
+    /// Write the current block if it has waited for [`FLUSH_INTERVAL`].
+    pub fn poll(&mut self, now: Duration) -> Result<(), RecorderError> {
+        if self.dirty && now.saturating_sub(self.last_flush) >= FLUSH_INTERVAL {
+            self.flush()?;
+            self.last_flush = now;
+        }
+        Ok(())
+    }
+
+    /// Write the current block now.
+    pub fn flush(&mut self) -> Result<(), RecorderError> {
+        if self.dirty {
+            self.write_block()?;
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> F {
+        self.file
+    }
+
+    fn write_block(&mut self) -> Result<(), RecorderError> {
+        let mut sector = [0u8; BLOCK_SIZE];
+        sector[..4].copy_from_slice(&self.seq.to_le_bytes());
+        let payload = &self.block.packed;
+        sector[4..6].copy_from_slice(&(payload.len() as u16).to_le_bytes());
+        sector[6..8].copy_from_slice(&(self.block.raw.len() as u16).to_le_bytes());
+        sector[BLOCK_HEADER..BLOCK_HEADER + payload.len()].copy_from_slice(payload);
+        self.file.seek(SeekFrom::Start(slot_offset(self.slot)))?;
+        self.file.write_all(&sector)?;
+        self.file.flush()?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Decode a whole ring file, oldest frame first.
+pub fn read_recording(bytes: &[u8]) -> Result<Vec<RecordedFrame>, RecorderError> {
+    let mut cursor = io::Cursor::new(bytes);
+    let blocks = read_header(&mut cursor)?;
+    let mut filled = Vec::new();
+    for slot in 0..blocks {
+        let start = slot_offset(slot) as usize;
+        let Some(sector) = bytes.get(start..start + BLOCK_SIZE) else {
+            break;
+        };
+        let seq = u32::from_le_bytes([sector[0], sector[1], sector[2], sector[3]]);
+        if seq != 0 {
+            filled.push((seq, slot, sector));
+        }
+    }
+    filled.sort_by_key(|&(seq, ..)| seq);
+    let mut frames = Vec::new();
+    for (_, slot, sector) in filled {
+        let len = usize::from(u16::from_le_bytes([sector[4], sector[5]]));
+        let raw_len = usize::from(u16::from_le_bytes([sector[6], sector[7]]));
+        let payload = sector
+            .get(BLOCK_HEADER..BLOCK_HEADER + len)
+            .and_then(|packed| compress::decompress_block(packed, raw_len).ok())
+            .ok_or(RecorderError::Corrupt { slot })?;
+        decode_block(&payload, &mut frames).ok_or(RecorderError::Corrupt { slot })?;
+    }
+    Ok(frames)
+}
+
+/// Whether `bytes` start like a flight recording.
+pub fn is_recording(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Records each changed reading of the wrapped sensor, if a recorder is
+/// attached. Storage errors are logged and stop the recording; the sensor
+/// keeps working.
+#[derive(Debug)]
+pub struct RecordingSensor<S, C, F> {
+    inner: S,
+    clock: C,
+    recorder: Option<FlightRecorder<F>>,
+    last: Option<ByColor<Bitboard>>,
+    reads: u64,
+}
+
+impl<S, C, F> RecordingSensor<S, C, F> {
+    pub fn new(inner: S, clock: C, recorder: Option<FlightRecorder<F>>) -> Self {
+        Self {
+            inner,
+            clock,
+            recorder,
+            last: None,
+            reads: 0,
+        }
+    }
+}
+
+impl<S: PieceSensor, C: Clock, F: Read + Write + Seek> PieceSensor for RecordingSensor<S, C, F> {
+    type Error = S::Error;
+
+    fn read_positions(&mut self) -> Result<ByColor<Bitboard>, Self::Error> {
+        let positions = self.inner.read_positions()?;
+        let read = self.reads;
+        self.reads += 1;
+        if let Some(recorder) = &mut self.recorder {
+            let now = self.clock.now();
+            let result = if self.last != Some(positions) {
+                recorder.record(RecordedFrame {
+                    time: now,
+                    tick: Tick { read, positions },
+                })
+            } else {
+                recorder.poll(now)
+            };
+            if let Err(e) = result {
+                log::warn!("Flight recorder stopped: {e}");
+                self.recorder = None;
+            }
+        }
+        self.last = Some(positions);
+        Ok(positions)
+    }
+
+    fn read_millivolts(&mut self) -> Option<Result<[u16; 64], Self::Error>> {
+        self.inner.read_millivolts()
+    }
+
+    fn calibrate(&mut self, calibration: Calibration) {
+        self.inner.calibrate(calibration);
+    }
+}
+
+fn slot_offset(slot: u32) -> u64 {
+    (u64::from(slot) + 1) * BLOCK_SIZE as u64
+}
+
+fn read_header(file: &mut (impl Read + Seek)) -> Result<u32, RecorderError> {
+    let mut header = [0u8; 9];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut header)
+        .map_err(|_| RecorderError::NotARecording)?;
+    if header[..4] != MAGIC || header[4] != VERSION {
+        return Err(RecorderError::NotARecording);
+    }
+    Ok(u32::from_le_bytes([
+        header[5], header[6], header[7], header[8],
+    ]))
+}
+
+fn format(file: &mut (impl Write + Seek), blocks: u32) -> Result<(), RecorderError> {
+    let mut header = [0u8; BLOCK_SIZE];
+    header[..4].copy_from_slice(&MAGIC);
+    header[4] = VERSION;
+    header[5..9].copy_from_slice(&blocks.to_le_bytes());
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(&header)?;
+    let empty = [0u8; BLOCK_SIZE];
+    for _ in 0..blocks {
+        file.write_all(&empty)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+/// Slot and sequence number of the newest block.
+fn latest_block(
+    file: &mut (impl Read + Seek),
+    blocks: u32,
+) -> Result<Option<(u32, u32)>, RecorderError> {
+    let mut latest = None;
+    for slot in 0..blocks {
+        let mut seq = [0u8; 4];
+        file.seek(SeekFrom::Start(slot_offset(slot)))?;
+        file.read_exact(&mut seq)?;
+        let seq = u32::from_le_bytes(seq);
+        if seq != 0 && latest.is_none_or(|(_, newest)| seq > newest) {
+            latest = Some((slot, seq));
+        }
+    }
+    Ok(latest)
+}
+
+/// Frames of one block being encoded.
+///
+/// Recording runs on every new reading, so the buffers are sized for a
+/// full block up front and reused: once open, a recorder does not allocate.
+#[derive(Debug)]
+struct BlockEncoder {
+    raw: Vec<u8>,
+    /// `raw`, compressed.
+    packed: Vec<u8>,
+    /// `raw` with the newest frame, compressed; becomes `packed` if it fits.
+    scratch: Vec<u8>,
+    compressor: BlockCompressor,
+    prev: Option<RecordedFrame>,
+}
+
+impl BlockEncoder {
+    fn new() -> Self {
+        let packed_capacity = compress::max_compressed_len(MAX_RAW_SIZE + MAX_FRAME_SIZE);
+        Self {
+            raw: Vec::with_capacity(MAX_RAW_SIZE + MAX_FRAME_SIZE),
+            packed: Vec::with_capacity(packed_capacity),
+            scratch: Vec::with_capacity(packed_capacity),
+            compressor: BlockCompressor::new(),
+            prev: None,
+        }
+    }
+
+    /// Start an empty block, keeping the buffers.
+    fn clear(&mut self) {
+        self.raw.clear();
+        self.packed.clear();
+        self.prev = None;
+    }
+
+    /// Append `frame`; `false` if the block has no room for it.
+    fn push(&mut self, frame: RecordedFrame) -> bool {
+        let len = self.raw.len();
+        let time = frame.time.as_millis() as u64;
+        match self.prev {
+            None => {
+                write_varint(&mut self.raw, time);
+                write_varint(&mut self.raw, frame.tick.read);
+                let positions = frame.tick.positions;
+                self.raw
+                    .extend_from_slice(&u64::from(positions.white).to_le_bytes());
+                self.raw
+                    .extend_from_slice(&u64::from(positions.black).to_le_bytes());
+            }
+            Some(prev) => {
+                let prev_time = prev.time.as_millis() as u64;
+                write_varint(&mut self.raw, time.saturating_sub(prev_time));
+                write_varint(
+                    &mut self.raw,
+                    frame.tick.read.saturating_sub(prev.tick.read),
+                );
+                write_changed_squares(&mut self.raw, prev.tick.positions, frame.tick.positions);
+            }
+        }
+        if self.raw.len() > MAX_RAW_SIZE {
+            self.raw.truncate(len);
+            return false;
+        }
+        self.compressor.compress_into(&self.raw, &mut self.scratch);
+        if self.scratch.len() > PAYLOAD_SIZE {
+            self.raw.truncate(len);
+            return false;
+        }
+        std::mem::swap(&mut self.packed, &mut self.scratch);
+        self.prev = Some(frame);
+        true
+    }
+}
+
+/// Append how many squares changed occupancy, then each as
+/// `square | color << 6`.
+fn write_changed_squares(out: &mut Vec<u8>, prev: ByColor<Bitboard>, next: ByColor<Bitboard>) {
+    let changed = ByColor::new_with(|color| prev[color] ^ next[color]);
+    write_varint(out, (changed.white.count() + changed.black.count()) as u64);
+    for (color, bit) in [(Color::White, 0), (Color::Black, 1 << 6)] {
+        for square in changed[color] {
+            out.push(square as u8 | bit);
+        }
+    }
+}
+
+fn decode_block(mut payload: &[u8], frames: &mut Vec<RecordedFrame>) -> Option<()> {
+    let time = read_varint(&mut payload)?;
+    let read = read_varint(&mut payload)?;
+    let white = Bitboard(u64::from_le_bytes(take(&mut payload, 8)?.try_into().ok()?));
+    let black = Bitboard(u64::from_le_bytes(take(&mut payload, 8)?.try_into().ok()?));
+    let mut frame = RecordedFrame {
+        time: Duration::from_millis(time),
+        tick: Tick {
+            read,
+            positions: ByColor { white, black },
+        },
+    };
+    frames.push(frame);
+    while !payload.is_empty() {
+        frame.time = frame
+            .time
+            .checked_add(Duration::from_millis(read_varint(&mut payload)?))?;
+        frame.tick.read = frame.tick.read.checked_add(read_varint(&mut payload)?)?;
+        let count = usize::try_from(read_varint(&mut payload)?).ok()?;
+        for &change in take(&mut payload, count)? {
+            let square = Square::new(u32::from(change & 0x3f));
+            let color = if change & (1 << 6) == 0 {
+                Color::White
+            } else {
+                Color::Black
+            };
+            frame.tick.positions[color].toggle(square);
+        }
+        frames.push(frame);
+    }
+    Some(())
+}
+
+fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if bytes.len() < n {
+        return None;
+    }
+    let (head, tail) = bytes.split_at(n);
+    *bytes = tail;
+    Some(head)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::{Chess, Position};
+    use std::io::Cursor;
+
+    fn start() -> ByColor<Bitboard> {
+        let board = Chess::default().board().clone();
+        ByColor {
+            white: board.by_color(Color::White),
+            black: board.by_color(Color::Black),
+        }
+    }
+
+    /// The starting position with the e-pawn lifted or back, alternately.
+    fn frames(n: u64) -> Vec<RecordedFrame> {
+        (0..n)
+            .map(|i| {
+                let mut positions = start();
+                if i % 2 == 1 {
+                    positions.white.toggle(Square::E2);
+                }
+                RecordedFrame {
+                    time: Duration::from_millis(i * 50),
+                    tick: Tick { read: i, positions },
+                }
+            })
+            .collect()
+    }
+
+    fn record(recorder: &mut FlightRecorder<Cursor<Vec<u8>>>, frames: &[RecordedFrame]) {
+        for &frame in frames {
+            recorder.record(frame).unwrap();
+        }
+        recorder.flush().unwrap();
+    }
+
+    #[test]
+    fn frames_round_trip_across_blocks() {
+        let frames = frames(400);
+        let mut recorder = FlightRecorder::open(Cursor::new(Vec::new()), 8).unwrap();
+        record(&mut recorder, &frames);
+
+        let bytes = recorder.into_inner().into_inner();
+        assert!(is_recording(&bytes));
+        assert_eq!(bytes.len(), 9 * BLOCK_SIZE);
+        assert_eq!(read_recording(&bytes).unwrap(), frames);
+    }
+
+    #[test]
+    fn recording_does_not_allocate_once_open() {
+        let frames = frames(2000);
+        let mut recorder = FlightRecorder::open(Cursor::new(Vec::new()), 4).unwrap();
+
+        let allocations = crate::testutil::allocations_during(|| record(&mut recorder, &frames));
+
+        assert_eq!(allocations, 0);
+        let recorded = read_recording(&recorder.into_inner().into_inner()).unwrap();
+        assert_eq!(recorded[..], frames[frames.len() - recorded.len()..]);
+    }
+
+    #[test]
+    fn full_ring_keeps_the_newest_frames() {
+        let frames = frames(20_000);
+        let mut recorder = FlightRecorder::open(Cursor::new(Vec::new()), 3).unwrap();
+        record(&mut recorder, &frames);
+
+        let recorded = read_recording(&recorder.into_inner().into_inner()).unwrap();
+
+        assert!(recorded.len() < frames.len());
+        assert_eq!(recorded[..], frames[frames.len() - recorded.len()..]);
+    }
+
+    #[test]
+    fn blocks_are_compressed() {
+        let frames = frames(1000);
+        let mut recorder = FlightRecorder::open(Cursor::new(Vec::new()), 8).unwrap();
+        record(&mut recorder, &frames);
+
+        let bytes = recorder.into_inner().into_inner();
+        let used = (0..8)
+            .filter(|&slot| bytes[slot_offset(slot) as usize..][..4] != [0; 4])
+            .count();
+        // About 4 bytes a frame before compression.
+        assert!(used * PAYLOAD_SIZE < 1000 * 4, "{used} blocks");
+        assert_eq!(read_recording(&bytes).unwrap(), frames);
+    }
+
+    #[test]
+    fn reopening_continues_after_the_newest_block() {
+        let frames = frames(10);
+        let mut recorder = FlightRecorder::open(Cursor::new(Vec::new()), 4).unwrap();
+        record(&mut recorder, &frames[..5]);
+        let mut recorder = FlightRecorder::open(recorder.into_inner(), 4).unwrap();
+        record(&mut recorder, &frames[5..]);
+
+        let recorded = read_recording(&recorder.into_inner().into_inner()).unwrap();
+
+        assert_eq!(recorded, frames);
+    }
+
+    #[test]
+    fn partial_block_waits_for_the_flush_interval() {
+        let frames = frames(3);
+        let mut recorder = FlightRecorder::open(Cursor::new(Vec::new()), 2).unwrap();
+        recorder.record(frames[0]).unwrap();
+        recorder.record(frames[1]).unwrap();
+
+        let written = |recorder: &FlightRecorder<Cursor<Vec<u8>>>| {
+            read_recording(recorder.file.get_ref()).unwrap().len()
+        };
+        assert_eq!(written(&recorder), 0);
+        recorder.poll(FLUSH_INTERVAL).unwrap();
+        assert_eq!(written(&recorder), 2);
+    }
+
+    #[test]
+    fn corrupted_recordings_are_errors_not_panics() {
+        let mut recorder = FlightRecorder::open(Cursor::new(Vec::new()), 2).unwrap();
+        record(&mut recorder, &frames(60));
+        let bytes = recorder.into_inner().into_inner();
+
+        for corrupted in crate::testutil::malformed_inputs(&bytes, 200, 3 * BLOCK_SIZE) {
+            let _ = read_recording(&corrupted);
+            let _ = FlightRecorder::open(Cursor::new(corrupted), 2);
+        }
+    }
+
+    #[test]
+    fn other_files_are_not_recordings() {
+        assert!(matches!(
+            read_recording(b"I (1200) app: hello"),
+            Err(RecorderError::NotARecording)
+        ));
+    }
+}
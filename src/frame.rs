@@ -0,0 +1,246 @@
+//! Platform-independent per-square color frames.
+//!
+//! A [`Frame`] is the color of every square after feedback has been mapped
+//! through a [`LedPalette`]. Hardware displays expand it to physical LEDs;
+//! tests inspect it directly.
+
+use shakmaty::Square;
+
+use crate::feedback::{BoardFeedback, SquareFeedback, StatusKind};
+
+/// Simple RGB color for WS2812 LEDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb8 {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb8 {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Interpolate towards `other` by `t / 255`.
+    pub const fn lerp(self, other: Self, t: u8) -> Self {
+        const fn ch(a: u8, b: u8, t: u8) -> u8 {
+            let (a, b, t) = (a as i32, b as i32, t as i32);
+            (a + (b - a) * t / 255) as u8
+        }
+        Self::new(
+            ch(self.r, other.r, t),
+            ch(self.g, other.g, t),
+            ch(self.b, other.b, t),
+        )
+    }
+
+    /// Scale every channel by `level / 255`.
+    pub const fn scale(self, level: u8) -> Self {
+        const fn ch(c: u8, level: u8) -> u8 {
+            (c as u16 * level as u16 / 255) as u8
+        }
+        Self::new(ch(self.r, level), ch(self.g, level), ch(self.b, level))
+    }
+}
+
+/// LED colors for each feedback type.
+///
+/// All values are RGB8 with conservative brightness to avoid
+/// washing out through the board surface. Feedback types that appear
+/// together must stay distinguishable under common color-vision
+/// deficiencies; see [`crate::color_vision`].
+#[derive(Debug, Clone, Copy)]
+pub struct LedPalette {
+    pub off: Rgb8,
+    pub destination: Rgb8,
+    pub rook_destination: Rgb8,
+    pub capture: Rgb8,
+    pub origin: Rgb8,
+    pub check: Rgb8,
+    pub checker: Rgb8,
+    pub victory: Rgb8,
+    pub stalemate: Rgb8,
+    pub status_pending: Rgb8,
+    pub status_success: Rgb8,
+    pub status_failure: Rgb8,
+}
+
+impl Default for LedPalette {
+    fn default() -> Self {
+        Self {
+            off: Rgb8::new(0, 0, 0),
+            destination: Rgb8::new(0, 20, 0),
+            rook_destination: Rgb8::new(20, 20, 20),
+            capture: Rgb8::new(20, 0, 20),
+            origin: Rgb8::new(0, 0, 20),
+            check: Rgb8::new(20, 0, 0),
+            checker: Rgb8::new(20, 0, 0),
+            victory: Rgb8::new(0, 20, 0),
+            stalemate: Rgb8::new(20, 15, 0),
+            status_pending: Rgb8::new(0, 0, 20),
+            status_success: Rgb8::new(0, 20, 0),
+            status_failure: Rgb8::new(20, 0, 0),
+        }
+    }
+}
+
+impl LedPalette {
+    /// Color for a square feedback variant.
+    pub fn square(&self, feedback: SquareFeedback) -> Rgb8 {
+        match feedback {
+            SquareFeedback::Destination => self.destination,
+            SquareFeedback::RookDestination => self.rook_destination,
+            SquareFeedback::Capture => self.capture,
+            SquareFeedback::Origin => self.origin,
+            SquareFeedback::Check => self.check,
+            SquareFeedback::Checker => self.checker,
+            SquareFeedback::Victory => self.victory,
+            SquareFeedback::Stalemate => self.stalemate,
+        }
+    }
+
+    /// Color for a status indication.
+    pub fn status(&self, status: StatusKind) -> Rgb8 {
+        match status {
+            StatusKind::Pending => self.status_pending,
+            StatusKind::Success => self.status_success,
+            StatusKind::Failure => self.status_failure,
+        }
+    }
+}
+
+/// Squares forming a hollow 4×4 ring in the board center (c3–f6, excluding d4–e5).
+pub const STATUS_RING: [Square; 12] = {
+    use shakmaty::Square::*;
+    [C3, D3, E3, F3, C4, F4, C5, F5, C6, D6, E6, F6]
+};
+
+/// One color per square, indexed by [`Square`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    squares: [Rgb8; 64],
+}
+
+impl Frame {
+    /// A frame with every square set to `color`.
+    pub const fn filled(color: Rgb8) -> Self {
+        Self {
+            squares: [color; 64],
+        }
+    }
+
+    /// Render feedback through a palette.
+    ///
+    /// A status indication replaces square feedback entirely and is drawn
+    /// on [`STATUS_RING`].
+    pub fn render(feedback: &BoardFeedback, palette: &LedPalette) -> Self {
+        let mut frame = Self::filled(palette.off);
+        if let Some(status) = feedback.status() {
+            let color = palette.status(status);
+            for sq in STATUS_RING {
+                frame.set(sq, color);
+            }
+        } else {
+            for (sq, fb) in feedback.squares() {
+                frame.set(sq, palette.square(fb));
+            }
+        }
+        frame
+    }
+
+    /// Color of a square.
+    #[inline]
+    pub fn get(&self, square: Square) -> Rgb8 {
+        self.squares[square as usize]
+    }
+
+    /// Set the color of a square.
+    #[inline]
+    pub fn set(&mut self, square: Square, color: Rgb8) {
+        self.squares[square as usize] = color;
+    }
+
+    /// A copy with every square scaled by `level / 255` (see [`Rgb8::scale`]).
+    pub fn scaled(mut self, level: u8) -> Self {
+        for color in &mut self.squares {
+            *color = color.scale(level);
+        }
+        self
+    }
+
+    /// Per-square interpolation towards `other` by `t / 255`.
+    pub fn blend(&self, other: &Frame, t: u8) -> Self {
+        let mut out = *self;
+        for (color, &target) in out.squares.iter_mut().zip(&other.squares) {
+            *color = color.lerp(target, t);
+        }
+        out
+    }
+
+    /// All squares with their colors, a1 first.
+    pub fn iter(&self) -> impl Iterator<Item = (Square, Rgb8)> + '_ {
+        Square::ALL.into_iter().zip(self.squares.iter().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_full_level_is_identity() {
+        let c = Rgb8::new(10, 20, 255);
+        assert_eq!(c.scale(255), c);
+    }
+
+    #[test]
+    fn scale_zero_level_is_black() {
+        assert_eq!(Rgb8::new(10, 20, 255).scale(0), Rgb8::new(0, 0, 0));
+    }
+
+    #[test]
+    fn lerp_endpoints_and_midpoint() {
+        let a = Rgb8::new(0, 20, 200);
+        let b = Rgb8::new(20, 0, 100);
+        assert_eq!(a.lerp(b, 0), a);
+        assert_eq!(a.lerp(b, 255), b);
+        assert_eq!(a.lerp(b, 128), Rgb8::new(10, 10, 150));
+    }
+
+    #[test]
+    fn scaled_frame_dims_every_square() {
+        let frame = Frame::filled(Rgb8::new(200, 100, 0)).scaled(128);
+        for (_, color) in frame.iter() {
+            assert_eq!(color, Rgb8::new(100, 50, 0));
+        }
+    }
+
+    #[test]
+    fn render_maps_square_feedback_through_palette() {
+        let palette = LedPalette::default();
+        let mut fb = BoardFeedback::new();
+        fb.set(Square::E4, SquareFeedback::Destination);
+        fb.set(Square::E2, SquareFeedback::Origin);
+
+        let frame = Frame::render(&fb, &palette);
+
+        assert_eq!(frame.get(Square::E4), palette.destination);
+        assert_eq!(frame.get(Square::E2), palette.origin);
+        assert_eq!(frame.get(Square::A1), palette.off);
+    }
+
+    #[test]
+    fn render_status_replaces_squares_with_ring() {
+        let palette = LedPalette::default();
+        let mut fb = BoardFeedback::with_status(StatusKind::Failure);
+        fb.set(Square::A1, SquareFeedback::Destination);
+
+        let frame = Frame::render(&fb, &palette);
+
+        assert_eq!(frame.get(Square::A1), palette.off);
+        for sq in STATUS_RING {
+            assert_eq!(frame.get(sq), palette.status_failure);
+        }
+        assert_eq!(frame.get(Square::D4), palette.off);
+    }
+}
@@ -0,0 +1,580 @@
+//! Commands given with the pieces themselves.
+//!
+//! A gesture is declared as a [`GestureDef`]: one or more [`Step`]s, each a
+//! [`Pattern`] of the sensors against the game position that must hold
+//! for a while, optionally within a time limit of the step before. A
+//! [`GestureRecognizer`] tracks its definitions across ticks and reports
+//! the first one held to the end, so a new gesture is a new definition
+//! rather than another state machine.
+//!
+//! The board's own gestures are [`BOARD_GESTURES`]. Laying your king on its
+//! side takes it off its sensor, so a king alone off its square for
+//! [`RESIGN_HOLD`] resigns for its side. Clearing every piece off both back
+//! ranks, with the rest of the board untouched, for [`NEW_GAME_HOLD`] asks
+//! for a new game with the same players.
+//!
+//! Neither pattern can be a move in progress, except the side to move
+//! holding its own king while deciding where to put it: that only counts
+//! once the hold runs out, and until then the game reads it as usual
+//! ([`GestureDef::quiet_on_turn`]). The others are reported as
+//! [`GestureSignal::Holding`], so the game shows the gesture instead of
+//! reporting the board out of sync.
+
+use std::time::Duration;
+
+use shakmaty::{Bitboard, ByColor, Chess, Color, Position, Rank};
+
+use crate::feedback::{BoardFeedback, SquareFeedback};
+
+/// How long a king must stay off the board to resign.
+pub const RESIGN_HOLD: Duration = Duration::from_secs(10);
+
+/// How long both back ranks must stay clear to start a new game.
+pub const NEW_GAME_HOLD: Duration = Duration::from_secs(3);
+
+/// Fewest pieces each back rank must hold for clearing them to count;
+/// with two or more on each, no single move lifts them all.
+const MIN_BACK_RANK_PIECES: usize = 2;
+
+/// A command given with the pieces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    Resign { color: Color },
+    NewGame,
+}
+
+/// The gestures recognized during a game, in priority order.
+pub const BOARD_GESTURES: [GestureDef<Gesture>; 3] = [
+    GestureDef::new(
+        Gesture::Resign {
+            color: Color::White,
+        },
+        &[Step::hold(
+            Pattern::Lifted(SquareSet::King(Color::White)),
+            RESIGN_HOLD,
+        )],
+    )
+    .quiet_on_turn(Color::White),
+    GestureDef::new(
+        Gesture::Resign {
+            color: Color::Black,
+        },
+        &[Step::hold(
+            Pattern::Lifted(SquareSet::King(Color::Black)),
+            RESIGN_HOLD,
+        )],
+    )
+    .quiet_on_turn(Color::Black),
+    GestureDef::new(
+        Gesture::NewGame,
+        &[Step::hold(
+            Pattern::Lifted(SquareSet::BackRanks),
+            NEW_GAME_HOLD,
+        )],
+    ),
+];
+
+/// Pieces of the game position a pattern refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SquareSet {
+    /// The position's pieces on these squares.
+    Squares(Bitboard),
+    /// The king of one side.
+    King(Color),
+    /// Both kings.
+    Kings,
+    /// Every piece on the first and eighth ranks, as long as each holds at
+    /// least two; otherwise none.
+    BackRanks,
+}
+
+impl SquareSet {
+    fn resolve(self, position: &Chess) -> Bitboard {
+        let board = position.board();
+        match self {
+            Self::Squares(squares) => board.occupied() & squares,
+            Self::King(color) => board.kings() & board.by_color(color),
+            Self::Kings => board.kings(),
+            Self::BackRanks => {
+                let first = board.occupied() & Bitboard::from_rank(Rank::First);
+                let eighth = board.occupied() & Bitboard::from_rank(Rank::Eighth);
+                if first.count() >= MIN_BACK_RANK_PIECES && eighth.count() >= MIN_BACK_RANK_PIECES {
+                    first | eighth
+                } else {
+                    Bitboard::EMPTY
+                }
+            }
+        }
+    }
+}
+
+/// What the sensors must show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    /// Exactly the pieces of the set are off the board, the rest stand
+    /// where the position has them, and nothing stands anywhere else. An
+    /// empty set never matches.
+    Lifted(SquareSet),
+    /// The board matches the position.
+    InPlace,
+    /// No piece on the board.
+    Empty,
+    /// A single piece on the board, on one of these squares.
+    LoneOn(Bitboard),
+}
+
+impl Pattern {
+    /// Whether `sensors` show this pattern on `position`.
+    pub fn matches(self, position: &Chess, sensors: ByColor<Bitboard>) -> bool {
+        let board = position.board();
+        let occupied = sensors.white | sensors.black;
+        match self {
+            Self::Lifted(set) => {
+                let extra = (sensors.white & !board.by_color(Color::White))
+                    | (sensors.black & !board.by_color(Color::Black));
+                let lifted = set.resolve(position);
+                extra.is_empty() && lifted.any() && board.occupied() & !occupied == lifted
+            }
+            Self::InPlace => {
+                sensors.white == board.by_color(Color::White)
+                    && sensors.black == board.by_color(Color::Black)
+            }
+            Self::Empty => occupied.is_empty(),
+            Self::LoneOn(squares) => occupied.count() == 1 && (occupied & squares).any(),
+        }
+    }
+}
+
+/// One step of a gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub pattern: Pattern,
+    /// How long the pattern must hold for the step to complete.
+    pub hold: Duration,
+    /// How long after the step before completes this one may take to
+    /// show. While waiting, the previous pattern may stay; anything else
+    /// drops the gesture. Ignored on the first step.
+    pub within: Option<Duration>,
+}
+
+impl Step {
+    pub const fn hold(pattern: Pattern, hold: Duration) -> Self {
+        Self {
+            pattern,
+            hold,
+            within: None,
+        }
+    }
+
+    pub const fn within(mut self, limit: Duration) -> Self {
+        self.within = Some(limit);
+        self
+    }
+}
+
+/// A gesture and the steps that make it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GestureDef<G> {
+    pub gesture: G,
+    pub steps: &'static [Step],
+    /// While it is this side's turn the pattern may be a move under way:
+    /// it is not reported as held, only once recognized.
+    pub quiet_on_turn: Option<Color>,
+}
+
+impl<G> GestureDef<G> {
+    pub const fn new(gesture: G, steps: &'static [Step]) -> Self {
+        Self {
+            gesture,
+            steps,
+            quiet_on_turn: None,
+        }
+    }
+
+    pub const fn quiet_on_turn(mut self, color: Color) -> Self {
+        self.quiet_on_turn = Some(color);
+        self
+    }
+}
+
+/// What the caller should do after feeding a reading to the recognizer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureSignal<G = Gesture> {
+    /// Carry on with the game.
+    Inactive,
+    /// A gesture is under way at `step`, entered `elapsed` ago; show
+    /// [`gesture_feedback`] instead of reading moves.
+    Holding {
+        gesture: G,
+        step: usize,
+        elapsed: Duration,
+    },
+    /// The gesture was held to the end.
+    Recognized(G),
+}
+
+/// How far one definition has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Progress {
+    step: usize,
+    /// When the current step began: its pattern first showing on step 0,
+    /// the step before completing after that.
+    entered: Duration,
+    /// When the current step's pattern last started showing.
+    matched: Option<Duration>,
+    /// Recognized or timed out on this step; wait for its pattern to go
+    /// before starting over.
+    spent: Option<usize>,
+}
+
+impl Progress {
+    fn advance<G: Copy + std::fmt::Debug>(
+        &mut self,
+        def: &GestureDef<G>,
+        shows: impl Fn(Pattern) -> bool,
+        now: Duration,
+    ) -> GestureSignal<G> {
+        if let Some(step) = self.spent {
+            if shows(def.steps[step].pattern) {
+                return GestureSignal::Inactive;
+            }
+            *self = Self::default();
+        }
+        let Some(current) = def.steps.get(self.step) else {
+            return GestureSignal::Inactive;
+        };
+        if shows(current.pattern) {
+            if self.step == 0 && self.matched.is_none() {
+                self.entered = now;
+            }
+            let since = *self.matched.get_or_insert(now);
+            if now.saturating_sub(since) >= current.hold {
+                if self.step + 1 == def.steps.len() {
+                    log::info!("Gesture recognized: {:?}", def.gesture);
+                    self.spent = Some(self.step);
+                    return GestureSignal::Recognized(def.gesture);
+                }
+                self.step += 1;
+                self.entered = now;
+                self.matched = None;
+            }
+            return self.holding(def, now);
+        }
+        self.matched = None;
+        if self.step == 0 {
+            return GestureSignal::Inactive;
+        }
+        let previous = def.steps[self.step - 1];
+        if current
+            .within
+            .is_some_and(|limit| now.saturating_sub(self.entered) >= limit)
+        {
+            log::info!("Gesture timed out: {:?}", def.gesture);
+            self.spent = Some(self.step - 1);
+            return GestureSignal::Inactive;
+        }
+        if shows(previous.pattern) {
+            return self.holding(def, now);
+        }
+        log::info!("Gesture dropped: {:?}", def.gesture);
+        *self = Self::default();
+        self.advance(def, shows, now)
+    }
+
+    fn holding<G: Copy>(&self, def: &GestureDef<G>, now: Duration) -> GestureSignal<G> {
+        GestureSignal::Holding {
+            gesture: def.gesture,
+            step: self.step,
+            elapsed: now.saturating_sub(self.entered),
+        }
+    }
+}
+
+/// Tracks a set of gesture definitions across ticks.
+#[derive(Debug, Clone)]
+pub struct GestureRecognizer<G = Gesture> {
+    defs: Vec<(GestureDef<G>, Progress)>,
+}
+
+impl GestureRecognizer {
+    /// A recognizer for [`BOARD_GESTURES`].
+    pub fn new() -> Self {
+        Self::with_definitions(&BOARD_GESTURES)
+    }
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G: Copy + std::fmt::Debug> GestureRecognizer<G> {
+    /// A recognizer for `defs`; earlier definitions win when several
+    /// complete on the same reading.
+    pub fn with_definitions(defs: &[GestureDef<G>]) -> Self {
+        Self {
+            defs: defs.iter().map(|&def| (def, Progress::default())).collect(),
+        }
+    }
+
+    /// Forget every gesture under way.
+    pub fn reset(&mut self) {
+        for (_, progress) in &mut self.defs {
+            *progress = Progress::default();
+        }
+    }
+
+    /// Advance every definition with the pieces on the board in `position`.
+    pub fn update(
+        &mut self,
+        position: &Chess,
+        sensors: ByColor<Bitboard>,
+        now: Duration,
+    ) -> GestureSignal<G> {
+        let shows = |pattern: Pattern| pattern.matches(position, sensors);
+        let mut signal = GestureSignal::Inactive;
+        for (def, progress) in &mut self.defs {
+            let next = match progress.advance(def, shows, now) {
+                GestureSignal::Holding { .. } if def.quiet_on_turn == Some(position.turn()) => {
+                    GestureSignal::Inactive
+                }
+                next => next,
+            };
+            signal = match (signal, next) {
+                (GestureSignal::Recognized(_), _) => signal,
+                (_, GestureSignal::Recognized(_)) => next,
+                (GestureSignal::Inactive, _) => next,
+                _ => signal,
+            };
+        }
+        signal
+    }
+}
+
+/// Feedback while `gesture` is held on `position`: the lifted king in
+/// check colors, or both back ranks lit for the new setup.
+pub fn gesture_feedback(position: &Chess, gesture: Gesture) -> BoardFeedback {
+    let mut feedback = BoardFeedback::new();
+    match gesture {
+        Gesture::Resign { color } => {
+            if let Some(king) = position.board().king_of(color) {
+                feedback.set(king, SquareFeedback::Check);
+            }
+        }
+        Gesture::NewGame => {
+            let back_ranks = Bitboard::from_rank(Rank::First) | Bitboard::from_rank(Rank::Eighth);
+            for square in back_ranks {
+                feedback.set(square, SquareFeedback::Destination);
+            }
+        }
+    }
+    feedback
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::Square;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn sensors(position: &Chess) -> ByColor<Bitboard> {
+        let board = position.board();
+        ByColor {
+            white: board.by_color(Color::White),
+            black: board.by_color(Color::Black),
+        }
+    }
+
+    fn without(mut sensors: ByColor<Bitboard>, squares: Bitboard) -> ByColor<Bitboard> {
+        sensors.white &= !squares;
+        sensors.black &= !squares;
+        sensors
+    }
+
+    #[test]
+    fn a_king_off_the_board_resigns_after_the_hold() {
+        let position = Chess::default();
+        let mut gestures = GestureRecognizer::new();
+        let lifted = without(sensors(&position), Bitboard::from(Square::E8));
+
+        assert_eq!(
+            gestures.update(&position, lifted, Duration::ZERO),
+            GestureSignal::Holding {
+                gesture: Gesture::Resign {
+                    color: Color::Black
+                },
+                step: 0,
+                elapsed: Duration::ZERO
+            }
+        );
+        assert!(matches!(
+            gestures.update(&position, lifted, RESIGN_HOLD - SECOND),
+            GestureSignal::Holding { .. }
+        ));
+        assert_eq!(
+            gestures.update(&position, lifted, RESIGN_HOLD),
+            GestureSignal::Recognized(Gesture::Resign {
+                color: Color::Black
+            })
+        );
+        assert_eq!(
+            gestures.update(&position, lifted, RESIGN_HOLD + SECOND),
+            GestureSignal::Inactive,
+            "recognized once"
+        );
+    }
+
+    #[test]
+    fn the_side_to_move_may_hold_its_king_until_the_hold_runs_out() {
+        let position = Chess::default();
+        let mut gestures = GestureRecognizer::new();
+        let lifted = without(sensors(&position), Bitboard::from(Square::E1));
+
+        assert_eq!(
+            gestures.update(&position, lifted, Duration::ZERO),
+            GestureSignal::Inactive
+        );
+        assert_eq!(
+            gestures.update(&position, sensors(&position), SECOND),
+            GestureSignal::Inactive
+        );
+        assert_eq!(
+            gestures.update(&position, lifted, 2 * SECOND),
+            GestureSignal::Inactive
+        );
+        assert_eq!(
+            gestures.update(&position, lifted, 2 * SECOND + RESIGN_HOLD),
+            GestureSignal::Recognized(Gesture::Resign {
+                color: Color::White
+            })
+        );
+    }
+
+    #[test]
+    fn clearing_both_back_ranks_starts_a_new_game() {
+        let position = Chess::default();
+        let mut gestures = GestureRecognizer::new();
+        let back_ranks = Bitboard::from_rank(Rank::First) | Bitboard::from_rank(Rank::Eighth);
+        let cleared = without(sensors(&position), back_ranks);
+
+        assert!(matches!(
+            gestures.update(&position, cleared, Duration::ZERO),
+            GestureSignal::Holding {
+                gesture: Gesture::NewGame,
+                ..
+            }
+        ));
+        assert_eq!(
+            gestures.update(&position, cleared, NEW_GAME_HOLD),
+            GestureSignal::Recognized(Gesture::NewGame)
+        );
+
+        let only_first = without(sensors(&position), Bitboard::from_rank(Rank::First));
+        assert_eq!(
+            GestureRecognizer::new().update(&position, only_first, Duration::ZERO),
+            GestureSignal::Inactive
+        );
+        let whole_board = without(sensors(&position), Bitboard::FULL);
+        assert_eq!(
+            GestureRecognizer::new().update(&position, whole_board, Duration::ZERO),
+            GestureSignal::Inactive,
+            "left to the abort gesture"
+        );
+    }
+
+    /// Lift the white king and put it back within a second.
+    const KING_TAP: GestureDef<&str> = GestureDef::new(
+        "tap",
+        &[
+            Step::hold(
+                Pattern::Lifted(SquareSet::King(Color::White)),
+                Duration::ZERO,
+            ),
+            Step::hold(Pattern::InPlace, Duration::ZERO).within(SECOND),
+        ],
+    );
+
+    #[test]
+    fn steps_are_recognized_in_sequence() {
+        let position = Chess::default();
+        let mut gestures = GestureRecognizer::with_definitions(&[KING_TAP]);
+        let lifted = without(sensors(&position), Bitboard::from(Square::E1));
+        let ms = Duration::from_millis;
+
+        assert_eq!(
+            gestures.update(&position, sensors(&position), ms(0)),
+            GestureSignal::Inactive
+        );
+        assert_eq!(
+            gestures.update(&position, lifted, ms(100)),
+            GestureSignal::Holding {
+                gesture: "tap",
+                step: 1,
+                elapsed: ms(0)
+            }
+        );
+        assert_eq!(
+            gestures.update(&position, lifted, ms(400)),
+            GestureSignal::Holding {
+                gesture: "tap",
+                step: 1,
+                elapsed: ms(300)
+            },
+            "the first step may stay while waiting for the second"
+        );
+        assert_eq!(
+            gestures.update(&position, sensors(&position), ms(600)),
+            GestureSignal::Recognized("tap")
+        );
+        assert_eq!(
+            gestures.update(&position, sensors(&position), ms(700)),
+            GestureSignal::Inactive
+        );
+    }
+
+    #[test]
+    fn a_step_not_shown_in_time_waits_for_the_one_before_to_go() {
+        let position = Chess::default();
+        let mut gestures = GestureRecognizer::with_definitions(&[KING_TAP]);
+        let lifted = without(sensors(&position), Bitboard::from(Square::E1));
+
+        gestures.update(&position, lifted, Duration::ZERO);
+        assert_eq!(
+            gestures.update(&position, lifted, SECOND),
+            GestureSignal::Inactive,
+            "timed out"
+        );
+        assert_eq!(
+            gestures.update(&position, sensors(&position), 2 * SECOND),
+            GestureSignal::Inactive,
+            "putting the king back late does not count"
+        );
+        assert!(matches!(
+            gestures.update(&position, lifted, 3 * SECOND),
+            GestureSignal::Holding { step: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn anything_else_between_steps_drops_the_gesture() {
+        let position = Chess::default();
+        let mut gestures = GestureRecognizer::with_definitions(&[KING_TAP]);
+        let lifted = without(sensors(&position), Bitboard::from(Square::E1));
+        let elsewhere = ByColor {
+            white: lifted.white | Bitboard::from(Square::E3),
+            ..lifted
+        };
+
+        gestures.update(&position, lifted, Duration::ZERO);
+        assert_eq!(
+            gestures.update(&position, elsewhere, SECOND / 4),
+            GestureSignal::Inactive
+        );
+        assert_eq!(
+            gestures.update(&position, sensors(&position), SECOND / 2),
+            GestureSignal::Inactive
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Known board hardware revisions and their sensor presets.
+//!
+//! Calibration measures each board, but a fresh board has to detect pieces
+//! before anyone runs it. The signal a piece produces depends on the Hall
+//! sensor's sensitivity, the magnets in the pieces and how thick the board
+//! top is, so each revision ships its own starting thresholds. The firmware
+//! picks one by name from `esp32::config::HARDWARE_REVISION`; a saved
+//! calibration still replaces its baseline and threshold.
+//!
+//! Only [`HardwareRevision::Rev1`] was measured on a built board. The others
+//! are scaled from it by sensor sensitivity and field strength at the
+//! surface, and are meant to be recalibrated.
+
+/// Sensor thresholds and timing for one hardware revision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensorPreset {
+    /// Sensor output (mV) with no magnet nearby.
+    pub baseline_mv: u16,
+    /// Least deviation from the baseline (mV) that counts as a piece.
+    pub threshold_mv: u16,
+    /// Delay (ms) after switching mux address lines before reading.
+    pub settle_delay_ms: u32,
+    /// ADC samples averaged into each reading.
+    pub samples: u8,
+}
+
+/// A board hardware revision with known sensor characteristics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HardwareRevision {
+    /// DRV5055A3 sensors, 10×3 mm N52 magnets, 3 mm top.
+    #[default]
+    Rev1,
+    /// Rev1 with a 6 mm wooden top: roughly half the field at the sensor.
+    Rev1Thick,
+    /// DRV5055A2 sensors (twice as sensitive), 8×2 mm N35 magnets, 3 mm top.
+    Rev2,
+}
+
+impl HardwareRevision {
+    pub const ALL: [Self; 3] = [Self::Rev1, Self::Rev1Thick, Self::Rev2];
+
+    /// Name used to select the revision in the build configuration.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rev1 => "rev1",
+            Self::Rev1Thick => "rev1-thick",
+            Self::Rev2 => "rev2",
+        }
+    }
+
+    /// The revision called `name`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|revision| revision.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Starting thresholds before the board is calibrated.
+    pub fn preset(self) -> SensorPreset {
+        match self {
+            Self::Rev1 => SensorPreset {
+                baseline_mv: 1440,
+                threshold_mv: 100,
+                settle_delay_ms: 2,
+                samples: 4,
+            },
+            // Half the signal over the same noise: average more.
+            Self::Rev1Thick => SensorPreset {
+                baseline_mv: 1440,
+                threshold_mv: 50,
+                settle_delay_ms: 2,
+                samples: 8,
+            },
+            // Weaker magnets, but a more sensitive sensor with more noise
+            // and a slower output stage.
+            Self::Rev2 => SensorPreset {
+                baseline_mv: 1440,
+                threshold_mv: 120,
+                settle_delay_ms: 3,
+                samples: 8,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revisions_are_selected_by_name() {
+        for revision in HardwareRevision::ALL {
+            assert_eq!(HardwareRevision::from_name(revision.name()), Some(revision));
+        }
+        assert_eq!(
+            HardwareRevision::from_name("REV1-Thick"),
+            Some(HardwareRevision::Rev1Thick)
+        );
+        assert_eq!(HardwareRevision::from_name("rev9"), None);
+    }
+
+    #[test]
+    fn thicker_top_lowers_the_threshold() {
+        assert!(
+            HardwareRevision::Rev1Thick.preset().threshold_mv
+                < HardwareRevision::Rev1.preset().threshold_mv
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Where a finished game happened, square by square.
+//!
+//! [`HeatMap`] counts how often pieces left, crossed and reached each
+//! square over a game's moves. [`HeatMapMode`] plays the game back as a
+//! growing heat map on the LEDs, one move per [`HEATMAP_STEP`], then holds
+//! the finished map; the terminal prints the same counts as digits.
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use shakmaty::{Bitboard, ByColor, CastlingSide, Move, Square, attacks};
+
+use crate::feedback::{BoardFeedback, SquareFeedback};
+use crate::mode::{GameMode, ModeStatus};
+
+/// How long each move adds its heat before the next one.
+pub const HEATMAP_STEP: Duration = Duration::from_millis(300);
+
+/// How long the finished map stays up before the mode ends.
+pub const HEATMAP_HOLD: Duration = Duration::from_secs(10);
+
+/// LED colors from cold to hot; the hottest squares are lit with the last.
+const HEAT_LEVELS: [SquareFeedback; 4] = [
+    SquareFeedback::Origin,
+    SquareFeedback::Destination,
+    SquareFeedback::Stalemate,
+    SquareFeedback::Capture,
+];
+
+/// Per-square count of pieces leaving, passing through and arriving.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeatMap {
+    counts: [u32; 64],
+}
+
+impl Default for HeatMap {
+    fn default() -> Self {
+        Self { counts: [0; 64] }
+    }
+}
+
+impl HeatMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The heat of every move in `moves`.
+    pub fn from_moves(moves: &[Move]) -> Self {
+        let mut map = Self::new();
+        for mv in moves {
+            map.add(mv);
+        }
+        map
+    }
+
+    /// Heat up the squares `mv`'s pieces travel, ends included. Castling
+    /// counts the king's and the rook's paths.
+    pub fn add(&mut self, mv: &Move) {
+        let paths = match *mv {
+            Move::Castle { king, rook } => {
+                let side = CastlingSide::from_king_side(king < rook);
+                let king_to = Square::from_coords(side.king_to_file(), king.rank());
+                let rook_to = Square::from_coords(side.rook_to_file(), rook.rank());
+                [travelled(king, king_to), travelled(rook, rook_to)]
+            }
+            Move::Put { to, .. } => [Bitboard::from(to), Bitboard::EMPTY],
+            _ => {
+                let path = mv
+                    .from()
+                    .map_or(Bitboard::EMPTY, |from| travelled(from, mv.to()));
+                [path, Bitboard::EMPTY]
+            }
+        };
+        for square in paths.into_iter().flatten() {
+            self.counts[square as usize] += 1;
+        }
+    }
+
+    pub fn count(&self, square: Square) -> u32 {
+        self.counts[square as usize]
+    }
+
+    /// The count of the hottest square.
+    pub fn max(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// `square`'s count scaled to `1..=levels` against the hottest square,
+    /// or 0 if no piece touched it.
+    pub fn level(&self, square: Square, levels: u32) -> u32 {
+        let count = self.count(square);
+        if count == 0 {
+            return 0;
+        }
+        (count * levels).div_ceil(self.max())
+    }
+
+    /// Every touched square lit in [`HEAT_LEVELS`] by its level.
+    pub fn feedback(&self) -> BoardFeedback {
+        let mut feedback = BoardFeedback::new();
+        for square in Square::ALL {
+            let level = self.level(square, HEAT_LEVELS.len() as u32) as usize;
+            if let Some(&color) = level.checked_sub(1).and_then(|i| HEAT_LEVELS.get(i)) {
+                feedback.set(square, color);
+            }
+        }
+        feedback
+    }
+
+    /// The board from White's side with each square's level from 1 to 9,
+    /// `.` for untouched squares.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for rank in (0..8).rev() {
+            let _ = write!(out, "{} ", rank + 1);
+            for file in 0..8 {
+                let level = self.level(Square::new(rank * 8 + file), 9);
+                let c = char::from_digit(level, 10)
+                    .filter(|_| level > 0)
+                    .unwrap_or('.');
+                let _ = write!(out, " {c}");
+            }
+            out.push('\n');
+        }
+        out.push_str("   a b c d e f g h");
+        out
+    }
+}
+
+/// `from`, `to` and the squares between them on a line; knights jump.
+fn travelled(from: Square, to: Square) -> Bitboard {
+    attacks::between(from, to) | from | to
+}
+
+/// Plays a finished game back as a heat map that builds up move by move.
+///
+/// The board's pieces are not read, so it can be cleared or left as the
+/// game ended.
+#[derive(Debug, Clone)]
+pub struct HeatMapMode {
+    moves: Vec<Move>,
+    map: HeatMap,
+    /// Moves added to the map so far.
+    shown: usize,
+    started_at: Option<Duration>,
+}
+
+impl HeatMapMode {
+    pub fn new(moves: Vec<Move>) -> Self {
+        Self {
+            moves,
+            map: HeatMap::new(),
+            shown: 0,
+            started_at: None,
+        }
+    }
+
+    pub fn map(&self) -> &HeatMap {
+        &self.map
+    }
+}
+
+impl GameMode for HeatMapMode {
+    fn name(&self) -> &'static str {
+        "heat map"
+    }
+
+    fn tick(&mut self, _positions: ByColor<Bitboard>, now: Duration) -> ModeStatus {
+        let elapsed = now.saturating_sub(*self.started_at.get_or_insert(now));
+        let due =
+            ((elapsed.as_millis() / HEATMAP_STEP.as_millis()) as usize + 1).min(self.moves.len());
+        for mv in &self.moves[self.shown..due.max(self.shown)] {
+            self.map.add(mv);
+        }
+        self.shown = due.max(self.shown);
+        let animated = HEATMAP_STEP * self.moves.len() as u32;
+        if elapsed >= animated + HEATMAP_HOLD {
+            ModeStatus::Finished
+        } else {
+            ModeStatus::Running
+        }
+    }
+
+    fn feedback(&self) -> BoardFeedback {
+        self.map.feedback()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::uci::UciMove;
+    use shakmaty::{Chess, Position};
+
+    fn moves(ucis: &[&str]) -> Vec<Move> {
+        let mut pos = Chess::default();
+        ucis.iter()
+            .map(|uci| {
+                let mv = uci.parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+                pos.play_unchecked(mv);
+                mv
+            })
+            .collect()
+    }
+
+    #[test]
+    fn paths_heat_every_square_crossed() {
+        let map = HeatMap::from_moves(&moves(&["e2e4", "g8f6", "f1c4", "f6e4"]));
+
+        assert_eq!(map.count(Square::E4), 2);
+        assert_eq!(map.count(Square::E3), 1, "double step passes e3");
+        assert_eq!(map.count(Square::D3), 1, "bishop path");
+        assert_eq!(map.count(Square::G7), 0, "knights jump");
+        assert_eq!(map.max(), 2);
+        assert_eq!(map.level(Square::E4, 4), 4);
+        assert_eq!(map.level(Square::D3, 4), 2);
+        assert_eq!(map.level(Square::A1, 4), 0);
+    }
+
+    #[test]
+    fn castling_heats_king_and_rook_paths() {
+        let map = HeatMap::from_moves(&moves(&[
+            "e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1",
+        ]));
+
+        for square in [Square::E1, Square::F1, Square::G1, Square::H1] {
+            assert!(map.count(square) > 0, "{square}");
+        }
+        assert_eq!(map.count(Square::F1), 3, "bishop, king and rook");
+    }
+
+    #[test]
+    fn hottest_squares_use_the_last_level_and_render_as_nine() {
+        let map = HeatMap::from_moves(&moves(&["g1f3", "g8f6", "f3g1", "f6g8", "g1f3"]));
+
+        let feedback = map.feedback();
+        assert_eq!(feedback.get(Square::F3), Some(SquareFeedback::Capture));
+        assert_eq!(feedback.get(Square::F6), Some(SquareFeedback::Stalemate));
+        assert_eq!(feedback.get(Square::E4), None);
+        let text = map.render();
+        assert!(text.contains("\n3  . . . . . 9 . .\n"), "{text}");
+    }
+
+    #[test]
+    fn mode_adds_one_move_per_step_then_holds() {
+        let mut mode = HeatMapMode::new(moves(&["e2e4", "e7e5"]));
+        let idle = ByColor::default();
+
+        assert_eq!(mode.tick(idle, Duration::from_secs(1)), ModeStatus::Running);
+        assert_eq!(mode.map().count(Square::E5), 0);
+        assert!(mode.feedback().get(Square::E4).is_some());
+
+        mode.tick(idle, Duration::from_secs(1) + HEATMAP_STEP);
+        assert_eq!(mode.map().count(Square::E5), 1);
+        mode.tick(idle, Duration::from_secs(1) + HEATMAP_STEP * 5);
+        assert_eq!(mode.map(), &HeatMap::from_moves(&moves(&["e2e4", "e7e5"])));
+
+        let end = Duration::from_secs(1) + HEATMAP_STEP * 2 + HEATMAP_HOLD;
+        assert_eq!(mode.tick(idle, end), ModeStatus::Finished);
+    }
+}
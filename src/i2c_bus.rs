@@ -0,0 +1,373 @@
+//! Sharing one I2C bus between drivers.
+//!
+//! The OLED, an external clock and GPIO expanders sit on the same two
+//! wires. Each driver registers its device with the [`I2cBusManager`] and
+//! talks through the [`I2cDevice`] handle it gets back: a transaction holds
+//! the bus lock from start to finish, so drivers on other threads cannot
+//! interleave with a register read-modify-write.
+//!
+//! Failures are counted per device. A device reset mid-transfer can hold
+//! SDA low and take every other device down with it, so after
+//! [`RECOVER_AFTER`] failed transactions in a row the manager asks the bus
+//! to [`I2cBus::recover`].
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Consecutive failed transactions, on any device, before the bus is
+/// recovered.
+pub const RECOVER_AFTER: u32 = 3;
+
+/// Raw access to an I2C controller, for [`I2cBusManager`].
+pub trait I2cBus {
+    type Error: std::fmt::Debug + std::fmt::Display;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Write `bytes`, then read into `buffer` after a repeated start.
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error>;
+
+    /// Free a bus a device is holding low: clock SCL until SDA is released,
+    /// then send a STOP and reset the controller.
+    fn recover(&mut self) -> Result<(), Self::Error>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum I2cError<E> {
+    #[error("I2C address {0:#04x} is not a 7-bit address")]
+    InvalidAddress(u8),
+    #[error("I2C address {address:#04x} already belongs to {owner}")]
+    AddressInUse { address: u8, owner: &'static str },
+    #[error("I2C {device}: {source}")]
+    Bus { device: &'static str, source: E },
+}
+
+/// Transaction counts for one device.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeviceStats {
+    pub transactions: u32,
+    pub errors: u32,
+}
+
+#[derive(Debug)]
+struct Device {
+    name: &'static str,
+    address: u8,
+    stats: DeviceStats,
+}
+
+#[derive(Debug)]
+struct Shared<B> {
+    bus: B,
+    devices: Vec<Device>,
+    failures_in_a_row: u32,
+    recoveries: u32,
+}
+
+/// Owns the bus and hands out [`I2cDevice`]s; clones share the same bus.
+#[derive(Debug)]
+pub struct I2cBusManager<B> {
+    shared: Arc<Mutex<Shared<B>>>,
+}
+
+impl<B> Clone for I2cBusManager<B> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<B: I2cBus> I2cBusManager<B> {
+    pub fn new(bus: B) -> Self {
+        Self {
+            shared: Arc::new(Mutex::new(Shared {
+                bus,
+                devices: Vec::new(),
+                failures_in_a_row: 0,
+                recoveries: 0,
+            })),
+        }
+    }
+
+    /// Claim `address` for the driver called `name`.
+    pub fn register(
+        &self,
+        name: &'static str,
+        address: u8,
+    ) -> Result<I2cDevice<B>, I2cError<B::Error>> {
+        if address > 0x7f {
+            return Err(I2cError::InvalidAddress(address));
+        }
+        let mut shared = lock(&self.shared);
+        if let Some(owner) = shared.devices.iter().find(|d| d.address == address) {
+            return Err(I2cError::AddressInUse {
+                address,
+                owner: owner.name,
+            });
+        }
+        shared.devices.push(Device {
+            name,
+            address,
+            stats: DeviceStats::default(),
+        });
+        log::info!("I2C: {name} at {address:#04x}");
+        Ok(I2cDevice {
+            shared: Arc::clone(&self.shared),
+            index: shared.devices.len() - 1,
+            name,
+            address,
+        })
+    }
+
+    /// Name, address and counts of every registered device.
+    pub fn devices(&self) -> Vec<(&'static str, u8, DeviceStats)> {
+        lock(&self.shared)
+            .devices
+            .iter()
+            .map(|d| (d.name, d.address, d.stats))
+            .collect()
+    }
+
+    /// How many times the bus has been recovered.
+    pub fn recoveries(&self) -> u32 {
+        lock(&self.shared).recoveries
+    }
+}
+
+/// One device's handle on the shared bus.
+#[derive(Debug)]
+pub struct I2cDevice<B> {
+    shared: Arc<Mutex<Shared<B>>>,
+    index: usize,
+    name: &'static str,
+    address: u8,
+}
+
+impl<B: I2cBus> I2cDevice<B> {
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    pub fn write(&self, bytes: &[u8]) -> Result<(), I2cError<B::Error>> {
+        self.transaction(|t| t.write(bytes))
+    }
+
+    pub fn write_read(&self, bytes: &[u8], buffer: &mut [u8]) -> Result<(), I2cError<B::Error>> {
+        self.transaction(|t| t.write_read(bytes, buffer))
+    }
+
+    /// Run `f` with the bus held, so its operations are not interleaved
+    /// with other devices'. The first error ends the transaction and counts
+    /// as one failure.
+    pub fn transaction<T>(
+        &self,
+        f: impl FnOnce(&mut Transaction<'_, B>) -> Result<T, B::Error>,
+    ) -> Result<T, I2cError<B::Error>> {
+        let mut guard = lock(&self.shared);
+        let shared = &mut *guard;
+        let result = f(&mut Transaction {
+            bus: &mut shared.bus,
+            address: self.address,
+        });
+        shared.devices[self.index].stats.transactions += 1;
+        let source = match result {
+            Ok(value) => {
+                shared.failures_in_a_row = 0;
+                return Ok(value);
+            }
+            Err(source) => source,
+        };
+
+        shared.devices[self.index].stats.errors += 1;
+        shared.failures_in_a_row += 1;
+        log::warn!("I2C {} failed: {source}", self.name);
+        if shared.failures_in_a_row >= RECOVER_AFTER {
+            shared.failures_in_a_row = 0;
+            shared.recoveries += 1;
+            log::warn!("I2C bus recovery after {RECOVER_AFTER} failures");
+            if let Err(e) = shared.bus.recover() {
+                log::error!("I2C bus recovery failed: {e}");
+            }
+        }
+        Err(I2cError::Bus {
+            device: self.name,
+            source,
+        })
+    }
+}
+
+/// Operations on one device while the bus is held.
+pub struct Transaction<'a, B> {
+    bus: &'a mut B,
+    address: u8,
+}
+
+impl<B: I2cBus> Transaction<'_, B> {
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), B::Error> {
+        self.bus.write(self.address, bytes)
+    }
+
+    pub fn write_read(&mut self, bytes: &[u8], buffer: &mut [u8]) -> Result<(), B::Error> {
+        self.bus.write_read(self.address, bytes, buffer)
+    }
+}
+
+/// The bus state stays consistent if a driver panicked mid-transaction:
+/// the next transaction starts fresh, and a stuck bus is recovered.
+fn lock<B>(shared: &Mutex<Shared<B>>) -> std::sync::MutexGuard<'_, Shared<B>> {
+    shared.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A bus with a register file per address; addresses in `stuck` fail
+    /// until the bus is recovered.
+    #[derive(Debug, Default)]
+    struct FakeBus {
+        registers: std::collections::HashMap<u8, [u8; 4]>,
+        stuck: Vec<u8>,
+        recoveries: u32,
+    }
+
+    impl I2cBus for FakeBus {
+        type Error = String;
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), String> {
+            if self.stuck.contains(&address) {
+                return Err("timeout".to_string());
+            }
+            if let [register, values @ ..] = bytes {
+                let file = self.registers.entry(address).or_default();
+                for (i, &value) in values.iter().enumerate() {
+                    file[*register as usize + i] = value;
+                }
+            }
+            Ok(())
+        }
+
+        fn write_read(
+            &mut self,
+            address: u8,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), String> {
+            self.write(address, bytes)?;
+            let file = self.registers.entry(address).or_default();
+            let start = bytes.first().copied().unwrap_or(0) as usize;
+            buffer.copy_from_slice(&file[start..start + buffer.len()]);
+            Ok(())
+        }
+
+        fn recover(&mut self) -> Result<(), String> {
+            self.recoveries += 1;
+            self.stuck.clear();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn devices_share_the_bus_by_address() {
+        let manager = I2cBusManager::new(FakeBus::default());
+        let oled = manager.register("oled", 0x3c).unwrap();
+        let expander = manager.register("expander", 0x20).unwrap();
+
+        oled.write(&[0, 0xaf]).unwrap();
+        let value = expander
+            .transaction(|t| {
+                let mut value = [0];
+                t.write_read(&[1], &mut value)?;
+                t.write(&[1, value[0] | 0x04])?;
+                Ok(value[0] | 0x04)
+            })
+            .unwrap();
+        let mut read = [0];
+        expander.write_read(&[1], &mut read).unwrap();
+
+        assert_eq!((value, read), (0x04, [0x04]));
+        assert_eq!(
+            manager.devices(),
+            [
+                (
+                    "oled",
+                    0x3c,
+                    DeviceStats {
+                        transactions: 1,
+                        errors: 0
+                    }
+                ),
+                (
+                    "expander",
+                    0x20,
+                    DeviceStats {
+                        transactions: 2,
+                        errors: 0
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn addresses_are_claimed_once() {
+        let manager = I2cBusManager::new(FakeBus::default());
+        manager.register("oled", 0x3c).unwrap();
+
+        assert_eq!(
+            manager.register("display", 0x3c).err(),
+            Some(I2cError::AddressInUse {
+                address: 0x3c,
+                owner: "oled"
+            })
+        );
+        assert_eq!(
+            manager.register("clock", 0xd0).err(),
+            Some(I2cError::InvalidAddress(0xd0))
+        );
+    }
+
+    #[test]
+    fn repeated_failures_recover_the_bus() {
+        let manager = I2cBusManager::new(FakeBus::default());
+        let clock = manager.register("clock", 0x68).unwrap();
+        let oled = manager.register("oled", 0x3c).unwrap();
+        lock(&manager.shared).bus.stuck = vec![0x68, 0x3c];
+
+        for _ in 0..RECOVER_AFTER - 1 {
+            assert!(clock.write(&[0]).is_err());
+        }
+        assert_eq!(manager.recoveries(), 0);
+        assert_eq!(
+            oled.write(&[0]),
+            Err(I2cError::Bus {
+                device: "oled",
+                source: "timeout".to_string()
+            })
+        );
+        assert_eq!(manager.recoveries(), 1);
+        assert_eq!(lock(&manager.shared).bus.recoveries, 1);
+
+        clock.write(&[0]).unwrap();
+        let stats = manager.devices();
+        assert_eq!(
+            stats[0].2,
+            DeviceStats {
+                transactions: 3,
+                errors: 2
+            }
+        );
+        assert_eq!(
+            stats[1].2,
+            DeviceStats {
+                transactions: 1,
+                errors: 1
+            }
+        );
+    }
+}
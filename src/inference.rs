@@ -0,0 +1,337 @@
+//! Move inference for partially observable boards.
+//!
+//! With dead sensors (see [`crate::session::GameSession::set_dead_squares`])
+//! a reading can fit more than one position: two rooks may both have
+//! reached the same square from squares the board cannot see. Instead of
+//! requiring every reading to match exactly one move, [`Inference`] keeps
+//! the few lines of play consistent with what has been observed since the
+//! last certain position and commits moves once the evidence agrees on them.
+
+use shakmaty::{Bitboard, Board, ByColor, Chess, Color, Move, Position, Role};
+
+use crate::bounded::BoundedVec;
+
+/// Upper bound on tracked lines; readings that fit more are not followed.
+pub const MAX_CANDIDATES: usize = 8;
+
+/// Most uncommitted moves a line can hold; larger `max_plies` are clamped.
+pub const MAX_PENDING_PLIES: usize = 8;
+
+/// Moves inferred since the last certain position.
+pub type PendingMoves = BoundedVec<Move, MAX_PENDING_PLIES>;
+
+type Lines = BoundedVec<Line, MAX_CANDIDATES>;
+
+/// Result of feeding one reading to [`Inference::observe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inferred {
+    /// The reading fits the candidates without any new move.
+    Unchanged,
+    /// Every candidate agrees on these moves; they are now certain.
+    Resolved(PendingMoves),
+    /// Several candidates remain and the reading does not decide between them.
+    Ambiguous(usize),
+    /// No candidate explains the reading (a move in progress, or noise).
+    /// The candidates are kept.
+    Inconsistent,
+}
+
+/// One line of play from the last certain position.
+#[derive(Debug, Clone)]
+struct Line {
+    position: Chess,
+    moves: PendingMoves,
+}
+
+/// Tracks the positions consistent with recent readings.
+#[derive(Debug, Clone)]
+pub struct Inference {
+    candidates: Lines,
+    max_plies: usize,
+}
+
+impl Inference {
+    /// Start from a certain position, following lines of up to
+    /// `max_plies` uncommitted moves.
+    pub fn new(position: Chess, max_plies: usize) -> Self {
+        let mut candidates = Lines::new();
+        let _ = candidates.push(Line {
+            position,
+            moves: PendingMoves::new(),
+        });
+        Self {
+            candidates,
+            max_plies: max_plies.clamp(1, MAX_PENDING_PLIES),
+        }
+    }
+
+    /// Forget all candidates and continue from a certain position, e.g.
+    /// after a move arrived from outside the board.
+    pub fn reset(&mut self, position: Chess) {
+        *self = Self::new(position, self.max_plies);
+    }
+
+    /// Positions still considered possible.
+    pub fn candidates(&self) -> impl Iterator<Item = &Chess> {
+        self.candidates.iter().map(|line| &line.position)
+    }
+
+    /// Squares whose contents differ between candidates.
+    pub fn uncertain(&self) -> Bitboard {
+        let Some(first) = self.candidates.first() else {
+            return Bitboard::EMPTY;
+        };
+        let reference = first.position.board();
+        self.candidates[1..]
+            .iter()
+            .map(|line| {
+                let board = line.position.board();
+                (board.by_color(Color::White) ^ reference.by_color(Color::White))
+                    | (board.by_color(Color::Black) ^ reference.by_color(Color::Black))
+            })
+            .fold(Bitboard::EMPTY, |acc, diff| acc | diff)
+    }
+
+    /// Narrow the candidates with a reading. Squares in `unknown` carry no
+    /// information.
+    ///
+    /// A candidate survives if it matches the reading, or if one of its
+    /// legal moves does and the moved piece is seen landing on a known
+    /// square. Moves that only remove pieces from view look the same as a
+    /// piece being lifted, so they are never inferred.
+    pub fn observe(&mut self, reading: ByColor<Bitboard>, unknown: Bitboard) -> Inferred {
+        let mut next = Lines::new();
+        let mut moved = false;
+        for line in &self.candidates {
+            if matches(line.position.board(), reading, unknown) {
+                if !push_unique(&mut next, line.clone()) {
+                    return crowded();
+                }
+                continue;
+            }
+            if line.moves.len() >= self.max_plies {
+                continue;
+            }
+            let mover = line.position.turn();
+            for mv in line.position.legal_moves() {
+                // Only queen promotions: no piece-selection on the board.
+                if mv.promotion().is_some_and(|role| role != Role::Queen) {
+                    continue;
+                }
+                let mut after = line.position.clone();
+                after.play_unchecked(mv);
+                let landed = after.board().by_color(mover)
+                    & !line.position.board().by_color(mover)
+                    & !unknown;
+                if landed.any() && matches(after.board(), reading, unknown) {
+                    let mut moves = line.moves.clone();
+                    // Fits: `max_plies` never exceeds the capacity.
+                    let _ = moves.push(mv);
+                    let line = Line {
+                        position: after,
+                        moves,
+                    };
+                    if !push_unique(&mut next, line) {
+                        return crowded();
+                    }
+                    moved = true;
+                }
+            }
+        }
+
+        if next.is_empty() {
+            return Inferred::Inconsistent;
+        }
+        self.candidates = next;
+
+        let settled = self.take_common_prefix();
+        if !settled.is_empty() {
+            Inferred::Resolved(settled)
+        } else if self.candidates.len() > 1 {
+            if moved {
+                log::info!("Move ambiguous: {} candidates", self.candidates.len());
+            }
+            Inferred::Ambiguous(self.candidates.len())
+        } else {
+            Inferred::Unchanged
+        }
+    }
+
+    /// Remove and return the moves every candidate starts with.
+    fn take_common_prefix(&mut self) -> PendingMoves {
+        let first = &self.candidates[0].moves;
+        let len = self.candidates[1..].iter().fold(first.len(), |len, line| {
+            first
+                .iter()
+                .zip(&line.moves)
+                .take(len)
+                .take_while(|(a, b)| a == b)
+                .count()
+        });
+        let mut prefix = PendingMoves::new();
+        for &mv in &first[..len] {
+            let _ = prefix.push(mv);
+        }
+        for line in self.candidates.iter_mut() {
+            line.moves.remove_prefix(len);
+        }
+        prefix
+    }
+}
+
+fn matches(board: &Board, reading: ByColor<Bitboard>, unknown: Bitboard) -> bool {
+    ((board.by_color(Color::White) ^ reading.white) & !unknown).is_empty()
+        && ((board.by_color(Color::Black) ^ reading.black) & !unknown).is_empty()
+}
+
+/// Add `line` unless a candidate already reaches the same position.
+/// Returns false when there is no room for it.
+fn push_unique(lines: &mut Lines, line: Line) -> bool {
+    let duplicate = lines.iter().any(|other| {
+        other.position.board() == line.position.board()
+            && other.position.turn() == line.position.turn()
+    });
+    duplicate || lines.push(line).is_ok()
+}
+
+fn crowded() -> Inferred {
+    log::warn!("Reading fits more than {MAX_CANDIDATES} positions, ignoring it");
+    Inferred::Inconsistent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::fen::Fen;
+    use shakmaty::uci::UciMove;
+    use shakmaty::{CastlingMode, Square};
+
+    fn position(fen: &str) -> Chess {
+        fen.parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap()
+    }
+
+    fn play(position: &Chess, ucis: &[&str]) -> Chess {
+        let mut pos = position.clone();
+        for uci in ucis {
+            let mv = uci.parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+            pos.play_unchecked(mv);
+        }
+        pos
+    }
+
+    fn reading(position: &Chess) -> ByColor<Bitboard> {
+        let board = position.board();
+        ByColor {
+            white: board.by_color(Color::White),
+            black: board.by_color(Color::Black),
+        }
+    }
+
+    fn ucis(moves: &[Move]) -> Vec<String> {
+        moves
+            .iter()
+            .map(|mv| mv.to_uci(CastlingMode::Standard).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn fully_observed_move_resolves_at_once() {
+        let start = Chess::default();
+        let mut inference = Inference::new(start.clone(), 4);
+
+        let result = inference.observe(reading(&play(&start, &["e2e4"])), Bitboard::EMPTY);
+
+        let Inferred::Resolved(moves) = result else {
+            panic!("expected resolution, got {result:?}");
+        };
+        assert_eq!(ucis(&moves), ["e2e4"]);
+    }
+
+    #[test]
+    fn unchanged_reading_keeps_the_position() {
+        let start = Chess::default();
+        let mut inference = Inference::new(start.clone(), 4);
+
+        assert_eq!(
+            inference.observe(reading(&start), Bitboard::EMPTY),
+            Inferred::Unchanged
+        );
+    }
+
+    #[test]
+    fn lifted_piece_is_inconsistent_and_keeps_candidates() {
+        let start = Chess::default();
+        let mut inference = Inference::new(start.clone(), 4);
+        let mut lifted = reading(&start);
+        lifted.white.discard(Square::E2);
+
+        assert_eq!(
+            inference.observe(lifted, Bitboard::EMPTY),
+            Inferred::Inconsistent
+        );
+        assert_eq!(inference.candidates().count(), 1);
+    }
+
+    #[test]
+    fn move_onto_unknown_square_is_not_inferred() {
+        let start = Chess::default();
+        let mut inference = Inference::new(start.clone(), 4);
+        let unknown = Bitboard::from(Square::E4);
+
+        let result = inference.observe(reading(&play(&start, &["e2e4"])), unknown);
+
+        assert_eq!(result, Inferred::Inconsistent);
+    }
+
+    #[test]
+    fn ambiguity_collapses_when_evidence_arrives() {
+        // Both rooks sit on dead squares; either could have reached d1.
+        let start = position("4k3/8/8/8/8/8/4K3/R6R w - - 0 1");
+        let unknown = Bitboard::from(Square::A1) | Bitboard::from(Square::H1);
+        let mut inference = Inference::new(start.clone(), 4);
+
+        let result = inference.observe(reading(&play(&start, &["a1d1"])), unknown);
+        assert_eq!(result, Inferred::Ambiguous(2));
+        assert_eq!(inference.uncertain(), unknown);
+
+        let result = inference.observe(reading(&play(&start, &["a1d1", "e8f8"])), unknown);
+        assert_eq!(result, Inferred::Ambiguous(2));
+
+        // Only the h1 rook can reach h4, so it never went to d1.
+        let line = ["a1d1", "e8f8", "h1h4"];
+        let result = inference.observe(reading(&play(&start, &line)), unknown);
+        let Inferred::Resolved(moves) = result else {
+            panic!("expected resolution, got {result:?}");
+        };
+        assert_eq!(ucis(&moves), line);
+        assert_eq!(inference.candidates().count(), 1);
+    }
+
+    #[test]
+    fn reading_with_too_many_candidates_is_ignored() {
+        // Eight knights and a queen on dead squares can all reach d4.
+        let start = position("7k/8/2N1N3/1N3N2/8/1N3N2/2N1N3/3Q3K w - - 0 1");
+        let unknown = start.board().knights() | Bitboard::from(Square::D1);
+        let mut inference = Inference::new(start.clone(), 4);
+
+        let result = inference.observe(reading(&play(&start, &["d1d4"])), unknown);
+
+        assert_eq!(result, Inferred::Inconsistent);
+        assert_eq!(inference.candidates().count(), 1);
+    }
+
+    #[test]
+    fn lines_stop_at_max_plies() {
+        let start = position("4k3/8/8/8/8/8/4K3/R6R w - - 0 1");
+        let unknown = Bitboard::from(Square::A1) | Bitboard::from(Square::H1);
+        let mut inference = Inference::new(start.clone(), 1);
+
+        inference.observe(reading(&play(&start, &["a1d1"])), unknown);
+        let result = inference.observe(reading(&play(&start, &["a1d1", "e8f8"])), unknown);
+
+        assert_eq!(result, Inferred::Inconsistent);
+    }
+}
@@ -1,11 +1,67 @@
-use shakmaty::{Bitboard, ByColor};
+// The firmware reports failures instead of panicking; tests may unwrap.
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
 
+use shakmaty::{Bitboard, ByColor, Square};
+
+pub mod abort;
+pub mod access;
+pub mod adjudication;
+pub mod animation;
+pub mod app;
+pub mod arbitration;
 pub mod ble_protocol;
 pub mod board_api;
+pub mod board_config;
+pub mod boot;
+pub mod bounded;
+pub mod calibration;
+pub mod checkers;
+pub mod chess_clock;
+pub mod color_vision;
+pub mod compress;
+pub mod debounce;
+pub mod differential;
+pub mod edge;
+pub mod export;
 pub mod feedback;
+pub mod flight_recorder;
+pub mod frame;
+pub mod gestures;
+pub mod hardware;
+pub mod heatmap;
+pub mod i2c_bus;
+pub mod inference;
+pub mod log_filter;
+pub mod material;
+pub mod minigames;
+pub mod mode;
+pub mod move_index;
+pub mod net;
+pub mod pairing;
+pub mod pgn_replay;
+pub mod pins;
+pub mod playback;
 pub mod player;
+pub mod power;
+pub mod profiles;
+pub mod rng;
+pub mod rules;
+pub mod saved_game;
+pub mod scan_bench;
+pub mod scenario;
+pub mod scheduler;
 pub mod session;
+pub mod settings;
 pub mod setup;
+pub mod stats;
+pub mod terminal;
+pub mod thermal;
+pub mod tick_log;
+pub mod tls;
+pub mod tournament;
+pub mod training;
+pub mod wifi;
+pub mod ws2812;
 
 /// Trait for reading piece positions from the board.
 ///
@@ -17,6 +73,21 @@ pub trait PieceSensor {
 
     /// Read current per-color piece positions from the board.
     fn read_positions(&mut self) -> Result<ByColor<Bitboard>, Self::Error>;
+
+    /// Read the raw per-square signal in millivolts, for calibration and
+    /// self-test.
+    ///
+    /// Sensors without analog readings return `None`.
+    fn read_millivolts(&mut self) -> Option<Result<[u16; 64], Self::Error>> {
+        None
+    }
+
+    /// Apply (and persist, where supported) a new calibration.
+    ///
+    /// Sensors without analog readings ignore this.
+    fn calibrate(&mut self, calibration: calibration::Calibration) {
+        let _ = calibration;
+    }
 }
 
 /// Trait for displaying board feedback to the player.
@@ -33,10 +104,89 @@ pub trait BoardDisplay {
     /// Implementations map [`feedback::SquareFeedback`] variants
     /// to hardware-specific output (LED colors, etc.).
     fn show(&mut self, feedback: &feedback::BoardFeedback) -> Result<(), Self::Error>;
+
+    /// Show `squares` in raw colors, and every other square dark, until the
+    /// next [`Self::show`] or `set_squares`.
+    ///
+    /// For layers that need colors outside [`feedback::SquareFeedback`] and
+    /// the palette. Brightness and power limits still apply; animations do
+    /// not. Displays without per-square colors ignore this.
+    fn set_squares(&mut self, squares: &[(Square, frame::Rgb8)]) -> Result<(), Self::Error> {
+        let _ = squares;
+        Ok(())
+    }
+
+    /// Start a transient animation over the feedback shown next.
+    ///
+    /// Displays without animation support ignore this.
+    fn play(&mut self, animation: animation::Animation) {
+        let _ = animation;
+    }
+
+    /// Switch brightness and colors, from the next frame on.
+    ///
+    /// Displays without adjustable output ignore this.
+    fn apply_settings(&mut self, settings: &settings::DisplaySettings) {
+        let _ = settings;
+    }
+}
+
+/// A display that failed to start: the board plays on without LEDs.
+impl<D: BoardDisplay> BoardDisplay for Option<D> {
+    type Error = D::Error;
+
+    fn show(&mut self, feedback: &feedback::BoardFeedback) -> Result<(), Self::Error> {
+        self.as_mut()
+            .map_or(Ok(()), |display| display.show(feedback))
+    }
+
+    fn set_squares(&mut self, squares: &[(Square, frame::Rgb8)]) -> Result<(), Self::Error> {
+        self.as_mut()
+            .map_or(Ok(()), |display| display.set_squares(squares))
+    }
+
+    fn play(&mut self, animation: animation::Animation) {
+        if let Some(display) = self {
+            display.play(animation);
+        }
+    }
+
+    fn apply_settings(&mut self, settings: &settings::DisplaySettings) {
+        if let Some(display) = self {
+            display.apply_settings(settings);
+        }
+    }
+}
+
+/// Trait for the notification LEDs around the edge of the board.
+///
+/// A second logical display next to [`BoardDisplay`], fed its own
+/// [`edge::EdgeFeedback`] channel every step. Displays without edge LEDs
+/// ignore it.
+pub trait EdgeDisplay {
+    /// Error type for display update failures.
+    type Error: std::fmt::Debug + std::fmt::Display;
+
+    /// Show the given edge state.
+    fn show_edge(&mut self, edge: &edge::EdgeFeedback) -> Result<(), Self::Error>;
+}
+
+impl<D: EdgeDisplay> EdgeDisplay for Option<D> {
+    type Error = D::Error;
+
+    fn show_edge(&mut self, edge: &edge::EdgeFeedback) -> Result<(), Self::Error> {
+        self.as_mut()
+            .map_or(Ok(()), |display| display.show_edge(edge))
+    }
 }
 
 #[cfg(target_os = "espidf")]
 pub mod esp32;
 
-#[cfg(test)]
-pub mod testutil;
+// Test support panics on misuse, like the tests it serves.
+#[cfg(any(test, feature = "testkit"))]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod testutil;
+
+#[cfg(feature = "testkit")]
+pub mod testkit;
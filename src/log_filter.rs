@@ -0,0 +1,288 @@
+//! Log levels per part of the firmware, changeable while it runs.
+//!
+//! Tracing every sensor reading is too much for normal use but exactly
+//! what a misbehaving board needs. [`FilteredLogger`] wraps the platform
+//! logger and drops records below the level set for their [`LogModule`],
+//! so a client (BLE `SetLogLevel`) or the terminal (`log sensor trace`)
+//! can turn one part up for a while without reflashing. Levels start at
+//! [`DEFAULT_LEVEL`] on every boot.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+/// Level of every module after boot.
+pub const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// The crate's own log targets start with this.
+const CRATE: &str = "unnamed_chess_project";
+
+/// A part of the firmware with its own log level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogModule {
+    /// Sensor drivers, debouncing, calibration and recordings.
+    Sensor,
+    /// Game logic: sessions, players, move detection and feedback.
+    Engine,
+    /// BLE, WiFi and online services.
+    Net,
+    /// LEDs and what they show.
+    Display,
+}
+
+impl LogModule {
+    pub const ALL: [Self; 4] = [Self::Sensor, Self::Engine, Self::Net, Self::Display];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sensor => "sensor",
+            Self::Engine => "engine",
+            Self::Net => "net",
+            Self::Display => "display",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|module| module.name() == name)
+    }
+
+    /// Crate modules logging under this one; `esp32` drivers are listed by
+    /// their submodule.
+    fn paths(self) -> &'static [&'static str] {
+        match self {
+            Self::Sensor => &[
+                "debounce",
+                "calibration",
+                "scan_bench",
+                "hardware",
+                "differential",
+                "flight_recorder",
+                "tick_log",
+                "i2c_bus",
+                "esp32::sensor",
+                "esp32::i2c",
+            ],
+            Self::Engine => &[
+                "app",
+                "session",
+                "player",
+                "inference",
+                "adjudication",
+                "rules",
+                "feedback",
+                "setup",
+                "abort",
+                "gestures",
+                "arbitration",
+                "chess_clock",
+                "saved_game",
+                "esp32::saved_game",
+                "tournament",
+                "esp32::tournament",
+            ],
+            Self::Net => &[
+                "net",
+                "wifi",
+                "tls",
+                "ble_protocol",
+                "pairing",
+                "access",
+                "scheduler",
+                "export",
+                "board_config",
+                "esp32::ble",
+                "esp32::wifi",
+                "esp32::lichess",
+                "esp32::relay",
+                "esp32::tls",
+                "esp32::web_config",
+            ],
+            Self::Display => &[
+                "frame",
+                "animation",
+                "ws2812",
+                "power",
+                "thermal",
+                "edge",
+                "esp32::display",
+            ],
+        }
+    }
+
+    /// The module a log target (a module path, by default) belongs to.
+    pub fn of(target: &str) -> Option<Self> {
+        let path = target.strip_prefix(CRATE)?.strip_prefix("::")?;
+        Self::ALL.into_iter().find(|module| {
+            module.paths().iter().any(|prefix| {
+                path.strip_prefix(prefix)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+        })
+    }
+}
+
+/// A level per [`LogModule`], plus one for every other target.
+#[derive(Debug)]
+pub struct LogLevels {
+    /// Indexed by [`LogModule`], then the other targets.
+    levels: [AtomicUsize; LogModule::ALL.len() + 1],
+}
+
+impl LogLevels {
+    pub const fn new(level: LevelFilter) -> Self {
+        let level = level as usize;
+        Self {
+            levels: [
+                AtomicUsize::new(level),
+                AtomicUsize::new(level),
+                AtomicUsize::new(level),
+                AtomicUsize::new(level),
+                AtomicUsize::new(level),
+            ],
+        }
+    }
+
+    pub fn level(&self, module: LogModule) -> LevelFilter {
+        load(&self.levels[module as usize])
+    }
+
+    pub fn set(&self, module: LogModule, level: LevelFilter) {
+        self.levels[module as usize].store(level as usize, Ordering::Relaxed);
+    }
+
+    /// Set every module and every other target to `level`.
+    pub fn set_all(&self, level: LevelFilter) {
+        for slot in &self.levels {
+            slot.store(level as usize, Ordering::Relaxed);
+        }
+    }
+
+    /// The most verbose level set anywhere, for [`log::set_max_level`].
+    pub fn max(&self) -> LevelFilter {
+        self.levels
+            .iter()
+            .map(load)
+            .max()
+            .unwrap_or(LevelFilter::Off)
+    }
+
+    pub fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        let slot = LogModule::of(metadata.target()).map_or(LogModule::ALL.len(), |m| m as usize);
+        metadata.level() <= load(&self.levels[slot])
+    }
+}
+
+fn load(slot: &AtomicUsize) -> LevelFilter {
+    LevelFilter::iter()
+        .nth(slot.load(Ordering::Relaxed))
+        .unwrap_or(DEFAULT_LEVEL)
+}
+
+/// The levels [`FilteredLogger::install`] filters by.
+pub static LEVELS: LogLevels = LogLevels::new(DEFAULT_LEVEL);
+
+/// Change the level of `module`, or of everything if `None`.
+pub fn set_level(module: Option<LogModule>, level: LevelFilter) {
+    match module {
+        Some(module) => {
+            log::info!("Log level of {} set to {level}", module.name());
+            LEVELS.set(module, level);
+        }
+        None => {
+            log::info!("Log level set to {level}");
+            LEVELS.set_all(level);
+        }
+    }
+    log::set_max_level(LEVELS.max());
+}
+
+/// A logger passing on the records [`LogLevels`] allows.
+#[derive(Debug)]
+pub struct FilteredLogger<L> {
+    inner: L,
+    levels: &'static LogLevels,
+}
+
+impl<L: Log + 'static> FilteredLogger<L> {
+    pub fn new(inner: L, levels: &'static LogLevels) -> Self {
+        Self { inner, levels }
+    }
+
+    /// Make `inner`, filtered by [`LEVELS`], the global logger.
+    pub fn install(inner: L) -> Result<(), SetLoggerError> {
+        let logger: &'static Self = Box::leak(Box::new(Self::new(inner, &LEVELS)));
+        log::set_logger(logger)?;
+        log::set_max_level(LEVELS.max());
+        Ok(())
+    }
+}
+
+impl<L: Log> Log for FilteredLogger<L> {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.levels.enabled(metadata) && self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.levels.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    fn metadata(target: &str, level: Level) -> Metadata<'_> {
+        Metadata::builder().target(target).level(level).build()
+    }
+
+    #[test]
+    fn targets_map_to_modules_by_path() {
+        let cases = [
+            ("unnamed_chess_project::session", Some(LogModule::Engine)),
+            (
+                "unnamed_chess_project::player::human",
+                Some(LogModule::Engine),
+            ),
+            (
+                "unnamed_chess_project::esp32::sensor",
+                Some(LogModule::Sensor),
+            ),
+            ("unnamed_chess_project::net::lichess", Some(LogModule::Net)),
+            ("unnamed_chess_project::ws2812", Some(LogModule::Display)),
+            ("unnamed_chess_project::settings", None),
+            ("unnamed_chess_project::networking", None),
+            ("esp_idf_svc::wifi", None),
+        ];
+        for (target, module) in cases {
+            assert_eq!(LogModule::of(target), module, "{target}");
+        }
+    }
+
+    #[test]
+    fn each_module_filters_at_its_own_level() {
+        let levels = LogLevels::new(LevelFilter::Info);
+        levels.set(LogModule::Sensor, LevelFilter::Trace);
+        levels.set(LogModule::Net, LevelFilter::Warn);
+
+        let sensor = "unnamed_chess_project::debounce";
+        let net = "unnamed_chess_project::wifi";
+        assert!(levels.enabled(&metadata(sensor, Level::Trace)));
+        assert!(!levels.enabled(&metadata(net, Level::Info)));
+        assert!(levels.enabled(&metadata(net, Level::Warn)));
+        assert!(levels.enabled(&metadata("esp_idf_svc::nvs", Level::Info)));
+        assert!(!levels.enabled(&metadata("esp_idf_svc::nvs", Level::Debug)));
+        assert_eq!(levels.max(), LevelFilter::Trace);
+
+        levels.set_all(LevelFilter::Error);
+        assert_eq!(levels.level(LogModule::Sensor), LevelFilter::Error);
+        assert!(!levels.enabled(&metadata("esp_idf_svc::nvs", Level::Warn)));
+        assert_eq!(levels.max(), LevelFilter::Error);
+    }
+}
@@ -1,71 +1,129 @@
-#[cfg(target_os = "espidf")]
-enum BoardState {
-    Idle,
-    AwaitingPieces {
-        white: unnamed_chess_project::board_api::PlayerType,
-        black: unnamed_chess_project::board_api::PlayerType,
-    },
-    InProgress {
-        session: unnamed_chess_project::session::GameSession,
-        white_tx: Option<std::sync::mpsc::Sender<shakmaty::Move>>,
-        black_tx: Option<std::sync::mpsc::Sender<shakmaty::Move>>,
-    },
-}
-
 #[cfg(target_os = "espidf")]
 fn main() {
+    use esp_idf_svc::eventloop::EspSystemEventLoop;
     use esp_idf_svc::hal::adc::oneshot::AdcDriver;
     use esp_idf_svc::hal::delay::FreeRtos;
     use esp_idf_svc::hal::peripherals::Peripherals;
+    use esp_idf_svc::hal::temp_sensor::{TempSensorConfig, TempSensorDriver};
+    use esp_idf_svc::nvs::EspDefaultNvsPartition;
     use esp_idf_svc::nvs::{EspNvsPartition, NvsCustom};
-    use shakmaty::{Color, Position};
-    use unnamed_chess_project::ble_protocol::{
-        BleCommand, CommandResult, CommandSource, ErrorCode,
+    use unnamed_chess_project::app::{BoardApp, Clock, SystemClock};
+    use unnamed_chess_project::board_config::PageStatus;
+    use unnamed_chess_project::boot::{BootReport, BootStage, DEGRADED_HOLD, StageOutcome};
+    use unnamed_chess_project::debounce::SensorDebouncer;
+    use unnamed_chess_project::edge::EdgeLayout;
+    use unnamed_chess_project::esp32::config::{
+        BOOT_BEHAVIOR, CONNECTION_LIMITS, EDGE_LEDS, FEEDBACK_SETTLE, FLIGHT_RECORDER_BLOCKS,
+        FLIGHT_RECORDER_PATH, LedPalette, PIN_ASSIGNMENTS, SENSOR_STABILITY, SQUARE_LEDS,
+        SensorCalibration, SensorConfig, WIFI_ENABLED, hardware_revision,
+    };
+    use unnamed_chess_project::esp32::{
+        ConfigServer, ConsoleLogger, Esp32LedDisplay, Esp32PieceSensor, EspLichess, NvsConfigStore,
+        NvsGameStore, NvsPairingStore, NvsPassphraseStore, NvsProfileStore, NvsTokenStore,
+        NvsTournamentStore, WifiManager, start_ble,
     };
-    use unnamed_chess_project::board_api;
-    use unnamed_chess_project::esp32::config::{LedPalette, SensorCalibration, SensorConfig};
-    use unnamed_chess_project::esp32::{Esp32LedDisplay, Esp32PieceSensor, start_ble};
-    use unnamed_chess_project::feedback::BoardFeedback;
-    use unnamed_chess_project::session::GameSession;
-    use unnamed_chess_project::setup::setup_feedback;
-    use unnamed_chess_project::{BoardDisplay, PieceSensor};
+    use unnamed_chess_project::export::JsonlExporter;
+    use unnamed_chess_project::flight_recorder::{FlightRecorder, RecordingSensor};
+    use unnamed_chess_project::log_filter::FilteredLogger;
+    use unnamed_chess_project::net::lichess::{Lichess, TokenStore};
+    use unnamed_chess_project::net::relay::PassphraseStore;
+    use unnamed_chess_project::pairing::Pairing;
+    use unnamed_chess_project::pins::{ESP32_S3, validate};
+    use unnamed_chess_project::saved_game::GameStore;
+    use unnamed_chess_project::thermal::ThermalConfig;
+    use unnamed_chess_project::tick_log::TickLogger;
+    use unnamed_chess_project::tournament::{Tournament, TournamentStore};
 
     esp_idf_svc::sys::link_patches();
-    esp_idf_svc::log::EspLogger::initialize_default();
+    // Clients can raise one module's log level at runtime (see
+    // `log_filter`).
+    FilteredLogger::install(ConsoleLogger).expect("logger already installed");
 
     let peripherals = Peripherals::take().expect("failed to take peripherals");
 
-    let mut display = Esp32LedDisplay::new(peripherals.pins.gpio2, LedPalette::default())
-        .expect("failed to init LED display");
+    // A bad pin table would fail in odd ways later; stop before any driver
+    // claims a pin.
+    if let Err(e) = validate(PIN_ASSIGNMENTS, &ESP32_S3) {
+        log::error!("Pin assignment error (code {}): {e}", e.code());
+        halt_blinking(peripherals.pins.gpio2, e.code());
+    }
 
-    // Load sensor calibration from the dedicated cal partition (survives erase-nvs)
-    let cal_partition =
-        EspNvsPartition::<NvsCustom>::take("cal").expect("failed to take cal NVS partition");
-    let sensor_config = match SensorCalibration::load(&cal_partition) {
-        Ok(Some(cal)) => {
-            log::info!(
-                "Using NVS calibration: baseline={}mV, threshold={}mV",
-                cal.baseline_mv,
-                cal.threshold_mv
-            );
-            SensorConfig {
-                baseline_mv: cal.baseline_mv,
-                threshold_mv: cal.threshold_mv,
-                ..SensorConfig::default()
+    // Bring the board up stage by stage (see `boot`). Only the sensors are
+    // essential; anything else that fails leaves the board playing without
+    // it.
+    let mut report = BootReport::new();
+
+    // Config: start from the preset for this hardware revision; calibration
+    // replaces its baseline and threshold.
+    let revision = hardware_revision();
+    let preset_config = SensorConfig::for_revision(revision);
+    log::info!(
+        "Hardware revision {}: baseline={}mV, threshold={}mV",
+        revision.name(),
+        preset_config.baseline_mv,
+        preset_config.threshold_mv
+    );
+    // Sensor calibration lives in the dedicated cal partition (survives
+    // erase-nvs).
+    let cal_partition = EspNvsPartition::<NvsCustom>::take("cal");
+    let (sensor_config, config_outcome) = match &cal_partition {
+        Err(e) => (
+            preset_config,
+            StageOutcome::Degraded(format!("cal partition unavailable: {e}")),
+        ),
+        Ok(partition) => match SensorCalibration::load(partition) {
+            Ok(Some(cal)) => {
+                log::info!(
+                    "Using NVS calibration: baseline={}mV, threshold={}mV",
+                    cal.baseline_mv,
+                    cal.threshold_mv
+                );
+                if cal.square_baselines.is_none() {
+                    log::info!("No per-square baselines saved; recalibrate to measure them");
+                }
+                (cal.apply(preset_config), StageOutcome::Ready)
             }
-        }
-        Ok(None) => {
-            log::info!("No sensor calibration in NVS, using defaults");
-            SensorConfig::default()
-        }
+            Ok(None) => (
+                preset_config,
+                StageOutcome::Degraded("uncalibrated, using the revision preset".into()),
+            ),
+            Err(e) => (
+                preset_config,
+                StageOutcome::Degraded(format!("calibration unreadable: {e}")),
+            ),
+        },
+    };
+    report.record(BootStage::Config, config_outcome);
+
+    let clock = SystemClock::new();
+    // Keep readings on the SD card, if one is mounted, for misdetections
+    // reported long after the fact.
+    let recorder = std::fs::File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(FLIGHT_RECORDER_PATH)
+        .map_err(|e| log::info!("Flight recorder off: {e}"))
+        .ok()
+        .and_then(|file| {
+            FlightRecorder::open(file, FLIGHT_RECORDER_BLOCKS)
+                .map_err(|e| log::warn!("Flight recorder off: {e}"))
+                .ok()
+        });
+
+    // Sensors: nothing works without them, so a failure halts the board.
+    let adc_driver = match AdcDriver::new(peripherals.adc1) {
+        Ok(adc) => adc,
         Err(e) => {
-            log::warn!("NVS calibration read failed: {e} -- using defaults");
-            SensorConfig::default()
+            report.record(
+                BootStage::Sensors,
+                StageOutcome::Failed(format!("ADC1: {e}")),
+            );
+            halt_blinking(peripherals.pins.gpio2, BootStage::Sensors.failure_code());
         }
     };
-
-    let adc_driver = AdcDriver::new(peripherals.adc1).expect("failed to init ADC1");
-    let mut sensor = Esp32PieceSensor::new(
+    let sensor = match Esp32PieceSensor::new(
         &adc_driver,
         peripherals.pins.gpio4,
         peripherals.pins.gpio5,
@@ -76,325 +134,348 @@ fn main() {
         peripherals.pins.gpio11,
         peripherals.pins.gpio12,
         sensor_config,
-    )
-    .expect("failed to init sensor");
+    ) {
+        Ok(sensor) => sensor,
+        Err(e) => {
+            report.record(BootStage::Sensors, StageOutcome::Failed(e.to_string()));
+            halt_blinking(peripherals.pins.gpio2, BootStage::Sensors.failure_code());
+        }
+    };
+    let sensor = match cal_partition {
+        Ok(partition) => sensor.with_calibration_store(partition),
+        Err(_) => sensor,
+    };
+    report.record(BootStage::Sensors, StageOutcome::Ready);
+    // Log every reading change so a serial capture can be replayed on the
+    // desktop (`just replay-log`).
+    let sensor = TickLogger::new(sensor);
+    let sensor = RecordingSensor::new(sensor, clock, recorder);
+    // Record raw readings, but only pass stable ones on to the game.
+    let sensor = SensorDebouncer::new(sensor, clock, SENSOR_STABILITY);
 
-    let (commands, notifier) = start_ble().expect("failed to start BLE server");
+    // LEDs: without them the board still plays and reports to clients.
+    let palette = LedPalette::default();
+    let mut display = match Esp32LedDisplay::new(peripherals.pins.gpio2, palette) {
+        Ok(display) => {
+            let display = display
+                .with_square_leds(SQUARE_LEDS)
+                .with_edge(EdgeLayout::new(EDGE_LEDS));
+            // Thermal throttling is best-effort: run at full brightness
+            // without it.
+            let display =
+                match TempSensorDriver::new(&TempSensorConfig::default(), peripherals.temp_sensor)
+                    .and_then(|mut t| t.enable().map(|()| t))
+                {
+                    Ok(temp) => display.with_thermal_throttle(temp, ThermalConfig::default()),
+                    Err(e) => {
+                        log::warn!("Temperature sensor unavailable, LED throttling disabled: {e}");
+                        display
+                    }
+                };
+            report.record(BootStage::Leds, StageOutcome::Ready);
+            Some(display)
+        }
+        Err(e) => {
+            report.record(BootStage::Leds, StageOutcome::Failed(e.to_string()));
+            None
+        }
+    };
+    show_progress(&mut display, &report, &palette);
 
-    let mut board_state = BoardState::Idle;
-    let mut prev_positions = None;
-    let mut prev_game_state: Option<board_api::GameStatus> = None;
+    // Clients: without BLE the board plays offline.
+    let (mut commands, notifier) = match start_ble(CONNECTION_LIMITS) {
+        Ok((commands, notifier)) => {
+            report.record(BootStage::Clients, StageOutcome::Ready);
+            (Some(commands), Some(notifier))
+        }
+        Err(e) => {
+            report.record(BootStage::Clients, StageOutcome::Failed(e.to_string()));
+            (None, None)
+        }
+    };
+    show_progress(&mut display, &report, &palette);
 
-    log::info!("Entering BLE command loop");
+    // Stream overlays read game updates as JSON Lines from the serial console.
+    let notifier = JsonlExporter::new(notifier, clock, std::io::stdout());
 
-    loop {
-        while let Some(cmd) = commands.try_recv() {
-            match cmd {
-                BleCommand::StartGame { white, black } => {
-                    if !matches!(board_state, BoardState::Idle) {
-                        notifier.notify_command_result(&CommandResult::error(
-                            CommandSource::StartGame,
-                            ErrorCode::GameAlreadyInProgress,
-                        ));
-                        continue;
-                    }
-                    notifier
-                        .notify_command_result(&CommandResult::success(CommandSource::StartGame));
-                    notifier.update_player_type(Color::White, white);
-                    notifier.update_player_type(Color::Black, black);
-                    notifier.notify_game_status(&board_api::GameStatus::AwaitingPieces);
-                    board_state = BoardState::AwaitingPieces { white, black };
-                    log::info!("Waiting for starting position...");
-                    break; // State mutation
-                }
-                BleCommand::CancelGame => match board_state {
-                    BoardState::Idle => {
-                        notifier.notify_command_result(&CommandResult::error(
-                            CommandSource::MatchControl,
-                            ErrorCode::NoGameInProgress,
-                        ));
-                    }
-                    _ => {
-                        board_state = BoardState::Idle;
-                        notifier.notify_command_result(&CommandResult::success(
-                            CommandSource::MatchControl,
-                        ));
-                        notifier.notify_game_status(&board_api::GameStatus::Idle);
-                        notifier.reset_player_types();
-                        notifier.reset_position();
-                        notifier.reset_last_move();
-                        log::info!("Game cancelled");
-                        break; // State mutation
-                    }
-                },
-                BleCommand::SubmitMove { uci } => {
-                    let BoardState::InProgress {
-                        ref session,
-                        ref white_tx,
-                        ref black_tx,
-                    } = board_state
-                    else {
-                        notifier.notify_command_result(&CommandResult::error(
-                            CommandSource::SubmitMove,
-                            ErrorCode::NoGameInProgress,
-                        ));
-                        continue;
-                    };
-                    let turn = session.position().turn();
-                    let tx = match turn {
-                        Color::White => white_tx,
-                        Color::Black => black_tx,
-                    };
-                    let Some(tx) = tx else {
-                        // Current player is Human, not Remote — reject
-                        notifier.notify_command_result(&CommandResult::error(
-                            CommandSource::SubmitMove,
-                            ErrorCode::NotYourTurn,
-                        ));
-                        continue;
-                    };
-                    // Parse and validate the UCI move
-                    match parse_uci_move(session.position(), &uci) {
-                        Ok(mv) => {
-                            if tx.send(mv).is_err() {
-                                log::warn!("SubmitMove: channel closed, receiver dropped");
-                                notifier.notify_command_result(&CommandResult::error(
-                                    CommandSource::SubmitMove,
-                                    ErrorCode::NoGameInProgress,
-                                ));
-                            } else {
-                                notifier.notify_command_result(&CommandResult::success(
-                                    CommandSource::SubmitMove,
-                                ));
-                            }
-                        }
-                        Err(_) => {
-                            notifier.notify_command_result(&CommandResult::error(
-                                CommandSource::SubmitMove,
-                                ErrorCode::IllegalMove,
-                            ));
-                        }
-                    }
-                    break; // Force tick before processing next command
-                }
-                BleCommand::Resign { color } => {
-                    if let BoardState::InProgress {
-                        ref mut session,
-                        ref white_tx,
-                        ref black_tx,
-                    } = board_state
-                    {
-                        // Check that the side being resigned is Human (not Remote)
-                        let is_remote = match color {
-                            Color::White => white_tx.is_some(),
-                            Color::Black => black_tx.is_some(),
-                        };
-                        if is_remote {
-                            notifier.notify_command_result(&CommandResult::error(
-                                CommandSource::MatchControl,
-                                ErrorCode::CannotResignForRemotePlayer,
-                            ));
-                        } else if session.resign(color) {
-                            log::info!("{color:?} resigns");
-                            notifier.notify_command_result(&CommandResult::success(
-                                CommandSource::MatchControl,
-                            ));
-                            notifier.notify_game_status(&session.game_state());
-                        } else {
-                            // Should not happen if is_remote check is correct, but handle gracefully
-                            notifier.notify_command_result(&CommandResult::error(
-                                CommandSource::MatchControl,
-                                ErrorCode::InvalidCommand,
-                            ));
-                        }
-                        break; // Force tick after state mutation
-                    } else {
-                        notifier.notify_command_result(&CommandResult::error(
-                            CommandSource::MatchControl,
-                            ErrorCode::NoGameInProgress,
-                        ));
-                    }
-                }
-            }
+    let mut app = BoardApp::new(sensor, display, notifier, clock);
+    app.set_dead_squares(sensor_config.dead_squares);
+    app.set_feedback_settle(FEEDBACK_SETTLE);
+    // Game restore: a game cut off by a power loss carries on; otherwise
+    // the board starts as configured.
+    let nvs = EspDefaultNvsPartition::take()
+        .inspect_err(|e| {
+            report.record(
+                BootStage::GameRestore,
+                StageOutcome::Failed(format!("default NVS partition unavailable: {e}")),
+            );
+        })
+        .ok();
+    // The owner's preferences from the config page; the defaults until
+    // first saved.
+    let mut config_store = nvs.clone().and_then(|nvs| {
+        NvsConfigStore::new(nvs)
+            .inspect_err(|e| log::warn!("Board config will not be saved: {e}"))
+            .ok()
+    });
+    let config = config_store
+        .as_ref()
+        .and_then(NvsConfigStore::load)
+        .unwrap_or_default();
+    app.apply_config(&config);
+    let saved = match nvs.clone().map(NvsGameStore::new) {
+        Some(Ok(store)) => {
+            let saved = store.load();
+            app.set_game_store(Box::new(store));
+            report.record(BootStage::GameRestore, StageOutcome::Ready);
+            saved
         }
-
-        if let BoardState::AwaitingPieces {
-            ref white,
-            ref black,
-        } = board_state
+        Some(Err(e)) => {
+            report.record(
+                BootStage::GameRestore,
+                StageOutcome::Degraded(format!("games will not survive a restart: {e}")),
+            );
+            None
+        }
+        None => None,
+    };
+    if let Some(saved) = saved {
+        if let Err(e) = app.restore_game(saved) {
+            report.record(
+                BootStage::GameRestore,
+                StageOutcome::Degraded(format!("saved game not restored: {e:?}")),
+            );
+        }
+    } else {
+        let day = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs() / 86_400);
+        if let Some(selection) = BOOT_BEHAVIOR.selection(day)
+            && let Err(e) = app.enter_mode(selection)
         {
-            let positions = match sensor.read_positions() {
-                Ok(p) => p,
-                Err(e) => {
-                    log::warn!("Sensor read failed: {e}");
-                    FreeRtos::delay_ms(100);
-                    continue;
-                }
-            };
-            match setup_feedback(&positions) {
-                Some(fb) => {
-                    if let Err(e) = display.show(&fb) {
-                        log::warn!("LED update failed: {e}");
-                    }
-                }
-                None => {
-                    // Starting position detected — create session
-                    if let Err(e) = display.show(&BoardFeedback::default()) {
-                        log::warn!("LED clear failed: {e}");
-                    }
-                    let initial = match sensor.read_positions() {
-                        Ok(p) => p,
-                        Err(e) => {
-                            log::error!("Initial sensor read failed: {e}");
-                            board_state = BoardState::Idle;
-                            notifier.notify_game_status(&board_api::GameStatus::Idle);
-                            continue;
-                        }
-                    };
-                    let (white_player, w_tx) = create_player(*white, initial);
-                    let (black_player, b_tx) = create_player(*black, initial);
-                    let new_session = GameSession::new(white_player, black_player);
-                    notifier.notify_game_status(&new_session.game_state());
-                    // Set initial position FEN
-                    let fen = shakmaty::fen::Fen::from_position(
-                        new_session.position(),
-                        shakmaty::EnPassantMode::Legal,
-                    )
-                    .to_string();
-                    notifier.update_position(&fen);
-                    prev_game_state = Some(new_session.game_state());
-                    prev_positions = Some(initial);
-                    board_state = BoardState::InProgress {
-                        session: new_session,
-                        white_tx: w_tx,
-                        black_tx: b_tx,
-                    };
-                    log::info!("Starting position detected, game started");
-                }
+            log::warn!("Boot mode not started: {e:?}");
+        }
+    }
+    // The club tournament, if one was running before the restart.
+    match nvs.clone().map(NvsTournamentStore::new) {
+        Some(Ok(store)) => {
+            let tournament = store.load();
+            app.set_tournament_store(Box::new(store));
+            if let Some(tournament) = tournament {
+                app.start_tournament(tournament);
             }
         }
+        Some(Err(e)) => log::warn!("Tournaments will not survive a restart: {e}"),
+        None => {}
+    }
+    // Player profiles from the config page.
+    match nvs.clone().map(NvsProfileStore::new) {
+        Some(Ok(store)) => app.set_profile_store(Box::new(store)),
+        Some(Err(e)) => log::warn!("Profiles will not survive a restart: {e}"),
+        None => {}
+    }
+    // Only paired clients may control the board. Codes and tokens come from
+    // the hardware RNG, which is seeded by radio noise once BLE is up.
+    // SAFETY: `esp_random` has no preconditions.
+    let mut pairing = Pairing::new(|| unsafe { esp_idf_svc::sys::esp_random() });
+    match nvs.clone().map(NvsPairingStore::new) {
+        Some(Ok(store)) => pairing.set_store(Box::new(store)),
+        Some(Err(e)) => log::warn!("Paired clients will not survive a restart: {e}"),
+        None => {}
+    }
+    app.set_pairing(pairing);
+    show_progress(app.display_mut(), &report, &palette);
 
-        if let BoardState::InProgress {
-            ref mut session, ..
-        } = board_state
-        {
-            // Check game-over FIRST (handles resign from command processing above)
-            if session.is_game_over() {
-                log::info!("Game over: {:?}", session.game_state());
-                board_state = BoardState::Idle;
-                prev_positions = None;
-                prev_game_state = None;
-                notifier.reset_player_types();
-                // Don't reset position/last_move — keep them so the app can read the final state
-                FreeRtos::delay_ms(50);
-                continue;
+    // Network: WiFi is best-effort, the board plays over BLE without it.
+    let mut wifi = None;
+    let mut token_store = nvs.clone().and_then(|nvs| NvsTokenStore::new(nvs).ok());
+    let mut passphrase_store = nvs
+        .clone()
+        .and_then(|nvs| NvsPassphraseStore::new(nvs).ok());
+    if WIFI_ENABLED {
+        let started = match (EspSystemEventLoop::take(), nvs) {
+            (Ok(sys_loop), Some(nvs)) => {
+                WifiManager::start(peripherals.modem, sys_loop, nvs).map_err(|e| e.to_string())
+            }
+            (Err(e), _) => Err(format!("system event loop: {e}")),
+            (_, None) => Err("no NVS for credentials".to_string()),
+        };
+        match started {
+            Ok(manager) => {
+                report.record(BootStage::Network, StageOutcome::Ready);
+                wifi = Some(manager);
             }
+            Err(e) => report.record(BootStage::Network, StageOutcome::Failed(e)),
+        }
+    }
+    // Online opponents, once the config page has linked a Lichess account
+    // or set a relay passphrase.
+    if wifi.is_some() {
+        if let Some(lichess) = token_store
+            .as_ref()
+            .and_then(|store| Lichess::from_store(EspLichess, store).ok())
+        {
+            app.set_lichess(lichess);
+        }
+        if let Some(passphrase) = passphrase_store.as_ref().and_then(|store| store.load()) {
+            join_relay(&mut app, &passphrase);
+        }
+    }
+    // The config page, on the home network and the setup access point.
+    let config_server = wifi.as_ref().and_then(|wifi| {
+        let status = PageStatus {
+            network: wifi.network().map(str::to_string),
+            lichess_linked: token_store.as_ref().is_some_and(|s| s.load().is_some()),
+            relay_joined: passphrase_store
+                .as_ref()
+                .is_some_and(|s| s.load().is_some()),
+            message: None,
+            crosstable: app.tournament().map(Tournament::crosstable),
+            profiles: app.profiles().to_vec(),
+        };
+        ConfigServer::start(config, status)
+            .inspect_err(|e| log::warn!("Config page unavailable: {e}"))
+            .ok()
+    });
+    show_progress(app.display_mut(), &report, &palette);
+    if !report.is_ready() {
+        FreeRtos::delay_ms(DEGRADED_HOLD.as_millis() as u32);
+    }
 
-            let positions = match sensor.read_positions() {
-                Ok(p) => p,
-                Err(e) => {
-                    log::warn!("Sensor read failed: {e}");
-                    FreeRtos::delay_ms(100);
-                    continue;
-                }
-            };
+    log::info!("Entering BLE command loop");
 
-            log_sensor_changes(prev_positions, positions);
-            prev_positions = Some(positions);
+    // What the config page's crosstable was last made from.
+    let mut shown_tournament = app.tournament().cloned();
 
-            let result = session.tick(positions);
-            if let Some(ref mv) = result.last_move {
-                use shakmaty::uci::UciMove;
-                log::info!("Move played: {mv}");
-                // The player who just moved is the opposite of current turn (turn already advanced)
-                let mover = !session.position().turn();
-                let uci = UciMove::from_move(*mv, shakmaty::CastlingMode::Standard).to_string();
-                notifier.notify_move_played(mover, &uci);
-                notifier.update_last_move(mover, &uci);
+    loop {
+        if let Some(server) = &config_server
+            && let Some(update) = server.take_update()
+        {
+            if let (Some(credentials), Some(wifi)) = (update.wifi, &mut wifi) {
+                wifi.provision(credentials);
             }
-
-            if result.last_move.is_some() {
-                let fen = shakmaty::fen::Fen::from_position(
-                    session.position(),
-                    shakmaty::EnPassantMode::Legal,
-                )
-                .to_string();
-                notifier.update_position(&fen);
+            let mut failed = Vec::new();
+            if let Some(token) = update.lichess_token {
+                match token_store.as_mut().map(|store| store.save(&token)) {
+                    Some(Ok(())) => log::info!("Lichess token saved"),
+                    Some(Err(e)) => failed.push(format!("Lichess token not saved: {e}")),
+                    None => failed.push("Lichess token not saved: no NVS".to_string()),
+                }
+                app.set_lichess(Lichess::new(EspLichess, token));
             }
-
-            if let Err(e) = display.show(&result.feedback) {
-                log::warn!("LED update failed: {e}");
+            if let Some(passphrase) = update.relay_passphrase {
+                match passphrase_store
+                    .as_mut()
+                    .map(|store| store.save(&passphrase))
+                {
+                    Some(Ok(())) => log::info!("Relay passphrase saved"),
+                    Some(Err(e)) => failed.push(format!("Relay passphrase not saved: {e}")),
+                    None => failed.push("Relay passphrase not saved: no NVS".to_string()),
+                }
+                join_relay(&mut app, &passphrase);
             }
-
-            let current_status = session.game_state();
-            let state_changed = prev_game_state.as_ref() != Some(&current_status);
-            if state_changed {
-                notifier.notify_game_status(&current_status);
-                prev_game_state = Some(current_status);
+            if let Some(tournament) = update.tournament {
+                log::info!("Tournament started: {}", tournament.format());
+                app.start_tournament(tournament);
+            }
+            if let Some(profiles) = update.profiles {
+                app.set_profiles(profiles);
+            }
+            app.apply_config(&update.config);
+            match config_store
+                .as_mut()
+                .map(|store| store.save(&update.config))
+            {
+                Some(Ok(())) => {}
+                Some(Err(e)) => failed.push(format!("Settings kept until restart: {e}")),
+                None => failed.push("Settings kept until restart: no NVS".to_string()),
+            }
+            if !failed.is_empty() {
+                log::warn!("{}", failed.join("; "));
+                server.report(failed.join(". "));
             }
         }
-
-        FreeRtos::delay_ms(50);
+        if let Some(wifi) = &mut wifi {
+            app.set_wifi_status(wifi.poll(clock.now()));
+        }
+        let delay = app.step(&mut commands);
+        if let Some(server) = &config_server
+            && app.tournament() != shown_tournament.as_ref()
+        {
+            shown_tournament = app.tournament().cloned();
+            server.set_crosstable(shown_tournament.as_ref().map(Tournament::crosstable));
+        }
+        FreeRtos::delay_ms(delay.as_millis() as u32);
     }
 }
 
+/// Let clients start games against boards sharing `passphrase`, if the
+/// firmware was built with a relay (`RELAY_URL`).
 #[cfg(target_os = "espidf")]
-#[derive(Debug, thiserror::Error)]
-enum MoveParseError {
-    #[error("invalid UCI notation")]
-    InvalidUci,
-    #[error("illegal move in current position")]
-    IllegalMove,
-}
+fn join_relay<S, D, N, C>(
+    app: &mut unnamed_chess_project::app::BoardApp<S, D, N, C>,
+    passphrase: &str,
+) {
+    use unnamed_chess_project::esp32::EspRelay;
+    use unnamed_chess_project::esp32::config::RELAY_URL;
+    use unnamed_chess_project::net::relay::{Relay, RelayKey};
 
-#[cfg(target_os = "espidf")]
-fn parse_uci_move(position: &shakmaty::Chess, uci: &str) -> Result<shakmaty::Move, MoveParseError> {
-    use shakmaty::uci::UciMove;
-    use std::str::FromStr;
-    let uci_move = UciMove::from_str(uci).map_err(|_| MoveParseError::InvalidUci)?;
-    uci_move
-        .to_move(position)
-        .map_err(|_| MoveParseError::IllegalMove)
+    let Some(url) = RELAY_URL else {
+        log::warn!("Relay passphrase set, but the firmware was built without RELAY_URL");
+        return;
+    };
+    match RelayKey::from_passphrase(passphrase) {
+        // SAFETY: `esp_random` has no preconditions.
+        Ok(key) => app.set_relay(Relay::new(EspRelay::new(url), key), || unsafe {
+            esp_idf_svc::sys::esp_random()
+        }),
+        Err(e) => log::warn!("Relay not joined: {e}"),
+    }
 }
 
+/// Show boot progress (see [`unnamed_chess_project::boot`]), if the LEDs
+/// came up.
 #[cfg(target_os = "espidf")]
-fn create_player(
-    player_type: unnamed_chess_project::board_api::PlayerType,
-    initial_positions: shakmaty::ByColor<shakmaty::Bitboard>,
-) -> (
-    Box<dyn unnamed_chess_project::player::Player>,
-    Option<std::sync::mpsc::Sender<shakmaty::Move>>,
+fn show_progress(
+    display: &mut impl unnamed_chess_project::BoardDisplay,
+    report: &unnamed_chess_project::boot::BootReport,
+    palette: &unnamed_chess_project::frame::LedPalette,
 ) {
-    use unnamed_chess_project::board_api;
-    use unnamed_chess_project::player::{HumanPlayer, RemotePlayer};
-
-    match player_type {
-        board_api::PlayerType::Human => (Box::new(HumanPlayer::new(initial_positions)), None),
-        board_api::PlayerType::Remote => {
-            let (tx, rx) = std::sync::mpsc::channel();
-            (Box::new(RemotePlayer::new(rx)), Some(tx))
-        }
+    if let Err(e) = display.set_squares(&report.colors(palette)) {
+        log::warn!("LED update failed: {e}");
     }
 }
 
+/// Blink `code` on the status ring forever, for errors that stop the
+/// board before it can report them any other way.
 #[cfg(target_os = "espidf")]
-fn log_sensor_changes(
-    prev: Option<shakmaty::ByColor<shakmaty::Bitboard>>,
-    current: shakmaty::ByColor<shakmaty::Bitboard>,
-) {
-    let Some(prev) = prev else { return };
+fn halt_blinking(led: impl esp_idf_svc::hal::gpio::OutputPin + 'static, code: u8) -> ! {
+    use esp_idf_svc::hal::delay::FreeRtos;
+    use unnamed_chess_project::BoardDisplay;
+    use unnamed_chess_project::esp32::Esp32LedDisplay;
+    use unnamed_chess_project::esp32::config::LedPalette;
+    use unnamed_chess_project::frame::STATUS_RING;
+    use unnamed_chess_project::pins::blink_schedule;
 
-    for sq in current.white & !prev.white {
-        log::debug!("+ {sq} white");
-    }
-    for sq in prev.white & !current.white {
-        log::debug!("- {sq} white");
-    }
-    for sq in current.black & !prev.black {
-        log::debug!("+ {sq} black");
-    }
-    for sq in prev.black & !current.black {
-        log::debug!("- {sq} black");
+    let palette = LedPalette::default();
+    let ring: Vec<_> = STATUS_RING
+        .into_iter()
+        .map(|square| (square, palette.status_failure))
+        .collect();
+    // The LED pin may be the one at fault; keep halting without it.
+    let mut display = Esp32LedDisplay::new(led, palette)
+        .inspect_err(|e| log::error!("LED display unavailable: {e}"))
+        .ok();
+    loop {
+        for (lit, duration) in blink_schedule(code) {
+            if let Some(display) = &mut display {
+                let squares = if lit { ring.as_slice() } else { &[] };
+                if let Err(e) = display.set_squares(squares) {
+                    log::warn!("LED update failed: {e}");
+                }
+            }
+            FreeRtos::delay_ms(duration.as_millis() as u32);
+        }
     }
 }
 
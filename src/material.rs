@@ -0,0 +1,96 @@
+//! Captured pieces and the material balance.
+//!
+//! Captures are read off the moves as they were played, not from the
+//! board, so a piece taken en passant or by a pawn that promotes on the
+//! same move is counted like any other. [`GameSession::captured`] lists
+//! them per side for the terminal and a captured-pieces indicator.
+//!
+//! [`GameSession::captured`]: crate::session::GameSession::captured
+
+use shakmaty::{Board, ByColor, Color, Move, Role};
+
+/// Conventional piece values, in pawns. The king has none, as it is never
+/// captured.
+pub const fn piece_value(role: Role) -> i32 {
+    match role {
+        Role::Pawn => 1,
+        Role::Knight | Role::Bishop => 3,
+        Role::Rook => 5,
+        Role::Queen => 9,
+        Role::King => 0,
+    }
+}
+
+/// The pieces each side captured over `moves`, in the order taken, with
+/// `first` making the first move.
+pub fn captured(first: Color, moves: &[Move]) -> ByColor<Vec<Role>> {
+    let mut captured = ByColor::<Vec<Role>>::default();
+    let mut mover = first;
+    for mv in moves {
+        if let Some(role) = mv.capture() {
+            captured[mover].push(role);
+        }
+        mover = mover.other();
+    }
+    captured
+}
+
+/// White's material minus Black's, in pawns. Unlike the captured pieces
+/// this counts promotions.
+pub fn material_balance(board: &Board) -> i32 {
+    let material = board.material();
+    let total = |color: Color| -> i32 {
+        Role::ALL
+            .into_iter()
+            .map(|role| i32::from(material[color][role]) * piece_value(role))
+            .sum()
+    };
+    total(Color::White) - total(Color::Black)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::uci::UciMove;
+    use shakmaty::{Chess, Position};
+
+    fn play(moves: &[&str]) -> (Chess, Vec<Move>) {
+        let mut position = Chess::default();
+        let moves = moves
+            .iter()
+            .map(|uci| {
+                let mv = uci.parse::<UciMove>().unwrap().to_move(&position).unwrap();
+                position.play_unchecked(mv);
+                mv
+            })
+            .collect();
+        (position, moves)
+    }
+
+    #[test]
+    fn captures_are_credited_to_the_capturing_side() {
+        // 1. e4 d5 2. exd5 Qxd5 3. Nc3 Qe5+ 4. Be2 Qxe2+ 5. Nxe2
+        let (position, moves) = play(&[
+            "e2e4", "d7d5", "e4d5", "d8d5", "b1c3", "d5e5", "f1e2", "e5e2", "g1e2",
+        ]);
+
+        let captured = captured(Color::White, &moves);
+
+        assert_eq!(captured.white, [Role::Pawn, Role::Queen]);
+        assert_eq!(captured.black, [Role::Pawn, Role::Bishop]);
+        assert_eq!(material_balance(position.board()), 6);
+    }
+
+    #[test]
+    fn en_passant_captures_a_pawn() {
+        let (position, moves) = play(&["e2e4", "a7a6", "e4e5", "d7d5", "e5d6"]);
+
+        assert_eq!(captured(Color::White, &moves).white, [Role::Pawn]);
+        assert_eq!(material_balance(position.board()), 1);
+        assert_eq!(
+            captured(Color::Black, &moves[1..]).white,
+            [Role::Pawn],
+            "sides follow the first mover"
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Knight's tour: visit every square exactly once with a single knight.
+
+use std::time::Duration;
+
+use shakmaty::{Bitboard, ByColor, Square, attacks};
+
+use crate::feedback::{BoardFeedback, SquareFeedback};
+use crate::mode::{GameMode, ModeStatus};
+
+/// Place one piece anywhere to start; the board then lights the unvisited
+/// squares a knight can jump to. The tour ends when every square has been
+/// visited or the knight has nowhere left to go.
+#[derive(Debug, Clone, Default)]
+pub struct KnightsTour {
+    at: Option<Square>,
+    visited: Bitboard,
+    /// Pieces that are not where the tour allows, shown as errors.
+    wrong: Bitboard,
+}
+
+impl KnightsTour {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn visited(&self) -> Bitboard {
+        self.visited
+    }
+
+    /// Whether every square has been visited.
+    pub fn is_complete(&self) -> bool {
+        self.visited == Bitboard::FULL
+    }
+
+    /// Unvisited squares the knight can jump to next.
+    pub fn next_squares(&self) -> Bitboard {
+        self.at.map_or(Bitboard::EMPTY, |at| {
+            attacks::knight_attacks(at) & !self.visited
+        })
+    }
+
+    fn status(&self) -> ModeStatus {
+        if self.at.is_some() && self.next_squares().is_empty() {
+            ModeStatus::Finished
+        } else {
+            ModeStatus::Running
+        }
+    }
+}
+
+impl GameMode for KnightsTour {
+    fn name(&self) -> &'static str {
+        "knight's tour"
+    }
+
+    fn tick(&mut self, positions: ByColor<Bitboard>, _now: Duration) -> ModeStatus {
+        let occupied = positions.white | positions.black;
+        let Some(at) = self.at else {
+            if let Some(start) = occupied.single_square() {
+                log::info!("Knight's tour started on {start}");
+                self.at = Some(start);
+                self.visited = Bitboard::from(start);
+                self.wrong = Bitboard::EMPTY;
+            } else {
+                self.wrong = if occupied.more_than_one() {
+                    occupied
+                } else {
+                    Bitboard::EMPTY
+                };
+            }
+            return self.status();
+        };
+
+        if self.status() == ModeStatus::Finished {
+            return ModeStatus::Finished;
+        }
+
+        // The knight standing still or lifted in hand is fine.
+        let others = occupied.without(at);
+        match occupied.single_square() {
+            Some(to) if self.next_squares().contains(to) => {
+                self.at = Some(to);
+                self.visited.add(to);
+                self.wrong = Bitboard::EMPTY;
+                if self.is_complete() {
+                    log::info!("Knight's tour complete");
+                } else if self.next_squares().is_empty() {
+                    log::info!("Knight's tour stuck after {} squares", self.visited.count());
+                }
+            }
+            _ => self.wrong = others,
+        }
+        self.status()
+    }
+
+    fn feedback(&self) -> BoardFeedback {
+        let mut fb = BoardFeedback::new();
+        if let Some(at) = self.at {
+            let kind = match self.status() {
+                ModeStatus::Running => SquareFeedback::Origin,
+                ModeStatus::Finished if self.is_complete() => SquareFeedback::Victory,
+                ModeStatus::Finished => SquareFeedback::Stalemate,
+            };
+            fb.set(at, kind);
+            for sq in self.next_squares() {
+                fb.set(sq, SquareFeedback::Destination);
+            }
+        }
+        for sq in self.wrong {
+            fb.set(sq, SquareFeedback::Check);
+        }
+        fb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn on(squares: Bitboard) -> ByColor<Bitboard> {
+        ByColor {
+            white: squares,
+            black: Bitboard::EMPTY,
+        }
+    }
+
+    fn jump(tour: &mut KnightsTour, to: Square) -> ModeStatus {
+        tour.tick(on(Bitboard::EMPTY), Duration::ZERO);
+        tour.tick(on(to.into()), Duration::ZERO)
+    }
+
+    #[test]
+    fn first_piece_starts_the_tour() {
+        let mut tour = KnightsTour::new();
+        tour.tick(on(Square::A1.into()), Duration::ZERO);
+
+        let fb = tour.feedback();
+        assert_eq!(fb.get(Square::A1), Some(SquareFeedback::Origin));
+        assert_eq!(fb.get(Square::B3), Some(SquareFeedback::Destination));
+        assert_eq!(fb.get(Square::C2), Some(SquareFeedback::Destination));
+        assert_eq!(fb.squares().count(), 3);
+    }
+
+    #[test]
+    fn knight_jump_moves_the_tour() {
+        let mut tour = KnightsTour::new();
+        tour.tick(on(Square::A1.into()), Duration::ZERO);
+
+        jump(&mut tour, Square::B3);
+
+        assert_eq!(tour.visited().count(), 2);
+        assert!(!tour.next_squares().contains(Square::A1));
+    }
+
+    #[test]
+    fn non_knight_move_is_flagged_and_ignored() {
+        let mut tour = KnightsTour::new();
+        tour.tick(on(Square::A1.into()), Duration::ZERO);
+
+        jump(&mut tour, Square::A2);
+
+        assert_eq!(tour.visited().count(), 1);
+        assert_eq!(tour.feedback().get(Square::A2), Some(SquareFeedback::Check));
+    }
+
+    #[test]
+    fn revisiting_a_square_is_not_allowed() {
+        let mut tour = KnightsTour::new();
+        tour.tick(on(Square::A1.into()), Duration::ZERO);
+        jump(&mut tour, Square::B3);
+
+        jump(&mut tour, Square::A1);
+
+        assert_eq!(tour.visited().count(), 2);
+    }
+
+    #[test]
+    fn warnsdorff_tour_completes() {
+        let mut tour = KnightsTour::new();
+        tour.tick(on(Square::A1.into()), Duration::ZERO);
+
+        // Warnsdorff's rule: jump to the square with the fewest onward moves.
+        let mut status = ModeStatus::Running;
+        while status == ModeStatus::Running {
+            let visited = tour.visited();
+            let next = tour
+                .next_squares()
+                .into_iter()
+                .min_by_key(|&sq| (attacks::knight_attacks(sq) & !visited).count())
+                .unwrap();
+            status = jump(&mut tour, next);
+        }
+
+        assert!(tour.is_complete());
+        let fb = tour.feedback();
+        assert_eq!(fb.squares().count(), 1);
+        assert!(
+            fb.squares()
+                .all(|(_, kind)| kind == SquareFeedback::Victory)
+        );
+    }
+
+    #[test]
+    fn dead_end_finishes_without_victory() {
+        let mut tour = KnightsTour::new();
+        tour.tick(on(Square::B3.into()), Duration::ZERO);
+        jump(&mut tour, Square::D4);
+        jump(&mut tour, Square::C2);
+
+        // Both of a1's knight moves (b3, c2) are already visited.
+        assert_eq!(jump(&mut tour, Square::A1), ModeStatus::Finished);
+        assert!(!tour.is_complete());
+        assert_eq!(
+            tour.feedback().get(Square::A1),
+            Some(SquareFeedback::Stalemate)
+        );
+    }
+}
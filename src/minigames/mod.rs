@@ -0,0 +1,78 @@
+//! Board-logic mini-games, each a [`GameMode`].
+
+mod knights_tour;
+mod notation;
+mod pawn_capture;
+
+pub use knights_tour::KnightsTour;
+pub use notation::{NotationEvent, NotationStats, NotationTrainer};
+pub use pawn_capture::{PAWN_PUZZLES, PawnCapture, PawnPuzzle};
+
+use crate::mode::GameMode;
+use crate::training::CoordinateTrainer;
+
+/// The selectable mini-games.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MiniGame {
+    CoordinateTraining,
+    KnightsTour,
+    /// Make the move named in SAN (see [`NotationTrainer`]).
+    NotationTraining,
+    /// One of [`PAWN_PUZZLES`], by index.
+    PawnCapture(usize),
+}
+
+impl MiniGame {
+    /// Every mini-game, in menu order.
+    pub fn all() -> impl Iterator<Item = MiniGame> {
+        [
+            MiniGame::CoordinateTraining,
+            MiniGame::KnightsTour,
+            MiniGame::NotationTraining,
+        ]
+        .into_iter()
+        .chain((0..PAWN_PUZZLES.len()).map(MiniGame::PawnCapture))
+    }
+
+    /// The puzzle of the day, `day` counted from any fixed date (e.g. days
+    /// since the Unix epoch), cycling through [`PAWN_PUZZLES`].
+    pub fn daily_puzzle(day: u64) -> MiniGame {
+        MiniGame::PawnCapture((day % PAWN_PUZZLES.len() as u64) as usize)
+    }
+
+    /// Build a fresh instance, or `None` for an unknown puzzle index.
+    ///
+    /// `seed` drives any random choices (e.g. training prompts).
+    pub fn create(self, seed: u32) -> Option<Box<dyn GameMode>> {
+        Some(match self {
+            MiniGame::CoordinateTraining => Box::new(CoordinateTrainer::new(seed)),
+            MiniGame::KnightsTour => Box::new(KnightsTour::new()),
+            MiniGame::NotationTraining => Box::new(NotationTrainer::new(seed)),
+            MiniGame::PawnCapture(index) => Box::new(PawnCapture::new(*PAWN_PUZZLES.get(index)?)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_listed_game_can_be_created() {
+        for game in MiniGame::all() {
+            assert!(game.create(1).is_some(), "{game:?}");
+        }
+        assert!(
+            MiniGame::PawnCapture(PAWN_PUZZLES.len())
+                .create(1)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn daily_puzzle_cycles_through_the_bundled_set() {
+        assert_eq!(MiniGame::daily_puzzle(0), MiniGame::PawnCapture(0));
+        let days = PAWN_PUZZLES.len() as u64;
+        assert_eq!(MiniGame::daily_puzzle(days + 1), MiniGame::PawnCapture(1));
+    }
+}
@@ -0,0 +1,342 @@
+//! Notation trainer: the board names a legal move in SAN and the player
+//! makes it on the board.
+
+use std::time::Duration;
+
+use shakmaty::san::SanPlus;
+use shakmaty::{Bitboard, ByColor, Chess, Color, Move, Position};
+
+use crate::feedback::{BoardFeedback, SquareFeedback};
+use crate::mode::{GameMode, ModeStatus};
+use crate::rng::XorShift32;
+use crate::setup::SetupGuide;
+
+/// Something that happened during an update, for clients and sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotationEvent {
+    /// A new move to make, to be read out or shown.
+    Prompt { san: SanPlus },
+    /// The prompted move was made after `time`.
+    Correct { san: SanPlus, time: Duration },
+    /// Another legal move was made; the board guides it back.
+    Wrong { expected: SanPlus, played: SanPlus },
+}
+
+/// Running score for a training session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NotationStats {
+    pub attempts: u32,
+    pub correct: u32,
+    /// Consecutive correct moves, reset by a wrong one.
+    pub streak: u32,
+    pub best_streak: u32,
+}
+
+#[derive(Debug, Clone)]
+enum Phase {
+    /// Guiding the pieces onto the position.
+    Setup(SetupGuide),
+    /// The board matches the position; the next tick prompts.
+    Ready {
+        previous: Option<Move>,
+    },
+    Prompting {
+        target: Move,
+        since: Duration,
+    },
+    /// Guiding a wrong move back.
+    TakeBack {
+        played: Move,
+        guide: SetupGuide,
+    },
+}
+
+/// Starts from the standard position, which it guides the pieces onto.
+/// Correct moves stay on the board, so the drill plays through a game;
+/// once it ends the board is set up again. The prompt is not lit, since
+/// reading it is the exercise: only mistakes are shown.
+#[derive(Debug, Clone)]
+pub struct NotationTrainer {
+    rng: XorShift32,
+    position: Chess,
+    phase: Phase,
+    stats: NotationStats,
+    feedback: BoardFeedback,
+}
+
+impl NotationTrainer {
+    pub fn new(seed: u32) -> Self {
+        let position = Chess::default();
+        Self {
+            rng: XorShift32::new(seed),
+            phase: Phase::Setup(SetupGuide::new(position.board())),
+            position,
+            stats: NotationStats::default(),
+            feedback: BoardFeedback::new(),
+        }
+    }
+
+    pub fn stats(&self) -> NotationStats {
+        self.stats
+    }
+
+    pub fn position(&self) -> &Chess {
+        &self.position
+    }
+
+    /// The move currently asked for, if any.
+    pub fn prompt(&self) -> Option<SanPlus> {
+        match &self.phase {
+            Phase::Prompting { target, .. } => Some(self.san(*target)),
+            _ => None,
+        }
+    }
+
+    /// Advance the drill with the current per-color piece positions.
+    pub fn update(&mut self, positions: ByColor<Bitboard>, now: Duration) -> Option<NotationEvent> {
+        self.feedback = BoardFeedback::new();
+        match &mut self.phase {
+            Phase::Setup(guide) => {
+                if let Some(fb) = guide.update(&positions) {
+                    self.feedback = fb;
+                    return None;
+                }
+                self.phase = Phase::Ready { previous: None };
+                None
+            }
+            Phase::Ready { previous } => {
+                let previous = *previous;
+                self.ask(previous, now)
+            }
+            &mut Phase::Prompting { target, since } => {
+                let played = self.played(positions)?;
+                Some(self.score(target, played, now.saturating_sub(since)))
+            }
+            Phase::TakeBack { played, guide } => {
+                let played = *played;
+                let Some(mut fb) = guide.update(&positions) else {
+                    self.phase = Phase::Ready {
+                        previous: Some(played),
+                    };
+                    return None;
+                };
+                if fb.get(played.to()).is_none() {
+                    fb.set(played.to(), SquareFeedback::Check);
+                }
+                self.feedback = fb;
+                None
+            }
+        }
+    }
+
+    fn san(&self, mv: Move) -> SanPlus {
+        SanPlus::from_move_and_play_unchecked(&mut self.position.clone(), mv)
+    }
+
+    /// The legal move the readings show was made, preferring `target` when
+    /// several match (promotions look alike to the sensors).
+    fn played(&self, positions: ByColor<Bitboard>) -> Option<Vec<Move>> {
+        let matching: Vec<Move> = self
+            .position
+            .legal_moves()
+            .into_iter()
+            .filter(|&mv| {
+                let mut after = self.position.clone();
+                after.play_unchecked(mv);
+                board_positions(&after) == positions
+            })
+            .collect();
+        (!matching.is_empty()).then_some(matching)
+    }
+
+    fn ask(&mut self, previous: Option<Move>, now: Duration) -> Option<NotationEvent> {
+        let moves = self.position.legal_moves();
+        let mut index = self.rng.below(moves.len() as u32) as usize;
+        if moves.len() > 1 && previous == Some(moves[index]) {
+            index = (index + 1) % moves.len();
+        }
+        let &target = moves.get(index)?;
+        let san = self.san(target);
+        log::info!("Notation: play {san}");
+        self.phase = Phase::Prompting { target, since: now };
+        Some(NotationEvent::Prompt { san })
+    }
+
+    fn score(&mut self, target: Move, played: Vec<Move>, time: Duration) -> NotationEvent {
+        let expected = self.san(target);
+        self.stats.attempts += 1;
+        if !played.contains(&target) {
+            let played = played[0];
+            let mut after = self.position.clone();
+            after.play_unchecked(played);
+            let played_san = self.san(played);
+            self.stats.streak = 0;
+            log::info!("Notation: {played_san} played, expected {expected}");
+            self.phase = Phase::TakeBack {
+                played,
+                guide: SetupGuide::between(after.board(), self.position.board()),
+            };
+            return NotationEvent::Wrong {
+                expected,
+                played: played_san,
+            };
+        }
+        let stats = &mut self.stats;
+        stats.correct += 1;
+        stats.streak += 1;
+        stats.best_streak = stats.best_streak.max(stats.streak);
+        log::info!("Notation: {expected} correct (streak {})", stats.streak);
+        self.position.play_unchecked(target);
+        self.phase = if self.position.is_game_over() {
+            let start = Chess::default();
+            let guide = SetupGuide::between(self.position.board(), start.board());
+            self.position = start;
+            Phase::Setup(guide)
+        } else {
+            Phase::Ready { previous: None }
+        };
+        NotationEvent::Correct {
+            san: expected,
+            time,
+        }
+    }
+}
+
+impl GameMode for NotationTrainer {
+    fn name(&self) -> &'static str {
+        "notation training"
+    }
+
+    /// The drill never finishes on its own; it runs until cancelled.
+    fn tick(&mut self, positions: ByColor<Bitboard>, now: Duration) -> ModeStatus {
+        self.update(positions, now);
+        ModeStatus::Running
+    }
+
+    fn feedback(&self) -> BoardFeedback {
+        self.feedback.clone()
+    }
+}
+
+fn board_positions(position: &Chess) -> ByColor<Bitboard> {
+    ByColor {
+        white: position.board().by_color(Color::White),
+        black: position.board().by_color(Color::Black),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::Square;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    fn after(position: &Chess, san: &str) -> Chess {
+        let mv = san
+            .parse::<SanPlus>()
+            .unwrap()
+            .san
+            .to_move(position)
+            .unwrap();
+        let mut after = position.clone();
+        after.play_unchecked(mv);
+        after
+    }
+
+    /// A trainer with its pieces set up and the first move asked for.
+    fn prompted(seed: u32) -> (NotationTrainer, SanPlus) {
+        let mut trainer = NotationTrainer::new(seed);
+        let start = board_positions(&Chess::default());
+        assert_eq!(trainer.update(start, ms(0)), None);
+        let Some(NotationEvent::Prompt { san }) = trainer.update(start, ms(0)) else {
+            panic!("expected a prompt");
+        };
+        assert_eq!(trainer.prompt(), Some(san));
+        (trainer, san)
+    }
+
+    #[test]
+    fn making_the_named_move_scores_and_keeps_it() {
+        let (mut trainer, san) = prompted(3);
+        let next = after(trainer.position(), &san.to_string());
+
+        let event = trainer.update(board_positions(&next), ms(2500));
+
+        assert_eq!(
+            event,
+            Some(NotationEvent::Correct {
+                san,
+                time: ms(2500)
+            })
+        );
+        assert_eq!(trainer.stats().streak, 1);
+        assert_eq!(trainer.position().board(), next.board());
+        assert!(trainer.feedback().is_empty(), "the prompt is not lit");
+        assert!(matches!(
+            trainer.update(board_positions(&next), ms(2600)),
+            Some(NotationEvent::Prompt { .. })
+        ));
+    }
+
+    #[test]
+    fn wrong_move_is_flagged_and_guided_back() {
+        let (mut trainer, san) = prompted(3);
+        let start = trainer.position().clone();
+        let wrong = if san.to_string() == "a3" { "h3" } else { "a3" };
+        let moved = board_positions(&after(&start, wrong));
+
+        // A lifted piece is not a move yet.
+        let lifted = ByColor {
+            white: moved.white.without(Square::A3).without(Square::H3),
+            black: moved.black,
+        };
+        assert_eq!(trainer.update(lifted, ms(100)), None);
+        assert_eq!(
+            trainer.update(moved, ms(200)),
+            Some(NotationEvent::Wrong {
+                expected: san,
+                played: wrong.parse().unwrap(),
+            })
+        );
+        assert_eq!(trainer.stats().streak, 0);
+
+        trainer.update(moved, ms(300));
+        let square = if wrong == "a3" {
+            Square::A3
+        } else {
+            Square::H3
+        };
+        assert!(trainer.feedback().get(square).is_some(), "{wrong} flagged");
+
+        assert_eq!(trainer.update(board_positions(&start), ms(400)), None);
+        assert!(matches!(
+            trainer.update(board_positions(&start), ms(500)),
+            Some(NotationEvent::Prompt { .. })
+        ));
+        assert_eq!(trainer.position().board(), start.board());
+    }
+
+    #[test]
+    fn streaks_track_across_moves() {
+        let (mut trainer, mut san) = prompted(7);
+        let mut now = ms(0);
+        for _ in 0..3 {
+            let next = after(trainer.position(), &san.to_string());
+            now += ms(1000);
+            trainer.update(board_positions(&next), now);
+            let Some(NotationEvent::Prompt { san: next_san }) =
+                trainer.update(board_positions(&next), now)
+            else {
+                panic!("expected a prompt");
+            };
+            san = next_san;
+        }
+
+        let stats = trainer.stats();
+        assert_eq!((stats.attempts, stats.correct), (3, 3));
+        assert_eq!(stats.best_streak, 3);
+    }
+}
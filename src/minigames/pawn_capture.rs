@@ -0,0 +1,322 @@
+//! "Capture all pawns" puzzles: one white piece must take every black pawn,
+//! capturing on every move.
+
+use std::time::Duration;
+
+use shakmaty::{Bitboard, ByColor, Color, Piece, Role, Square, attacks};
+
+use crate::feedback::{BoardFeedback, SquareFeedback};
+use crate::mode::{GameMode, ModeStatus};
+use crate::setup::placement_feedback;
+
+/// A puzzle layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PawnPuzzle {
+    pub name: &'static str,
+    pub role: Role,
+    pub start: Square,
+    pub pawns: Bitboard,
+}
+
+const fn squares<const N: usize>(list: [Square; N]) -> Bitboard {
+    let mut bb = 0u64;
+    let mut i = 0;
+    while i < N {
+        bb |= 1 << list[i] as u32;
+        i += 1;
+    }
+    Bitboard(bb)
+}
+
+/// Built-in puzzles, roughly easiest first.
+pub const PAWN_PUZZLES: [PawnPuzzle; 4] = [
+    PawnPuzzle {
+        name: "rook",
+        role: Role::Rook,
+        start: Square::A1,
+        pawns: squares([Square::A5, Square::E5, Square::E2, Square::H2]),
+    },
+    PawnPuzzle {
+        name: "bishop",
+        role: Role::Bishop,
+        start: Square::C1,
+        pawns: squares([Square::E3, Square::G5, Square::D8]),
+    },
+    PawnPuzzle {
+        name: "knight",
+        role: Role::Knight,
+        start: Square::B1,
+        pawns: squares([Square::C3, Square::E4, Square::F6, Square::H7]),
+    },
+    PawnPuzzle {
+        name: "queen",
+        role: Role::Queen,
+        start: Square::D1,
+        pawns: squares([Square::D4, Square::G7, Square::B7, Square::B2]),
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// Waiting for the board to match the puzzle layout.
+    Setup,
+    Solving {
+        at: Square,
+        pawns: Bitboard,
+    },
+    Solved {
+        at: Square,
+    },
+    /// Pawns remain but none can be captured.
+    Stuck {
+        at: Square,
+        pawns: Bitboard,
+    },
+}
+
+/// Plays one [`PawnPuzzle`] from the board sensors.
+///
+/// The piece's color tells it apart from the pawns, so a capture is seen
+/// as the white piece appearing on a square a black pawn just left.
+#[derive(Debug, Clone)]
+pub struct PawnCapture {
+    puzzle: PawnPuzzle,
+    phase: Phase,
+    /// Last sensor reading, for setup and error feedback.
+    positions: ByColor<Bitboard>,
+}
+
+impl PawnCapture {
+    pub fn new(puzzle: PawnPuzzle) -> Self {
+        Self {
+            puzzle,
+            phase: Phase::Setup,
+            positions: ByColor::default(),
+        }
+    }
+
+    pub fn puzzle(&self) -> &PawnPuzzle {
+        &self.puzzle
+    }
+
+    pub fn is_solved(&self) -> bool {
+        matches!(self.phase, Phase::Solved { .. })
+    }
+
+    fn layout(&self) -> ByColor<Bitboard> {
+        ByColor {
+            white: self.puzzle.start.into(),
+            black: self.puzzle.pawns,
+        }
+    }
+
+    /// Pawns the piece on `at` can capture next.
+    fn targets(&self, at: Square, pawns: Bitboard) -> Bitboard {
+        let piece = Piece {
+            color: Color::White,
+            role: self.puzzle.role,
+        };
+        attacks::attacks(at, piece, pawns.with(at)) & pawns
+    }
+
+    fn advance(&mut self, at: Square, pawns: Bitboard) {
+        let current = self.positions;
+        let Some(to) = current.white.single_square() else {
+            return;
+        };
+        if to == at || current.black != pawns.without(to) || !self.targets(at, pawns).contains(to) {
+            return;
+        }
+        let pawns = pawns.without(to);
+        self.phase = if pawns.is_empty() {
+            log::info!("Pawn puzzle '{}' solved", self.puzzle.name);
+            Phase::Solved { at: to }
+        } else if self.targets(to, pawns).is_empty() {
+            log::info!("Pawn puzzle '{}' stuck", self.puzzle.name);
+            Phase::Stuck { at: to, pawns }
+        } else {
+            Phase::Solving { at: to, pawns }
+        };
+    }
+
+    /// Squares that differ from what the puzzle allows right now.
+    fn misplaced(&self, at: Square, pawns: Bitboard) -> Bitboard {
+        let current = self.positions;
+        let allowed_white = Bitboard::from(at) | self.targets(at, pawns);
+        (current.white & !allowed_white) | (current.black & !pawns)
+    }
+}
+
+impl GameMode for PawnCapture {
+    fn name(&self) -> &'static str {
+        "pawn capture"
+    }
+
+    fn tick(&mut self, positions: ByColor<Bitboard>, _now: Duration) -> ModeStatus {
+        self.positions = positions;
+        match self.phase {
+            Phase::Setup => {
+                if positions == self.layout() {
+                    log::info!("Pawn puzzle '{}' ready", self.puzzle.name);
+                    self.phase = Phase::Solving {
+                        at: self.puzzle.start,
+                        pawns: self.puzzle.pawns,
+                    };
+                }
+                ModeStatus::Running
+            }
+            Phase::Solving { at, pawns } => {
+                self.advance(at, pawns);
+                match self.phase {
+                    Phase::Solving { .. } => ModeStatus::Running,
+                    _ => ModeStatus::Finished,
+                }
+            }
+            Phase::Solved { .. } | Phase::Stuck { .. } => ModeStatus::Finished,
+        }
+    }
+
+    fn feedback(&self) -> BoardFeedback {
+        match self.phase {
+            Phase::Setup => {
+                let mut fb =
+                    placement_feedback(&self.layout(), &self.positions).unwrap_or_default();
+                let layout = self.layout();
+                let extra =
+                    (self.positions.white & !layout.white) | (self.positions.black & !layout.black);
+                for sq in extra {
+                    fb.set(sq, SquareFeedback::Check);
+                }
+                fb
+            }
+            Phase::Solving { at, pawns } => {
+                let mut fb = BoardFeedback::new();
+                // Hints only while the piece is lifted, as in a chess game.
+                if !self.positions.white.contains(at) {
+                    fb.set(at, SquareFeedback::Origin);
+                    for sq in self.targets(at, pawns) {
+                        fb.set(sq, SquareFeedback::Capture);
+                    }
+                }
+                for sq in self.misplaced(at, pawns) {
+                    fb.set(sq, SquareFeedback::Check);
+                }
+                fb
+            }
+            Phase::Solved { at } => {
+                let mut fb = BoardFeedback::new();
+                fb.set(at, SquareFeedback::Victory);
+                fb
+            }
+            Phase::Stuck { at, pawns } => {
+                let mut fb = BoardFeedback::new();
+                fb.set(at, SquareFeedback::Stalemate);
+                for sq in pawns {
+                    fb.set(sq, SquareFeedback::Checker);
+                }
+                fb
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(white: Bitboard, black: Bitboard) -> ByColor<Bitboard> {
+        ByColor { white, black }
+    }
+
+    /// A puzzle whose layout is already on the board.
+    fn ready(puzzle: PawnPuzzle) -> PawnCapture {
+        let mut game = PawnCapture::new(puzzle);
+        game.tick(positions(puzzle.start.into(), puzzle.pawns), Duration::ZERO);
+        game
+    }
+
+    /// Lift the pawn on `to`, then put the piece there.
+    fn capture(game: &mut PawnCapture, to: Square) -> ModeStatus {
+        let Phase::Solving { at, pawns } = game.phase else {
+            panic!("not solving");
+        };
+        game.tick(positions(at.into(), pawns.without(to)), Duration::ZERO);
+        game.tick(
+            positions(Bitboard::EMPTY, pawns.without(to)),
+            Duration::ZERO,
+        );
+        game.tick(positions(to.into(), pawns.without(to)), Duration::ZERO)
+    }
+
+    #[test]
+    fn setup_shows_missing_pieces() {
+        let mut game = PawnCapture::new(PAWN_PUZZLES[0]);
+        game.tick(
+            positions(Square::A1.into(), Bitboard::EMPTY),
+            Duration::ZERO,
+        );
+
+        let fb = game.feedback();
+        assert_eq!(fb.get(Square::A5), Some(SquareFeedback::Capture));
+        assert_eq!(fb.get(Square::A1), None);
+    }
+
+    #[test]
+    fn every_builtin_puzzle_is_solvable() {
+        for puzzle in PAWN_PUZZLES {
+            let mut game = ready(puzzle);
+            let mut status = ModeStatus::Running;
+            while status == ModeStatus::Running {
+                let Phase::Solving { at, pawns } = game.phase else {
+                    unreachable!();
+                };
+                // Greedy is enough for the built-in layouts.
+                let to = game.targets(at, pawns).first().unwrap();
+                status = capture(&mut game, to);
+            }
+            assert!(game.is_solved(), "{} puzzle not solved", puzzle.name);
+        }
+    }
+
+    #[test]
+    fn lifting_piece_shows_capturable_pawns() {
+        let puzzle = PAWN_PUZZLES[0];
+        let mut game = ready(puzzle);
+
+        game.tick(positions(Bitboard::EMPTY, puzzle.pawns), Duration::ZERO);
+
+        let fb = game.feedback();
+        assert_eq!(fb.get(Square::A1), Some(SquareFeedback::Origin));
+        assert_eq!(fb.get(Square::A5), Some(SquareFeedback::Capture));
+        assert_eq!(fb.get(Square::E5), None, "not on the rook's lines");
+    }
+
+    #[test]
+    fn non_capturing_move_is_flagged() {
+        let puzzle = PAWN_PUZZLES[0];
+        let mut game = ready(puzzle);
+
+        game.tick(positions(Square::A3.into(), puzzle.pawns), Duration::ZERO);
+
+        assert_eq!(game.feedback().get(Square::A3), Some(SquareFeedback::Check));
+        assert!(matches!(game.phase, Phase::Solving { at: Square::A1, .. }));
+    }
+
+    #[test]
+    fn capturing_into_a_dead_end_finishes_stuck() {
+        let puzzle = PawnPuzzle {
+            name: "dead end",
+            role: Role::Rook,
+            start: Square::A1,
+            pawns: squares([Square::A3, Square::H8]),
+        };
+        let mut game = ready(puzzle);
+
+        assert_eq!(capture(&mut game, Square::A3), ModeStatus::Finished);
+
+        assert!(!game.is_solved());
+        let fb = game.feedback();
+        assert_eq!(fb.get(Square::A3), Some(SquareFeedback::Stalemate));
+        assert_eq!(fb.get(Square::H8), Some(SquareFeedback::Checker));
+    }
+}
@@ -0,0 +1,405 @@
+//! Board activities other than a chess game.
+//!
+//! A [`GameMode`] is a self-contained state machine fed the same sensor
+//! readings as a chess game and producing the same [`BoardFeedback`], so
+//! mini-games, drills, analysis and replays share the sensor and display
+//! pipeline. [`crate::app::BoardApp`] runs any mode the same way, so adding
+//! one does not touch the main loop.
+
+use std::time::Duration;
+
+use shakmaty::uci::UciMove;
+use shakmaty::{Bitboard, ByColor, CastlingMode, Chess, Color, Move, Position};
+
+use crate::checkers::CheckersMode;
+use crate::feedback::{BoardFeedback, SquareFeedback};
+use crate::minigames::MiniGame;
+use crate::player::{HumanPlayer, Player};
+use crate::session::GameSession;
+use crate::setup::SetupGuide;
+
+/// Whether a mode wants to keep running after a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModeStatus {
+    Running,
+    /// The activity is over (solved, completed, or stuck).
+    Finished,
+}
+
+/// An activity driven by board sensor readings.
+pub trait GameMode {
+    /// Short name for logs.
+    fn name(&self) -> &'static str;
+
+    /// Advance with the current per-color piece positions.
+    fn tick(&mut self, positions: ByColor<Bitboard>, now: Duration) -> ModeStatus;
+
+    /// What the board should show after the last tick.
+    fn feedback(&self) -> BoardFeedback;
+}
+
+/// A mode a client can start from the idle board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModeSelection {
+    MiniGame(MiniGame),
+    /// Free play from the starting position, both sides moved by hand.
+    Analysis,
+    Checkers,
+    /// The last finished game as a heat map (see [`crate::heatmap::HeatMapMode`]).
+    HeatMap,
+}
+
+/// What the board does when it powers up with no game to resume.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BootBehavior {
+    /// Wait for a client or the starting position.
+    #[default]
+    Idle,
+    /// Start the day's puzzle (see [`MiniGame::daily_puzzle`]), which
+    /// guides the pieces onto its layout.
+    DailyPuzzle,
+}
+
+impl BootBehavior {
+    /// The mode to start on `day`, if any.
+    pub fn selection(self, day: u64) -> Option<ModeSelection> {
+        match self {
+            BootBehavior::Idle => None,
+            BootBehavior::DailyPuzzle => Some(ModeSelection::MiniGame(MiniGame::daily_puzzle(day))),
+        }
+    }
+}
+
+impl ModeSelection {
+    /// Build a fresh mode, or `None` if the selection does not exist
+    /// (e.g. an unknown puzzle index).
+    ///
+    /// [`ModeSelection::HeatMap`] needs the last game, so
+    /// [`crate::app::BoardApp`] builds it; this returns `None` for it.
+    pub fn create(self, seed: u32) -> Option<Box<dyn GameMode>> {
+        match self {
+            ModeSelection::MiniGame(game) => game.create(seed),
+            ModeSelection::Analysis => Some(Box::new(ChessMode::analysis())),
+            ModeSelection::Checkers => Some(Box::new(CheckersMode::new())),
+            ModeSelection::HeatMap => None,
+        }
+    }
+}
+
+/// Standard chess as a [`GameMode`]: set up the starting position, then
+/// play with legal-move guidance until the game ends.
+///
+/// Sides without an opponent are played on the board. Games with remote
+/// players run through [`crate::app::BoardApp`]'s game lifecycle instead,
+/// since they need client plumbing this trait does not carry.
+pub struct ChessMode {
+    name: &'static str,
+    start: Chess,
+    /// Non-board players, handed to the session once the board is set up.
+    opponents: ByColor<Option<Box<dyn Player>>>,
+    session: Option<GameSession>,
+    setup: SetupGuide,
+    feedback: BoardFeedback,
+}
+
+impl ChessMode {
+    /// Both sides played on the board, starting from the standard position.
+    pub fn analysis() -> Self {
+        Self::from_position(Chess::default())
+    }
+
+    /// Both sides played on the board from `start` (e.g. a loaded FEN),
+    /// set up step by step (see [`SetupGuide`]).
+    pub fn from_position(start: Chess) -> Self {
+        Self {
+            name: "analysis",
+            setup: SetupGuide::new(start.board()),
+            start,
+            opponents: ByColor::default(),
+            session: None,
+            feedback: BoardFeedback::new(),
+        }
+    }
+
+    /// `opponent` plays `color`; the other side is played on the board.
+    pub fn against(color: Color, opponent: Box<dyn Player>) -> Self {
+        let mut mode = Self::analysis();
+        mode.name = "versus";
+        mode.opponents[color] = Some(opponent);
+        mode
+    }
+
+    pub fn position(&self) -> &Chess {
+        self.session.as_ref().map_or(&self.start, |s| s.position())
+    }
+}
+
+impl GameMode for ChessMode {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn tick(&mut self, positions: ByColor<Bitboard>, _now: Duration) -> ModeStatus {
+        let Some(session) = &mut self.session else {
+            if let Some(fb) = self.setup.update(&positions) {
+                self.feedback = fb;
+                return ModeStatus::Running;
+            }
+            let mut player = |color: Color| -> Box<dyn Player> {
+                self.opponents[color]
+                    .take()
+                    .unwrap_or_else(|| Box::new(HumanPlayer::new(positions)))
+            };
+            let (white, black) = (player(Color::White), player(Color::Black));
+            let mut session = GameSession::builder()
+                .position(self.start.clone())
+                .build(white, black);
+            self.feedback = session.tick(positions).feedback;
+            self.session = Some(session);
+            return ModeStatus::Running;
+        };
+        self.feedback = session.tick(positions).feedback;
+        if session.is_game_over() {
+            ModeStatus::Finished
+        } else {
+            ModeStatus::Running
+        }
+    }
+
+    fn feedback(&self) -> BoardFeedback {
+        self.feedback.clone()
+    }
+}
+
+/// Replays a recorded game: the board shows each move in turn and
+/// advances once it has been made on the board.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    position: Chess,
+    moves: Vec<Move>,
+    next: usize,
+    started: bool,
+    setup: SetupGuide,
+    setup_feedback: BoardFeedback,
+}
+
+impl Replay {
+    /// Replay `moves` from `start`. Replay stops early at the first move
+    /// that is illegal in its position.
+    pub fn new(start: Chess, moves: Vec<Move>) -> Self {
+        Self {
+            setup: SetupGuide::new(start.board()),
+            position: start,
+            moves,
+            next: 0,
+            started: false,
+            setup_feedback: BoardFeedback::new(),
+        }
+    }
+
+    /// Moves replayed so far.
+    pub fn played(&self) -> usize {
+        self.next
+    }
+
+    fn next_move(&self) -> Option<&Move> {
+        self.moves
+            .get(self.next)
+            .filter(|mv| self.position.legal_moves().contains(mv))
+    }
+}
+
+impl GameMode for Replay {
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    fn tick(&mut self, positions: ByColor<Bitboard>, _now: Duration) -> ModeStatus {
+        if !self.started {
+            self.started = positions == board_positions(&self.position);
+            self.setup_feedback = self.setup.update(&positions).unwrap_or_default();
+            return ModeStatus::Running;
+        }
+        let Some(&mv) = self.next_move() else {
+            return ModeStatus::Finished;
+        };
+        let mut after = self.position.clone();
+        after.play_unchecked(mv);
+        if positions == board_positions(&after) {
+            self.position = after;
+            self.next += 1;
+            if self.next_move().is_none() {
+                log::info!("Replay finished after {} moves", self.next);
+                return ModeStatus::Finished;
+            }
+        }
+        ModeStatus::Running
+    }
+
+    fn feedback(&self) -> BoardFeedback {
+        if !self.started {
+            return self.setup_feedback.clone();
+        }
+        let mut fb = BoardFeedback::new();
+        let Some(mv) = self.next_move() else {
+            return fb;
+        };
+        if let UciMove::Normal { from, to, .. } = mv.to_uci(CastlingMode::Standard) {
+            fb.set(from, SquareFeedback::Origin);
+            let kind = if mv.is_capture() {
+                SquareFeedback::Capture
+            } else {
+                SquareFeedback::Destination
+            };
+            fb.set(to, kind);
+        }
+        fb
+    }
+}
+
+fn board_positions(position: &Chess) -> ByColor<Bitboard> {
+    let board = position.board();
+    ByColor {
+        white: board.by_color(Color::White),
+        black: board.by_color(Color::Black),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::Square;
+
+    fn moves(position: &Chess, ucis: &[&str]) -> Vec<Move> {
+        let mut pos = position.clone();
+        ucis.iter()
+            .map(|uci| {
+                let mv = uci.parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+                pos.play_unchecked(mv);
+                mv
+            })
+            .collect()
+    }
+
+    fn after(position: &Chess, ucis: &[&str]) -> ByColor<Bitboard> {
+        let mut pos = position.clone();
+        for mv in moves(position, ucis) {
+            pos.play_unchecked(mv);
+        }
+        board_positions(&pos)
+    }
+
+    #[test]
+    fn replay_waits_for_setup_then_shows_next_move() {
+        let start = Chess::default();
+        let mut replay = Replay::new(start.clone(), moves(&start, &["e2e4", "e7e5"]));
+
+        replay.tick(ByColor::default(), Duration::ZERO);
+        assert_eq!(
+            replay.feedback().get(Square::E2),
+            Some(SquareFeedback::Destination),
+            "setup feedback first"
+        );
+
+        replay.tick(board_positions(&start), Duration::ZERO);
+        let fb = replay.feedback();
+        assert_eq!(fb.get(Square::E2), Some(SquareFeedback::Origin));
+        assert_eq!(fb.get(Square::E4), Some(SquareFeedback::Destination));
+    }
+
+    #[test]
+    fn replay_advances_on_each_move_and_finishes() {
+        let start = Chess::default();
+        let mut replay = Replay::new(start.clone(), moves(&start, &["e2e4", "e7e5"]));
+        replay.tick(board_positions(&start), Duration::ZERO);
+
+        let status = replay.tick(after(&start, &["e2e4"]), Duration::ZERO);
+        assert_eq!(status, ModeStatus::Running);
+        assert_eq!(replay.played(), 1);
+        assert_eq!(
+            replay.feedback().get(Square::E7),
+            Some(SquareFeedback::Origin)
+        );
+
+        let status = replay.tick(after(&start, &["e2e4", "e7e5"]), Duration::ZERO);
+        assert_eq!(status, ModeStatus::Finished);
+    }
+
+    #[test]
+    fn replay_ignores_other_moves() {
+        let start = Chess::default();
+        let mut replay = Replay::new(start.clone(), moves(&start, &["e2e4"]));
+        replay.tick(board_positions(&start), Duration::ZERO);
+
+        replay.tick(after(&start, &["d2d4"]), Duration::ZERO);
+
+        assert_eq!(replay.played(), 0);
+    }
+
+    #[test]
+    fn chess_mode_plays_against_any_opponent() {
+        use crate::testutil::ScriptedPlayer;
+        let start = Chess::default();
+        let mut after_e4 = start.clone();
+        after_e4.play_unchecked(moves(&start, &["e2e4"])[0]);
+        let reply = moves(&after_e4, &["e7e5"]);
+        let mut mode = ChessMode::against(Color::Black, Box::new(ScriptedPlayer::new(reply)));
+        mode.tick(board_positions(&start), Duration::ZERO);
+
+        mode.tick(after(&start, &["e2e4"]), Duration::ZERO);
+        mode.tick(after(&start, &["e2e4"]), Duration::ZERO);
+
+        assert_eq!(mode.name(), "versus");
+        assert_eq!(mode.position().turn(), Color::White);
+        assert_eq!(
+            mode.feedback().get(Square::E5),
+            Some(SquareFeedback::Destination),
+            "reply shown for the player to make on the board"
+        );
+    }
+
+    #[test]
+    fn analysis_plays_both_sides_on_the_board() {
+        let mut mode = ChessMode::analysis();
+        let start = Chess::default();
+        mode.tick(board_positions(&start), Duration::ZERO);
+
+        mode.tick(after(&start, &["e2e4"]), Duration::ZERO);
+        mode.tick(after(&start, &["e2e4", "e7e5"]), Duration::ZERO);
+
+        assert_eq!(mode.position().turn(), Color::White);
+        assert_eq!(mode.position().fullmoves().get(), 2);
+    }
+
+    #[test]
+    fn replay_from_a_midgame_position_is_set_up_from_the_starting_one() {
+        let start = Chess::default();
+        let midgame = {
+            let mut pos = start.clone();
+            for mv in moves(&start, &["e2e4", "e7e5"]) {
+                pos.play_unchecked(mv);
+            }
+            pos
+        };
+        let mut replay = Replay::new(midgame.clone(), moves(&midgame, &["g1f3"]));
+
+        replay.tick(board_positions(&start), Duration::ZERO);
+        let fb = replay.feedback();
+        assert_eq!(fb.squares().count(), 2, "one piece at a time");
+        assert_eq!(fb.get(Square::E2), Some(SquareFeedback::Origin));
+        assert_eq!(fb.get(Square::E4), Some(SquareFeedback::Destination));
+
+        replay.tick(after(&start, &["e2e4"]), Duration::ZERO);
+        assert_eq!(
+            replay.feedback().get(Square::E7),
+            Some(SquareFeedback::Origin)
+        );
+
+        replay.tick(board_positions(&midgame), Duration::ZERO);
+        assert_eq!(
+            replay.feedback().get(Square::G1),
+            Some(SquareFeedback::Origin),
+            "set up: replay starts"
+        );
+    }
+}
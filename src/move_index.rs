@@ -0,0 +1,199 @@
+//! Legal moves indexed by square.
+//!
+//! Feedback looks moves up by the square a piece was lifted from or the
+//! square of a captured piece, several times per frame. [`MoveIndex`]
+//! generates the legal moves once and buckets them by both squares, so
+//! each lookup is a slice instead of a scan over every legal move.
+//!
+//! Drops ([`Move::Put`], a piece placed from the hand) have no origin and
+//! capture nothing; they are kept in a bucket of their own after the last
+//! square.
+//!
+//! The buckets live in fixed-capacity [`MoveList`]s and are filled by a
+//! counting sort, so building an index never touches the heap: feedback is
+//! computed every tick on the board.
+
+use shakmaty::{Bitboard, Chess, Move, MoveList, Position, Square};
+
+/// Bucket of the drops in `by_origin`.
+const DROPS: usize = 64;
+
+/// A position's legal moves, bucketed by origin and by captured square.
+#[derive(Debug, Clone)]
+pub struct MoveIndex {
+    /// Legal moves grouped by origin square, in generation order within
+    /// each square, then the drops.
+    by_origin: MoveList,
+    /// `by_origin[origin_start[sq]..origin_start[sq + 1]]` leave `sq`;
+    /// bucket [`DROPS`] holds the drops.
+    origin_start: [u16; 66],
+    /// Captures grouped by the square of the captured piece.
+    by_captured: MoveList,
+    captured_start: [u16; 65],
+}
+
+impl MoveIndex {
+    pub fn new(position: &Chess) -> Self {
+        Self::from_moves(&position.legal_moves())
+    }
+
+    /// Index an already generated list of legal moves (at most a
+    /// [`MoveList`] full, as any position's legal moves are).
+    pub fn from_moves(moves: &[Move]) -> Self {
+        let (by_origin, origin_start) = bucket(
+            moves
+                .iter()
+                .map(|mv| (mv.from().map_or(DROPS, |sq| sq as usize), *mv)),
+        );
+        let (by_captured, captured_start) = bucket(
+            moves
+                .iter()
+                .filter_map(|mv| Some((captured_square(mv)? as usize, *mv))),
+        );
+        Self {
+            by_origin,
+            origin_start,
+            by_captured,
+            captured_start,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_origin.is_empty()
+    }
+
+    /// Every legal move except drops, grouped by origin square.
+    pub fn all(&self) -> &[Move] {
+        &self.by_origin[..usize::from(self.origin_start[DROPS])]
+    }
+
+    /// Legal moves of the piece on `square`, including castling for a king.
+    pub fn moves_from(&self, square: Square) -> &[Move] {
+        slice(&self.by_origin, &self.origin_start, square as usize)
+    }
+
+    /// Legal moves that capture the piece on `square` (en passant captures
+    /// the pawn beside the destination).
+    pub fn moves_capturing(&self, square: Square) -> &[Move] {
+        slice(&self.by_captured, &self.captured_start, square as usize)
+    }
+
+    /// Legal drops of any piece in hand.
+    pub fn drops(&self) -> &[Move] {
+        slice(&self.by_origin, &self.origin_start, DROPS)
+    }
+
+    /// Squares some piece in hand can be dropped on.
+    pub fn drop_squares(&self) -> Bitboard {
+        self.drops().iter().map(|mv| mv.to()).collect()
+    }
+}
+
+/// The square of the piece `mv` captures, if any.
+pub fn captured_square(mv: &Move) -> Option<Square> {
+    match *mv {
+        Move::Normal {
+            capture: Some(_),
+            to,
+            ..
+        } => Some(to),
+        Move::EnPassant { from, to } => Some(Square::from_coords(to.file(), from.rank())),
+        _ => None,
+    }
+}
+
+/// Group `entries` by bucket with a stable counting sort, returning the
+/// moves and the start offset of each bucket (`N` is one more than the
+/// number of buckets).
+fn bucket<const N: usize>(
+    entries: impl Iterator<Item = (usize, Move)> + Clone,
+) -> (MoveList, [u16; N]) {
+    let mut start = [0u16; N];
+    let mut moves = MoveList::new();
+    for (i, mv) in entries.clone() {
+        start[i + 1] += 1;
+        moves.push(mv);
+    }
+    for i in 1..start.len() {
+        start[i] += start[i - 1];
+    }
+    let mut next = start;
+    for (i, mv) in entries {
+        moves[usize::from(next[i])] = mv;
+        next[i] += 1;
+    }
+    (moves, start)
+}
+
+fn slice<'a, const N: usize>(moves: &'a [Move], start: &[u16; N], bucket: usize) -> &'a [Move] {
+    &moves[usize::from(start[bucket])..usize::from(start[bucket + 1])]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::fen::Fen;
+    use shakmaty::{CastlingMode, Role};
+
+    fn position_from_fen(fen: &str) -> Chess {
+        fen.parse::<Fen>()
+            .expect("invalid FEN")
+            .into_position(CastlingMode::Standard)
+            .expect("invalid position")
+    }
+
+    #[test]
+    fn moves_are_bucketed_by_origin() {
+        let index = MoveIndex::new(&Chess::default());
+
+        assert_eq!(index.all().len(), 20);
+        assert_eq!(index.moves_from(Square::G1).len(), 2);
+        assert!(
+            index
+                .moves_from(Square::E2)
+                .iter()
+                .all(|mv| mv.from() == Some(Square::E2))
+        );
+        assert!(index.moves_from(Square::E4).is_empty());
+    }
+
+    #[test]
+    fn captures_are_bucketed_by_captured_piece() {
+        // White pawn on e5 can take d5 en passant; the knight on c3 can take d5 too.
+        let position =
+            position_from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/2N5/PPPP1PPP/R1BQKBNR w KQkq d6 0 1");
+        let index = MoveIndex::new(&position);
+
+        let captures = index.moves_capturing(Square::D5);
+
+        assert_eq!(captures.len(), 2);
+        assert!(
+            captures
+                .iter()
+                .any(|mv| matches!(mv, Move::EnPassant { .. }))
+        );
+        assert!(captures.iter().any(|mv| mv.role() == Role::Knight));
+    }
+
+    #[test]
+    fn drops_are_kept_apart() {
+        let knight_drop = Move::Put {
+            role: Role::Knight,
+            to: Square::E4,
+        };
+        let pawn_drop = Move::Put {
+            role: Role::Pawn,
+            to: Square::D3,
+        };
+        let index = MoveIndex::from_moves(&[knight_drop, pawn_drop]);
+
+        assert!(!index.is_empty());
+        assert!(index.all().is_empty());
+        assert_eq!(index.drops(), [knight_drop, pawn_drop]);
+        assert_eq!(
+            index.drop_squares(),
+            Bitboard::from_iter([Square::E4, Square::D3])
+        );
+        assert!(index.moves_capturing(Square::E4).is_empty());
+    }
+}
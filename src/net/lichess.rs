@@ -0,0 +1,825 @@
+//! A live Lichess game played on the board through the Lichess Board API.
+//!
+//! [`Lichess::find_game`] seeks a game or accepts a challenge, then follows
+//! it and returns a [`LichessGame`]. That is the [`Player`] for the online
+//! opponent: it reads their moves from the game stream and the session
+//! lights them as guidance, since it is not interactive. It POSTs the moves
+//! detected on the board to Lichess as they are played.
+//!
+//! All requests run on worker threads, so the board keeps ticking. If WiFi
+//! drops, the stream is reopened with growing delays (see [`Pacing`]). The
+//! `gameFull` line that opens every game stream replays the whole move
+//! list, so moves are read by ply and a reconnect loses nothing. Seeks,
+//! challenge accepts and moves are POSTed one at a time through a
+//! [`RequestScheduler`], which retries one that fails on the network the
+//! same way. Only a rejected token, a refused request or a server certificate
+//! that does not verify ends the game with [`PlayerStatus::Error`]; the
+//! board shows which certificate problem it was (see [`crate::tls`]).
+//!
+//! Only standard chess from the starting position is played.
+
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+
+use shakmaty::uci::UciMove;
+use shakmaty::{Bitboard, ByColor, CastlingMode, Chess, Color, Move, Position};
+
+use super::json::Json;
+use super::{jitter_seed, send_scheduled};
+use crate::feedback::BoardFeedback;
+use crate::player::{GameAction, Player, PlayerStatus};
+use crate::scheduler::{Outcome, Pacing, RequestScheduler};
+use crate::tls::CertificateError;
+
+/// Longest token a [`TokenStore`] keeps; Lichess personal tokens are
+/// well under this.
+pub const MAX_TOKEN_LEN: usize = 128;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LichessError {
+    #[error("no Lichess token stored")]
+    NoToken,
+    #[error("Lichess I/O: {0}")]
+    Io(#[from] io::Error),
+    #[error("Lichess unreachable: {0}")]
+    Certificate(#[from] CertificateError),
+    #[error("Lichess rejected the API token")]
+    Unauthorized,
+    #[error("Lichess answered HTTP {0}")]
+    Status(u16),
+    #[error("Lichess sent an illegal move: {0}")]
+    IllegalMove(String),
+}
+
+impl LichessError {
+    /// Whether the request may succeed if sent again, e.g. after WiFi
+    /// reconnects or while Lichess is overloaded.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            LichessError::Io(_) => true,
+            LichessError::Status(code) => *code == 429 || *code >= 500,
+            _ => false,
+        }
+    }
+}
+
+/// Keeps the personal API token (with the `board:play` scope) between
+/// boots.
+pub trait TokenStore {
+    fn load(&self) -> Option<String>;
+    fn save(&mut self, token: &str) -> io::Result<()>;
+}
+
+/// HTTP access to `https://lichess.org`.
+///
+/// Paths start with `/api/`. Every request carries the token as
+/// `Authorization: Bearer …`. A 401 answer is
+/// [`LichessError::Unauthorized`], and any other status outside 2xx is
+/// [`LichessError::Status`].
+pub trait LichessTransport: Clone + Send + 'static {
+    /// GET `path` and return its body, newline-delimited JSON, to read as
+    /// it arrives.
+    fn stream(&self, path: &str, token: &str) -> Result<Box<dyn BufRead + Send>, LichessError>;
+
+    /// POST the urlencoded `form` to `path` and wait until the response
+    /// has been read to the end.
+    fn post(&self, path: &str, token: &str, form: &str) -> Result<(), LichessError>;
+}
+
+/// How an opponent is found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Matchmaking {
+    /// Seek a game against anyone, with `minutes` plus `increment`
+    /// seconds a move.
+    Seek {
+        minutes: u32,
+        increment: u32,
+        rated: bool,
+    },
+    /// Accept the first standard chess challenge sent to the account.
+    AcceptChallenge,
+}
+
+/// A Lichess account to play from.
+#[derive(Debug, Clone)]
+pub struct Lichess<T> {
+    transport: T,
+    token: String,
+    pacing: Pacing,
+}
+
+impl<T: LichessTransport> Lichess<T> {
+    pub fn new(transport: T, token: impl Into<String>) -> Self {
+        Self {
+            transport,
+            token: token.into(),
+            pacing: Pacing::default(),
+        }
+    }
+
+    /// Play with the token kept in `store`.
+    pub fn from_store(transport: T, store: &impl TokenStore) -> Result<Self, LichessError> {
+        let token = store.load().ok_or(LichessError::NoToken)?;
+        Ok(Self::new(transport, token))
+    }
+
+    /// Space requests and retries by `pacing` instead of
+    /// [`Pacing::default`].
+    pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+        self.pacing = pacing;
+        self
+    }
+
+    /// Look for a game in the background. The returned player is the
+    /// opponent once the game starts; see [`LichessGame::local_color`].
+    pub fn find_game(&self, matchmaking: Matchmaking) -> LichessGame {
+        let (update_tx, updates) = mpsc::channel();
+        let (outgoing, outgoing_rx) = mpsc::channel();
+        let flags = Arc::new(Flags::default());
+        let worker = Worker {
+            api: self.clone(),
+            updates: update_tx,
+            flags: flags.clone(),
+        };
+        let poster = worker.clone();
+        let requests = outgoing.clone();
+        thread::spawn(move || worker.follow(matchmaking, &requests));
+        thread::spawn(move || poster.post_requests(&outgoing_rx));
+        LichessGame {
+            updates,
+            outgoing,
+            flags,
+            game_id: None,
+            local_color: None,
+            moves: Vec::new(),
+        }
+    }
+}
+
+/// What the workers report to the player.
+#[derive(Debug)]
+enum Update {
+    Started {
+        id: String,
+        color: Color,
+    },
+    /// Every move of the game so far.
+    Moves(Vec<UciMove>),
+}
+
+/// Raised by either side; the session reads the player's status without
+/// polling it, so these are not sent as updates.
+#[derive(Debug, Default)]
+struct Flags {
+    /// The game has started; a seek still queued is dropped.
+    started: AtomicBool,
+    /// Lichess ended the game.
+    over: AtomicBool,
+    failed: AtomicBool,
+    /// Why the server could not be reached securely, if that ended the
+    /// game.
+    certificate: OnceLock<CertificateError>,
+    /// The player is gone and the workers should stop.
+    dropped: AtomicBool,
+}
+
+impl Flags {
+    fn fail(&self, error: &LichessError) {
+        log::error!("Lichess game failed: {error}");
+        if let LichessError::Certificate(e) = error {
+            let _ = self.certificate.set(*e);
+        }
+        self.failed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A request on behalf of the board's side.
+#[derive(Debug)]
+enum Outgoing {
+    /// Seek a game with these form fields, kept open until one starts.
+    Seek(String),
+    AcceptChallenge(String),
+    Move {
+        game: String,
+        uci: String,
+    },
+    Resign {
+        game: String,
+    },
+}
+
+impl Outgoing {
+    /// Path and form of the POST.
+    fn request(&self) -> (String, &str) {
+        match self {
+            Outgoing::Seek(form) => ("/api/board/seek".to_string(), form),
+            Outgoing::AcceptChallenge(id) => (format!("/api/challenge/{id}/accept"), ""),
+            Outgoing::Move { game, uci } => (format!("/api/board/game/{game}/move/{uci}"), ""),
+            Outgoing::Resign { game } => (format!("/api/board/game/{game}/resign"), ""),
+        }
+    }
+}
+
+/// The online opponent in a Lichess game.
+///
+/// Until the game starts it never moves. Its status is
+/// [`PlayerStatus::GameOver`] once Lichess ends the game, and
+/// [`PlayerStatus::Error`] if the account cannot play.
+#[derive(Debug)]
+pub struct LichessGame {
+    updates: Receiver<Update>,
+    outgoing: Sender<Outgoing>,
+    flags: Arc<Flags>,
+    game_id: Option<String>,
+    local_color: Option<Color>,
+    moves: Vec<UciMove>,
+}
+
+impl LichessGame {
+    /// The color played on the board, once the game has started. The
+    /// opponent has the other one.
+    pub fn local_color(&mut self) -> Option<Color> {
+        self.receive();
+        self.local_color
+    }
+
+    /// The Lichess game ID, once the game has started.
+    pub fn game_id(&self) -> Option<&str> {
+        self.game_id.as_deref()
+    }
+
+    fn receive(&mut self) {
+        while let Ok(update) = self.updates.try_recv() {
+            match update {
+                Update::Started { id, color } => {
+                    log::info!("Lichess game {id} started, playing {color}");
+                    self.game_id = Some(id);
+                    self.local_color = Some(color);
+                }
+                Update::Moves(moves) => self.moves = moves,
+            }
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.flags.over.load(Ordering::Relaxed)
+    }
+
+    fn has_failed(&self) -> bool {
+        self.flags.failed.load(Ordering::Relaxed)
+    }
+
+    /// Send the request `for_game` makes for the current game.
+    fn send(&mut self, for_game: impl FnOnce(String) -> Outgoing) {
+        let Some(id) = self.game_id.clone() else {
+            log::warn!("No Lichess game to send to");
+            return;
+        };
+        let _ = self.outgoing.send(for_game(id));
+    }
+}
+
+impl Drop for LichessGame {
+    fn drop(&mut self) {
+        self.flags.dropped.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Player for LichessGame {
+    fn poll_move(&mut self, position: &Chess, _sensors: ByColor<Bitboard>) -> Option<Move> {
+        self.receive();
+        if self.is_over() || self.has_failed() {
+            return None;
+        }
+        let uci = self.moves.get(ply(position))?;
+        match uci.to_move(position) {
+            Ok(mv) => Some(mv),
+            Err(_) => {
+                self.flags.fail(&LichessError::IllegalMove(uci.to_string()));
+                None
+            }
+        }
+    }
+
+    fn opponent_moved(&mut self, _position: &Chess, opponent_move: &Move) {
+        let uci = opponent_move.to_uci(CastlingMode::Standard);
+        self.send(|game| Outgoing::Move {
+            game,
+            uci: uci.to_string(),
+        });
+    }
+
+    fn status(&self) -> PlayerStatus {
+        if self.has_failed() {
+            PlayerStatus::Error
+        } else if self.is_over() {
+            PlayerStatus::GameOver
+        } else {
+            PlayerStatus::Active
+        }
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+
+    fn allows_takeback(&self) -> bool {
+        false
+    }
+
+    fn error_feedback(&self) -> Option<BoardFeedback> {
+        self.flags.certificate.get().map(CertificateError::feedback)
+    }
+
+    fn notify(&mut self, action: &GameAction) {
+        if let GameAction::Resign(color) = action
+            && Some(*color) == self.local_color
+        {
+            self.send(|game| Outgoing::Resign { game });
+        }
+    }
+}
+
+/// Moves played since the starting position.
+fn ply(position: &Chess) -> usize {
+    let full = position.fullmoves().get() as usize - 1;
+    full * 2 + usize::from(position.turn() == Color::Black)
+}
+
+/// One of the background threads of a [`LichessGame`].
+#[derive(Debug, Clone)]
+struct Worker<T> {
+    api: Lichess<T>,
+    updates: Sender<Update>,
+    flags: Arc<Flags>,
+}
+
+impl<T: LichessTransport> Worker<T> {
+    fn stopped(&self) -> bool {
+        self.flags.dropped.load(Ordering::Relaxed)
+    }
+
+    fn stream(&self, path: &str) -> Result<Box<dyn BufRead + Send>, LichessError> {
+        self.api.transport.stream(path, &self.api.token)
+    }
+
+    fn post(&self, path: &str, form: &str) -> Result<(), LichessError> {
+        self.api.transport.post(path, &self.api.token, form)
+    }
+
+    /// Find the game, then follow it to the end. Seeks and challenge
+    /// accepts go to the poster through `requests`.
+    fn follow(self, matchmaking: Matchmaking, requests: &Sender<Outgoing>) {
+        if let Matchmaking::Seek {
+            minutes,
+            increment,
+            rated,
+        } = matchmaking
+        {
+            let form = format!(
+                "rated={rated}&time={minutes}&increment={increment}&variant=standard&color=random"
+            );
+            let _ = requests.send(Outgoing::Seek(form));
+        }
+        let result = self
+            .await_start(matchmaking, requests)
+            .and_then(|(id, color)| {
+                self.flags.started.store(true, Ordering::Relaxed);
+                let _ = self.updates.send(Update::Started {
+                    id: id.clone(),
+                    color,
+                });
+                self.follow_game(&id)
+            });
+        if let Err(e) = result
+            && !self.stopped()
+        {
+            self.flags.fail(&e);
+        }
+    }
+
+    /// Read the account's event stream until a game starts, accepting
+    /// challenges if asked to. Returns the game ID and the board's color.
+    fn await_start(
+        &self,
+        matchmaking: Matchmaking,
+        requests: &Sender<Outgoing>,
+    ) -> Result<(String, Color), LichessError> {
+        self.read_stream("/api/stream/event", |event| {
+            match event.str_at(&["type"])? {
+                "challenge" if matchmaking == Matchmaking::AcceptChallenge => {
+                    let challenge = event.get("challenge")?;
+                    let id = challenge.str_at(&["id"])?;
+                    if challenge.str_at(&["variant", "key"]) != Some("standard") {
+                        log::info!("Ignoring Lichess challenge {id}: not standard chess");
+                        return None;
+                    }
+                    let _ = requests.send(Outgoing::AcceptChallenge(id.to_string()));
+                    None
+                }
+                "gameStart" => {
+                    let game = event.get("game")?;
+                    let color = match game.str_at(&["color"])? {
+                        "white" => Color::White,
+                        "black" => Color::Black,
+                        _ => return None,
+                    };
+                    Some(Ok((game.str_at(&["gameId"])?.to_string(), color)))
+                }
+                _ => None,
+            }
+        })
+    }
+
+    /// Pass the game's moves on until it ends.
+    fn follow_game(&self, id: &str) -> Result<(), LichessError> {
+        self.read_stream(&format!("/api/board/game/stream/{id}"), |event| {
+            let state = match event.str_at(&["type"])? {
+                "gameFull" => event.get("state")?,
+                "gameState" => event,
+                _ => return None,
+            };
+            let moves = state
+                .str_at(&["moves"])?
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<UciMove>, _>>()
+                .ok()?;
+            if self.updates.send(Update::Moves(moves)).is_err() {
+                return Some(Ok(()));
+            }
+            match state.str_at(&["status"]) {
+                Some("created" | "started") | None => None,
+                Some(status) => {
+                    log::info!("Lichess game {id} ended: {status}");
+                    self.flags.over.store(true, Ordering::Relaxed);
+                    Some(Ok(()))
+                }
+            }
+        })
+    }
+
+    /// Open the NDJSON stream at `path` and hand each line to `handle`
+    /// until it returns a result. A stream that fails or ends is reopened
+    /// after a delay. Once the player is dropped, reading stops with an
+    /// interrupted [`LichessError::Io`].
+    fn read_stream<R>(
+        &self,
+        path: &str,
+        mut handle: impl FnMut(&Json) -> Option<Result<R, LichessError>>,
+    ) -> Result<R, LichessError> {
+        let mut backoff = self.api.pacing.backoff(jitter_seed());
+        loop {
+            if self.stopped() {
+                return Err(LichessError::Io(io::ErrorKind::Interrupted.into()));
+            }
+            let reader = match self.stream(path) {
+                Ok(reader) => reader,
+                // Lichess asks for a full minute's pause after a 429.
+                Err(LichessError::Status(429)) => {
+                    log::warn!("Lichess stream {path} rate limited");
+                    thread::sleep(self.api.pacing.rate_limit_pause);
+                    continue;
+                }
+                Err(e) if e.is_transient() => {
+                    log::warn!("Lichess stream {path} unavailable: {e}");
+                    backoff.wait();
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            backoff.reset();
+            for line in reader.lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        log::warn!("Lichess stream {path} dropped: {e}");
+                        break;
+                    }
+                };
+                if self.stopped() {
+                    return Err(LichessError::Io(io::ErrorKind::Interrupted.into()));
+                }
+                // Blank lines are keep-alives.
+                let Some(event) = Json::parse(&line) else {
+                    continue;
+                };
+                if let Some(result) = handle(&event) {
+                    return result;
+                }
+            }
+            backoff.wait();
+        }
+    }
+
+    /// Send the board's requests in order, each until Lichess has it.
+    fn post_requests(self, requests: &Receiver<Outgoing>) {
+        let scheduler = RequestScheduler::new(self.api.pacing, jitter_seed());
+        send_scheduled(requests, scheduler, |request, retry| {
+            self.post_request(request, retry)
+        });
+    }
+
+    /// One attempt at `request`; `retry` if an earlier one failed.
+    fn post_request(&self, request: &Outgoing, retry: bool) -> Outcome {
+        if let Outgoing::Seek(_) = request
+            && (self.flags.started.load(Ordering::Relaxed) || self.stopped())
+        {
+            return Outcome::Rejected;
+        }
+        let (path, form) = request.request();
+        match self.post(&path, form) {
+            // A seek returns once its game has started; Lichess drops it
+            // with the connection, so a failed one is sent again.
+            Ok(()) => Outcome::Sent,
+            Err(LichessError::Status(429)) if !self.stopped() => {
+                log::warn!("Lichess rate limited {request:?}, pausing");
+                Outcome::RateLimited
+            }
+            Err(e) if e.is_transient() && !self.stopped() => {
+                log::warn!("Lichess {request:?} not sent, retrying: {e}");
+                Outcome::Failed
+            }
+            // Our own challenges show up too; Lichess refuses to accept
+            // those.
+            Err(e) if matches!(request, Outgoing::AcceptChallenge(_)) => {
+                log::warn!("Could not accept Lichess challenge: {e}");
+                Outcome::Rejected
+            }
+            // The first attempt arrived before the connection broke.
+            Err(LichessError::Status(400)) if retry => Outcome::Sent,
+            Err(e) => {
+                self.flags.fail(&e);
+                Outcome::Rejected
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, VecDeque};
+    use std::io::Cursor;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    /// Scripted Lichess: each GET of a path returns the next queued body
+    /// or error, and POSTs are recorded and fail as queued for their path.
+    #[derive(Clone, Default)]
+    struct FakeLichess(Arc<Mutex<FakeState>>);
+
+    #[derive(Default)]
+    struct FakeState {
+        streams: HashMap<String, VecDeque<Result<String, LichessError>>>,
+        post_failures: VecDeque<(String, LichessError)>,
+        posts: Vec<String>,
+    }
+
+    impl FakeLichess {
+        fn queue(&self, path: &str, body: Result<&str, LichessError>) {
+            self.0
+                .lock()
+                .unwrap()
+                .streams
+                .entry(path.to_string())
+                .or_default()
+                .push_back(body.map(str::to_string));
+        }
+
+        fn fail_next_post(&self, path: &str, error: LichessError) {
+            let failure = (path.to_string(), error);
+            self.0.lock().unwrap().post_failures.push_back(failure);
+        }
+
+        fn posts(&self) -> Vec<String> {
+            self.0.lock().unwrap().posts.clone()
+        }
+    }
+
+    fn offline() -> LichessError {
+        LichessError::Io(io::ErrorKind::NotConnected.into())
+    }
+
+    impl LichessTransport for FakeLichess {
+        fn stream(
+            &self,
+            path: &str,
+            _token: &str,
+        ) -> Result<Box<dyn BufRead + Send>, LichessError> {
+            let next = self
+                .0
+                .lock()
+                .unwrap()
+                .streams
+                .get_mut(path)
+                .and_then(VecDeque::pop_front);
+            let body = next.unwrap_or_else(|| Err(offline()))?;
+            Ok(Box::new(Cursor::new(body.into_bytes())))
+        }
+
+        fn post(&self, path: &str, _token: &str, form: &str) -> Result<(), LichessError> {
+            let mut state = self.0.lock().unwrap();
+            state.posts.push(format!("{path}?{form}"));
+            match state.post_failures.front() {
+                Some((failing, _)) if failing == path => state
+                    .post_failures
+                    .pop_front()
+                    .map_or(Ok(()), |(_, e)| Err(e)),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    fn lichess(fake: &FakeLichess) -> Lichess<FakeLichess> {
+        Lichess::new(fake.clone(), "lip_token").with_pacing(Pacing {
+            min_interval: Duration::ZERO,
+            rate_limit_pause: Duration::from_millis(50),
+            retry_delay: Duration::from_millis(1),
+            max_retry_delay: Duration::from_millis(1),
+        })
+    }
+
+    /// Poll until `done` holds.
+    fn wait_for(game: &mut LichessGame, mut done: impl FnMut(&mut LichessGame) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done(game) {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn play(position: &Chess, uci: &str) -> (Chess, Move) {
+        let mv = uci.parse::<UciMove>().unwrap().to_move(position).unwrap();
+        (position.clone().play(mv).unwrap(), mv)
+    }
+
+    const GAME_START: &str = r#"{"type":"gameStart","game":{"gameId":"g1","color":"black"}}"#;
+
+    #[test]
+    fn accepted_challenge_streams_opponent_moves_and_posts_ours() {
+        let fake = FakeLichess::default();
+        fake.queue(
+            "/api/stream/event",
+            Ok(&format!(
+                "{}\n\n{GAME_START}\n",
+                r#"{"type":"challenge","challenge":{"id":"c1","variant":{"key":"standard"}}}"#
+            )),
+        );
+        fake.queue(
+            "/api/board/game/stream/g1",
+            Ok(concat!(
+                r#"{"type":"gameFull","state":{"moves":"","status":"started"}}"#,
+                "\n",
+                r#"{"type":"gameState","moves":"e2e4","status":"started"}"#,
+                "\n",
+            )),
+        );
+        let mut game = lichess(&fake).find_game(Matchmaking::AcceptChallenge);
+        let start = Chess::default();
+
+        wait_for(&mut game, |g| {
+            g.poll_move(&start, ByColor::default()).is_some()
+        });
+        assert_eq!(game.local_color(), Some(Color::Black));
+        assert_eq!(game.game_id(), Some("g1"));
+        assert!(!game.is_interactive());
+        let (after_e4, e4) = play(&start, "e2e4");
+        assert_eq!(game.poll_move(&start, ByColor::default()), Some(e4));
+        assert_eq!(game.poll_move(&after_e4, ByColor::default()), None);
+
+        let (after_e5, e5) = play(&after_e4, "e7e5");
+        game.opponent_moved(&after_e5, &e5);
+        game.notify(&GameAction::Resign(Color::Black));
+        wait_for(&mut game, |_| fake.posts().len() == 3);
+        assert_eq!(
+            fake.posts(),
+            [
+                "/api/challenge/c1/accept?",
+                "/api/board/game/g1/move/e7e5?",
+                "/api/board/game/g1/resign?",
+            ]
+        );
+    }
+
+    #[test]
+    fn dropped_connections_are_retried_without_losing_moves() {
+        let fake = FakeLichess::default();
+        fake.queue("/api/stream/event", Err(offline()));
+        fake.queue("/api/stream/event", Ok(GAME_START));
+        fake.queue("/api/board/game/stream/g1", Err(offline()));
+        // Cut off after the opening gameFull...
+        fake.queue(
+            "/api/board/game/stream/g1",
+            Ok(r#"{"type":"gameFull","state":{"moves":"","status":"started"}}"#),
+        );
+        // ...which the next gameFull replays, then the game ends.
+        fake.queue(
+            "/api/board/game/stream/g1",
+            Ok(concat!(
+                r#"{"type":"gameFull","state":{"moves":"e2e4 e7e5 d2d4","status":"started"}}"#,
+                "\n",
+                r#"{"type":"gameState","moves":"e2e4 e7e5 d2d4","status":"resign"}"#,
+            )),
+        );
+        let e5_path = "/api/board/game/g1/move/e7e5";
+        fake.fail_next_post(e5_path, offline());
+        fake.fail_next_post(e5_path, LichessError::Status(400));
+        let seek = Matchmaking::Seek {
+            minutes: 15,
+            increment: 10,
+            rated: false,
+        };
+        let mut game = lichess(&fake).find_game(seek);
+
+        wait_for(&mut game, |g| g.local_color().is_some());
+        let (after_e4, _) = play(&Chess::default(), "e2e4");
+        let (after_e5, e5) = play(&after_e4, "e7e5");
+        game.opponent_moved(&after_e5, &e5);
+        let move_posts = || {
+            let posts = fake.posts();
+            posts.iter().filter(|p| p.starts_with(e5_path)).count()
+        };
+        wait_for(&mut game, |_| move_posts() == 2);
+        wait_for(&mut game, |g| g.status() == PlayerStatus::GameOver);
+        assert_eq!(game.poll_move(&after_e5, ByColor::default()), None);
+
+        assert_eq!(
+            game.status(),
+            PlayerStatus::GameOver,
+            "the 400 was a resend"
+        );
+        let posts = fake.posts();
+        let seek = "/api/board/seek?rated=false&time=15&increment=10&variant=standard&color=random";
+        assert!(posts.iter().any(|p| p == seek), "{posts:?}");
+        assert_eq!(move_posts(), 2, "{posts:?}");
+    }
+
+    #[test]
+    fn rate_limited_moves_wait_for_the_pause() {
+        let fake = FakeLichess::default();
+        fake.queue("/api/stream/event", Ok(GAME_START));
+        fake.queue(
+            "/api/board/game/stream/g1",
+            Ok(r#"{"type":"gameFull","state":{"moves":"e2e4","status":"started"}}"#),
+        );
+        let e5_path = "/api/board/game/g1/move/e7e5";
+        fake.fail_next_post(e5_path, LichessError::Status(429));
+        let mut game = lichess(&fake).find_game(Matchmaking::AcceptChallenge);
+
+        wait_for(&mut game, |g| g.local_color().is_some());
+        let (after_e4, _) = play(&Chess::default(), "e2e4");
+        let (after_e5, e5) = play(&after_e4, "e7e5");
+        let sent = Instant::now();
+        game.opponent_moved(&after_e5, &e5);
+        wait_for(&mut game, |_| fake.posts().len() == 2);
+
+        assert!(sent.elapsed() >= Duration::from_millis(50));
+        assert_eq!(game.status(), PlayerStatus::Active);
+    }
+
+    #[test]
+    fn missing_or_rejected_token_is_an_error() {
+        struct NoToken;
+        impl TokenStore for NoToken {
+            fn load(&self) -> Option<String> {
+                None
+            }
+            fn save(&mut self, _token: &str) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let fake = FakeLichess::default();
+        assert!(matches!(
+            Lichess::from_store(fake.clone(), &NoToken),
+            Err(LichessError::NoToken)
+        ));
+
+        fake.queue("/api/stream/event", Err(LichessError::Unauthorized));
+        let mut game = lichess(&fake).find_game(Matchmaking::AcceptChallenge);
+        wait_for(&mut game, |g| {
+            g.poll_move(&Chess::default(), ByColor::default());
+            g.status() == PlayerStatus::Error
+        });
+        assert_eq!(game.error_feedback(), None);
+    }
+
+    #[test]
+    fn certificate_failure_is_shown_on_the_board() {
+        let fake = FakeLichess::default();
+        fake.queue(
+            "/api/stream/event",
+            Err(CertificateError::NotYetValid.into()),
+        );
+        let mut game = lichess(&fake).find_game(Matchmaking::AcceptChallenge);
+        wait_for(&mut game, |g| g.status() == PlayerStatus::Error);
+
+        assert_eq!(
+            game.error_feedback(),
+            Some(CertificateError::NotYetValid.feedback())
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Clients for online services, reached over WiFi.
+//!
+//! The protocol logic lives here and is platform-independent; each client
+//! talks HTTP through a small transport trait that `esp32` implements on
+//! top of the verified HTTPS connections of `esp32::tls`.
+//!
+//! Every POST a client makes goes through a
+//! [`crate::scheduler::RequestScheduler`] on the client's posting thread,
+//! so requests go out one at a time, spaced apart, and a failed one is
+//! retried after a [`Backoff`]. Long-lived streams are reopened after the
+//! same backoff.
+
+use std::fmt;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::rng::XorShift32;
+use crate::scheduler::{Outcome, RequestScheduler};
+
+mod json;
+pub mod lichess;
+pub mod relay;
+
+/// First delay before a failed request is retried; it doubles per failure.
+pub const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between retries while the network is down.
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Doubling delays between retries, from `first` up to `max`, with "equal
+/// jitter": half of each delay is fixed and half random, so boards that
+/// lost the same server do not all come back at once.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    first: Duration,
+    max: Duration,
+    /// Failures since the last [`Self::reset`].
+    failures: u32,
+    rng: XorShift32,
+}
+
+impl Backoff {
+    /// `seed` drives the jitter.
+    pub fn new(first: Duration, max: Duration, seed: u32) -> Self {
+        Self {
+            first,
+            max: max.max(first),
+            failures: 0,
+            rng: XorShift32::new(seed),
+        }
+    }
+
+    /// The delay before the next retry; each call doubles the one after.
+    pub fn next_delay(&mut self) -> Duration {
+        let full = self
+            .first
+            .saturating_mul(1 << self.failures.min(16))
+            .min(self.max);
+        self.failures += 1;
+        let half = full / 2;
+        let jitter_ms = self.rng.below(
+            u32::try_from(half.as_millis())
+                .unwrap_or(u32::MAX)
+                .saturating_add(1),
+        );
+        half + Duration::from_millis(u64::from(jitter_ms))
+    }
+
+    /// Sleep for [`Self::next_delay`].
+    pub fn wait(&mut self) {
+        thread::sleep(self.next_delay());
+    }
+
+    /// Start again from `first` after a success.
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+}
+
+/// A jitter seed that differs between boards and boots.
+fn jitter_seed() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.subsec_nanos())
+}
+
+/// Send the requests arriving on `requests` as `scheduler` allows, until
+/// the channel closes and the queue is empty.
+///
+/// `send` makes one attempt at a request; its second argument says whether
+/// the request failed before.
+fn send_scheduled<R: fmt::Debug>(
+    requests: &Receiver<R>,
+    mut scheduler: RequestScheduler<R>,
+    mut send: impl FnMut(&R, bool) -> Outcome,
+) {
+    let clock = Instant::now();
+    scheduler.set_online(true, Duration::ZERO);
+    let mut open = true;
+    let mut retry = false;
+    loop {
+        let received = match (scheduler.ready_in(clock.elapsed()), open) {
+            (None, false) => return,
+            (None, true) => requests.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            (Some(delay), true) => requests.recv_timeout(delay),
+            (Some(delay), false) => {
+                thread::sleep(delay);
+                Err(RecvTimeoutError::Timeout)
+            }
+        };
+        match received {
+            Ok(request) => {
+                if let Err(request) = scheduler.push(request) {
+                    log::warn!("Request queue full, dropping {request:?}");
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => open = false,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        let Some(request) = scheduler.next(clock.elapsed()) else {
+            continue;
+        };
+        let outcome = send(request, retry);
+        retry = matches!(outcome, Outcome::Failed | Outcome::RateLimited);
+        scheduler.complete(outcome, clock.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_with_jitter_up_to_the_cap() {
+        let first = Duration::from_secs(2);
+        let max = Duration::from_secs(300);
+        let mut backoff = Backoff::new(first, max, 1);
+        let waits: Vec<Duration> = (0..12).map(|_| backoff.next_delay()).collect();
+
+        assert!(waits[0] >= first / 2 && waits[0] <= first);
+        assert!(waits[3] >= first * 4);
+        assert!(waits.iter().all(|&wait| wait <= max));
+        assert!(waits[11] >= max / 2);
+
+        backoff.reset();
+        assert!(backoff.next_delay() <= first);
+    }
+}
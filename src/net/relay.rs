@@ -0,0 +1,996 @@
+//! Two boards playing each other across the internet through a relay.
+//!
+//! Neither board needs an open port: both post to and read from a room on
+//! a rendezvous server, which keeps every line posted to the room and
+//! streams them to each reader in order (see [`RelayTransport`]). The
+//! server only ever sees ciphertext. The owners agree on a passphrase,
+//! from which [`RelayKey`] derives both the room's name and an AES-256-GCM
+//! key that seals every line; lines that do not open are ignored. The
+//! room's name is public, so both come from a slow PBKDF2 of the
+//! passphrase: whoever sees the name has to pay [`KDF_ROUNDS`] hashes for
+//! every passphrase they try.
+//!
+//! [`Relay::find_game`] joins the room with a fresh random session and
+//! pairs with the next board to join after it. Every board reads the room
+//! from the start and sees the same lines in the same order, so they all
+//! agree on who plays whom: the first `start` naming two free sessions
+//! pairs them, and the lower session plays White. The returned
+//! [`RelayGame`] is then the [`Player`] for the other board, like a
+//! Lichess opponent: it reads their moves from the room by ply, and posts
+//! the moves detected here. A dropped stream is reopened and replayed
+//! from the start, so a reconnect loses nothing.
+//!
+//! Each board keeps its own clock. A move carries the mover's remaining
+//! times ([`RelayGame::set_clock`]); the other board reads them with
+//! [`RelayGame::peer_clock`] to correct for the network delay.
+//!
+//! There is no separate online-backend trait: as with [`super::lichess`],
+//! a remote opponent is a [`Player`] fed by worker threads over a small
+//! transport trait, which is all [`crate::session::GameSession`] needs
+//! of it. Each service keeps its own matchmaking.
+//!
+//! Only standard chess from the starting position is played.
+
+use std::fmt;
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use pbkdf2::pbkdf2_hmac;
+use sha2::{Digest, Sha256};
+use shakmaty::uci::UciMove;
+use shakmaty::{Bitboard, ByColor, CastlingMode, Chess, Color, Move, Position};
+
+use super::send_scheduled;
+use crate::feedback::BoardFeedback;
+use crate::player::{GameAction, Player, PlayerStatus};
+use crate::scheduler::{Outcome, Pacing, RequestScheduler};
+use crate::tls::CertificateError;
+
+/// Shortest passphrase [`RelayKey::from_passphrase`] accepts.
+pub const MIN_PASSPHRASE_LEN: usize = 12;
+
+/// Longest passphrase a [`PassphraseStore`] keeps, in bytes.
+pub const MAX_PASSPHRASE_LEN: usize = 64;
+
+/// PBKDF2-HMAC-SHA256 rounds from the passphrase to [`RelayKey`]; run
+/// once per [`Relay`], before joining the room.
+pub const KDF_ROUNDS: u32 = 100_000;
+
+/// Fixed salt, so the same passphrase meets in the same room on every
+/// board; it keeps tables built for other uses of PBKDF2 out.
+const KDF_SALT: &[u8] = b"unnamed-chess-project relay v1";
+
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RelayError {
+    #[error("relay passphrase must be at least {MIN_PASSPHRASE_LEN} characters")]
+    ShortPassphrase,
+    #[error("relay I/O: {0}")]
+    Io(#[from] io::Error),
+    #[error("relay unreachable: {0}")]
+    Certificate(#[from] CertificateError),
+    #[error("relay answered HTTP {0}")]
+    Status(u16),
+    #[error("relay message could not be sealed")]
+    Seal,
+    #[error("the other board sent an illegal move: {0}")]
+    IllegalMove(String),
+}
+
+impl RelayError {
+    /// Whether the request may succeed if sent again.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            RelayError::Io(_) => true,
+            RelayError::Status(code) => *code == 429 || *code >= 500,
+            _ => false,
+        }
+    }
+}
+
+/// The room and cipher key shared by two boards, derived from a
+/// passphrase.
+#[derive(Clone)]
+pub struct RelayKey {
+    key: [u8; 32],
+    room: String,
+}
+
+impl fmt::Debug for RelayKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RelayKey")
+            .field("room", &self.room)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RelayKey {
+    pub fn from_passphrase(passphrase: &str) -> Result<Self, RelayError> {
+        if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+            return Err(RelayError::ShortPassphrase);
+        }
+        let mut secret = [0; 32];
+        pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), KDF_SALT, KDF_ROUNDS, &mut secret);
+        let derive = |purpose: &str| -> [u8; 32] {
+            Sha256::new()
+                .chain_update(KDF_SALT)
+                .chain_update(purpose)
+                .chain_update(b"\0")
+                .chain_update(secret)
+                .finalize()
+                .into()
+        };
+        Ok(Self {
+            key: derive("key"),
+            room: hex(&derive("room")[..16]),
+        })
+    }
+
+    /// The room's name on the server. It is seen in the clear, and
+    /// guessing the passphrase from it costs [`KDF_ROUNDS`] per guess.
+    pub fn room(&self) -> &str {
+        &self.room
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(&self.key.into())
+    }
+
+    /// `text` sealed under `nonce`, as hex of the nonce then the
+    /// ciphertext and tag.
+    fn seal(&self, nonce: [u8; NONCE_LEN], text: &str) -> Result<String, RelayError> {
+        let sealed = self
+            .cipher()
+            .encrypt(Nonce::from_slice(&nonce), text.as_bytes())
+            .map_err(|_| RelayError::Seal)?;
+        Ok(hex(&nonce) + &hex(&sealed))
+    }
+
+    /// The text sealed in `line`, if it was sealed with this key.
+    fn open(&self, line: &str) -> Option<String> {
+        let bytes = unhex(line.trim())?;
+        if bytes.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, sealed) = bytes.split_at(NONCE_LEN);
+        let text = self
+            .cipher()
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .ok()?;
+        String::from_utf8(text).ok()
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Keeps the room's passphrase between boots.
+pub trait PassphraseStore {
+    fn load(&self) -> Option<String>;
+    fn save(&mut self, passphrase: &str) -> io::Result<()>;
+}
+
+/// HTTP access to the rendezvous server.
+///
+/// Rooms live at `/rooms/{room}`. Any status outside 2xx is
+/// [`RelayError::Status`].
+pub trait RelayTransport: Clone + Send + 'static {
+    /// GET the room: every line posted to it so far, oldest first, then
+    /// each new one as it is posted.
+    fn stream(&self, room: &str) -> Result<Box<dyn BufRead + Send>, RelayError>;
+
+    /// POST `line` to the end of the room.
+    fn post(&self, room: &str, line: &str) -> Result<(), RelayError>;
+}
+
+/// What a board says in the room.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Message {
+    /// The session is looking for a game.
+    Hello,
+    /// Pair two sessions, if both are still free.
+    Start {
+        white: u32,
+        black: u32,
+    },
+    /// The move at `ply`, and the mover's clock after it.
+    Move {
+        ply: usize,
+        uci: UciMove,
+        clock: Option<ByColor<Duration>>,
+    },
+    Resign,
+}
+
+/// A [`Message`] and the session that sent it, as sealed in a line:
+/// `1a2b3c4d move 0 e2e4 59500 60000`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Envelope {
+    from: u32,
+    message: Message,
+}
+
+impl Envelope {
+    fn encode(&self) -> String {
+        let from = self.from;
+        match &self.message {
+            Message::Hello => format!("{from:08x} hello"),
+            Message::Start { white, black } => format!("{from:08x} start {white:08x} {black:08x}"),
+            Message::Move { ply, uci, clock } => {
+                let mut line = format!("{from:08x} move {ply} {uci}");
+                if let Some(clock) = clock {
+                    line += &format!(" {} {}", clock.white.as_millis(), clock.black.as_millis());
+                }
+                line
+            }
+            Message::Resign => format!("{from:08x} resign"),
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let session = |word: &str| u32::from_str_radix(word, 16).ok();
+        let millis = |word: &str| word.parse().ok().map(Duration::from_millis);
+        let words: Vec<&str> = text.split(' ').collect();
+        let (&from, rest) = words.split_first()?;
+        let message = match rest {
+            ["hello"] => Message::Hello,
+            ["start", white, black] => Message::Start {
+                white: session(white)?,
+                black: session(black)?,
+            },
+            ["move", ply, uci] | ["move", ply, uci, _, _] => Message::Move {
+                ply: ply.parse().ok()?,
+                uci: uci.parse().ok()?,
+                clock: match rest {
+                    [.., white, black] if rest.len() == 5 => Some(ByColor {
+                        white: millis(white)?,
+                        black: millis(black)?,
+                    }),
+                    _ => None,
+                },
+            },
+            ["resign"] => Message::Resign,
+            _ => return None,
+        };
+        Some(Self {
+            from: session(from)?,
+            message,
+        })
+    }
+}
+
+/// Who plays whom, worked out from the room's lines in order.
+///
+/// A session pairs with the first other session to say hello after it,
+/// by posting a `start` for the two. A `start` counts only if neither
+/// session is in an earlier one, so every reader agrees on the pairs.
+#[derive(Debug, Clone)]
+struct Rendezvous {
+    session: u32,
+    /// Our hello has come by; later hellos are from boards that joined
+    /// after us.
+    joined: bool,
+    /// Sessions already in a game.
+    paired: Vec<u32>,
+    /// The session we asked to pair with, until that `start` comes by.
+    proposed: Option<u32>,
+    /// Our game: White's session, then Black's.
+    game: Option<(u32, u32)>,
+}
+
+impl Rendezvous {
+    fn new(session: u32) -> Self {
+        Self {
+            session,
+            joined: false,
+            paired: Vec::new(),
+            proposed: None,
+            game: None,
+        }
+    }
+
+    /// Take in the next line of the room. Returns a `start` to post when
+    /// we pair with the sender.
+    fn observe(&mut self, envelope: &Envelope) -> Option<Message> {
+        let us = self.session;
+        match envelope.message {
+            Message::Hello if envelope.from == us => self.joined = true,
+            Message::Hello => {
+                let them = envelope.from;
+                if self.joined
+                    && self.game.is_none()
+                    && self.proposed.is_none()
+                    && !self.paired.contains(&them)
+                {
+                    self.proposed = Some(them);
+                    return Some(Message::Start {
+                        white: us.min(them),
+                        black: us.max(them),
+                    });
+                }
+            }
+            Message::Start { white, black } => {
+                let free = white != black
+                    && !self.paired.contains(&white)
+                    && !self.paired.contains(&black);
+                if free {
+                    self.paired.extend([white, black]);
+                    if self.game.is_none() && (white == us || black == us) {
+                        self.game = Some((white, black));
+                    }
+                }
+                if self
+                    .proposed
+                    .is_some_and(|them| self.paired.contains(&them))
+                {
+                    // Our proposal is settled, one way or the other.
+                    self.proposed = None;
+                }
+            }
+            Message::Move { .. } | Message::Resign => {}
+        }
+        None
+    }
+
+    /// Our color and the other board's session, once paired.
+    fn game(&self) -> Option<(Color, u32)> {
+        let (white, black) = self.game?;
+        Some(if white == self.session {
+            (Color::White, black)
+        } else {
+            (Color::Black, white)
+        })
+    }
+}
+
+/// A room to find games in.
+#[derive(Debug, Clone)]
+pub struct Relay<T> {
+    transport: T,
+    key: RelayKey,
+    pacing: Pacing,
+}
+
+impl<T: RelayTransport> Relay<T> {
+    pub fn new(transport: T, key: RelayKey) -> Self {
+        Self {
+            transport,
+            key,
+            pacing: Pacing::default(),
+        }
+    }
+
+    /// Space posts and retries by `pacing` instead of
+    /// [`Pacing::default`].
+    pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+        self.pacing = pacing;
+        self
+    }
+
+    /// Join the room in the background. `random` draws the session and
+    /// the nonces; on the board, the hardware RNG. The returned player is
+    /// the other board once paired; see [`RelayGame::local_color`].
+    pub fn find_game(&self, mut random: impl FnMut() -> u32 + Send + 'static) -> RelayGame {
+        let session = random();
+        let (update_tx, updates) = mpsc::channel();
+        let (outgoing, outgoing_rx) = mpsc::channel();
+        let flags = Arc::new(Flags::default());
+        let worker = Worker {
+            relay: self.clone(),
+            session,
+            updates: update_tx,
+            flags: flags.clone(),
+        };
+        let poster = worker.clone();
+        let _ = outgoing.send(Message::Hello);
+        let starts = outgoing.clone();
+        thread::spawn(move || worker.follow(&starts));
+        thread::spawn(move || poster.post_messages(&outgoing_rx, &mut random));
+        log::info!("Joining relay room {} as {session:08x}", self.key.room());
+        RelayGame {
+            updates,
+            outgoing,
+            flags,
+            local_color: None,
+            moves: Vec::new(),
+            clock: None,
+            peer_clock: None,
+        }
+    }
+}
+
+/// What the reader reports to the player.
+#[derive(Debug)]
+enum Update {
+    Started(Color),
+    Move {
+        ply: usize,
+        uci: UciMove,
+        clock: Option<ByColor<Duration>>,
+    },
+}
+
+/// Raised by either side; the session reads the player's status without
+/// polling it, so these are not sent as updates.
+#[derive(Debug, Default)]
+struct Flags {
+    /// A board resigned.
+    over: AtomicBool,
+    failed: AtomicBool,
+    /// Why the server could not be reached securely, if that ended the
+    /// game.
+    certificate: OnceLock<CertificateError>,
+    /// The player is gone and the workers should stop.
+    dropped: AtomicBool,
+}
+
+impl Flags {
+    fn fail(&self, error: &RelayError) {
+        log::error!("Relay game failed: {error}");
+        if let RelayError::Certificate(e) = error {
+            let _ = self.certificate.set(*e);
+        }
+        self.failed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The other board in a relayed game.
+///
+/// Until the boards are paired it never moves. Its status is
+/// [`PlayerStatus::GameOver`] once either board resigns, and
+/// [`PlayerStatus::Error`] if the server refuses the room or fails
+/// certificate verification, or the other board sends an illegal move.
+#[derive(Debug)]
+pub struct RelayGame {
+    updates: Receiver<Update>,
+    outgoing: Sender<Message>,
+    flags: Arc<Flags>,
+    local_color: Option<Color>,
+    /// Both boards' moves, by ply.
+    moves: Vec<UciMove>,
+    /// Our remaining times, sent with our next move.
+    clock: Option<ByColor<Duration>>,
+    peer_clock: Option<ByColor<Duration>>,
+}
+
+impl RelayGame {
+    /// The color played on this board, once paired. The other board has
+    /// the other one.
+    pub fn local_color(&mut self) -> Option<Color> {
+        self.receive();
+        self.local_color
+    }
+
+    /// Remaining times on this board's clock, to send with the next move.
+    pub fn set_clock(&mut self, remaining: ByColor<Duration>) {
+        self.clock = Some(remaining);
+    }
+
+    /// Remaining times on the other board's clock after its last move.
+    pub fn peer_clock(&mut self) -> Option<ByColor<Duration>> {
+        self.receive();
+        self.peer_clock
+    }
+
+    fn receive(&mut self) {
+        while let Ok(update) = self.updates.try_recv() {
+            match update {
+                Update::Started(color) => {
+                    log::info!("Relay game started, playing {color}");
+                    self.local_color = Some(color);
+                }
+                Update::Move { ply, uci, clock } => {
+                    if ply == self.moves.len() {
+                        self.moves.push(uci);
+                        if clock.is_some() && Some(mover(ply)) != self.local_color {
+                            self.peer_clock = clock;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.flags.over.load(Ordering::Relaxed)
+    }
+
+    fn has_failed(&self) -> bool {
+        self.flags.failed.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for RelayGame {
+    fn drop(&mut self) {
+        self.flags.dropped.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Player for RelayGame {
+    fn poll_move(&mut self, position: &Chess, _sensors: ByColor<Bitboard>) -> Option<Move> {
+        self.receive();
+        if self.is_over() || self.has_failed() {
+            return None;
+        }
+        let uci = self.moves.get(ply(position))?;
+        match uci.to_move(position) {
+            Ok(mv) => Some(mv),
+            Err(_) => {
+                self.flags.fail(&RelayError::IllegalMove(uci.to_string()));
+                None
+            }
+        }
+    }
+
+    fn opponent_moved(&mut self, position: &Chess, opponent_move: &Move) {
+        let message = Message::Move {
+            ply: ply(position).saturating_sub(1),
+            uci: opponent_move.to_uci(CastlingMode::Standard),
+            clock: self.clock.take(),
+        };
+        let _ = self.outgoing.send(message);
+    }
+
+    fn status(&self) -> PlayerStatus {
+        if self.has_failed() {
+            PlayerStatus::Error
+        } else if self.is_over() {
+            PlayerStatus::GameOver
+        } else {
+            PlayerStatus::Active
+        }
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+
+    fn allows_takeback(&self) -> bool {
+        false
+    }
+
+    fn error_feedback(&self) -> Option<BoardFeedback> {
+        self.flags.certificate.get().map(CertificateError::feedback)
+    }
+
+    fn notify(&mut self, action: &GameAction) {
+        if let GameAction::Resign(color) = action
+            && Some(*color) == self.local_color
+        {
+            let _ = self.outgoing.send(Message::Resign);
+        }
+    }
+}
+
+/// Moves played since the starting position.
+fn ply(position: &Chess) -> usize {
+    let full = position.fullmoves().get() as usize - 1;
+    full * 2 + usize::from(position.turn() == Color::Black)
+}
+
+/// The side that plays at `ply`.
+fn mover(ply: usize) -> Color {
+    Color::from_white(ply.is_multiple_of(2))
+}
+
+/// One of the background threads of a [`RelayGame`].
+#[derive(Debug, Clone)]
+struct Worker<T> {
+    relay: Relay<T>,
+    session: u32,
+    updates: Sender<Update>,
+    flags: Arc<Flags>,
+}
+
+impl<T: RelayTransport> Worker<T> {
+    fn stopped(&self) -> bool {
+        self.flags.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Read the room until the player is dropped, reopening it whenever
+    /// the stream fails or ends. Each reading starts from the first line.
+    /// Our `start`s go to the poster through `starts`.
+    fn follow(self, starts: &Sender<Message>) {
+        let room = self.relay.key.room().to_string();
+        let mut backoff = self.relay.pacing.backoff(self.session);
+        // Kept across readings, so nothing is posted or reported twice.
+        let mut proposed = Vec::new();
+        let mut started = false;
+        let mut over = false;
+        while !self.stopped() {
+            let reader = match self.relay.transport.stream(&room) {
+                Ok(reader) => reader,
+                Err(RelayError::Status(429)) => {
+                    log::warn!("Relay room rate limited");
+                    thread::sleep(self.relay.pacing.rate_limit_pause);
+                    continue;
+                }
+                Err(e) if e.is_transient() => {
+                    log::warn!("Relay room unavailable: {e}");
+                    backoff.wait();
+                    continue;
+                }
+                Err(e) => {
+                    self.flags.fail(&e);
+                    return;
+                }
+            };
+            backoff.reset();
+            let mut rendezvous = Rendezvous::new(self.session);
+            for line in reader.lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        log::warn!("Relay room dropped: {e}");
+                        break;
+                    }
+                };
+                if self.stopped() {
+                    return;
+                }
+                let Some(envelope) = self
+                    .relay
+                    .key
+                    .open(&line)
+                    .as_deref()
+                    .and_then(Envelope::parse)
+                else {
+                    // Keep-alives, and lines sealed with another key.
+                    continue;
+                };
+                if let Some(start) = rendezvous.observe(&envelope)
+                    && !proposed.contains(&start)
+                {
+                    proposed.push(start.clone());
+                    let _ = starts.send(start);
+                }
+                let Some((color, peer)) = rendezvous.game() else {
+                    continue;
+                };
+                if !started {
+                    started = true;
+                    let _ = self.updates.send(Update::Started(color));
+                }
+                if envelope.from != self.session && envelope.from != peer {
+                    continue;
+                }
+                match envelope.message {
+                    Message::Move { ply, uci, clock } => {
+                        let _ = self.updates.send(Update::Move { ply, uci, clock });
+                    }
+                    Message::Resign if !over => {
+                        over = true;
+                        log::info!(
+                            "Relay game ended: {} resigned",
+                            if envelope.from == self.session {
+                                "we"
+                            } else {
+                                "they"
+                            }
+                        );
+                        self.flags.over.store(true, Ordering::Relaxed);
+                    }
+                    _ => {}
+                }
+            }
+            backoff.wait();
+        }
+    }
+
+    /// Seal and post the board's messages in order, each until the server
+    /// has it.
+    fn post_messages(self, messages: &Receiver<Message>, random: &mut impl FnMut() -> u32) {
+        let scheduler = RequestScheduler::new(self.relay.pacing, random());
+        send_scheduled(messages, scheduler, |message, _| {
+            self.post_message(message, random)
+        });
+    }
+
+    /// One attempt at posting `message`, sealed with a fresh nonce.
+    fn post_message(&self, message: &Message, random: &mut impl FnMut() -> u32) -> Outcome {
+        let text = Envelope {
+            from: self.session,
+            message: message.clone(),
+        }
+        .encode();
+        let mut nonce = [0; NONCE_LEN];
+        for chunk in nonce.chunks_mut(4) {
+            chunk.copy_from_slice(&random().to_le_bytes());
+        }
+        let result = self
+            .relay
+            .key
+            .seal(nonce, &text)
+            .and_then(|line| self.relay.transport.post(self.relay.key.room(), &line));
+        match result {
+            Ok(()) => Outcome::Sent,
+            Err(RelayError::Status(429)) if !self.stopped() => {
+                log::warn!("Relay rate limited, pausing");
+                Outcome::RateLimited
+            }
+            Err(e) if e.is_transient() && !self.stopped() => {
+                log::warn!("Relay message not sent, retrying: {e}");
+                Outcome::Failed
+            }
+            Err(e) => {
+                self.flags.fail(&e);
+                Outcome::Rejected
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift32;
+    use crate::testutil::malformed_inputs;
+    use std::io::Cursor;
+    use std::sync::{Mutex, OnceLock};
+    use std::time::Instant;
+
+    /// A rendezvous server with one room per name. Each stream returns the
+    /// room as it is, then ends, so readers keep reconnecting; the first
+    /// `drops` streams fail.
+    #[derive(Clone, Default)]
+    struct FakeRelay(Arc<Mutex<FakeState>>);
+
+    #[derive(Default)]
+    struct FakeState {
+        lines: Vec<(String, String)>,
+        drops: usize,
+    }
+
+    impl FakeRelay {
+        fn lines(&self) -> usize {
+            self.0.lock().unwrap().lines.len()
+        }
+    }
+
+    impl RelayTransport for FakeRelay {
+        fn stream(&self, room: &str) -> Result<Box<dyn BufRead + Send>, RelayError> {
+            let mut state = self.0.lock().unwrap();
+            if state.drops > 0 {
+                state.drops -= 1;
+                return Err(RelayError::Io(io::ErrorKind::NotConnected.into()));
+            }
+            let mut body = String::new();
+            for (_, line) in state.lines.iter().filter(|(r, _)| r == room) {
+                body += line;
+                body += "\n\n";
+            }
+            Ok(Box::new(Cursor::new(body.into_bytes())))
+        }
+
+        fn post(&self, room: &str, line: &str) -> Result<(), RelayError> {
+            let mut state = self.0.lock().unwrap();
+            state.lines.push((room.to_string(), line.to_string()));
+            Ok(())
+        }
+    }
+
+    /// Derived once: the KDF is slow by design.
+    fn key() -> RelayKey {
+        static KEY: OnceLock<RelayKey> = OnceLock::new();
+        KEY.get_or_init(|| RelayKey::from_passphrase("correct horse battery staple").unwrap())
+            .clone()
+    }
+
+    fn join(server: &FakeRelay, seed: u32) -> RelayGame {
+        let mut rng = XorShift32::new(seed);
+        Relay::new(server.clone(), key())
+            .with_pacing(Pacing {
+                min_interval: Duration::ZERO,
+                rate_limit_pause: Duration::from_millis(50),
+                retry_delay: Duration::from_millis(1),
+                max_retry_delay: Duration::from_millis(1),
+            })
+            .find_game(move || rng.next_u32())
+    }
+
+    /// Poll until `done` holds.
+    fn wait_for(game: &mut RelayGame, mut done: impl FnMut(&mut RelayGame) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done(game) {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn play(position: &Chess, uci: &str) -> (Chess, Move) {
+        let mv = uci.parse::<UciMove>().unwrap().to_move(position).unwrap();
+        (position.clone().play(mv).unwrap(), mv)
+    }
+
+    fn hello(from: u32) -> Envelope {
+        Envelope {
+            from,
+            message: Message::Hello,
+        }
+    }
+
+    fn start(from: u32, white: u32, black: u32) -> Envelope {
+        Envelope {
+            from,
+            message: Message::Start { white, black },
+        }
+    }
+
+    #[test]
+    fn the_passphrase_names_the_room_and_seals_its_lines() {
+        let key = key();
+        // Pinned: boards on either side of a firmware update must still
+        // meet in the same room with the same key.
+        assert_eq!(key.room(), "2795f94dd8ef4da483f788b42b6024f2");
+        assert_eq!(
+            hex(&key.key),
+            "70f244ecd0671e60ba23131b3d4065dc3b878d6f7d8e3752492df458f57138c3"
+        );
+        let other = RelayKey::from_passphrase("correct horse battery stapler").unwrap();
+        assert_ne!(key.room(), other.room());
+        assert!(matches!(
+            RelayKey::from_passphrase("short"),
+            Err(RelayError::ShortPassphrase)
+        ));
+        assert!(!format!("{key:?}").contains("key:"), "{key:?}");
+
+        let line = key.seal([7; NONCE_LEN], "0000002a hello").unwrap();
+        assert!(!line.contains("hello"));
+        assert_eq!(key.open(&line).as_deref(), Some("0000002a hello"));
+        assert_eq!(other.open(&line), None, "sealed with another key");
+        let mut tampered = line.into_bytes();
+        let last = tampered.len() - 1;
+        tampered[last] = if tampered[last] == b'0' { b'1' } else { b'0' };
+        assert_eq!(key.open(&String::from_utf8(tampered).unwrap()), None);
+    }
+
+    #[test]
+    fn envelopes_read_back_as_written() {
+        let envelopes = [
+            hello(0xdead_beef),
+            start(1, 1, 2),
+            Envelope {
+                from: 2,
+                message: Message::Move {
+                    ply: 3,
+                    uci: "e7e8q".parse().unwrap(),
+                    clock: Some(ByColor {
+                        white: Duration::from_millis(59_500),
+                        black: Duration::from_secs(61),
+                    }),
+                },
+            },
+            Envelope {
+                from: 2,
+                message: Message::Move {
+                    ply: 0,
+                    uci: "e2e4".parse().unwrap(),
+                    clock: None,
+                },
+            },
+            Envelope {
+                from: 1,
+                message: Message::Resign,
+            },
+        ];
+        for envelope in envelopes {
+            assert_eq!(Envelope::parse(&envelope.encode()), Some(envelope.clone()));
+        }
+    }
+
+    #[test]
+    fn boards_pair_with_the_next_to_join() {
+        let (a, b, c) = (30, 10, 20);
+        let mut boards = [Rendezvous::new(a), Rendezvous::new(b), Rendezvous::new(c)];
+        let mut room = vec![hello(99), hello(a), hello(b), hello(c)];
+        let mut read = 0;
+        // Each board reads the room and posts its starts at the end.
+        while read < room.len() {
+            let envelope = room[read].clone();
+            read += 1;
+            for board in &mut boards {
+                if let Some(message) = board.observe(&envelope) {
+                    room.push(Envelope {
+                        from: board.session,
+                        message,
+                    });
+                }
+            }
+        }
+
+        assert_eq!(boards[0].game(), Some((Color::Black, b)), "a saw b join");
+        assert_eq!(boards[1].game(), Some((Color::White, a)));
+        assert_eq!(boards[2].game(), None, "b was taken; c waits for the next");
+        assert!(boards[2].proposed.is_none());
+        assert!(
+            room.contains(&start(b, b, c)),
+            "b asked c before reading a's start"
+        );
+    }
+
+    #[test]
+    fn two_boards_play_through_the_relay() {
+        let server = FakeRelay::default();
+        server.0.lock().unwrap().drops = 3;
+        let mut first = join(&server, 1);
+        let mut second = join(&server, 2);
+
+        wait_for(&mut first, |g| g.local_color().is_some());
+        wait_for(&mut second, |g| g.local_color().is_some());
+        let white_first = first.local_color() == Some(Color::White);
+        assert_eq!(second.local_color(), Some(!first.local_color().unwrap()));
+        let (white, black) = if white_first {
+            (&mut first, &mut second)
+        } else {
+            (&mut second, &mut first)
+        };
+
+        // White's board detects e4 and tells Black's, clock and all.
+        let start = Chess::default();
+        let (after_e4, e4) = play(&start, "e2e4");
+        let clock = ByColor {
+            white: Duration::from_millis(58_250),
+            black: Duration::from_secs(60),
+        };
+        white.set_clock(clock);
+        white.opponent_moved(&after_e4, &e4);
+        wait_for(black, |g| g.poll_move(&start, ByColor::default()).is_some());
+        assert_eq!(black.poll_move(&start, ByColor::default()), Some(e4));
+        assert_eq!(black.peer_clock(), Some(clock));
+        assert!(!black.is_interactive());
+
+        let (after_e5, e5) = play(&after_e4, "e7e5");
+        black.opponent_moved(&after_e5, &e5);
+        wait_for(white, |g| {
+            g.poll_move(&after_e4, ByColor::default()).is_some()
+        });
+        assert_eq!(white.poll_move(&after_e4, ByColor::default()), Some(e5));
+        assert_eq!(white.peer_clock(), None, "sent without a clock");
+
+        // Resigning for the other board's color is not ours to send.
+        white.notify(&GameAction::Resign(Color::Black));
+        black.notify(&GameAction::Resign(Color::Black));
+        wait_for(white, |g| g.status() == PlayerStatus::GameOver);
+        wait_for(black, |g| g.status() == PlayerStatus::GameOver);
+        assert!(server.lines() >= 6);
+    }
+
+    #[test]
+    fn malformed_lines_are_ignored() {
+        let key = key();
+        let envelope = Envelope {
+            from: 5,
+            message: Message::Move {
+                ply: 1,
+                uci: "e7e5".parse().unwrap(),
+                clock: Some(ByColor::default()),
+            },
+        };
+        let text = envelope.encode();
+        for bytes in malformed_inputs(text.as_bytes(), 256, 64) {
+            let _ = Envelope::parse(&String::from_utf8_lossy(&bytes));
+        }
+        let line = key.seal([1; NONCE_LEN], &text).unwrap();
+        for bytes in malformed_inputs(line.as_bytes(), 256, 200) {
+            let _ = key.open(&String::from_utf8_lossy(&bytes));
+        }
+    }
+}
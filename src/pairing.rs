@@ -0,0 +1,386 @@
+//! Pairing and authentication of control clients.
+//!
+//! Without pairing, any device in radio range can start, cancel or play a
+//! game. With [`Pairing`] enabled, a client first asks for a pairing code,
+//! which the board shows as [`CODE_SQUARES`] lit squares. Whoever can see
+//! the board types those squares into the client, which then receives a
+//! token. Each later connection presents the token before sending commands.
+//! Authentication belongs to the connection that paired or presented the
+//! token and ends when that connection closes.
+//!
+//! A wrong code discards the pending one, so codes cannot be guessed one
+//! attempt at a time; the client must request a new code, which the person
+//! at the board sees. After [`MAX_FAILED_PAIRINGS`] wrong codes in a row
+//! pairing is locked for [`LOCKOUT`], twice as long after each further
+//! lockout up to [`MAX_LOCKOUT`], so a client cannot work through the
+//! codes by requesting them over and over.
+//!
+//! Tokens survive a restart when a [`PairingStore`] is set.
+
+use std::fmt::Write as _;
+use std::io;
+use std::time::Duration;
+
+use shakmaty::{Bitboard, Square};
+
+use crate::access::ConnectionId;
+
+/// Number of squares in a pairing code.
+pub const CODE_SQUARES: usize = 3;
+
+/// How long a pairing code stays valid.
+pub const CODE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Paired clients remembered; pairing another forgets the oldest.
+pub const MAX_TOKENS: usize = 8;
+
+/// Wrong codes in a row that lock pairing.
+pub const MAX_FAILED_PAIRINGS: u32 = 3;
+
+/// How long the first lockout lasts.
+pub const LOCKOUT: Duration = Duration::from_secs(60);
+
+/// Longest lockout.
+pub const MAX_LOCKOUT: Duration = Duration::from_secs(60 * 60);
+
+const VERSION: &str = "1";
+
+/// Secret a paired client presents to authenticate.
+pub type Token = u64;
+
+/// Why a pairing code was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum PairingError {
+    #[error("wrong, expired or missing pairing code")]
+    WrongCode,
+    #[error("pairing locked after repeated wrong codes")]
+    Locked,
+}
+
+/// Where the tokens of paired clients are kept between power cycles.
+pub trait PairingStore {
+    /// The stored tokens, oldest first. Ones that cannot be read are logged
+    /// and treated as absent.
+    fn load(&self) -> Vec<Token>;
+
+    fn save(&mut self, tokens: &[Token]) -> io::Result<()>;
+}
+
+/// Issues pairing codes and tokens, and tracks which connections have
+/// authenticated.
+pub struct Pairing {
+    /// Source of codes and tokens; should be a hardware RNG on the board.
+    random: Box<dyn FnMut() -> u32>,
+    /// Oldest first.
+    tokens: Vec<Token>,
+    store: Option<Box<dyn PairingStore>>,
+    /// Squares of the pending code and when it was issued.
+    code: Option<(Bitboard, Duration)>,
+    authenticated: Vec<ConnectionId>,
+    /// Wrong codes since the last success or lockout.
+    failures: u32,
+    /// Lockouts since the last success.
+    lockouts: u32,
+    locked_until: Option<Duration>,
+}
+
+impl std::fmt::Debug for Pairing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pairing")
+            .field("tokens", &self.tokens.len())
+            .field("authenticated", &self.authenticated)
+            .field("locked_until", &self.locked_until)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Pairing {
+    /// No client paired yet.
+    pub fn new(random: impl FnMut() -> u32 + 'static) -> Self {
+        Self {
+            random: Box::new(random),
+            tokens: Vec::new(),
+            store: None,
+            code: None,
+            authenticated: Vec::new(),
+            failures: 0,
+            lockouts: 0,
+            locked_until: None,
+        }
+    }
+
+    /// Keep tokens in `store`, starting from the ones it holds.
+    pub fn set_store(&mut self, store: Box<dyn PairingStore>) {
+        let mut tokens = store.load();
+        tokens.drain(..tokens.len().saturating_sub(MAX_TOKENS));
+        self.tokens = tokens;
+        self.store = Some(store);
+    }
+
+    /// Issue a new pairing code, replacing any pending one, unless pairing
+    /// is locked.
+    pub fn request_code(&mut self, now: Duration) -> Result<Bitboard, PairingError> {
+        self.check_lock(now)?;
+        let mut squares = Bitboard::EMPTY;
+        while squares.count() < CODE_SQUARES {
+            squares.add(Square::new((self.random)() % 64));
+        }
+        self.code = Some((squares, now));
+        Ok(squares)
+    }
+
+    /// The pending pairing code, unless it expired.
+    pub fn code(&mut self, now: Duration) -> Option<Bitboard> {
+        if let Some((_, issued)) = self.code
+            && now.saturating_sub(issued) >= CODE_TIMEOUT
+        {
+            self.code = None;
+        }
+        self.code.map(|(squares, _)| squares)
+    }
+
+    /// Pair `connection` with the pending code. The code is used up either
+    /// way; on success the connection is authenticated and gets a new
+    /// token.
+    pub fn pair(
+        &mut self,
+        connection: ConnectionId,
+        squares: Bitboard,
+        now: Duration,
+    ) -> Result<Token, PairingError> {
+        self.check_lock(now)?;
+        let code = self.code(now);
+        self.code = None;
+        if code != Some(squares) {
+            self.failures += 1;
+            if self.failures >= MAX_FAILED_PAIRINGS {
+                let lockout = LOCKOUT
+                    .saturating_mul(1 << self.lockouts.min(16))
+                    .min(MAX_LOCKOUT);
+                log::warn!("Pairing locked for {}s", lockout.as_secs());
+                self.locked_until = Some(now + lockout);
+                self.lockouts += 1;
+                self.failures = 0;
+            }
+            return Err(PairingError::WrongCode);
+        }
+        self.failures = 0;
+        self.lockouts = 0;
+        let token = u64::from((self.random)()) << 32 | u64::from((self.random)());
+        if self.tokens.len() == MAX_TOKENS {
+            self.tokens.remove(0);
+        }
+        self.tokens.push(token);
+        self.save_tokens();
+        self.set_authenticated(connection, true);
+        Ok(token)
+    }
+
+    /// Authenticate `connection` with a token from an earlier pairing.
+    pub fn authenticate(&mut self, connection: ConnectionId, token: Token) -> bool {
+        let known = self.tokens.contains(&token);
+        self.set_authenticated(connection, known);
+        known
+    }
+
+    /// Whether `connection` may send commands.
+    pub fn is_authenticated(&self, connection: ConnectionId) -> bool {
+        self.authenticated.contains(&connection)
+    }
+
+    /// `connection` closed; a new connection must authenticate again, even
+    /// if it reuses the same ID.
+    pub fn disconnect(&mut self, connection: ConnectionId) {
+        self.set_authenticated(connection, false);
+    }
+
+    fn set_authenticated(&mut self, connection: ConnectionId, authenticated: bool) {
+        self.authenticated.retain(|&c| c != connection);
+        if authenticated {
+            self.authenticated.push(connection);
+        }
+    }
+
+    fn check_lock(&mut self, now: Duration) -> Result<(), PairingError> {
+        match self.locked_until {
+            Some(until) if now < until => Err(PairingError::Locked),
+            _ => {
+                self.locked_until = None;
+                Ok(())
+            }
+        }
+    }
+
+    fn save_tokens(&mut self) {
+        if let Some(store) = &mut self.store
+            && let Err(e) = store.save(&self.tokens)
+        {
+            log::warn!("Saving the pairing tokens failed: {e}");
+        }
+    }
+
+    /// `tokens` as stored text.
+    pub fn encode_tokens(tokens: &[Token]) -> String {
+        let mut text = format!("version {VERSION}\n");
+        for token in tokens {
+            let _ = writeln!(text, "{token:016x}");
+        }
+        text
+    }
+
+    /// Read tokens stored with [`Self::encode_tokens`]; `None` if the text
+    /// is not such a list.
+    pub fn decode_tokens(text: &str) -> Option<Vec<Token>> {
+        let (header, list) = text.split_once('\n').unwrap_or((text, ""));
+        if header.strip_prefix("version ") != Some(VERSION) {
+            return None;
+        }
+        list.lines()
+            .map(|line| Token::from_str_radix(line, 16).ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift32;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn pairing() -> Pairing {
+        let mut rng = XorShift32::new(7);
+        Pairing::new(move || rng.next_u32())
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn wrong(code: Bitboard) -> Bitboard {
+        (code ^ Bitboard::FULL).first().map(Bitboard::from).unwrap()
+    }
+
+    #[test]
+    fn right_code_issues_a_token_that_authenticates_later() {
+        let mut pairing = pairing();
+        let code = pairing.request_code(secs(0)).unwrap();
+        assert_eq!(code.count(), CODE_SQUARES);
+
+        let token = pairing.pair(1, code, secs(10)).unwrap();
+        assert!(pairing.is_authenticated(1));
+        assert!(!pairing.is_authenticated(2));
+        assert_eq!(pairing.code(secs(10)), None);
+
+        pairing.disconnect(1);
+        assert!(!pairing.is_authenticated(1));
+        assert!(!pairing.authenticate(1, token ^ 1));
+        assert!(pairing.authenticate(1, token));
+    }
+
+    #[test]
+    fn authentication_ends_with_its_connection() {
+        let mut pairing = pairing();
+        let code = pairing.request_code(secs(0)).unwrap();
+        let token = pairing.pair(1, code, secs(0)).unwrap();
+        assert!(pairing.authenticate(2, token));
+
+        pairing.disconnect(1);
+
+        assert!(!pairing.is_authenticated(1));
+        assert!(pairing.is_authenticated(2));
+    }
+
+    #[test]
+    fn wrong_or_expired_code_is_used_up() {
+        let mut pairing = pairing();
+        let code = pairing.request_code(secs(0)).unwrap();
+        assert_eq!(
+            pairing.pair(1, wrong(code), secs(1)),
+            Err(PairingError::WrongCode)
+        );
+        assert_eq!(pairing.pair(1, code, secs(2)), Err(PairingError::WrongCode));
+
+        let code = pairing.request_code(secs(10)).unwrap();
+        assert_eq!(pairing.code(secs(10) + CODE_TIMEOUT), None);
+        assert!(pairing.pair(1, code, secs(10) + CODE_TIMEOUT).is_err());
+        assert!(!pairing.is_authenticated(1));
+    }
+
+    #[test]
+    fn repeated_wrong_codes_lock_pairing_for_longer_each_time() {
+        let mut pairing = pairing();
+        let mut now = secs(0);
+        for lockout in [LOCKOUT, LOCKOUT * 2] {
+            for _ in 0..MAX_FAILED_PAIRINGS {
+                let code = pairing.request_code(now).unwrap();
+                assert_eq!(
+                    pairing.pair(1, wrong(code), now),
+                    Err(PairingError::WrongCode)
+                );
+            }
+            assert_eq!(pairing.request_code(now), Err(PairingError::Locked));
+            assert_eq!(
+                pairing.pair(1, Bitboard::EMPTY, now + lockout - secs(1)),
+                Err(PairingError::Locked)
+            );
+            now += lockout;
+        }
+
+        let code = pairing.request_code(now).unwrap();
+        assert!(pairing.pair(1, code, now).is_ok());
+        for _ in 0..MAX_FAILED_PAIRINGS {
+            let code = pairing.request_code(now).unwrap();
+            let _ = pairing.pair(1, wrong(code), now);
+        }
+        assert!(pairing.request_code(now + LOCKOUT).is_ok());
+    }
+
+    #[test]
+    fn oldest_token_is_forgotten() {
+        let mut pairing = pairing();
+        let tokens: Vec<Token> = (0..=MAX_TOKENS)
+            .map(|_| {
+                let code = pairing.request_code(secs(0)).unwrap();
+                pairing.pair(1, code, secs(0)).unwrap()
+            })
+            .collect();
+
+        assert!(!pairing.authenticate(1, tokens[0]));
+        assert!(pairing.authenticate(1, tokens[1]));
+    }
+
+    #[derive(Default, Clone)]
+    struct MemoryStore(Rc<RefCell<Option<String>>>);
+
+    impl PairingStore for MemoryStore {
+        fn load(&self) -> Vec<Token> {
+            self.0
+                .borrow()
+                .as_deref()
+                .and_then(Pairing::decode_tokens)
+                .unwrap_or_default()
+        }
+
+        fn save(&mut self, tokens: &[Token]) -> io::Result<()> {
+            *self.0.borrow_mut() = Some(Pairing::encode_tokens(tokens));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn tokens_survive_a_restart_through_the_store() {
+        let store = MemoryStore::default();
+        let mut first = pairing();
+        first.set_store(Box::new(store.clone()));
+        let code = first.request_code(secs(0)).unwrap();
+        let token = first.pair(1, code, secs(0)).unwrap();
+
+        let mut restarted = pairing();
+        restarted.set_store(Box::new(store.clone()));
+
+        assert!(restarted.authenticate(1, token));
+        assert_eq!(Pairing::decode_tokens("version 2\n"), None);
+        assert_eq!(Pairing::decode_tokens("version 1\nxyz\n"), None);
+    }
+}
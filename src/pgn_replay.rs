@@ -0,0 +1,376 @@
+//! Replaying recorded games through move detection, with no board.
+//!
+//! [`parse_pgn`] reads the games of a PGN file, and [`replay_game`] plays
+//! each move on a simulated board the way a player would make it: lift
+//! the piece, take off what it captures, put it down (see [`readings`]).
+//! Every reading goes through a [`GameSession`] with human players, and
+//! the first ply where the session detects something other than the
+//! recorded move is returned as a [`MoveDiff`]. The `simulate` binary runs a whole file, so a change to
+//! detection can be checked against thousands of real games.
+
+use shakmaty::fen::Fen;
+use shakmaty::san::SanPlus;
+use shakmaty::{Bitboard, ByColor, CastlingMode, Chess, Color, Move, Position, Role, Square};
+
+use crate::move_index::captured_square;
+use crate::player::{HumanPlayer, MatcherKind};
+use crate::session::{GameSession, PromotionPolicy};
+use crate::tick_log::MoveDiff;
+
+/// Reads each reading is held for, enough for the settling matcher.
+pub const READS_PER_STEP: u32 = 4;
+
+/// One game of a PGN file.
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub start: Chess,
+    pub moves: Vec<Move>,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// `White - Black`, for reports.
+    pub fn title(&self) -> String {
+        format!(
+            "{} - {}",
+            self.tag("White").unwrap_or("?"),
+            self.tag("Black").unwrap_or("?")
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PgnError {
+    #[error("game {game}: invalid FEN tag {fen:?}")]
+    InvalidFen { game: usize, fen: String },
+    #[error("game {game}, ply {ply}: cannot play {san:?}")]
+    IllegalMove {
+        game: usize,
+        ply: usize,
+        san: String,
+    },
+}
+
+/// Every game in `text`. Comments, variations and annotation glyphs are
+/// skipped; a `FEN` tag sets the starting position.
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut games = Vec::new();
+    let mut tags = Vec::new();
+    let mut movetext = String::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('%') {
+            continue;
+        }
+        if let Some(tag) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if !movetext.trim().is_empty() {
+                games.push(parse_game(games.len() + 1, &tags, &movetext)?);
+                tags.clear();
+                movetext.clear();
+            }
+            if let Some((name, value)) = tag.split_once(' ') {
+                let value = value.trim().trim_matches('"').replace("\\\"", "\"");
+                tags.push((name.to_string(), value));
+            }
+            continue;
+        }
+        movetext.push_str(line);
+        movetext.push('\n');
+    }
+    if !tags.is_empty() || !movetext.trim().is_empty() {
+        games.push(parse_game(games.len() + 1, &tags, &movetext)?);
+    }
+    Ok(games)
+}
+
+fn parse_game(
+    number: usize,
+    tags: &[(String, String)],
+    movetext: &str,
+) -> Result<PgnGame, PgnError> {
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let mode = if tag("Variant").is_some_and(|v| v.eq_ignore_ascii_case("chess960")) {
+        CastlingMode::Chess960
+    } else {
+        CastlingMode::Standard
+    };
+    let start = match tag("FEN") {
+        Some(fen) => fen
+            .parse::<Fen>()
+            .ok()
+            .and_then(|fen| fen.into_position(mode).ok())
+            .ok_or_else(|| PgnError::InvalidFen {
+                game: number,
+                fen: fen.to_string(),
+            })?,
+        None => Chess::default(),
+    };
+    let mut position = start.clone();
+    let mut moves = Vec::new();
+    for token in movetext_tokens(movetext) {
+        if matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*") {
+            break;
+        }
+        let san = token.replace("0-0", "O-O");
+        let mv = SanPlus::from_ascii(san.as_bytes())
+            .ok()
+            .and_then(|san| san.san.to_move(&position).ok())
+            .ok_or_else(|| PgnError::IllegalMove {
+                game: number,
+                ply: moves.len() + 1,
+                san: token.to_string(),
+            })?;
+        position.play_unchecked(mv);
+        moves.push(mv);
+    }
+    Ok(PgnGame {
+        tags: tags.to_vec(),
+        start,
+        moves,
+    })
+}
+
+/// The moves and result of a game's movetext, without move numbers,
+/// comments, variations, glyphs or `!`/`?` annotations.
+fn movetext_tokens(movetext: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    let mut rest = movetext;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '{' => {
+                rest = rest.find('}').map_or("", |end| &rest[end + 1..]);
+                continue;
+            }
+            ';' => {
+                rest = rest.find('\n').map_or("", |end| &rest[end..]);
+                continue;
+            }
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() => {}
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "{;()".contains(c))
+                    .unwrap_or(rest.len());
+                let token = &rest[..end];
+                rest = &rest[end..];
+                if depth == 0 {
+                    tokens.extend(move_token(token));
+                }
+                continue;
+            }
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    tokens
+}
+
+/// `token` without a leading move number or trailing annotation, if a
+/// move or result is left.
+fn move_token(token: &str) -> Option<&str> {
+    if token.starts_with('$') {
+        return None;
+    }
+    let token = match token.rfind('.') {
+        Some(dot) if token.starts_with(|c: char| c.is_ascii_digit()) => &token[dot + 1..],
+        _ => token,
+    };
+    let token = token.trim_end_matches(['!', '?']);
+    (!token.is_empty()).then_some(token)
+}
+
+/// The sensors over the pieces of `position`.
+pub fn sensors(position: &Chess) -> ByColor<Bitboard> {
+    ByColor::new_with(|color| position.board().by_color(color))
+}
+
+/// Readings from `position` through `mv` until the pieces stand as after
+/// it: the piece lifted, what it captures taken off, the piece put down.
+/// Castling lifts king and rook before putting either down (in Chess960
+/// the king may land on its rook's square), and en passant takes the pawn
+/// off last.
+pub fn readings(position: &Chess, mv: &Move) -> Vec<ByColor<Bitboard>> {
+    let us = position.turn();
+    let them = !us;
+    let mut steps: Vec<(Color, Square, bool)> = Vec::new();
+    match (*mv, mv.castling_side()) {
+        (Move::Castle { king, rook }, Some(side)) => {
+            steps.push((us, king, false));
+            steps.push((us, rook, false));
+            steps.push((us, side.king_to(us), true));
+            steps.push((us, side.rook_to(us), true));
+        }
+        (Move::EnPassant { from, to }, _) => {
+            steps.push((us, from, false));
+            steps.push((us, to, true));
+            if let Some(captured) = captured_square(mv) {
+                steps.push((them, captured, false));
+            }
+        }
+        _ => {
+            if let Some(from) = mv.from() {
+                steps.push((us, from, false));
+            }
+            if let Some(captured) = captured_square(mv) {
+                steps.push((them, captured, false));
+            }
+            steps.push((us, mv.to(), true));
+        }
+    }
+    let mut reading = sensors(position);
+    let mut readings = Vec::new();
+    for (color, square, placed) in steps {
+        if placed {
+            reading[color].add(square);
+        } else {
+            reading[color].discard(square);
+        }
+        if readings.last() != Some(&reading) {
+            readings.push(reading);
+        }
+    }
+    readings
+}
+
+fn uci(mv: &Move) -> String {
+    mv.to_uci(CastlingMode::Standard).to_string()
+}
+
+/// Play `game` on a simulated board through a [`GameSession`] with
+/// `matcher`, returning the plies replayed or where detection first
+/// differs from the recorded moves.
+pub fn replay_game(game: &PgnGame, matcher: MatcherKind) -> Result<usize, MoveDiff> {
+    let initial = sensors(&game.start);
+    let mut session = GameSession::builder()
+        .position(game.start.clone())
+        .promotion_policy(PromotionPolicy::ExternalPrompt)
+        .build(
+            Box::new(HumanPlayer::with_matcher(matcher.build(initial))),
+            Box::new(HumanPlayer::with_matcher(matcher.build(initial))),
+        );
+    for (ply, mv) in game.moves.iter().enumerate() {
+        for reading in readings(session.position(), mv) {
+            for _ in 0..READS_PER_STEP {
+                session.tick(reading);
+            }
+        }
+        if session.pending_promotion().is_some() {
+            session.choose_promotion(mv.promotion().unwrap_or(Role::Queen));
+        }
+        let detected = session.moves().get(ply);
+        if detected != Some(mv) {
+            return Err(MoveDiff {
+                ply,
+                expected: Some(uci(mv)),
+                actual: detected.map(uci),
+            });
+        }
+    }
+    Ok(game.moves.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::malformed_inputs;
+
+    const GAMES: &str = r#"[Event "Casual"]
+[White "Anderssen"]
+[Black "Kieseritzky"]
+[Result "1-0"]
+
+1. e4 e5 2. f4 exf4 3. Bc4 Qh4+ {the queen comes out early} 4. Kf1 b5 $2
+5. Bxb5 Nf6 6. Nf3 Qh6 7. d3 Nh5 8. Nh4 Qg5 (8... g6 9. Nf5) 9. Nf5 c6
+10. g4 Nf6 11. Rg1 cxb5 12. h4 Qg6 13. h5 Qg5 14. Qf3 Ng8 15. Bxf4 Qf6
+16. Nc3 Bc5 17. Nd5 Qxb2 18. Bd6 Bxg1 19. e5 Qxa1+ 20. Ke2 Na6 21. Nxg7+ Kd8
+22. Qf6+ Nxf6 23. Be7# 1-0
+
+[White "Promoter"]
+[Black "Castler"]
+[FEN "4k2r/1P6/8/3pP3/8/8/8/4K3 w k d6 0 1"]
+
+1. exd6 O-O 2. b8=N *
+"#;
+
+    #[test]
+    fn games_are_read_with_their_tags_and_moves() {
+        let games = parse_pgn(GAMES).unwrap();
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].title(), "Anderssen - Kieseritzky");
+        assert_eq!(games[0].moves.len(), 45);
+        assert_eq!(uci(&games[0].moves[44]), "d6e7");
+        assert_eq!(
+            games[1].moves.iter().map(uci).collect::<Vec<_>>(),
+            ["e5d6", "e8g8", "b7b8n"]
+        );
+    }
+
+    #[test]
+    fn unplayable_moves_are_reported_with_their_ply() {
+        assert_eq!(
+            parse_pgn("1. e4 e5 2. Ke3 *").unwrap_err(),
+            PgnError::IllegalMove {
+                game: 1,
+                ply: 3,
+                san: "Ke3".into()
+            }
+        );
+        assert!(matches!(
+            parse_pgn("[FEN \"not a fen\"]\n\n*"),
+            Err(PgnError::InvalidFen { game: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn malformed_files_do_not_panic() {
+        for bytes in malformed_inputs(GAMES.as_bytes(), 256, 400) {
+            let _ = parse_pgn(&String::from_utf8_lossy(&bytes));
+        }
+    }
+
+    #[test]
+    fn a_capture_lifts_both_pieces_before_placing_one() {
+        let games = parse_pgn(GAMES).unwrap();
+        let mut position = games[0].start.clone();
+        for mv in &games[0].moves[..3] {
+            position.play_unchecked(*mv);
+        }
+        let exf4 = games[0].moves[3];
+        let steps = readings(&position, &exf4);
+
+        assert_eq!(steps.len(), 3);
+        assert!(!steps[0].black.contains(Square::E5));
+        assert!(!steps[1].white.contains(Square::F4));
+        assert_eq!(
+            *steps.last().unwrap(),
+            sensors(&position.play(exf4).unwrap())
+        );
+    }
+
+    #[test]
+    fn recorded_games_replay_without_divergence() {
+        for game in parse_pgn(GAMES).unwrap() {
+            for matcher in [MatcherKind::Strict, MatcherKind::Settling] {
+                assert_eq!(
+                    replay_game(&game, matcher),
+                    Ok(game.moves.len()),
+                    "{} with {matcher:?}",
+                    game.title()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Checking the board's GPIO assignments before any driver touches them.
+//!
+//! A pin given to two drivers, a strapping pin that changes how the chip
+//! boots, or an input-only pin asked to drive a line fails in confusing
+//! ways long after startup. [`validate`] catches all three, plus pins the
+//! chip lacks or reserves for flash and USB, from the assignment table
+//! alone; the firmware halts on the first error and blinks its
+//! [`PinError::code`] on the status ring (see [`blink_schedule`]).
+
+use std::time::Duration;
+
+/// How a driver uses a pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinMode {
+    Input,
+    Output,
+    /// Both directions, e.g. open-drain I2C lines.
+    Bidirectional,
+    Analog,
+}
+
+impl PinMode {
+    fn drives(self) -> bool {
+        matches!(self, Self::Output | Self::Bidirectional)
+    }
+}
+
+/// One pin handed to one driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinAssignment {
+    /// What the pin is for, e.g. `"mux S0"`.
+    pub function: &'static str,
+    pub gpio: u8,
+    pub mode: PinMode,
+}
+
+impl PinAssignment {
+    pub const fn new(function: &'static str, gpio: u8, mode: PinMode) -> Self {
+        Self {
+            function,
+            gpio,
+            mode,
+        }
+    }
+}
+
+/// The GPIOs of a chip and the ones that must be left alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChipPins {
+    pub name: &'static str,
+    /// GPIO numbers run from 0 to `count - 1`, minus `missing`.
+    pub count: u8,
+    pub missing: &'static [u8],
+    /// Sampled at reset to pick the boot mode and flash voltage.
+    pub strapping: &'static [u8],
+    /// No output driver.
+    pub input_only: &'static [u8],
+    /// Wired to the flash, PSRAM or USB on the module.
+    pub reserved: &'static [u8],
+}
+
+/// ESP32-S3 on a module with octal flash and PSRAM.
+pub const ESP32_S3: ChipPins = ChipPins {
+    name: "ESP32-S3",
+    count: 49,
+    missing: &[22, 23, 24, 25],
+    strapping: &[0, 3, 45, 46],
+    input_only: &[],
+    reserved: &[19, 20, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37],
+};
+
+/// The original ESP32, whose GPIOs 34–39 are input only.
+pub const ESP32: ChipPins = ChipPins {
+    name: "ESP32",
+    count: 40,
+    missing: &[20, 24, 28, 29, 30, 31],
+    strapping: &[0, 2, 5, 12, 15],
+    input_only: &[34, 35, 36, 37, 38, 39],
+    reserved: &[6, 7, 8, 9, 10, 11],
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum PinError {
+    #[error("GPIO{gpio} ({function}) does not exist on the {chip}")]
+    NoSuchPin {
+        gpio: u8,
+        function: &'static str,
+        chip: &'static str,
+    },
+    #[error("GPIO{gpio} is assigned to both {first} and {second}")]
+    Conflict {
+        gpio: u8,
+        first: &'static str,
+        second: &'static str,
+    },
+    #[error("GPIO{gpio} ({function}) is a strapping pin")]
+    Strapping { gpio: u8, function: &'static str },
+    #[error("GPIO{gpio} ({function}) is input only and cannot drive a line")]
+    InputOnly { gpio: u8, function: &'static str },
+    #[error("GPIO{gpio} ({function}) is reserved for flash, PSRAM or USB")]
+    Reserved { gpio: u8, function: &'static str },
+}
+
+impl PinError {
+    /// Number of blinks that identifies the error on the status ring.
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::NoSuchPin { .. } => 1,
+            Self::Conflict { .. } => 2,
+            Self::Strapping { .. } => 3,
+            Self::InputOnly { .. } => 4,
+            Self::Reserved { .. } => 5,
+        }
+    }
+}
+
+/// Check `assignments` against `chip`, returning the first problem in
+/// table order.
+pub fn validate(assignments: &[PinAssignment], chip: &ChipPins) -> Result<(), PinError> {
+    for (i, pin) in assignments.iter().enumerate() {
+        let &PinAssignment {
+            function,
+            gpio,
+            mode,
+        } = pin;
+        if gpio >= chip.count || chip.missing.contains(&gpio) {
+            return Err(PinError::NoSuchPin {
+                gpio,
+                function,
+                chip: chip.name,
+            });
+        }
+        if let Some(first) = assignments[..i].iter().find(|other| other.gpio == gpio) {
+            return Err(PinError::Conflict {
+                gpio,
+                first: first.function,
+                second: function,
+            });
+        }
+        if chip.reserved.contains(&gpio) {
+            return Err(PinError::Reserved { gpio, function });
+        }
+        if chip.strapping.contains(&gpio) {
+            return Err(PinError::Strapping { gpio, function });
+        }
+        if mode.drives() && chip.input_only.contains(&gpio) {
+            return Err(PinError::InputOnly { gpio, function });
+        }
+    }
+    Ok(())
+}
+
+/// How long each blink is lit, and the gap before the next.
+pub const BLINK: Duration = Duration::from_millis(300);
+
+/// Dark time between repetitions of the code.
+pub const BLINK_PAUSE: Duration = Duration::from_millis(1500);
+
+/// One repetition of `code` as (lit, how long) steps: `code` blinks, then
+/// a pause. Repeat it for as long as the board is halted.
+pub fn blink_schedule(code: u8) -> impl Iterator<Item = (bool, Duration)> {
+    (0..code)
+        .flat_map(|_| [(true, BLINK), (false, BLINK)])
+        .chain([(false, BLINK_PAUSE)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: [PinAssignment; 3] = [
+        PinAssignment::new("LED data", 2, PinMode::Output),
+        PinAssignment::new("mux S0", 4, PinMode::Output),
+        PinAssignment::new("mux 1 signal", 9, PinMode::Analog),
+    ];
+
+    fn with(pin: PinAssignment) -> Vec<PinAssignment> {
+        let mut pins = BOARD.to_vec();
+        pins.push(pin);
+        pins
+    }
+
+    #[test]
+    fn each_problem_is_reported_with_its_code() {
+        assert_eq!(validate(&BOARD, &ESP32_S3), Ok(()));
+
+        let cases = [
+            (
+                with(PinAssignment::new("button", 23, PinMode::Input)),
+                PinError::NoSuchPin {
+                    gpio: 23,
+                    function: "button",
+                    chip: "ESP32-S3",
+                },
+                1,
+            ),
+            (
+                with(PinAssignment::new("OLED SDA", 4, PinMode::Bidirectional)),
+                PinError::Conflict {
+                    gpio: 4,
+                    first: "mux S0",
+                    second: "OLED SDA",
+                },
+                2,
+            ),
+            (
+                with(PinAssignment::new("button", 0, PinMode::Input)),
+                PinError::Strapping {
+                    gpio: 0,
+                    function: "button",
+                },
+                3,
+            ),
+            (
+                with(PinAssignment::new("OLED SCL", 30, PinMode::Output)),
+                PinError::Reserved {
+                    gpio: 30,
+                    function: "OLED SCL",
+                },
+                5,
+            ),
+        ];
+        for (pins, error, code) in cases {
+            assert_eq!(validate(&pins, &ESP32_S3), Err(error));
+            assert_eq!(error.code(), code);
+        }
+    }
+
+    #[test]
+    fn input_only_pins_may_not_drive() {
+        let sensor = [PinAssignment::new("sensor", 34, PinMode::Analog)];
+        let led = [PinAssignment::new("LED data", 34, PinMode::Output)];
+
+        assert_eq!(validate(&sensor, &ESP32), Ok(()));
+        let error = PinError::InputOnly {
+            gpio: 34,
+            function: "LED data",
+        };
+        assert_eq!(validate(&led, &ESP32), Err(error));
+        assert_eq!(error.code(), 4);
+    }
+
+    #[test]
+    fn codes_blink_once_per_unit_then_pause() {
+        let schedule: Vec<_> = blink_schedule(2).collect();
+
+        assert_eq!(
+            schedule,
+            [
+                (true, BLINK),
+                (false, BLINK),
+                (true, BLINK),
+                (false, BLINK),
+                (false, BLINK_PAUSE)
+            ]
+        );
+    }
+}
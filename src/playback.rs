@@ -0,0 +1,297 @@
+//! Step through a flight recording and what the engine made of it.
+//!
+//! [`Playback`] replays the frames of a recording copied off a board (see
+//! [`crate::flight_recorder`]) through a human-vs-human game, like
+//! `replay-log`, but keeps the game state after every frame: the position,
+//! the feedback the board would have shown, and the moves detected. The
+//! desktop terminal's `open` command scrubs through it as a timeline, so a
+//! field capture can be inspected reading by reading.
+//!
+//! A recording spans many games. A game is followed from the first time
+//! the starting position is seen, and a new one starts whenever the
+//! starting position is set up again after a finished game or with the
+//! pieces reset mid-game. Until then, frames show the setup feedback the
+//! board lights while waiting for pieces.
+
+use std::time::Duration;
+
+use shakmaty::{Bitboard, ByColor, Chess, Color, Move, Position};
+
+use crate::compress::{self, DecompressError};
+use crate::feedback::BoardFeedback;
+use crate::flight_recorder::{RecordedFrame, RecorderError, is_recording, read_recording};
+use crate::player::{HumanPlayer, MatcherKind};
+use crate::session::GameSession;
+use crate::setup::placement_feedback;
+
+/// Most reads a frame is fed for, however long it was held; long enough
+/// for any matcher to settle.
+const MAX_HELD_READS: u64 = 20;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PlaybackError {
+    #[error(transparent)]
+    Decompress(#[from] DecompressError),
+    #[error(transparent)]
+    Recording(#[from] RecorderError),
+    #[error("not a flight recording")]
+    NotARecording,
+    #[error("the recording has no frames")]
+    Empty,
+}
+
+/// One recorded reading and the game state after it.
+#[derive(Debug, Clone)]
+pub struct PlaybackFrame {
+    /// Time since boot when the reading was first seen.
+    pub time: Duration,
+    pub readings: ByColor<Bitboard>,
+    /// The game position after the reading; the starting position while
+    /// no game is followed.
+    pub position: Chess,
+    /// What the board would have shown.
+    pub feedback: BoardFeedback,
+    /// Moves detected on this reading.
+    pub moves: Vec<Move>,
+    /// Whether a game was being followed.
+    pub in_game: bool,
+}
+
+/// A replayed recording with a cursor on one of its frames.
+#[derive(Debug, Clone)]
+pub struct Playback {
+    frames: Vec<PlaybackFrame>,
+    cursor: usize,
+}
+
+impl Playback {
+    /// Read a flight recording, optionally packed with [`compress`], and
+    /// replay it with `matcher`.
+    pub fn load(bytes: &[u8], matcher: MatcherKind) -> Result<Self, PlaybackError> {
+        let unpacked;
+        let mut bytes = bytes;
+        if compress::is_compressed(bytes) {
+            unpacked = compress::decompress(bytes)?;
+            bytes = &unpacked;
+        }
+        if !is_recording(bytes) {
+            return Err(PlaybackError::NotARecording);
+        }
+        Self::replay(&read_recording(bytes)?, matcher)
+    }
+
+    /// Replay `frames` with `matcher`, the cursor on the first frame.
+    pub fn replay(frames: &[RecordedFrame], matcher: MatcherKind) -> Result<Self, PlaybackError> {
+        if frames.is_empty() {
+            return Err(PlaybackError::Empty);
+        }
+        let start = Chess::default();
+        let start_readings = board_positions(&start);
+        let mut session: Option<GameSession> = None;
+        let mut replayed = Vec::with_capacity(frames.len());
+        for (i, frame) in frames.iter().enumerate() {
+            let readings = frame.tick.positions;
+            let restarted = readings == start_readings
+                && session.as_ref().is_none_or(|s| {
+                    s.is_game_over() || board_positions(s.position()) != start_readings
+                });
+            if restarted {
+                session = Some(GameSession::new(
+                    Box::new(HumanPlayer::with_matcher(matcher.build(readings))),
+                    Box::new(HumanPlayer::with_matcher(matcher.build(readings))),
+                ));
+            }
+            let Some(session) = &mut session else {
+                let feedback = placement_feedback(&start_readings, &readings).unwrap_or_default();
+                replayed.push(PlaybackFrame {
+                    time: frame.time,
+                    readings,
+                    position: start.clone(),
+                    feedback,
+                    moves: Vec::new(),
+                    in_game: false,
+                });
+                continue;
+            };
+            let held = frames
+                .get(i + 1)
+                .map_or(MAX_HELD_READS, |next| {
+                    next.tick.read.saturating_sub(frame.tick.read)
+                })
+                .clamp(1, MAX_HELD_READS);
+            let mut moves = Vec::new();
+            let mut feedback = BoardFeedback::new();
+            for _ in 0..held {
+                let result = session.tick(readings);
+                moves.extend(result.last_move);
+                feedback = result.feedback;
+            }
+            replayed.push(PlaybackFrame {
+                time: frame.time,
+                readings,
+                position: session.position().clone(),
+                feedback,
+                moves,
+                in_game: true,
+            });
+        }
+        Ok(Self {
+            frames: replayed,
+            cursor: 0,
+        })
+    }
+
+    pub fn frames(&self) -> &[PlaybackFrame] {
+        &self.frames
+    }
+
+    /// Index of the frame under the cursor.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn current(&self) -> &PlaybackFrame {
+        &self.frames[self.cursor]
+    }
+
+    /// Put the cursor on frame `index`, or the last frame past the end.
+    pub fn seek(&mut self, index: usize) {
+        self.cursor = index.min(self.frames.len() - 1);
+    }
+
+    /// Move the cursor `frames` forward, or back with a negative count,
+    /// stopping at either end.
+    pub fn step(&mut self, frames: isize) {
+        self.seek(self.cursor.saturating_add_signed(frames));
+    }
+
+    /// Move the cursor to the next frame, in `direction` (+1 or -1), on
+    /// which a move was detected. Returns whether there was one.
+    pub fn step_to_move(&mut self, direction: isize) -> bool {
+        let mut index = self.cursor;
+        while let Some(next) = index.checked_add_signed(direction)
+            && next < self.frames.len()
+        {
+            index = next;
+            if !self.frames[index].moves.is_empty() {
+                self.cursor = index;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+fn board_positions(position: &Chess) -> ByColor<Bitboard> {
+    ByColor {
+        white: position.board().by_color(Color::White),
+        black: position.board().by_color(Color::Black),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feedback::SquareFeedback;
+    use crate::flight_recorder::FlightRecorder;
+    use crate::tick_log::Tick;
+    use shakmaty::Square;
+    use std::io::Cursor;
+
+    /// Frames 50 ms and one read apart, from toggling `script`'s squares
+    /// one batch at a time on the starting position. A piece put on an
+    /// empty square is black if a black piece is lifted.
+    fn frames(script: &[&[Square]]) -> Vec<RecordedFrame> {
+        let mut positions = board_positions(&Chess::default());
+        let mut frames = Vec::new();
+        for (i, toggles) in script.iter().enumerate() {
+            for &square in *toggles {
+                let black_lifted = positions.black.count() < 16;
+                let color = if positions.black.contains(square)
+                    || !positions.white.contains(square) && black_lifted
+                {
+                    Color::Black
+                } else {
+                    Color::White
+                };
+                positions[color].toggle(square);
+            }
+            frames.push(RecordedFrame {
+                time: Duration::from_millis(i as u64 * 50),
+                tick: Tick {
+                    read: i as u64,
+                    positions,
+                },
+            });
+        }
+        frames
+    }
+
+    #[test]
+    fn frames_keep_the_feedback_and_moves_of_each_reading() {
+        let script: &[&[Square]] = &[&[], &[Square::E2], &[Square::E4], &[Square::E7]];
+        let playback = Playback::replay(&frames(script), MatcherKind::Strict).unwrap();
+
+        let lifted = &playback.frames()[1];
+        assert_eq!(
+            lifted.feedback.get(Square::E4),
+            Some(SquareFeedback::Destination)
+        );
+        assert!(lifted.moves.is_empty());
+        assert_eq!(playback.frames()[2].moves.len(), 1);
+        assert_eq!(playback.frames()[3].position.turn(), Color::Black);
+    }
+
+    #[test]
+    fn frames_before_the_starting_position_show_setup() {
+        let script: &[&[Square]] = &[&[Square::A1], &[Square::A1], &[Square::E2]];
+        let playback = Playback::replay(&frames(script), MatcherKind::Strict).unwrap();
+
+        let setup = &playback.frames()[0];
+        assert!(!setup.in_game);
+        assert_eq!(
+            setup.feedback.get(Square::A1),
+            Some(SquareFeedback::Destination)
+        );
+        assert!(playback.frames()[1].in_game);
+    }
+
+    #[test]
+    fn cursor_steps_seeks_and_jumps_between_moves() {
+        let script: &[&[Square]] = &[
+            &[],
+            &[Square::E2],
+            &[Square::E4],
+            &[Square::E7],
+            &[Square::E5],
+        ];
+        let mut playback = Playback::replay(&frames(script), MatcherKind::Strict).unwrap();
+
+        assert!(playback.step_to_move(1));
+        assert_eq!(playback.cursor(), 2);
+        assert!(playback.step_to_move(1));
+        assert_eq!(playback.cursor(), 4);
+        assert!(!playback.step_to_move(1));
+        playback.step(-10);
+        assert_eq!(playback.cursor(), 0);
+        playback.seek(99);
+        assert_eq!(playback.cursor(), 4);
+    }
+
+    #[test]
+    fn load_reads_a_recorder_file() {
+        let mut recorder = FlightRecorder::open(Cursor::new(Vec::new()), 4).unwrap();
+        for frame in frames(&[&[], &[Square::E2], &[Square::E4]]) {
+            recorder.record(frame).unwrap();
+        }
+        recorder.flush().unwrap();
+        let bytes = recorder.into_inner().into_inner();
+
+        let playback = Playback::load(&bytes, MatcherKind::Strict).unwrap();
+        assert_eq!(playback.frames().len(), 3);
+        assert!(matches!(
+            Playback::load(b"not a recording", MatcherKind::Strict),
+            Err(PlaybackError::NotARecording)
+        ));
+    }
+}
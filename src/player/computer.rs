@@ -0,0 +1,154 @@
+use shakmaty::{Bitboard, ByColor, Chess, Move, Position, Role};
+
+use super::Player;
+use crate::rng::XorShift32;
+
+/// Strongest [`ComputerPlayer`] level.
+pub const MAX_LEVEL: u8 = 3;
+
+/// Score of a checkmate, above any material balance.
+const MATE: i32 = 100_000;
+
+/// The board's built-in opponent: looks `level` moves ahead, counting
+/// material only, and picks randomly among equally good moves.
+///
+/// Level 0 plays random moves like [`super::RandomPlayer`]; level 1 takes
+/// free material, level 2 also sees the reply. The level is capped at
+/// [`MAX_LEVEL`] to keep each search short.
+#[derive(Debug)]
+pub struct ComputerPlayer {
+    level: u8,
+    rng: XorShift32,
+}
+
+impl ComputerPlayer {
+    pub fn new(level: u8, seed: u32) -> Self {
+        Self {
+            level: level.min(MAX_LEVEL),
+            rng: XorShift32::new(seed),
+        }
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+}
+
+impl Player for ComputerPlayer {
+    fn poll_move(&mut self, position: &Chess, _sensors: ByColor<Bitboard>) -> Option<Move> {
+        let moves = position.legal_moves();
+        if self.level == 0 {
+            let index = self.rng.below(moves.len() as u32) as usize;
+            return moves.get(index).copied();
+        }
+        let mut best = Vec::new();
+        let mut best_score = i32::MIN;
+        for mv in moves {
+            let mut after = position.clone();
+            after.play_unchecked(mv);
+            let score = -negamax(&after, self.level - 1, -MATE - 1, MATE + 1);
+            if score > best_score {
+                best_score = score;
+                best.clear();
+            }
+            if score == best_score {
+                best.push(mv);
+            }
+        }
+        let index = self.rng.below(best.len() as u32) as usize;
+        best.get(index).copied()
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+/// Best material balance for the side to move, searching `depth` plies.
+fn negamax(position: &Chess, depth: u8, mut alpha: i32, beta: i32) -> i32 {
+    let moves = position.legal_moves();
+    if moves.is_empty() {
+        return if position.is_check() { -MATE } else { 0 };
+    }
+    if depth == 0 {
+        return material(position);
+    }
+    for mv in moves {
+        let mut after = position.clone();
+        after.play_unchecked(mv);
+        let score = -negamax(&after, depth - 1, -beta, -alpha);
+        if score >= beta {
+            return beta;
+        }
+        alpha = alpha.max(score);
+    }
+    alpha
+}
+
+/// Material of the side to move minus the opponent's, in centipawns.
+fn material(position: &Chess) -> i32 {
+    let board = position.board();
+    let us = position.turn();
+    Role::ALL
+        .into_iter()
+        .map(|role| {
+            let value = match role {
+                Role::Pawn => 100,
+                Role::Knight | Role::Bishop => 300,
+                Role::Rook => 500,
+                Role::Queen => 900,
+                Role::King => 0,
+            };
+            let ours = (board.by_role(role) & board.by_color(us)).count() as i32;
+            let theirs = (board.by_role(role) & board.by_color(!us)).count() as i32;
+            value * (ours - theirs)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::CastlingMode;
+    use shakmaty::fen::Fen;
+
+    fn position(fen: &str) -> Chess {
+        fen.parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap()
+    }
+
+    fn best(level: u8, fen: &str) -> String {
+        let mv = ComputerPlayer::new(level, 1)
+            .poll_move(&position(fen), ByColor::default())
+            .unwrap();
+        mv.to_uci(CastlingMode::Standard).to_string()
+    }
+
+    #[test]
+    fn level_one_takes_free_material() {
+        // Black queen en prise to the e4 knight.
+        assert_eq!(best(1, "4k3/8/5q2/8/4N3/8/8/4K3 w - - 0 1"), "e4f6");
+    }
+
+    #[test]
+    fn level_two_sees_a_defended_piece() {
+        // The d5 pawn is defended by the e6 pawn: taking it loses the queen.
+        let fen = "4k3/8/4p3/3p4/8/8/3Q4/4K3 w - - 0 1";
+        assert_eq!(best(1, fen), "d2d5");
+        assert_ne!(best(2, fen), "d2d5");
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        assert_eq!(best(1, "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1"), "a1a8");
+    }
+
+    #[test]
+    fn level_zero_plays_random_legal_moves_and_levels_are_capped() {
+        assert_eq!(ComputerPlayer::new(9, 1).level(), MAX_LEVEL);
+        let mv = ComputerPlayer::new(0, 1).poll_move(&Chess::default(), ByColor::default());
+        assert!(mv.is_some_and(|mv| Chess::default().legal_moves().contains(&mv)));
+    }
+}
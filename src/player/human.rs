@@ -1,63 +1,33 @@
-use shakmaty::{Bitboard, ByColor, Chess, Move, Position, Role};
+use shakmaty::{Bitboard, ByColor, Chess, Move};
 
 use super::Player;
+use super::matcher::{MoveMatcher, StrictMatcher};
 
 /// Human player that detects moves from physical board sensor state.
 ///
 /// Compares sensor bitboards against the chess position's expected board
-/// to find which legal move matches the physical piece placement.
+/// to find which legal move matches the physical piece placement. How a
+/// reading is matched is up to the [`MoveMatcher`].
 #[derive(Debug)]
 pub struct HumanPlayer {
-    last_sensors: ByColor<Bitboard>,
+    matcher: Box<dyn MoveMatcher>,
 }
 
 impl HumanPlayer {
-    /// Create a new human player with the initial sensor state.
+    /// Create a new human player with the initial sensor state, matching
+    /// moves with a [`StrictMatcher`].
     pub fn new(initial_sensors: ByColor<Bitboard>) -> Self {
-        Self {
-            last_sensors: initial_sensors,
-        }
+        Self::with_matcher(Box::new(StrictMatcher::new(initial_sensors)))
+    }
+
+    pub fn with_matcher(matcher: Box<dyn MoveMatcher>) -> Self {
+        Self { matcher }
     }
 }
 
 impl Player for HumanPlayer {
     fn poll_move(&mut self, position: &Chess, sensors: ByColor<Bitboard>) -> Option<Move> {
-        if sensors == self.last_sensors {
-            return None;
-        }
-        self.last_sensors = sensors;
-
-        let turn = position.turn();
-        let expected_our = position.board().by_color(turn);
-        let our_current = sensors[turn];
-        let our_placed = our_current & !expected_our;
-
-        if our_placed.is_empty() {
-            return None;
-        }
-
-        let current_combined = sensors.white | sensors.black;
-
-        for mv in position.legal_moves() {
-            // Castling: mv.to() is the rook origin, not king destination,
-            // so skip the destination pre-filter.
-            if !matches!(mv, Move::Castle { .. }) && !our_placed.contains(mv.to()) {
-                continue;
-            }
-
-            // Only allow queen promotions (no piece-selection mechanism on hardware).
-            if mv.promotion().is_some_and(|role| role != Role::Queen) {
-                continue;
-            }
-
-            let mut after = position.clone();
-            after.play_unchecked(mv);
-            if after.board().occupied() == current_combined {
-                return Some(mv);
-            }
-        }
-
-        None
+        self.matcher.match_move(position, sensors)
     }
 }
 
@@ -65,7 +35,7 @@ impl Player for HumanPlayer {
 mod tests {
     use super::*;
     use crate::testutil::ScriptedSensor;
-    use shakmaty::{Chess, Color, Square};
+    use shakmaty::{Chess, Color, Position, Role, Square};
 
     fn position_from_fen(fen: &str) -> Chess {
         use shakmaty::{CastlingMode, fen::Fen};
@@ -0,0 +1,239 @@
+//! Strategies for turning sensor readings into moves.
+//!
+//! [`HumanPlayer`](super::HumanPlayer) delegates move detection to a
+//! [`MoveMatcher`], so matching policy can be swapped at runtime and
+//! compared on recorded tick logs (see [`crate::tick_log`]).
+
+use std::fmt::Debug;
+
+use shakmaty::{Bitboard, ByColor, Chess, Move, Position, Role, attacks};
+
+/// Readings a [`SettlingMatcher`] waits for by default (150 ms at the
+/// 50 ms tick rate).
+pub const DEFAULT_SETTLE_TICKS: u32 = 3;
+
+/// Decides which legal move, if any, the current sensor reading shows.
+pub trait MoveMatcher: Debug {
+    /// Called every tick while the owning player is to move.
+    fn match_move(&mut self, position: &Chess, sensors: ByColor<Bitboard>) -> Option<Move>;
+}
+
+/// Available matchers, for selecting one at runtime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatcherKind {
+    #[default]
+    Strict,
+    Settling,
+}
+
+impl MatcherKind {
+    pub fn build(self, initial_sensors: ByColor<Bitboard>) -> Box<dyn MoveMatcher> {
+        match self {
+            MatcherKind::Strict => Box::new(StrictMatcher::new(initial_sensors)),
+            MatcherKind::Settling => Box::new(SettlingMatcher::new(DEFAULT_SETTLE_TICKS)),
+        }
+    }
+}
+
+/// The legal move whose resulting occupancy equals the reading, if the
+/// side to move has placed a piece on a new square.
+///
+/// Only queen promotions are considered (no piece selection on hardware).
+pub fn find_move(position: &Chess, sensors: ByColor<Bitboard>) -> Option<Move> {
+    let turn = position.turn();
+    let expected_our = position.board().by_color(turn);
+    let our_placed = sensors[turn] & !expected_our;
+
+    if our_placed.is_empty() {
+        return None;
+    }
+
+    let current_combined = sensors.white | sensors.black;
+
+    position.legal_moves().into_iter().find(|&mv| {
+        // Castling: mv.to() is the rook origin, not king destination,
+        // so skip the destination pre-filter.
+        if !matches!(mv, Move::Castle { .. }) && !our_placed.contains(mv.to()) {
+            return false;
+        }
+        if mv.promotion().is_some_and(|role| role != Role::Queen) {
+            return false;
+        }
+        let mut after = position.clone();
+        after.play_unchecked(mv);
+        after.board().occupied() == current_combined
+    })
+}
+
+/// Whether the reading that matched `mv` may show a piece still on its way
+/// somewhere else: the moving piece passes over `mv.to()` on the way to
+/// another of its legal destinations (a slide that stopped early, a king
+/// half-way to castling).
+pub fn is_uncertain(position: &Chess, mv: &Move) -> bool {
+    let Some(from) = mv.from() else {
+        return false;
+    };
+    if matches!(mv, Move::Castle { .. }) {
+        return false;
+    }
+    position.legal_moves().iter().any(|other| {
+        other.from() == Some(from)
+            && other.to() != mv.to()
+            && attacks::between(from, other.to()).contains(mv.to())
+    })
+}
+
+/// Plays a move on the first reading that matches it.
+///
+/// Fast, but a piece slid across the board can briefly match a shorter
+/// move on the way.
+#[derive(Debug)]
+pub struct StrictMatcher {
+    last_sensors: ByColor<Bitboard>,
+}
+
+impl StrictMatcher {
+    pub fn new(initial_sensors: ByColor<Bitboard>) -> Self {
+        Self {
+            last_sensors: initial_sensors,
+        }
+    }
+}
+
+impl MoveMatcher for StrictMatcher {
+    fn match_move(&mut self, position: &Chess, sensors: ByColor<Bitboard>) -> Option<Move> {
+        if sensors == self.last_sensors {
+            return None;
+        }
+        self.last_sensors = sensors;
+        find_move(position, sensors)
+    }
+}
+
+/// Plays a move only once the same matching reading has been seen for
+/// several consecutive ticks, riding out pieces in transit and sensor
+/// bounce.
+#[derive(Debug)]
+pub struct SettlingMatcher {
+    settle_ticks: u32,
+    /// The reading currently matching a move, and for how many ticks.
+    candidate: Option<(ByColor<Bitboard>, Move, u32)>,
+}
+
+impl SettlingMatcher {
+    pub fn new(settle_ticks: u32) -> Self {
+        Self {
+            settle_ticks: settle_ticks.max(1),
+            candidate: None,
+        }
+    }
+}
+
+impl MoveMatcher for SettlingMatcher {
+    fn match_move(&mut self, position: &Chess, sensors: ByColor<Bitboard>) -> Option<Move> {
+        let Some(mv) = find_move(position, sensors) else {
+            self.candidate = None;
+            return None;
+        };
+        let seen = match self.candidate {
+            Some((reading, candidate, seen)) if reading == sensors && candidate == mv => seen + 1,
+            _ => 1,
+        };
+        if seen >= self.settle_ticks {
+            self.candidate = None;
+            return Some(mv);
+        }
+        self.candidate = Some((sensors, mv, seen));
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::{Color, Square};
+
+    fn reading(position: &Chess) -> ByColor<Bitboard> {
+        let board = position.board();
+        ByColor {
+            white: board.by_color(Color::White),
+            black: board.by_color(Color::Black),
+        }
+    }
+
+    fn white_moved(position: &Chess, from: Square, to: Square) -> ByColor<Bitboard> {
+        let mut sensors = reading(position);
+        sensors.white.discard(from);
+        sensors.white.add(to);
+        sensors
+    }
+
+    /// The rook on a1 slides through a3 on its way to a4.
+    fn sliding_rook() -> (Chess, [ByColor<Bitboard>; 2]) {
+        use shakmaty::{CastlingMode, fen::Fen};
+        let position: Chess = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        let mut via = reading(&position);
+        via.white.discard(Square::A1);
+        let mut to = via;
+        via.white.add(Square::A3);
+        to.white.add(Square::A4);
+        (position, [via, to])
+    }
+
+    #[test]
+    fn stopping_short_of_other_destinations_is_uncertain() {
+        let (position, [via, to]) = sliding_rook();
+        let short = find_move(&position, via).unwrap();
+        let long = find_move(&position, to).unwrap();
+
+        assert!(is_uncertain(&position, &short));
+        assert!(is_uncertain(&position, &long), "a5 to a8 lie beyond");
+
+        let start = Chess::default();
+        let single_push = find_move(&start, white_moved(&start, Square::E2, Square::E3)).unwrap();
+        let double_push = find_move(&start, white_moved(&start, Square::E2, Square::E4)).unwrap();
+        let knight = find_move(&start, white_moved(&start, Square::G1, Square::F3)).unwrap();
+        assert!(is_uncertain(&start, &single_push));
+        assert!(!is_uncertain(&start, &double_push));
+        assert!(!is_uncertain(&start, &knight));
+    }
+
+    #[test]
+    fn strict_matcher_takes_the_first_match() {
+        let (position, [via, _]) = sliding_rook();
+        let mut matcher = StrictMatcher::new(reading(&position));
+
+        let mv = matcher.match_move(&position, via).unwrap();
+
+        assert_eq!(mv.to(), Square::A3);
+    }
+
+    #[test]
+    fn settling_matcher_ignores_squares_passed_through() {
+        let (position, [via, to]) = sliding_rook();
+        let mut matcher = SettlingMatcher::new(3);
+
+        assert_eq!(matcher.match_move(&position, via), None);
+        assert_eq!(matcher.match_move(&position, to), None);
+        assert_eq!(matcher.match_move(&position, to), None);
+        let mv = matcher.match_move(&position, to).unwrap();
+
+        assert_eq!(mv.to(), Square::A4);
+    }
+
+    #[test]
+    fn settling_matcher_resets_when_the_reading_changes() {
+        let (position, [via, to]) = sliding_rook();
+        let mut matcher = SettlingMatcher::new(2);
+
+        matcher.match_move(&position, to);
+        matcher.match_move(&position, via);
+
+        assert_eq!(matcher.match_move(&position, to), None);
+        assert!(matcher.match_move(&position, to).is_some());
+    }
+}
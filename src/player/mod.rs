@@ -1,11 +1,21 @@
+mod computer;
 mod human;
+pub mod matcher;
+mod random;
 mod remote;
+mod uci;
 
+pub use computer::{ComputerPlayer, MAX_LEVEL};
 pub use human::HumanPlayer;
+pub use matcher::MatcherKind;
+pub use random::RandomPlayer;
 pub use remote::RemotePlayer;
+pub use uci::{DEFAULT_MOVETIME, UciEngine, UciError};
 
 use shakmaty::{Bitboard, ByColor, Chess, Color, Move};
 
+use crate::feedback::BoardFeedback;
+
 /// A game-level action initiated by a player or the session.
 ///
 /// These are "meta game" actions (resign, draw offers, takebacks) that
@@ -15,7 +25,12 @@ use shakmaty::{Bitboard, ByColor, Chess, Color, Move};
 pub enum GameAction {
     /// A player resigned. Contains the color of the resigning player.
     Resign(Color),
-    // Future: OfferDraw(Color), AcceptDraw(Color), RequestTakeback(Color), AcceptTakeback(Color)
+    /// The last move was taken back. Contains the move.
+    Takeback(Move),
+    /// The player to move claimed a threefold repetition or 50-move draw.
+    /// Contains their color.
+    DrawClaimed(Color),
+    // Future: OfferDraw(Color), AcceptDraw(Color)
 }
 
 /// Player health status, checked by the session each tick.
@@ -30,6 +45,12 @@ pub enum PlayerStatus {
 }
 
 /// A chess player — either human (detecting moves from sensors) or computer (computing moves).
+///
+/// This is the one interface for wherever a side's moves come from: the
+/// hand on the board ([`HumanPlayer`]), a client ([`RemotePlayer`]), the
+/// board itself ([`RandomPlayer`]) or a scripted line in tests. The
+/// session and game modes only talk to this trait, so they are agnostic of
+/// who the opponent is.
 pub trait Player {
     /// Return a move if one is detected/ready. Called every tick for the active player.
     ///
@@ -58,6 +79,23 @@ pub trait Player {
         true
     }
 
+    /// Whether a move can be taken back with this player in the game.
+    ///
+    /// Players that mirror a game kept elsewhere (Lichess, another board)
+    /// cannot follow a takeback, so [`crate::session::GameSession::undo_last_move`]
+    /// refuses it while they play.
+    fn allows_takeback(&self) -> bool {
+        true
+    }
+
+    /// What the board shows while this player reports
+    /// [`PlayerStatus::Error`], when the error is one the user can fix
+    /// (e.g. a [`crate::tls::CertificateError`]). Default is `None`, which
+    /// shows only the failure status over the game.
+    fn error_feedback(&self) -> Option<BoardFeedback> {
+        None
+    }
+
     /// Notification of a game-level action (resign, draw offer, etc.).
     ///
     /// Override for async players (e.g. Lichess) that need to forward
@@ -0,0 +1,86 @@
+use shakmaty::{Bitboard, ByColor, Chess, Move, Position};
+
+use super::Player;
+use crate::rng::XorShift32;
+
+/// A player that plays a uniformly random legal move as soon as it is its
+/// turn.
+///
+/// A gentle first opponent for beginners, and a cheap way to drive full
+/// games through the session in soak tests. Seeded, so a game can be
+/// reproduced.
+#[derive(Debug)]
+pub struct RandomPlayer {
+    rng: XorShift32,
+}
+
+impl RandomPlayer {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            rng: XorShift32::new(seed),
+        }
+    }
+}
+
+impl Player for RandomPlayer {
+    fn poll_move(&mut self, position: &Chess, _sensors: ByColor<Bitboard>) -> Option<Move> {
+        let moves = position.legal_moves();
+        let index = self.rng.below(moves.len() as u32) as usize;
+        moves.get(index).copied()
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::GameSession;
+
+    fn play_out(seed: u32) -> GameSession {
+        let mut session = GameSession::new(
+            Box::new(RandomPlayer::new(seed)),
+            Box::new(RandomPlayer::new(seed.wrapping_add(1))),
+        );
+        let sensors = ByColor::default();
+        while !session.is_game_over() {
+            session.tick(sensors);
+        }
+        session
+    }
+
+    #[test]
+    fn plays_legal_moves_until_the_game_ends() {
+        for seed in 0..20 {
+            let session = play_out(seed);
+            let mut replay = Chess::default();
+            for mv in session.moves() {
+                assert!(replay.legal_moves().contains(mv), "seed {seed}: {mv}");
+                replay.play_unchecked(*mv);
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        assert_eq!(play_out(7).moves(), play_out(7).moves());
+    }
+
+    #[test]
+    fn no_move_without_legal_moves() {
+        use shakmaty::CastlingMode;
+        use shakmaty::fen::Fen;
+        let stalemate: Chess = "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+
+        assert_eq!(
+            RandomPlayer::new(1).poll_move(&stalemate, ByColor::default()),
+            None
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use shakmaty::fen::Fen;
+use shakmaty::uci::UciMove;
+use shakmaty::{Bitboard, ByColor, Chess, EnPassantMode, Move};
+
+use super::{Player, PlayerStatus};
+
+/// How long the engine thinks about each move unless told otherwise.
+pub const DEFAULT_MOVETIME: Duration = Duration::from_secs(1);
+
+#[derive(Debug, thiserror::Error)]
+pub enum UciError {
+    #[error("engine I/O: {0}")]
+    Io(#[from] io::Error),
+    #[error("engine closed the connection")]
+    Closed,
+    #[error("engine sent a malformed bestmove: '{0}'")]
+    Malformed(String),
+    #[error("engine played an illegal move: {0}")]
+    IllegalMove(String),
+}
+
+/// A position to search, as FEN, and the engine's answer for it.
+type Reply = (String, Result<UciMove, UciError>);
+
+/// An external chess engine speaking UCI, e.g. Stockfish.
+///
+/// The engine runs on a worker thread: each new position is sent as
+/// `position fen … / go movetime …`, and `poll_move` returns the
+/// `bestmove` once it has arrived, so the board keeps ticking while the
+/// engine thinks. The session then lights the reply for the human to make
+/// on the board, as for any non-interactive player.
+///
+/// Any engine failure is logged and reported as [`PlayerStatus::Error`].
+#[derive(Debug)]
+pub struct UciEngine {
+    searches: mpsc::Sender<String>,
+    replies: mpsc::Receiver<Reply>,
+    /// FEN of the position being searched.
+    searching: Option<String>,
+    failed: bool,
+    /// The engine process, when the board started it.
+    #[cfg(not(target_os = "espidf"))]
+    child: Option<std::process::Child>,
+}
+
+impl UciEngine {
+    /// Talk UCI over `reader` and `writer`, giving the engine `movetime`
+    /// per move.
+    pub fn new<R, W>(reader: R, writer: W, movetime: Duration) -> Self
+    where
+        R: BufRead + Send + 'static,
+        W: Write + Send + 'static,
+    {
+        let (searches, search_rx) = mpsc::channel();
+        let (reply_tx, replies) = mpsc::channel();
+        thread::spawn(move || {
+            if let Err(e) = run(reader, writer, movetime, &search_rx, &reply_tx) {
+                // Fail whatever is asked next, or the search in progress.
+                let fen = search_rx.try_recv().unwrap_or_default();
+                let _ = reply_tx.send((fen, Err(e)));
+            }
+        });
+        Self {
+            searches,
+            replies,
+            searching: None,
+            failed: false,
+            #[cfg(not(target_os = "espidf"))]
+            child: None,
+        }
+    }
+
+    /// Start `program` (with `args`) and talk to it over its stdin and
+    /// stdout.
+    #[cfg(not(target_os = "espidf"))]
+    pub fn spawn(program: &str, args: &[&str], movetime: Duration) -> io::Result<Self> {
+        use std::process::{Command, Stdio};
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            return Err(io::Error::other("engine pipes unavailable"));
+        };
+        let mut engine = Self::new(BufReader::new(stdout), stdin, movetime);
+        engine.child = Some(child);
+        Ok(engine)
+    }
+
+    /// Talk to an engine served over TCP (e.g. Stockfish behind a socket
+    /// bridge on the local network), for boards that cannot run one.
+    pub fn connect(addr: impl ToSocketAddrs, movetime: Duration) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        let reader = BufReader::new(stream.try_clone()?);
+        Ok(Self::new(reader, stream, movetime))
+    }
+
+    fn fail(&mut self, error: &UciError) {
+        log::error!("UCI engine failed: {error}");
+        self.failed = true;
+        self.searching = None;
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        #[cfg(not(target_os = "espidf"))]
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Player for UciEngine {
+    fn poll_move(&mut self, position: &Chess, _sensors: ByColor<Bitboard>) -> Option<Move> {
+        if self.failed {
+            return None;
+        }
+        let fen = Fen::from_position(position, EnPassantMode::Legal).to_string();
+        if self.searching.as_ref() != Some(&fen) {
+            if self.searches.send(fen.clone()).is_err() {
+                self.fail(&UciError::Closed);
+                return None;
+            }
+            self.searching = Some(fen.clone());
+        }
+        loop {
+            match self.replies.try_recv() {
+                // An answer for a position taken back since.
+                Ok((searched, _)) if searched != fen && !searched.is_empty() => {}
+                Ok((_, Ok(uci))) => {
+                    self.searching = None;
+                    match uci.to_move(position) {
+                        Ok(mv) => return Some(mv),
+                        Err(_) => self.fail(&UciError::IllegalMove(uci.to_string())),
+                    }
+                    return None;
+                }
+                Ok((_, Err(e))) => {
+                    self.fail(&e);
+                    return None;
+                }
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    self.fail(&UciError::Closed);
+                    return None;
+                }
+            }
+        }
+    }
+
+    fn status(&self) -> PlayerStatus {
+        if self.failed {
+            PlayerStatus::Error
+        } else {
+            PlayerStatus::Active
+        }
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+/// The worker: handshake, then one search per position received, until
+/// the player is dropped.
+fn run(
+    mut reader: impl BufRead,
+    mut writer: impl Write,
+    movetime: Duration,
+    searches: &mpsc::Receiver<String>,
+    replies: &mpsc::Sender<Reply>,
+) -> Result<(), UciError> {
+    writeln!(writer, "uci")?;
+    writer.flush()?;
+    read_until(&mut reader, |line| line == "uciok")?;
+    writeln!(writer, "ucinewgame\nisready")?;
+    writer.flush()?;
+    read_until(&mut reader, |line| line == "readyok")?;
+    while let Ok(fen) = searches.recv() {
+        writeln!(writer, "position fen {fen}")?;
+        writeln!(writer, "go movetime {}", movetime.as_millis())?;
+        writer.flush()?;
+        let line = read_until(&mut reader, |line| line.starts_with("bestmove"))?;
+        let best = line.split_whitespace().nth(1).unwrap_or_default();
+        let uci = best
+            .parse::<UciMove>()
+            .map_err(|_| UciError::Malformed(line.clone()));
+        if replies.send((fen, uci)).is_err() {
+            break;
+        }
+    }
+    let _ = writeln!(writer, "quit");
+    let _ = writer.flush();
+    Ok(())
+}
+
+/// Read lines until one matches `done`, and return it.
+fn read_until(reader: &mut impl BufRead, done: impl Fn(&str) -> bool) -> Result<String, UciError> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(UciError::Closed);
+        }
+        let trimmed = line.trim();
+        if done(trimmed) {
+            return Ok(trimmed.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::{Position, Square};
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    /// Collects what the player sends to the engine.
+    #[derive(Clone, Default)]
+    struct Sent(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Sent {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Sent {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    /// Poll until the engine answers or fails.
+    fn answer(engine: &mut UciEngine, position: &Chess) -> Option<Move> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let Some(mv) = engine.poll_move(position, ByColor::default()) {
+                return Some(mv);
+            }
+            if engine.status() == PlayerStatus::Error {
+                return None;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        None
+    }
+
+    fn after_e4() -> Chess {
+        let mut position = Chess::default();
+        let e4 = "e2e4"
+            .parse::<UciMove>()
+            .unwrap()
+            .to_move(&position)
+            .unwrap();
+        position.play_unchecked(e4);
+        position
+    }
+
+    #[test]
+    fn engine_reply_is_played_after_the_handshake() {
+        let output = "id name Fake\nuciok\nreadyok\ninfo depth 1\nbestmove e7e5 ponder g1f3\n";
+        let sent = Sent::default();
+        let mut engine = UciEngine::new(
+            Cursor::new(output.as_bytes().to_vec()),
+            sent.clone(),
+            Duration::from_millis(250),
+        );
+
+        let mv = answer(&mut engine, &after_e4()).unwrap();
+
+        assert_eq!((mv.from(), mv.to()), (Some(Square::E7), Square::E5));
+        assert!(!engine.is_interactive());
+        let sent = sent.text();
+        assert!(sent.starts_with("uci\nucinewgame\nisready\n"), "{sent}");
+        assert!(
+            sent.contains("position fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1\ngo movetime 250\n"),
+            "{sent}"
+        );
+    }
+
+    #[test]
+    fn illegal_reply_or_closed_engine_is_an_error() {
+        let output = "uciok\nreadyok\nbestmove e2e4\n";
+        let mut engine = UciEngine::new(
+            Cursor::new(output.as_bytes().to_vec()),
+            Sent::default(),
+            DEFAULT_MOVETIME,
+        );
+        assert_eq!(answer(&mut engine, &after_e4()), None);
+        assert_eq!(engine.status(), PlayerStatus::Error);
+
+        let mut engine = UciEngine::new(
+            Cursor::new(b"uciok\n".to_vec()),
+            Sent::default(),
+            DEFAULT_MOVETIME,
+        );
+        assert_eq!(answer(&mut engine, &after_e4()), None);
+        assert_eq!(engine.status(), PlayerStatus::Error);
+    }
+}
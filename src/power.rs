@@ -0,0 +1,120 @@
+//! LED current budgeting.
+//!
+//! On USB power, lighting many squares at once can pull the supply below
+//! the ESP32's brown-out threshold. [`CurrentLimit`] estimates the current
+//! a [`Frame`] draws and scales it down uniformly until it fits the budget,
+//! so colors keep their proportions and only overall brightness drops.
+
+use crate::frame::Frame;
+
+/// WS2812 current model and the budget frames must fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrentLimit {
+    /// Maximum current (mA) the LEDs may draw in total.
+    pub budget_ma: u32,
+    /// Current (mA) of one color channel at full brightness.
+    pub channel_full_ma: u32,
+    /// Current (mA) one LED draws while dark.
+    pub idle_ma: u32,
+    /// Physical LEDs behind each square.
+    pub leds_per_square: u32,
+}
+
+impl Default for CurrentLimit {
+    /// Leave headroom for the ESP32 and sensors on a 500 mA USB port.
+    fn default() -> Self {
+        Self {
+            budget_ma: 300,
+            channel_full_ma: 20,
+            idle_ma: 1,
+            leds_per_square: 2,
+        }
+    }
+}
+
+impl CurrentLimit {
+    /// Estimated current (mA) for showing `frame`.
+    pub fn estimate_ma(&self, frame: &Frame) -> u32 {
+        self.idle_total_ma() + self.channel_ma(frame)
+    }
+
+    /// Scale `frame` down, if needed, so its estimate fits the budget.
+    pub fn limit(&self, frame: Frame) -> Frame {
+        let dynamic = self.channel_ma(&frame);
+        let available = self.budget_ma.saturating_sub(self.idle_total_ma());
+        if dynamic <= available {
+            return frame;
+        }
+        let level = (available * 255 / dynamic) as u8;
+        log::debug!(
+            "LED current {}mA over {}mA budget, scaling to {level}/255",
+            dynamic + self.idle_total_ma(),
+            self.budget_ma
+        );
+        frame.scaled(level)
+    }
+
+    fn idle_total_ma(&self) -> u32 {
+        64 * self.leds_per_square * self.idle_ma
+    }
+
+    /// Channel current in mA, rounded up so estimates never undershoot.
+    fn channel_ma(&self, frame: &Frame) -> u32 {
+        let levels: u32 = frame
+            .iter()
+            .map(|(_, c)| u32::from(c.r) + u32::from(c.g) + u32::from(c.b))
+            .sum();
+        (levels * self.channel_full_ma * self.leds_per_square).div_ceil(255)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::Rgb8;
+    use shakmaty::Square;
+
+    #[test]
+    fn dark_frame_draws_idle_current() {
+        let limit = CurrentLimit::default();
+        assert_eq!(limit.estimate_ma(&Frame::filled(Rgb8::new(0, 0, 0))), 128);
+    }
+
+    #[test]
+    fn estimate_counts_every_channel_on_both_leds() {
+        let limit = CurrentLimit::default();
+        let mut frame = Frame::filled(Rgb8::new(0, 0, 0));
+        frame.set(Square::E4, Rgb8::new(255, 255, 255));
+        assert_eq!(limit.estimate_ma(&frame), 128 + 120);
+    }
+
+    #[test]
+    fn frame_within_budget_is_unchanged() {
+        let limit = CurrentLimit::default();
+        let mut frame = Frame::filled(Rgb8::new(0, 0, 0));
+        frame.set(Square::E4, Rgb8::new(0, 20, 0));
+        assert_eq!(limit.limit(frame), frame);
+    }
+
+    #[test]
+    fn full_white_board_is_scaled_into_budget() {
+        let limit = CurrentLimit::default();
+        let frame = Frame::filled(Rgb8::new(255, 255, 255));
+
+        let limited = limit.limit(frame);
+
+        assert!(limit.estimate_ma(&limited) <= limit.budget_ma);
+        let c = limited.get(Square::A1);
+        assert!(c.r > 0 && c.r == c.g && c.g == c.b, "got {c:?}");
+    }
+
+    #[test]
+    fn budget_below_idle_blanks_frame() {
+        let limit = CurrentLimit {
+            budget_ma: 50,
+            ..CurrentLimit::default()
+        };
+        let limited = limit.limit(Frame::filled(Rgb8::new(20, 20, 20)));
+        assert_eq!(limited, Frame::filled(Rgb8::new(0, 0, 0)));
+    }
+}
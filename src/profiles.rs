@@ -0,0 +1,245 @@
+//! Per-player preferences.
+//!
+//! A [`Profile`] is one player's preferred theme, assist level, clock and
+//! near side. The owner lists the profiles on the config page (see
+//! [`crate::board_config`]), one per line:
+//!
+//! ```text
+//! Ann: high-contrast minimal 5+3 black
+//! Bo: 10+5b
+//! ```
+//!
+//! The words after the name may come in any order, and what a line leaves
+//! out is the default: the classic theme, full assist, no clock and White
+//! at the near edge. A client selects a profile before a game (BLE
+//! `SelectProfile`); [`crate::app::BoardApp`] applies it at once and puts
+//! the preferences from before back when the game ends.
+//!
+//! Profiles are stored in the same text, after a `version 1` line.
+
+use std::fmt;
+use std::io;
+
+use shakmaty::Color;
+
+use crate::chess_clock::TimeControl;
+use crate::settings::{AssistLevel, Theme};
+
+/// The most profiles the board keeps.
+pub const MAX_PROFILES: usize = 8;
+
+/// Longest profile name, in characters.
+pub const MAX_NAME_LEN: usize = 20;
+
+const VERSION: &str = "1";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProfileError {
+    #[error("at most {MAX_PROFILES} profiles, not {0}")]
+    Count(usize),
+    #[error("profiles are written NAME: PREFERENCES, not {0:?}")]
+    Line(String),
+    #[error("profile names must be 1 to {MAX_NAME_LEN} characters, not {0:?}")]
+    Name(String),
+    #[error("{0} has two profiles")]
+    Duplicate(String),
+    #[error(
+        "unknown preference {0:?}: use a theme, full or minimal, off or a clock such as 5+3, white or black"
+    )]
+    Preference(String),
+    #[error("unsupported profiles version {0:?}")]
+    Version(String),
+}
+
+/// Where the profiles are kept between power cycles.
+pub trait ProfileStore {
+    /// The stored profiles. Ones that cannot be read are logged and
+    /// treated as absent.
+    fn load(&self) -> Vec<Profile>;
+
+    fn save(&mut self, profiles: &[Profile]) -> io::Result<()>;
+}
+
+/// A player's preferences.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub theme: Theme,
+    pub assist: AssistLevel,
+    /// Clock for the player's games; `None` plays without one.
+    pub clock: Option<TimeControl>,
+    /// The side the player sets up at the near edge.
+    pub orientation: Color,
+}
+
+impl Profile {
+    /// A profile with the default preferences.
+    pub fn new(name: &str) -> Result<Self, ProfileError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN || name.contains('\n') {
+            return Err(ProfileError::Name(name.to_string()));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            theme: Theme::default(),
+            assist: AssistLevel::default(),
+            clock: None,
+            orientation: Color::White,
+        })
+    }
+
+    /// Read one `NAME: PREFERENCES` line.
+    pub fn parse(line: &str) -> Result<Self, ProfileError> {
+        let (name, preferences) = line
+            .split_once(':')
+            .ok_or_else(|| ProfileError::Line(line.trim().to_string()))?;
+        let mut profile = Self::new(name)?;
+        for word in preferences.split_whitespace() {
+            if let Some(theme) = Theme::from_name(word) {
+                profile.theme = theme;
+            } else if let Some(assist) = AssistLevel::from_name(word) {
+                profile.assist = assist;
+            } else if word == "off" {
+                profile.clock = None;
+            } else if let Some(clock) = TimeControl::parse(word) {
+                profile.clock = Some(clock);
+            } else if word == "white" {
+                profile.orientation = Color::White;
+            } else if word == "black" {
+                profile.orientation = Color::Black;
+            } else {
+                return Err(ProfileError::Preference(word.to_string()));
+            }
+        }
+        Ok(profile)
+    }
+
+    /// Read a list of profiles, one per line; blank lines are skipped.
+    pub fn parse_list(text: &str) -> Result<Vec<Self>, ProfileError> {
+        let profiles = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(Self::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if profiles.len() > MAX_PROFILES {
+            return Err(ProfileError::Count(profiles.len()));
+        }
+        for (i, profile) in profiles.iter().enumerate() {
+            if profiles[..i].iter().any(|other| other.name == profile.name) {
+                return Err(ProfileError::Duplicate(profile.name.clone()));
+            }
+        }
+        Ok(profiles)
+    }
+
+    /// `profiles` as stored text.
+    pub fn encode(profiles: &[Self]) -> String {
+        let mut text = format!("version {VERSION}\n");
+        for profile in profiles {
+            text += &format!("{profile}\n");
+        }
+        text
+    }
+
+    /// Read profiles stored with [`Self::encode`].
+    pub fn decode(text: &str) -> Result<Vec<Self>, ProfileError> {
+        let (header, list) = text.split_once('\n').unwrap_or((text, ""));
+        match header.strip_prefix("version ") {
+            Some(VERSION) => Self::parse_list(list),
+            _ => Err(ProfileError::Version(header.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} {} ",
+            self.name,
+            self.theme.name(),
+            self.assist.name()
+        )?;
+        match self.clock {
+            Some(clock) => write!(f, "{clock}")?,
+            None => f.write_str("off")?,
+        }
+        match self.orientation {
+            Color::White => f.write_str(" white"),
+            Color::Black => f.write_str(" black"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::malformed_inputs;
+
+    #[test]
+    fn lines_set_what_they_mention() {
+        let profiles =
+            Profile::parse_list("Ann: high-contrast minimal 5+3 black\n\n Bo :10+5b\n").unwrap();
+
+        assert_eq!(
+            profiles[0],
+            Profile {
+                name: "Ann".into(),
+                theme: Theme::HighContrast,
+                assist: AssistLevel::Minimal,
+                clock: TimeControl::parse("5+3"),
+                orientation: Color::Black,
+            }
+        );
+        assert_eq!(
+            profiles[1],
+            Profile {
+                clock: TimeControl::parse("10+5b"),
+                ..Profile::new("Bo").unwrap()
+            }
+        );
+    }
+
+    #[test]
+    fn bad_lists_are_rejected() {
+        let cases = [
+            (
+                "Ann high-contrast",
+                ProfileError::Line("Ann high-contrast".into()),
+            ),
+            (": black", ProfileError::Name(String::new())),
+            ("Ann: neon", ProfileError::Preference("neon".into())),
+            (
+                "Ann: black\nAnn: white",
+                ProfileError::Duplicate("Ann".into()),
+            ),
+            ("A:\nB:\nC:\nD:\nE:\nF:\nG:\nH:\nI:", ProfileError::Count(9)),
+        ];
+        for (text, error) in cases {
+            assert_eq!(Profile::parse_list(text), Err(error), "{text}");
+        }
+    }
+
+    #[test]
+    fn profiles_round_trip_through_storage() {
+        let profiles =
+            Profile::parse_list("Ann: high-contrast minimal 5+3 black\nBo: 10+5b").unwrap();
+
+        let text = Profile::encode(&profiles);
+
+        assert!(text.starts_with("version 1\nAnn: high-contrast minimal 5+3 black\n"));
+        assert_eq!(Profile::decode(&text), Ok(profiles));
+        assert_eq!(
+            Profile::decode("version 2\nAnn:"),
+            Err(ProfileError::Version("version 2".into()))
+        );
+    }
+
+    #[test]
+    fn malformed_input_does_not_panic() {
+        let text = Profile::encode(&Profile::parse_list("Ann: black 5+3\nBo: minimal").unwrap());
+        for bytes in malformed_inputs(text.as_bytes(), 256, 40) {
+            let _ = Profile::decode(&String::from_utf8_lossy(&bytes));
+        }
+    }
+}
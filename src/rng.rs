@@ -0,0 +1,76 @@
+//! Small deterministic pseudo-random generator.
+//!
+//! Training games and test players only need "unpredictable enough" choices,
+//! not cryptographic randomness, so a seeded xorshift keeps them
+//! dependency-free and reproducible in tests.
+
+use shakmaty::{Bitboard, Square};
+
+/// Xorshift32 generator.
+#[derive(Debug, Clone)]
+pub struct XorShift32 {
+    state: u32,
+}
+
+impl XorShift32 {
+    /// Create a generator from `seed`. A zero seed is replaced, since
+    /// xorshift would only ever produce zeros from it.
+    pub fn new(seed: u32) -> Self {
+        Self {
+            state: if seed == 0 { 0x9E37_79B9 } else { seed },
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// A value in `0..n`. Returns 0 when `n` is 0.
+    pub fn below(&mut self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        self.next_u32() % n
+    }
+
+    /// A square picked uniformly from `squares`, or `None` if it is empty.
+    pub fn square_in(&mut self, squares: Bitboard) -> Option<Square> {
+        let index = self.below(squares.count() as u32) as usize;
+        squares.into_iter().nth(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = XorShift32::new(42);
+        let mut b = XorShift32::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u32(), b.next_u32());
+        }
+    }
+
+    #[test]
+    fn zero_seed_still_produces_values() {
+        let mut rng = XorShift32::new(0);
+        assert_ne!(rng.next_u32(), 0);
+    }
+
+    #[test]
+    fn square_in_stays_within_set() {
+        let mut rng = XorShift32::new(7);
+        let set = Bitboard::from(Square::C3) | Bitboard::from(Square::F6);
+        for _ in 0..20 {
+            assert!(set.contains(rng.square_in(set).unwrap()));
+        }
+        assert_eq!(rng.square_in(Bitboard::EMPTY), None);
+    }
+}
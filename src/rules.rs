@@ -0,0 +1,231 @@
+//! Extension points for chess variants.
+//!
+//! A [`RulesHook`] installed on a [`crate::session::GameSession`] can veto
+//! otherwise legal moves, react to played moves, and track extra physical
+//! entities on the board (such as the duck in duck chess) that standard
+//! chess knows nothing about. Squares held by entities are hidden from
+//! move detection and game feedback, so the core engine keeps working on
+//! plain chess positions.
+
+use shakmaty::{Bitboard, ByColor, Chess, Move, Position, Square, attacks};
+
+use crate::feedback::{BoardFeedback, SquareFeedback};
+
+/// Variant rules layered over standard chess.
+///
+/// Every method has a no-op default, so a hook only implements what its
+/// variant changes.
+pub trait RulesHook {
+    /// Veto a move that standard chess allows.
+    fn allow_move(&self, position: &Chess, mv: &Move) -> bool {
+        let _ = (position, mv);
+        true
+    }
+
+    /// Called after a move has been applied; `position` is the new position.
+    fn after_move(&mut self, position: &Chess, mv: &Move) {
+        let _ = (position, mv);
+    }
+
+    /// Update entity tracking from the raw sensor reading, before move
+    /// detection. Return `false` to hold the game (no move is polled) while
+    /// the variant waits for something, e.g. the duck to be placed.
+    fn observe(&mut self, position: &Chess, sensors: ByColor<Bitboard>) -> bool {
+        let _ = (position, sensors);
+        true
+    }
+
+    /// Squares occupied by non-chess entities.
+    fn entities(&self) -> Bitboard {
+        Bitboard::EMPTY
+    }
+
+    /// Adjust the feedback about to be shown.
+    fn decorate(&self, position: &Chess, feedback: &mut BoardFeedback) {
+        let _ = (position, feedback);
+    }
+}
+
+/// Duck chess: after every move, the mover relocates the duck to an empty
+/// square. Nothing may move onto or through the duck.
+///
+/// The duck is any extra piece on the board that the chess position does
+/// not account for.
+#[derive(Debug, Clone, Default)]
+pub struct DuckChess {
+    duck: Option<Square>,
+    awaiting_duck: bool,
+}
+
+impl DuckChess {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn duck(&self) -> Option<Square> {
+        self.duck
+    }
+
+    /// Whether the game is held until the duck is moved.
+    pub fn awaiting_duck(&self) -> bool {
+        self.awaiting_duck
+    }
+}
+
+impl RulesHook for DuckChess {
+    fn allow_move(&self, _position: &Chess, mv: &Move) -> bool {
+        let Some(duck) = self.duck else {
+            return true;
+        };
+        let blocked = mv
+            .from()
+            .is_some_and(|from| attacks::between(from, mv.to()).contains(duck));
+        mv.to() != duck && !blocked
+    }
+
+    fn after_move(&mut self, _position: &Chess, _mv: &Move) {
+        self.awaiting_duck = true;
+    }
+
+    fn observe(&mut self, position: &Chess, sensors: ByColor<Bitboard>) -> bool {
+        if !self.awaiting_duck {
+            return true;
+        }
+        let occupied = sensors.white | sensors.black;
+        let chess = position.board().occupied();
+        // Every chess piece in place plus exactly one new square.
+        let extra = occupied & !chess;
+        if (chess & !occupied).is_empty()
+            && let Some(square) = extra.single_square()
+            && Some(square) != self.duck
+        {
+            log::info!("Duck placed on {square}");
+            self.duck = Some(square);
+            self.awaiting_duck = false;
+            return true;
+        }
+        false
+    }
+
+    fn entities(&self) -> Bitboard {
+        self.duck.map_or(Bitboard::EMPTY, Bitboard::from)
+    }
+
+    fn decorate(&self, _position: &Chess, feedback: &mut BoardFeedback) {
+        if let Some(duck) = self.duck {
+            feedback.clear(duck);
+            if self.awaiting_duck {
+                feedback.set(duck, SquareFeedback::Origin);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::HumanPlayer;
+    use crate::session::GameSession;
+    use shakmaty::Color;
+    use shakmaty::uci::UciMove;
+
+    fn sensors(position: &Chess) -> ByColor<Bitboard> {
+        let board = position.board();
+        ByColor {
+            white: board.by_color(Color::White),
+            black: board.by_color(Color::Black),
+        }
+    }
+
+    fn session_with(rules: impl RulesHook + 'static) -> GameSession {
+        let start = sensors(&Chess::default());
+        let mut session = GameSession::new(
+            Box::new(HumanPlayer::new(start)),
+            Box::new(HumanPlayer::new(start)),
+        );
+        session.set_rules(Box::new(rules));
+        session
+    }
+
+    /// Sensor reading after `uci` is played from the session's position,
+    /// plus any extra squares.
+    fn after(session: &GameSession, uci: &str, extra: Bitboard) -> ByColor<Bitboard> {
+        let mut pos = session.position().clone();
+        let mv = uci.parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+        pos.play_unchecked(mv);
+        let mut s = sensors(&pos);
+        s.white |= extra;
+        s
+    }
+
+    struct NoCaptures;
+
+    impl RulesHook for NoCaptures {
+        fn allow_move(&self, _position: &Chess, mv: &Move) -> bool {
+            !mv.is_capture()
+        }
+    }
+
+    #[test]
+    fn vetoed_move_is_not_played() {
+        let mut session = session_with(NoCaptures);
+        for uci in ["e2e4", "d7d5"] {
+            let reading = after(&session, uci, Bitboard::EMPTY);
+            assert!(session.tick(reading).last_move.is_some());
+        }
+
+        let reading = after(&session, "e4d5", Bitboard::EMPTY);
+        let result = session.tick(reading);
+
+        assert_eq!(result.last_move, None);
+        assert_eq!(session.position().turn(), Color::White);
+    }
+
+    #[test]
+    fn duck_must_move_before_the_reply() {
+        let mut session = session_with(DuckChess::new());
+        let reading = after(&session, "e2e4", Bitboard::EMPTY);
+        session.tick(reading);
+
+        // Black replies before the duck is placed: held.
+        let early = after(&session, "d7d5", Bitboard::EMPTY);
+        assert_eq!(session.tick(early).last_move, None);
+
+        // Back to the position after e4, then the duck lands on e5.
+        let duck = Bitboard::from(Square::E5);
+        session.tick(sensors(session.position()));
+        let with_duck = ByColor {
+            white: sensors(session.position()).white | duck,
+            black: sensors(session.position()).black,
+        };
+        session.tick(with_duck);
+
+        let reply = after(&session, "d7d5", duck);
+        assert!(session.tick(reply).last_move.is_some());
+    }
+
+    #[test]
+    fn nothing_moves_onto_or_through_the_duck() {
+        let duck = DuckChess {
+            duck: Some(Square::E5),
+            awaiting_duck: false,
+        };
+        let position = Chess::default();
+        let onto = UciMove::from_ascii(b"e2e4")
+            .unwrap()
+            .to_move(&position)
+            .unwrap();
+        assert!(duck.allow_move(&position, &onto));
+
+        let duck = DuckChess {
+            duck: Some(Square::E3),
+            awaiting_duck: false,
+        };
+        assert!(!duck.allow_move(&position, &onto), "e2e4 jumps over e3");
+        let blocked = UciMove::from_ascii(b"e2e3")
+            .unwrap()
+            .to_move(&position)
+            .unwrap();
+        assert!(!duck.allow_move(&position, &blocked));
+    }
+}
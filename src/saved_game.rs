@@ -0,0 +1,315 @@
+//! The game in progress, saved so it survives a power cut.
+//!
+//! [`crate::app::BoardApp`] hands a [`SavedGame`] to its [`GameStore`]
+//! after every move and clears it when the game ends. At boot a stored
+//! game is resumed with [`crate::app::BoardApp::restore_game`], which
+//! guides the pieces back to the saved position if they were disturbed.
+//!
+//! Games are stored as short text, one `key value` pair per line:
+//!
+//! ```text
+//! version 1
+//! white human
+//! black random
+//! start rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1
+//! moves e2e4 e7e5
+//! fen rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2
+//! clock 300000 3000 fischer 0 291200 296500
+//! ```
+//!
+//! `fen` is the position after `moves` and must agree with them. `clock`
+//! holds the time control in milliseconds, its [`TimingMethod`], whether
+//! moves need confirming, and each side's remaining time as of the last
+//! move; it is absent for games without a clock.
+
+use std::io;
+use std::time::Duration;
+
+use shakmaty::fen::Fen;
+use shakmaty::uci::UciMove;
+use shakmaty::{ByColor, CastlingMode, Chess, EnPassantMode, Move, Position};
+
+use crate::board_api::PlayerType;
+use crate::chess_clock::{ClockSettings, TimeControl, TimingMethod};
+
+const VERSION: &str = "1";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SavedGameError {
+    #[error("unsupported saved game version {0:?}")]
+    Version(String),
+    #[error("malformed {0} line")]
+    Malformed(&'static str),
+    #[error("saved game has no {0} line")]
+    Missing(&'static str),
+    #[error("illegal move {0} in saved game")]
+    IllegalMove(String),
+    #[error("saved moves do not lead to the saved position")]
+    PositionMismatch,
+}
+
+/// Where the game in progress is kept between power cycles.
+pub trait GameStore {
+    /// The saved game, if any. A game that cannot be read is logged and
+    /// treated as absent.
+    fn load(&self) -> Option<SavedGame>;
+
+    fn save(&mut self, game: &SavedGame) -> io::Result<()>;
+
+    fn clear(&mut self) -> io::Result<()>;
+}
+
+/// Clock state of a saved game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SavedClock {
+    pub settings: ClockSettings,
+    pub remaining: ByColor<Duration>,
+}
+
+/// A game in progress: who plays, how it started, what has been played,
+/// and the clock.
+#[derive(Debug, Clone)]
+pub struct SavedGame {
+    pub white: PlayerType,
+    pub black: PlayerType,
+    pub start: Chess,
+    pub moves: Vec<Move>,
+    pub clock: Option<SavedClock>,
+}
+
+impl SavedGame {
+    /// The position after [`Self::moves`].
+    pub fn position(&self) -> Chess {
+        let mut position = self.start.clone();
+        for &mv in &self.moves {
+            position.play_unchecked(mv);
+        }
+        position
+    }
+
+    pub fn encode(&self) -> String {
+        let moves: Vec<String> = self
+            .moves
+            .iter()
+            .map(|&mv| UciMove::from_move(mv, CastlingMode::Standard).to_string())
+            .collect();
+        let mut text = format!(
+            "version {VERSION}\nwhite {}\nblack {}\nstart {}\nmoves {}\nfen {}\n",
+            player_name(self.white),
+            player_name(self.black),
+            fen(&self.start),
+            moves.join(" "),
+            fen(&self.position()),
+        );
+        if let Some(clock) = &self.clock {
+            let control = clock.settings.time_control;
+            text.push_str(&format!(
+                "clock {} {} {} {} {} {}\n",
+                control.initial.as_millis(),
+                control.increment.as_millis(),
+                method_name(control.method),
+                u8::from(clock.settings.confirm_moves),
+                clock.remaining.white.as_millis(),
+                clock.remaining.black.as_millis(),
+            ));
+        }
+        text
+    }
+
+    pub fn decode(text: &str) -> Result<Self, SavedGameError> {
+        let field = |key: &'static str| {
+            text.lines()
+                .find_map(|line| match line.split_once(' ') {
+                    Some((k, value)) if k == key => Some(value),
+                    _ if line == key => Some(""),
+                    _ => None,
+                })
+                .ok_or(SavedGameError::Missing(key))
+        };
+        let version = field("version")?;
+        if version != VERSION {
+            return Err(SavedGameError::Version(version.to_string()));
+        }
+        let player = |key| parse_player(field(key)?).ok_or(SavedGameError::Malformed(key));
+        let (white, black) = (player("white")?, player("black")?);
+        let start = parse_fen(field("start")?).ok_or(SavedGameError::Malformed("start"))?;
+
+        let mut position = start.clone();
+        let mut moves = Vec::new();
+        for uci in field("moves")?.split_whitespace() {
+            let mv = uci
+                .parse::<UciMove>()
+                .ok()
+                .and_then(|uci| uci.to_move(&position).ok())
+                .ok_or_else(|| SavedGameError::IllegalMove(uci.to_string()))?;
+            position.play_unchecked(mv);
+            moves.push(mv);
+        }
+        if field("fen")? != fen(&position) {
+            return Err(SavedGameError::PositionMismatch);
+        }
+
+        let clock = match field("clock") {
+            Ok(clock) => Some(parse_clock(clock).ok_or(SavedGameError::Malformed("clock"))?),
+            Err(_) => None,
+        };
+        Ok(Self {
+            white,
+            black,
+            start,
+            moves,
+            clock,
+        })
+    }
+}
+
+fn fen(position: &Chess) -> String {
+    Fen::from_position(position, EnPassantMode::Legal).to_string()
+}
+
+fn parse_fen(text: &str) -> Option<Chess> {
+    text.parse::<Fen>()
+        .ok()?
+        .into_position(CastlingMode::Standard)
+        .ok()
+}
+
+fn player_name(player: PlayerType) -> &'static str {
+    match player {
+        PlayerType::Human => "human",
+        PlayerType::Remote => "remote",
+        PlayerType::Random => "random",
+        PlayerType::Lichess => "lichess",
+        PlayerType::Relay => "relay",
+    }
+}
+
+fn parse_player(name: &str) -> Option<PlayerType> {
+    match name {
+        "human" => Some(PlayerType::Human),
+        "remote" => Some(PlayerType::Remote),
+        "random" => Some(PlayerType::Random),
+        "lichess" => Some(PlayerType::Lichess),
+        "relay" => Some(PlayerType::Relay),
+        _ => None,
+    }
+}
+
+fn method_name(method: TimingMethod) -> &'static str {
+    match method {
+        TimingMethod::Fischer => "fischer",
+        TimingMethod::Bronstein => "bronstein",
+        TimingMethod::Delay => "delay",
+    }
+}
+
+fn parse_clock(text: &str) -> Option<SavedClock> {
+    let fields: Vec<&str> = text.split_whitespace().collect();
+    let &[initial, increment, method, confirm, white, black] = fields.as_slice() else {
+        return None;
+    };
+    let millis = |field: &str| field.parse().ok().map(Duration::from_millis);
+    let method = match method {
+        "fischer" => TimingMethod::Fischer,
+        "bronstein" => TimingMethod::Bronstein,
+        "delay" => TimingMethod::Delay,
+        _ => return None,
+    };
+    Some(SavedClock {
+        settings: ClockSettings {
+            time_control: TimeControl {
+                initial: millis(initial)?,
+                increment: millis(increment)?,
+                method,
+            },
+            confirm_moves: confirm == "1",
+        },
+        remaining: ByColor {
+            white: millis(white)?,
+            black: millis(black)?,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn after_e4_e5() -> SavedGame {
+        let start = Chess::default();
+        let mut position = start.clone();
+        let moves = ["e2e4", "e7e5"]
+            .into_iter()
+            .map(|uci| {
+                let mv = uci.parse::<UciMove>().unwrap().to_move(&position).unwrap();
+                position.play_unchecked(mv);
+                mv
+            })
+            .collect();
+        SavedGame {
+            white: PlayerType::Human,
+            black: PlayerType::Random,
+            start,
+            moves,
+            clock: Some(SavedClock {
+                settings: ClockSettings {
+                    time_control: TimeControl {
+                        initial: Duration::from_secs(300),
+                        increment: Duration::from_secs(3),
+                        method: TimingMethod::Bronstein,
+                    },
+                    confirm_moves: true,
+                },
+                remaining: ByColor {
+                    white: Duration::from_millis(291_200),
+                    black: Duration::from_millis(296_500),
+                },
+            }),
+        }
+    }
+
+    #[test]
+    fn saved_game_round_trips() {
+        let saved = after_e4_e5();
+        let text = saved.encode();
+        assert!(text.contains("\nmoves e2e4 e7e5\n"), "{text}");
+        assert!(
+            text.contains("\nclock 300000 3000 bronstein 1 291200 296500\n"),
+            "{text}"
+        );
+
+        let decoded = SavedGame::decode(&text).unwrap();
+        assert_eq!(decoded.moves, saved.moves);
+        assert_eq!(decoded.clock, saved.clock);
+        assert_eq!(decoded.black, PlayerType::Random);
+        assert_eq!(decoded.encode(), text);
+
+        let unclocked = SavedGame {
+            clock: None,
+            ..saved
+        };
+        assert_eq!(SavedGame::decode(&unclocked.encode()).unwrap().clock, None);
+    }
+
+    #[test]
+    fn corrupt_saves_are_rejected() {
+        let text = after_e4_e5().encode();
+
+        assert_eq!(
+            SavedGame::decode(&text.replace("e7e5", "e7e4")).err(),
+            Some(SavedGameError::IllegalMove("e7e4".to_string()))
+        );
+        assert_eq!(
+            SavedGame::decode(&text.replace("moves e2e4 e7e5", "moves e2e4")).err(),
+            Some(SavedGameError::PositionMismatch)
+        );
+        assert_eq!(
+            SavedGame::decode(&text.replace("version 1", "version 9")).err(),
+            Some(SavedGameError::Version("9".to_string()))
+        );
+        assert_eq!(
+            SavedGame::decode("version 1\nwhite human\n").err(),
+            Some(SavedGameError::Missing("black"))
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Timing full sensor scans to choose how much to oversample.
+//!
+//! A scan selects each of the 16 mux addresses, waits out the settle delay
+//! and reads the four mux outputs, each [`ScanVariant::samples`] times.
+//! More samples mean less noise, but the scan takes longer and a move shows
+//! up later. [`run`] times each variant on the board and measures its noise
+//! on the empty board, so the [`crate::hardware`] presets can be set from
+//! numbers instead of guesses. The diagnostics binary runs it at startup
+//! and logs [`BenchReport::lines`].
+//!
+//! The board reads its sensors through analog muxes only; there are no
+//! shift registers, so the only thing to compare is the ADC sampling.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::app::Clock;
+
+/// Scans timed per variant.
+pub const SCANS_PER_VARIANT: u32 = 20;
+
+/// One way to scan the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanVariant {
+    /// ADC reads averaged into each square's reading.
+    pub samples: u8,
+}
+
+impl ScanVariant {
+    /// A single read per square, then `configured` and double that, so
+    /// the report shows what the current setting buys and costs.
+    pub fn compared_with(configured: u8) -> Vec<Self> {
+        let configured = configured.max(1);
+        let mut samples = vec![1, configured, configured.saturating_mul(2)];
+        samples.dedup();
+        samples
+            .into_iter()
+            .map(|samples| Self { samples })
+            .collect()
+    }
+}
+
+impl fmt::Display for ScanVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.samples {
+            1 => f.write_str("single sample"),
+            n => write!(f, "{n}x oversampled"),
+        }
+    }
+}
+
+/// How long scans took and how much their readings moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanTiming {
+    pub scans: u32,
+    pub total: Duration,
+    pub fastest: Duration,
+    pub slowest: Duration,
+    /// Largest spread of any one square's readings across the scans; on
+    /// an empty board, its noise.
+    pub noise_mv: u16,
+}
+
+impl ScanTiming {
+    pub fn per_scan(&self) -> Duration {
+        self.total / self.scans.max(1)
+    }
+}
+
+impl fmt::Display for ScanTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}us per scan (fastest {}us, slowest {}us, {} scans), noise {}mV",
+            self.per_scan().as_micros(),
+            self.fastest.as_micros(),
+            self.slowest.as_micros(),
+            self.scans,
+            self.noise_mv
+        )
+    }
+}
+
+/// Run `scan` `scans` times (at least once), timing each against `clock`.
+pub fn time_scans<E>(
+    clock: &impl Clock,
+    scans: u32,
+    mut scan: impl FnMut() -> Result<[u16; 64], E>,
+) -> Result<ScanTiming, E> {
+    let scans = scans.max(1);
+    let mut total = Duration::ZERO;
+    let mut fastest = Duration::MAX;
+    let mut slowest = Duration::ZERO;
+    let mut low = [u16::MAX; 64];
+    let mut high = [0u16; 64];
+    for _ in 0..scans {
+        let start = clock.now();
+        let mv = scan()?;
+        let took = clock.now().saturating_sub(start);
+        total += took;
+        fastest = fastest.min(took);
+        slowest = slowest.max(took);
+        for (i, &reading) in mv.iter().enumerate() {
+            low[i] = low[i].min(reading);
+            high[i] = high[i].max(reading);
+        }
+    }
+    let noise_mv = low
+        .iter()
+        .zip(&high)
+        .map(|(&low, &high)| high - low)
+        .max()
+        .unwrap_or(0);
+    Ok(ScanTiming {
+        scans,
+        total,
+        fastest,
+        slowest,
+        noise_mv,
+    })
+}
+
+/// Timings of several variants.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BenchReport {
+    results: Vec<(ScanVariant, ScanTiming)>,
+}
+
+impl BenchReport {
+    pub fn results(&self) -> &[(ScanVariant, ScanTiming)] {
+        &self.results
+    }
+
+    /// The variant with the least noise, taking the faster on a tie.
+    pub fn quietest(&self) -> Option<ScanVariant> {
+        self.results
+            .iter()
+            .min_by_key(|(_, timing)| (timing.noise_mv, timing.per_scan()))
+            .map(|&(variant, _)| variant)
+    }
+
+    /// One log line per variant.
+    pub fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        self.results
+            .iter()
+            .map(|(variant, timing)| format!("{variant}: {timing}"))
+    }
+}
+
+/// Time every variant in `variants`, configuring the scanner for each with
+/// `configure` before scanning.
+pub fn run<S, E>(
+    clock: &impl Clock,
+    variants: &[ScanVariant],
+    scanner: &mut S,
+    mut configure: impl FnMut(&mut S, ScanVariant),
+    mut scan: impl FnMut(&mut S) -> Result<[u16; 64], E>,
+) -> Result<BenchReport, E> {
+    let mut report = BenchReport::default();
+    for &variant in variants {
+        configure(scanner, variant);
+        let timing = time_scans(clock, SCANS_PER_VARIANT, || scan(scanner))?;
+        report.results.push((variant, timing));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::VirtualClock;
+
+    /// A scanner taking 2ms plus 1ms per sample, whose noise halves with
+    /// every doubling of the samples.
+    struct FakeScanner {
+        clock: VirtualClock,
+        samples: u8,
+        scans: u32,
+    }
+
+    impl FakeScanner {
+        fn scan(&mut self) -> Result<[u16; 64], ()> {
+            self.clock
+                .advance(Duration::from_millis(2 + u64::from(self.samples)));
+            self.scans += 1;
+            let noise = 40 / u16::from(self.samples);
+            let offset = if self.scans.is_multiple_of(2) {
+                noise
+            } else {
+                0
+            };
+            Ok([1440 + offset; 64])
+        }
+    }
+
+    #[test]
+    fn each_variant_is_timed_with_its_noise() {
+        let clock = VirtualClock::new();
+        let mut scanner = FakeScanner {
+            clock: clock.clone(),
+            samples: 0,
+            scans: 0,
+        };
+        let variants = ScanVariant::compared_with(4);
+
+        let report = run(
+            &clock,
+            &variants,
+            &mut scanner,
+            |scanner, variant| scanner.samples = variant.samples,
+            FakeScanner::scan,
+        )
+        .unwrap();
+
+        assert_eq!(
+            variants.iter().map(|v| v.samples).collect::<Vec<_>>(),
+            [1, 4, 8]
+        );
+        let [(_, single), (_, four), (_, eight)] = report.results() else {
+            panic!("three variants timed");
+        };
+        assert_eq!(single.per_scan(), Duration::from_millis(3));
+        assert_eq!(single.noise_mv, 40);
+        assert_eq!(four.per_scan(), Duration::from_millis(6));
+        assert_eq!(four.noise_mv, 10);
+        assert_eq!(eight.scans, SCANS_PER_VARIANT);
+        assert_eq!(report.quietest(), Some(ScanVariant { samples: 8 }));
+        assert_eq!(
+            report.lines().next().unwrap(),
+            "single sample: 3000us per scan (fastest 3000us, slowest 3000us, 20 scans), noise 40mV"
+        );
+    }
+
+    #[test]
+    fn a_failed_scan_stops_the_run() {
+        let clock = VirtualClock::new();
+        let mut calls = 0;
+        let timing = time_scans(&clock, 5, || {
+            calls += 1;
+            if calls == 3 { Err("adc") } else { Ok([0; 64]) }
+        });
+
+        assert_eq!(timing, Err("adc"));
+        assert_eq!(calls, 3);
+    }
+}
@@ -0,0 +1,189 @@
+//! Scenario files for the terminal's watch mode.
+//!
+//! A scenario is a text file of [`Terminal`] input lines (square toggles and
+//! commands, `#` starts a comment) played on a fresh board. Its outcome is
+//! the final FEN and the feedback the board shows. [`ScenarioWatcher`]
+//! re-runs the scenarios in a directory whenever one is saved and reports
+//! how its outcome changed, so an author can edit a scenario, or the engine
+//! under it, and see the effect at once (`terminal dev <dir>`).
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::terminal::{Terminal, TerminalError};
+
+/// Extension of scenario files.
+pub const EXTENSION: &str = "board";
+
+/// A scenario line the terminal rejected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("line {line}: {error}")]
+pub struct ScenarioError {
+    /// 1-based line number.
+    pub line: usize,
+    pub error: TerminalError,
+}
+
+/// Play `script` on a fresh board. Returns the outcome as lines: the FEN,
+/// then each lit square.
+pub fn run(script: &str) -> Result<Vec<String>, ScenarioError> {
+    let mut terminal = Terminal::default();
+    for (i, line) in script.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        terminal
+            .execute(line)
+            .map_err(|error| ScenarioError { line: i + 1, error })?;
+    }
+    let mut outcome = vec![format!("fen {}", terminal.fen())];
+    outcome.extend(
+        terminal
+            .feedback()
+            .squares()
+            .map(|(square, feedback)| format!("{square}: {feedback:?}")),
+    );
+    Ok(outcome)
+}
+
+/// Lines of `old` missing from `new` prefixed with `-`, then lines new in
+/// `new` prefixed with `+`.
+pub fn diff(old: &[String], new: &[String]) -> Vec<String> {
+    let removed = old.iter().filter(|line| !new.contains(line));
+    let added = new.iter().filter(|line| !old.contains(line));
+    removed
+        .map(|line| format!("- {line}"))
+        .chain(added.map(|line| format!("+ {line}")))
+        .collect()
+}
+
+/// What a re-run of one scenario produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Report {
+    /// First run: the whole outcome.
+    New(Vec<String>),
+    /// Re-run after a change: the diff to the previous outcome, empty if
+    /// it did not change.
+    Changed(Vec<String>),
+    Failed(ScenarioError),
+    Removed,
+}
+
+/// Re-runs the scenarios of a directory when they change.
+#[derive(Debug)]
+pub struct ScenarioWatcher {
+    dir: PathBuf,
+    /// Contents and last outcome of each scenario seen.
+    scenarios: BTreeMap<PathBuf, (String, Option<Vec<String>>)>,
+}
+
+impl ScenarioWatcher {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            scenarios: BTreeMap::new(),
+        }
+    }
+
+    /// Run the scenarios added or saved since the last poll (all of them on
+    /// the first) and report each, in path order.
+    pub fn poll(&mut self) -> io::Result<Vec<(PathBuf, Report)>> {
+        let mut reports = Vec::new();
+        let mut seen = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != EXTENSION) {
+                continue;
+            }
+            let script = std::fs::read_to_string(&path)?;
+            seen.push(path.clone());
+            let previous = self.scenarios.get(&path);
+            if previous.is_some_and(|(old, _)| *old == script) {
+                continue;
+            }
+            let last = previous.and_then(|(_, outcome)| outcome.clone());
+            let (report, outcome) = match (run(&script), last) {
+                (Ok(outcome), Some(last)) => {
+                    (Report::Changed(diff(&last, &outcome)), Some(outcome))
+                }
+                (Ok(outcome), None) => (Report::New(outcome.clone()), Some(outcome)),
+                // Keep the last good outcome to diff against once fixed.
+                (Err(e), last) => (Report::Failed(e), last),
+            };
+            self.scenarios.insert(path.clone(), (script, outcome));
+            reports.push((path, report));
+        }
+        let removed: Vec<_> = self
+            .scenarios
+            .keys()
+            .filter(|path| !seen.contains(path))
+            .cloned()
+            .collect();
+        for path in removed {
+            self.scenarios.remove(&path);
+            reports.push((path, Report::Removed));
+        }
+        reports.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(reports)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outcome_is_the_fen_and_lit_squares() {
+        let outcome = run("e2 We4  # king's pawn\nplay e7e5\ng1\n").unwrap();
+
+        assert_eq!(
+            outcome[0],
+            "fen rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"
+        );
+        assert!(outcome.contains(&"g1: Origin".to_string()));
+        assert!(outcome.contains(&"f3: Destination".to_string()));
+        assert_eq!(
+            run("e2 We4\nplay e2e4"),
+            Err(ScenarioError {
+                line: 2,
+                error: TerminalError::IllegalMove("e2e4".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn saved_scenarios_are_rerun_and_diffed() {
+        let dir = std::env::temp_dir().join(format!("scenarios-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("opening.board");
+        std::fs::write(&path, "play e2e4\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a scenario").unwrap();
+        let mut watcher = ScenarioWatcher::new(&dir);
+
+        let reports = watcher.poll().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert!(matches!(&reports[0], (p, Report::New(_)) if *p == path));
+        assert_eq!(watcher.poll().unwrap(), [], "nothing saved");
+
+        std::fs::write(&path, "play e2e4\ne7\n").unwrap();
+        let reports = watcher.poll().unwrap();
+        assert_eq!(
+            reports,
+            [(
+                path.clone(),
+                Report::Changed(vec![
+                    "+ e5: Destination".to_string(),
+                    "+ e6: Destination".to_string(),
+                    "+ e7: Origin".to_string(),
+                ])
+            )]
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(watcher.poll().unwrap(), [(path, Report::Removed)]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
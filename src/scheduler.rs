@@ -0,0 +1,257 @@
+//! Outbound API request scheduling.
+//!
+//! Lichess asks clients to make one request at a time and to wait a full
+//! minute after an HTTP 429, and bans tokens that keep hammering it. A board
+//! on flaky WiFi reconnects often, so requests go through a
+//! [`RequestScheduler`] instead of straight to the network: it queues moves
+//! while offline, spaces requests out, and retries failures with jittered
+//! exponential backoff ([`Backoff`]), so no reconnect loop can turn into a
+//! burst. The Lichess and relay clients in [`crate::net`] post through one.
+//!
+//! The scheduler does no I/O. The network task asks it for the [`next`]
+//! request when it may send one and reports the [`Outcome`] back.
+//!
+//! [`next`]: RequestScheduler::next
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::net::{Backoff, MAX_RETRY_DELAY, RETRY_DELAY};
+
+/// Least time between the start of two requests.
+pub const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Pause after the server answered "too many requests".
+pub const RATE_LIMIT_PAUSE: Duration = Duration::from_secs(60);
+
+/// Requests held while offline; more are refused.
+pub const QUEUE_CAPACITY: usize = 64;
+
+/// How far apart a [`RequestScheduler`] sends requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pacing {
+    /// Least time between the start of two requests.
+    pub min_interval: Duration,
+    /// Pause after the server answered "too many requests".
+    pub rate_limit_pause: Duration,
+    /// Delay before the first retry; doubles with each further failure.
+    pub retry_delay: Duration,
+    /// Longest delay between retries.
+    pub max_retry_delay: Duration,
+}
+
+impl Default for Pacing {
+    fn default() -> Self {
+        Self {
+            min_interval: MIN_INTERVAL,
+            rate_limit_pause: RATE_LIMIT_PAUSE,
+            retry_delay: RETRY_DELAY,
+            max_retry_delay: MAX_RETRY_DELAY,
+        }
+    }
+}
+
+impl Pacing {
+    /// The retry delays, jittered by `seed`.
+    pub fn backoff(&self, seed: u32) -> Backoff {
+        Backoff::new(self.retry_delay, self.max_retry_delay, seed)
+    }
+}
+
+/// How a request handed out by [`RequestScheduler::next`] went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Done; the request is dropped from the queue.
+    Sent,
+    /// The server refused it for good (e.g. an illegal move); dropped.
+    Rejected,
+    /// HTTP 429: retried after [`Pacing::rate_limit_pause`].
+    RateLimited,
+    /// Network or server error: retried after a backoff.
+    Failed,
+}
+
+/// Queues requests and decides when the next one may go out.
+#[derive(Debug)]
+pub struct RequestScheduler<R> {
+    queue: VecDeque<R>,
+    online: bool,
+    /// The front request was handed out and has no outcome yet.
+    in_flight: bool,
+    /// No request starts before this.
+    not_before: Duration,
+    pacing: Pacing,
+    /// Retry delays of the front request.
+    backoff: Backoff,
+}
+
+impl<R> RequestScheduler<R> {
+    /// An empty, offline scheduler. `seed` drives the retry jitter.
+    pub fn new(pacing: Pacing, seed: u32) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            online: false,
+            in_flight: false,
+            not_before: Duration::ZERO,
+            pacing,
+            backoff: pacing.backoff(seed),
+        }
+    }
+
+    /// Queue `request` behind the others. Hands it back if the queue is
+    /// full.
+    pub fn push(&mut self, request: R) -> Result<(), R> {
+        if self.queue.len() >= QUEUE_CAPACITY {
+            return Err(request);
+        }
+        self.queue.push_back(request);
+        Ok(())
+    }
+
+    /// Whether the network is up. Requests wait in the queue while it is
+    /// not; a request in flight when the connection drops counts as
+    /// [`Outcome::Failed`].
+    pub fn set_online(&mut self, online: bool, now: Duration) {
+        if !online && self.in_flight {
+            self.complete(Outcome::Failed, now);
+        }
+        self.online = online;
+    }
+
+    /// The request to send now, if one may be sent.
+    ///
+    /// It stays queued until its outcome is reported with
+    /// [`Self::complete`]; until then no other request is handed out.
+    pub fn next(&mut self, now: Duration) -> Option<&R> {
+        if !self.online || self.in_flight || now < self.not_before {
+            return None;
+        }
+        let request = self.queue.front()?;
+        self.in_flight = true;
+        self.not_before = now + self.pacing.min_interval;
+        Some(request)
+    }
+
+    /// How long until the next request may be sent, or `None` if none is
+    /// queued.
+    pub fn ready_in(&self, now: Duration) -> Option<Duration> {
+        (!self.queue.is_empty()).then(|| self.not_before.saturating_sub(now))
+    }
+
+    /// Report how the request from [`Self::next`] went.
+    pub fn complete(&mut self, outcome: Outcome, now: Duration) {
+        if !self.in_flight {
+            return;
+        }
+        self.in_flight = false;
+        match outcome {
+            Outcome::Sent | Outcome::Rejected => {
+                self.queue.pop_front();
+                self.backoff.reset();
+            }
+            Outcome::RateLimited => {
+                self.not_before = self.not_before.max(now + self.pacing.rate_limit_pause);
+            }
+            Outcome::Failed => {
+                self.not_before = self.not_before.max(now + self.backoff.next_delay());
+            }
+        }
+    }
+
+    /// Requests waiting, including one in flight.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn online() -> RequestScheduler<&'static str> {
+        let mut scheduler = RequestScheduler::new(Pacing::default(), 1);
+        scheduler.set_online(true, Duration::ZERO);
+        scheduler
+    }
+
+    #[test]
+    fn requests_queue_while_offline_and_go_out_in_order_spaced_apart() {
+        let mut scheduler = RequestScheduler::new(Pacing::default(), 1);
+        scheduler.push("e2e4").unwrap();
+        scheduler.push("export").unwrap();
+        assert_eq!(scheduler.next(secs(0)), None);
+
+        scheduler.set_online(true, secs(5));
+        assert_eq!(scheduler.next(secs(5)), Some(&"e2e4"));
+        assert_eq!(scheduler.next(secs(5)), None, "one at a time");
+        scheduler.complete(Outcome::Sent, secs(5));
+        assert_eq!(scheduler.next(secs(5)), None, "spaced apart");
+
+        assert_eq!(scheduler.next(secs(5) + MIN_INTERVAL), Some(&"export"));
+        scheduler.complete(Outcome::Rejected, secs(7));
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn rate_limit_pauses_for_a_minute() {
+        let mut scheduler = online();
+        scheduler.push("e2e4").unwrap();
+        scheduler.next(secs(0));
+        scheduler.complete(Outcome::RateLimited, secs(0));
+
+        assert_eq!(scheduler.next(RATE_LIMIT_PAUSE - secs(1)), None);
+        assert_eq!(scheduler.next(RATE_LIMIT_PAUSE), Some(&"e2e4"));
+    }
+
+    #[test]
+    fn failures_are_retried_after_a_growing_backoff() {
+        let mut scheduler = online();
+        scheduler.push("e2e4").unwrap();
+        let mut now = Duration::ZERO;
+        let mut waits = Vec::new();
+        for _ in 0..8 {
+            assert!(scheduler.next(now).is_some());
+            scheduler.complete(Outcome::Failed, now);
+            let retry = scheduler.not_before;
+            assert_eq!(scheduler.next(retry - Duration::from_millis(1)), None);
+            waits.push(retry - now);
+            now = retry;
+        }
+
+        assert!(waits[0] <= RETRY_DELAY);
+        assert!(waits[3] >= RETRY_DELAY * 4);
+        assert!(waits.iter().all(|&wait| wait <= MAX_RETRY_DELAY));
+        assert!(waits[7] >= MAX_RETRY_DELAY / 2);
+    }
+
+    #[test]
+    fn dropped_connection_fails_the_request_in_flight() {
+        let mut scheduler = online();
+        scheduler.push("e2e4").unwrap();
+        scheduler.next(secs(0));
+        scheduler.set_online(false, secs(1));
+        scheduler.set_online(true, secs(1));
+
+        assert_eq!(scheduler.next(secs(1)), None);
+        assert_eq!(scheduler.next(secs(1) + RETRY_DELAY), Some(&"e2e4"));
+    }
+
+    #[test]
+    fn full_queue_refuses_requests() {
+        let mut scheduler = RequestScheduler::new(Pacing::default(), 1);
+        for _ in 0..QUEUE_CAPACITY {
+            scheduler.push("move").unwrap();
+        }
+
+        assert_eq!(scheduler.push("export"), Err("export"));
+        assert_eq!(scheduler.len(), QUEUE_CAPACITY);
+    }
+}
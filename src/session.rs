@@ -1,13 +1,221 @@
-use shakmaty::{Bitboard, ByColor, Chess, Color, Move, Position};
-
-use crate::board_api::GameStatus;
-use crate::feedback::{BoardFeedback, StatusKind, compute_feedback, compute_state_feedback};
+use shakmaty::fen::{Fen, ParseFenError};
+use shakmaty::{
+    Bitboard, Board, ByColor, CastlingMode, Chess, Color, File, Move, Position, PositionErrorKinds,
+    Rank, Role, Square,
+};
+
+use crate::adjudication::{self, Adjudication, DrawReason, History};
+use crate::board_api::{ExternalResult, GameStatus};
+use crate::bounded::BoundedVec;
+use crate::feedback::{
+    BoardFeedback, SquareFeedback, StatusKind, compute_feedback, compute_state_feedback,
+    has_both_kings, is_desynced, result_feedback,
+};
+use crate::inference::{Inference, Inferred};
+use crate::material;
+use crate::move_index::MoveIndex;
+use crate::player::matcher::is_uncertain;
 use crate::player::{GameAction, Player, PlayerStatus};
+use crate::rules::RulesHook;
+use crate::settings::AssistLevel;
+use crate::setup::Placement;
+
+/// How many uncertain moves a human-vs-human game may run ahead before the
+/// board waits for a move to be announced.
+const MAX_INFERRED_PLIES: usize = 4;
+
+/// Most events a single tick reports.
+pub const MAX_TICK_EVENTS: usize = 36;
+
+/// Room kept for the move, check, draw and desync events, so a reading that
+/// disturbs many squares at once only loses per-square events.
+const GAME_EVENT_SLOTS: usize = 4;
+
+pub type TickEvents = BoundedVec<TickEvent, MAX_TICK_EVENTS>;
+
+/// Promotion pieces in the order re-placing the pawn cycles through them.
+const PROMOTION_CHOICES: [Role; 4] = [Role::Queen, Role::Rook, Role::Bishop, Role::Knight];
+
+/// How a pawn reaching the last rank on the board picks its piece.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PromotionPolicy {
+    /// Promote to a queen at once.
+    #[default]
+    QueenOnly,
+    /// Hold the promotion; lifting the pawn and putting it back cycles
+    /// through the pieces (see [`GameSession::promotion_choice`]) until
+    /// [`GameSession::choose_promotion`] is called.
+    GestureSelect,
+    /// Hold the promotion until a client picks the piece with
+    /// [`GameSession::choose_promotion`].
+    ExternalPrompt,
+}
+
+/// When a move detected on the board needs confirming before it counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MoveConfirmation {
+    /// Every detected move is played at once.
+    #[default]
+    Never,
+    /// Tournament rules: every move waits for the mover's clock press.
+    Always,
+    /// Moves that may be a misdetection (see
+    /// [`is_uncertain`](crate::player::matcher::is_uncertain)) wait for a
+    /// clock press or a tap: lifting the piece off its square and putting
+    /// it back. Other moves are played at once.
+    WhenUncertain,
+}
+
+/// A FEN given to [`GameSessionBuilder::fen`] that cannot start a game.
+#[derive(Debug, thiserror::Error)]
+pub enum StartPositionError {
+    #[error("invalid FEN: {0}")]
+    Fen(#[from] ParseFenError),
+    #[error("illegal position: {0:?}")]
+    Position(PositionErrorKinds),
+}
+
+/// Options for a [`GameSession`], set in any order and applied together
+/// by [`Self::build`]. Start with [`GameSession::builder`].
+#[derive(Default)]
+pub struct GameSessionBuilder {
+    position: Chess,
+    rules: Option<Box<dyn RulesHook>>,
+    promotion_policy: PromotionPolicy,
+    confirmation: MoveConfirmation,
+    assist: AssistLevel,
+    dead_squares: Bitboard,
+    adjudication: Adjudication,
+    takeback_limit: Option<usize>,
+    history: Vec<Move>,
+}
+
+impl GameSessionBuilder {
+    /// Start from `position` instead of the standard starting position.
+    pub fn position(mut self, position: Chess) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Start from the standard-chess position in `fen`.
+    pub fn fen(self, fen: &str) -> Result<Self, StartPositionError> {
+        let position = fen
+            .parse::<Fen>()?
+            .into_position(CastlingMode::Standard)
+            .map_err(|e| StartPositionError::Position(e.kinds()))?;
+        Ok(self.position(position))
+    }
+
+    /// Play a variant (see [`GameSession::set_rules`]).
+    pub fn rules(mut self, rules: Box<dyn RulesHook>) -> Self {
+        self.rules = Some(rules);
+        self
+    }
+
+    /// See [`GameSession::set_promotion_policy`].
+    pub fn promotion_policy(mut self, policy: PromotionPolicy) -> Self {
+        self.promotion_policy = policy;
+        self
+    }
+
+    /// How strictly detected moves are held for confirmation (see
+    /// [`GameSession::set_move_confirmation`]).
+    pub fn move_confirmation(mut self, confirmation: MoveConfirmation) -> Self {
+        self.confirmation = confirmation;
+        self
+    }
+
+    /// See [`GameSession::set_assist_level`].
+    pub fn assist_level(mut self, assist: AssistLevel) -> Self {
+        self.assist = assist;
+        self
+    }
+
+    /// See [`GameSession::set_dead_squares`].
+    pub fn dead_squares(mut self, squares: Bitboard) -> Self {
+        self.dead_squares = squares;
+        self
+    }
+
+    /// See [`GameSession::set_adjudication`].
+    pub fn adjudication(mut self, adjudication: Adjudication) -> Self {
+        self.adjudication = adjudication;
+        self
+    }
+
+    /// Let [`GameSession::undo_last_move`] take back at most the last
+    /// `plies` moves. Unlimited by default.
+    pub fn takeback_limit(mut self, plies: usize) -> Self {
+        self.takeback_limit = Some(plies);
+        self
+    }
+
+    /// Moves already played from the start position, e.g. in a game
+    /// resumed after a restart. The session continues after them and
+    /// keeps them for takebacks and repetition; replay stops at the first
+    /// illegal move.
+    pub fn history(mut self, moves: Vec<Move>) -> Self {
+        self.history = moves;
+        self
+    }
+
+    /// Create the session. Players choose how they detect moves (see
+    /// [`crate::player::MatcherKind`]).
+    pub fn build(self, white: Box<dyn Player>, black: Box<dyn Player>) -> GameSession {
+        let mut session = GameSession::from_position(self.position, white, black);
+        if let Some(rules) = self.rules {
+            session.set_rules(rules);
+        }
+        session.set_promotion_policy(self.promotion_policy);
+        session.set_move_confirmation(self.confirmation);
+        session.set_assist_level(self.assist);
+        session.set_dead_squares(self.dead_squares);
+        session.set_adjudication(self.adjudication);
+        session.takeback_limit = self.takeback_limit;
+        session.replay(self.history);
+        session
+    }
+}
+
+/// Something that happened on the board during a tick, for outputs (LEDs,
+/// buzzer, network) that react to occurrences rather than compare states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickEvent {
+    /// A piece of the side to move left its square.
+    PieceLifted { square: Square },
+    /// A piece of the side not to move left its square, as when it is
+    /// being captured.
+    CaptureStarted { square: Square },
+    /// A move was detected and played.
+    MoveDetected { mv: Move },
+    /// A piece was put down where it does not belong, leaving the board out
+    /// of sync with the game.
+    IllegalPlacement { square: Square },
+    /// A move put `color`'s king in check.
+    Check { color: Color },
+    /// A move checkmated `loser`.
+    Checkmate { loser: Color },
+    /// The board stopped matching the game in a way no move in progress
+    /// explains (see [`crate::feedback::is_desynced`]); see
+    /// [`GameSession::recovery`] for what to fix.
+    BoardDesync,
+    /// The board matches the game again after a [`Self::BoardDesync`].
+    BoardRestored,
+    /// A move left a draw the player to move may claim (see
+    /// [`GameSession::claim_draw`]).
+    DrawClaimable { reason: DrawReason },
+}
 
 #[derive(Debug, Clone)]
 pub struct TickResult {
     pub feedback: BoardFeedback,
     pub last_move: Option<Move>,
+    /// The game's status after the tick; terminal once it is over, so
+    /// displays can announce the result.
+    pub status: GameStatus,
+    /// What happened during the tick, in order. Each occurrence is reported
+    /// once, on the tick it starts.
+    pub events: TickEvents,
 }
 
 /// Per-tick orchestration: poll active player -> apply move -> notify opponent -> compute feedback.
@@ -17,28 +225,322 @@ pub struct GameSession {
     black: Box<dyn Player>,
     reference_sensors: ByColor<Bitboard>,
     illegal_move: bool,
-    resigned: Option<Color>,
+    /// Terminal status set by resignation or an external result.
+    terminated: Option<GameStatus>,
+    /// Whether human promotions wait for [`Self::choose_promotion`].
+    promotion_policy: PromotionPolicy,
+    /// A detected promotion waiting for a piece choice (queen as placeholder).
+    pending_promotion: Option<Move>,
+    /// Piece the pending promotion becomes, cycled by re-placing the pawn.
+    promotion_choice: Role,
+    /// The pending promotion's pawn is lifted off the last rank.
+    promotion_lifted: bool,
+    /// How much the feedback guides the player.
+    assist: AssistLevel,
+    /// Which human moves are held until confirmed.
+    confirmation: MoveConfirmation,
+    /// A detected move waiting for [`Self::confirm_move`].
+    pending_move: Option<Move>,
+    /// The pending move's piece is lifted for a confirmation tap.
+    pending_tapped: bool,
+    /// Variant rules layered over standard chess, if any.
+    rules: Option<Box<dyn RulesHook>>,
+    /// Squares whose sensors are known to be dead; their occupancy is inferred.
+    dead_squares: Bitboard,
+    /// Candidate positions while dead squares hide part of the board.
+    inference: Option<Inference>,
+    /// Draw rules applied without a claim.
+    adjudication: Adjudication,
+    /// Positions since the last irreversible move, for repetition.
+    history: History,
+    /// The draw the player to move may claim, if any.
+    claimable: Option<DrawReason>,
+    /// Both kings were lifted while a draw was claimable; putting them
+    /// back claims it.
+    kings_lifted: bool,
+    /// The last reading passed to [`Self::tick`] (the starting pieces
+    /// before the first), to report changes.
+    last_sensors: ByColor<Bitboard>,
+    /// How to bring the board back in sync, while the last tick found it
+    /// out of sync; reset at the start of each tick and set where feedback
+    /// is computed.
+    recovery: Option<Placement>,
+    /// Position the session started from.
+    start: Chess,
+    /// Moves played since the session started.
+    moves: Vec<Move>,
+    /// How many of the last moves may be taken back, if limited.
+    takeback_limit: Option<usize>,
+    /// Moves before this index can no longer be taken back.
+    takeback_floor: usize,
+    /// A move was taken back and the pieces are not back in place yet.
+    restoring: bool,
+    /// Conditional replies `(opponent move, reply)` stored for the
+    /// opponent's next move.
+    conditionals: Vec<(Move, Move)>,
+    /// A stored reply whose condition was met, shown until it is played.
+    guided_move: Option<Move>,
 }
 
 impl GameSession {
+    /// Collect options for a new session.
+    pub fn builder() -> GameSessionBuilder {
+        GameSessionBuilder::default()
+    }
+
     pub fn new(white: Box<dyn Player>, black: Box<dyn Player>) -> Self {
         Self::from_position(Chess::default(), white, black)
     }
 
+    /// Start a session from `position`.
+    ///
+    /// A position without one king per side (see [`has_both_kings`]) cannot
+    /// be played: the session starts out [`GameStatus::Aborted`] and its
+    /// ticks light the failure status instead of detecting moves.
     pub fn from_position(position: Chess, white: Box<dyn Player>, black: Box<dyn Player>) -> Self {
-        let board = position.board();
-        let reference_sensors = ByColor {
-            white: board.by_color(Color::White),
-            black: board.by_color(Color::Black),
-        };
+        let reference_sensors = board_sensors(position.board());
+        let history = History::new(&position);
+        let terminated = (!has_both_kings(position.board())).then(|| {
+            log::error!("Refusing to play {}: missing king", position.board());
+            GameStatus::Aborted
+        });
         Self {
+            start: position.clone(),
             position,
             white,
             black,
             reference_sensors,
             illegal_move: false,
-            resigned: None,
+            terminated,
+            promotion_policy: PromotionPolicy::QueenOnly,
+            pending_promotion: None,
+            promotion_choice: Role::Queen,
+            promotion_lifted: false,
+            assist: AssistLevel::Full,
+            confirmation: MoveConfirmation::Never,
+            pending_move: None,
+            pending_tapped: false,
+            rules: None,
+            dead_squares: Bitboard::EMPTY,
+            inference: None,
+            adjudication: Adjudication::default(),
+            history,
+            claimable: None,
+            kings_lifted: false,
+            moves: Vec::new(),
+            takeback_limit: None,
+            takeback_floor: 0,
+            restoring: false,
+            last_sensors: reference_sensors,
+            recovery: None,
+            conditionals: Vec::new(),
+            guided_move: None,
+        }
+    }
+
+    /// Store a conditional reply for correspondence play: if the opponent
+    /// (a non-interactive player, to move now) plays `trigger`, the board
+    /// guides the human to play `reply`. A later condition on the same
+    /// trigger replaces the earlier one.
+    ///
+    /// When the opponent moves, all conditions are cleared; if one matched,
+    /// its reply is shown as [`Self::guided_move`] until a move is played.
+    ///
+    /// Returns `false` if the game is over, the player to move is
+    /// interactive, `trigger` is illegal, or `reply` is illegal after it.
+    pub fn add_conditional(&mut self, trigger: Move, reply: Move) -> bool {
+        let player = match self.position.turn() {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        };
+        if self.is_game_over()
+            || player.is_interactive()
+            || !self.position.legal_moves().contains(&trigger)
+        {
+            return false;
+        }
+        let mut after = self.position.clone();
+        after.play_unchecked(trigger);
+        if !after.legal_moves().contains(&reply) {
+            return false;
+        }
+        self.conditionals.retain(|&(stored, _)| stored != trigger);
+        self.conditionals.push((trigger, reply));
+        true
+    }
+
+    /// Conditional replies waiting for the opponent's move.
+    pub fn conditionals(&self) -> &[(Move, Move)] {
+        &self.conditionals
+    }
+
+    /// The stored reply to play now, if the opponent's last move matched a
+    /// condition.
+    pub fn guided_move(&self) -> Option<&Move> {
+        self.guided_move.as_ref()
+    }
+
+    /// Choose which draws are declared automatically (see
+    /// [`crate::adjudication`]).
+    pub fn set_adjudication(&mut self, adjudication: Adjudication) {
+        self.adjudication = adjudication;
+    }
+
+    /// Ignore the sensors on `squares`, e.g. after a sensor has failed.
+    ///
+    /// Occupancy on dead squares is inferred (see [`crate::inference`]): a
+    /// move out of a dead square is detected once the piece lands on a live
+    /// square, but a move onto a dead square only shows up as a lifted piece
+    /// and must be announced with [`Self::announce_move`].
+    pub fn set_dead_squares(&mut self, squares: Bitboard) {
+        self.dead_squares = squares;
+        // Follow several plies only when nobody else is waiting on the
+        // position, i.e. both sides move on the board.
+        let max_plies = if self.white.is_interactive() && self.black.is_interactive() {
+            MAX_INFERRED_PLIES
+        } else {
+            1
+        };
+        self.inference = squares
+            .any()
+            .then(|| Inference::new(self.position.clone(), max_plies));
+    }
+
+    pub fn dead_squares(&self) -> Bitboard {
+        self.dead_squares
+    }
+
+    /// Play a move for an interactive player that the sensors cannot see
+    /// because it involves a dead square.
+    ///
+    /// Returns `false` if the move is illegal, vetoed by the rules, does not
+    /// touch a dead square, or the player to move is not interactive.
+    pub fn announce_move(&mut self, mv: Move) -> bool {
+        let player = match self.position.turn() {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        };
+        let touches_dead = {
+            let mut after = self.position.clone();
+            after.play_unchecked(mv);
+            let changed = self.position.board().occupied() ^ after.board().occupied()
+                | mv.from().map_or(Bitboard::EMPTY, Bitboard::from)
+                | Bitboard::from(mv.to());
+            (changed & self.dead_squares).any()
+        };
+        let allowed = self
+            .rules
+            .as_ref()
+            .is_none_or(|rules| rules.allow_move(&self.position, &mv));
+        if self.is_game_over()
+            || !player.is_interactive()
+            || !touches_dead
+            || !allowed
+            || !self.position.legal_moves().contains(&mv)
+        {
+            return false;
+        }
+        log::info!("Announced move {mv}");
+        self.pending_promotion = None;
+        self.pending_move = None;
+        self.pending_tapped = false;
+        self.apply(mv);
+        true
+    }
+
+    /// Play under variant rules (see [`crate::rules`]).
+    pub fn set_rules(&mut self, rules: Box<dyn RulesHook>) {
+        self.rules = Some(rules);
+    }
+
+    /// Choose how promotions played on the board pick their piece. Moves
+    /// from non-interactive players always carry their own piece.
+    pub fn set_promotion_policy(&mut self, policy: PromotionPolicy) {
+        self.promotion_policy = policy;
+    }
+
+    /// Choose how much the feedback guides the player, from the next tick on.
+    pub fn set_assist_level(&mut self, assist: AssistLevel) {
+        self.assist = assist;
+    }
+
+    /// Choose which moves made on the board stay pending until the mover
+    /// confirms them with [`Self::confirm_move`] (pressing their clock).
+    /// Changing the board before that withdraws the move.
+    pub fn set_move_confirmation(&mut self, confirmation: MoveConfirmation) {
+        self.confirmation = confirmation;
+    }
+
+    pub fn move_confirmation(&self) -> MoveConfirmation {
+        self.confirmation
+    }
+
+    /// The move waiting for a clock press, if any.
+    pub fn pending_move(&self) -> Option<&Move> {
+        self.pending_move.as_ref()
+    }
+
+    /// Commit the pending move when `color`, the side to move, presses
+    /// their clock. Returns the move played.
+    pub fn confirm_move(&mut self, color: Color) -> Option<Move> {
+        if self.position.turn() != color {
+            return None;
+        }
+        let mv = self.pending_move.take()?;
+        self.pending_tapped = false;
+        self.apply(mv);
+        Some(mv)
+    }
+
+    /// The promotion waiting for a piece choice, if any.
+    ///
+    /// The returned move carries [`Role::Queen`] as a placeholder.
+    pub fn pending_promotion(&self) -> Option<&Move> {
+        self.pending_promotion.as_ref()
+    }
+
+    /// The piece chosen on the board for the pending promotion, if any.
+    ///
+    /// Starts at [`Role::Queen`]; each time the pawn is lifted and put back
+    /// on the promotion square the choice moves on to the next piece.
+    /// Always `None` unless the policy is [`PromotionPolicy::GestureSelect`].
+    pub fn promotion_choice(&self) -> Option<Role> {
+        self.pending_promotion.and(self.gesture_choice())
+    }
+
+    /// The piece picked on the board, if the policy lets the board pick.
+    fn gesture_choice(&self) -> Option<Role> {
+        (self.promotion_policy == PromotionPolicy::GestureSelect).then_some(self.promotion_choice)
+    }
+
+    /// Complete the pending promotion with the chosen piece.
+    ///
+    /// Returns the move played, or `None` if no promotion is pending or
+    /// `role` is not a legal promotion piece.
+    pub fn choose_promotion(&mut self, role: Role) -> Option<Move> {
+        let Some(Move::Normal {
+            role: pawn,
+            from,
+            capture,
+            to,
+            ..
+        }) = self.pending_promotion
+        else {
+            return None;
+        };
+        let mv = Move::Normal {
+            role: pawn,
+            from,
+            capture,
+            to,
+            promotion: Some(role),
+        };
+        if !self.position.legal_moves().contains(&mv) {
+            return None;
         }
+        self.pending_promotion = None;
+        self.apply(mv);
+        Some(mv)
     }
 
     /// Returns `true` if the resignation was accepted, `false` if rejected
@@ -57,7 +559,7 @@ impl GameSession {
             return false;
         }
 
-        self.resigned = Some(color);
+        self.terminated = Some(GameStatus::Resigned { color });
         let action = GameAction::Resign(color);
         // Broadcast to both players so external players (e.g. RemotePlayer)
         // can propagate the action upstream.
@@ -66,13 +568,124 @@ impl GameSession {
         true
     }
 
+    /// The draw the player to move may claim, if any (see
+    /// [`adjudication::claimable`]).
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        self.claimable
+    }
+
+    /// End the game as a draw on `color`'s claim. On the board, the player
+    /// to move claims by lifting both kings and putting them back.
+    ///
+    /// Returns `false` if no draw is claimable, `color` is not to move, or
+    /// their player is not interactive. Both players are notified with
+    /// [`GameAction::DrawClaimed`].
+    pub fn claim_draw(&mut self, color: Color) -> bool {
+        let player = match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        };
+        let Some(reason) = self.claimable else {
+            return false;
+        };
+        if self.is_game_over() || self.position.turn() != color || !player.is_interactive() {
+            return false;
+        }
+        log::info!("{color:?} claimed a draw: {reason:?}");
+        self.terminated = Some(GameStatus::Draw);
+        self.claimable = None;
+        self.kings_lifted = false;
+        let action = GameAction::DrawClaimed(color);
+        self.white.notify(&action);
+        self.black.notify(&action);
+        true
+    }
+
+    /// Take back the last move: the position and the expected board go
+    /// back to before it, and until the pieces are put back the board
+    /// shows how to restore them instead of detecting moves. Both players
+    /// are notified with [`GameAction::Takeback`].
+    ///
+    /// Returns the move, or `None` if there is none, it is beyond the
+    /// [`GameSessionBuilder::takeback_limit`], the game was ended by a
+    /// resignation, result or adjudication, variant rules are active
+    /// (their state cannot be rewound), or a player cannot follow it (see
+    /// [`Player::allows_takeback`]).
+    pub fn undo_last_move(&mut self) -> Option<Move> {
+        if self.terminated.is_some()
+            || self.rules.is_some()
+            || self.moves.len() <= self.takeback_floor
+            || !self.white.allows_takeback()
+            || !self.black.allows_takeback()
+        {
+            return None;
+        }
+        let mv = self.moves.pop()?;
+        let mut position = self.start.clone();
+        let mut history = History::new(&position);
+        for &played in &self.moves {
+            position.play_unchecked(played);
+            history.push(&position);
+        }
+        log::info!("Took back {mv}");
+        if let Some(inference) = &mut self.inference {
+            inference.reset(position.clone());
+        }
+        self.claimable = adjudication::claimable(&position, &history);
+        self.kings_lifted = false;
+        self.position = position;
+        self.history = history;
+        self.illegal_move = false;
+        self.pending_promotion = None;
+        self.pending_move = None;
+        self.pending_tapped = false;
+        self.conditionals.clear();
+        self.guided_move = None;
+        self.restoring = true;
+        let action = GameAction::Takeback(mv);
+        self.white.notify(&action);
+        self.black.notify(&action);
+        Some(mv)
+    }
+
+    /// End the game with a result decided outside the board, e.g. by the
+    /// online service behind a remote player.
+    ///
+    /// Returns `false` if the game is already over.
+    pub fn report_result(&mut self, result: ExternalResult) -> bool {
+        if self.is_game_over() {
+            return false;
+        }
+        self.terminated = Some(result.status());
+        true
+    }
+
+    /// End the game because `loser`'s clock ran out. Adjudicated as a draw
+    /// instead when enabled and the opponent cannot mate.
+    ///
+    /// Returns `false` if the game is already over.
+    pub fn time_out(&mut self, loser: Color) -> bool {
+        if self.is_game_over() {
+            return false;
+        }
+        let status = self.adjudication.timeout(&self.position, loser);
+        if status == GameStatus::Draw {
+            log::info!(
+                "Adjudicated draw: {loser:?} flagged but {:?} cannot mate",
+                loser.other()
+            );
+        }
+        self.terminated = Some(status);
+        true
+    }
+
     pub fn is_game_over(&self) -> bool {
         self.game_state().is_terminal()
     }
 
     pub fn game_state(&self) -> GameStatus {
-        if let Some(color) = self.resigned {
-            return GameStatus::Resigned { color };
+        if let Some(status) = &self.terminated {
+            return status.clone();
         }
         let legal_moves = self.position.legal_moves();
         if legal_moves.is_empty() {
@@ -89,32 +702,230 @@ impl GameSession {
     }
 
     pub fn tick(&mut self, sensors: ByColor<Bitboard>) -> TickResult {
+        let before = self.position.board().clone();
+        let turn = self.position.turn();
+        let desynced_before = self.recovery.take().is_some();
+        let (feedback, last_move) = self.tick_board(sensors);
+        let status = self.game_state();
+
+        let mut events = TickEvents::new();
+        let square_events = MAX_TICK_EVENTS - GAME_EVENT_SLOTS;
+        if !status.is_terminal() || last_move.is_some() {
+            let last = self.last_sensors;
+            let left = (last.white | last.black) & !(sensors.white | sensors.black);
+            for square in left {
+                match before.color_at(square) {
+                    Some(color) if color == turn => {
+                        record(
+                            &mut events,
+                            square_events,
+                            TickEvent::PieceLifted { square },
+                        );
+                    }
+                    Some(_) => {
+                        record(
+                            &mut events,
+                            square_events,
+                            TickEvent::CaptureStarted { square },
+                        );
+                    }
+                    None => {}
+                }
+            }
+            if self.recovery.is_some() {
+                let board = self.position.board();
+                let misplaced = (sensors.white & !last.white & !board.by_color(Color::White))
+                    | (sensors.black & !last.black & !board.by_color(Color::Black));
+                for square in misplaced {
+                    record(
+                        &mut events,
+                        square_events,
+                        TickEvent::IllegalPlacement { square },
+                    );
+                }
+            }
+            if let Some(mv) = last_move {
+                record(&mut events, MAX_TICK_EVENTS, TickEvent::MoveDetected { mv });
+                if let GameStatus::Checkmate { loser } = status {
+                    record(&mut events, MAX_TICK_EVENTS, TickEvent::Checkmate { loser });
+                } else if self.position.is_check() {
+                    let color = self.position.turn();
+                    record(&mut events, MAX_TICK_EVENTS, TickEvent::Check { color });
+                }
+                if let Some(reason) = self.claimable {
+                    record(
+                        &mut events,
+                        MAX_TICK_EVENTS,
+                        TickEvent::DrawClaimable { reason },
+                    );
+                }
+            }
+        }
+        match (desynced_before, self.recovery.is_some()) {
+            (false, true) => record(&mut events, MAX_TICK_EVENTS, TickEvent::BoardDesync),
+            (true, false) => record(&mut events, MAX_TICK_EVENTS, TickEvent::BoardRestored),
+            _ => {}
+        }
+        self.last_sensors = sensors;
+
+        TickResult {
+            feedback,
+            last_move,
+            status,
+            events,
+        }
+    }
+
+    /// Detect and apply a move from `sensors`, returning the feedback to
+    /// show and the move played, if any.
+    fn tick_board(&mut self, mut sensors: ByColor<Bitboard>) -> (BoardFeedback, Option<Move>) {
         // Short-circuit: game already ended.
-        if self.is_game_over() {
-            return TickResult {
-                feedback: compute_state_feedback(&self.position, sensors),
-                last_move: None,
-            };
+        let status = self.game_state();
+        if status.is_terminal() {
+            if !has_both_kings(self.position.board()) {
+                return (BoardFeedback::with_status(StatusKind::Failure), None);
+            }
+            return (
+                result_feedback(&self.position, &status)
+                    .unwrap_or_else(|| compute_state_feedback(&self.position, sensors)),
+                None,
+            );
+        }
+
+        if let Some(rules) = &mut self.rules {
+            if !rules.observe(&self.position, sensors) {
+                let mut feedback = BoardFeedback::new();
+                rules.decorate(&self.position, &mut feedback);
+                return (feedback, None);
+            }
+            // Hide variant entities from move detection and feedback.
+            let entities = rules.entities();
+            sensors.white &= !entities;
+            sensors.black &= !entities;
         }
 
         let mut last_move = None;
 
+        if self.dead_squares.any() {
+            last_move = self.infer_moves(sensors);
+            if let Some(inference) = &self.inference
+                && inference.candidates().count() > 1
+            {
+                // Hold until a later move or an announcement decides.
+                let mut feedback = BoardFeedback::new();
+                for square in inference.uncertain() {
+                    feedback.set(square, SquareFeedback::Stalemate);
+                }
+                return (feedback, last_move);
+            }
+            let board = self.position.board();
+            let dead = self.dead_squares;
+            sensors.white = (sensors.white & !dead) | (board.by_color(Color::White) & dead);
+            sensors.black = (sensors.black & !dead) | (board.by_color(Color::Black) & dead);
+        }
+
+        if self.restoring {
+            if sensors != board_sensors(self.position.board()) {
+                // Guide the pieces back rather than read the taken back
+                // move off the board again.
+                return (compute_state_feedback(&self.position, sensors), None);
+            }
+            log::info!("Board restored after takeback");
+            self.restoring = false;
+            self.reference_sensors = sensors;
+        }
+
+        if let Some(feedback) = self.claim_gesture(sensors) {
+            return (feedback, None);
+        }
+
+        if let Some(pending) = self.pending_promotion {
+            let mut after = self.position.clone();
+            after.play_unchecked(pending);
+            let expected = after.board().occupied();
+            let current = sensors.white | sensors.black;
+            if expected == current || expected.without(pending.to()) == current {
+                // Lifting the pawn and putting it back picks the next piece.
+                let gestures = self.promotion_policy == PromotionPolicy::GestureSelect;
+                if gestures && expected == current && self.promotion_lifted {
+                    self.promotion_choice = next_promotion_choice(self.promotion_choice);
+                    log::info!(
+                        "Promotion {pending} now chooses {:?}",
+                        self.promotion_choice
+                    );
+                }
+                self.promotion_lifted = expected != current;
+                return (promotion_feedback(&pending, self.gesture_choice()), None);
+            }
+            // The pawn went somewhere else; treat the move as taken back.
+            log::info!("Promotion {pending} withdrawn before a piece was chosen");
+            self.pending_promotion = None;
+            self.promotion_lifted = false;
+        }
+
+        let mut tapped = None;
+        if let Some(pending) = self.pending_move {
+            let mut after = self.position.clone();
+            after.play_unchecked(pending);
+            let expected = after.board().occupied();
+            let current = sensors.white | sensors.black;
+            let taps = self.confirmation == MoveConfirmation::WhenUncertain;
+            if expected == current && taps && self.pending_tapped {
+                log::info!("Move {pending} confirmed by a tap");
+                self.pending_move = None;
+                self.pending_tapped = false;
+                tapped = Some(pending);
+            } else if expected == current || taps && expected.without(pending.to()) == current {
+                self.pending_tapped |= expected != current;
+                let mut feedback = BoardFeedback::new();
+                feedback.set(pending.to(), SquareFeedback::Destination);
+                return (feedback, None);
+            } else {
+                log::info!("Move {pending} withdrawn before it was confirmed");
+                self.pending_move = None;
+                self.pending_tapped = false;
+            }
+        }
+
         // Poll the active player.
         let turn = self.position.turn();
         let player = match turn {
             Color::White => &mut self.white,
             Color::Black => &mut self.black,
         };
-        if let Some(mv) = player.poll_move(&self.position, sensors) {
-            if self.position.legal_moves().contains(&mv) {
-                self.position.play_unchecked(mv);
-                last_move = Some(mv);
-
-                let other = match turn {
-                    Color::White => &mut self.black,
-                    Color::Black => &mut self.white,
+        if let Some(mv) = tapped {
+            self.apply(mv);
+            last_move = Some(mv);
+        } else if let Some(mv) = player.poll_move(&self.position, sensors) {
+            let allowed = self
+                .rules
+                .as_ref()
+                .is_none_or(|rules| rules.allow_move(&self.position, &mv));
+            if allowed && self.position.legal_moves().contains(&mv) {
+                if self.promotion_policy != PromotionPolicy::QueenOnly
+                    && mv.is_promotion()
+                    && player.is_interactive()
+                {
+                    log::info!("Promotion {mv} detected, waiting for piece choice");
+                    self.pending_promotion = Some(mv);
+                    self.promotion_choice = Role::Queen;
+                    self.promotion_lifted = false;
+                    return (promotion_feedback(&mv, self.gesture_choice()), None);
+                }
+                let confirm = match self.confirmation {
+                    MoveConfirmation::Never => false,
+                    MoveConfirmation::Always => true,
+                    MoveConfirmation::WhenUncertain => is_uncertain(&self.position, &mv),
                 };
-                other.opponent_moved(&self.position, &mv);
+                if confirm && player.is_interactive() {
+                    log::info!("Move {mv} detected, waiting for confirmation");
+                    self.pending_move = Some(mv);
+                    let mut feedback = BoardFeedback::new();
+                    feedback.set(mv.to(), SquareFeedback::Destination);
+                    return (feedback, None);
+                }
+                self.apply(mv);
+                last_move = Some(mv);
             } else {
                 log::warn!("Illegal move from {turn:?} player: {mv}");
                 self.illegal_move = true;
@@ -127,16 +938,27 @@ impl GameSession {
             Color::White => self.white.is_interactive(),
             Color::Black => self.black.is_interactive(),
         };
-        let mut feedback = if active_is_interactive {
-            compute_feedback(&self.position, sensors, self.reference_sensors)
-        } else {
+        let settled = board_sensors(self.position.board());
+        if active_is_interactive && is_desynced(&self.position, sensors, self.reference_sensors) {
+            self.recovery = Some(Placement::between(&settled, &sensors));
+        }
+        let only_lifted = (sensors.white & !settled.white).is_empty()
+            && (sensors.black & !settled.black).is_empty();
+        let mut feedback = if !active_is_interactive {
             compute_state_feedback(&self.position, sensors)
+        } else if self.assist == AssistLevel::Minimal && only_lifted {
+            // No move hints: show the board as if nothing were lifted.
+            compute_state_feedback(&self.position, settled)
+        } else {
+            compute_feedback(&self.position, sensors, self.reference_sensors)
         };
 
-        if self.illegal_move
-            || self.white.status() == PlayerStatus::Error
-            || self.black.status() == PlayerStatus::Error
-        {
+        let failed = [&self.white, &self.black]
+            .into_iter()
+            .filter(|player| player.status() == PlayerStatus::Error);
+        if let Some(error) = failed.clone().find_map(|player| player.error_feedback()) {
+            feedback = error;
+        } else if self.illegal_move || failed.count() > 0 {
             feedback = feedback.with_merged_status(StatusKind::Failure);
         }
 
@@ -148,16 +970,291 @@ impl GameSession {
             self.reference_sensors = sensors;
         }
 
-        TickResult {
-            feedback,
-            last_move,
+        if active_is_interactive {
+            if let Some(guided) = self.guided_move
+                && feedback.is_empty()
+            {
+                // The board is caught up; show the stored reply.
+                if let Some(from) = guided.from() {
+                    feedback.set(from, SquareFeedback::Origin);
+                }
+                feedback.set(guided.to(), SquareFeedback::Destination);
+            }
+            self.remind_announce(sensors, &mut feedback);
+        }
+
+        if let Some(rules) = &self.rules {
+            rules.decorate(&self.position, &mut feedback);
+        }
+
+        (feedback, last_move)
+    }
+
+    /// Follow the draw claim gesture: while a draw is claimable, lifting
+    /// both kings, and nothing else, lights their squares until both are
+    /// back, which claims it. Returns the feedback to show instead of detecting
+    /// moves.
+    ///
+    /// Any other piece leaving or arriving cancels the gesture, so clearing
+    /// the board (see [`crate::abort`]) and setting it up again does not
+    /// claim a draw.
+    fn claim_gesture(&mut self, sensors: ByColor<Bitboard>) -> Option<BoardFeedback> {
+        let player = match self.position.turn() {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        };
+        if self.claimable.is_none()
+            || !player.is_interactive()
+            || self.pending_promotion.is_some()
+            || self.pending_move.is_some()
+        {
+            return None;
+        }
+        let kings = self.position.board().kings();
+        let settled = board_sensors(self.position.board());
+        let only_kings_off = |color: Color| {
+            let expected = settled[color];
+            (sensors[color] & !expected).is_empty()
+                && (expected & !kings & !sensors[color]).is_empty()
+        };
+        if !only_kings_off(Color::White) || !only_kings_off(Color::Black) {
+            self.kings_lifted = false;
+            return None;
+        }
+        let off = kings & !(sensors.white | sensors.black);
+        self.kings_lifted |= off == kings;
+        if !self.kings_lifted {
+            return None;
+        }
+        if off.any() {
+            let mut feedback = BoardFeedback::new();
+            for king in off {
+                feedback.set(king, SquareFeedback::Destination);
+            }
+            return Some(feedback);
+        }
+        self.kings_lifted = false;
+        if !self.claim_draw(self.position.turn()) {
+            return None;
+        }
+        result_feedback(&self.position, &GameStatus::Draw)
+    }
+
+    /// Feed a reading to the dead-square inference and play the moves it
+    /// settles on. Returns the last move played.
+    fn infer_moves(&mut self, sensors: ByColor<Bitboard>) -> Option<Move> {
+        let active_is_interactive = match self.position.turn() {
+            Color::White => self.white.is_interactive(),
+            Color::Black => self.black.is_interactive(),
+        };
+        if !active_is_interactive || self.pending_promotion.is_some() || self.pending_move.is_some()
+        {
+            return None;
+        }
+        // Take the tracker out so `apply` keeps the lines it still follows.
+        let mut inference = self.inference.take()?;
+        let mut last_move = None;
+        if let Inferred::Resolved(moves) = inference.observe(sensors, self.dead_squares) {
+            for mv in moves {
+                let allowed = self
+                    .rules
+                    .as_ref()
+                    .is_none_or(|rules| rules.allow_move(&self.position, &mv));
+                if !allowed || !self.position.legal_moves().contains(&mv) {
+                    log::warn!("Inferred move {mv} not allowed");
+                    inference.reset(self.position.clone());
+                    break;
+                }
+                log::info!("Inferred move {mv}");
+                self.apply(mv);
+                last_move = Some(mv);
+            }
+        }
+        self.inference = Some(inference);
+        last_move
+    }
+
+    /// Mark dead destinations of a lifted piece, reminding the player to
+    /// announce the move if they put the piece there.
+    fn remind_announce(&self, sensors: ByColor<Bitboard>, feedback: &mut BoardFeedback) {
+        if self.dead_squares.is_empty() {
+            return;
+        }
+        let turn = self.position.turn();
+        let lifted = self.position.board().by_color(turn) & !sensors[turn];
+        let Some(from) = lifted.single_square() else {
+            return;
+        };
+        for mv in MoveIndex::new(&self.position).moves_from(from) {
+            if self.dead_squares.contains(mv.to()) {
+                feedback.set(mv.to(), SquareFeedback::Stalemate);
+            }
+        }
+    }
+
+    /// Play `moves` without telling the players, as if before the session
+    /// began.
+    fn replay(&mut self, moves: Vec<Move>) {
+        if moves.is_empty() {
+            return;
+        }
+        for mv in moves {
+            if !self.position.legal_moves().contains(&mv) {
+                log::warn!("History stops before illegal move {mv}");
+                break;
+            }
+            self.position.play_unchecked(mv);
+            self.moves.push(mv);
+            self.history.push(&self.position);
+        }
+        self.claimable = adjudication::claimable(&self.position, &self.history);
+        self.reference_sensors = board_sensors(self.position.board());
+        self.last_sensors = self.reference_sensors;
+        if let Some(inference) = &mut self.inference {
+            inference.reset(self.position.clone());
+        }
+    }
+
+    fn apply(&mut self, mv: Move) {
+        let turn = self.position.turn();
+        self.position.play_unchecked(mv);
+        self.moves.push(mv);
+        if let Some(limit) = self.takeback_limit {
+            self.takeback_floor = self
+                .takeback_floor
+                .max(self.moves.len().saturating_sub(limit));
+        }
+        self.guided_move = None;
+        if !self.conditionals.is_empty() {
+            let conditionals = std::mem::take(&mut self.conditionals);
+            self.guided_move = conditionals
+                .into_iter()
+                .find(|&(trigger, _)| trigger == mv)
+                .map(|(_, reply)| reply);
+            match self.guided_move {
+                Some(reply) => log::info!("{mv} met a condition, guiding reply {reply}"),
+                None => log::info!("{mv} met no condition, conditions cleared"),
+            }
+        }
+        let other = match turn {
+            Color::White => &mut self.black,
+            Color::Black => &mut self.white,
+        };
+        other.opponent_moved(&self.position, &mv);
+        if let Some(rules) = &mut self.rules {
+            rules.after_move(&self.position, &mv);
+        }
+        if let Some(inference) = &mut self.inference {
+            inference.reset(self.position.clone());
+        }
+        self.history.push(&self.position);
+        if let Some(reason) = self.adjudication.after_move(&self.position, &self.history) {
+            log::info!("Adjudicated draw after {mv}: {reason:?}");
+            self.terminated = Some(GameStatus::Draw);
+        }
+        self.kings_lifted = false;
+        self.claimable = self
+            .terminated
+            .is_none()
+            .then(|| adjudication::claimable(&self.position, &self.history))
+            .flatten();
+        if let Some(reason) = self.claimable {
+            log::info!("Draw claimable after {mv}: {reason:?}");
         }
     }
 
+    /// The position the game started from.
+    pub fn start(&self) -> &Chess {
+        &self.start
+    }
+
     #[inline]
     pub fn position(&self) -> &Chess {
         &self.position
     }
+
+    /// While the board is out of sync with the game (see
+    /// [`TickEvent::BoardDesync`]), the pieces missing from their squares
+    /// and the pieces that do not belong where they stand.
+    ///
+    /// A lifted piece of the side to move still counts as missing here,
+    /// since it has to go somewhere for the board to match again.
+    pub fn recovery(&self) -> Option<&Placement> {
+        self.recovery.as_ref()
+    }
+
+    /// Moves played so far, in order.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// The pieces each side has captured, in the order taken (see
+    /// [`crate::material`]). Taking back a move returns its capture.
+    pub fn captured(&self) -> ByColor<Vec<Role>> {
+        material::captured(self.start.turn(), &self.moves)
+    }
+}
+
+fn next_promotion_choice(role: Role) -> Role {
+    let at = PROMOTION_CHOICES
+        .iter()
+        .position(|&r| r == role)
+        .unwrap_or(0);
+    PROMOTION_CHOICES[(at + 1) % PROMOTION_CHOICES.len()]
+}
+
+/// The square lit for each promotion piece: c–f on the rank in front of
+/// the promotion, read from the promoting player's left to right.
+fn promotion_choice_square(color: Color, role: Role) -> Square {
+    let at = PROMOTION_CHOICES
+        .iter()
+        .position(|&r| r == role)
+        .unwrap_or(0) as u32;
+    match color {
+        Color::White => Square::from_coords(File::new(2 + at), Rank::Seventh),
+        Color::Black => Square::from_coords(File::new(5 - at), Rank::Second),
+    }
+}
+
+/// The promotion square lit as a destination and, with a `choice` to
+/// show, the four choice squares with the current choice also lit as a
+/// destination.
+fn promotion_feedback(pending: &Move, choice: Option<Role>) -> BoardFeedback {
+    let color = match pending.to().rank() {
+        Rank::Eighth => Color::White,
+        _ => Color::Black,
+    };
+    let mut feedback = BoardFeedback::new();
+    feedback.set(pending.to(), SquareFeedback::Destination);
+    let Some(choice) = choice else {
+        return feedback;
+    };
+    for role in PROMOTION_CHOICES {
+        feedback.set(
+            promotion_choice_square(color, role),
+            SquareFeedback::RookDestination,
+        );
+    }
+    feedback.set(
+        promotion_choice_square(color, choice),
+        SquareFeedback::Destination,
+    );
+    feedback
+}
+
+/// Append `event` while `events` holds fewer than `limit`. Per-square events
+/// use a limit below the capacity, so the game events always fit.
+fn record(events: &mut TickEvents, limit: usize, event: TickEvent) {
+    if events.len() < limit {
+        let _ = events.push(event);
+    }
+}
+
+fn board_sensors(board: &Board) -> ByColor<Bitboard> {
+    ByColor {
+        white: board.by_color(Color::White),
+        black: board.by_color(Color::Black),
+    }
 }
 
 #[cfg(test)]
@@ -165,6 +1262,7 @@ mod tests {
     use super::*;
     use crate::player::{HumanPlayer, RemotePlayer};
     use crate::testutil::ScriptedSensor;
+    use shakmaty::uci::UciMove;
     use shakmaty::{Color, Position, Square};
     use std::sync::mpsc;
 
@@ -178,6 +1276,15 @@ mod tests {
         last.expect("script should produce at least one tick")
     }
 
+    /// Every event of every tick in the script, in order.
+    fn script_events(sensor: &mut ScriptedSensor, session: &mut GameSession) -> Vec<TickEvent> {
+        let mut events = Vec::new();
+        sensor
+            .drain(|p| events.extend(session.tick(p).events))
+            .expect("script should parse");
+        events
+    }
+
     fn human_vs_human() -> (ScriptedSensor, GameSession) {
         let sensor = ScriptedSensor::new();
         let initial = sensor.read_positions();
@@ -258,6 +1365,21 @@ mod tests {
         }
     }
 
+    /// Test player that failed on a server certificate.
+    struct CertificateErrorPlayer;
+
+    impl Player for CertificateErrorPlayer {
+        fn poll_move(&mut self, _position: &Chess, _sensors: ByColor<Bitboard>) -> Option<Move> {
+            None
+        }
+        fn status(&self) -> PlayerStatus {
+            PlayerStatus::Error
+        }
+        fn error_feedback(&self) -> Option<BoardFeedback> {
+            Some(crate::tls::CertificateError::Expired.feedback())
+        }
+    }
+
     #[test]
     fn human_move_advances_game() {
         let (mut sensor, mut session) = human_vs_human();
@@ -309,6 +1431,97 @@ mod tests {
         assert!(result.last_move.is_none());
     }
 
+    #[test]
+    fn events_report_lifts_captures_and_moves() {
+        let (mut sensor, mut session) = human_vs_human();
+        sensor.push_script("e2 We4. d7 Bd5.").unwrap();
+        run_script(&mut sensor, &mut session);
+
+        sensor.push_script("d5. e4 Wd5.").unwrap();
+        let events = script_events(&mut sensor, &mut session);
+        let mv = *session.moves().last().unwrap();
+        assert_eq!(
+            events,
+            [
+                TickEvent::CaptureStarted { square: Square::D5 },
+                TickEvent::PieceLifted { square: Square::E4 },
+                TickEvent::MoveDetected { mv },
+            ]
+        );
+    }
+
+    #[test]
+    fn events_report_check_and_checkmate() {
+        let (mut sensor, mut session) = human_vs_human();
+        sensor.push_script("e2 We4. f7 Bf6.").unwrap();
+        run_script(&mut sensor, &mut session);
+        sensor.push_script("d1 Wh5.").unwrap();
+        let events = script_events(&mut sensor, &mut session);
+        assert_eq!(
+            events.last(),
+            Some(&TickEvent::Check {
+                color: Color::Black
+            })
+        );
+
+        let (mut sensor, mut session) = human_vs_human();
+        sensor
+            .push_script("f2 Wf3. e7 Be5. g2 Wg4. d8 Bh4.")
+            .unwrap();
+        let events = script_events(&mut sensor, &mut session);
+        assert_eq!(
+            events.last(),
+            Some(&TickEvent::Checkmate {
+                loser: Color::White
+            })
+        );
+    }
+
+    #[test]
+    fn stray_piece_reports_desync_once() {
+        let (mut sensor, mut session) = human_vs_human();
+        sensor.push_script("We4. .").unwrap();
+        let events = script_events(&mut sensor, &mut session);
+        assert_eq!(
+            events,
+            [
+                TickEvent::IllegalPlacement { square: Square::E4 },
+                TickEvent::BoardDesync,
+            ]
+        );
+
+        let recovery = session.recovery().expect("out of sync");
+        assert_eq!(recovery.extra, Bitboard::from(Square::E4));
+        assert!(recovery.missing.white.is_empty());
+
+        // Cleared and put back: a new desync.
+        sensor.push_script("e4. We4.").unwrap();
+        let events = script_events(&mut sensor, &mut session);
+        assert_eq!(
+            events,
+            [
+                TickEvent::BoardRestored,
+                TickEvent::IllegalPlacement { square: Square::E4 },
+                TickEvent::BoardDesync,
+            ]
+        );
+        assert!(session.recovery().is_some());
+    }
+
+    #[test]
+    fn crowded_reading_keeps_the_desync_event() {
+        let (_, mut session) = human_vs_human();
+        let crowded = ByColor {
+            white: Bitboard::FULL,
+            black: Bitboard::EMPTY,
+        };
+
+        let events = session.tick(crowded).events;
+
+        assert_eq!(events.len(), MAX_TICK_EVENTS - GAME_EVENT_SLOTS + 1);
+        assert_eq!(events.last(), Some(&TickEvent::BoardDesync));
+    }
+
     #[test]
     fn lifted_piece_shows_destinations() {
         let (mut sensor, mut session) = human_vs_human();
@@ -320,6 +1533,26 @@ mod tests {
         assert!(result.feedback.get(Square::E4).is_some());
     }
 
+    #[test]
+    fn minimal_assist_hides_move_hints_but_not_recovery() {
+        let (mut sensor, mut session) = human_vs_human();
+        session.set_assist_level(AssistLevel::Minimal);
+
+        sensor.push_script("e2.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert!(result.feedback.is_empty());
+
+        // A piece put down on a wrong square is still pointed out.
+        sensor.push_script("We5.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert!(result.feedback.get(Square::E5).is_some());
+
+        session.set_assist_level(AssistLevel::Full);
+        sensor.push_script("e5.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert!(result.feedback.get(Square::E4).is_some());
+    }
+
     #[test]
     fn lifted_piece_guidance_persists_across_ticks() {
         use crate::feedback::SquareFeedback;
@@ -385,6 +1618,74 @@ mod tests {
         assert!(!result.feedback.is_empty());
     }
 
+    #[test]
+    fn matching_condition_guides_the_stored_reply() {
+        let (mut sensor, mut session, tx) = human_vs_remote();
+        sensor.push_script("e2 We4.").unwrap();
+        run_script(&mut sensor, &mut session);
+
+        let e7e5 = parse(&session, "e7e5");
+        let mut after = session.position().clone();
+        after.play_unchecked(e7e5);
+        let g1f3 = parse_at(&after, "g1f3");
+        assert!(session.add_conditional(e7e5, g1f3));
+        assert!(
+            !session.add_conditional(g1f3, e7e5),
+            "trigger must be legal"
+        );
+
+        tx.send(e7e5).unwrap();
+        session.tick(sensor.read_positions());
+        assert_eq!(session.guided_move(), Some(&g1f3));
+        assert!(session.conditionals().is_empty());
+
+        // Execute the remote move on the board; the reply lights up.
+        sensor.push_script("e7 Be5.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert_eq!(
+            result.feedback.get(Square::G1),
+            Some(SquareFeedback::Origin)
+        );
+        assert_eq!(
+            result.feedback.get(Square::F3),
+            Some(SquareFeedback::Destination)
+        );
+
+        sensor.push_script("g1 Wf3.").unwrap();
+        run_script(&mut sensor, &mut session);
+        assert_eq!(session.guided_move(), None);
+    }
+
+    #[test]
+    fn other_opponent_move_clears_conditions() {
+        let (mut sensor, mut session, tx) = human_vs_remote();
+        sensor.push_script("e2 We4.").unwrap();
+        run_script(&mut sensor, &mut session);
+        let reply_out_of_turn = parse(&session, "d7d5");
+        assert!(!session.add_conditional(parse(&session, "e7e5"), reply_out_of_turn));
+
+        let e7e5 = parse(&session, "e7e5");
+        let mut after = session.position().clone();
+        after.play_unchecked(e7e5);
+        assert!(session.add_conditional(e7e5, parse_at(&after, "g1f3")));
+
+        tx.send(parse(&session, "c7c5")).unwrap();
+        session.tick(sensor.read_positions());
+
+        assert_eq!(session.guided_move(), None);
+        assert!(session.conditionals().is_empty());
+    }
+
+    #[test]
+    fn conditions_are_only_stored_on_the_opponents_turn() {
+        let (_sensor, mut session) = human_vs_human();
+        let e2e4 = parse(&session, "e2e4");
+        let mut after = session.position().clone();
+        after.play_unchecked(e2e4);
+
+        assert!(!session.add_conditional(e2e4, parse_at(&after, "e7e5")));
+    }
+
     #[test]
     fn position_accessor() {
         let (_sensor, session) = human_vs_human();
@@ -415,6 +1716,235 @@ mod tests {
         assert_eq!(session.position().turn(), Color::Black);
     }
 
+    /// White pawn on e7 ready to promote, human vs human.
+    fn promotion_session() -> (ScriptedSensor, GameSession) {
+        let position: Chess = "7k/4P3/8/8/8/8/8/K7 w - - 0 1"
+            .parse::<shakmaty::fen::Fen>()
+            .unwrap()
+            .into_position(shakmaty::CastlingMode::Standard)
+            .unwrap();
+        let board = position.board();
+        let sensor = ScriptedSensor::from_bitboards(
+            board.by_color(Color::White),
+            board.by_color(Color::Black),
+        )
+        .unwrap();
+        let initial = sensor.read_positions();
+        let session = GameSession::from_position(
+            position,
+            Box::new(HumanPlayer::new(initial)),
+            Box::new(HumanPlayer::new(initial)),
+        );
+        (sensor, session)
+    }
+
+    #[test]
+    fn promotion_plays_queen_without_prompt() {
+        let (mut sensor, mut session) = promotion_session();
+        sensor.push_script("e7 We8.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+
+        assert_eq!(
+            result.last_move.and_then(|mv| mv.promotion()),
+            Some(Role::Queen)
+        );
+        assert!(session.pending_promotion().is_none());
+    }
+
+    #[test]
+    fn promotion_prompt_holds_move_until_choice() {
+        let (mut sensor, mut session) = promotion_session();
+        session.set_promotion_policy(PromotionPolicy::GestureSelect);
+        sensor.push_script("e7 We8.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+
+        assert!(result.last_move.is_none());
+        assert_eq!(
+            session.pending_promotion().map(|mv| mv.to()),
+            Some(Square::E8)
+        );
+        assert_eq!(
+            result.feedback.get(Square::E8),
+            Some(SquareFeedback::Destination)
+        );
+        assert_eq!(session.position().turn(), Color::White);
+
+        let mv = session
+            .choose_promotion(Role::Knight)
+            .expect("promotion pending");
+        assert_eq!(mv.promotion(), Some(Role::Knight));
+        assert_eq!(
+            session.position().board().piece_at(Square::E8),
+            Some(Role::Knight.of(Color::White))
+        );
+        assert_eq!(session.position().turn(), Color::Black);
+        assert!(session.pending_promotion().is_none());
+    }
+
+    #[test]
+    fn promotion_prompt_withdrawn_when_pawn_returns() {
+        let (mut sensor, mut session) = promotion_session();
+        session.set_promotion_policy(PromotionPolicy::GestureSelect);
+        sensor.push_script("e7 We8. e8 We7.").unwrap();
+        run_script(&mut sensor, &mut session);
+
+        assert!(session.pending_promotion().is_none());
+        assert_eq!(session.position().turn(), Color::White);
+        assert!(session.choose_promotion(Role::Queen).is_none());
+    }
+
+    #[test]
+    fn replacing_the_promoted_pawn_cycles_the_choice() {
+        let (mut sensor, mut session) = promotion_session();
+        session.set_promotion_policy(PromotionPolicy::GestureSelect);
+        sensor.push_script("e7 We8.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert_eq!(session.promotion_choice(), Some(Role::Queen));
+        assert_eq!(
+            result.feedback.get(Square::C7),
+            Some(SquareFeedback::Destination)
+        );
+        assert_eq!(
+            result.feedback.get(Square::F7),
+            Some(SquareFeedback::RookDestination)
+        );
+
+        sensor.push_script("e8. We8. e8. We8. e8. We8.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert_eq!(session.promotion_choice(), Some(Role::Knight));
+        assert_eq!(
+            result.feedback.get(Square::F7),
+            Some(SquareFeedback::Destination)
+        );
+        assert_eq!(
+            result.feedback.get(Square::C7),
+            Some(SquareFeedback::RookDestination)
+        );
+        assert!(session.pending_promotion().is_some(), "still waiting");
+
+        sensor.push_script("e8. We8.").unwrap();
+        run_script(&mut sensor, &mut session);
+        assert_eq!(session.promotion_choice(), Some(Role::Queen));
+    }
+
+    #[test]
+    fn external_prompt_ignores_the_pawn_gesture() {
+        let (mut sensor, mut session) = promotion_session();
+        session.set_promotion_policy(PromotionPolicy::ExternalPrompt);
+        sensor.push_script("e7 We8. e8. We8.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+
+        assert!(session.pending_promotion().is_some());
+        assert_eq!(session.promotion_choice(), None);
+        assert_eq!(
+            result.feedback.get(Square::E8),
+            Some(SquareFeedback::Destination)
+        );
+        assert_eq!(result.feedback.get(Square::C7), None);
+
+        let mv = session.choose_promotion(Role::Rook);
+        assert!(mv.is_some_and(|mv| mv.promotion() == Some(Role::Rook)));
+    }
+
+    #[test]
+    fn confirmed_move_waits_for_clock_press() {
+        let (mut sensor, mut session) = human_vs_human();
+        session.set_move_confirmation(MoveConfirmation::Always);
+
+        sensor.push_script("e2 We4.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+
+        assert_eq!(result.last_move, None);
+        assert_eq!(session.position().turn(), Color::White);
+        assert!(
+            session.confirm_move(Color::Black).is_none(),
+            "not their turn"
+        );
+
+        let mv = session.confirm_move(Color::White).expect("pending move");
+        assert_eq!(mv.to(), Square::E4);
+        assert_eq!(session.position().turn(), Color::Black);
+    }
+
+    #[test]
+    fn changing_the_board_withdraws_pending_move() {
+        let (mut sensor, mut session) = human_vs_human();
+        session.set_move_confirmation(MoveConfirmation::Always);
+
+        sensor.push_script("e2 We4. e4 We3.").unwrap();
+        run_script(&mut sensor, &mut session);
+
+        let mv = session.pending_move().expect("e2e3 pending");
+        assert_eq!(mv.to(), Square::E3);
+
+        sensor.push_script("e3 We2.").unwrap();
+        run_script(&mut sensor, &mut session);
+
+        assert!(session.pending_move().is_none());
+        assert!(session.confirm_move(Color::White).is_none());
+    }
+
+    #[test]
+    fn only_uncertain_moves_wait_for_confirmation() {
+        let (mut sensor, mut session) = human_vs_human();
+        session.set_move_confirmation(MoveConfirmation::WhenUncertain);
+
+        sensor.push_script("e2 We4.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert_eq!(result.last_move.map(|mv| mv.to()), Some(Square::E4));
+
+        // e6 could be a pawn on its way to e5.
+        sensor.push_script("e7 Be6.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert_eq!(result.last_move, None);
+        assert_eq!(
+            result.feedback.get(Square::E6),
+            Some(SquareFeedback::Destination)
+        );
+
+        let mv = session.confirm_move(Color::Black).expect("e7e6 pending");
+        assert_eq!(mv.to(), Square::E6);
+    }
+
+    #[test]
+    fn tap_confirms_uncertain_move() {
+        let (mut sensor, mut session) = human_vs_human();
+        session.set_move_confirmation(MoveConfirmation::WhenUncertain);
+
+        sensor.push_script("e2 We3. e3.").unwrap();
+        run_script(&mut sensor, &mut session);
+        assert!(session.pending_move().is_some(), "lifted, not withdrawn");
+
+        sensor.push_script("We3.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+
+        assert_eq!(result.last_move.map(|mv| mv.to()), Some(Square::E3));
+        assert_eq!(session.position().turn(), Color::Black);
+    }
+
+    #[test]
+    fn sliding_on_after_an_uncertain_match_plays_the_longer_move() {
+        let (mut sensor, mut session) = human_vs_human();
+        session.set_move_confirmation(MoveConfirmation::WhenUncertain);
+
+        sensor.push_script("e2 We3. e3. We4.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+
+        assert!(session.pending_move().is_none());
+        assert_eq!(result.last_move.map(|mv| mv.to()), Some(Square::E4));
+    }
+
+    #[test]
+    fn choose_promotion_rejects_king() {
+        let (mut sensor, mut session) = promotion_session();
+        session.set_promotion_policy(PromotionPolicy::GestureSelect);
+        sensor.push_script("e7 We8.").unwrap();
+        run_script(&mut sensor, &mut session);
+
+        assert!(session.choose_promotion(Role::King).is_none());
+        assert!(session.pending_promotion().is_some());
+    }
+
     #[test]
     fn delayed_player_returns_move_after_ticks() {
         let sensor = ScriptedSensor::new();
@@ -454,6 +1984,22 @@ mod tests {
         assert_eq!(result.feedback.status(), Some(StatusKind::Failure));
     }
 
+    #[test]
+    fn certificate_error_is_shown_instead_of_the_game() {
+        let sensor = ScriptedSensor::new();
+        let initial = sensor.read_positions();
+        let mut session = GameSession::new(
+            Box::new(HumanPlayer::new(initial)),
+            Box::new(CertificateErrorPlayer),
+        );
+
+        let result = session.tick(sensor.read_positions());
+        assert_eq!(
+            result.feedback,
+            crate::tls::CertificateError::Expired.feedback()
+        );
+    }
+
     #[test]
     fn no_capture_guidance_during_opponent_move_recovery() {
         use crate::feedback::SquareFeedback;
@@ -669,6 +2215,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn report_result_ends_game() {
+        let (_sensor, mut session, _tx) = human_vs_remote();
+
+        assert!(session.report_result(ExternalResult::Timeout {
+            loser: Color::Black
+        }));
+        assert_eq!(
+            session.game_state(),
+            GameStatus::Timeout {
+                loser: Color::Black
+            }
+        );
+    }
+
+    #[test]
+    fn report_result_accepts_remote_resignation() {
+        let (_sensor, mut session, _tx) = human_vs_remote();
+
+        assert!(session.report_result(ExternalResult::Resigned {
+            color: Color::Black
+        }));
+        assert_eq!(
+            session.game_state(),
+            GameStatus::Resigned {
+                color: Color::Black
+            }
+        );
+    }
+
+    #[test]
+    fn report_result_rejected_when_game_already_over() {
+        let (_sensor, mut session, _tx) = human_vs_remote();
+        assert!(session.resign(Color::White));
+
+        assert!(!session.report_result(ExternalResult::Draw));
+        assert_eq!(
+            session.game_state(),
+            GameStatus::Resigned {
+                color: Color::White
+            }
+        );
+    }
+
+    #[test]
+    fn tick_after_external_result_shows_result_feedback() {
+        let (mut sensor, mut session, _tx) = human_vs_remote();
+        assert!(session.report_result(ExternalResult::Draw));
+
+        sensor.push_script("e2 We4.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+
+        assert!(result.last_move.is_none());
+        assert_eq!(
+            result.feedback.get(Square::E1),
+            Some(crate::feedback::SquareFeedback::Stalemate)
+        );
+    }
+
     #[test]
     fn game_state_checkmate() {
         use crate::board_api::GameStatus;
@@ -707,6 +2312,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn tick_reports_the_result_of_the_mating_move() {
+        use crate::board_api::GameStatus;
+
+        let (mut sensor, mut session) = human_vs_human();
+        sensor.push_script("f2 Wf3. e7 Be6.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert_eq!(result.status, GameStatus::InProgress);
+
+        sensor.push_script("g2 Wg4. d8 Bh4.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+
+        assert!(result.last_move.is_some());
+        assert_eq!(
+            result.status,
+            GameStatus::Checkmate {
+                loser: Color::White
+            }
+        );
+    }
+
+    #[test]
+    fn session_without_a_king_aborts_instead_of_panicking() {
+        use crate::board_api::GameStatus;
+
+        // A king captured by a move nobody checked.
+        let mut position = Chess::default();
+        position.play_unchecked(Move::Normal {
+            role: Role::Queen,
+            from: Square::D1,
+            capture: Some(Role::King),
+            to: Square::E8,
+            promotion: None,
+        });
+        let sensors = board_sensors(position.board());
+        let mut session = GameSession::from_position(
+            position,
+            Box::new(HumanPlayer::new(sensors)),
+            Box::new(HumanPlayer::new(sensors)),
+        );
+
+        assert!(session.is_game_over());
+        assert!(session.undo_last_move().is_none());
+        let result = session.tick(sensors);
+        assert_eq!(result.status, GameStatus::Aborted);
+        assert_eq!(result.feedback.status(), Some(StatusKind::Failure));
+        assert_eq!(result.last_move, None);
+    }
+
     #[test]
     fn game_state_stalemate() {
         use crate::board_api::GameStatus;
@@ -751,4 +2405,388 @@ mod tests {
             "game_state should report Stalemate status"
         );
     }
+
+    // ── dead squares ────────────────────────────────────────────────
+
+    fn with_dead(squares: &[Square]) -> (ScriptedSensor, GameSession) {
+        let (mut sensor, mut session) = human_vs_human();
+        let dead: Bitboard = squares.iter().copied().collect();
+        session.set_dead_squares(dead);
+        // Dead sensors read empty.
+        let occupied = sensor.read_positions();
+        sensor
+            .load_bitboards(occupied.white & !dead, occupied.black & !dead)
+            .unwrap();
+        (sensor, session)
+    }
+
+    #[test]
+    fn move_out_of_dead_square_is_inferred() {
+        let (mut sensor, mut session) = with_dead(&[Square::G1]);
+
+        sensor.push_script("Wf3.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+
+        let mv = result.last_move.expect("Ng1-f3 inferred");
+        assert_eq!(mv.from(), Some(Square::G1));
+        assert_eq!(mv.to(), Square::F3);
+    }
+
+    #[test]
+    fn dead_square_does_not_show_as_recovery() {
+        let (sensor, mut session) = with_dead(&[Square::G1]);
+
+        let result = session.tick(sensor.read_positions());
+
+        assert!(result.feedback.is_empty());
+    }
+
+    #[test]
+    fn move_onto_dead_square_must_be_announced() {
+        use crate::feedback::SquareFeedback;
+
+        let (mut sensor, mut session) = with_dead(&[Square::E4]);
+
+        sensor.push_script("e2.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+
+        assert_eq!(result.last_move, None);
+        assert_eq!(
+            result.feedback.get(Square::E3),
+            Some(SquareFeedback::Destination)
+        );
+        assert_eq!(
+            result.feedback.get(Square::E4),
+            Some(SquareFeedback::Stalemate),
+            "reminder to announce"
+        );
+
+        let e2e4 = parse(&session, "e2e4");
+        assert!(session.announce_move(e2e4));
+        assert_eq!(session.position().turn(), Color::Black);
+        assert!(session.tick(sensor.read_positions()).feedback.is_empty());
+    }
+
+    #[test]
+    fn announce_rejects_moves_the_sensors_can_see() {
+        let (_, mut session) = with_dead(&[Square::E4]);
+
+        let d2d4 = parse(&session, "d2d4");
+
+        assert!(!session.announce_move(d2d4));
+        assert_eq!(session.position().turn(), Color::White);
+    }
+
+    fn parse(session: &GameSession, uci: &str) -> Move {
+        parse_at(session.position(), uci)
+    }
+
+    fn parse_at(position: &Chess, uci: &str) -> Move {
+        uci.parse::<shakmaty::uci::UciMove>()
+            .unwrap()
+            .to_move(position)
+            .unwrap()
+    }
+
+    #[test]
+    fn ambiguous_move_holds_until_announced() {
+        use crate::feedback::SquareFeedback;
+        use shakmaty::CastlingMode;
+        use shakmaty::fen::Fen;
+
+        let start: Chess = "4k3/8/8/8/8/8/4K3/R6R w - - 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        let dead = Bitboard::from(Square::A1) | Bitboard::from(Square::H1);
+        let board = start.board();
+        let mut reading = ByColor {
+            white: board.by_color(Color::White) & !dead,
+            black: board.by_color(Color::Black),
+        };
+        let mut session = GameSession::from_position(
+            start,
+            Box::new(HumanPlayer::new(reading)),
+            Box::new(HumanPlayer::new(reading)),
+        );
+        session.set_dead_squares(dead);
+
+        // A rook lands on d1: from a1 or h1?
+        reading.white.add(Square::D1);
+        let result = session.tick(reading);
+
+        assert_eq!(result.last_move, None);
+        assert_eq!(
+            result.feedback.get(Square::A1),
+            Some(SquareFeedback::Stalemate)
+        );
+        assert_eq!(
+            result.feedback.get(Square::H1),
+            Some(SquareFeedback::Stalemate)
+        );
+
+        assert!(session.announce_move(parse(&session, "h1d1")));
+        assert!(session.tick(reading).feedback.is_empty());
+        assert_eq!(session.position().turn(), Color::Black);
+    }
+
+    // ── adjudication ────────────────────────────────────────────────
+
+    #[test]
+    fn fivefold_repetition_ends_the_game_in_a_draw() {
+        let start = board_sensors(Chess::default().board());
+        let mut session = GameSession::new(
+            Box::new(HumanPlayer::new(start)),
+            Box::new(HumanPlayer::new(start)),
+        );
+
+        for _ in 0..4 {
+            for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                let mut after = session.position().clone();
+                after.play_unchecked(parse(&session, uci));
+                assert!(
+                    session
+                        .tick(board_sensors(after.board()))
+                        .last_move
+                        .is_some()
+                );
+            }
+        }
+
+        assert_eq!(session.game_state(), GameStatus::Draw);
+    }
+
+    #[test]
+    fn capturing_the_last_piece_ends_the_game_in_a_draw() {
+        let position: Chess = "7k/8/8/8/8/8/4r3/4K3 w - - 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(shakmaty::CastlingMode::Standard)
+            .unwrap();
+        let sensors = board_sensors(position.board());
+        let mut session = GameSession::from_position(
+            position,
+            Box::new(HumanPlayer::new(sensors)),
+            Box::new(HumanPlayer::new(sensors)),
+        );
+
+        let mut after = session.position().clone();
+        after.play_unchecked(parse(&session, "e1e2"));
+        assert!(
+            session
+                .tick(board_sensors(after.board()))
+                .last_move
+                .is_some()
+        );
+
+        assert_eq!(session.game_state(), GameStatus::Draw);
+    }
+
+    #[test]
+    fn lifting_both_kings_claims_a_threefold_repetition() {
+        let (mut sensor, mut session) = human_vs_human();
+        sensor
+            .push_script("g1 Wf3. g8 Bf6. f3 Wg1. f6 Bg8. g1 Wf3. g8 Bf6. f3 Wg1.")
+            .unwrap();
+        let events = script_events(&mut sensor, &mut session);
+        assert!(
+            !events
+                .iter()
+                .any(|e| matches!(e, TickEvent::DrawClaimable { .. }))
+        );
+
+        sensor.push_script("f6 Bg8.").unwrap();
+        let events = script_events(&mut sensor, &mut session);
+        assert_eq!(
+            events.last(),
+            Some(&TickEvent::DrawClaimable {
+                reason: DrawReason::ThreefoldRepetition
+            })
+        );
+        assert!(!session.claim_draw(Color::Black), "only the side to move");
+
+        sensor.push_script("e1 e8.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert_eq!(result.status, GameStatus::InProgress);
+        assert_eq!(
+            result.feedback.get(Square::E8),
+            Some(SquareFeedback::Destination)
+        );
+        assert!(session.recovery().is_none());
+
+        sensor.push_script("We1. Be8.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert_eq!(result.status, GameStatus::Draw);
+        assert_eq!(session.claimable_draw(), None);
+    }
+
+    #[test]
+    fn takeback_guides_the_pieces_back_before_detecting_moves() {
+        let (mut sensor, mut session) = human_vs_human();
+        sensor.push_script("e2 We4. d7 Bd5. e4 d5 Wd5.").unwrap();
+        run_script(&mut sensor, &mut session);
+        let exd5 = session.moves()[2];
+        assert_eq!(session.captured().white, [Role::Pawn]);
+
+        assert_eq!(session.undo_last_move(), Some(exd5));
+        assert_eq!(session.moves().len(), 2);
+        assert!(session.captured().white.is_empty());
+        assert_eq!(session.position().turn(), Color::White);
+
+        // The board still shows exd5: put the pawn back on e4 and the
+        // captured pawn on d5, without the old move being detected again.
+        let result = session.tick(sensor.read_positions());
+        assert_eq!(result.last_move, None);
+        assert_eq!(
+            result.feedback.get(Square::E4),
+            Some(SquareFeedback::Destination)
+        );
+        assert_eq!(
+            result.feedback.get(Square::D5),
+            Some(SquareFeedback::Capture)
+        );
+
+        sensor.push_script("d5 We4. Bd5.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert_eq!(result.last_move, None);
+        assert!(result.feedback.is_empty());
+
+        sensor.push_script("g1 Wf3.").unwrap();
+        let result = run_script(&mut sensor, &mut session);
+        assert_eq!(result.last_move.map(|mv| mv.to()), Some(Square::F3));
+        assert_eq!(session.moves().len(), 3);
+    }
+
+    #[test]
+    fn takeback_is_refused_without_moves_or_after_resigning() {
+        let (mut sensor, mut session) = human_vs_human();
+        assert_eq!(session.undo_last_move(), None);
+
+        sensor.push_script("e2 We4.").unwrap();
+        run_script(&mut sensor, &mut session);
+        session.resign(Color::Black);
+        assert_eq!(session.undo_last_move(), None);
+    }
+
+    #[test]
+    fn takeback_is_refused_when_a_player_cannot_follow_it() {
+        struct Mirrored;
+        impl Player for Mirrored {
+            fn poll_move(
+                &mut self,
+                _position: &Chess,
+                _sensors: ByColor<Bitboard>,
+            ) -> Option<Move> {
+                None
+            }
+            fn is_interactive(&self) -> bool {
+                false
+            }
+            fn allows_takeback(&self) -> bool {
+                false
+            }
+        }
+
+        let mut sensor = ScriptedSensor::new();
+        let initial = sensor.read_positions();
+        let mut session = GameSession::new(Box::new(HumanPlayer::new(initial)), Box::new(Mirrored));
+        sensor.push_script("e2 We4.").unwrap();
+        run_script(&mut sensor, &mut session);
+
+        assert_eq!(session.undo_last_move(), None);
+        assert_eq!(session.moves().len(), 1);
+    }
+
+    #[test]
+    fn takeback_limit_stops_at_older_moves() {
+        let mut sensor = ScriptedSensor::new();
+        let initial = sensor.read_positions();
+        let mut session = GameSession::builder().takeback_limit(1).build(
+            Box::new(HumanPlayer::new(initial)),
+            Box::new(HumanPlayer::new(initial)),
+        );
+        sensor.push_script("e2 We4. e7 Be5.").unwrap();
+        run_script(&mut sensor, &mut session);
+
+        assert!(session.undo_last_move().is_some());
+        assert!(session.undo_last_move().is_none(), "e4 is out of reach");
+        assert_eq!(session.moves().len(), 1);
+    }
+
+    #[test]
+    fn history_is_replayed_without_the_players() {
+        let mut position = Chess::default();
+        let moves: Vec<Move> = ["e2e4", "e7e5"]
+            .into_iter()
+            .map(|uci| {
+                let mv = uci.parse::<UciMove>().unwrap().to_move(&position).unwrap();
+                position.play_unchecked(mv);
+                mv
+            })
+            .collect();
+        let after = board_sensors(position.board());
+        let mut session = GameSession::builder().history(moves.clone()).build(
+            Box::new(HumanPlayer::new(after)),
+            Box::new(HumanPlayer::new(after)),
+        );
+
+        assert_eq!(session.moves(), moves.as_slice());
+        assert_eq!(session.position().turn(), Color::White);
+        assert!(session.tick(after).feedback.is_empty(), "board in sync");
+        assert_eq!(session.undo_last_move(), Some(moves[1]));
+    }
+
+    #[test]
+    fn builder_applies_every_option() {
+        let sensor = ScriptedSensor::new();
+        let initial = sensor.read_positions();
+        let session = GameSession::builder()
+            .fen("7k/4P3/8/8/8/8/8/K7 w - - 0 1")
+            .unwrap()
+            .promotion_policy(PromotionPolicy::GestureSelect)
+            .move_confirmation(MoveConfirmation::WhenUncertain)
+            .assist_level(AssistLevel::Minimal)
+            .dead_squares(Bitboard::from(Square::A1))
+            .build(
+                Box::new(HumanPlayer::new(initial)),
+                Box::new(HumanPlayer::new(initial)),
+            );
+
+        assert_eq!(session.position().board().occupied().count(), 3);
+        assert_eq!(session.promotion_policy, PromotionPolicy::GestureSelect);
+        assert_eq!(session.move_confirmation(), MoveConfirmation::WhenUncertain);
+        assert_eq!(session.assist, AssistLevel::Minimal);
+        assert_eq!(session.dead_squares, Bitboard::from(Square::A1));
+    }
+
+    #[test]
+    fn builder_rejects_bad_fen() {
+        assert!(matches!(
+            GameSession::builder().fen("not a fen"),
+            Err(StartPositionError::Fen(_))
+        ));
+        assert!(matches!(
+            GameSession::builder().fen("8/8/8/8/8/8/8/8 w - - 0 1"),
+            Err(StartPositionError::Position(_))
+        ));
+    }
+
+    #[test]
+    fn time_out_ends_the_game_once() {
+        let start = board_sensors(Chess::default().board());
+        let mut session = GameSession::new(
+            Box::new(HumanPlayer::new(start)),
+            Box::new(HumanPlayer::new(start)),
+        );
+
+        assert!(session.time_out(Color::White));
+        assert_eq!(
+            session.game_state(),
+            GameStatus::Timeout {
+                loser: Color::White
+            }
+        );
+        assert!(!session.time_out(Color::Black), "already over");
+    }
 }
@@ -0,0 +1,119 @@
+//! Player preferences that take effect while the board is running.
+//!
+//! A companion app changes one [`Setting`] at a time (BLE
+//! `ChangeSetting`). [`crate::app::BoardApp`] hands display settings to the
+//! display through [`crate::BoardDisplay::apply_settings`] and the assist
+//! level to the running game, so nothing waits for a restart.
+
+use crate::frame::{LedPalette, Rgb8};
+
+/// Colors used for feedback.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Theme {
+    /// The default [`LedPalette`].
+    #[default]
+    Classic,
+    /// Brighter colors with hues further apart, for well-lit rooms and
+    /// thick board surfaces.
+    HighContrast,
+}
+
+impl Theme {
+    pub const ALL: [Self; 2] = [Self::Classic, Self::HighContrast];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::HighContrast => "high-contrast",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|theme| theme.name() == name)
+    }
+
+    pub fn palette(self) -> LedPalette {
+        match self {
+            Theme::Classic => LedPalette::default(),
+            Theme::HighContrast => LedPalette {
+                off: Rgb8::new(0, 0, 0),
+                destination: Rgb8::new(0, 48, 8),
+                rook_destination: Rgb8::new(40, 40, 40),
+                capture: Rgb8::new(48, 32, 0),
+                origin: Rgb8::new(0, 8, 48),
+                check: Rgb8::new(48, 0, 0),
+                checker: Rgb8::new(48, 0, 0),
+                victory: Rgb8::new(0, 48, 8),
+                stalemate: Rgb8::new(48, 40, 0),
+                status_pending: Rgb8::new(0, 8, 48),
+                status_success: Rgb8::new(0, 48, 8),
+                status_failure: Rgb8::new(48, 0, 0),
+            },
+        }
+    }
+}
+
+/// How much a game guides the player.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AssistLevel {
+    /// Lifting a piece lights its legal moves.
+    #[default]
+    Full,
+    /// No move hints: a lifted piece lights nothing. Check, results and
+    /// guidance for a board that does not match the game are still shown.
+    Minimal,
+}
+
+impl AssistLevel {
+    pub const ALL: [Self; 2] = [Self::Full, Self::Minimal];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::Minimal => "minimal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|assist| assist.name() == name)
+    }
+}
+
+/// What the display needs to know to render feedback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplaySettings {
+    /// Overall brightness out of 255, applied on top of thermal throttling.
+    pub brightness: u8,
+    pub theme: Theme,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            brightness: u8::MAX,
+            theme: Theme::default(),
+        }
+    }
+}
+
+/// A single preference change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    Brightness(u8),
+    Theme(Theme),
+    Assist(AssistLevel),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color_vision::confusions;
+
+    #[test]
+    fn every_theme_stays_distinguishable() {
+        for theme in Theme::ALL {
+            let confused = confusions(&theme.palette());
+            assert!(confused.is_empty(), "{theme:?}: {confused:?}");
+        }
+    }
+}
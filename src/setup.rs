@@ -1,43 +1,294 @@
-use shakmaty::{Bitboard, ByColor, Chess, Color, Position};
+//! Guidance for setting up pieces on the board.
+//!
+//! [`setup_feedback`] and [`placement_feedback`] light every square still
+//! missing a piece and every piece that does not belong (see
+//! [`Placement`]). When the pieces on the board are known, a
+//! [`SetupGuide`] does better: it reuses them, moving each piece that is
+//! wrong but needed elsewhere once instead of clearing the board, and
+//! lights one step at a time.
+
+use std::fmt;
+
+use shakmaty::{Bitboard, Board, ByColor, Chess, Color, Piece, Position, Role, Square};
 
 use crate::feedback::{BoardFeedback, SquareFeedback};
 
 fn starting_positions() -> ByColor<Bitboard> {
-    let chess = Chess::default();
-    let board = chess.board();
+    board_positions(Chess::default().board())
+}
+
+fn board_positions(board: &Board) -> ByColor<Bitboard> {
     ByColor {
         white: board.by_color(Color::White),
         black: board.by_color(Color::Black),
     }
 }
 
-/// Compute setup feedback showing which squares still need pieces.
+/// How the pieces on the board differ from an expected arrangement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Placement {
+    /// Squares still waiting for a piece of each color.
+    pub missing: ByColor<Bitboard>,
+    /// Squares holding a piece that does not belong there, including one
+    /// of the wrong color.
+    pub extra: Bitboard,
+}
+
+impl Placement {
+    pub fn between(expected: &ByColor<Bitboard>, current: &ByColor<Bitboard>) -> Self {
+        Self {
+            missing: ByColor::new_with(|color| expected[color] & !current[color]),
+            extra: (current.white & !expected.white) | (current.black & !expected.black),
+        }
+    }
+
+    /// Whether the board matches the arrangement exactly.
+    pub fn is_complete(&self) -> bool {
+        self.missing.white.is_empty() && self.missing.black.is_empty() && self.extra.is_empty()
+    }
+
+    /// Missing pieces lit as `Destination` (white) or `Capture` (black),
+    /// pieces to take off as `Origin`; `None` once complete.
+    pub fn feedback(&self) -> Option<BoardFeedback> {
+        if self.is_complete() {
+            return None;
+        }
+        let mut fb = BoardFeedback::new();
+        for color in Color::ALL {
+            for sq in self.missing[color] {
+                fb.set(sq, missing_piece(color));
+            }
+        }
+        // A piece of the wrong color has to come off first.
+        for sq in self.extra {
+            fb.set(sq, SquareFeedback::Origin);
+        }
+        Some(fb)
+    }
+}
+
+/// Compute setup feedback showing which squares still need pieces and
+/// which pieces do not belong.
 ///
-/// Returns `None` when the board matches the starting position.
-/// Uses `Destination` for missing white pieces and `Capture` for missing black pieces.
+/// Returns `None` when the board matches the starting position exactly.
 pub fn setup_feedback(current: &ByColor<Bitboard>) -> Option<BoardFeedback> {
-    let expected = starting_positions();
-    let missing_white = expected.white & !current.white;
-    let missing_black = expected.black & !current.black;
+    placement_feedback(&starting_positions(), current)
+}
+
+/// Setup feedback for an arbitrary target arrangement (e.g. a puzzle).
+///
+/// Same colors as [`Placement::feedback`]; returns `None` once the board
+/// matches.
+pub fn placement_feedback(
+    expected: &ByColor<Bitboard>,
+    current: &ByColor<Bitboard>,
+) -> Option<BoardFeedback> {
+    Placement::between(expected, current).feedback()
+}
 
-    if missing_white.is_empty() && missing_black.is_empty() {
-        return None;
+/// How the board differs from the starting position.
+pub fn setup_placement(current: &ByColor<Bitboard>) -> Placement {
+    Placement::between(&starting_positions(), current)
+}
+
+/// One step of a [`SetupGuide`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupStep {
+    /// Move `piece` from one square to another.
+    Move {
+        piece: Piece,
+        from: Square,
+        to: Square,
+    },
+    /// Take `piece` off the board.
+    Remove { piece: Piece, square: Square },
+    /// Put `piece` (from off the board) on `square`.
+    Place { piece: Piece, square: Square },
+}
+
+impl SetupStep {
+    /// Whether `readings` show the step done. Sensors only see colors, so
+    /// a piece of the right color counts as the right piece.
+    fn is_done(&self, readings: &ByColor<Bitboard>) -> bool {
+        let occupied = readings.white | readings.black;
+        match *self {
+            SetupStep::Move { piece, from, to } => {
+                !occupied.contains(from) && readings[piece.color].contains(to)
+            }
+            SetupStep::Remove { square, .. } => !occupied.contains(square),
+            SetupStep::Place { piece, square } => readings[piece.color].contains(square),
+        }
     }
 
-    let mut fb = BoardFeedback::new();
-    for sq in missing_white {
-        fb.set(sq, SquareFeedback::Destination);
+    fn feedback(&self) -> BoardFeedback {
+        let mut fb = BoardFeedback::new();
+        match *self {
+            SetupStep::Move { from, to, .. } => {
+                fb.set(from, SquareFeedback::Origin);
+                fb.set(to, SquareFeedback::Destination);
+            }
+            SetupStep::Remove { square, .. } => fb.set(square, SquareFeedback::Origin),
+            SetupStep::Place { piece, square } => fb.set(square, missing_piece(piece.color)),
+        }
+        fb
     }
-    for sq in missing_black {
-        fb.set(sq, SquareFeedback::Capture);
+}
+
+impl fmt::Display for SetupStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupStep::Move { piece, from, to } => {
+                write!(f, "move {} from {from} to {to}", piece.char())
+            }
+            SetupStep::Remove { piece, square } => {
+                write!(f, "remove {} from {square}", piece.char())
+            }
+            SetupStep::Place { piece, square } => write!(f, "place {} on {square}", piece.char()),
+        }
+    }
+}
+
+/// Step-by-step setup of a target arrangement.
+///
+/// Built for a known starting arrangement with [`Self::between`], or with
+/// [`Self::new`], which plans from the standard starting position once the
+/// board reads as one (the usual state before loading a position) and
+/// otherwise falls back to [`placement_feedback`].
+#[derive(Debug, Clone)]
+pub struct SetupGuide {
+    target: Board,
+    steps: Vec<SetupStep>,
+    planned: bool,
+    next: usize,
+}
+
+impl SetupGuide {
+    /// Guide towards `target` from whatever is on the board.
+    pub fn new(target: &Board) -> Self {
+        Self {
+            target: target.clone(),
+            steps: Vec::new(),
+            planned: false,
+            next: 0,
+        }
+    }
+
+    /// Guide from the pieces of `from` to those of `to`.
+    pub fn between(from: &Board, to: &Board) -> Self {
+        Self {
+            target: to.clone(),
+            steps: plan(from, to),
+            planned: true,
+            next: 0,
+        }
+    }
+
+    /// The planned steps, empty until the arrangement on the board is known.
+    pub fn steps(&self) -> &[SetupStep] {
+        &self.steps
+    }
+
+    /// The step being shown, if any.
+    pub fn current(&self) -> Option<&SetupStep> {
+        self.steps.get(self.next)
+    }
+
+    /// Advance with the latest readings and return what to show, or `None`
+    /// once every target piece is in place.
+    pub fn update(&mut self, readings: &ByColor<Bitboard>) -> Option<BoardFeedback> {
+        let fallback = placement_feedback(&board_positions(&self.target), readings)?;
+        if !self.planned && *readings == starting_positions() {
+            self.steps = plan(&Board::new(), &self.target);
+            self.planned = true;
+            log::info!("Setup in {} steps", self.steps.len());
+        }
+        while let Some(step) = self.current() {
+            if !step.is_done(readings) {
+                break;
+            }
+            self.next += 1;
+            if let Some(step) = self.current() {
+                log::info!("Setup: {step}");
+            }
+        }
+        Some(self.current().map_or(fallback, SetupStep::feedback))
+    }
+}
+
+/// Plan the steps from `from` to `to`: pieces that are wrong but needed
+/// elsewhere move there (the nearest one first), the rest come off, and
+/// missing pieces are placed. Moves wait for their destination to be
+/// cleared; pieces that block each other in a cycle are broken up by
+/// lifting one off the board and putting it down last.
+fn plan(from: &Board, to: &Board) -> Vec<SetupStep> {
+    let mut removes = Vec::new();
+    let mut moves = Vec::new();
+    let mut places = Vec::new();
+    for color in Color::ALL {
+        for role in Role::ALL {
+            let piece = Piece { color, role };
+            let have = from.by_piece(piece);
+            let want = to.by_piece(piece);
+            let mut sources: Vec<Square> = (have & !want).into_iter().collect();
+            let mut targets: Vec<Square> = (want & !have).into_iter().collect();
+            while let Some((s, t)) = nearest_pair(&sources, &targets) {
+                moves.push((piece, sources.remove(s), targets.remove(t)));
+            }
+            removes.extend(sources.into_iter().map(|square| (piece, square)));
+            places.extend(targets.into_iter().map(|square| (piece, square)));
+        }
+    }
+
+    let mut occupied = from.occupied();
+    let mut steps: Vec<SetupStep> = removes
+        .into_iter()
+        .map(|(piece, square)| {
+            occupied.discard(square);
+            SetupStep::Remove { piece, square }
+        })
+        .collect();
+    loop {
+        if let Some(i) = moves.iter().position(|&(_, _, to)| !occupied.contains(to)) {
+            let (piece, from, to) = moves.remove(i);
+            occupied.discard(from);
+            occupied.add(to);
+            steps.push(SetupStep::Move { piece, from, to });
+        } else if let Some(i) = places.iter().position(|&(_, sq)| !occupied.contains(sq)) {
+            let (piece, square) = places.remove(i);
+            occupied.add(square);
+            steps.push(SetupStep::Place { piece, square });
+        } else if !moves.is_empty() {
+            let (piece, from, to) = moves.remove(0);
+            occupied.discard(from);
+            steps.push(SetupStep::Remove {
+                piece,
+                square: from,
+            });
+            places.push((piece, to));
+        } else {
+            break;
+        }
+    }
+    steps
+}
+
+/// Indices of the closest source and target square, if neither is empty.
+fn nearest_pair(sources: &[Square], targets: &[Square]) -> Option<(usize, usize)> {
+    (0..sources.len())
+        .flat_map(|s| (0..targets.len()).map(move |t| (s, t)))
+        .min_by_key(|&(s, t)| sources[s].distance(targets[t]))
+}
+
+fn missing_piece(color: Color) -> SquareFeedback {
+    match color {
+        Color::White => SquareFeedback::Destination,
+        Color::Black => SquareFeedback::Capture,
     }
-    Some(fb)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use shakmaty::Square;
 
     #[test]
     fn complete_starting_position_returns_none() {
@@ -98,13 +349,121 @@ mod tests {
     }
 
     #[test]
-    fn extra_pieces_on_board_are_ignored() {
+    fn extra_pieces_must_come_off() {
         let expected = starting_positions();
         let positions = ByColor {
             white: expected.white | Bitboard::from(Square::E4),
             black: expected.black,
         };
 
-        assert!(setup_feedback(&positions).is_none());
+        let fb = setup_feedback(&positions).expect("should have feedback");
+        assert_eq!(fb.get(Square::E4), Some(SquareFeedback::Origin));
+        assert_eq!(fb.squares().count(), 1);
+        assert_eq!(
+            setup_placement(&positions).extra,
+            Bitboard::from(Square::E4)
+        );
+    }
+
+    #[test]
+    fn piece_of_the_wrong_color_is_extra_and_missing() {
+        let expected = starting_positions();
+        let positions = ByColor {
+            white: expected.white.without(Square::D1),
+            black: expected.black | Bitboard::from(Square::D1),
+        };
+
+        let placement = setup_placement(&positions);
+        assert_eq!(placement.missing.white, Bitboard::from(Square::D1));
+        assert_eq!(placement.extra, Bitboard::from(Square::D1));
+        assert!(!placement.is_complete());
+        assert_eq!(
+            placement.feedback().and_then(|fb| fb.get(Square::D1)),
+            Some(SquareFeedback::Origin)
+        );
+        assert!(setup_placement(&expected).is_complete());
+    }
+
+    fn board(fen: &str) -> Board {
+        fen.parse().unwrap()
+    }
+
+    #[test]
+    fn guide_moves_pieces_already_on_the_board() {
+        // 1. e4 e5 2. Nf3 Nc6 3. Bc4
+        let target = board("r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R");
+        let guide = SetupGuide::between(&Board::new(), &target);
+
+        assert_eq!(guide.steps().len(), 5);
+        assert!(
+            guide
+                .steps()
+                .iter()
+                .all(|step| matches!(step, SetupStep::Move { .. }))
+        );
+    }
+
+    #[test]
+    fn guide_removes_captured_pieces() {
+        let target = board("rnbqkbnr/pppp1ppp/8/8/8/8/PPPP1PPP/RNBQKBNR");
+        let guide = SetupGuide::between(&Board::new(), &target);
+
+        assert_eq!(
+            guide.steps(),
+            [
+                SetupStep::Remove {
+                    piece: Color::White.pawn(),
+                    square: Square::E2
+                },
+                SetupStep::Remove {
+                    piece: Color::Black.pawn(),
+                    square: Square::E7
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn guide_breaks_cycles_by_lifting_a_piece() {
+        let target = board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RBNQKBNR");
+        let guide = SetupGuide::between(&Board::new(), &target);
+
+        assert_eq!(
+            guide.steps(),
+            [
+                SetupStep::Remove {
+                    piece: Color::White.knight(),
+                    square: Square::B1
+                },
+                SetupStep::Move {
+                    piece: Color::White.bishop(),
+                    from: Square::C1,
+                    to: Square::B1
+                },
+                SetupStep::Place {
+                    piece: Color::White.knight(),
+                    square: Square::C1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn guide_plans_from_the_starting_position_and_follows_the_board() {
+        let target = board("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR");
+        let mut guide = SetupGuide::new(&target);
+
+        let fb = guide.update(&ByColor::default()).unwrap();
+        assert_eq!(
+            fb.squares().count(),
+            32,
+            "unknown pieces: all missing shown"
+        );
+
+        let fb = guide.update(&starting_positions()).unwrap();
+        assert_eq!(fb.get(Square::E2), Some(SquareFeedback::Origin));
+        assert_eq!(fb.get(Square::E4), Some(SquareFeedback::Destination));
+
+        assert_eq!(guide.update(&board_positions(&target)), None);
     }
 }
@@ -0,0 +1,255 @@
+//! Usage statistics since the board was switched on.
+//!
+//! [`crate::app::BoardApp`] records every finished or cancelled game and
+//! every failed sensor read. Club operators read the totals through
+//! [`crate::app::BoardApp::stats`]; [`SessionStats::report`] renders them
+//! as the plain-text `stats` summary that is also logged after each game.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use shakmaty::{CastlingMode, Color, Move};
+
+use crate::board_api::GameStatus;
+
+/// Plies that identify an opening.
+pub const OPENING_PLIES: usize = 4;
+
+/// Openings listed in [`SessionStats::report`].
+const REPORTED_OPENINGS: usize = 3;
+
+/// Finished games by result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResultTally {
+    pub white_wins: u32,
+    pub black_wins: u32,
+    pub draws: u32,
+    pub aborted: u32,
+}
+
+impl ResultTally {
+    fn add(&mut self, status: &GameStatus) {
+        let winner = match *status {
+            GameStatus::Checkmate { loser } | GameStatus::Timeout { loser } => loser.other(),
+            GameStatus::Resigned { color } => color.other(),
+            GameStatus::Stalemate | GameStatus::Draw => {
+                self.draws += 1;
+                return;
+            }
+            GameStatus::Aborted => {
+                self.aborted += 1;
+                return;
+            }
+            GameStatus::Idle | GameStatus::AwaitingPieces | GameStatus::InProgress => return,
+        };
+        match winner {
+            Color::White => self.white_wins += 1,
+            Color::Black => self.black_wins += 1,
+        }
+    }
+}
+
+/// Aggregated statistics for one power-on session.
+#[derive(Debug, Clone, Default)]
+pub struct SessionStats {
+    games: u32,
+    results: ResultTally,
+    total_plies: u64,
+    total_duration: Duration,
+    /// Opening (first [`OPENING_PLIES`] moves in UCI) and how often it was played.
+    openings: Vec<(String, u32)>,
+    sensor_errors: u32,
+}
+
+impl SessionStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a game that ended with `status` after `moves`.
+    pub fn record_game(&mut self, status: &GameStatus, moves: &[Move], duration: Duration) {
+        self.games += 1;
+        self.results.add(status);
+        self.total_plies += moves.len() as u64;
+        self.total_duration += duration;
+        if moves.len() >= OPENING_PLIES {
+            let opening = moves[..OPENING_PLIES]
+                .iter()
+                .map(|mv| mv.to_uci(CastlingMode::Standard).to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            match self.openings.iter_mut().find(|(o, _)| *o == opening) {
+                Some((_, count)) => *count += 1,
+                None => self.openings.push((opening, 1)),
+            }
+        }
+    }
+
+    pub fn record_sensor_error(&mut self) {
+        self.sensor_errors += 1;
+    }
+
+    pub fn games_played(&self) -> u32 {
+        self.games
+    }
+
+    pub fn results(&self) -> ResultTally {
+        self.results
+    }
+
+    /// Mean number of plies per game.
+    pub fn average_plies(&self) -> Option<u64> {
+        (self.games > 0).then(|| self.total_plies / u64::from(self.games))
+    }
+
+    /// Mean wall-clock duration per game.
+    pub fn average_duration(&self) -> Option<Duration> {
+        (self.games > 0).then(|| self.total_duration / self.games)
+    }
+
+    /// The `n` most played openings, most common first.
+    pub fn top_openings(&self, n: usize) -> Vec<(&str, u32)> {
+        let mut openings: Vec<_> = self
+            .openings
+            .iter()
+            .map(|(opening, count)| (opening.as_str(), *count))
+            .collect();
+        openings.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        openings.truncate(n);
+        openings
+    }
+
+    pub fn sensor_errors(&self) -> u32 {
+        self.sensor_errors
+    }
+
+    /// Plain-text summary, one statistic per line.
+    pub fn report(&self) -> String {
+        let r = self.results;
+        let mut out = format!(
+            "games: {}\nresults: {} white, {} black, {} drawn, {} aborted\n",
+            self.games, r.white_wins, r.black_wins, r.draws, r.aborted
+        );
+        if let (Some(plies), Some(duration)) = (self.average_plies(), self.average_duration()) {
+            let _ = writeln!(
+                out,
+                "average length: {plies} plies, {}s",
+                duration.as_secs()
+            );
+        }
+        for (opening, count) in self.top_openings(REPORTED_OPENINGS) {
+            let _ = writeln!(out, "opening: {opening} ({count})");
+        }
+        let _ = write!(out, "sensor errors: {}", self.sensor_errors);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::uci::UciMove;
+    use shakmaty::{Chess, Position};
+
+    fn moves(ucis: &[&str]) -> Vec<Move> {
+        let mut pos = Chess::default();
+        ucis.iter()
+            .map(|uci| {
+                let mv = uci.parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+                pos.play_unchecked(mv);
+                mv
+            })
+            .collect()
+    }
+
+    #[test]
+    fn results_are_tallied_by_winner() {
+        let mut stats = SessionStats::new();
+        let none = Duration::ZERO;
+        stats.record_game(
+            &GameStatus::Checkmate {
+                loser: Color::Black,
+            },
+            &[],
+            none,
+        );
+        stats.record_game(
+            &GameStatus::Resigned {
+                color: Color::White,
+            },
+            &[],
+            none,
+        );
+        stats.record_game(&GameStatus::Stalemate, &[], none);
+        stats.record_game(&GameStatus::Aborted, &[], none);
+
+        assert_eq!(stats.games_played(), 4);
+        assert_eq!(
+            stats.results(),
+            ResultTally {
+                white_wins: 1,
+                black_wins: 1,
+                draws: 1,
+                aborted: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn averages_cover_all_games() {
+        let mut stats = SessionStats::new();
+        assert_eq!(stats.average_plies(), None);
+
+        stats.record_game(
+            &GameStatus::Draw,
+            &moves(&["e2e4"]),
+            Duration::from_secs(60),
+        );
+        stats.record_game(
+            &GameStatus::Draw,
+            &moves(&["e2e4", "e7e5", "g1f3"]),
+            Duration::from_secs(180),
+        );
+
+        assert_eq!(stats.average_plies(), Some(2));
+        assert_eq!(stats.average_duration(), Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn openings_are_ranked_by_frequency() {
+        let mut stats = SessionStats::new();
+        let italian = moves(&["e2e4", "e7e5", "g1f3", "b8c6", "f1c4"]);
+        let queens = moves(&["d2d4", "d7d5", "c2c4", "e7e6"]);
+        stats.record_game(&GameStatus::Draw, &queens, Duration::ZERO);
+        stats.record_game(&GameStatus::Draw, &italian, Duration::ZERO);
+        stats.record_game(&GameStatus::Draw, &italian, Duration::ZERO);
+        stats.record_game(&GameStatus::Draw, &moves(&["e2e4"]), Duration::ZERO);
+
+        assert_eq!(
+            stats.top_openings(5),
+            [("e2e4 e7e5 g1f3 b8c6", 2), ("d2d4 d7d5 c2c4 e7e6", 1)]
+        );
+    }
+
+    #[test]
+    fn report_lists_every_statistic() {
+        let mut stats = SessionStats::new();
+        stats.record_game(
+            &GameStatus::Timeout {
+                loser: Color::White,
+            },
+            &moves(&["e2e4", "e7e5", "g1f3", "b8c6"]),
+            Duration::from_secs(90),
+        );
+        stats.record_sensor_error();
+
+        assert_eq!(
+            stats.report(),
+            "games: 1\n\
+             results: 0 white, 1 black, 0 drawn, 0 aborted\n\
+             average length: 4 plies, 90s\n\
+             opening: e2e4 e7e5 g1f3 b8c6 (1)\n\
+             sensor errors: 1"
+        );
+    }
+}
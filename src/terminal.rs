@@ -0,0 +1,1421 @@
+//! Line-based board simulator for exploring the engine on the desktop.
+//!
+//! [`Terminal`] runs a human-vs-human [`GameSession`] on simulated sensor
+//! readings. Each input line is either a batch of square toggles in
+//! BoardScript style (`e2 We4`: lift e2, put a white piece on e4) fed to
+//! the session as one reading, or a command:
+//!
+//! | command      | effect                                            |
+//! |--------------|---------------------------------------------------|
+//! | `t <squares>`| toggle squares (same as a bare batch)             |
+//! | `play <uci>` | make a legal move on the board                    |
+//! | `ai on <level> [white\|black]` | hand a side (default black) to the built-in [`ComputerPlayer`] |
+//! | `ai off`     | play both sides on the board again                |
+//! | `clock <min>+<inc>[b\|d]` | start a [`GameClock`], e.g. `clock 5+3`; a trailing `b` or `d` makes the increment a Bronstein or simple delay; `clock off` removes it |
+//! | `wait <secs>` | let simulated time pass on the clock              |
+//! | `setup`      | clear the board and set the position up again     |
+//! | `undo`       | take back the last move, then put the pieces back on the lit squares |
+//! | `played`     | list the moves played, in SAN                     |
+//! | `moves`      | list the legal moves, in SAN                      |
+//! | `hint`       | light up the move the strongest [`ComputerPlayer`] would play |
+//! | `script <boardscript>` | run several readings at once (see below) |
+//! | `heatmap`    | print how often pieces crossed each square (see [`HeatMap`]) |
+//! | `fen`        | print the position as FEN                         |
+//! | `board`      | print the board and the feedback it shows         |
+//! | `open <file>`| step through a flight recording (see below)       |
+//! | `log <module\|all> <level>` | print log records of a [`LogModule`] at `level` and above to stderr |
+//! | `back [n]`, `forward [n]` | step through the last readings (see below) |
+//! | `help`       | list the commands                                 |
+//!
+//! The computer's replies are applied to the game at once but not to the
+//! simulated board: like on the real board, the feedback shows the move and
+//! the user makes it with `t`, which runs the whole guidance
+//! pipeline on the desktop.
+//!
+//! The clock runs on simulated time that only `wait` advances, so time
+//! controls can be stepped through at any speed: moves switch it, and a
+//! side that runs out of time loses as on the board.
+//!
+//! `script` takes BoardScript: toggles as for `t`, with `.` ending each
+//! reading and `@200ms` or `@2s` letting that much simulated time pass, so
+//! `script e2 We4. @3s e7 Be5.` plays two moves three seconds apart. It
+//! stops at the first bad token; the readings before it stay.
+//!
+//! After `setup` the board is empty and, as while the board waits for the
+//! starting position, the feedback lights the squares still missing a
+//! piece and the pieces that do not belong; the game goes on once the
+//! board matches.
+//!
+//! `open` loads a flight recorder file copied off a board and replays it
+//! (see [`Playback`]). Until `close`, the board shows the recording
+//! instead of the game: `next`/`prev` scrub through the readings, by
+//! count or to the next reading a move was detected on, and `seek` jumps
+//! to a reading. Each is shown with a timeline, the time since the board
+//! booted, the feedback the board would have shown and the moves
+//! detected.
+//!
+//! Every reading fed to the game is kept, the last [`HISTORY_LEN`] of
+//! them, with the position, status and feedback it led to. `back` and
+//! `forward` step through them to see how a surprising detection came
+//! about; any other command returns to the present first.
+//!
+//! The `terminal` binary draws the board full screen (`just terminal`) or
+//! reads lines from stdin (`just terminal-plain`).
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use shakmaty::fen::Fen;
+use shakmaty::san::SanPlus;
+use shakmaty::uci::UciMove;
+use shakmaty::{
+    Bitboard, ByColor, CastlingMode, Chess, Color, EnPassantMode, Move, Position, Square,
+};
+
+use crate::board_api::GameStatus;
+use crate::chess_clock::{GameClock, TimeControl};
+use crate::feedback::BoardFeedback;
+use crate::feedback::SquareFeedback;
+use crate::heatmap::HeatMap;
+use crate::log_filter::{self, LogModule};
+use crate::material;
+use crate::playback::Playback;
+use crate::player::{ComputerPlayer, HumanPlayer, MAX_LEVEL, MatcherKind, Player};
+use crate::session::GameSession;
+use crate::setup::placement_feedback;
+
+pub const HELP: &str = "\
+squares   toggle pieces, e.g. `e2 We4` (W/B prefix places on an empty square)
+t SQUARES same as typing the squares
+play MOVE make a legal move in UCI, e.g. `play g1f3`
+ai on LEVEL [white|black]
+          let the computer (level 0-3) play a side, black by default
+ai off    play both sides on the board
+clock MIN+INC[b|d]
+          start a clock, e.g. `clock 5+3`; `5+3b` or `5+3d` for a
+          Bronstein or simple delay; `clock off` removes it
+wait SECS let time pass on the clock
+setup     clear the board and place the pieces again
+undo      take back the last move; put the pieces back as lit
+played    list the moves played
+moves     list the legal moves
+hint      light up a good move for the side to move
+script BOARDSCRIPT
+          run readings in one go: `.` ends a reading, `@2s` or
+          `@500ms` waits, e.g. `script e2 We4. @3s e7 Be5.`
+heatmap   show how often pieces crossed each square, 1 (rarely) to 9
+fen       print the position as FEN
+board     print the board and its feedback
+open FILE step through a flight recording copied off a board
+next [N|move], prev [N|move]
+          scrub the recording by N readings or to the next detected move
+seek N    show reading N of the recording
+close     back to the game
+back [N], forward [N]
+          step through the last readings and what they led to
+log MODULE|all LEVEL
+          log sensor, engine, net or display records at LEVEL (off,
+          error, warn, info, debug or trace) to stderr
+help      show this text";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TerminalError {
+    #[error("unknown command or square: '{0}' (try `help`)")]
+    Unknown(String),
+    #[error("{0}: say W or B to place a piece on an empty square")]
+    MissingColor(Square),
+    #[error("illegal move: '{0}'")]
+    IllegalMove(String),
+    #[error("no move can be taken back")]
+    NothingToUndo,
+    #[error("usage: ai on LEVEL [white|black] | ai off")]
+    AiUsage,
+    #[error("usage: clock MINUTES+INCREMENT[b|d] | clock off")]
+    ClockUsage,
+    #[error("usage: wait SECONDS")]
+    WaitUsage,
+    #[error("cannot open {path}: {reason}")]
+    Open { path: String, reason: String },
+    #[error("no recording open (try `open FILE`)")]
+    NoRecording,
+    #[error("a recording is open; `close` it to play")]
+    RecordingOpen,
+    #[error("usage: next [N|move] | prev [N|move] | seek N")]
+    SeekUsage,
+    #[error("usage: log sensor|engine|net|display|all off|error|warn|info|debug|trace")]
+    LogUsage,
+    #[error("usage: back [N] | forward [N]")]
+    HistoryUsage,
+    #[error("no readings yet")]
+    NoHistory,
+    #[error("no move to suggest")]
+    NoHint,
+    #[error("invalid delay: '{0}' (e.g. @500ms or @2s)")]
+    InvalidDelay(String),
+}
+
+/// Readings kept for `back` and `forward`.
+pub const HISTORY_LEN: usize = 200;
+
+/// The board after one reading.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Readings the board had taken when this one came, counting from 1.
+    pub tick: u64,
+    pub readings: ByColor<Bitboard>,
+    pub position: Chess,
+    pub status: GameStatus,
+    pub feedback: BoardFeedback,
+    /// Moves detected on this reading, the computer's reply included.
+    pub moves: Vec<Move>,
+    /// The board was waiting for its pieces after `setup`.
+    pub setting_up: bool,
+}
+
+/// The board as shown: readings, the position they are checked against
+/// and the squares lit.
+#[derive(Debug, Clone, Copy)]
+pub struct BoardView<'a> {
+    pub position: &'a Chess,
+    pub readings: ByColor<Bitboard>,
+    pub feedback: &'a BoardFeedback,
+}
+
+impl BoardView<'_> {
+    /// The piece on `square` where the readings agree with the position,
+    /// `.` where both say empty and `?` where they disagree.
+    pub fn square_char(&self, square: Square) -> char {
+        let piece = self.position.board().piece_at(square);
+        let read = [Color::White, Color::Black]
+            .into_iter()
+            .find(|&c| self.readings[c].contains(square));
+        match (piece, read) {
+            (Some(p), Some(c)) if p.color == c => p.char(),
+            (None, None) => '.',
+            _ => '?',
+        }
+    }
+}
+
+/// A simulated board with a game in progress.
+pub struct Terminal {
+    session: GameSession,
+    readings: ByColor<Bitboard>,
+    feedback: BoardFeedback,
+    /// Side played by the computer, and its level.
+    ai: Option<(Color, u8)>,
+    clock: Option<GameClock>,
+    /// Simulated time, advanced by `wait`.
+    now: Duration,
+    /// The pieces are being set up after `setup`; no moves are detected.
+    setting_up: bool,
+    /// Recording shown instead of the game after `open`.
+    playback: Option<Playback>,
+    /// The last [`HISTORY_LEN`] readings, oldest first.
+    history: VecDeque<Snapshot>,
+    /// Readings taken so far.
+    ticks: u64,
+    /// Index into `history` shown by `back`/`forward` instead of the
+    /// present.
+    rewound: Option<usize>,
+}
+
+impl Default for Terminal {
+    fn default() -> Self {
+        Self::new(Chess::default())
+    }
+}
+
+impl std::fmt::Debug for Terminal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Terminal")
+            .field("fen", &self.fen())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Terminal {
+    /// A game from `start`, with its pieces set up.
+    pub fn new(start: Chess) -> Self {
+        let (session, readings) = session_at(start, Vec::new(), None);
+        Self {
+            session,
+            readings,
+            feedback: BoardFeedback::new(),
+            ai: None,
+            clock: None,
+            now: Duration::ZERO,
+            setting_up: false,
+            playback: None,
+            history: VecDeque::new(),
+            ticks: 0,
+            rewound: None,
+        }
+    }
+
+    /// Run one input line and return what to print.
+    pub fn execute(&mut self, line: &str) -> Result<String, TerminalError> {
+        let mut words = line.split_whitespace();
+        let Some(first) = words.next() else {
+            return Ok(String::new());
+        };
+        match first {
+            "help" => Ok(HELP.to_string()),
+            "open" => {
+                let path = line.trim_start()[first.len()..].trim();
+                self.open(path)?;
+                Ok(self.render())
+            }
+            "close" => {
+                self.playback.take().ok_or(TerminalError::NoRecording)?;
+                Ok(self.render())
+            }
+            "next" | "prev" | "seek" => {
+                let playback = self.playback.as_mut().ok_or(TerminalError::NoRecording)?;
+                let direction = if first == "prev" { -1 } else { 1 };
+                match (first, words.next(), words.next()) {
+                    (_, _, Some(_)) | ("seek", None, _) => {
+                        return Err(TerminalError::SeekUsage);
+                    }
+                    ("seek", Some(n), None) => {
+                        let n: usize = n.parse().map_err(|_| TerminalError::SeekUsage)?;
+                        playback.seek(n.saturating_sub(1));
+                    }
+                    (_, Some("move"), None) => {
+                        playback.step_to_move(direction);
+                    }
+                    (_, count, None) => {
+                        let count: isize = count
+                            .map_or(Ok(1), str::parse)
+                            .map_err(|_| TerminalError::SeekUsage)?;
+                        playback.step(direction * count);
+                    }
+                }
+                Ok(self.render())
+            }
+            "board" => Ok(self.render()),
+            "log" => {
+                let module = match words.next() {
+                    Some("all") => None,
+                    Some(name) => Some(LogModule::from_name(name).ok_or(TerminalError::LogUsage)?),
+                    None => return Err(TerminalError::LogUsage),
+                };
+                let level = match (words.next(), words.next()) {
+                    (Some(level), None) => level.parse().map_err(|_| TerminalError::LogUsage)?,
+                    _ => return Err(TerminalError::LogUsage),
+                };
+                log_filter::set_level(module, level);
+                let name = module.map_or("all", LogModule::name);
+                Ok(format!(
+                    "log {name} {}",
+                    level.as_str().to_ascii_lowercase()
+                ))
+            }
+            _ if self.playback.is_some() => Err(TerminalError::RecordingOpen),
+            "back" | "forward" => {
+                let steps: isize = match (words.next(), words.next()) {
+                    (None, None) => 1,
+                    (Some(n), None) => n.parse().map_err(|_| TerminalError::HistoryUsage)?,
+                    _ => return Err(TerminalError::HistoryUsage),
+                };
+                let direction = if first == "back" { -1 } else { 1 };
+                self.step_history(direction * steps)?;
+                Ok(self.render())
+            }
+            _ if self.rewound.take().is_some() => self.execute(line),
+            "fen" => Ok(self.fen()),
+            "played" => Ok(self.move_list()),
+            "moves" => Ok(self.legal_moves()),
+            "hint" => {
+                let mv = self.hint()?;
+                let san = SanPlus::from_move(self.position().clone(), mv);
+                Ok(format!("hint {san}\n{}", self.render()))
+            }
+            "script" => {
+                self.script(&line.trim_start()[first.len()..])?;
+                Ok(self.render())
+            }
+            "heatmap" => Ok(HeatMap::from_moves(self.moves()).render()),
+            "setup" => {
+                self.setup();
+                Ok(self.render())
+            }
+            "undo" => {
+                self.undo()?;
+                Ok(self.render())
+            }
+            "play" => {
+                let uci = words.next().unwrap_or_default();
+                self.play(uci)?;
+                Ok(self.render())
+            }
+            "ai" => {
+                let ai = match (words.next(), words.next(), words.next()) {
+                    (Some("off"), None, None) => None,
+                    (Some("on"), Some(level), side) => {
+                        let level = level.parse().map_err(|_| TerminalError::AiUsage)?;
+                        let color = match side {
+                            None | Some("black") => Color::Black,
+                            Some("white") => Color::White,
+                            Some(_) => return Err(TerminalError::AiUsage),
+                        };
+                        Some((color, level))
+                    }
+                    _ => return Err(TerminalError::AiUsage),
+                };
+                self.set_ai(ai);
+                Ok(self.render())
+            }
+            "clock" => {
+                let control = match (words.next(), words.next()) {
+                    (Some("off"), None) => None,
+                    (Some(control), None) => {
+                        Some(TimeControl::parse(control).ok_or(TerminalError::ClockUsage)?)
+                    }
+                    _ => return Err(TerminalError::ClockUsage),
+                };
+                self.set_clock(control);
+                Ok(self.render())
+            }
+            "wait" => {
+                let secs = match (words.next(), words.next()) {
+                    (Some(secs), None) => secs.parse().ok(),
+                    _ => None,
+                };
+                let duration = secs
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                    .ok_or(TerminalError::WaitUsage)?;
+                self.wait(duration);
+                Ok(self.render())
+            }
+            "t" => {
+                self.toggle(&line.trim_start()[1..])?;
+                Ok(self.render())
+            }
+            _ => {
+                self.toggle(line)?;
+                Ok(self.render())
+            }
+        }
+    }
+
+    /// Load the flight recording at `path` and show its first reading.
+    pub fn open(&mut self, path: &str) -> Result<(), TerminalError> {
+        let open_error = |reason: String| TerminalError::Open {
+            path: path.to_string(),
+            reason,
+        };
+        let bytes = std::fs::read(path).map_err(|e| open_error(e.to_string()))?;
+        let playback =
+            Playback::load(&bytes, MatcherKind::Strict).map_err(|e| open_error(e.to_string()))?;
+        self.playback = Some(playback);
+        Ok(())
+    }
+
+    /// The readings kept for `back`/`forward`, oldest first.
+    pub fn history(&self) -> &VecDeque<Snapshot> {
+        &self.history
+    }
+
+    /// The past reading shown instead of the present, if any.
+    pub fn rewound(&self) -> Option<&Snapshot> {
+        self.rewound.and_then(|index| self.history.get(index))
+    }
+
+    /// Move `steps` readings through the history, forward if positive.
+    /// Going past the latest reading returns to the present.
+    pub fn step_history(&mut self, steps: isize) -> Result<(), TerminalError> {
+        let latest = self
+            .history
+            .len()
+            .checked_sub(1)
+            .ok_or(TerminalError::NoHistory)?;
+        let from = self.rewound.unwrap_or(latest) as isize;
+        let to = from.saturating_add(steps).max(0) as usize;
+        self.rewound = (to < latest).then_some(to);
+        Ok(())
+    }
+
+    /// The recording opened with [`Self::open`], if any.
+    pub fn playback(&self) -> Option<&Playback> {
+        self.playback.as_ref()
+    }
+
+    /// The position of the game.
+    pub fn position(&self) -> &Chess {
+        self.session.position()
+    }
+
+    /// What the board shows.
+    pub fn feedback(&self) -> &BoardFeedback {
+        &self.feedback
+    }
+
+    /// The board [`Self::render`] draws: the reading under the cursor of
+    /// an open recording, the reading stepped back to, or the game.
+    pub fn view(&self) -> BoardView<'_> {
+        match (&self.playback, self.rewound()) {
+            (Some(playback), _) => {
+                let frame = playback.current();
+                BoardView {
+                    position: &frame.position,
+                    readings: frame.readings,
+                    feedback: &frame.feedback,
+                }
+            }
+            (None, Some(snapshot)) => BoardView {
+                position: &snapshot.position,
+                readings: snapshot.readings,
+                feedback: &snapshot.feedback,
+            },
+            (None, None) => BoardView {
+                position: self.position(),
+                readings: self.readings,
+                feedback: &self.feedback,
+            },
+        }
+    }
+
+    /// Whether the game is on or how it ended.
+    pub fn status(&self) -> GameStatus {
+        self.session.game_state()
+    }
+
+    /// Simulated time since the terminal started.
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    /// The moves played from the start, less those taken back.
+    pub fn moves(&self) -> &[Move] {
+        self.session.moves()
+    }
+
+    /// The position as FEN.
+    pub fn fen(&self) -> String {
+        Fen::from_position(self.position(), EnPassantMode::Legal).to_string()
+    }
+
+    /// The moves played in SAN, numbered.
+    pub fn move_list(&self) -> String {
+        let mut pos = self.session.start().clone();
+        let mut out = String::new();
+        for mv in self.moves() {
+            if pos.turn() == Color::White {
+                let _ = write!(out, "{}. ", pos.fullmoves());
+            } else if out.is_empty() {
+                let _ = write!(out, "{}... ", pos.fullmoves());
+            }
+            let san = SanPlus::from_move_and_play_unchecked(&mut pos, *mv);
+            let _ = write!(out, "{san} ");
+        }
+        out.truncate(out.trim_end().len());
+        out
+    }
+
+    /// The legal moves in SAN.
+    pub fn legal_moves(&self) -> String {
+        let position = self.position();
+        let moves: Vec<String> = position
+            .legal_moves()
+            .into_iter()
+            .map(|mv| SanPlus::from_move(position.clone(), mv).to_string())
+            .collect();
+        moves.join(" ")
+    }
+
+    /// Light up the move the strongest [`ComputerPlayer`] would play, as
+    /// the board guides a move: its origin and destination. The next
+    /// reading replaces it.
+    pub fn hint(&mut self) -> Result<Move, TerminalError> {
+        if self.setting_up || self.session.is_game_over() {
+            return Err(TerminalError::NoHint);
+        }
+        let mut computer = ComputerPlayer::new(MAX_LEVEL, self.ticks as u32);
+        let mv = computer
+            .poll_move(self.position(), self.readings)
+            .ok_or(TerminalError::NoHint)?;
+        let mut feedback = BoardFeedback::new();
+        if let Some(from) = mv.from() {
+            feedback.set(from, SquareFeedback::Origin);
+        }
+        let to = if mv.is_capture() {
+            SquareFeedback::Capture
+        } else {
+            SquareFeedback::Destination
+        };
+        feedback.set(mv.to(), to);
+        self.feedback = feedback;
+        Ok(mv)
+    }
+
+    /// Run `script` (see the module docs), each reading as from `t`.
+    pub fn script(&mut self, script: &str) -> Result<(), TerminalError> {
+        let mut batch = Vec::new();
+        for token in script.split_whitespace() {
+            if let Some(delay) = token.strip_prefix('@') {
+                self.flush_batch(&mut batch)?;
+                let delay = parse_delay(delay)
+                    .ok_or_else(|| TerminalError::InvalidDelay(token.to_string()))?;
+                self.wait(delay);
+                continue;
+            }
+            let (squares, ends) = match token.strip_suffix('.') {
+                Some(squares) => (squares, true),
+                None => (token, false),
+            };
+            if !squares.is_empty() {
+                batch.push(squares);
+            }
+            if ends {
+                self.flush_batch(&mut batch)?;
+            }
+        }
+        self.flush_batch(&mut batch)
+    }
+
+    /// Toggle `batch` as one reading, if it holds anything.
+    fn flush_batch(&mut self, batch: &mut Vec<&str>) -> Result<(), TerminalError> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let line = batch.join(" ");
+        batch.clear();
+        self.toggle(&line)
+    }
+
+    /// Let the computer play `color` at `level` (see [`ComputerPlayer`]),
+    /// or with `None` play both sides on the board again. The pieces are
+    /// reset to the game position.
+    pub fn set_ai(&mut self, ai: Option<(Color, u8)>) {
+        self.ai = ai.map(|(color, level)| (color, level.min(MAX_LEVEL)));
+        self.restart();
+    }
+
+    /// Start a clock with `control` for the side to move, or with `None`
+    /// play untimed.
+    pub fn set_clock(&mut self, control: Option<TimeControl>) {
+        self.clock = control.map(|control| {
+            let mut clock = GameClock::new(control);
+            if !self.session.is_game_over() {
+                clock.start(self.position().turn(), self.now);
+            }
+            clock
+        });
+    }
+
+    /// The game clock, if one is set.
+    pub fn clock(&self) -> Option<&GameClock> {
+        self.clock.as_ref()
+    }
+
+    /// Let `duration` of simulated time pass. A side whose time runs out
+    /// loses.
+    pub fn wait(&mut self, duration: Duration) {
+        self.now += duration;
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+        if let Some(loser) = clock.flagged(self.now) {
+            clock.stop(self.now);
+            self.session.time_out(loser);
+        }
+    }
+
+    /// Clear the board and wait for the pieces of the game position to be
+    /// put back, showing which squares still need one and which pieces
+    /// have to come off.
+    pub fn setup(&mut self) {
+        self.setting_up = true;
+        self.tick(ByColor::default());
+    }
+
+    /// Whether the board is waiting for its pieces after [`Self::setup`].
+    pub fn is_setting_up(&self) -> bool {
+        self.setting_up
+    }
+
+    /// Take back the last move, as the board does (see
+    /// [`GameSession::undo_last_move`]): the pieces stay where they are
+    /// and the squares to put them back on light up. Against the
+    /// computer, its reply is taken back too.
+    pub fn undo(&mut self) -> Result<(), TerminalError> {
+        self.session
+            .undo_last_move()
+            .ok_or(TerminalError::NothingToUndo)?;
+        while let Some((color, _)) = self.ai
+            && self.position().turn() == color
+            && self.session.undo_last_move().is_some()
+        {}
+        if let Some(clock) = &mut self.clock
+            && clock.running().is_some()
+        {
+            clock.start(self.session.position().turn(), self.now);
+        }
+        self.tick(self.readings);
+        Ok(())
+    }
+
+    /// A fresh session with the same moves and its pieces set up. The
+    /// computer moves at once if it is its turn.
+    fn restart(&mut self) {
+        (self.session, self.readings) =
+            session_at(self.session.start().clone(), self.moves().to_vec(), self.ai);
+        self.feedback = BoardFeedback::new();
+        if let Some(clock) = &mut self.clock {
+            clock.start(self.session.position().turn(), self.now);
+        }
+        self.tick(self.readings);
+    }
+
+    /// Make `uci` on the board: lift the moving piece (and any captured
+    /// one), then put it down.
+    pub fn play(&mut self, uci: &str) -> Result<(), TerminalError> {
+        let illegal = || TerminalError::IllegalMove(uci.to_string());
+        let mv = uci
+            .parse::<UciMove>()
+            .ok()
+            .and_then(|u| u.to_move(self.position()).ok())
+            .ok_or_else(illegal)?;
+        let mut after = self.position().clone();
+        after.play_unchecked(mv);
+        let mut lifted = self.readings;
+        lifted.white &= after.board().by_color(Color::White);
+        lifted.black &= after.board().by_color(Color::Black);
+        self.tick(lifted);
+        self.tick(board_positions(&after));
+        Ok(())
+    }
+
+    /// Toggle one square as its own reading. A piece put on an empty
+    /// square is of the colour that has one lifted (the computer's, while
+    /// making its reply), or the side to move's if both or neither do.
+    pub fn toggle_square(&mut self, square: Square) {
+        let mut readings = self.readings;
+        let board = self.position().board();
+        let lifted = |color: Color| readings[color].count() < board.by_color(color).count();
+        let color = if readings.white.contains(square) {
+            Color::White
+        } else if readings.black.contains(square) {
+            Color::Black
+        } else {
+            match (lifted(Color::White), lifted(Color::Black)) {
+                (true, false) => Color::White,
+                (false, true) => Color::Black,
+                _ => self.position().turn(),
+            }
+        };
+        readings[color].toggle(square);
+        self.tick(readings);
+    }
+
+    /// Toggle the squares in `line` as one batch.
+    fn toggle(&mut self, line: &str) -> Result<(), TerminalError> {
+        let mut readings = self.readings;
+        for word in line.split_whitespace() {
+            let unknown = || TerminalError::Unknown(word.to_string());
+            let (color, square) = match word.as_bytes().first() {
+                Some(b'W') => (Some(Color::White), &word[1..]),
+                Some(b'B') => (Some(Color::Black), &word[1..]),
+                _ => (None, word),
+            };
+            let square: Square = square.parse().map_err(|_| unknown())?;
+            let color = if readings.white.contains(square) {
+                Color::White
+            } else if readings.black.contains(square) {
+                Color::Black
+            } else {
+                color.ok_or(TerminalError::MissingColor(square))?
+            };
+            readings[color].toggle(square);
+        }
+        self.tick(readings);
+        Ok(())
+    }
+
+    fn tick(&mut self, readings: ByColor<Bitboard>) {
+        let played = self.moves().len();
+        self.rewound = None;
+        self.tick_game(readings);
+        self.ticks += 1;
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(Snapshot {
+            tick: self.ticks,
+            readings,
+            position: self.position().clone(),
+            status: self.session.game_state(),
+            feedback: self.feedback.clone(),
+            moves: self.moves().get(played..).unwrap_or_default().to_vec(),
+            setting_up: self.setting_up,
+        });
+    }
+
+    fn tick_game(&mut self, readings: ByColor<Bitboard>) {
+        self.readings = readings;
+        if self.setting_up {
+            let expected = board_positions(self.position());
+            if let Some(feedback) = placement_feedback(&expected, &readings) {
+                self.feedback = feedback;
+                return;
+            }
+            // Set up: the session sees the finished board as its next reading.
+            self.setting_up = false;
+        }
+        self.tick_session();
+        if self
+            .ai
+            .is_some_and(|(color, _)| color == self.position().turn())
+            && !self.session.is_game_over()
+        {
+            // The computer answers within the same reading.
+            self.tick_session();
+        }
+    }
+
+    fn tick_session(&mut self) {
+        let result = self.session.tick(self.readings);
+        if result.last_move.is_some()
+            && let Some(clock) = &mut self.clock
+        {
+            clock.switch(self.now);
+            if self.session.is_game_over() {
+                clock.stop(self.now);
+            }
+        }
+        self.feedback = result.feedback;
+    }
+
+    /// The clocks if set, then the board from White's side: pieces of the
+    /// game position where the readings agree, `?` where they do not, then
+    /// the lit squares. With a recording open, its timeline and the
+    /// reading under the cursor instead.
+    pub fn render(&self) -> String {
+        match (&self.playback, self.rewound()) {
+            (Some(playback), _) => render_playback(playback),
+            (None, Some(snapshot)) => self.render_snapshot(snapshot),
+            (None, None) => self.render_with_cursor(None),
+        }
+    }
+
+    /// A past reading: how far back it is, what it led to, then the
+    /// board as it was.
+    fn render_snapshot(&self, snapshot: &Snapshot) -> String {
+        let back = self.ticks - snapshot.tick;
+        let mut out = format!(
+            "reading {}  ({back} back)  {:?}\n",
+            snapshot.tick, snapshot.status
+        );
+        if snapshot.setting_up {
+            out.push_str("setting up\n");
+        } else if !snapshot.moves.is_empty() {
+            let moves: Vec<String> = snapshot
+                .moves
+                .iter()
+                .map(|mv| mv.to_uci(CastlingMode::Standard).to_string())
+                .collect();
+            let _ = writeln!(out, "detected {}", moves.join(" "));
+        }
+        let view = BoardView {
+            position: &snapshot.position,
+            readings: snapshot.readings,
+            feedback: &snapshot.feedback,
+        };
+        render_board(&mut out, view, None);
+        out
+    }
+
+    /// [`Self::render`] with `cursor` in brackets, for the keyboard-driven
+    /// mode of the `terminal` binary.
+    pub fn render_with_cursor(&self, cursor: Option<Square>) -> String {
+        let mut out = String::new();
+        if let Some(clock) = &self.clock {
+            for color in [Color::White, Color::Black] {
+                let remaining = clock.remaining(color, self.now);
+                let marker = if clock.running() == Some(color) {
+                    " *"
+                } else if remaining.is_zero() {
+                    " (flag)"
+                } else {
+                    ""
+                };
+                let name = color.fold_wb("White", "Black");
+                let _ = write!(out, "{name} {}{marker}  ", format_clock(remaining));
+            }
+            out.truncate(out.trim_end().len());
+            out.push('\n');
+        }
+        if let Some(line) = captured_line(self.position(), self.session.start(), self.moves()) {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        let view = BoardView {
+            position: self.position(),
+            readings: self.readings,
+            feedback: &self.feedback,
+        };
+        render_board(&mut out, view, cursor);
+        out
+    }
+}
+
+/// Width of the playback timeline, in characters.
+const TIMELINE_WIDTH: usize = 40;
+
+/// The timeline, the reading under the cursor and what was detected on
+/// it, then the board as [`Terminal::render`] draws it.
+fn render_playback(playback: &Playback) -> String {
+    let frame = playback.current();
+    let (index, count) = (playback.cursor(), playback.frames().len());
+    let filled = (index + 1) * TIMELINE_WIDTH / count;
+    let mut out = format!(
+        "reading {}/{count}  {}  [{}{}]\n",
+        index + 1,
+        format_elapsed(frame.time),
+        "#".repeat(filled),
+        ".".repeat(TIMELINE_WIDTH - filled),
+    );
+    if !frame.in_game {
+        out.push_str("waiting for the starting position\n");
+    } else if !frame.moves.is_empty() {
+        let moves: Vec<String> = frame
+            .moves
+            .iter()
+            .map(|mv| mv.to_uci(CastlingMode::Standard).to_string())
+            .collect();
+        let _ = writeln!(out, "detected {}", moves.join(" "));
+    }
+    let view = BoardView {
+        position: &frame.position,
+        readings: frame.readings,
+        feedback: &frame.feedback,
+    };
+    render_board(&mut out, view, None);
+    out
+}
+
+/// The board from White's side, then the lit squares.
+fn render_board(out: &mut String, view: BoardView<'_>, cursor: Option<Square>) {
+    for rank in (0..8).rev() {
+        let _ = write!(out, "{} ", rank + 1);
+        let mut after_cursor = false;
+        for file in 0..8 {
+            let square = Square::new(rank * 8 + file);
+            let separator = match (Some(square) == cursor, after_cursor) {
+                (true, _) => '[',
+                (false, true) => ']',
+                (false, false) => ' ',
+            };
+            after_cursor = Some(square) == cursor;
+            let _ = write!(out, "{separator}{}", view.square_char(square));
+        }
+        if after_cursor {
+            out.push(']');
+        }
+        out.push('\n');
+    }
+    out.push_str("   a b c d e f g h");
+    for (square, feedback) in view.feedback.squares() {
+        let _ = write!(out, "\n{square}: {feedback:?}");
+    }
+}
+
+/// The pieces each side took and who is ahead, e.g.
+/// `captured  White: p n  Black: P  (White +3)`; `None` before the first
+/// capture.
+fn captured_line(position: &Chess, start: &Chess, moves: &[Move]) -> Option<String> {
+    let captured = material::captured(start.turn(), moves);
+    if captured.iter().all(Vec::is_empty) {
+        return None;
+    }
+    let pieces = |by: Color| -> String {
+        let names: Vec<String> = captured[by]
+            .iter()
+            .map(|&role| role.of(by.other()).char().to_string())
+            .collect();
+        names.join(" ")
+    };
+    let balance = match material::material_balance(position.board()) {
+        0 => "even".to_string(),
+        n if n > 0 => format!("White +{n}"),
+        n => format!("Black +{}", -n),
+    };
+    Some(format!(
+        "captured  White: {}  Black: {}  ({balance})",
+        pieces(Color::White),
+        pieces(Color::Black)
+    ))
+}
+/// `h:mm:ss.mmm` since boot.
+fn format_elapsed(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!(
+        "{}:{:02}:{:02}.{:03}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        time.subsec_millis()
+    )
+}
+
+/// `200ms` or `2s`, as in BoardScript.
+fn parse_delay(delay: &str) -> Option<Duration> {
+    if let Some(ms) = delay.strip_suffix("ms") {
+        ms.parse().ok().map(Duration::from_millis)
+    } else {
+        delay
+            .strip_suffix('s')?
+            .parse()
+            .ok()
+            .map(Duration::from_secs)
+    }
+}
+
+/// `m:ss`, with tenths under a minute.
+pub fn format_clock(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    if secs < 60 {
+        format!("0:{secs:02}.{}", remaining.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+fn board_positions(position: &Chess) -> ByColor<Bitboard> {
+    ByColor {
+        white: position.board().by_color(Color::White),
+        black: position.board().by_color(Color::Black),
+    }
+}
+
+/// A fresh session from `start` after `moves` and the readings of its
+/// pieces, with `ai` handing one side to the computer.
+fn session_at(
+    start: Chess,
+    moves: Vec<Move>,
+    ai: Option<(Color, u8)>,
+) -> (GameSession, ByColor<Bitboard>) {
+    let mut position = start.clone();
+    for &mv in &moves {
+        position.play_unchecked(mv);
+    }
+    let readings = board_positions(&position);
+    let player = |color: Color| -> Box<dyn Player> {
+        match ai {
+            Some((ai_color, level)) if ai_color == color => {
+                Box::new(ComputerPlayer::new(level, position.fullmoves().get()))
+            }
+            _ => Box::new(HumanPlayer::new(readings)),
+        }
+    };
+    let session = GameSession::builder()
+        .position(start)
+        .history(moves)
+        .build(player(Color::White), player(Color::Black));
+    (session, readings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feedback::SquareFeedback;
+
+    #[test]
+    fn moves_made_by_toggling_squares_are_listed_in_san() {
+        let mut terminal = Terminal::default();
+        terminal.execute("e2 We4").unwrap();
+        terminal.execute("play e7e5").unwrap();
+        terminal.execute("play g1f3").unwrap();
+
+        assert_eq!(terminal.execute("played").unwrap(), "1. e4 e5 2. Nf3");
+        let heat = terminal.execute("heatmap").unwrap();
+        assert!(heat.starts_with("8  . . . . . . . .\n"), "{heat}");
+        assert!(heat.contains("\n4  . . . . 9 . . .\n"), "{heat}");
+    }
+
+    #[test]
+    fn captures_and_balance_are_shown_above_the_board() {
+        let mut terminal = Terminal::default();
+        let board = terminal.execute("play e2e4").unwrap();
+        assert!(!board.contains("captured"), "{board}");
+
+        terminal.execute("play d7d5").unwrap();
+        terminal.execute("play e4d5").unwrap();
+        let board = terminal.execute("play d8d5").unwrap();
+        assert!(
+            board.starts_with("captured  White: p  Black: P  (even)\n"),
+            "{board}"
+        );
+        terminal.execute("play b1c3").unwrap();
+        terminal.execute("play d5e5").unwrap();
+        terminal.execute("play f1e2").unwrap();
+        let board = terminal.execute("play e5e2").unwrap();
+        assert!(
+            board.starts_with("captured  White: p  Black: P B  (Black +3)\n"),
+            "{board}"
+        );
+    }
+
+    #[test]
+    fn log_levels_are_set_per_module() {
+        let mut terminal = Terminal::default();
+
+        assert_eq!(
+            terminal.execute("log engine info").unwrap(),
+            "log engine info"
+        );
+        assert_eq!(
+            log_filter::LEVELS.level(LogModule::Engine),
+            log::LevelFilter::Info
+        );
+        for line in ["log engine", "log board debug", "log net loud"] {
+            assert_eq!(
+                terminal.execute(line),
+                Err(TerminalError::LogUsage),
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    fn undo_takes_back_the_last_move() {
+        let mut terminal = Terminal::default();
+        terminal.execute("play e2e4").unwrap();
+        terminal.execute("play e7e5").unwrap();
+
+        terminal.execute("undo").unwrap();
+        assert_eq!(
+            terminal.execute("fen").unwrap(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        );
+        assert_eq!(
+            terminal.feedback().get(Square::E7),
+            Some(SquareFeedback::Destination),
+            "the pawn goes back"
+        );
+        terminal.execute("e5 Be7").unwrap();
+        assert!(terminal.feedback().is_empty());
+        terminal.execute("play c7c5").unwrap();
+        assert_eq!(terminal.move_list(), "1. e4 c5");
+
+        terminal.execute("undo").unwrap();
+        terminal.execute("undo").unwrap();
+        assert_eq!(terminal.execute("undo"), Err(TerminalError::NothingToUndo));
+        assert!(terminal.moves().is_empty());
+    }
+
+    #[test]
+    fn legal_moves_are_listed_in_san() {
+        let start: Chess = "4k3/8/8/8/8/8/4P3/4K2R w K - 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        let mut terminal = Terminal::new(start);
+        let moves = terminal.execute("moves").unwrap();
+
+        for san in ["e3", "e4", "Kf1", "O-O", "Rh8+"] {
+            assert!(moves.split(' ').any(|mv| mv == san), "{san} in {moves}");
+        }
+        assert_eq!(moves.split(' ').count(), 16, "{moves}");
+    }
+
+    #[test]
+    fn hint_lights_a_good_move_until_the_next_reading() {
+        let start: Chess = "4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        let mut terminal = Terminal::new(start);
+
+        let board = terminal.execute("hint").unwrap();
+        assert!(board.starts_with("hint Rxd5\n"), "{board}");
+        assert_eq!(
+            terminal.feedback().squares().collect::<Vec<_>>(),
+            [
+                (Square::D2, SquareFeedback::Origin),
+                (Square::D5, SquareFeedback::Capture)
+            ]
+        );
+
+        terminal.execute("d2").unwrap();
+        assert_eq!(
+            terminal.feedback().get(Square::D5),
+            Some(SquareFeedback::Capture)
+        );
+        terminal.execute("setup").unwrap();
+        assert_eq!(terminal.execute("hint"), Err(TerminalError::NoHint));
+    }
+
+    #[test]
+    fn scripts_run_several_readings_with_delays() {
+        let mut terminal = Terminal::default();
+        terminal.execute("clock 1+0").unwrap();
+        terminal
+            .execute("script e2 We4. @3s e7. Be5 @500ms g1 Wf3")
+            .unwrap();
+
+        assert_eq!(terminal.move_list(), "1. e4 e5 2. Nf3");
+        let clock = terminal.clock().unwrap();
+        assert_eq!(
+            clock.remaining(Color::White, terminal.now),
+            Duration::from_millis(59_500)
+        );
+        assert_eq!(
+            clock.remaining(Color::Black, terminal.now),
+            Duration::from_secs(57)
+        );
+        assert_eq!(
+            terminal.execute("script @soon"),
+            Err(TerminalError::InvalidDelay("@soon".to_string()))
+        );
+        assert!(matches!(
+            terminal.execute("script ~e4:3"),
+            Err(TerminalError::Unknown(_))
+        ));
+    }
+
+    #[test]
+    fn computer_replies_are_shown_for_the_user_to_make() {
+        let mut terminal = Terminal::default();
+        terminal.execute("ai on 1").unwrap();
+        terminal.execute("play e2e4").unwrap();
+
+        assert_eq!(terminal.moves().len(), 2, "computer replied");
+        let reply = terminal.moves()[1];
+        let (from, to) = (reply.from().unwrap(), reply.to());
+        assert!(terminal.feedback.get(from).is_some() && terminal.feedback.get(to).is_some());
+
+        terminal.execute(&format!("t {from} B{to}")).unwrap();
+        assert_eq!(terminal.readings, board_positions(terminal.position()));
+
+        terminal.execute("undo").unwrap();
+        assert!(
+            terminal.moves().is_empty(),
+            "reply taken back with the move"
+        );
+    }
+
+    #[test]
+    fn clock_switches_on_moves_and_flags_after_waiting() {
+        let mut terminal = Terminal::default();
+        terminal.execute("clock 1+2").unwrap();
+        terminal.execute("wait 10").unwrap();
+        let board = terminal.execute("play e2e4").unwrap();
+        assert!(board.starts_with("White 0:52.0  Black 1:00 *\n"), "{board}");
+
+        terminal.execute("wait 59.5").unwrap();
+        assert!(!terminal.session.is_game_over());
+        let board = terminal.execute("wait 1").unwrap();
+        assert!(
+            board.starts_with("White 0:52.0  Black 0:00.0 (flag)\n"),
+            "{board}"
+        );
+        assert!(terminal.session.is_game_over());
+
+        assert_eq!(terminal.execute("clock 5"), Err(TerminalError::ClockUsage));
+        assert_eq!(
+            terminal.execute("clock 5+2x"),
+            Err(TerminalError::ClockUsage)
+        );
+        assert_eq!(terminal.execute("wait -1"), Err(TerminalError::WaitUsage));
+    }
+
+    #[test]
+    fn clock_delay_holds_the_countdown() {
+        let mut terminal = Terminal::default();
+        terminal.execute("clock 1+5d").unwrap();
+        let board = terminal.execute("wait 4").unwrap();
+        assert!(board.starts_with("White 1:00 *  Black 1:00\n"), "{board}");
+        let board = terminal.execute("wait 10").unwrap();
+        assert!(board.starts_with("White 0:51.0 *  Black 1:00\n"), "{board}");
+    }
+
+    #[test]
+    fn single_toggles_put_pieces_down_in_the_colour_lifted() {
+        let mut terminal = Terminal::default();
+        terminal.execute("ai on 1").unwrap();
+        for square in [Square::E2, Square::E4] {
+            terminal.toggle_square(square);
+        }
+        let reply = terminal.moves()[1];
+        terminal.toggle_square(reply.from().unwrap());
+        terminal.toggle_square(reply.to());
+
+        assert_eq!(terminal.readings, board_positions(terminal.position()));
+    }
+
+    #[test]
+    fn setup_lights_missing_and_extra_pieces_until_the_board_matches() {
+        let start: Chess = "4k3/8/8/8/8/8/8/4K3 w - - 0 1"
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(shakmaty::CastlingMode::Standard)
+            .unwrap();
+        let mut terminal = Terminal::new(start);
+        terminal.execute("setup").unwrap();
+        assert!(terminal.is_setting_up());
+        assert_eq!(
+            terminal.feedback().get(Square::E1),
+            Some(SquareFeedback::Destination)
+        );
+        assert_eq!(
+            terminal.feedback().get(Square::E8),
+            Some(SquareFeedback::Capture)
+        );
+
+        terminal.execute("We1 Be8 Wd4").unwrap();
+        assert!(terminal.is_setting_up());
+        assert_eq!(
+            terminal.feedback().squares().collect::<Vec<_>>(),
+            [(Square::D4, SquareFeedback::Origin)]
+        );
+
+        terminal.execute("d4").unwrap();
+        assert!(!terminal.is_setting_up());
+        assert!(terminal.feedback().is_empty());
+        terminal.execute("play e1e2").unwrap();
+        assert_eq!(terminal.moves().len(), 1);
+    }
+
+    #[test]
+    fn recording_is_scrubbed_with_the_detected_moves() {
+        use crate::flight_recorder::RecordedFrame;
+        use crate::tick_log::Tick;
+
+        let mut terminal = Terminal::default();
+        let mut positions = board_positions(&Chess::default());
+        let mut frames = Vec::new();
+        for (read, square) in [Square::A3, Square::E2, Square::E4].into_iter().enumerate() {
+            if square != Square::A3 {
+                positions.white.toggle(square);
+            }
+            let time = Duration::from_millis(61_000 + read as u64 * 50);
+            let read = read as u64;
+            frames.push(RecordedFrame {
+                time,
+                tick: Tick { read, positions },
+            });
+        }
+        terminal.playback = Some(Playback::replay(&frames, MatcherKind::Strict).unwrap());
+
+        let board = terminal.execute("next move").unwrap();
+        assert!(
+            board.starts_with(&format!(
+                "reading 3/3  0:01:01.100  [{}]\ndetected e2e4\n",
+                "#".repeat(TIMELINE_WIDTH)
+            )),
+            "{board}"
+        );
+        let board = terminal.execute("prev").unwrap();
+        assert!(board.contains("e4: Destination"), "{board}");
+        assert_eq!(
+            terminal.execute("play e2e4"),
+            Err(TerminalError::RecordingOpen)
+        );
+        assert_eq!(terminal.execute("seek"), Err(TerminalError::SeekUsage));
+        terminal.execute("seek 1").unwrap();
+        assert_eq!(terminal.playback().unwrap().cursor(), 0);
+
+        terminal.execute("close").unwrap();
+        assert_eq!(terminal.execute("next"), Err(TerminalError::NoRecording));
+        assert!(matches!(
+            terminal.execute("open /nonexistent/flight.rec"),
+            Err(TerminalError::Open { .. })
+        ));
+    }
+
+    #[test]
+    fn back_and_forward_step_through_past_readings() {
+        let mut terminal = Terminal::default();
+        assert_eq!(terminal.execute("back"), Err(TerminalError::NoHistory));
+        terminal.execute("e2").unwrap();
+        terminal.execute("We4").unwrap();
+        terminal.execute("e7").unwrap();
+
+        let board = terminal.execute("back").unwrap();
+        assert!(
+            board.starts_with("reading 2  (1 back)  InProgress\ndetected e2e4\n"),
+            "{board}"
+        );
+        let board = terminal.execute("back 5").unwrap();
+        assert!(board.starts_with("reading 1  (2 back)"), "{board}");
+        assert!(board.contains("e2: Origin"), "{board}");
+        assert_eq!(terminal.rewound().unwrap().moves, []);
+
+        terminal.execute("forward 2").unwrap();
+        assert!(terminal.rewound().is_none(), "back in the present");
+        terminal.execute("back").unwrap();
+        terminal.execute("Be5").unwrap();
+        assert!(terminal.rewound().is_none());
+        assert_eq!(terminal.execute("played").unwrap(), "1. e4 e5");
+        assert_eq!(terminal.execute("back x"), Err(TerminalError::HistoryUsage));
+    }
+
+    #[test]
+    fn history_keeps_the_last_readings() {
+        let mut terminal = Terminal::default();
+        // Lifting a2 and putting it back, over and over.
+        for toggle in ["a2", "Wa2"].iter().cycle().take(HISTORY_LEN) {
+            terminal.execute(toggle).unwrap();
+        }
+
+        assert_eq!(terminal.history().len(), HISTORY_LEN);
+        assert_eq!(terminal.history().front().unwrap().tick, 1);
+        terminal.execute("a2").unwrap();
+        assert_eq!(terminal.history().len(), HISTORY_LEN);
+        assert_eq!(terminal.history().front().unwrap().tick, 2);
+    }
+
+    #[test]
+    fn cursor_is_drawn_in_brackets() {
+        let terminal = Terminal::default();
+        let board = terminal.render_with_cursor(Some(Square::H2));
+
+        assert!(board.contains("\n2  P P P P P P P[P]\n"), "{board}");
+        let board = terminal.render_with_cursor(Some(Square::D1));
+        assert!(board.contains("\n1  R N B[Q]K B N R\n"), "{board}");
+    }
+
+    #[test]
+    fn the_view_follows_what_is_shown() {
+        let mut terminal = Terminal::default();
+        terminal.execute("e2").unwrap();
+        let view = terminal.view();
+        assert_eq!(view.square_char(Square::E2), '?', "lifted, not yet moved");
+        assert_eq!(view.square_char(Square::E1), 'K');
+        assert_eq!(view.square_char(Square::E4), '.');
+        assert_eq!(
+            view.feedback.get(Square::E4),
+            Some(SquareFeedback::Destination)
+        );
+
+        terminal.execute("We4").unwrap();
+        assert_eq!(terminal.view().square_char(Square::E4), 'P');
+        terminal.execute("back").unwrap();
+        assert_eq!(terminal.view().square_char(Square::E2), '?');
+        assert_eq!(terminal.view().square_char(Square::E4), '.');
+        assert_eq!(terminal.status(), GameStatus::InProgress);
+    }
+
+    #[test]
+    fn bad_input_is_reported() {
+        let mut terminal = Terminal::default();
+
+        assert_eq!(
+            terminal.execute("play e2e5"),
+            Err(TerminalError::IllegalMove("e2e5".to_string()))
+        );
+        assert_eq!(
+            terminal.execute("e4"),
+            Err(TerminalError::MissingColor(Square::E4))
+        );
+        assert!(matches!(
+            terminal.execute("xyzzy"),
+            Err(TerminalError::Unknown(_))
+        ));
+    }
+}
@@ -0,0 +1,214 @@
+//! Fixtures for testing integrations against the crate, behind the
+//! `testkit` feature.
+//!
+//! Companion apps and other code built on the crate can drive a
+//! [`Simulation`] (a [`crate::app::BoardApp`] on a [`ScriptedSensor`], a
+//! [`CapturingDisplay`] and a [`VirtualClock`]) with client commands and
+//! board scripts, then check the notifications and frames it produced,
+//! without hardware. These are the helpers the crate's own tests use;
+//! [`ScriptedGame`] and [`fen`] add whole games and common positions.
+
+use shakmaty::fen::Fen;
+use shakmaty::uci::UciMove;
+use shakmaty::{CastlingMode, Chess, Color, Move, Position, Square};
+
+pub use crate::testutil::{
+    CLIENT, CapturingDisplay, Notification, ParseError, QueuedCommands, RecordingNotifier,
+    ScriptedPlayer, ScriptedSensor, Simulation, VirtualClock,
+};
+
+/// Standard positions, as FEN.
+pub mod fen {
+    pub const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    /// After 1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5: both sides can castle short.
+    pub const ITALIAN: &str = "r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4";
+    /// The "Kiwipete" perft position: castling, en passant, pins and
+    /// promotions all within a few moves.
+    pub const KIWIPETE: &str =
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    /// White to move and promote on a8.
+    pub const PROMOTION: &str = "7k/P7/8/8/8/8/8/K7 w - - 0 1";
+    /// King and queen against king, White to mate.
+    pub const QUEEN_ENDGAME: &str = "8/8/8/4k3/8/8/8/4K2Q w - - 0 1";
+}
+
+/// The position `fen` describes.
+///
+/// # Panics
+///
+/// If `fen` is not a legal standard chess position.
+pub fn position(fen: &str) -> Chess {
+    fen.parse::<Fen>()
+        .ok()
+        .and_then(|fen| fen.into_position(CastlingMode::Standard).ok())
+        .unwrap_or_else(|| panic!("invalid fixture FEN {fen:?}"))
+}
+
+/// A sensor reading the pieces of `position`.
+pub fn sensor_for(position: &Chess) -> ScriptedSensor {
+    let board = position.board();
+    ScriptedSensor::from_bitboards(board.by_color(Color::White), board.by_color(Color::Black))
+        .unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// A complete game from the starting position, in UCI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptedGame {
+    pub name: &'static str,
+    pub moves: &'static [&'static str],
+    /// The side checkmated at the end.
+    pub loser: Color,
+}
+
+/// Morphy against the Duke of Brunswick and Count Isouard, Paris 1858:
+/// captures, checks and long castling, ending in mate on move 17.
+pub const OPERA_GAME: ScriptedGame = ScriptedGame {
+    name: "Opera Game",
+    moves: &[
+        "e2e4", "e7e5", "g1f3", "d7d6", "d2d4", "c8g4", "d4e5", "g4f3", "d1f3", "d6e5", "f1c4",
+        "g8f6", "f3b3", "d8e7", "b1c3", "c7c6", "c1g5", "b7b5", "c3b5", "c6b5", "c4b5", "b8d7",
+        "e1c1", "a8d8", "d1d7", "d8d7", "h1d1", "e7e6", "b5d7", "f6d7", "b3b8", "d7b8", "d1d8",
+    ],
+    loser: Color::Black,
+};
+
+/// The shortest mate: 1. f3 e5 2. g4 Qh4#.
+pub const FOOLS_MATE: ScriptedGame = ScriptedGame {
+    name: "Fool's Mate",
+    moves: &["f2f3", "e7e5", "g2g4", "d8h4"],
+    loser: Color::White,
+};
+
+impl ScriptedGame {
+    /// The moves, checked against the position each is played in.
+    ///
+    /// # Panics
+    ///
+    /// If a move is not legal where it is played.
+    pub fn moves(&self) -> Vec<Move> {
+        let mut position = Chess::default();
+        self.moves
+            .iter()
+            .map(|uci| {
+                let mv = uci
+                    .parse::<UciMove>()
+                    .ok()
+                    .and_then(|uci| uci.to_move(&position).ok())
+                    .unwrap_or_else(|| panic!("{}: illegal move {uci}", self.name));
+                position.play_unchecked(mv);
+                mv
+            })
+            .collect()
+    }
+
+    /// The final position.
+    pub fn position(&self) -> Chess {
+        let mut position = Chess::default();
+        for mv in self.moves() {
+            position.play_unchecked(mv);
+        }
+        position
+    }
+
+    /// A board script playing the whole game by hand (see
+    /// [`move_script`]).
+    pub fn board_script(&self) -> String {
+        let mut position = Chess::default();
+        let mut script = String::new();
+        for mv in self.moves() {
+            if !script.is_empty() {
+                script.push(' ');
+            }
+            script.push_str(&move_script(&position, mv));
+            position.play_unchecked(mv);
+        }
+        script
+    }
+}
+
+/// A board script making `mv` in `position` the way a player would: every
+/// piece that leaves or is captured is lifted in one reading, then every
+/// piece that arrives is placed in the next.
+pub fn move_script(position: &Chess, mv: Move) -> String {
+    let before = position.board();
+    let mut after = position.clone();
+    after.play_unchecked(mv);
+    let after = after.board();
+    let changed: Vec<Square> = Square::ALL
+        .into_iter()
+        .filter(|&sq| before.color_at(sq) != after.color_at(sq))
+        .collect();
+
+    let lifted: Vec<String> = changed
+        .iter()
+        .filter(|&&sq| before.color_at(sq).is_some())
+        .map(|sq| sq.to_string())
+        .collect();
+    let placed: Vec<String> = changed
+        .iter()
+        .filter_map(|&sq| match after.color_at(sq)? {
+            Color::White => Some(format!("W{sq}")),
+            Color::Black => Some(format!("B{sq}")),
+        })
+        .collect();
+    format!("{}. {}.", lifted.join(" "), placed.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::ble_protocol::BleCommand;
+    use crate::board_api::{GameStatus, PlayerType};
+
+    #[test]
+    fn fixture_positions_are_legal() {
+        for fen in [
+            fen::START,
+            fen::ITALIAN,
+            fen::KIWIPETE,
+            fen::PROMOTION,
+            fen::QUEEN_ENDGAME,
+        ] {
+            assert!(!position(fen).legal_moves().is_empty(), "{fen}");
+        }
+        assert_eq!(position(fen::START), Chess::default());
+    }
+
+    #[test]
+    fn captures_lift_both_pieces_before_placing() {
+        let position = position("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+        let mv = "e4d5"
+            .parse::<UciMove>()
+            .unwrap()
+            .to_move(&position)
+            .unwrap();
+
+        assert_eq!(move_script(&position, mv), "e4 d5. Wd5.");
+    }
+
+    #[test]
+    fn scripted_games_play_through_the_app() {
+        for game in [FOOLS_MATE, OPERA_GAME] {
+            let mut sim = Simulation::new();
+            sim.send(BleCommand::StartGame {
+                white: PlayerType::Human,
+                black: PlayerType::Human,
+            });
+            sim.step();
+            sim.push_script(&game.board_script()).unwrap();
+            sim.run_for(Duration::from_secs(5));
+
+            assert!(
+                sim.notifications()
+                    .contains(&Notification::GameStatus(GameStatus::Checkmate {
+                        loser: game.loser
+                    })),
+                "{} did not end in mate",
+                game.name
+            );
+            assert!(game.position().is_checkmate());
+        }
+    }
+}
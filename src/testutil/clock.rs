@@ -0,0 +1,32 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::app::Clock;
+
+/// A manually advanced [`Clock`] for host simulation.
+///
+/// Clones share the same underlying time, so a test can hold one handle
+/// while the application owns another.
+#[derive(Debug, Clone, Default)]
+pub struct VirtualClock {
+    now: Rc<Cell<Duration>>,
+}
+
+impl VirtualClock {
+    /// Create a clock starting at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move time forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
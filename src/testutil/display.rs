@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use shakmaty::Square;
+
+use crate::animation::Animation;
+use crate::app::Clock;
+use crate::edge::EdgeFeedback;
+use crate::feedback::{BoardFeedback, SquareFeedback};
+use crate::frame::Rgb8;
+use crate::settings::DisplaySettings;
+use crate::{BoardDisplay, EdgeDisplay};
+
+use super::VirtualClock;
+
+/// A [`BoardDisplay`] that records every frame it is asked to show and
+/// every animation it is asked to play, timestamped with virtual time;
+/// raw colors from [`BoardDisplay::set_squares`] are recorded separately.
+/// As an [`EdgeDisplay`] it keeps the latest edge state. Applied
+/// settings are kept but do not change the recorded frames. The
+/// `assert_*` helpers check every recorded frame, not just the last.
+#[derive(Debug, Clone)]
+pub struct CapturingDisplay {
+    clock: VirtualClock,
+    frames: Vec<(Duration, BoardFeedback)>,
+    raw_frames: Vec<(Duration, Vec<(Square, Rgb8)>)>,
+    animations: Vec<(Duration, Animation)>,
+    edge: Option<EdgeFeedback>,
+    settings: Option<DisplaySettings>,
+}
+
+impl CapturingDisplay {
+    /// Create a display that timestamps frames with `clock`.
+    pub fn new(clock: VirtualClock) -> Self {
+        Self {
+            clock,
+            frames: Vec::new(),
+            raw_frames: Vec::new(),
+            animations: Vec::new(),
+            edge: None,
+            settings: None,
+        }
+    }
+
+    /// All frames shown so far, oldest first.
+    pub fn frames(&self) -> &[(Duration, BoardFeedback)] {
+        &self.frames
+    }
+
+    /// All raw-color frames shown so far, oldest first.
+    pub fn raw_frames(&self) -> &[(Duration, Vec<(Square, Rgb8)>)] {
+        &self.raw_frames
+    }
+
+    /// All animations started so far, oldest first.
+    pub fn animations(&self) -> &[(Duration, Animation)] {
+        &self.animations
+    }
+
+    /// The most recently shown frame.
+    pub fn last(&self) -> Option<&BoardFeedback> {
+        self.frames.last().map(|(_, fb)| fb)
+    }
+
+    /// The display settings most recently applied.
+    pub fn settings(&self) -> Option<&DisplaySettings> {
+        self.settings.as_ref()
+    }
+
+    /// The most recently shown edge state.
+    pub fn edge(&self) -> Option<&EdgeFeedback> {
+        self.edge.as_ref()
+    }
+
+    /// When `square` first showed `feedback`, if it ever did.
+    pub fn first_shown(&self, square: Square, feedback: SquareFeedback) -> Option<Duration> {
+        self.frames
+            .iter()
+            .find(|(_, fb)| fb.get(square) == Some(feedback))
+            .map(|&(at, _)| at)
+    }
+
+    /// What `square` showed over time: one entry per change, starting
+    /// from its first frame.
+    pub fn history(&self, square: Square) -> Vec<(Duration, Option<SquareFeedback>)> {
+        let mut history: Vec<(Duration, Option<SquareFeedback>)> = Vec::new();
+        for (at, fb) in &self.frames {
+            let shown = fb.get(square);
+            if history.last().is_none_or(|&(_, last)| last != shown) {
+                history.push((*at, shown));
+            }
+        }
+        history
+    }
+
+    /// Panic unless `square` showed `feedback` in some frame.
+    #[track_caller]
+    pub fn assert_shown(&self, square: Square, feedback: SquareFeedback) {
+        assert!(
+            self.first_shown(square, feedback).is_some(),
+            "{square} never showed {feedback:?}; it showed {:?}",
+            self.history(square)
+        );
+    }
+
+    /// Panic on the first frame for which `bad` holds, naming it `what`.
+    #[track_caller]
+    pub fn assert_never(&self, what: &str, bad: impl Fn(&BoardFeedback) -> bool) {
+        if let Some((at, fb)) = self.frames.iter().find(|(_, fb)| bad(fb)) {
+            panic!("frame at {at:?} showed {what}: {fb:?}");
+        }
+    }
+
+    /// Panic on the first frame lighting one square `first` and another
+    /// `second`, e.g. a check indicator left up while a piece is lifted.
+    #[track_caller]
+    pub fn assert_never_together(&self, first: SquareFeedback, second: SquareFeedback) {
+        let shows = |fb: &BoardFeedback, kind| fb.squares().any(|(_, shown)| shown == kind);
+        self.assert_never(&format!("{first:?} with {second:?}"), |fb| {
+            shows(fb, first) && shows(fb, second)
+        });
+    }
+}
+
+impl BoardDisplay for CapturingDisplay {
+    type Error = std::convert::Infallible;
+
+    fn show(&mut self, feedback: &BoardFeedback) -> Result<(), Self::Error> {
+        self.frames.push((self.clock.now(), feedback.clone()));
+        Ok(())
+    }
+
+    fn set_squares(&mut self, squares: &[(Square, Rgb8)]) -> Result<(), Self::Error> {
+        self.raw_frames.push((self.clock.now(), squares.to_vec()));
+        Ok(())
+    }
+
+    fn play(&mut self, animation: Animation) {
+        self.animations.push((self.clock.now(), animation));
+    }
+
+    fn apply_settings(&mut self, settings: &DisplaySettings) {
+        self.settings = Some(*settings);
+    }
+}
+
+impl EdgeDisplay for CapturingDisplay {
+    type Error = std::convert::Infallible;
+
+    fn show_edge(&mut self, edge: &EdgeFeedback) -> Result<(), Self::Error> {
+        self.edge = Some(*edge);
+        Ok(())
+    }
+}
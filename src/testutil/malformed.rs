@@ -0,0 +1,26 @@
+use crate::rng::XorShift32;
+
+/// Inputs a parser must survive without panicking: every prefix of
+/// `valid`, `valid` with each byte flipped in turn, and `count` random
+/// buffers of up to `max_len` bytes. Half the random buffers start with
+/// `valid`'s first byte so they get past a tag check.
+pub fn malformed_inputs(valid: &[u8], count: usize, max_len: usize) -> Vec<Vec<u8>> {
+    let mut inputs: Vec<Vec<u8>> = (0..valid.len()).map(|len| valid[..len].to_vec()).collect();
+    for i in 0..valid.len() {
+        let mut flipped = valid.to_vec();
+        flipped[i] = !flipped[i];
+        inputs.push(flipped);
+    }
+    let mut rng = XorShift32::new(0x5eed);
+    for n in 0..count {
+        let len = rng.below(max_len as u32 + 1) as usize;
+        let mut bytes: Vec<u8> = (0..len).map(|_| rng.next_u32() as u8).collect();
+        if n % 2 == 0
+            && let (Some(first), Some(&tag)) = (bytes.first_mut(), valid.first())
+        {
+            *first = tag;
+        }
+        inputs.push(bytes);
+    }
+    inputs
+}
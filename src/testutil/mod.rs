@@ -1,3 +1,22 @@
+#[cfg(test)]
+mod alloc;
+mod clock;
+mod display;
+#[cfg(test)]
+mod malformed;
+mod opponent;
 mod script;
+mod sim;
 
+#[cfg(test)]
+pub use alloc::allocations_during;
+pub use clock::VirtualClock;
+pub use display::CapturingDisplay;
+#[cfg(test)]
+pub use malformed::malformed_inputs;
+pub use opponent::ScriptedPlayer;
 pub use script::ScriptedSensor;
+pub use sim::{CLIENT, Notification, QueuedCommands, RecordingNotifier, Simulation};
+// Only `testkit` uses this.
+#[cfg(feature = "testkit")]
+pub use script::ParseError;
@@ -0,0 +1,36 @@
+use std::collections::VecDeque;
+
+use shakmaty::{Bitboard, ByColor, Chess, Move, Position};
+
+use crate::player::Player;
+
+/// An opponent that plays a fixed line, one move per turn.
+///
+/// Stops moving once the line is exhausted or its next move is illegal
+/// in the current position (e.g. the game went another way).
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedPlayer {
+    line: VecDeque<Move>,
+}
+
+impl ScriptedPlayer {
+    pub fn new(line: impl IntoIterator<Item = Move>) -> Self {
+        Self {
+            line: line.into_iter().collect(),
+        }
+    }
+}
+
+impl Player for ScriptedPlayer {
+    fn poll_move(&mut self, position: &Chess, _sensors: ByColor<Bitboard>) -> Option<Move> {
+        let next = *self.line.front()?;
+        if !position.legal_moves().contains(&next) {
+            return None;
+        }
+        self.line.pop_front()
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
@@ -1,8 +1,12 @@
 use std::collections::VecDeque;
+use std::time::Duration;
 
 use shakmaty::{Bitboard, ByColor, Chess, Color, Position, Square};
 use thiserror::Error;
 
+use crate::calibration::Calibration;
+use crate::rng::XorShift32;
+
 /// Error when parsing or executing a board script.
 #[derive(Debug, Clone, Error, PartialEq, Eq)]
 pub enum ParseError {
@@ -15,18 +19,44 @@ pub enum ParseError {
     /// A square is occupied by pieces of both colors, which is physically impossible.
     #[error("square(s) occupied by both colors: {0}")]
     OverlappingSquares(String),
+    /// A delay token is not a number followed by `ms` or `s`.
+    #[error("invalid delay: '{0}'")]
+    InvalidDelay(String),
+    /// A noise token is not a square, `:` and a tick count.
+    #[error("invalid noise directive: '{0}'")]
+    InvalidNoise(String),
 }
 
 /// A scriptable mock sensor that processes BoardScript format.
 ///
 /// Maintains per-color bitboard state and executes script batches on demand.
-/// New script can be appended at any time for interactive use.
+/// New script can be appended at any time for interactive use. Flickering
+/// squares (see [`push_script`](Self::push_script)) change what the sensor
+/// reads, not where the pieces are.
 #[derive(Debug, Clone)]
 pub struct ScriptedSensor {
     positions: ByColor<Bitboard>,
-    pending_batches: VecDeque<Vec<BatchEntry>>,
+    /// `positions` as read, with the flickering squares' noise.
+    reading: ByColor<Bitboard>,
+    pending_batches: VecDeque<Step>,
+    /// End of the delay being waited out.
+    wait_until: Option<Duration>,
+    flickers: Vec<Flicker>,
+    noise: XorShift32,
+    calibration: Option<Calibration>,
 }
 
+/// Millivolts an empty square reads; white pieces read
+/// [`SCRIPTED_PIECE_MV`] above, black pieces as much below.
+const SCRIPTED_BASELINE_MV: u16 = 1440;
+
+/// Signal of a scripted piece.
+const SCRIPTED_PIECE_MV: u16 = 400;
+
+/// Seed of the flicker noise unless set with
+/// [`ScriptedSensor::with_noise_seed`].
+const DEFAULT_NOISE_SEED: u32 = 0x5EED;
+
 impl Default for ScriptedSensor {
     fn default() -> Self {
         Self::new()
@@ -49,23 +79,48 @@ impl ScriptedSensor {
         check_overlap(white, black)?;
         Ok(Self {
             positions: ByColor { white, black },
+            reading: ByColor { white, black },
             pending_batches: VecDeque::new(),
+            wait_until: None,
+            flickers: Vec::new(),
+            noise: XorShift32::new(DEFAULT_NOISE_SEED),
+            calibration: None,
         })
     }
 
-    /// Per-color piece positions.
+    /// Draw flicker noise from `seed` instead of the fixed default.
+    pub fn with_noise_seed(mut self, seed: u32) -> Self {
+        self.noise = XorShift32::new(seed);
+        self
+    }
+
+    /// Per-color piece positions as the sensor reads them, flicker
+    /// included.
     #[inline]
     pub fn read_positions(&self) -> ByColor<Bitboard> {
+        self.reading
+    }
+
+    /// Where the pieces actually are, ignoring flicker.
+    pub fn true_positions(&self) -> ByColor<Bitboard> {
         self.positions
     }
 
+    /// The calibration last applied through [`crate::PieceSensor::calibrate`].
+    pub fn calibration(&self) -> Option<Calibration> {
+        self.calibration
+    }
+
     /// Load separate white and black bitboards directly (e.g. when loading a FEN position).
     ///
     /// Returns [`ParseError::OverlappingSquares`] if any square appears in both bitboards.
     pub fn load_bitboards(&mut self, white: Bitboard, black: Bitboard) -> Result<(), ParseError> {
         check_overlap(white, black)?;
         self.positions = ByColor { white, black };
+        self.reading = self.positions;
         self.pending_batches.clear();
+        self.wait_until = None;
+        self.flickers.clear();
         Ok(())
     }
 
@@ -80,9 +135,19 @@ impl ScriptedSensor {
     /// A color prefix is required when placing a piece on an empty square;
     /// omitting it for an occupied square infers the color from the current state.
     ///
+    /// Timing and noise, for debouncing and sensor glitches:
+    /// - `@200ms` or `@2s` ends the batch and holds the board as it is until
+    ///   that much time has passed (see [`tick_at`](Self::tick_at))
+    /// - `~e4:3` makes e4 flicker for the next 3 ticks, starting with the
+    ///   batch it is in: it reads wrong on the first tick and at random
+    ///   after that, then reads true again. `~We4:3` gives the color an
+    ///   empty square flickers with.
+    ///
     /// Examples:
     /// - `"e2 We4."` - Lift e2, place white on e4, then tick
     /// - `"e2.  We4."` - Lift e2, tick, place white on e4, tick
+    /// - `"e2. @300ms We4."` - Lift e2, hold it for 300ms, place it on e4
+    /// - `"~e2:4."` - e2 glitches for four ticks without being touched
     pub fn push_script(&mut self, script: &str) -> Result<(), ParseError> {
         let batches = parse_script(script)?;
         self.pending_batches.extend(batches);
@@ -91,19 +156,88 @@ impl ScriptedSensor {
 
     /// Execute next pending batch, returning new per-color positions.
     ///
+    /// Delays take no time here; use [`tick_at`](Self::tick_at) to wait
+    /// them out.
+    ///
     /// Returns `Ok(None)` if no pending batches remain.
     /// Returns `Err` if a placement is attempted on an empty square without a color.
     pub fn tick(&mut self) -> Result<Option<ByColor<Bitboard>>, ParseError> {
-        let Some(batch) = self.pending_batches.pop_front() else {
-            return Ok(None);
+        self.advance(None)
+    }
+
+    /// As [`tick`](Self::tick) at time `now`: while a delay runs, the board
+    /// stays as it is (flicker aside) and the positions are returned.
+    pub fn tick_at(&mut self, now: Duration) -> Result<Option<ByColor<Bitboard>>, ParseError> {
+        self.advance(Some(now))
+    }
+
+    fn advance(&mut self, now: Option<Duration>) -> Result<Option<ByColor<Bitboard>>, ParseError> {
+        let batch = loop {
+            match self.pending_batches.front() {
+                // Once the last flicker is over, one more tick reads true.
+                None if self.flickers.is_empty() && self.reading == self.positions => {
+                    return Ok(None);
+                }
+                None => break Vec::new(),
+                Some(Step::Wait(delay)) => {
+                    if let Some(now) = now {
+                        let until = *self.wait_until.get_or_insert(now + *delay);
+                        if now < until {
+                            break Vec::new();
+                        }
+                    }
+                    self.wait_until = None;
+                    self.pending_batches.pop_front();
+                }
+                Some(Step::Batch(_)) => {
+                    let Some(Step::Batch(batch)) = self.pending_batches.pop_front() else {
+                        unreachable!("front was a batch");
+                    };
+                    break batch;
+                }
+            }
         };
-        for (square, color) in batch {
-            self.toggle_square(square, color)?;
+        for action in batch {
+            match action {
+                Action::Toggle(square, color) => self.toggle_square(square, color)?,
+                Action::Flicker(flicker) => {
+                    self.flickers.retain(|f| f.square != flicker.square);
+                    self.flickers.push(flicker);
+                }
+            }
+        }
+        self.read_with_noise()?;
+        Ok(Some(self.reading))
+    }
+
+    /// Apply one tick of every flicker to the true positions.
+    fn read_with_noise(&mut self) -> Result<(), ParseError> {
+        self.reading = self.positions;
+        for flicker in &mut self.flickers {
+            let wrong = flicker.first || self.noise.below(2) == 0;
+            flicker.first = false;
+            flicker.ticks -= 1;
+            if !wrong {
+                continue;
+            }
+            let square = flicker.square;
+            if self.reading.white.contains(square) {
+                self.reading.white.discard(square);
+            } else if self.reading.black.contains(square) {
+                self.reading.black.discard(square);
+            } else {
+                let color = flicker
+                    .color
+                    .ok_or_else(|| ParseError::MissingColor(square.to_string()))?;
+                self.reading[color].add(square);
+            }
         }
-        Ok(Some(self.positions))
+        self.flickers.retain(|flicker| flicker.ticks > 0);
+        Ok(())
     }
 
     /// Execute all pending batches, calling the provided callback for each.
+    /// Delays take no time.
     pub fn drain<F>(&mut self, mut on_tick: F) -> Result<(), ParseError>
     where
         F: FnMut(ByColor<Bitboard>),
@@ -136,7 +270,24 @@ impl crate::PieceSensor for ScriptedSensor {
 
     #[inline]
     fn read_positions(&mut self) -> Result<ByColor<Bitboard>, Self::Error> {
-        Ok(self.positions)
+        Ok(self.reading)
+    }
+
+    /// Ideal readings of the current positions: no noise, every piece
+    /// equally strong.
+    fn read_millivolts(&mut self) -> Option<Result<[u16; 64], Self::Error>> {
+        let mut mv = [SCRIPTED_BASELINE_MV; 64];
+        for sq in self.reading.white {
+            mv[sq as usize] += SCRIPTED_PIECE_MV;
+        }
+        for sq in self.reading.black {
+            mv[sq as usize] -= SCRIPTED_PIECE_MV;
+        }
+        Some(Ok(mv))
+    }
+
+    fn calibrate(&mut self, calibration: Calibration) {
+        self.calibration = Some(calibration);
     }
 }
 
@@ -156,66 +307,139 @@ fn check_overlap(white: Bitboard, black: Bitboard) -> Result<(), ParseError> {
     }
 }
 
-/// A parsed batch entry: a square to toggle and an optional color prefix.
-type BatchEntry = (Square, Option<Color>);
+/// A parsed script step: a batch applied in one tick, or a delay.
+#[derive(Debug, Clone)]
+enum Step {
+    Batch(Vec<Action>),
+    Wait(Duration),
+}
+
+/// One entry of a batch.
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    /// A square to toggle and an optional color prefix.
+    Toggle(Square, Option<Color>),
+    Flicker(Flicker),
+}
 
-/// Parse a BoardScript string into batches of `(Square, Option<Color>)` to toggle.
-fn parse_script(script: &str) -> Result<Vec<Vec<BatchEntry>>, ParseError> {
-    let mut batches: Vec<Vec<BatchEntry>> = vec![Vec::new()];
+/// A square reading wrong for a while.
+#[derive(Debug, Clone, Copy)]
+struct Flicker {
+    square: Square,
+    /// Color read on the square if it is empty.
+    color: Option<Color>,
+    /// Ticks left, this one included.
+    ticks: u32,
+    /// The first tick always reads wrong.
+    first: bool,
+}
+
+/// Parse a BoardScript string into batches and delays.
+fn parse_script(script: &str) -> Result<Vec<Step>, ParseError> {
+    let mut steps = vec![Step::Batch(Vec::new())];
     let mut current_token = String::new();
 
     for ch in script.chars() {
         match ch {
             '.' => {
-                flush_token(&mut current_token, &mut batches)?;
-                batches.push(Vec::new());
+                flush_token(&mut current_token, &mut steps)?;
+                steps.push(Step::Batch(Vec::new()));
             }
             c if c.is_whitespace() => {
-                flush_token(&mut current_token, &mut batches)?;
+                flush_token(&mut current_token, &mut steps)?;
             }
             _ => {
                 current_token.push(ch);
 
                 // Tokens are 2 chars for bare squares (e.g. "e2") or 3 chars
-                // when prefixed with a color ('W' or 'B', e.g. "We4").
-                let has_color_prefix =
-                    matches!(current_token.chars().next(), Some('W') | Some('B'));
-                let expected_len = if has_color_prefix { 3 } else { 2 };
-                if current_token.len() == expected_len {
-                    flush_token(&mut current_token, &mut batches)?;
+                // when prefixed with a color ('W' or 'B', e.g. "We4"). Delay
+                // and noise tokens run to the next space or period.
+                let expected_len = match current_token.chars().next() {
+                    Some('@' | '~') => None,
+                    Some('W' | 'B') => Some(3),
+                    _ => Some(2),
+                };
+                if expected_len == Some(current_token.len()) {
+                    flush_token(&mut current_token, &mut steps)?;
                 }
             }
         }
     }
 
     // Flush any remaining token
-    flush_token(&mut current_token, &mut batches)?;
+    flush_token(&mut current_token, &mut steps)?;
 
     // Remove empty batches
-    batches.retain(|b| !b.is_empty());
-    Ok(batches)
+    steps.retain(|step| !matches!(step, Step::Batch(batch) if batch.is_empty()));
+    Ok(steps)
 }
 
-/// Parse the current token into a [`BatchEntry`] and clear the token.
-fn flush_token(token: &mut String, batches: &mut [Vec<BatchEntry>]) -> Result<(), ParseError> {
-    if !token.is_empty() {
-        let (color, square_str) = match token.chars().next() {
-            Some('W') => (Some(Color::White), &token[1..]),
-            Some('B') => (Some(Color::Black), &token[1..]),
-            _ => (None, token.as_str()),
-        };
-        let square: Square = square_str
-            .parse()
-            .map_err(|_| ParseError::InvalidSquare(token.clone()))?;
-        batches
-            .last_mut()
-            .expect("batches should never be empty")
-            .push((square, color));
+/// Parse the current token into a step or batch entry and clear the token.
+fn flush_token(token: &mut String, steps: &mut Vec<Step>) -> Result<(), ParseError> {
+    if token.is_empty() {
+        return Ok(());
+    }
+    if let Some(delay) = token.strip_prefix('@') {
+        let delay = parse_delay(delay).ok_or_else(|| ParseError::InvalidDelay(token.clone()))?;
+        steps.push(Step::Wait(delay));
+        steps.push(Step::Batch(Vec::new()));
         token.clear();
+        return Ok(());
     }
+    let action = match token.strip_prefix('~') {
+        Some(noise) => Action::Flicker(
+            parse_flicker(noise).ok_or_else(|| ParseError::InvalidNoise(token.clone()))?,
+        ),
+        None => {
+            let (square, color) =
+                parse_square(token).ok_or_else(|| ParseError::InvalidSquare(token.clone()))?;
+            Action::Toggle(square, color)
+        }
+    };
+    let Some(Step::Batch(batch)) = steps.last_mut() else {
+        unreachable!("a delay is always followed by a batch");
+    };
+    batch.push(action);
+    token.clear();
     Ok(())
 }
 
+/// A square with an optional `W` or `B` color prefix.
+fn parse_square(token: &str) -> Option<(Square, Option<Color>)> {
+    let (color, square) = match token.chars().next() {
+        Some('W') => (Some(Color::White), &token[1..]),
+        Some('B') => (Some(Color::Black), &token[1..]),
+        _ => (None, token),
+    };
+    Some((square.parse().ok()?, color))
+}
+
+/// `200ms` or `2s`.
+fn parse_delay(delay: &str) -> Option<Duration> {
+    if let Some(ms) = delay.strip_suffix("ms") {
+        ms.parse().ok().map(Duration::from_millis)
+    } else {
+        delay
+            .strip_suffix('s')?
+            .parse()
+            .ok()
+            .map(Duration::from_secs)
+    }
+}
+
+/// `e4:3` or `We4:3`, for at least one tick.
+fn parse_flicker(noise: &str) -> Option<Flicker> {
+    let (square, ticks) = noise.split_once(':')?;
+    let (square, color) = parse_square(square)?;
+    let ticks = ticks.parse().ok().filter(|&ticks| ticks > 0)?;
+    Some(Flicker {
+        square,
+        color,
+        ticks,
+        first: true,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -331,6 +555,65 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_delay_holds_the_board_until_it_passes() {
+        let mut sensor = ScriptedSensor::new();
+        sensor.push_script("e2. @300ms We4.").unwrap();
+        let lifted = sensor.tick_at(Duration::ZERO).unwrap().unwrap();
+        assert!(!lifted.white.contains(Square::E2));
+
+        for ms in [0, 100, 299] {
+            assert_eq!(
+                sensor.tick_at(Duration::from_millis(ms)).unwrap(),
+                Some(lifted)
+            );
+        }
+        let placed = sensor.tick_at(Duration::from_millis(300)).unwrap().unwrap();
+        assert!(placed.white.contains(Square::E4));
+        assert_eq!(sensor.tick_at(Duration::from_millis(400)).unwrap(), None);
+
+        sensor.push_script("e4 @1s We2.").unwrap();
+        sensor.tick().unwrap();
+        assert!(
+            sensor.tick().unwrap().unwrap().white.contains(Square::E2),
+            "tick takes no time"
+        );
+    }
+
+    #[test]
+    fn test_flicker_changes_the_reading_not_the_pieces() {
+        let mut sensor = ScriptedSensor::new();
+        let start = sensor.read_positions();
+        sensor.push_script("~e2:3 ~Bd5:3.").unwrap();
+
+        let first = sensor.tick().unwrap().unwrap();
+        assert!(!first.white.contains(Square::E2), "first tick reads wrong");
+        assert!(first.black.contains(Square::D5));
+        assert_eq!(sensor.true_positions(), start);
+
+        sensor.tick().unwrap().unwrap();
+        sensor.tick().unwrap().unwrap();
+        assert_eq!(sensor.tick().unwrap(), Some(start), "flicker over");
+        assert_eq!(sensor.tick().unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_error_invalid_delay_and_noise() {
+        let mut sensor = ScriptedSensor::new();
+        assert_eq!(
+            sensor.push_script("e2. @soon."),
+            Err(ParseError::InvalidDelay("@soon".to_string()))
+        );
+        assert_eq!(
+            sensor.push_script("~e2."),
+            Err(ParseError::InvalidNoise("~e2".to_string()))
+        );
+        assert_eq!(
+            sensor.push_script("~e2:0."),
+            Err(ParseError::InvalidNoise("~e2:0".to_string()))
+        );
+    }
+
     #[test]
     fn test_new_matches_starting_position_colors() {
         let chess = Chess::default();
@@ -0,0 +1,248 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use shakmaty::Color;
+
+use crate::access::ConnectionId;
+use crate::app::{BoardApp, BoardNotifier, ClientEvent, Clock, CommandQueue};
+use crate::ble_protocol::{BleCommand, CommandResult};
+use crate::board_api::{GameEvent, GameStatus, PlayerType, StateChecksum};
+use crate::pairing::Token;
+
+use super::script::ParseError;
+use super::{CapturingDisplay, ScriptedSensor, VirtualClock};
+
+/// A single outbound update recorded by [`RecordingNotifier`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Notification {
+    GameStatus(GameStatus),
+    CommandResult(CommandResult),
+    PlayerType(Color, PlayerType),
+    MovePlayed(Color, String),
+    Position(String),
+    LastMove(Color, String),
+    ResetPlayerTypes,
+    ResetPosition,
+    ResetLastMove,
+    PendingPromotion(Color, String),
+    ResetPendingPromotion,
+    GameEvent(GameEvent),
+    PairingToken(Token),
+}
+
+/// A [`BoardNotifier`] that records every update in order.
+#[derive(Debug, Clone)]
+pub struct RecordingNotifier {
+    pub notifications: Vec<Notification>,
+    /// Checksum sent with the latest `MovePlayed`.
+    pub last_checksum: Option<StateChecksum>,
+    /// Reported by [`BoardNotifier::is_connected`].
+    pub connected: bool,
+}
+
+impl Default for RecordingNotifier {
+    fn default() -> Self {
+        Self {
+            notifications: Vec::new(),
+            last_checksum: None,
+            connected: true,
+        }
+    }
+}
+
+impl BoardNotifier for RecordingNotifier {
+    fn notify_game_status(&mut self, status: &GameStatus) {
+        self.notifications
+            .push(Notification::GameStatus(status.clone()));
+    }
+
+    fn notify_command_result(&mut self, result: &CommandResult) {
+        self.notifications
+            .push(Notification::CommandResult(result.clone()));
+    }
+
+    fn update_player_type(&mut self, color: Color, player_type: PlayerType) {
+        self.notifications
+            .push(Notification::PlayerType(color, player_type));
+    }
+
+    fn notify_move_played(&mut self, color: Color, uci: &str, checksum: StateChecksum) {
+        self.notifications
+            .push(Notification::MovePlayed(color, uci.to_string()));
+        self.last_checksum = Some(checksum);
+    }
+
+    fn update_position(&mut self, fen: &str) {
+        self.notifications
+            .push(Notification::Position(fen.to_string()));
+    }
+
+    fn update_last_move(&mut self, color: Color, uci: &str, _checksum: StateChecksum) {
+        self.notifications
+            .push(Notification::LastMove(color, uci.to_string()));
+    }
+
+    fn reset_player_types(&mut self) {
+        self.notifications.push(Notification::ResetPlayerTypes);
+    }
+
+    fn reset_position(&mut self) {
+        self.notifications.push(Notification::ResetPosition);
+    }
+
+    fn reset_last_move(&mut self) {
+        self.notifications.push(Notification::ResetLastMove);
+    }
+
+    fn update_pending_promotion(&mut self, color: Color, uci: &str) {
+        self.notifications
+            .push(Notification::PendingPromotion(color, uci.to_string()));
+    }
+
+    fn reset_pending_promotion(&mut self) {
+        self.notifications.push(Notification::ResetPendingPromotion);
+    }
+
+    fn notify_game_event(&mut self, event: &GameEvent) {
+        self.notifications.push(Notification::GameEvent(*event));
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    fn notify_pairing_token(&mut self, token: Token) {
+        self.notifications.push(Notification::PairingToken(token));
+    }
+}
+
+/// The connection [`Simulation::send`] sends from.
+pub const CLIENT: ConnectionId = 0;
+
+/// A [`CommandQueue`] fed directly by tests.
+#[derive(Debug, Clone, Default)]
+pub struct QueuedCommands(pub VecDeque<ClientEvent>);
+
+impl CommandQueue for QueuedCommands {
+    fn try_recv(&mut self) -> Option<ClientEvent> {
+        self.0.pop_front()
+    }
+}
+
+type SimApp = BoardApp<ScriptedSensor, CapturingDisplay, RecordingNotifier, VirtualClock>;
+
+/// Runs the real [`BoardApp`] loop on the host with virtual time.
+///
+/// Each [`step`](Self::step) applies the next pending BoardScript batch,
+/// runs one application step, and advances the clock by the delay the
+/// application requested — exactly what the hardware loop does with
+/// `FreeRtos::delay_ms`.
+#[derive(Debug)]
+pub struct Simulation {
+    app: SimApp,
+    commands: QueuedCommands,
+    clock: VirtualClock,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simulation {
+    /// Create a simulation with all pieces on their starting squares.
+    pub fn new() -> Self {
+        Self::with_sensor(ScriptedSensor::new())
+    }
+
+    /// Create a simulation around a custom sensor.
+    pub fn with_sensor(sensor: ScriptedSensor) -> Self {
+        let clock = VirtualClock::new();
+        let app = BoardApp::new(
+            sensor,
+            CapturingDisplay::new(clock.clone()),
+            RecordingNotifier::default(),
+            clock.clone(),
+        );
+        Self {
+            app,
+            commands: QueuedCommands::default(),
+            clock,
+        }
+    }
+
+    /// Queue a command from [`CLIENT`] for the next step.
+    pub fn send(&mut self, cmd: BleCommand) {
+        self.send_from(CLIENT, cmd);
+    }
+
+    /// Queue a command from `connection` for the next step.
+    pub fn send_from(&mut self, connection: ConnectionId, cmd: BleCommand) {
+        self.commands
+            .0
+            .push_back(ClientEvent::Command(connection, cmd));
+    }
+
+    /// Queue the close of `connection` for the next step.
+    pub fn disconnect(&mut self, connection: ConnectionId) {
+        self.commands
+            .0
+            .push_back(ClientEvent::Disconnected(connection));
+    }
+
+    /// Queue BoardScript to be played out one batch per step; delays in
+    /// it run on virtual time.
+    pub fn push_script(&mut self, script: &str) -> Result<(), ParseError> {
+        self.app.sensor_mut().push_script(script)
+    }
+
+    /// Run one loop iteration and advance virtual time by the returned delay.
+    pub fn step(&mut self) -> Duration {
+        let now = self.clock.now();
+        if let Err(e) = self.app.sensor_mut().tick_at(now) {
+            panic!("invalid BoardScript: {e}");
+        }
+        let delay = self.app.step(&mut self.commands);
+        self.clock.advance(delay);
+        delay
+    }
+
+    /// Step until at least `duration` of virtual time has elapsed.
+    pub fn run_for(&mut self, duration: Duration) {
+        let deadline = self.clock.now() + duration;
+        while self.clock.now() < deadline {
+            self.step();
+        }
+    }
+
+    /// Current virtual time.
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// The application under simulation.
+    pub fn app(&self) -> &SimApp {
+        &self.app
+    }
+
+    /// Mutable access to the application under simulation.
+    pub fn app_mut(&mut self) -> &mut SimApp {
+        &mut self.app
+    }
+
+    /// Frames captured by the display.
+    pub fn display(&self) -> &CapturingDisplay {
+        self.app.display()
+    }
+
+    /// Notifications recorded so far.
+    pub fn notifications(&self) -> &[Notification] {
+        &self.app.notifier().notifications
+    }
+
+    /// Forget all recorded notifications.
+    pub fn clear_notifications(&mut self) {
+        self.app.notifier_mut().notifications.clear();
+    }
+}
@@ -0,0 +1,218 @@
+//! LED brightness throttling from board temperature.
+//!
+//! With every LED lit, boards in closed wooden cases heat up enough to
+//! matter. [`ThermalThrottle`] turns temperature readings (the ESP32's
+//! internal sensor, optionally an NTC on the LED rail) into a brightness
+//! level for the display, with hysteresis so the level does not flap
+//! around a threshold.
+
+/// Temperature thresholds and the brightness used above each.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThermalConfig {
+    /// Temperature (°C) at which brightness is reduced to `warm_level`.
+    pub warm_c: f32,
+    /// Temperature (°C) at which brightness is reduced to `hot_level`.
+    pub hot_c: f32,
+    /// How far (°C) below a threshold the temperature must fall to step back up.
+    pub hysteresis_c: f32,
+    /// Brightness out of 255 while warm.
+    pub warm_level: u8,
+    /// Brightness out of 255 while hot.
+    pub hot_level: u8,
+}
+
+impl Default for ThermalConfig {
+    fn default() -> Self {
+        Self {
+            warm_c: 55.0,
+            hot_c: 65.0,
+            hysteresis_c: 5.0,
+            warm_level: 160,
+            hot_level: 64,
+        }
+    }
+}
+
+/// Current throttling stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThermalState {
+    Normal,
+    Warm,
+    Hot,
+}
+
+/// Tracks the throttling stage across readings.
+#[derive(Debug, Clone)]
+pub struct ThermalThrottle {
+    config: ThermalConfig,
+    state: ThermalState,
+}
+
+impl ThermalThrottle {
+    pub fn new(config: ThermalConfig) -> Self {
+        Self {
+            config,
+            state: ThermalState::Normal,
+        }
+    }
+
+    pub fn state(&self) -> ThermalState {
+        self.state
+    }
+
+    /// Brightness out of 255 for the current stage.
+    pub fn level(&self) -> u8 {
+        match self.state {
+            ThermalState::Normal => u8::MAX,
+            ThermalState::Warm => self.config.warm_level,
+            ThermalState::Hot => self.config.hot_level,
+        }
+    }
+
+    /// Feed the latest readings (°C) and return the brightness to use.
+    ///
+    /// The hottest reading decides; with no readings the stage is kept.
+    pub fn update(&mut self, readings: impl IntoIterator<Item = f32>) -> u8 {
+        let Some(temp) = readings.into_iter().reduce(f32::max) else {
+            return self.level();
+        };
+        let ThermalConfig {
+            warm_c,
+            hot_c,
+            hysteresis_c,
+            ..
+        } = self.config;
+
+        let next = match self.state {
+            _ if temp >= hot_c => ThermalState::Hot,
+            ThermalState::Hot if temp > hot_c - hysteresis_c => ThermalState::Hot,
+            _ if temp >= warm_c => ThermalState::Warm,
+            ThermalState::Hot | ThermalState::Warm if temp > warm_c - hysteresis_c => {
+                ThermalState::Warm
+            }
+            _ => ThermalState::Normal,
+        };
+
+        if next != self.state {
+            log::warn!(
+                "LED thermal throttle {:?} -> {next:?} at {temp:.1}°C",
+                self.state
+            );
+            self.state = next;
+        }
+        self.level()
+    }
+}
+
+/// A voltage-divider NTC thermistor (NTC to ground, fixed resistor to supply).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ntc {
+    /// Divider supply in millivolts.
+    pub supply_mv: u16,
+    /// Fixed resistor in ohms.
+    pub fixed_ohm: f32,
+    /// Thermistor resistance at `nominal_c`.
+    pub nominal_ohm: f32,
+    /// Reference temperature for `nominal_ohm`, in °C.
+    pub nominal_c: f32,
+    /// Beta coefficient.
+    pub beta: f32,
+}
+
+impl Default for Ntc {
+    /// A common 10k B3950 part against a 10k resistor on 3.3 V.
+    fn default() -> Self {
+        Self {
+            supply_mv: 3300,
+            fixed_ohm: 10_000.0,
+            nominal_ohm: 10_000.0,
+            nominal_c: 25.0,
+            beta: 3950.0,
+        }
+    }
+}
+
+impl Ntc {
+    /// Convert the divider midpoint voltage to °C (beta equation).
+    ///
+    /// Returns `None` for readings at or beyond the rails (open or shorted
+    /// thermistor).
+    pub fn celsius(&self, mv: u16) -> Option<f32> {
+        if mv == 0 || mv >= self.supply_mv {
+            return None;
+        }
+        let v = f32::from(mv);
+        let resistance = self.fixed_ohm * v / (f32::from(self.supply_mv) - v);
+        let t0 = self.nominal_c + 273.15;
+        let inv_t = 1.0 / t0 + (resistance / self.nominal_ohm).ln() / self.beta;
+        Some(1.0 / inv_t - 273.15)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle() -> ThermalThrottle {
+        ThermalThrottle::new(ThermalConfig::default())
+    }
+
+    #[test]
+    fn cool_board_runs_at_full_brightness() {
+        let mut t = throttle();
+        assert_eq!(t.update([30.0]), 255);
+        assert_eq!(t.state(), ThermalState::Normal);
+    }
+
+    #[test]
+    fn steps_down_as_temperature_rises() {
+        let mut t = throttle();
+        assert_eq!(t.update([56.0]), 160);
+        assert_eq!(t.update([66.0]), 64);
+        assert_eq!(t.state(), ThermalState::Hot);
+    }
+
+    #[test]
+    fn hysteresis_holds_stage_until_cooled() {
+        let mut t = throttle();
+        t.update([66.0]);
+        assert_eq!(t.update([62.0]), 64, "still within hysteresis of hot");
+        assert_eq!(t.update([59.0]), 160);
+        assert_eq!(t.update([52.0]), 160, "still within hysteresis of warm");
+        assert_eq!(t.update([49.0]), 255);
+    }
+
+    #[test]
+    fn hottest_reading_decides() {
+        let mut t = throttle();
+        assert_eq!(t.update([40.0, 67.0]), 64);
+    }
+
+    #[test]
+    fn no_readings_keep_stage() {
+        let mut t = throttle();
+        t.update([57.0]);
+        assert_eq!(t.update([]), 160);
+    }
+
+    #[test]
+    fn ntc_reads_nominal_at_midpoint() {
+        let ntc = Ntc::default();
+        let c = ntc.celsius(1650).unwrap();
+        assert!((c - 25.0).abs() < 0.1, "got {c}");
+    }
+
+    #[test]
+    fn ntc_hotter_means_lower_voltage() {
+        let ntc = Ntc::default();
+        let warm = ntc.celsius(1000).unwrap();
+        assert!(warm > 40.0 && warm < 60.0, "got {warm}");
+    }
+
+    #[test]
+    fn ntc_rejects_rail_readings() {
+        let ntc = Ntc::default();
+        assert_eq!(ntc.celsius(0), None);
+        assert_eq!(ntc.celsius(3300), None);
+    }
+}
@@ -0,0 +1,297 @@
+//! Sensor tick logs for replaying field sessions on the desktop.
+//!
+//! [`TickLogger`] wraps the board's sensor and logs every reading that
+//! differs from the previous one as a `tick <read> <white> <black>` line:
+//! the number of the sensor read, then hex bitboards. A serial capture of
+//! a session is therefore an event log of everything move detection saw,
+//! including how long each reading was held. The `replay-log` binary feeds such a log
+//! through [`replay`] with whatever firmware revision it was built from and
+//! diffs the detected moves against the moves that were actually played.
+
+use shakmaty::{Bitboard, ByColor, CastlingMode, Chess, Color, Position};
+
+use crate::PieceSensor;
+use crate::calibration::Calibration;
+use crate::player::{HumanPlayer, MatcherKind};
+use crate::session::GameSession;
+
+/// Marks a tick line; anything before it (log level, module path) is ignored.
+const TICK_MARKER: &str = "tick ";
+
+/// Reads the last reading of a log is held for when replayed, long enough
+/// for any matcher to settle.
+const FINAL_HOLD_READS: u64 = 20;
+
+/// One logged reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    /// Sensor read count when the reading was first seen.
+    pub read: u64,
+    pub positions: ByColor<Bitboard>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TickLogError {
+    #[error("line {line}: malformed tick record")]
+    Malformed { line: usize },
+    #[error("line {line}: square occupied by both colors")]
+    Overlapping { line: usize },
+}
+
+/// Format one reading as a tick line (without any log prefix).
+pub fn format_tick(tick: Tick) -> String {
+    format!(
+        "{TICK_MARKER}{} {:016x} {:016x}",
+        tick.read,
+        u64::from(tick.positions.white),
+        u64::from(tick.positions.black)
+    )
+}
+
+/// Extract the readings from a log, skipping every line without a tick
+/// record.
+pub fn parse_log(text: &str) -> Result<Vec<Tick>, TickLogError> {
+    let mut ticks = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        let Some((_, record)) = line.rsplit_once(TICK_MARKER) else {
+            continue;
+        };
+        let mut fields = record.split_whitespace();
+        let (Some(read), Some(white), Some(black), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(TickLogError::Malformed { line: line_no });
+        };
+        let malformed = |_| TickLogError::Malformed { line: line_no };
+        let parse = |field: &str| {
+            u64::from_str_radix(field, 16)
+                .map(Bitboard)
+                .map_err(malformed)
+        };
+        let positions = ByColor {
+            white: parse(white)?,
+            black: parse(black)?,
+        };
+        if (positions.white & positions.black).any() {
+            return Err(TickLogError::Overlapping { line: line_no });
+        }
+        ticks.push(Tick {
+            read: read.parse().map_err(malformed)?,
+            positions,
+        });
+    }
+    Ok(ticks)
+}
+
+/// Replay readings through a human-vs-human game using `matcher` and
+/// return the moves detected, in UCI.
+///
+/// Each reading is fed once per sensor read it was held for. Readings
+/// before the starting position is first seen are skipped, like the board
+/// does while waiting for pieces.
+pub fn replay(ticks: &[Tick], matcher: MatcherKind) -> Vec<String> {
+    let start = Chess::default();
+    let initial = ByColor {
+        white: start.board().by_color(Color::White),
+        black: start.board().by_color(Color::Black),
+    };
+    let Some(first) = ticks.iter().position(|t| t.positions == initial) else {
+        return Vec::new();
+    };
+    let mut session = GameSession::new(
+        Box::new(HumanPlayer::with_matcher(matcher.build(initial))),
+        Box::new(HumanPlayer::with_matcher(matcher.build(initial))),
+    );
+    let ticks = &ticks[first..];
+    for (i, tick) in ticks.iter().enumerate() {
+        let held = ticks
+            .get(i + 1)
+            .map_or(FINAL_HOLD_READS, |next| next.read.saturating_sub(tick.read))
+            .max(1);
+        for _ in 0..held {
+            session.tick(tick.positions);
+        }
+    }
+    session
+        .moves()
+        .iter()
+        .map(|mv| mv.to_uci(CastlingMode::Standard).to_string())
+        .collect()
+}
+
+/// First ply where two move lists differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveDiff {
+    /// Zero-based ply index.
+    pub ply: usize,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+/// Compare detected moves against the expected ones.
+pub fn diff_moves(expected: &[String], actual: &[String]) -> Option<MoveDiff> {
+    let len = expected.len().max(actual.len());
+    (0..len)
+        .find(|&ply| expected.get(ply) != actual.get(ply))
+        .map(|ply| MoveDiff {
+            ply,
+            expected: expected.get(ply).cloned(),
+            actual: actual.get(ply).cloned(),
+        })
+}
+
+/// Logs each changed reading of the wrapped sensor as a tick line.
+#[derive(Debug)]
+pub struct TickLogger<S> {
+    inner: S,
+    last: Option<ByColor<Bitboard>>,
+    reads: u64,
+}
+
+impl<S> TickLogger<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            last: None,
+            reads: 0,
+        }
+    }
+}
+
+impl<S: PieceSensor> PieceSensor for TickLogger<S> {
+    type Error = S::Error;
+
+    fn read_positions(&mut self) -> Result<ByColor<Bitboard>, Self::Error> {
+        let positions = self.inner.read_positions()?;
+        let read = self.reads;
+        self.reads += 1;
+        if self.last != Some(positions) {
+            log::info!("{}", format_tick(Tick { read, positions }));
+            self.last = Some(positions);
+        }
+        Ok(positions)
+    }
+
+    fn read_millivolts(&mut self) -> Option<Result<[u16; 64], Self::Error>> {
+        self.inner.read_millivolts()
+    }
+
+    fn calibrate(&mut self, calibration: Calibration) {
+        self.inner.calibrate(calibration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::Square;
+    use shakmaty::uci::UciMove;
+
+    fn reading(position: &Chess) -> ByColor<Bitboard> {
+        let board = position.board();
+        ByColor {
+            white: board.by_color(Color::White),
+            black: board.by_color(Color::Black),
+        }
+    }
+
+    fn after(ucis: &[&str]) -> ByColor<Bitboard> {
+        let mut pos = Chess::default();
+        for uci in ucis {
+            let mv = uci.parse::<UciMove>().unwrap().to_move(&pos).unwrap();
+            pos.play_unchecked(mv);
+        }
+        reading(&pos)
+    }
+
+    /// One tick per reading, each held for `held` sensor reads.
+    fn ticks(readings: &[ByColor<Bitboard>], held: u64) -> Vec<Tick> {
+        readings
+            .iter()
+            .enumerate()
+            .map(|(i, &positions)| Tick {
+                read: i as u64 * held,
+                positions,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn serial_capture_round_trips() {
+        let tick = Tick {
+            read: 42,
+            positions: reading(&Chess::default()),
+        };
+        let log = format!(
+            "I (1200) app: Starting position detected\n\
+             I (1250) unnamed_chess_project::tick_log: {}\n",
+            format_tick(tick)
+        );
+
+        assert_eq!(parse_log(&log), Ok(vec![tick]));
+    }
+
+    #[test]
+    fn malformed_and_overlapping_ticks_are_rejected() {
+        assert_eq!(
+            parse_log("ok\ntick 1 12zz 0"),
+            Err(TickLogError::Malformed { line: 2 })
+        );
+        assert_eq!(
+            parse_log("tick ffff 0"),
+            Err(TickLogError::Malformed { line: 1 })
+        );
+        let both = format_tick(Tick {
+            read: 0,
+            positions: ByColor {
+                white: Bitboard::from(Square::E4),
+                black: Bitboard::from(Square::E4),
+            },
+        });
+        assert_eq!(parse_log(&both), Err(TickLogError::Overlapping { line: 1 }));
+    }
+
+    #[test]
+    fn replay_detects_moves_after_setup() {
+        let mut lifted = after(&["e2e4"]);
+        lifted.black.discard(Square::E7);
+        let readings = [
+            ByColor::default(),
+            after(&[]),
+            after(&["e2e4"]),
+            lifted,
+            after(&["e2e4", "e7e5"]),
+        ];
+
+        for matcher in [MatcherKind::Strict, MatcherKind::Settling] {
+            assert_eq!(replay(&ticks(&readings, 5), matcher), ["e2e4", "e7e5"]);
+        }
+    }
+
+    #[test]
+    fn matchers_can_be_compared_on_the_same_log() {
+        // The e-pawn touches e3 for a single read on its way to e4.
+        let readings = [after(&[]), after(&["e2e3"]), after(&["e2e4"])];
+        let log = ticks(&readings, 1);
+
+        assert_eq!(replay(&log, MatcherKind::Strict), ["e2e3"]);
+        assert_eq!(replay(&log, MatcherKind::Settling), ["e2e4"]);
+    }
+
+    #[test]
+    fn diff_reports_first_divergence() {
+        let expected = vec!["e2e4".to_string(), "e7e5".to_string()];
+        let actual = vec!["e2e4".to_string()];
+
+        assert_eq!(diff_moves(&expected, &expected), None);
+        assert_eq!(
+            diff_moves(&expected, &actual),
+            Some(MoveDiff {
+                ply: 1,
+                expected: Some("e7e5".to_string()),
+                actual: None,
+            })
+        );
+    }
+}
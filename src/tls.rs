@@ -0,0 +1,118 @@
+//! Certificate problems of outbound TLS connections.
+//!
+//! Online backends (Lichess, telemetry) are only reached over TLS with the
+//! root certificates bundled into the firmware, or the one configured for a
+//! custom relay (see `esp32::tls`). When the handshake fails on the
+//! certificate, mbedTLS reports why as verification flags, which
+//! `esp32::tls::connect` turns into a [`CertificateError`]. It names the
+//! problem for the log, and the Lichess or relay player that hit it shows
+//! it on the board ([`crate::player::Player::error_feedback`]), since a
+//! wrong clock or a captive portal is something the user can fix:
+//!
+//! | problem       | lit square |
+//! |---------------|------------|
+//! | expired       | a1         |
+//! | not yet valid | b1         |
+//! | untrusted     | c1         |
+//! | wrong host    | d1         |
+//! | other         | e1         |
+//!
+//! together with the failure status colour.
+
+use shakmaty::Square;
+
+use crate::feedback::{BoardFeedback, SquareFeedback, StatusKind};
+
+// mbedTLS `MBEDTLS_X509_BADCERT_*` verification flags.
+const BADCERT_EXPIRED: u32 = 0x01;
+const BADCERT_CN_MISMATCH: u32 = 0x04;
+const BADCERT_NOT_TRUSTED: u32 = 0x08;
+const BADCERT_FUTURE: u32 = 0x0200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum CertificateError {
+    /// Often a board clock that was never set rather than an old server
+    /// certificate.
+    #[error("server certificate expired (is the board's clock set?)")]
+    Expired,
+    #[error("server certificate not yet valid (is the board's clock set?)")]
+    NotYetValid,
+    /// Signed by a root that is not pinned, e.g. a captive portal or an
+    /// intercepting proxy.
+    #[error("server certificate not signed by a pinned root")]
+    Untrusted,
+    #[error("server certificate is for another host")]
+    WrongHost,
+    #[error("server certificate rejected (mbedTLS flags 0x{0:x})")]
+    Other(u32),
+}
+
+impl CertificateError {
+    /// The problem behind mbedTLS verification `flags`, or `None` if the
+    /// certificate verified.
+    ///
+    /// Several flags may be set at once; the one the user is most likely
+    /// to fix wins.
+    pub fn from_verify_flags(flags: u32) -> Option<Self> {
+        if flags == 0 {
+            None
+        } else if flags & BADCERT_EXPIRED != 0 {
+            Some(Self::Expired)
+        } else if flags & BADCERT_FUTURE != 0 {
+            Some(Self::NotYetValid)
+        } else if flags & BADCERT_NOT_TRUSTED != 0 {
+            Some(Self::Untrusted)
+        } else if flags & BADCERT_CN_MISMATCH != 0 {
+            Some(Self::WrongHost)
+        } else {
+            Some(Self::Other(flags))
+        }
+    }
+
+    /// The problem shown on the board (see the module docs).
+    pub fn feedback(&self) -> BoardFeedback {
+        let square = match self {
+            Self::Expired => Square::A1,
+            Self::NotYetValid => Square::B1,
+            Self::Untrusted => Square::C1,
+            Self::WrongHost => Square::D1,
+            Self::Other(_) => Square::E1,
+        };
+        let mut fb = BoardFeedback::with_status(StatusKind::Failure);
+        fb.set(square, SquareFeedback::Capture);
+        fb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_flags_name_the_fixable_problem() {
+        assert_eq!(CertificateError::from_verify_flags(0), None);
+        assert_eq!(
+            CertificateError::from_verify_flags(BADCERT_NOT_TRUSTED | BADCERT_EXPIRED),
+            Some(CertificateError::Expired)
+        );
+        assert_eq!(
+            CertificateError::from_verify_flags(BADCERT_CN_MISMATCH),
+            Some(CertificateError::WrongHost)
+        );
+        assert_eq!(
+            CertificateError::from_verify_flags(0x0100),
+            Some(CertificateError::Other(0x0100))
+        );
+    }
+
+    #[test]
+    fn each_problem_lights_its_square_with_failure_status() {
+        let fb = CertificateError::Untrusted.feedback();
+
+        assert_eq!(fb.status(), Some(StatusKind::Failure));
+        assert_eq!(
+            fb.squares().collect::<Vec<_>>(),
+            [(Square::C1, SquareFeedback::Capture)]
+        );
+    }
+}
@@ -0,0 +1,1062 @@
+//! Club tournaments played on one board.
+//!
+//! A [`Tournament`] pairs up to [`MAX_PLAYERS`] players either as a round
+//! robin, where everyone meets everyone once (Berger tables), or as a
+//! Swiss over a fixed number of rounds, where players on equal scores
+//! meet and nobody meets twice. The games are played one at a time:
+//! [`Tournament::next_game`] is the pairing the board announces (see
+//! [`Tournament::announcement`]), and [`crate::app::BoardApp`] records
+//! the result of each finished game between two players on the board.
+//! A Swiss round is paired once the one before it is complete.
+//!
+//! [`Tournament::standings`] ranks the players by points, then by
+//! Sonneborn-Berger in a round robin or Buchholz in a Swiss;
+//! [`Tournament::crosstable`] is the plain-text table to print or post
+//! when it is over.
+//!
+//! Tournaments are stored as short text, one `key value` pair per line,
+//! every game by round with its seeds (counting from 0) and result:
+//!
+//! ```text
+//! version 1
+//! format swiss 3
+//! player Alice
+//! player Bob
+//! player Carol
+//! game 1 0 1 1-0
+//! game 1 2 bye
+//! game 2 2 0 -
+//! ```
+
+use std::cmp::Reverse;
+use std::fmt::Write as _;
+use std::io;
+
+use shakmaty::{Color, Square};
+
+use crate::board_api::GameStatus;
+use crate::feedback::{BoardFeedback, SquareFeedback};
+
+/// The most players a tournament takes: one per square of a side's two
+/// home ranks, so [`Tournament::announcement`] can show every seed.
+pub const MAX_PLAYERS: usize = 16;
+
+/// Longest player name, in characters.
+pub const MAX_NAME_LEN: usize = 20;
+
+const VERSION: &str = "1";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TournamentError {
+    #[error("a tournament needs 2 to {MAX_PLAYERS} players, not {0}")]
+    PlayerCount(usize),
+    #[error("player names must be 1 to {MAX_NAME_LEN} characters on one line, not {0:?}")]
+    Name(String),
+    #[error("{0} is entered twice")]
+    Duplicate(String),
+    #[error("a Swiss for these players takes 1 to {0} rounds")]
+    Rounds(u8),
+    #[error("format must be round-robin or swiss ROUNDS, not {0:?}")]
+    Format(String),
+    #[error("the tournament is over")]
+    Finished,
+    #[error("unsupported tournament version {0:?}")]
+    Version(String),
+    #[error("malformed {0} line")]
+    Malformed(&'static str),
+    #[error("stored tournament has no {0} line")]
+    Missing(&'static str),
+}
+
+/// Where the tournament is kept between power cycles.
+pub trait TournamentStore {
+    /// The stored tournament, if any. One that cannot be read is logged
+    /// and treated as absent.
+    fn load(&self) -> Option<Tournament>;
+
+    fn save(&mut self, tournament: &Tournament) -> io::Result<()>;
+}
+
+/// How the players are paired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    RoundRobin,
+    Swiss { rounds: u8 },
+}
+
+impl Format {
+    /// `round-robin` or `swiss ROUNDS`, as stored and typed on the config
+    /// page.
+    pub fn parse(text: &str) -> Result<Self, TournamentError> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match words.as_slice() {
+            ["round-robin"] => Ok(Format::RoundRobin),
+            ["swiss", rounds] => match rounds.parse() {
+                Ok(rounds) => Ok(Format::Swiss { rounds }),
+                Err(_) => Err(TournamentError::Format(text.to_string())),
+            },
+            _ => Err(TournamentError::Format(text.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::RoundRobin => write!(f, "round-robin"),
+            Format::Swiss { rounds } => write!(f, "swiss {rounds}"),
+        }
+    }
+}
+
+/// How a tournament game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    Draw,
+    BlackWins,
+}
+
+impl GameResult {
+    /// The result of a game that ended with `status`; `None` while it is
+    /// on or if it was abandoned, which leaves it to be played again.
+    pub fn from_status(status: &GameStatus) -> Option<Self> {
+        let winner = match *status {
+            GameStatus::Checkmate { loser } | GameStatus::Timeout { loser } => loser.other(),
+            GameStatus::Resigned { color } => color.other(),
+            GameStatus::Stalemate | GameStatus::Draw => return Some(GameResult::Draw),
+            GameStatus::Idle
+            | GameStatus::AwaitingPieces
+            | GameStatus::InProgress
+            | GameStatus::Aborted => return None,
+        };
+        Some(match winner {
+            Color::White => GameResult::WhiteWins,
+            Color::Black => GameResult::BlackWins,
+        })
+    }
+
+    /// What the game scored for `color`, in half points.
+    fn half_points(self, color: Color) -> u16 {
+        match (self, color) {
+            (GameResult::Draw, _) => 1,
+            (GameResult::WhiteWins, Color::White) | (GameResult::BlackWins, Color::Black) => 2,
+            _ => 0,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::Draw => "1/2",
+            GameResult::BlackWins => "0-1",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "1-0" => Some(GameResult::WhiteWins),
+            "1/2" => Some(GameResult::Draw),
+            "0-1" => Some(GameResult::BlackWins),
+            _ => None,
+        }
+    }
+}
+
+/// One game of a round, by seed; without `black` it is a bye for
+/// `white`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pairing {
+    /// Counting from 1.
+    pub round: u8,
+    pub white: usize,
+    pub black: Option<usize>,
+    pub result: Option<GameResult>,
+}
+
+impl Pairing {
+    /// `player`'s color and opponent in this game, if they play in it.
+    fn seat(&self, player: usize) -> Option<(Color, usize)> {
+        let black = self.black?;
+        if self.white == player {
+            Some((Color::White, black))
+        } else if black == player {
+            Some((Color::Black, self.white))
+        } else {
+            None
+        }
+    }
+}
+
+/// A player's place in [`Tournament::standings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Standing {
+    /// Seed, counting from 0.
+    pub player: usize,
+    /// Points, in halves.
+    pub half_points: u16,
+    pub wins: u8,
+    pub draws: u8,
+    pub losses: u8,
+    /// Sonneborn-Berger in a round robin, Buchholz in a Swiss, in
+    /// quarter points.
+    pub tiebreak: u32,
+}
+
+/// Players, their pairings and the results so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tournament {
+    format: Format,
+    players: Vec<String>,
+    /// Every pairing made, round by round.
+    games: Vec<Pairing>,
+}
+
+impl Tournament {
+    /// A tournament for `players`, in seed order, with its first round
+    /// paired; a round robin is paired in full.
+    pub fn new(players: Vec<String>, format: Format) -> Result<Self, TournamentError> {
+        check_players(&players)?;
+        let mut tournament = Self {
+            format,
+            players,
+            games: Vec::new(),
+        };
+        match format {
+            Format::RoundRobin => tournament.pair_round_robin(),
+            Format::Swiss { rounds } => {
+                let max = tournament.max_swiss_rounds();
+                if rounds == 0 || rounds > max {
+                    return Err(TournamentError::Rounds(max));
+                }
+                tournament.pair_swiss_round(1);
+            }
+        }
+        Ok(tournament)
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Names by seed.
+    pub fn players(&self) -> &[String] {
+        &self.players
+    }
+
+    /// The pairings made so far, round by round.
+    pub fn games(&self) -> &[Pairing] {
+        &self.games
+    }
+
+    /// The game to play next, or `None` once the tournament is over.
+    pub fn next_game(&self) -> Option<&Pairing> {
+        self.games
+            .iter()
+            .find(|game| game.black.is_some() && game.result.is_none())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_game().is_none()
+    }
+
+    /// Record the result of [`Self::next_game`], pairing the next Swiss
+    /// round once this one is complete.
+    pub fn record(&mut self, result: GameResult) -> Result<(), TournamentError> {
+        let game = self
+            .games
+            .iter_mut()
+            .find(|game| game.black.is_some() && game.result.is_none())
+            .ok_or(TournamentError::Finished)?;
+        game.result = Some(result);
+        let round = game.round;
+        if let Format::Swiss { rounds } = self.format
+            && round < rounds
+            && self.is_finished()
+        {
+            self.pair_swiss_round(round + 1);
+        }
+        Ok(())
+    }
+
+    /// `round 2: Alice (1) - Bob (4)`, seeds counting from 1.
+    pub fn describe(&self, pairing: &Pairing) -> String {
+        let name = |seed: usize| format!("{} ({})", self.players[seed], seed + 1);
+        match pairing.black {
+            Some(black) => format!(
+                "round {}: {} - {}",
+                pairing.round,
+                name(pairing.white),
+                name(black)
+            ),
+            None => format!("round {}: bye for {}", pairing.round, name(pairing.white)),
+        }
+    }
+
+    /// The next pairing on the LEDs: each player's seed number lit on
+    /// their own two home ranks, counted from their left along the first
+    /// rank, then the second, so seed 1 playing White lights a1 and seed
+    /// 10 playing Black lights g7.
+    pub fn announcement(&self) -> Option<BoardFeedback> {
+        let game = self.next_game()?;
+        let black = game.black?;
+        let mut feedback = BoardFeedback::new();
+        feedback.set(
+            seat_square(game.white, Color::White),
+            SquareFeedback::Destination,
+        );
+        feedback.set(
+            seat_square(black, Color::Black),
+            SquareFeedback::Destination,
+        );
+        Some(feedback)
+    }
+
+    /// Every player, best first.
+    pub fn standings(&self) -> Vec<Standing> {
+        let scores: Vec<u16> = (0..self.players.len())
+            .map(|player| self.half_points(player))
+            .collect();
+        let mut standings: Vec<Standing> = (0..self.players.len())
+            .map(|player| {
+                let mut standing = Standing {
+                    player,
+                    half_points: scores[player],
+                    wins: 0,
+                    draws: 0,
+                    losses: 0,
+                    tiebreak: 0,
+                };
+                for game in &self.games {
+                    let (Some((color, opponent)), Some(result)) = (game.seat(player), game.result)
+                    else {
+                        continue;
+                    };
+                    let scored = result.half_points(color);
+                    match scored {
+                        2 => standing.wins += 1,
+                        1 => standing.draws += 1,
+                        _ => standing.losses += 1,
+                    }
+                    standing.tiebreak += match self.format {
+                        Format::RoundRobin => u32::from(scored) * u32::from(scores[opponent]),
+                        Format::Swiss { .. } => 2 * u32::from(scores[opponent]),
+                    };
+                }
+                standing
+            })
+            .collect();
+        standings.sort_by_key(|s| (Reverse(s.half_points), Reverse(s.tiebreak), s.player));
+        standings
+    }
+
+    /// The standings as a plain-text crosstable. A round robin has a
+    /// column per opponent by rank, a Swiss a column per round with the
+    /// opponent's rank, color and result (`4w+`).
+    pub fn crosstable(&self) -> String {
+        let standings = self.standings();
+        let mut rank = vec![0; self.players.len()];
+        for (i, standing) in standings.iter().enumerate() {
+            rank[standing.player] = i + 1;
+        }
+        let played = self
+            .games
+            .iter()
+            .filter(|g| g.black.is_some() && g.result.is_some())
+            .count();
+        let total = self.games.iter().filter(|g| g.black.is_some()).count();
+        let (mut out, columns, tiebreak) = match self.format {
+            Format::RoundRobin => (
+                format!(
+                    "Round robin, {} players, {played} of {total} games played\n\n",
+                    self.players.len()
+                ),
+                (1..=self.players.len())
+                    .map(|r| format!("{r:>3}"))
+                    .collect::<String>(),
+                "SB",
+            ),
+            Format::Swiss { rounds } => (
+                format!(
+                    "Swiss, {} players, {rounds} rounds, {played} of {total} games played\n\n",
+                    self.players.len()
+                ),
+                (1..=rounds)
+                    .map(|r| format!(" R{r:<4}"))
+                    .collect::<String>(),
+                "Buch",
+            ),
+        };
+        let _ = writeln!(
+            out,
+            " #  {:<MAX_NAME_LEN$}{columns}   Pts  {tiebreak:>5}",
+            "Name"
+        );
+        for (i, standing) in standings.iter().enumerate() {
+            let player = standing.player;
+            let cells: String = match self.format {
+                Format::RoundRobin => standings
+                    .iter()
+                    .map(|other| {
+                        let cell = if other.player == player {
+                            "*"
+                        } else {
+                            self.games
+                                .iter()
+                                .filter(|g| g.seat(player).is_some_and(|(_, o)| o == other.player))
+                                .find_map(|g| {
+                                    let (color, _) = g.seat(player)?;
+                                    Some(half_point_name(g.result?.half_points(color)))
+                                })
+                                .unwrap_or(".")
+                        };
+                        format!("{cell:>3}")
+                    })
+                    .collect(),
+                Format::Swiss { rounds } => (1..=rounds)
+                    .map(|round| {
+                        let game = self.games.iter().find(|g| {
+                            g.round == round && (g.white == player || g.black == Some(player))
+                        });
+                        let cell = match game.map(|g| (g, g.seat(player))) {
+                            None => ".".to_string(),
+                            Some((_, None)) => "bye".to_string(),
+                            Some((g, Some((color, opponent)))) => {
+                                let result = match g.result.map(|r| r.half_points(color)) {
+                                    Some(2) => "+",
+                                    Some(1) => "=",
+                                    Some(_) => "-",
+                                    None => "",
+                                };
+                                let color = color.fold_wb('w', 'b');
+                                format!("{}{color}{result}", rank[opponent])
+                            }
+                        };
+                        format!(" {cell:<5}")
+                    })
+                    .collect(),
+            };
+            let _ = writeln!(
+                out,
+                "{:>2}  {:<MAX_NAME_LEN$}{cells}  {:>4}  {:>5}",
+                i + 1,
+                self.players[player],
+                points(standing.half_points),
+                quarters(standing.tiebreak),
+            );
+        }
+        out
+    }
+
+    pub fn encode(&self) -> String {
+        let mut text = format!("version {VERSION}\nformat {}\n", self.format);
+        for player in &self.players {
+            let _ = writeln!(text, "player {player}");
+        }
+        for game in &self.games {
+            let _ = match game.black {
+                Some(black) => writeln!(
+                    text,
+                    "game {} {} {black} {}",
+                    game.round,
+                    game.white,
+                    game.result.map_or("-", GameResult::name)
+                ),
+                None => writeln!(text, "game {} {} bye", game.round, game.white),
+            };
+        }
+        text
+    }
+
+    pub fn decode(text: &str) -> Result<Self, TournamentError> {
+        let values = |key: &'static str| {
+            text.lines()
+                .filter_map(move |line| match line.split_once(' ') {
+                    Some((k, value)) if k == key => Some(value),
+                    _ => None,
+                })
+        };
+        let version = values("version")
+            .next()
+            .ok_or(TournamentError::Missing("version"))?;
+        if version != VERSION {
+            return Err(TournamentError::Version(version.to_string()));
+        }
+        let format = values("format")
+            .next()
+            .ok_or(TournamentError::Missing("format"))?;
+        let format = Format::parse(format).map_err(|_| TournamentError::Malformed("format"))?;
+        let players: Vec<String> = values("player").map(str::to_string).collect();
+        check_players(&players)?;
+        let seed = |word: &str| word.parse().ok().filter(|&seed| seed < players.len());
+        let mut games = Vec::new();
+        for line in values("game") {
+            let words: Vec<&str> = line.split(' ').collect();
+            let game = match words.as_slice() {
+                [round, white, "bye"] => {
+                    round
+                        .parse()
+                        .ok()
+                        .zip(seed(white))
+                        .map(|(round, white)| Pairing {
+                            round,
+                            white,
+                            black: None,
+                            result: None,
+                        })
+                }
+                [round, white, black, result] => {
+                    let result = match *result {
+                        "-" => Some(None),
+                        result => GameResult::from_name(result).map(Some),
+                    };
+                    match (round.parse().ok(), seed(white), seed(black), result) {
+                        (Some(round), Some(white), Some(black), Some(result)) if white != black => {
+                            Some(Pairing {
+                                round,
+                                white,
+                                black: Some(black),
+                                result,
+                            })
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            games.push(game.ok_or(TournamentError::Malformed("game"))?);
+        }
+        Ok(Self {
+            format,
+            players,
+            games,
+        })
+    }
+
+    /// Score of `player` in half points; a Swiss bye scores a point.
+    fn half_points(&self, player: usize) -> u16 {
+        self.games
+            .iter()
+            .map(|game| match game.seat(player) {
+                Some((color, _)) => game.result.map_or(0, |r| r.half_points(color)),
+                None if game.white == player && matches!(self.format, Format::Swiss { .. }) => 2,
+                None => 0,
+            })
+            .sum()
+    }
+
+    /// Rounds before some players would have to meet again.
+    fn max_swiss_rounds(&self) -> u8 {
+        let even = self.players.len().next_multiple_of(2);
+        u8::try_from(even - 1).unwrap_or(u8::MAX)
+    }
+
+    /// Every round at once by Berger tables: the last seat stays put while
+    /// the others rotate past it. With an odd number of players, whoever
+    /// faces the empty last seat has a bye.
+    fn pair_round_robin(&mut self) {
+        let count = self.players.len();
+        let seats = count.next_multiple_of(2);
+        let fixed = seats - 1;
+        for round in 0..fixed {
+            let mut pairs = vec![if round % 2 == 0 {
+                (round, fixed)
+            } else {
+                (fixed, round)
+            }];
+            for i in 1..seats / 2 {
+                pairs.push(((round + i) % fixed, (round + fixed - i) % fixed));
+            }
+            for (white, black) in pairs {
+                let round = u8::try_from(round + 1).unwrap_or(u8::MAX);
+                let pairing = match (white < count, black < count) {
+                    (true, true) => Pairing {
+                        round,
+                        white,
+                        black: Some(black),
+                        result: None,
+                    },
+                    (true, false) | (false, true) => Pairing {
+                        round,
+                        white: white.min(black),
+                        black: None,
+                        result: None,
+                    },
+                    (false, false) => continue,
+                };
+                self.games.push(pairing);
+            }
+        }
+    }
+
+    /// Pair `round` of a Swiss: by score then seed, each player against
+    /// the best-placed opponent they have not met that still lets everyone
+    /// below pair up. With an odd number of players, the lowest placed
+    /// without a bye yet sits out.
+    fn pair_swiss_round(&mut self, round: u8) {
+        let mut pool: Vec<usize> = (0..self.players.len()).collect();
+        pool.sort_by_key(|&p| (Reverse(self.half_points(p)), p));
+        if pool.len() % 2 == 1 {
+            let bye = pool
+                .iter()
+                .rposition(|&p| !self.games.iter().any(|g| g.white == p && g.black.is_none()))
+                .unwrap_or(pool.len() - 1);
+            let white = pool.remove(bye);
+            self.games.push(Pairing {
+                round,
+                white,
+                black: None,
+                result: None,
+            });
+        }
+        let met = |a: usize, b: usize| {
+            self.games
+                .iter()
+                .any(|g| g.seat(a).is_some_and(|(_, o)| o == b))
+        };
+        let pairs = pair_off(&pool, &|a, b| !met(a, b))
+            // Only when every pairing is a rematch; with the round limit
+            // of `new` that cannot happen, but a decoded table may ask.
+            .or_else(|| pair_off(&pool, &|_, _| true))
+            .unwrap_or_default();
+        for (higher, lower) in pairs {
+            let (white, black) = self.colors(higher, lower);
+            self.games.push(Pairing {
+                round,
+                white,
+                black: Some(black),
+                result: None,
+            });
+        }
+    }
+
+    /// White goes to whoever has had it less often, then to whoever had
+    /// Black last, then to the higher placed.
+    fn colors(&self, higher: usize, lower: usize) -> (usize, usize) {
+        let balance = |player: usize| -> i32 {
+            self.games
+                .iter()
+                .filter_map(|g| g.seat(player))
+                .map(|(color, _)| color.fold_wb(1, -1))
+                .sum()
+        };
+        let last = |player: usize| {
+            self.games
+                .iter()
+                .rev()
+                .find_map(|g| g.seat(player))
+                .map(|(c, _)| c)
+        };
+        match balance(higher).cmp(&balance(lower)) {
+            std::cmp::Ordering::Less => (higher, lower),
+            std::cmp::Ordering::Greater => (lower, higher),
+            std::cmp::Ordering::Equal if last(higher) == Some(Color::White) => (lower, higher),
+            std::cmp::Ordering::Equal => (higher, lower),
+        }
+    }
+}
+
+/// `pool`, in placing order, split into pairs; `None` if `allowed` rules
+/// that out.
+fn pair_off(
+    pool: &[usize],
+    allowed: &impl Fn(usize, usize) -> bool,
+) -> Option<Vec<(usize, usize)>> {
+    let Some((&first, rest)) = pool.split_first() else {
+        return Some(Vec::new());
+    };
+    for (i, &opponent) in rest.iter().enumerate() {
+        if !allowed(first, opponent) {
+            continue;
+        }
+        let mut others = rest.to_vec();
+        others.remove(i);
+        if let Some(mut pairs) = pair_off(&others, allowed) {
+            pairs.insert(0, (first, opponent));
+            return Some(pairs);
+        }
+    }
+    None
+}
+
+fn check_players(players: &[String]) -> Result<(), TournamentError> {
+    if !(2..=MAX_PLAYERS).contains(&players.len()) {
+        return Err(TournamentError::PlayerCount(players.len()));
+    }
+    for (i, name) in players.iter().enumerate() {
+        let length = name.chars().count();
+        if length == 0
+            || length > MAX_NAME_LEN
+            || name.trim() != name
+            || name.chars().any(char::is_control)
+        {
+            return Err(TournamentError::Name(name.clone()));
+        }
+        if players[..i].contains(name) {
+            return Err(TournamentError::Duplicate(name.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Where seed `seed` lights up for the player of `color`.
+fn seat_square(seed: usize, color: Color) -> Square {
+    let square = Square::new(u32::try_from(seed).unwrap_or(0) % 16);
+    match color {
+        Color::White => square,
+        Color::Black => square.flip_vertical().flip_horizontal(),
+    }
+}
+
+/// `2½`, `½`, `0`.
+fn points(half_points: u16) -> String {
+    match (half_points / 2, half_points % 2) {
+        (0, 1) => "½".to_string(),
+        (whole, 1) => format!("{whole}½"),
+        (whole, _) => whole.to_string(),
+    }
+}
+
+fn half_point_name(half_points: u16) -> &'static str {
+    match half_points {
+        2 => "1",
+        1 => "½",
+        _ => "0",
+    }
+}
+
+/// Quarter points as a decimal: `3.25`, `4`.
+fn quarters(quarters: u32) -> String {
+    let fraction = match quarters % 4 {
+        0 => "",
+        1 => ".25",
+        2 => ".5",
+        _ => ".75",
+    };
+    format!("{}{fraction}", quarters / 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::malformed_inputs;
+
+    fn names(count: usize) -> Vec<String> {
+        [
+            "Alice", "Bob", "Carol", "Dave", "Erin", "Frank", "Grace", "Heidi",
+        ]
+        .iter()
+        .cycle()
+        .take(count)
+        .enumerate()
+        .map(|(i, name)| {
+            if i < 8 {
+                name.to_string()
+            } else {
+                format!("{name} {i}")
+            }
+        })
+        .collect()
+    }
+
+    /// Play every game, White winning unless `result` says otherwise.
+    fn play_out(tournament: &mut Tournament, mut result: impl FnMut(&Pairing) -> GameResult) {
+        while let Some(game) = tournament.next_game() {
+            let outcome = result(game);
+            tournament.record(outcome).unwrap();
+        }
+    }
+
+    #[test]
+    fn a_round_robin_pairs_everyone_once_with_balanced_colors() {
+        for count in 2..=MAX_PLAYERS {
+            let tournament = Tournament::new(names(count), Format::RoundRobin).unwrap();
+            let games: Vec<&Pairing> = tournament
+                .games()
+                .iter()
+                .filter(|g| g.black.is_some())
+                .collect();
+            assert_eq!(games.len(), count * (count - 1) / 2, "{count} players");
+            for a in 0..count {
+                for b in a + 1..count {
+                    let met = games
+                        .iter()
+                        .filter(|g| g.seat(a).is_some_and(|(_, o)| o == b));
+                    assert_eq!(met.count(), 1, "{a} and {b} of {count}");
+                }
+                let whites = games.iter().filter(|g| g.white == a).count();
+                let played = count - 1;
+                assert!(
+                    whites.abs_diff(played - whites) <= 1,
+                    "{a} of {count}: {whites} whites"
+                );
+            }
+            let byes = tournament
+                .games()
+                .iter()
+                .filter(|g| g.black.is_none())
+                .count();
+            assert_eq!(byes, if count % 2 == 1 { count } else { 0 });
+        }
+    }
+
+    #[test]
+    fn round_robin_standings_and_crosstable() {
+        let mut tournament = Tournament::new(names(3), Format::RoundRobin).unwrap();
+        // Alice beats Bob, Bob beats Carol, Carol draws Alice.
+        play_out(&mut tournament, |game| {
+            match (
+                game.white.min(game.black.unwrap()),
+                game.white.max(game.black.unwrap()),
+            ) {
+                (0, 2) => GameResult::Draw,
+                (lower, _) if lower == game.white => GameResult::WhiteWins,
+                _ => GameResult::BlackWins,
+            }
+        });
+
+        let standings = tournament.standings();
+        let order: Vec<(usize, u16)> = standings
+            .iter()
+            .map(|s| (s.player, s.half_points))
+            .collect();
+        assert_eq!(order, [(0, 3), (1, 2), (2, 1)]);
+        // Alice: Bob's 1 for the win, half of Carol's ½ for the draw.
+        assert_eq!(standings[0].tiebreak, 4 + 1);
+        assert_eq!(
+            (standings[0].wins, standings[0].draws, standings[0].losses),
+            (1, 1, 0)
+        );
+
+        let table = tournament.crosstable();
+        assert!(
+            table.starts_with("Round robin, 3 players, 3 of 3 games played\n"),
+            "{table}"
+        );
+        assert!(
+            table.contains("\n 1  Alice                 *  1  ½    1½   1.25\n"),
+            "{table}"
+        );
+        assert!(
+            table.contains("\n 3  Carol                 ½  0  *     ½   0.75\n"),
+            "{table}"
+        );
+        assert_eq!(
+            tournament.record(GameResult::Draw),
+            Err(TournamentError::Finished)
+        );
+    }
+
+    #[test]
+    fn a_swiss_pairs_equal_scores_without_rematches() {
+        let mut tournament = Tournament::new(names(5), Format::Swiss { rounds: 3 }).unwrap();
+        let first: Vec<(Option<usize>, usize)> = tournament
+            .games()
+            .iter()
+            .map(|g| (g.black, g.white))
+            .collect();
+        assert_eq!(first.len(), 3, "two games and a bye");
+        assert!(
+            first.contains(&(None, 4)),
+            "the lowest seed sits out: {first:?}"
+        );
+
+        // The later seed always wins.
+        play_out(&mut tournament, |game| {
+            if game.white > game.black.unwrap() {
+                GameResult::WhiteWins
+            } else {
+                GameResult::BlackWins
+            }
+        });
+
+        let games = tournament.games();
+        assert_eq!(games.iter().map(|g| g.round).max(), Some(3));
+        for (i, a) in games.iter().enumerate() {
+            for b in &games[i + 1..] {
+                let pair = |g: &Pairing| {
+                    g.black
+                        .map(|black| (g.white.min(black), g.white.max(black)))
+                };
+                assert!(
+                    pair(a).is_none() || pair(a) != pair(b),
+                    "rematch: {a:?} {b:?}"
+                );
+            }
+        }
+        let byes: Vec<usize> = games
+            .iter()
+            .filter(|g| g.black.is_none())
+            .map(|g| g.white)
+            .collect();
+        assert_eq!(byes.len(), 3);
+        assert!(
+            byes.iter()
+                .all(|p| byes.iter().filter(|q| *q == p).count() == 1),
+            "{byes:?}"
+        );
+        // Round 2 pairs the winners of round 1.
+        let round_two = games
+            .iter()
+            .find(|g| g.round == 2 && g.black.is_some())
+            .unwrap();
+        let winners: Vec<usize> = games
+            .iter()
+            .filter(|g| g.round == 1 && g.black.is_some())
+            .map(|g| g.white.max(g.black.unwrap()))
+            .collect();
+        assert!(winners.contains(&round_two.white) && winners.contains(&round_two.black.unwrap()));
+
+        let table = tournament.crosstable();
+        assert!(
+            table.starts_with("Swiss, 5 players, 3 rounds, 6 of 6 games played\n"),
+            "{table}"
+        );
+        assert!(table.contains("bye"), "{table}");
+    }
+
+    #[test]
+    fn the_next_pairing_lights_each_players_seed_on_their_side() {
+        let tournament = Tournament::new(names(12), Format::RoundRobin).unwrap();
+        let game = *tournament.next_game().unwrap();
+        let lit: Vec<Square> = tournament
+            .announcement()
+            .unwrap()
+            .squares()
+            .map(|(s, _)| s)
+            .collect();
+
+        assert!(lit.contains(&seat_square(game.white, Color::White)));
+        assert_eq!(seat_square(0, Color::White), Square::A1);
+        assert_eq!(seat_square(9, Color::Black), Square::G7);
+        assert_eq!(
+            tournament.describe(&game),
+            format!(
+                "round 1: {} ({}) - {} ({})",
+                tournament.players()[game.white],
+                game.white + 1,
+                tournament.players()[game.black.unwrap()],
+                game.black.unwrap() + 1
+            )
+        );
+    }
+
+    #[test]
+    fn results_come_from_the_game_status() {
+        let cases = [
+            (
+                GameStatus::Checkmate {
+                    loser: Color::Black,
+                },
+                Some(GameResult::WhiteWins),
+            ),
+            (
+                GameStatus::Resigned {
+                    color: Color::White,
+                },
+                Some(GameResult::BlackWins),
+            ),
+            (
+                GameStatus::Timeout {
+                    loser: Color::White,
+                },
+                Some(GameResult::BlackWins),
+            ),
+            (GameStatus::Stalemate, Some(GameResult::Draw)),
+            (GameStatus::Aborted, None),
+        ];
+        for (status, result) in cases {
+            assert_eq!(GameResult::from_status(&status), result, "{status:?}");
+        }
+    }
+
+    #[test]
+    fn bad_entries_are_rejected() {
+        let cases = [
+            (
+                names(1),
+                Format::RoundRobin,
+                TournamentError::PlayerCount(1),
+            ),
+            (
+                names(17),
+                Format::RoundRobin,
+                TournamentError::PlayerCount(17),
+            ),
+            (
+                names(4),
+                Format::Swiss { rounds: 4 },
+                TournamentError::Rounds(3),
+            ),
+            (
+                names(5),
+                Format::Swiss { rounds: 0 },
+                TournamentError::Rounds(5),
+            ),
+            (
+                vec!["Al".into(), "Al".into()],
+                Format::RoundRobin,
+                TournamentError::Duplicate("Al".into()),
+            ),
+            (
+                vec!["Al".into(), "".into()],
+                Format::RoundRobin,
+                TournamentError::Name("".into()),
+            ),
+            (
+                vec!["Al".into(), "B\nC".into()],
+                Format::RoundRobin,
+                TournamentError::Name("B\nC".into()),
+            ),
+        ];
+        for (players, format, error) in cases {
+            assert_eq!(Tournament::new(players, format), Err(error));
+        }
+        assert_eq!(Format::parse("swiss 5"), Ok(Format::Swiss { rounds: 5 }));
+        assert_eq!(
+            Format::parse("knockout"),
+            Err(TournamentError::Format("knockout".into()))
+        );
+    }
+
+    #[test]
+    fn tournaments_round_trip_through_storage() {
+        let mut tournament = Tournament::new(names(3), Format::Swiss { rounds: 2 }).unwrap();
+        tournament.record(GameResult::Draw).unwrap();
+        let text = tournament.encode();
+        assert!(
+            text.starts_with("version 1\nformat swiss 2\nplayer Alice\n"),
+            "{text}"
+        );
+        assert!(text.contains(" 1/2\n"), "{text}");
+        assert!(text.contains(" bye\n"), "{text}");
+
+        let decoded = Tournament::decode(&text).unwrap();
+        assert_eq!(decoded, tournament);
+        assert_eq!(
+            Tournament::decode(&text.replace("version 1", "version 2")),
+            Err(TournamentError::Version("2".into()))
+        );
+        assert_eq!(
+            Tournament::decode(&text.replace(" 1/2", " 2-0")),
+            Err(TournamentError::Malformed("game"))
+        );
+        assert_eq!(
+            Tournament::decode("version 1\nplayer Alice\n"),
+            Err(TournamentError::Missing("format"))
+        );
+    }
+
+    #[test]
+    fn malformed_input_does_not_panic() {
+        let mut tournament = Tournament::new(names(4), Format::Swiss { rounds: 3 }).unwrap();
+        tournament.record(GameResult::WhiteWins).unwrap();
+        let text = tournament.encode();
+        for bytes in malformed_inputs(text.as_bytes(), 256, 200) {
+            if let Ok(decoded) = Tournament::decode(&String::from_utf8_lossy(&bytes)) {
+                let _ = decoded.crosstable();
+                let _ = decoded.announcement();
+            }
+        }
+    }
+}
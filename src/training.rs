@@ -0,0 +1,336 @@
+//! Square coordinate training game.
+//!
+//! The board lights a random empty square and the player places a piece on
+//! it as fast as they can. Each prompt is also reported as a
+//! [`TrainerEvent::Prompt`] so a client can name the square instead of (or
+//! as well as) lighting it, which turns the drill into a coordinate quiz.
+//! After each attempt the result stays lit until the piece is lifted again.
+
+use std::time::Duration;
+
+use shakmaty::{Bitboard, ByColor, Square};
+
+use crate::feedback::{BoardFeedback, SquareFeedback};
+use crate::mode::{GameMode, ModeStatus};
+use crate::rng::XorShift32;
+
+/// Something that happened during an update, for clients and sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainerEvent {
+    /// A new square to find.
+    Prompt { square: Square },
+    /// The piece landed on the prompted square after `time`.
+    Hit { square: Square, time: Duration },
+    /// The piece landed somewhere else.
+    Miss { expected: Square, actual: Square },
+}
+
+/// Running score for a training session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrainerStats {
+    pub attempts: u32,
+    pub hits: u32,
+    /// Consecutive hits, reset by a miss.
+    pub streak: u32,
+    pub best_streak: u32,
+    /// Fastest hit so far.
+    pub best_time: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// No target yet (first reading, or no empty square to ask for).
+    Starting,
+    Prompting {
+        target: Square,
+        since: Duration,
+        /// Occupancy the placement is measured against.
+        baseline: Bitboard,
+    },
+    /// Showing the last result until the placed piece is lifted.
+    Result { target: Square, actual: Square },
+}
+
+/// Drives the coordinate drill from board occupancy.
+#[derive(Debug, Clone)]
+pub struct CoordinateTrainer {
+    rng: XorShift32,
+    phase: Phase,
+    stats: TrainerStats,
+}
+
+impl CoordinateTrainer {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            rng: XorShift32::new(seed),
+            phase: Phase::Starting,
+            stats: TrainerStats::default(),
+        }
+    }
+
+    pub fn stats(&self) -> TrainerStats {
+        self.stats
+    }
+
+    /// The square currently asked for, if any.
+    pub fn target(&self) -> Option<Square> {
+        match self.phase {
+            Phase::Prompting { target, .. } => Some(target),
+            _ => None,
+        }
+    }
+
+    /// Advance the drill with the current board occupancy.
+    pub fn update(&mut self, occupied: Bitboard, now: Duration) -> Option<TrainerEvent> {
+        match self.phase {
+            Phase::Starting => self.prompt(occupied, now, None),
+            Phase::Prompting {
+                target,
+                since,
+                baseline,
+            } => {
+                let placed = occupied & !baseline;
+                let Some(actual) = placed.first() else {
+                    // Lifting pieces (e.g. to pick one up) just moves the baseline.
+                    self.phase = Phase::Prompting {
+                        target,
+                        since,
+                        baseline: occupied,
+                    };
+                    return None;
+                };
+                self.phase = Phase::Result { target, actual };
+                Some(self.score(target, actual, now.saturating_sub(since)))
+            }
+            Phase::Result { target, actual } => {
+                if occupied.contains(actual) {
+                    return None;
+                }
+                self.prompt(occupied, now, Some(target))
+            }
+        }
+    }
+
+    /// Feedback for the current phase: the target to find, or the last result.
+    pub fn feedback(&self) -> BoardFeedback {
+        let mut fb = BoardFeedback::new();
+        match self.phase {
+            Phase::Starting => {}
+            Phase::Prompting { target, .. } => fb.set(target, SquareFeedback::Destination),
+            Phase::Result { target, actual } if target == actual => {
+                fb.set(target, SquareFeedback::Victory);
+            }
+            Phase::Result { target, actual } => {
+                fb.set(actual, SquareFeedback::Check);
+                fb.set(target, SquareFeedback::Destination);
+            }
+        }
+        fb
+    }
+
+    fn prompt(
+        &mut self,
+        occupied: Bitboard,
+        now: Duration,
+        previous: Option<Square>,
+    ) -> Option<TrainerEvent> {
+        let mut candidates = !occupied;
+        if let Some(prev) = previous
+            && candidates.count() > 1
+        {
+            candidates.discard(prev);
+        }
+        let Some(target) = self.rng.square_in(candidates) else {
+            self.phase = Phase::Starting;
+            return None;
+        };
+        self.phase = Phase::Prompting {
+            target,
+            since: now,
+            baseline: occupied,
+        };
+        Some(TrainerEvent::Prompt { square: target })
+    }
+
+    fn score(&mut self, expected: Square, actual: Square, time: Duration) -> TrainerEvent {
+        let stats = &mut self.stats;
+        stats.attempts += 1;
+        if expected != actual {
+            stats.streak = 0;
+            log::info!("Training: {actual} placed, expected {expected}");
+            return TrainerEvent::Miss { expected, actual };
+        }
+        stats.hits += 1;
+        stats.streak += 1;
+        stats.best_streak = stats.best_streak.max(stats.streak);
+        if stats.best_time.is_none_or(|best| time < best) {
+            stats.best_time = Some(time);
+        }
+        log::info!(
+            "Training: {actual} in {}ms (streak {})",
+            time.as_millis(),
+            stats.streak
+        );
+        TrainerEvent::Hit {
+            square: actual,
+            time,
+        }
+    }
+}
+
+impl GameMode for CoordinateTrainer {
+    fn name(&self) -> &'static str {
+        "coordinate training"
+    }
+
+    /// The drill never finishes on its own; it runs until cancelled.
+    fn tick(&mut self, positions: ByColor<Bitboard>, now: Duration) -> ModeStatus {
+        self.update(positions.white | positions.black, now);
+        ModeStatus::Running
+    }
+
+    fn feedback(&self) -> BoardFeedback {
+        CoordinateTrainer::feedback(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::Rank;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    /// Start a trainer on an empty board and return the first target.
+    fn started() -> (CoordinateTrainer, Square) {
+        let mut trainer = CoordinateTrainer::new(1);
+        let Some(TrainerEvent::Prompt { square }) = trainer.update(Bitboard::EMPTY, ms(0)) else {
+            panic!("expected a prompt");
+        };
+        (trainer, square)
+    }
+
+    fn other_than(square: Square) -> Square {
+        if square == Square::A1 {
+            Square::H8
+        } else {
+            Square::A1
+        }
+    }
+
+    #[test]
+    fn prompt_lights_an_empty_square() {
+        let mut trainer = CoordinateTrainer::new(5);
+        let occupied = Bitboard::from_rank(Rank::Eighth);
+        let Some(TrainerEvent::Prompt { square }) = trainer.update(occupied, ms(0)) else {
+            panic!("expected a prompt");
+        };
+        assert!(!occupied.contains(square));
+        assert_eq!(
+            trainer.feedback().get(square),
+            Some(SquareFeedback::Destination)
+        );
+    }
+
+    #[test]
+    fn placing_on_target_scores_hit_with_time() {
+        let (mut trainer, target) = started();
+
+        let event = trainer.update(target.into(), ms(1200));
+
+        assert_eq!(
+            event,
+            Some(TrainerEvent::Hit {
+                square: target,
+                time: ms(1200)
+            })
+        );
+        assert_eq!(trainer.stats().best_time, Some(ms(1200)));
+        assert_eq!(trainer.stats().streak, 1);
+        assert_eq!(
+            trainer.feedback().get(target),
+            Some(SquareFeedback::Victory)
+        );
+    }
+
+    #[test]
+    fn miss_resets_streak_and_shows_both_squares() {
+        let (mut trainer, target) = started();
+        let wrong = other_than(target);
+
+        let event = trainer.update(wrong.into(), ms(800));
+
+        assert_eq!(
+            event,
+            Some(TrainerEvent::Miss {
+                expected: target,
+                actual: wrong
+            })
+        );
+        assert_eq!(trainer.stats().streak, 0);
+        let fb = trainer.feedback();
+        assert_eq!(fb.get(wrong), Some(SquareFeedback::Check));
+        assert_eq!(fb.get(target), Some(SquareFeedback::Destination));
+    }
+
+    #[test]
+    fn next_prompt_waits_for_piece_to_be_lifted() {
+        let (mut trainer, target) = started();
+        trainer.update(target.into(), ms(500));
+
+        assert_eq!(trainer.update(target.into(), ms(600)), None);
+        let Some(TrainerEvent::Prompt { square }) = trainer.update(Bitboard::EMPTY, ms(700)) else {
+            panic!("expected a new prompt");
+        };
+        assert_ne!(
+            square, target,
+            "the same square is not asked twice in a row"
+        );
+    }
+
+    #[test]
+    fn streak_and_best_time_track_across_rounds() {
+        let (mut trainer, _) = started();
+        let mut now = ms(0);
+        for time in [900, 400, 700] {
+            let target = trainer.target().unwrap();
+            trainer.update(target.into(), now + ms(time));
+            now += ms(time + 100);
+            trainer.update(Bitboard::EMPTY, now);
+        }
+
+        let stats = trainer.stats();
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.best_streak, 3);
+        assert_eq!(stats.best_time, Some(ms(400)));
+    }
+
+    #[test]
+    fn lifting_a_piece_does_not_count_as_placement() {
+        let mut trainer = CoordinateTrainer::new(9);
+        let start = Bitboard::from_rank(Rank::First);
+        trainer.update(start, ms(0));
+        let target = trainer.target().unwrap();
+
+        assert_eq!(trainer.update(start.without(Square::A1), ms(300)), None);
+        assert_eq!(
+            trainer.update(start.without(Square::A1).with(target), ms(600)),
+            Some(TrainerEvent::Hit {
+                square: target,
+                time: ms(600)
+            })
+        );
+    }
+
+    #[test]
+    fn full_board_waits_for_an_empty_square() {
+        let mut trainer = CoordinateTrainer::new(1);
+        assert_eq!(trainer.update(Bitboard::FULL, ms(0)), None);
+        assert!(matches!(
+            trainer.update(Bitboard::FULL.without(Square::E4), ms(50)),
+            Some(TrainerEvent::Prompt { square: Square::E4 })
+        ));
+    }
+}
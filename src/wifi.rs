@@ -0,0 +1,335 @@
+//! WiFi credentials and the connection state machine.
+//!
+//! [`WifiLink`] decides when the board connects, retries and asks for new
+//! credentials; `esp32::wifi` carries out its [`WifiAction`]s on the radio.
+//! Until credentials are stored, and again after [`PORTAL_AFTER_ATTEMPTS`]
+//! failed attempts with new ones, the board opens a setup access point
+//! whose page takes the network name and password
+//! ([`WifiCredentials::from_form`]).
+//!
+//! A lost connection is retried at once and then with doubling delays up
+//! to [`MAX_RETRY_DELAY`]. Network features (Lichess, NTP, OTA) call
+//! [`WifiLink::subscribe`] to hear when the network comes and goes. The
+//! edge ring shows [`WifiStatus`] on its own LED (see [`crate::edge`]).
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+/// Longest SSID, in bytes.
+pub const MAX_SSID_LEN: usize = 32;
+/// Longest WPA passphrase, in bytes.
+pub const MAX_PASSWORD_LEN: usize = 64;
+
+/// Delay before the second attempt after a connection is lost; it doubles
+/// per failure.
+pub const RETRY_DELAY: Duration = Duration::from_secs(2);
+/// Longest delay between attempts.
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+/// Failed attempts with credentials that never connected before the setup
+/// access point opens again, e.g. after a mistyped password.
+pub const PORTAL_AFTER_ATTEMPTS: u32 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CredentialError {
+    #[error("network name must be 1 to {MAX_SSID_LEN} bytes")]
+    Ssid,
+    #[error("password must be at most {MAX_PASSWORD_LEN} bytes")]
+    Password,
+    #[error("setup form has no network name")]
+    MissingSsid,
+}
+
+/// The network to join.
+#[derive(Clone, PartialEq, Eq)]
+pub struct WifiCredentials {
+    ssid: String,
+    password: String,
+}
+
+impl std::fmt::Debug for WifiCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WifiCredentials")
+            .field("ssid", &self.ssid)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WifiCredentials {
+    /// An open network has an empty `password`.
+    pub fn new(ssid: &str, password: &str) -> Result<Self, CredentialError> {
+        if ssid.is_empty() || ssid.len() > MAX_SSID_LEN {
+            return Err(CredentialError::Ssid);
+        }
+        if password.len() > MAX_PASSWORD_LEN {
+            return Err(CredentialError::Password);
+        }
+        Ok(Self {
+            ssid: ssid.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    /// Read the `ssid` and `password` fields of the setup page's
+    /// urlencoded form.
+    pub fn from_form(body: &str) -> Result<Self, CredentialError> {
+        let mut ssid = None;
+        let mut password = String::new();
+        for pair in body.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            match key {
+                "ssid" => ssid = Some(url_decode(value)),
+                "password" => password = url_decode(value),
+                _ => {}
+            }
+        }
+        Self::new(&ssid.ok_or(CredentialError::MissingSsid)?, &password)
+    }
+
+    pub fn ssid(&self) -> &str {
+        &self.ssid
+    }
+
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+}
+
+/// Decode `+` and `%XX` escapes; malformed escapes are kept as they are.
+pub(crate) fn url_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], escaped) {
+            (_, Some(byte)) => {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', None) => out.push(b' '),
+            (byte, None) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// What the WiFi LED shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiStatus {
+    /// Waiting for credentials on the setup access point.
+    Provisioning,
+    Connecting,
+    Connected,
+}
+
+/// Sent to subscribers when the network comes or goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiEvent {
+    Connected,
+    Disconnected,
+}
+
+/// What the radio should do next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiAction {
+    /// Join the stored network.
+    Connect,
+    /// Open the setup access point.
+    OpenPortal,
+    /// Close the setup access point; the board is online.
+    ClosePortal,
+}
+
+/// The connection state machine, fed whether the link is up.
+#[derive(Debug)]
+pub struct WifiLink {
+    status: WifiStatus,
+    has_credentials: bool,
+    portal_open: bool,
+    /// The stored credentials have connected at least once.
+    proven: bool,
+    /// Failed attempts since the link was last up.
+    attempts: u32,
+    next_attempt: Option<Duration>,
+    subscribers: Vec<Sender<WifiEvent>>,
+}
+
+impl WifiLink {
+    pub fn new(has_credentials: bool) -> Self {
+        Self {
+            status: if has_credentials {
+                WifiStatus::Connecting
+            } else {
+                WifiStatus::Provisioning
+            },
+            has_credentials,
+            portal_open: false,
+            // Stored credentials worked when they were saved.
+            proven: has_credentials,
+            attempts: 0,
+            next_attempt: None,
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub fn status(&self) -> WifiStatus {
+        self.status
+    }
+
+    /// Hear about every later change of the connection.
+    pub fn subscribe(&mut self) -> Receiver<WifiEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    /// New credentials were stored; try them at once.
+    pub fn provisioned(&mut self) {
+        self.has_credentials = true;
+        self.proven = false;
+        self.attempts = 0;
+        self.next_attempt = None;
+        if self.status != WifiStatus::Connected {
+            self.status = WifiStatus::Connecting;
+        }
+    }
+
+    /// Advance with whether the link is up (associated and holding an
+    /// address) at `now`.
+    pub fn update(&mut self, link_up: bool, now: Duration) -> Option<WifiAction> {
+        if link_up {
+            self.attempts = 0;
+            self.next_attempt = None;
+            self.proven = true;
+            if self.status != WifiStatus::Connected {
+                self.status = WifiStatus::Connected;
+                self.broadcast(WifiEvent::Connected);
+            }
+            return self.set_portal(false);
+        }
+        if self.status == WifiStatus::Connected {
+            self.status = WifiStatus::Connecting;
+            self.broadcast(WifiEvent::Disconnected);
+        }
+        if !self.has_credentials {
+            self.status = WifiStatus::Provisioning;
+            return self.set_portal(true);
+        }
+        if !self.proven && self.attempts >= PORTAL_AFTER_ATTEMPTS && !self.portal_open {
+            self.status = WifiStatus::Provisioning;
+            return self.set_portal(true);
+        }
+        if self.next_attempt.is_some_and(|at| now < at) {
+            return None;
+        }
+        let delay = match self.attempts {
+            0 => Duration::ZERO,
+            n => (RETRY_DELAY * 2u32.saturating_pow(n - 1)).min(MAX_RETRY_DELAY),
+        };
+        // The first attempt is immediate, so wait one step before the next.
+        self.next_attempt = Some(now + delay.max(RETRY_DELAY));
+        self.attempts += 1;
+        Some(WifiAction::Connect)
+    }
+
+    fn set_portal(&mut self, open: bool) -> Option<WifiAction> {
+        if self.portal_open == open {
+            return None;
+        }
+        self.portal_open = open;
+        Some(if open {
+            WifiAction::OpenPortal
+        } else {
+            WifiAction::ClosePortal
+        })
+    }
+
+    fn broadcast(&mut self, event: WifiEvent) {
+        log::info!("WiFi {event:?}");
+        self.subscribers.retain(|tx| tx.send(event).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn setup_form_is_decoded_and_checked() {
+        let credentials = WifiCredentials::from_form("ssid=Club+WiFi%21&password=p%26ss").unwrap();
+        assert_eq!(credentials.ssid(), "Club WiFi!");
+        assert_eq!(credentials.password(), "p&ss");
+        assert!(!format!("{credentials:?}").contains("p&ss"));
+
+        assert_eq!(
+            WifiCredentials::from_form("password=x"),
+            Err(CredentialError::MissingSsid)
+        );
+        assert_eq!(
+            WifiCredentials::from_form(&format!("ssid={}", "a".repeat(33))),
+            Err(CredentialError::Ssid)
+        );
+    }
+
+    #[test]
+    fn lost_connection_is_retried_with_growing_delays() {
+        let mut link = WifiLink::new(true);
+        let events = link.subscribe();
+
+        assert_eq!(link.update(false, secs(0)), Some(WifiAction::Connect));
+        assert_eq!(link.update(true, secs(1)), None);
+        assert_eq!(link.status(), WifiStatus::Connected);
+
+        assert_eq!(link.update(false, secs(10)), Some(WifiAction::Connect));
+        assert_eq!(link.status(), WifiStatus::Connecting);
+        assert_eq!(link.update(false, secs(11)), None);
+        assert_eq!(link.update(false, secs(12)), Some(WifiAction::Connect));
+        assert_eq!(link.update(false, secs(13)), None);
+        assert_eq!(link.update(false, secs(14)), Some(WifiAction::Connect));
+        assert_eq!(link.update(false, secs(17)), None, "4 s after the third");
+        assert_eq!(link.update(false, secs(18)), Some(WifiAction::Connect));
+        assert_eq!(link.status(), WifiStatus::Connecting, "proven network");
+
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            [WifiEvent::Connected, WifiEvent::Disconnected]
+        );
+    }
+
+    #[test]
+    fn portal_opens_without_or_with_failing_credentials() {
+        let mut link = WifiLink::new(false);
+        assert_eq!(link.update(false, secs(0)), Some(WifiAction::OpenPortal));
+        assert_eq!(link.status(), WifiStatus::Provisioning);
+        assert_eq!(link.update(false, secs(1)), None);
+
+        link.provisioned();
+        assert_eq!(link.update(false, secs(2)), Some(WifiAction::Connect));
+        assert_eq!(link.update(true, secs(3)), Some(WifiAction::ClosePortal));
+
+        link.provisioned();
+        let mut now = secs(3);
+        let mut actions = Vec::new();
+        while link.status() != WifiStatus::Provisioning {
+            now += secs(1);
+            actions.extend(link.update(false, now));
+        }
+        assert_eq!(
+            actions,
+            [
+                [WifiAction::Connect; PORTAL_AFTER_ATTEMPTS as usize].as_slice(),
+                &[WifiAction::OpenPortal]
+            ]
+            .concat()
+        );
+    }
+}
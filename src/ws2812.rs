@@ -0,0 +1,210 @@
+//! WS2812 strip layout, brightness curve and bit encoding.
+//!
+//! The platform-independent half of `esp32::display`: which strip LEDs
+//! light each square, how a brightness setting maps to PWM level, and the
+//! RMT symbols a frame is sent as, so all three can be tested on the host.
+//!
+//! Palette colors are already linear PWM levels (see
+//! [`crate::color_vision`]), so only the brightness setting goes through
+//! the gamma curve.
+
+use std::time::Duration;
+
+use shakmaty::Square;
+
+use crate::frame::Rgb8;
+
+/// Perceived brightness is roughly PWM level to the power 1/2.2.
+const GAMMA: f32 = 2.2;
+
+/// Bit 0: short high pulse, long low.
+pub const BIT0: Pulse = Pulse {
+    high: Duration::from_nanos(400),
+    low: Duration::from_nanos(850),
+};
+
+/// Bit 1: long high pulse, short low.
+pub const BIT1: Pulse = Pulse {
+    high: Duration::from_nanos(800),
+    low: Duration::from_nanos(450),
+};
+
+/// Low time after a frame that latches it; newer WS2812B parts need more
+/// than the 50 µs of the original datasheet.
+pub const RESET: Duration = Duration::from_micros(300);
+
+/// How the square LEDs are wired.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SquareLeds {
+    /// Two LEDs per square, 16 per row: the square at file `f` is lit by
+    /// LEDs `f` and `15 - f` of its row, so pairs converge on the center.
+    #[default]
+    Pair,
+    /// Four LEDs per square in a 16×16 grid, each square a 2×2 block.
+    /// Grid rows run from rank 1 and snake: even rows from the a-file,
+    /// odd rows back from the h-file.
+    Quad,
+}
+
+impl SquareLeds {
+    pub const fn per_square(self) -> usize {
+        match self {
+            Self::Pair => 2,
+            Self::Quad => 4,
+        }
+    }
+
+    /// Square LEDs on the strip; edge LEDs follow them.
+    pub const fn count(self) -> usize {
+        64 * self.per_square()
+    }
+
+    /// Strip indices of the LEDs lighting `square`.
+    pub fn indices(self, square: Square) -> impl Iterator<Item = usize> {
+        let (rank, file) = (square.rank() as usize, square.file() as usize);
+        let indices = match self {
+            Self::Pair => {
+                let base = rank * 16;
+                [base + file, base + 15 - file, 0, 0]
+            }
+            Self::Quad => {
+                let at = |row: usize, col: usize| {
+                    row * 16 + if row.is_multiple_of(2) { col } else { 15 - col }
+                };
+                let (row, col) = (rank * 2, file * 2);
+                [
+                    at(row, col),
+                    at(row, col + 1),
+                    at(row + 1, col),
+                    at(row + 1, col + 1),
+                ]
+            }
+        };
+        indices.into_iter().take(self.per_square())
+    }
+}
+
+/// PWM level, out of 255, for a perceived `brightness` out of 255. Any
+/// brightness above zero keeps the LEDs at least faintly on.
+pub fn brightness_level(brightness: u8) -> u8 {
+    if brightness == 0 {
+        return 0;
+    }
+    let level = (f32::from(brightness) / 255.0).powf(GAMMA) * 255.0;
+    (level.round() as u8).max(1)
+}
+
+/// One bit on the data line: a high pulse followed by a low one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pulse {
+    pub high: Duration,
+    pub low: Duration,
+}
+
+/// Encodes strip colors as RMT symbols.
+///
+/// Each symbol is the 32-bit word the RMT peripheral reads: a 15-bit
+/// duration in ticks and a level bit for the high half, then the same for
+/// the low half. Colors go out in GRB order, most significant bit first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ws2812Encoder {
+    bit0: u32,
+    bit1: u32,
+}
+
+impl Ws2812Encoder {
+    /// An encoder for an RMT channel counting `resolution_hz` ticks per
+    /// second.
+    pub fn new(resolution_hz: u32) -> Self {
+        Self {
+            bit0: symbol(BIT0, resolution_hz),
+            bit1: symbol(BIT1, resolution_hz),
+        }
+    }
+
+    /// The symbols for `colors` in strip order, appended to `out`.
+    pub fn encode(&self, colors: &[Rgb8], out: &mut Vec<u32>) {
+        out.reserve(colors.len() * 24);
+        for byte in colors.iter().flat_map(|c| [c.g, c.r, c.b]) {
+            for bit in (0..8).rev() {
+                out.push(if byte & (1 << bit) != 0 {
+                    self.bit1
+                } else {
+                    self.bit0
+                });
+            }
+        }
+    }
+}
+
+/// Ticks of `duration` at `resolution_hz`, rounded and clamped to the
+/// 15 bits of a symbol half.
+pub fn ticks(duration: Duration, resolution_hz: u32) -> u16 {
+    let ticks = (duration.as_nanos() * u128::from(resolution_hz) + 500_000_000) / 1_000_000_000;
+    ticks.min(0x7fff) as u16
+}
+
+fn symbol(pulse: Pulse, resolution_hz: u32) -> u32 {
+    let high = u32::from(ticks(pulse.high, resolution_hz));
+    let low = u32::from(ticks(pulse.low, resolution_hz));
+    high | 1 << 15 | low << 16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_leds_cover_the_strip_once() {
+        for layout in [SquareLeds::Pair, SquareLeds::Quad] {
+            let mut seen = vec![false; layout.count()];
+            for square in Square::ALL {
+                for index in layout.indices(square) {
+                    assert!(!seen[index], "{layout:?} LED {index} reused");
+                    seen[index] = true;
+                }
+            }
+            assert!(seen.iter().all(|&lit| lit), "{layout:?}");
+        }
+        assert_eq!(
+            SquareLeds::Pair.indices(Square::B1).collect::<Vec<_>>(),
+            [1, 14]
+        );
+        assert_eq!(
+            SquareLeds::Quad.indices(Square::B1).collect::<Vec<_>>(),
+            [2, 3, 29, 28]
+        );
+    }
+
+    #[test]
+    fn brightness_follows_the_gamma_curve() {
+        assert_eq!(brightness_level(0), 0);
+        assert_eq!(brightness_level(255), 255);
+        assert_eq!(brightness_level(128), 56);
+        assert_eq!(brightness_level(1), 1, "dimmest setting stays on");
+    }
+
+    #[test]
+    fn colors_encode_as_grb_bits_msb_first() {
+        let encoder = Ws2812Encoder::new(10_000_000);
+        // 400 ns high, 850 ns low at 100 ns per tick.
+        let bit0 = 4 | 1 << 15 | 9 << 16;
+        let bit1 = 8 | 1 << 15 | 5 << 16;
+
+        let mut out = Vec::new();
+        encoder.encode(&[Rgb8::new(0, 0x80, 0x01)], &mut out);
+
+        assert_eq!(out.len(), 24);
+        assert_eq!(out[0], bit1, "green first, MSB first");
+        assert!(out[1..15].iter().all(|&s| s == bit0));
+        assert_eq!(out[23], bit1, "blue LSB last");
+    }
+
+    #[test]
+    fn ticks_round_to_the_resolution() {
+        assert_eq!(ticks(Duration::from_nanos(850), 10_000_000), 9);
+        assert_eq!(ticks(Duration::from_nanos(849), 10_000_000), 8);
+        assert_eq!(ticks(RESET, 10_000_000), 3000);
+        assert_eq!(ticks(Duration::from_secs(1), 10_000_000), 0x7fff);
+    }
+}